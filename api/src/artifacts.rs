@@ -0,0 +1,130 @@
+use arbiter_core::RunArtifact;
+use axum::extract::State;
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use uuid::Uuid;
+
+use crate::auth::jwt::AuthClaims;
+use crate::extractors::ValidatedPath;
+use crate::responses::ApiResponse;
+use crate::state::AppState;
+
+/// The tenant of run `id` if the caller can see the run, else the response to give.
+async fn visible_run_tenant<T>(
+    state: &AppState,
+    id: Uuid,
+    scope: Option<Uuid>,
+) -> Result<Uuid, ApiResponse<T>> {
+    let db_error = |e: arbiter_core::ArbiterError| {
+        ApiResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "db_error", e.to_string())
+    };
+    let not_found = || {
+        ApiResponse::error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("run {id} not found"),
+        )
+    };
+    let run = state
+        .store
+        .get_run(id, scope)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(not_found)?;
+    state
+        .store
+        .job_tenant(run.job_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(not_found)
+}
+
+#[utoipa::path(
+    get,
+    path = "/runs/{id}/artifacts",
+    responses(
+        (status = 200, body = ApiResponse<Vec<RunArtifact>>, description = "The run's artifacts across its attempts (no content)"),
+        (status = 404, description = "Run not found")
+    )
+)]
+#[axum::debug_handler]
+pub async fn list_run_artifacts(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+    ValidatedPath(id): ValidatedPath<Uuid>,
+) -> Result<ApiResponse<Vec<RunArtifact>>, StatusCode> {
+    let tenant = match visible_run_tenant(&state, id, claims.scope()).await {
+        Ok(t) => t,
+        Err(resp) => return Ok(resp),
+    };
+    match state.artifacts.list_artifacts(tenant, id).await {
+        Ok(artifacts) => Ok(ApiResponse::ok(artifacts, StatusCode::OK)),
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "artifact_store_error",
+            e.to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/runs/{id}/artifacts/{attempt}/{name}",
+    responses(
+        (status = 200, body = Vec<u8>, content_type = "application/octet-stream", description = "The artifact's bytes"),
+        (status = 404, description = "Run or artifact not found")
+    )
+)]
+#[axum::debug_handler]
+pub async fn download_run_artifact(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+    ValidatedPath((id, attempt, name)): ValidatedPath<(Uuid, u32, String)>,
+) -> Response {
+    let tenant = match visible_run_tenant::<()>(&state, id, claims.scope()).await {
+        Ok(t) => t,
+        Err(resp) => return resp.into_response(),
+    };
+    match state
+        .artifacts
+        .get_artifact(tenant, id, attempt, &name)
+        .await
+    {
+        Ok(Some(bytes)) => (
+            [
+                (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+                (header::CONTENT_DISPOSITION, content_disposition(&name)),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Ok(None) => ApiResponse::<()>::error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("artifact {name} of run {id} attempt {attempt} not found"),
+        )
+        .into_response(),
+        Err(e) => ApiResponse::<()>::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "artifact_store_error",
+            e.to_string(),
+        )
+        .into_response(),
+    }
+}
+
+/// An `attachment` disposition naming the download after the artifact. Characters a quoted
+/// header parameter cannot carry as-is become `_`.
+fn content_disposition(name: &str) -> String {
+    let safe: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("attachment; filename=\"{safe}\"")
+}
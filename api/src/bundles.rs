@@ -0,0 +1,121 @@
+use arbiter_core::{ArbiterError, BundleFormat, CodeBundle};
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::StatusCode;
+use sha2::{Digest, Sha256};
+
+use crate::auth::jwt::AuthClaims;
+use crate::extractors::ValidatedPath;
+use crate::responses::ApiResponse;
+use crate::state::AppState;
+
+/// Upload limit for a code bundle body.
+pub const MAX_CODE_BUNDLE_BYTES: usize = 64 * 1024 * 1024;
+
+#[utoipa::path(
+    post,
+    path = "/code-bundles/{name}",
+    request_body(content = Vec<u8>, content_type = "application/octet-stream", description = "A tar, tar.gz or zip archive"),
+    responses(
+        (status = 201, body = ApiResponse<CodeBundle>, description = "Stored as the next version (or the latest, if its bytes are unchanged)"),
+        (status = 400, description = "Invalid name, or body is not a tar, tar.gz or zip archive"),
+        (status = 413, description = "Bundle too large")
+    )
+)]
+#[axum::debug_handler]
+pub async fn upload_code_bundle(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+    ValidatedPath(name): ValidatedPath<String>,
+    body: Bytes,
+) -> Result<ApiResponse<CodeBundle>, StatusCode> {
+    let Some(format) = BundleFormat::detect(&body) else {
+        return Ok(ApiResponse::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_bundle",
+            "body is not a tar, tar.gz or zip archive",
+        ));
+    };
+    let sha256 = format!("{:x}", Sha256::digest(&body));
+    match state
+        .store
+        .put_code_bundle(claims.create_tenant(), &name, format, &sha256, &body)
+        .await
+    {
+        Ok(bundle) => Ok(ApiResponse::ok(bundle, StatusCode::CREATED)),
+        Err(ArbiterError::InvalidInput(msg)) => Ok(ApiResponse::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_bundle_name",
+            msg,
+        )),
+        Err(ArbiterError::Conflict(msg)) => {
+            Ok(ApiResponse::error(StatusCode::CONFLICT, "conflict", msg))
+        }
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/code-bundles",
+    responses(
+        (status = 200, body = ApiResponse<Vec<CodeBundle>>, description = "Every stored bundle version (no content)")
+    )
+)]
+#[axum::debug_handler]
+pub async fn list_code_bundles(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+) -> Result<ApiResponse<Vec<CodeBundle>>, StatusCode> {
+    match state.store.list_code_bundles(claims.scope()).await {
+        Ok(bundles) => Ok(ApiResponse::ok(bundles, StatusCode::OK)),
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/code-bundles/{name}/{version}",
+    responses(
+        (status = 204, description = "Bundle version deleted"),
+        (status = 404, description = "Bundle version not found"),
+        (status = 409, description = "A job pins this version, or follows the latest and it is the last one")
+    )
+)]
+#[axum::debug_handler]
+pub async fn delete_code_bundle(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+    ValidatedPath((name, version)): ValidatedPath<(String, u32)>,
+) -> Result<ApiResponse<()>, StatusCode> {
+    match state
+        .store
+        .delete_code_bundle(&name, version, claims.scope())
+        .await
+    {
+        Ok(()) => Ok(ApiResponse::ok((), StatusCode::NO_CONTENT)),
+        Err(ArbiterError::NotFound(_)) => Ok(ApiResponse::error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("code bundle {name} v{version} not found"),
+        )),
+        Err(ArbiterError::Conflict(_)) => Ok(ApiResponse::error(
+            StatusCode::CONFLICT,
+            "bundle_in_use",
+            format!("code bundle {name} v{version} is used by a job"),
+        )),
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        )),
+    }
+}
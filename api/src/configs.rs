@@ -1,6 +1,6 @@
 use axum::extract::State;
 use axum::http::StatusCode;
-use arbiter_core::SharedDbConfig;
+use arbiter_core::{ConfigTestRequest, ConfigTestResult, SharedDbConfig};
 use uuid::Uuid;
 
 use crate::auth::jwt::AuthClaims;
@@ -9,6 +9,18 @@ use crate::requests::{CreateDbConfigRequest, UpdateDbConfigRequest};
 use crate::responses::ApiResponse;
 use crate::state::AppState;
 
+/// Returns an error response if a requested pool size is outside `1..=MAX_POOL_SIZE`.
+fn pool_size_error(pool_size: Option<u32>) -> Option<(StatusCode, &'static str, String)> {
+    match pool_size {
+        Some(n) if n == 0 || n > SharedDbConfig::MAX_POOL_SIZE => Some((
+            StatusCode::BAD_REQUEST,
+            "invalid_pool_size",
+            format!("poolSize must be between 1 and {}", SharedDbConfig::MAX_POOL_SIZE),
+        )),
+        _ => None,
+    }
+}
+
 /// Returns an error response if the config is outside the caller's scope (or missing), so
 /// a tenant caller cannot read or mutate another tenant's config.
 async fn config_scope_error(
@@ -37,6 +49,9 @@ pub async fn create_db_config(
     AuthClaims(claims): AuthClaims,
     ValidatedJson(req): ValidatedJson<CreateDbConfigRequest>,
 ) -> Result<ApiResponse<SharedDbConfig>, StatusCode> {
+    if let Some((sc, code, msg)) = pool_size_error(req.pool_size) {
+        return Ok(ApiResponse::error(sc, code, msg));
+    }
     match state
         .store
         .create_db_config(
@@ -48,6 +63,7 @@ pub async fn create_db_config(
             &req.username,
             &req.password_secret,
             &req.database,
+            req.pool_size,
         )
         .await
     {
@@ -130,6 +146,9 @@ pub async fn update_db_config(
     if let Some((sc, code, msg)) = config_scope_error(&state, id, claims.scope()).await {
         return Ok(ApiResponse::error(sc, code, msg));
     }
+    if let Some((sc, code, msg)) = pool_size_error(req.pool_size) {
+        return Ok(ApiResponse::error(sc, code, msg));
+    }
     match state
         .store
         .update_db_config(
@@ -140,6 +159,7 @@ pub async fn update_db_config(
             req.username.as_deref(),
             req.password_secret.as_deref(),
             req.database.as_deref(),
+            req.pool_size,
         )
         .await
     {
@@ -178,3 +198,61 @@ pub async fn delete_db_config(
         )),
     }
 }
+
+#[utoipa::path(
+    post,
+    path = "/db-configs/{id}/test",
+    responses(
+        (status = 200, body = ApiResponse<ConfigTestResult>),
+        (status = 404, description = "Config not found"),
+        (status = 504, description = "No worker reported a result in time")
+    )
+)]
+#[axum::debug_handler]
+pub async fn test_db_config(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+    ValidatedPath(id): ValidatedPath<Uuid>,
+) -> Result<ApiResponse<ConfigTestResult>, StatusCode> {
+    if let Some((sc, code, msg)) = config_scope_error(&state, id, claims.scope()).await {
+        return Ok(ApiResponse::error(sc, code, msg));
+    }
+    // The test runs on a worker (it holds the KEK and has reach to the target); queue it
+    // and wait for one to report. A failed connection is still a 200 with `ok: false`.
+    let test_id = match state.store.request_config_test(id).await {
+        Ok(test_id) => test_id,
+        Err(e) => {
+            return Ok(ApiResponse::error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "db_error",
+                e.to_string(),
+            ));
+        }
+    };
+    let deadline = tokio::time::Instant::now()
+        + std::time::Duration::from_secs(ConfigTestRequest::MAX_WAIT_SECS as u64);
+    loop {
+        match state.store.get_config_test(test_id).await {
+            Ok(Some(result)) => return Ok(ApiResponse::ok(result, StatusCode::OK)),
+            Ok(None) => {}
+            Err(e) => {
+                return Ok(ApiResponse::error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "db_error",
+                    e.to_string(),
+                ));
+            }
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Ok(ApiResponse::error(
+                StatusCode::GATEWAY_TIMEOUT,
+                "no_worker",
+                format!(
+                    "no worker reported a result within {}s; is a worker running?",
+                    ConfigTestRequest::MAX_WAIT_SECS
+                ),
+            ));
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+    }
+}
@@ -29,6 +29,45 @@ where
     }
 }
 
+/// Like [`ValidatedJson`], but an empty body is `None` rather than an error, for
+/// endpoints whose body is optional (clients may still send a JSON content type).
+pub struct OptionalJson<T>(pub Option<T>);
+
+impl<S, T> FromRequest<S> for OptionalJson<T>
+where
+    T: DeserializeOwned + Send + 'static,
+    S: Send + Sync,
+{
+    type Rejection = ApiResponse<()>;
+
+    async fn from_request(
+        req: axum::http::Request<axum::body::Body>,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let bytes = match req.extract::<axum::body::Bytes, _>().await {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                return Err(ApiResponse::error(
+                    StatusCode::BAD_REQUEST,
+                    "Invalid JSON",
+                    err.to_string(),
+                ));
+            }
+        };
+        if bytes.iter().all(u8::is_ascii_whitespace) {
+            return Ok(OptionalJson(None));
+        }
+        match serde_json::from_slice(&bytes) {
+            Ok(value) => Ok(OptionalJson(Some(value))),
+            Err(err) => Err(ApiResponse::error(
+                StatusCode::BAD_REQUEST,
+                "Invalid JSON",
+                err.to_string(),
+            )),
+        }
+    }
+}
+
 pub struct ValidatedQuery<T>(pub T);
 
 impl<S, T> FromRequest<S> for ValidatedQuery<T>
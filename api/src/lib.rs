@@ -1,6 +1,9 @@
+mod artifacts;
 mod auth;
+mod bundles;
 mod configs;
 mod extractors;
+mod modules;
 mod nodes;
 mod queries;
 mod requests;
@@ -13,13 +16,17 @@ mod tenants;
 mod users;
 
 use axum::extract::ConnectInfo;
+use axum::extract::DefaultBodyLimit;
 use axum::extract::rejection::JsonRejection;
 use axum::http::{StatusCode, header};
 use axum::middleware;
 use axum::routing::get_service;
 use arbiter_config::ApiConfig;
-use arbiter_core::{SecretAdmin, Store};
+use arbiter_core::{ArtifactStore, SecretAdmin, Store};
+use artifacts::*;
+use bundles::*;
 use configs::*;
+use modules::*;
 use nodes::*;
 use routes::*;
 use secrets::*;
@@ -75,6 +82,8 @@ pub fn api_router_v1(keys: JwtKeys) -> OpenApiRouter<AppState> {
         .routes(routes!(get_run))
         .routes(routes!(get_run_logs))
         .route("/runs/{id}/stream", axum::routing::get(run_stream))
+        .routes(routes!(list_run_artifacts))
+        .routes(routes!(download_run_artifact))
         .routes(routes!(cancel_run))
         .routes(routes!(prune_runs))
         .routes(routes!(list_settings))
@@ -87,6 +96,11 @@ pub fn api_router_v1(keys: JwtKeys) -> OpenApiRouter<AppState> {
         .routes(routes!(rotation_status))
         .route("/secrets/rotation/stream", axum::routing::get(rotation_stream))
         .routes(routes!(delete_secret))
+        .routes(routes!(list_secret_versions))
+        .routes(routes!(rollback_secret))
+        .routes(routes!(list_secret_references))
+        .routes(routes!(set_secret_policy))
+        .routes(routes!(list_secret_audit))
         .routes(routes!(create_tenant))
         .routes(routes!(list_tenants))
         .routes(routes!(create_db_config))
@@ -94,6 +108,20 @@ pub fn api_router_v1(keys: JwtKeys) -> OpenApiRouter<AppState> {
         .routes(routes!(get_db_config))
         .routes(routes!(update_db_config))
         .routes(routes!(delete_db_config))
+        .routes(routes!(test_db_config))
+        .merge(
+            OpenApiRouter::new()
+                .routes(routes!(upload_wasm_module, list_wasm_modules))
+                .layer(DefaultBodyLimit::max(MAX_WASM_MODULE_BYTES)),
+        )
+        .routes(routes!(delete_wasm_module))
+        .merge(
+            OpenApiRouter::new()
+                .routes(routes!(upload_code_bundle))
+                .layer(DefaultBodyLimit::max(MAX_CODE_BUNDLE_BYTES)),
+        )
+        .routes(routes!(list_code_bundles))
+        .routes(routes!(delete_code_bundle))
         .routes(routes!(list_node_keys))
         .routes(routes!(approve_node))
         .routes(routes!(revoke_node))
@@ -121,6 +149,7 @@ pub fn auth_router(keys: JwtKeys) -> OpenApiRouter<AppState> {
 pub async fn run_http_api(
     store: Arc<dyn Store + Send + Sync>,
     secrets: Option<Arc<dyn SecretAdmin>>,
+    artifacts: Arc<dyn ArtifactStore + Send + Sync>,
     cfg: &ApiConfig,
 ) -> anyhow::Result<()> {
     let jwt_keys = JwtKeys::from_secret(&cfg.jwt_secret);
@@ -128,6 +157,7 @@ pub async fn run_http_api(
         store,
         jwt_keys: jwt_keys.clone(),
         secrets,
+        artifacts,
     };
 
     let trace_layer = TraceLayer::new_for_http()
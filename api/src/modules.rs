@@ -0,0 +1,116 @@
+use arbiter_core::ArbiterError;
+use arbiter_core::WasmModuleInfo;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::StatusCode;
+use sha2::{Digest, Sha256};
+
+use crate::auth::jwt::AuthClaims;
+use crate::extractors::ValidatedPath;
+use crate::responses::ApiResponse;
+use crate::state::AppState;
+
+/// Upload limit for a module body; larger than axum's default, which would cut off most
+/// real-world modules.
+pub const MAX_WASM_MODULE_BYTES: usize = 64 * 1024 * 1024;
+
+#[utoipa::path(
+    post,
+    path = "/wasm-modules",
+    request_body(content = Vec<u8>, content_type = "application/wasm"),
+    responses(
+        (status = 201, body = ApiResponse<WasmModuleInfo>, description = "Module stored (idempotent per content hash)"),
+        (status = 400, description = "Body is not a WebAssembly binary"),
+        (status = 413, description = "Module too large")
+    )
+)]
+#[axum::debug_handler]
+pub async fn upload_wasm_module(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+    body: Bytes,
+) -> Result<ApiResponse<WasmModuleInfo>, StatusCode> {
+    // Binary modules only: text (WAT) would be accepted by the engine too, but its hash would
+    // not match what toolchains report for the compiled artifact.
+    if !body.starts_with(b"\0asm") {
+        return Ok(ApiResponse::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_module",
+            "body is not a WebAssembly binary (missing \\0asm magic)",
+        ));
+    }
+    let sha256 = format!("{:x}", Sha256::digest(&body));
+    match state
+        .store
+        .put_wasm_module(claims.create_tenant(), &sha256, &body)
+        .await
+    {
+        Ok(info) => Ok(ApiResponse::ok(info, StatusCode::CREATED)),
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/wasm-modules",
+    responses(
+        (status = 200, body = ApiResponse<Vec<WasmModuleInfo>>, description = "Stored module metadata (no content)")
+    )
+)]
+#[axum::debug_handler]
+pub async fn list_wasm_modules(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+) -> Result<ApiResponse<Vec<WasmModuleInfo>>, StatusCode> {
+    match state.store.list_wasm_modules(claims.scope()).await {
+        Ok(modules) => Ok(ApiResponse::ok(modules, StatusCode::OK)),
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/wasm-modules/{sha256}",
+    responses(
+        (status = 204, description = "Module deleted"),
+        (status = 404, description = "Module not found"),
+        (status = 409, description = "Module is referenced by a job")
+    )
+)]
+#[axum::debug_handler]
+pub async fn delete_wasm_module(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+    ValidatedPath(sha256): ValidatedPath<String>,
+) -> Result<ApiResponse<()>, StatusCode> {
+    match state
+        .store
+        .delete_wasm_module(&sha256, claims.scope())
+        .await
+    {
+        Ok(()) => Ok(ApiResponse::ok((), StatusCode::NO_CONTENT)),
+        Err(ArbiterError::NotFound(_)) => Ok(ApiResponse::error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("wasm module {sha256} not found"),
+        )),
+        Err(ArbiterError::Conflict(_)) => Ok(ApiResponse::error(
+            StatusCode::CONFLICT,
+            "module_in_use",
+            format!("wasm module {sha256} is referenced by a job"),
+        )),
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        )),
+    }
+}
@@ -42,3 +42,25 @@ pub struct PruneRunsQuery {
     #[ts(optional)]
     pub older_than_days: Option<u32>,
 }
+
+#[derive(Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct DeleteSecretQuery {
+    /// Delete even while jobs or DB configs still reference the secret.
+    #[ts(optional)]
+    pub force: Option<bool>,
+}
+
+/// Page through a secret's audit trail, newest first.
+#[derive(Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SecretAuditQuery {
+    /// Only events before this time.
+    #[ts(optional)]
+    pub before: Option<DateTime<Utc>>,
+    /// At most this many events (default 100, max 1000).
+    #[ts(optional)]
+    pub limit: Option<u32>,
+}
@@ -1,4 +1,4 @@
-use arbiter_core::{DbEngine, MisfirePolicy, RetryConfig, RunnerConfig};
+use arbiter_core::{DbEngine, MisfirePolicy, RetryConfig, RunParam, RunnerConfig};
 use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
 use ts_rs::TS;
@@ -29,6 +29,28 @@ pub struct CreateSecretRequest {
     pub value: String,
 }
 
+/// Roll a secret back to one of its retained versions. The value of `version` becomes a new
+/// current version; the history is kept.
+#[derive(Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RollbackSecretRequest {
+    pub version: u32,
+}
+
+/// Set a secret's expiry and rotation interval. Both are reminders: an expired or overdue
+/// secret still resolves. `null` clears either.
+#[derive(Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SecretPolicyRequest {
+    #[serde(default)]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub rotate_every_secs: Option<u64>,
+}
+
 /// Create a shared DB connection config. `passwordSecret` is a `secret:<name>` reference,
 /// not a plaintext password.
 #[derive(Deserialize, TS, ToSchema)]
@@ -42,6 +64,8 @@ pub struct CreateDbConfigRequest {
     pub username: String,
     pub password_secret: String,
     pub database: String,
+    /// Per-worker connection pool cap (1..=64); default 4.
+    pub pool_size: Option<u32>,
 }
 
 /// Update a shared DB connection config. Absent fields are left unchanged. The engine is
@@ -56,6 +80,17 @@ pub struct UpdateDbConfigRequest {
     pub username: Option<String>,
     pub password_secret: Option<String>,
     pub database: Option<String>,
+    pub pool_size: Option<u32>,
+}
+
+/// Optional body for a run-now request.
+#[derive(Deserialize, Default, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RunJobRequest {
+    /// Ad-hoc parameters, bound by name (`:name`) into DB runner queries.
+    #[serde(default)]
+    pub params: HashMap<String, RunParam>,
 }
 
 /// Replace a job's environment variables (replace-all).
@@ -90,7 +90,17 @@ pub struct HealthCheckResponse {
 pub struct SecretMetaResponse {
     pub id: uuid::Uuid,
     pub name: String,
+    /// The current version; older retained ones are listed under `/secrets/{id}/versions`.
+    pub version: u32,
     pub kek_version: u32,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[ts(type = "number | null")]
+    pub rotate_every_secs: Option<u64>,
+    /// When the value is due to be replaced (rotation interval or expiry, whichever is
+    /// first); past it the secret is overdue.
+    pub rotation_due_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_resolved_run: Option<uuid::Uuid>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -98,15 +108,34 @@ pub struct SecretMetaResponse {
 impl From<arbiter_core::SecretMeta> for SecretMetaResponse {
     fn from(m: arbiter_core::SecretMeta) -> Self {
         Self {
+            rotation_due_at: m.rotation_due_at(),
             id: m.id,
             name: m.name,
+            version: m.version,
             kek_version: m.kek_version,
+            expires_at: m.expires_at,
+            rotate_every_secs: m.rotate_every_secs,
+            last_resolved_at: m.last_resolved_at,
+            last_resolved_run: m.last_resolved_run,
             created_at: m.created_at,
             updated_at: m.updated_at,
         }
     }
 }
 
+/// One retained version of a secret: metadata only, like [`SecretMetaResponse`].
+/// `restoredFrom` is set when the version was made by rolling back to an older one.
+#[derive(Serialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SecretVersionResponse {
+    pub version: u32,
+    pub kek_version: u32,
+    pub current: bool,
+    pub restored_from: Option<u32>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
 /// A page of a run's log chunks plus the overall size, so the client can decide whether to
 /// page further (load earlier or follow newer).
 #[derive(Serialize, TS, ToSchema)]
@@ -1,7 +1,10 @@
 use std::convert::Infallible;
 use std::time::Duration;
 
-use arbiter_core::RotationPhase;
+use arbiter_core::{
+    ArbiterError, RotationPhase, SecretActor, SecretAuditAction, SecretAuditEvent,
+    SecretAuditOutcome, SecretReference,
+};
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::sse::{Event, KeepAlive, Sse};
@@ -9,9 +12,12 @@ use futures::Stream;
 use uuid::Uuid;
 
 use crate::auth::jwt::{AdminRequired, AuthClaims};
-use crate::extractors::{ValidatedJson, ValidatedPath};
-use crate::requests::CreateSecretRequest;
-use crate::responses::{ApiResponse, RotateKekResponse, SecretMetaResponse};
+use crate::extractors::{ValidatedJson, ValidatedPath, ValidatedQuery};
+use crate::queries::{DeleteSecretQuery, SecretAuditQuery};
+use crate::requests::{CreateSecretRequest, RollbackSecretRequest, SecretPolicyRequest};
+use crate::responses::{
+    ApiResponse, RotateKekResponse, SecretMetaResponse, SecretVersionResponse,
+};
 use crate::state::AppState;
 
 #[utoipa::path(
@@ -37,6 +43,14 @@ pub async fn create_secret(
             "secret name must not be empty",
         ));
     }
+    // `@` separates a pinned version in a `secret:<name>@<version>` reference.
+    if req.name.contains('@') {
+        return Ok(ApiResponse::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_name",
+            "secret name must not contain '@'",
+        ));
+    }
 
     // Creating a secret needs the KEK, held only by a node running with one. A node
     // without it can still list/delete secret metadata, just not encrypt new values.
@@ -49,9 +63,28 @@ pub async fn create_secret(
     };
 
     let tenant = claims.create_tenant();
-    let id = match secrets.set_secret(tenant, &req.name, req.value.as_bytes()).await {
+    let actor = SecretActor::User {
+        user_id: claims.sub,
+    };
+    let id = match secrets
+        .set_secret(tenant, &req.name, req.value.as_bytes())
+        .await
+    {
         Ok(id) => id,
         Err(e) => {
+            audit(
+                &state,
+                SecretAuditEvent::refused(
+                    None,
+                    tenant,
+                    &req.name,
+                    SecretAuditAction::Set,
+                    actor,
+                    SecretAuditOutcome::Failed,
+                    e.to_string(),
+                ),
+            )
+            .await;
             return Ok(ApiResponse::error(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "secret_error",
@@ -61,17 +94,19 @@ pub async fn create_secret(
     };
 
     // Return metadata only, fetched back so timestamps are accurate. Never the value.
-    match state.store.get_secret(id, claims.scope()).await {
-        Ok(Some(s)) => Ok(ApiResponse::ok(
-            SecretMetaResponse {
-                id: s.id,
-                name: s.name,
-                kek_version: s.kek_version,
-                created_at: s.created_at,
-                updated_at: s.updated_at,
-            },
-            StatusCode::CREATED,
-        )),
+    match state.store.get_secret_meta(id, claims.scope()).await {
+        Ok(Some(m)) => {
+            audit(
+                &state,
+                SecretAuditEvent::ok(id, tenant, &m.name, SecretAuditAction::Set, actor)
+                    .with_version(m.version),
+            )
+            .await;
+            Ok(ApiResponse::ok(
+                SecretMetaResponse::from(m),
+                StatusCode::CREATED,
+            ))
+        }
         Ok(None) => Ok(ApiResponse::error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "db_error",
@@ -216,9 +251,13 @@ pub async fn rotation_stream(
 #[utoipa::path(
     delete,
     path = "/secrets/{id}",
+    params(
+        ("force" = Option<bool>, Query, description = "Delete even while the secret is referenced")
+    ),
     responses(
         (status = 204, description = "Secret deleted"),
-        (status = 404, description = "Secret not found")
+        (status = 404, description = "Secret not found"),
+        (status = 409, description = "Secret still referenced by jobs or DB configs")
     )
 )]
 #[axum::debug_handler]
@@ -226,10 +265,11 @@ pub async fn delete_secret(
     State(state): State<AppState>,
     AuthClaims(claims): AuthClaims,
     ValidatedPath(id): ValidatedPath<Uuid>,
+    ValidatedQuery(q): ValidatedQuery<DeleteSecretQuery>,
 ) -> Result<ApiResponse<()>, StatusCode> {
     // Scope guard: a tenant caller may only delete a secret in its own tenant.
-    match state.store.get_secret(id, claims.scope()).await {
-        Ok(Some(_)) => {}
+    let meta = match state.store.get_secret_meta(id, claims.scope()).await {
+        Ok(Some(m)) => m,
         Ok(None) => {
             return Ok(ApiResponse::error(
                 StatusCode::NOT_FOUND,
@@ -244,8 +284,63 @@ pub async fn delete_secret(
                 e.to_string(),
             ));
         }
+    };
+    let actor = SecretActor::User {
+        user_id: claims.sub,
+    };
+    // A referenced secret would fail its jobs at the next run; deleting it takes `force`.
+    if !q.force.unwrap_or(false) {
+        let references = match references_to(&state, &meta).await {
+            Ok(r) => r,
+            Err(e) => {
+                return Ok(ApiResponse::error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "db_error",
+                    e.to_string(),
+                ));
+            }
+        };
+        if !references.is_empty() {
+            let users: Vec<String> =
+                references.iter().map(|r| format!("{} ({})", r.name, r.field)).collect();
+            let message = format!("secret {} is in use by {}", meta.name, users.join(", "));
+            audit(
+                &state,
+                SecretAuditEvent::refused(
+                    Some(id),
+                    meta.tenant_id,
+                    &meta.name,
+                    SecretAuditAction::Delete,
+                    actor,
+                    SecretAuditOutcome::Denied,
+                    message.clone(),
+                ),
+            )
+            .await;
+            return Ok(ApiResponse::error(StatusCode::CONFLICT, "in_use", message));
+        }
     }
-    match state.store.delete_secret(id).await {
+    let deleted = state.store.delete_secret(id).await;
+    let event = match &deleted {
+        Ok(()) => SecretAuditEvent::ok(
+            id,
+            meta.tenant_id,
+            &meta.name,
+            SecretAuditAction::Delete,
+            actor,
+        ),
+        Err(e) => SecretAuditEvent::refused(
+            Some(id),
+            meta.tenant_id,
+            &meta.name,
+            SecretAuditAction::Delete,
+            actor,
+            SecretAuditOutcome::Failed,
+            e.to_string(),
+        ),
+    };
+    audit(&state, event).await;
+    match deleted {
         Ok(()) => Ok(ApiResponse::ok((), StatusCode::NO_CONTENT)),
         Err(e) => Ok(ApiResponse::error(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -254,3 +349,356 @@ pub async fn delete_secret(
         )),
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/secrets/{id}/versions",
+    responses(
+        (status = 200, body = ApiResponse<Vec<SecretVersionResponse>>, description = "Retained versions, newest first (no values)"),
+        (status = 404, description = "Secret not found")
+    )
+)]
+#[axum::debug_handler]
+pub async fn list_secret_versions(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+    ValidatedPath(id): ValidatedPath<Uuid>,
+) -> Result<ApiResponse<Vec<SecretVersionResponse>>, StatusCode> {
+    let current = match state.store.get_secret(id, claims.scope()).await {
+        Ok(Some(s)) => s.version,
+        Ok(None) => {
+            return Ok(ApiResponse::error(
+                StatusCode::NOT_FOUND,
+                "not_found",
+                format!("secret {id} not found"),
+            ));
+        }
+        Err(e) => {
+            return Ok(ApiResponse::error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "db_error",
+                e.to_string(),
+            ));
+        }
+    };
+    match state.store.list_secret_versions(id).await {
+        Ok(versions) => Ok(ApiResponse::ok(
+            versions
+                .into_iter()
+                .map(|v| SecretVersionResponse {
+                    version: v.version,
+                    kek_version: v.kek_version,
+                    current: v.version == current,
+                    restored_from: v.restored_from,
+                    created_at: v.created_at,
+                })
+                .collect(),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/secrets/{id}/rollback",
+    request_body = RollbackSecretRequest,
+    responses(
+        (status = 200, body = ApiResponse<SecretMetaResponse>, description = "Rolled back; the restored value is the new current version"),
+        (status = 404, description = "Secret or version not found"),
+        (status = 503, description = "This node cannot manage secrets (no key)")
+    )
+)]
+#[axum::debug_handler]
+pub async fn rollback_secret(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+    ValidatedPath(id): ValidatedPath<Uuid>,
+    ValidatedJson(req): ValidatedJson<RollbackSecretRequest>,
+) -> Result<ApiResponse<SecretMetaResponse>, StatusCode> {
+    let Some(secrets) = state.secrets.as_ref() else {
+        return Ok(ApiResponse::error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "no_key",
+            "this node cannot manage secrets (no encryption key)",
+        ));
+    };
+    // Scope guard: a tenant caller may only roll back a secret in its own tenant.
+    let meta = match state.store.get_secret_meta(id, claims.scope()).await {
+        Ok(Some(m)) => m,
+        Ok(None) => {
+            return Ok(ApiResponse::error(
+                StatusCode::NOT_FOUND,
+                "not_found",
+                format!("secret {id} not found"),
+            ));
+        }
+        Err(e) => {
+            return Ok(ApiResponse::error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "db_error",
+                e.to_string(),
+            ));
+        }
+    };
+    let actor = SecretActor::User {
+        user_id: claims.sub,
+    };
+    let refused = |outcome, detail: String| {
+        SecretAuditEvent::refused(
+            Some(id),
+            meta.tenant_id,
+            &meta.name,
+            SecretAuditAction::Rollback,
+            actor,
+            outcome,
+            detail,
+        )
+        .with_version(req.version)
+    };
+    match secrets.rollback_secret(id, req.version).await {
+        Ok(_) => {
+            audit(
+                &state,
+                SecretAuditEvent::ok(
+                    id,
+                    meta.tenant_id,
+                    &meta.name,
+                    SecretAuditAction::Rollback,
+                    actor,
+                )
+                .with_version(req.version),
+            )
+            .await;
+        }
+        Err(ArbiterError::NotFound(_)) => {
+            let message = format!("secret {id} has no version {}", req.version);
+            audit(
+                &state,
+                refused(SecretAuditOutcome::NotFound, message.clone()),
+            )
+            .await;
+            return Ok(ApiResponse::error(
+                StatusCode::NOT_FOUND,
+                "not_found",
+                message,
+            ));
+        }
+        Err(e) => {
+            audit(&state, refused(SecretAuditOutcome::Failed, e.to_string())).await;
+            return Ok(ApiResponse::error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "secret_error",
+                e.to_string(),
+            ));
+        }
+    }
+    match state.store.get_secret_meta(id, claims.scope()).await {
+        Ok(Some(m)) => Ok(ApiResponse::ok(SecretMetaResponse::from(m), StatusCode::OK)),
+        Ok(None) => Ok(ApiResponse::error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("secret {id} not found"),
+        )),
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        )),
+    }
+}
+
+/// Where a secret is referenced within its own tenant.
+async fn references_to(
+    state: &AppState,
+    meta: &arbiter_core::SecretMeta,
+) -> arbiter_core::Result<Vec<SecretReference>> {
+    let mut index = arbiter_core::secret_references(state.store.as_ref(), meta.tenant_id).await?;
+    Ok(index.remove(&meta.name).unwrap_or_default())
+}
+
+#[utoipa::path(
+    get,
+    path = "/secrets/{id}/references",
+    responses(
+        (status = 200, body = ApiResponse<Vec<SecretReference>>, description = "Jobs and DB configs referencing the secret"),
+        (status = 404, description = "Secret not found")
+    )
+)]
+#[axum::debug_handler]
+pub async fn list_secret_references(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+    ValidatedPath(id): ValidatedPath<Uuid>,
+) -> Result<ApiResponse<Vec<SecretReference>>, StatusCode> {
+    let meta = match state.store.get_secret_meta(id, claims.scope()).await {
+        Ok(Some(m)) => m,
+        Ok(None) => {
+            return Ok(ApiResponse::error(
+                StatusCode::NOT_FOUND,
+                "not_found",
+                format!("secret {id} not found"),
+            ));
+        }
+        Err(e) => {
+            return Ok(ApiResponse::error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "db_error",
+                e.to_string(),
+            ));
+        }
+    };
+    match references_to(&state, &meta).await {
+        Ok(references) => Ok(ApiResponse::ok(references, StatusCode::OK)),
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/secrets/{id}/policy",
+    request_body = SecretPolicyRequest,
+    responses(
+        (status = 200, body = ApiResponse<SecretMetaResponse>, description = "Expiry and rotation interval set"),
+        (status = 400, description = "Invalid rotation interval"),
+        (status = 404, description = "Secret not found")
+    )
+)]
+#[axum::debug_handler]
+pub async fn set_secret_policy(
+    State(state): State<AppState>,
+    AuthClaims(claims): AuthClaims,
+    ValidatedPath(id): ValidatedPath<Uuid>,
+    ValidatedJson(req): ValidatedJson<SecretPolicyRequest>,
+) -> Result<ApiResponse<SecretMetaResponse>, StatusCode> {
+    if req.rotate_every_secs == Some(0) {
+        return Ok(ApiResponse::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_policy",
+            "rotateEverySecs must be positive (null for none)",
+        ));
+    }
+    // Scope guard: a tenant caller may only change a secret in its own tenant.
+    let meta = match state.store.get_secret_meta(id, claims.scope()).await {
+        Ok(Some(m)) => m,
+        Ok(None) => {
+            return Ok(ApiResponse::error(
+                StatusCode::NOT_FOUND,
+                "not_found",
+                format!("secret {id} not found"),
+            ));
+        }
+        Err(e) => {
+            return Ok(ApiResponse::error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "db_error",
+                e.to_string(),
+            ));
+        }
+    };
+    let actor = SecretActor::User {
+        user_id: claims.sub,
+    };
+    let set = state
+        .store
+        .set_secret_policy(id, req.expires_at, req.rotate_every_secs)
+        .await;
+    let event = match &set {
+        Ok(()) => SecretAuditEvent::ok(
+            id,
+            meta.tenant_id,
+            &meta.name,
+            SecretAuditAction::Policy,
+            actor,
+        ),
+        Err(e) => SecretAuditEvent::refused(
+            Some(id),
+            meta.tenant_id,
+            &meta.name,
+            SecretAuditAction::Policy,
+            actor,
+            SecretAuditOutcome::Failed,
+            e.to_string(),
+        ),
+    };
+    audit(&state, event).await;
+    if let Err(e) = set {
+        return Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        ));
+    }
+    match state.store.get_secret_meta(id, claims.scope()).await {
+        Ok(Some(m)) => Ok(ApiResponse::ok(SecretMetaResponse::from(m), StatusCode::OK)),
+        Ok(None) => Ok(ApiResponse::error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("secret {id} not found"),
+        )),
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        )),
+    }
+}
+
+/// Append to the secret audit trail. The action it records already happened, so a failed
+/// write is logged rather than failing the request.
+async fn audit(state: &AppState, event: SecretAuditEvent) {
+    if let Err(e) = state.store.append_secret_audit(&event).await {
+        tracing::error!(
+            "cannot audit {} of secret {} by {}: {e}",
+            event.action,
+            event.secret_name,
+            event.actor.kind()
+        );
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/secrets/{id}/audit",
+    params(
+        ("before" = Option<DateTime<Utc>>, Query, description = "Only events before this time (paging)"),
+        ("limit" = Option<u32>, Query, description = "At most this many events (default 100, max 1000)")
+    ),
+    responses(
+        (status = 200, body = ApiResponse<Vec<SecretAuditEvent>>, description = "Audit events, newest first"),
+        (status = 403, description = "Admin only")
+    )
+)]
+#[axum::debug_handler]
+pub async fn list_secret_audit(
+    State(state): State<AppState>,
+    AdminRequired(claims): AdminRequired,
+    ValidatedPath(id): ValidatedPath<Uuid>,
+    ValidatedQuery(q): ValidatedQuery<SecretAuditQuery>,
+) -> Result<ApiResponse<Vec<SecretAuditEvent>>, StatusCode> {
+    // Events outlive their secret, so this is scoped by the events' own tenant rather than
+    // by looking the secret up: a deleted secret's trail stays readable.
+    let limit = q.limit.unwrap_or(100).min(1000);
+    match state.store.list_secret_audit(id, q.before, limit).await {
+        Ok(events) => Ok(ApiResponse::ok(
+            events
+                .into_iter()
+                .filter(|e| claims.scope().is_none_or(|t| t == e.tenant_id))
+                .collect(),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(ApiResponse::error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "db_error",
+            e.to_string(),
+        )),
+    }
+}
@@ -37,6 +37,7 @@ pub fn change_stream(
 /// once terminal (or when `snapshot` returns `None`, e.g. the resource is gone). Use this
 /// when the client needs the data itself (a single run's status/output, rotation progress)
 /// rather than a refetch ping.
+#[allow(dead_code)]
 pub fn snapshot_stream(
     backstop: Duration,
     wake: impl Fn() -> BoxFuture<'static, ()> + Send + 'static,
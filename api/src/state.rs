@@ -1,4 +1,4 @@
-use arbiter_core::{SecretAdmin, Store};
+use arbiter_core::{ArtifactStore, SecretAdmin, Store};
 use std::sync::Arc;
 
 use crate::auth::jwt::JwtKeys;
@@ -10,4 +10,6 @@ pub struct AppState {
     /// Encrypt-capable secret writer. `Some` only on a node that holds a KEK; `None`
     /// means this node can list/delete secret metadata but cannot create them.
     pub secrets: Option<Arc<dyn SecretAdmin>>,
+    /// Where run artifacts are read from: the store itself or the node's configured backend.
+    pub artifacts: Arc<dyn ArtifactStore + Send + Sync>,
 }
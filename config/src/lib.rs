@@ -74,6 +74,9 @@ pub struct RetentionConfig {
     pub run_retention_days: u32,
     /// How often the leader runs a retention prune.
     pub prune_interval_secs: u64,
+    /// Keep secret audit events for this many days, independent of run retention.
+    /// `0` keeps them forever. Default 365.
+    pub secret_audit_days: u32,
 }
 
 impl Default for RetentionConfig {
@@ -81,6 +84,7 @@ impl Default for RetentionConfig {
         Self {
             run_retention_days: 0,
             prune_interval_secs: 3600,
+            secret_audit_days: 365,
         }
     }
 }
@@ -114,6 +118,20 @@ pub struct WorkerSettings {
     /// truncated with a marker so a runaway job cannot fill storage. `0` = no cap.
     /// Default 10 MiB.
     pub max_log_bytes: u64,
+    /// Directory of out-of-process runner plugins (executables speaking the stdio
+    /// JSON-RPC plugin protocol), probed at startup. Unset = no plugins.
+    #[serde(default)]
+    pub plugin_dir: Option<String>,
+    /// The only host directory `wasm` jobs may reach: their preopens and on-disk modules
+    /// must be canonical paths inside it. Unset = host paths refused (uploaded modules only).
+    #[serde(default)]
+    pub wasm_host_root: Option<String>,
+    /// How long a finished run's workspace is kept for inspection before the sweep
+    /// removes it. `0` = removed as soon as the run is over. Default 0.
+    pub workspace_keep_run_secs: u64,
+    /// How long a per-job workspace may go unused before the sweep removes it. `0` =
+    /// kept forever. Default 7 days.
+    pub workspace_job_idle_secs: u64,
 }
 
 impl Default for WorkerSettings {
@@ -121,6 +139,65 @@ impl Default for WorkerSettings {
         Self {
             claim_backstop_secs: 300,
             max_log_bytes: 10 * 1024 * 1024,
+            plugin_dir: None,
+            wasm_host_root: None,
+            workspace_keep_run_secs: 0,
+            workspace_job_idle_secs: 7 * 86_400,
+        }
+    }
+}
+
+/// Where run artifacts are kept and how much a tenant may hold.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ArtifactsConfig {
+    /// `db` keeps artifacts as blobs in the shared store; `fs` keeps them as files under
+    /// `root`, which every api and worker node must share (e.g. a network mount).
+    pub backend: String,
+    /// Directory of the `fs` backend. Unset = `<node.data_dir>/artifacts`.
+    #[serde(default)]
+    pub root: Option<String>,
+    /// Cap on the artifact bytes one tenant may hold; past it new artifacts are skipped.
+    /// `0` = no cap. Default 1 GiB.
+    pub max_tenant_bytes: u64,
+}
+
+impl Default for ArtifactsConfig {
+    fn default() -> Self {
+        Self {
+            backend: "db".into(),
+            root: None,
+            max_tenant_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// How secrets are kept.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SecretsConfig {
+    /// How many versions of each secret are kept for pinning and rollback (at least the
+    /// current one). Default 10.
+    pub max_versions: u32,
+    /// Where the KEK comes from. `cluster` (default) generates it in the cluster and seals it
+    /// to each node's identity. `passphrase` (derived with Argon2id) and `key` (32 bytes, raw
+    /// or base64) hold it under an operator key read at startup from `kek_file` or
+    /// `kek_fd`. Fixed when the cluster's first KEK is created.
+    pub kek_source: String,
+    /// File the passphrase or key is read from (e.g. a mounted secret or a named pipe).
+    #[serde(default)]
+    pub kek_file: Option<String>,
+    /// Inherited file descriptor the passphrase or key is read from, when no `kek_file`.
+    /// Read to its end and closed at startup.
+    #[serde(default)]
+    pub kek_fd: Option<u32>,
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            max_versions: 10,
+            kek_source: "cluster".into(),
+            kek_file: None,
+            kek_fd: None,
         }
     }
 }
@@ -141,6 +218,10 @@ pub struct NodeConfig {
     pub scheduler: SchedulerSettings,
     #[serde(default)]
     pub worker: WorkerSettings,
+    #[serde(default)]
+    pub artifacts: ArtifactsConfig,
+    #[serde(default)]
+    pub secrets: SecretsConfig,
 }
 
 impl NodeConfig {
@@ -172,6 +253,11 @@ impl NodeConfig {
                 RetentionConfig::default().prune_interval_secs as i64,
             )
             .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_default(
+                "retention.secret_audit_days",
+                RetentionConfig::default().secret_audit_days as i64,
+            )
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
             .set_default(
                 "scheduler.misfire_catchup_secs",
                 SchedulerSettings::default().misfire_catchup_secs as i64,
@@ -192,6 +278,30 @@ impl NodeConfig {
                 WorkerSettings::default().max_log_bytes as i64,
             )
             .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_default(
+                "worker.workspace_keep_run_secs",
+                WorkerSettings::default().workspace_keep_run_secs as i64,
+            )
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_default(
+                "worker.workspace_job_idle_secs",
+                WorkerSettings::default().workspace_job_idle_secs as i64,
+            )
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_default("artifacts.backend", ArtifactsConfig::default().backend)
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_default(
+                "artifacts.max_tenant_bytes",
+                ArtifactsConfig::default().max_tenant_bytes as i64,
+            )
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_default(
+                "secrets.max_versions",
+                SecretsConfig::default().max_versions as i64,
+            )
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_default("secrets.kek_source", SecretsConfig::default().kek_source)
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
             .set_default("node.data_dir", NodeSettings::default().data_dir)
             .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
             .set_default("node.identity_path", NodeSettings::default().identity_path)
@@ -230,6 +340,25 @@ impl NodeConfig {
                 std::env::var("ARBITER_NODE_IDENTITY").ok(),
             )
             .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_override_option(
+                "worker.plugin_dir",
+                std::env::var("ARBITER_WORKER_PLUGIN_DIR").ok(),
+            )
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_override_option(
+                "worker.wasm_host_root",
+                std::env::var("ARBITER_WORKER_WASM_HOST_ROOT").ok(),
+            )
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_override_option("secrets.kek_source", std::env::var("ARBITER_KEK_SOURCE").ok())
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_override_option("secrets.kek_file", std::env::var("ARBITER_KEK_FILE").ok())
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
+            .set_override_option(
+                "secrets.kek_fd",
+                std::env::var("ARBITER_KEK_FD").ok().and_then(|v| v.parse::<i64>().ok()),
+            )
+            .map_err(|e| ArbiterError::ValidationError(e.to_string()))?
             .set_override_option(
                 "node.allow_multi_id",
                 std::env::var("ARBITER_ALLOW_MULTI_ID")
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt, str::FromStr};
+use std::{borrow::Cow, collections::HashMap, fmt, path::{Component, Path, PathBuf}, str::FromStr};
 
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
@@ -108,16 +108,30 @@ pub struct JobSpec {
     pub backoff_strategy: BackoffStrategy,
     pub backoff_base_secs: u32,
     pub backoff_cap_secs: u32,
+    /// Which outcomes count as `retryable` for this job (defaults when unset).
+    #[serde(default)]
+    pub retry_on: RetryOn,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
 #[serde(rename_all = "camelCase", tag = "type")]
 #[ts(export)]
+#[allow(clippy::large_enum_variant)] // HTTP carries TLS + signing options; configs are few
 pub enum RunnerConfig {
+    /// A shell command, run in its [`WorkspaceMode`] workspace unless `working_dir` says
+    /// otherwise. With `code`, it runs in the fetched code's directory instead (a relative
+    /// `working_dir` is taken inside it). `secret_delivery` says how the `secret:<name>`
+    /// values of its env reach it.
     #[serde(rename_all = "camelCase")]
     Shell {
         command: String,
         working_dir: Option<String>,
+        #[serde(default)]
+        code: Option<CodeSource>,
+        #[serde(default)]
+        workspace: WorkspaceMode,
+        #[serde(default)]
+        secret_delivery: SecretDelivery,
     },
     #[serde(rename_all = "camelCase")]
     Http {
@@ -126,35 +140,435 @@ pub enum RunnerConfig {
         headers: Option<HashMap<String, String>>,
         body: Option<String>,
         timeout_sec: Option<u32>,
+        #[serde(default)]
+        tls: Option<HttpTlsConfig>,
+        #[serde(default)]
+        signing: Option<HttpSigningConfig>,
     },
     #[serde(rename_all = "camelCase")]
     PgSql {
         config_id: Uuid,
         query: String,
         timeout_sec: Option<u32>,
+        #[serde(default)]
+        output: Option<DbOutputConfig>,
     },
     #[serde(rename_all = "camelCase")]
     MySql {
         config_id: Uuid,
         query: String,
         timeout_sec: Option<u32>,
+        #[serde(default)]
+        output: Option<DbOutputConfig>,
     },
+    /// A SQLite database file on the worker. `query` may hold several statements; they
+    /// run in one transaction. `busy_timeout_sec` (default 5) bounds the wait for another
+    /// connection's lock on the file.
+    #[serde(rename_all = "camelCase")]
+    Sqlite {
+        path: String,
+        query: String,
+        timeout_sec: Option<u32>,
+        #[serde(default)]
+        busy_timeout_sec: Option<u32>,
+        #[serde(default)]
+        output: Option<DbOutputConfig>,
+    },
+    /// A Python entrypoint run through the injected runtime. With the `socket` transport
+    /// the worker keeps the interpreter resident between fires (see [`RuntimeTransport`]).
+    /// `secrets` names the tenant secrets the code may read with `ctx.secret(name)`; they
+    /// are handed over in a private file or on the socket, never in argv or env. With
+    /// `code`, the fetched code's directory leads `PYTHONPATH`. The entrypoint runs in
+    /// its [`WorkspaceMode`] workspace. Env secrets follow `secret_delivery`.
     #[serde(rename_all = "camelCase")]
     Python {
         module: String,
         class_name: String,
         timeout_sec: Option<u32>,
+        #[serde(default)]
+        transport: RuntimeTransport,
+        #[serde(default)]
+        max_runs: Option<u32>,
+        #[serde(default)]
+        secrets: Vec<String>,
+        #[serde(default)]
+        code: Option<CodeSource>,
+        #[serde(default)]
+        workspace: WorkspaceMode,
+        #[serde(default)]
+        secret_delivery: SecretDelivery,
     },
+    /// A Node entrypoint run through the injected runtime; `transport`, `secrets`, `code`,
+    /// `workspace` and `secret_delivery` as for Python (the code's directory leads
+    /// `NODE_PATH`).
     #[serde(rename_all = "camelCase")]
     Node {
         module: String,
         function_name: String,
         timeout_sec: Option<u32>,
+        #[serde(default)]
+        transport: RuntimeTransport,
+        #[serde(default)]
+        max_runs: Option<u32>,
+        #[serde(default)]
+        secrets: Vec<String>,
+        #[serde(default)]
+        code: Option<CodeSource>,
+        #[serde(default)]
+        workspace: WorkspaceMode,
+        #[serde(default)]
+        secret_delivery: SecretDelivery,
+    },
+    /// A WASI module run in-process by the worker (no interpreter on the host). It sees
+    /// only the job's env, `args`, and the `preopens` directories. `fuel` bounds the
+    /// instructions executed and `max_memory_mb` its linear memory (defaults in
+    /// [`WasmLimits`]).
+    #[serde(rename_all = "camelCase")]
+    Wasm {
+        module: WasmModuleSource,
+        timeout_sec: Option<u32>,
+        #[serde(default)]
+        #[ts(type = "number | null")]
+        fuel: Option<u64>,
+        #[serde(default)]
+        max_memory_mb: Option<u32>,
+        #[serde(default)]
+        preopens: Vec<WasmPreopen>,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// An out-of-process runner plugin discovered by the worker (see `PLUGIN_PROTOCOL.md`).
+    /// `config` is handed to the plugin as-is; string values of the form `secret:<name>`
+    /// are resolved by the worker and sent alongside it.
+    #[serde(rename_all = "camelCase")]
+    Plugin {
+        plugin: String,
+        #[serde(default = "empty_json_object")]
+        #[ts(type = "Record<string, unknown>")]
+        #[schema(value_type = Object)]
+        config: serde_json::Value,
+        timeout_sec: Option<u32>,
+    },
+}
+
+fn empty_json_object() -> serde_json::Value {
+    serde_json::Value::Object(Default::default())
+}
+
+/// Where a `wasm` runner's module comes from: a `.wasm` file on the worker (under its
+/// `wasm_host_root`), or a module uploaded to the store (tenant-scoped, addressed by the hex
+/// SHA-256 of its bytes).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase", tag = "source")]
+#[ts(export)]
+pub enum WasmModuleSource {
+    #[serde(rename_all = "camelCase")]
+    Path { path: String },
+    #[serde(rename_all = "camelCase")]
+    Stored { sha256: String },
+}
+
+impl WasmModuleSource {
+    /// The on-disk path, for a `path` source.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            WasmModuleSource::Path { path } => Some(path),
+            WasmModuleSource::Stored { .. } => None,
+        }
+    }
+
+    /// The uploaded module's SHA-256, for a `stored` source.
+    pub fn sha256(&self) -> Option<&str> {
+        match self {
+            WasmModuleSource::Path { .. } => None,
+            WasmModuleSource::Stored { sha256 } => Some(sha256),
+        }
+    }
+}
+
+/// Where a shell/python/node job's code comes from when it is not already on the worker.
+/// The worker fetches it into a local cache before the run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase", tag = "source")]
+#[ts(export)]
+pub enum CodeSource {
+    /// An uploaded [`CodeBundle`] of the job's tenant. `version` pins one; unset runs the
+    /// latest at the time of the run.
+    #[serde(rename_all = "camelCase")]
+    Bundle {
+        name: String,
+        #[serde(default)]
+        version: Option<u32>,
+    },
+    /// A git repository (any URL `git` fetches from, including `file://` and local mirror
+    /// paths) at `rev`: a branch, tag or commit, by default the remote's HEAD. Each run
+    /// checks out the commit it resolves to; `subdir` narrows the checkout to one
+    /// directory of the tree.
+    #[serde(rename_all = "camelCase")]
+    Git {
+        url: String,
+        #[serde(default)]
+        rev: Option<String>,
+        #[serde(default)]
+        subdir: Option<String>,
     },
 }
 
+impl CodeSource {
+    /// Checks the reference is well-formed (not that it exists).
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            CodeSource::Bundle { name, version } => {
+                validate_bundle_name(name)?;
+                if *version == Some(0) {
+                    return Err(ArbiterError::InvalidInput(
+                        "bundle versions start at 1".to_string(),
+                    ));
+                }
+                Ok(())
+            }
+            CodeSource::Git { url, rev, subdir } => {
+                let invalid = |msg: &str| Err(ArbiterError::InvalidInput(msg.to_string()));
+                // Both end up as `git` arguments: an option-looking value is refused.
+                if url.trim().is_empty() || url.starts_with('-') || url.chars().any(char::is_control)
+                {
+                    return invalid("git url must be non-empty and not start with '-'");
+                }
+                if let Some(rev) = rev
+                    && (rev.is_empty()
+                        || rev.starts_with('-')
+                        || rev.chars().any(|c| c.is_whitespace() || c.is_control()))
+                {
+                    return invalid("git rev must be a branch, tag or commit");
+                }
+                if let Some(subdir) = subdir {
+                    let path = Path::new(subdir);
+                    if subdir.is_empty()
+                        || !path.components().all(|c| matches!(c, Component::Normal(_)))
+                    {
+                        return invalid("git subdir must be a relative path inside the repository");
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Archive format of a [`CodeBundle`], detected from its leading bytes on upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum BundleFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl BundleFormat {
+    /// The format of an archive, or `None` if the bytes are not a tar, gzipped tar or zip.
+    /// A gzip stream is assumed to wrap a tar.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+            Some(BundleFormat::Zip)
+        } else if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(BundleFormat::TarGz)
+        } else if bytes.get(257..262) == Some(b"ustar") {
+            Some(BundleFormat::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for BundleFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            BundleFormat::Tar => "tar",
+            BundleFormat::TarGz => "tarGz",
+            BundleFormat::Zip => "zip",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for BundleFormat {
+    type Err = ArbiterError;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "tar" => Ok(BundleFormat::Tar),
+            "tarGz" => Ok(BundleFormat::TarGz),
+            "zip" => Ok(BundleFormat::Zip),
+            _ => Err(ArbiterError::InvalidInput(format!(
+                "invalid bundle format: {s}"
+            ))),
+        }
+    }
+}
+
+/// Longest accepted code bundle name, in bytes.
+pub const MAX_BUNDLE_NAME_BYTES: usize = 128;
+
+/// A bundle name is 1-128 letters, digits, `-`, `_` or `.`, not starting with `.`.
+pub fn validate_bundle_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_BUNDLE_NAME_BYTES
+        && !name.starts_with('.')
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ArbiterError::InvalidInput(format!(
+            "bundle name must be 1-{MAX_BUNDLE_NAME_BYTES} letters, digits, '-', '_' or '.' \
+             (not starting with '.'), got {name:?}"
+        )))
+    }
+}
+
+/// A host directory the module may access, mounted at `guest_path`. Nothing else on the
+/// worker's filesystem is visible to it. `host_path` must be a canonical path (no symlinks,
+/// `.` or `..`) inside the worker's `wasm_host_root`; a worker without one refuses it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct WasmPreopen {
+    pub host_path: String,
+    pub guest_path: String,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// Defaults and ceilings for a `wasm` run's resource limits.
+pub struct WasmLimits;
+
+impl WasmLimits {
+    pub const DEFAULT_FUEL: u64 = 10_000_000_000;
+    pub const DEFAULT_MAX_MEMORY_MB: u32 = 256;
+    pub const MAX_MEMORY_MB: u32 = 4096;
+}
+
+/// How the worker talks to a Python/Node runtime.
+///
+/// - `file`: a fresh interpreter per run that writes its result to a file and exits.
+/// - `socket`: a resident interpreter per job, reached over a Unix socket. It runs the
+///   module's `prepare(ctx)` once and `run(ctx)` per fire, and is recycled after
+///   `max_runs` runs (default [`RuntimeTransport::DEFAULT_MAX_RUNS`]) or when it dies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum RuntimeTransport {
+    #[default]
+    File,
+    Socket,
+}
+
+impl RuntimeTransport {
+    pub const DEFAULT_MAX_RUNS: u32 = 100;
+}
+
+impl fmt::Display for RuntimeTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            RuntimeTransport::File => "file",
+            RuntimeTransport::Socket => "socket",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for RuntimeTransport {
+    type Err = ArbiterError;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "file" => Ok(RuntimeTransport::File),
+            "socket" => Ok(RuntimeTransport::Socket),
+            _ => Err(ArbiterError::InvalidInput(format!(
+                "invalid runtime transport: {s}"
+            ))),
+        }
+    }
+}
+
+/// The working directory a shell/python/node run gets under the worker's data dir.
+/// - `run` (default): a fresh directory per run attempt, removed once the attempt is over
+///   (or kept for a while, per the worker's workspace retention).
+/// - `job`: one directory per job that persists across its runs, removed once the job
+///   has not run on the worker for a while. Concurrent runs of the job share it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum WorkspaceMode {
+    #[default]
+    Run,
+    Job,
+}
+
+impl fmt::Display for WorkspaceMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            WorkspaceMode::Run => "run",
+            WorkspaceMode::Job => "job",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for WorkspaceMode {
+    type Err = ArbiterError;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "run" => Ok(WorkspaceMode::Run),
+            "job" => Ok(WorkspaceMode::Job),
+            _ => Err(ArbiterError::InvalidInput(format!(
+                "invalid workspace mode: {s}"
+            ))),
+        }
+    }
+}
+
+/// How the `secret:<name>` values of a shell/python/node job's env reach the process.
+/// - `env` (default): resolved in place, as the variable's value.
+/// - `files`: each written to a 0600 file named after its variable, in a private directory
+///   of the run's that is removed when the run is over (also on timeout). The variable is
+///   left out of the env; the directory is passed as `ARBITER_SECRETS_DIR` to a shell job,
+///   and to a runtime as its context's secrets dir. Keeps values out of the environment,
+///   which grandchildren inherit and `/proc/<pid>/environ` and crash dumps expose.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum SecretDelivery {
+    #[default]
+    Env,
+    Files,
+}
+
+impl fmt::Display for SecretDelivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            SecretDelivery::Env => "env",
+            SecretDelivery::Files => "files",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for SecretDelivery {
+    type Err = ArbiterError;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "env" => Ok(SecretDelivery::Env),
+            "files" => Ok(SecretDelivery::Files),
+            _ => Err(ArbiterError::InvalidInput(format!(
+                "invalid secret delivery: {s}"
+            ))),
+        }
+    }
+}
+
 /// Per-job retry configuration. `Default` = no retry (`max_attempts = 1`).
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct RetryConfig {
@@ -162,6 +576,8 @@ pub struct RetryConfig {
     pub backoff_strategy: BackoffStrategy,
     pub backoff_base_secs: u32,
     pub backoff_cap_secs: u32,
+    #[serde(default)]
+    pub retry_on: RetryOn,
 }
 
 impl Default for RetryConfig {
@@ -171,10 +587,194 @@ impl Default for RetryConfig {
             backoff_strategy: BackoffStrategy::Exponential,
             backoff_base_secs: 30,
             backoff_cap_secs: 3600,
+            retry_on: RetryOn::default(),
         }
     }
 }
 
+/// Per-job classification of which failures are `retryable`. An unset list keeps the
+/// built-in default for that runner kind; a set list replaces it (so `[]` = never).
+/// `error_types` match the structured `error.type` a Python/Node runtime reports.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RetryOn {
+    #[serde(default)]
+    pub http_statuses: Option<Vec<u16>>,
+    #[serde(default)]
+    pub exit_codes: Option<Vec<i32>>,
+    #[serde(default)]
+    pub error_types: Vec<String>,
+}
+
+impl RetryOn {
+    /// The conventionally-transient HTTP statuses.
+    pub const DEFAULT_HTTP_STATUSES: &[u16] = &[408, 425, 429, 500, 502, 503, 504];
+    /// EX_TEMPFAIL.
+    pub const DEFAULT_EXIT_CODES: &[i32] = &[75];
+
+    pub fn retries_http_status(&self, status: u16) -> bool {
+        self.http_statuses
+            .as_deref()
+            .unwrap_or(Self::DEFAULT_HTTP_STATUSES)
+            .contains(&status)
+    }
+
+    pub fn retries_exit_code(&self, code: i32) -> bool {
+        self.exit_codes
+            .as_deref()
+            .unwrap_or(Self::DEFAULT_EXIT_CODES)
+            .contains(&code)
+    }
+
+    pub fn retries_error_type(&self, type_: &str) -> bool {
+        self.error_types.iter().any(|t| t == type_)
+    }
+}
+
+/// TLS options for an HTTP job. `client_cert`/`client_key` (mTLS, PEM) and `ca_bundle`
+/// (extra trusted roots, PEM) are resolved at execution in the job's tenant. The cert and
+/// CA bundle may be inline PEM or `secret:<name>` references; the private key must be a
+/// reference, so it is never stored in the job. `insecure_skip_verify` may only be set by
+/// an admin.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct HttpTlsConfig {
+    #[serde(default)]
+    pub client_cert: Option<String>,
+    #[serde(default)]
+    pub client_key: Option<String>,
+    #[serde(default)]
+    pub ca_bundle: Option<String>,
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
+impl HttpTlsConfig {
+    /// A client identity needs both halves, and its key must be a secret reference.
+    pub fn validate(&self) -> Result<()> {
+        if self.client_cert.is_some() != self.client_key.is_some() {
+            return Err(ArbiterError::ValidationError(
+                "tls.clientCert and tls.clientKey must be set together".to_string(),
+            ));
+        }
+        if let Some(key) = &self.client_key
+            && !key.starts_with(SECRET_REF_PREFIX)
+        {
+            return Err(ArbiterError::ValidationError(
+                "tls.clientKey must be a secret:<name> reference, not an inline key".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Outbound request signing for an HTTP job. `key` is usually a `secret:<name>`
+/// reference; for `awsSigV4` its value is `ACCESS_KEY_ID:SECRET_ACCESS_KEY`.
+///
+/// HMAC signs `canonical`, a template over `{method}`, `{path}` (path + query),
+/// `{timestamp}` (unix seconds, also sent in `timestamp_header`), `{body_sha256}` (hex)
+/// and `{body}`; the default is `{method}\n{path}\n{timestamp}\n{body_sha256}`. The
+/// signature goes in `header` (default `X-Signature`), after an optional `prefix`
+/// such as `sha256=`. `awsSigV4` ignores those and sets `Authorization`/`X-Amz-Date`
+/// for `region`/`service`, signing the job's own headers plus `host`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct HttpSigningConfig {
+    pub algorithm: SigningAlgorithm,
+    pub key: String,
+    #[serde(default)]
+    pub header: Option<String>,
+    #[serde(default)]
+    pub timestamp_header: Option<String>,
+    #[serde(default)]
+    pub canonical: Option<String>,
+    #[serde(default)]
+    pub prefix: Option<String>,
+    #[serde(default)]
+    pub encoding: SignatureEncoding,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub service: Option<String>,
+}
+
+impl HttpSigningConfig {
+    pub const DEFAULT_HEADER: &str = "X-Signature";
+    pub const DEFAULT_TIMESTAMP_HEADER: &str = "X-Signature-Timestamp";
+    pub const DEFAULT_CANONICAL: &str = "{method}\n{path}\n{timestamp}\n{body_sha256}";
+
+    /// SigV4 needs its credential scope.
+    pub fn validate(&self) -> Result<()> {
+        if self.algorithm == SigningAlgorithm::AwsSigV4
+            && (self.region.is_none() || self.service.is_none())
+        {
+            return Err(ArbiterError::ValidationError(
+                "signing.region and signing.service are required for awsSigV4".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum SigningAlgorithm {
+    HmacSha256,
+    HmacSha512,
+    AwsSigV4,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+/// How a pgsql/mysql run reports a statement that returns rows. The result set goes into
+/// `result` as JSON (`columns` + typed `rows`, `row_count`, `truncated`) or as CSV with a
+/// header line, capped at `max_rows` rows and `max_bytes` of rendered output. Statements
+/// without a result set still report `rows_affected`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct DbOutputConfig {
+    #[serde(default)]
+    pub format: DbOutputFormat,
+    #[serde(default)]
+    pub max_rows: Option<u32>,
+    #[serde(default)]
+    pub max_bytes: Option<u32>,
+}
+
+impl DbOutputConfig {
+    pub const DEFAULT_MAX_ROWS: u32 = 1000;
+    pub const DEFAULT_MAX_BYTES: u32 = 1024 * 1024;
+
+    pub fn max_rows(&self) -> usize {
+        self.max_rows.unwrap_or(Self::DEFAULT_MAX_ROWS) as usize
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes.unwrap_or(Self::DEFAULT_MAX_BYTES) as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum DbOutputFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
 impl RunnerConfig {
     pub fn type_of_str(&self) -> &str {
         match self {
@@ -182,8 +782,37 @@ impl RunnerConfig {
             RunnerConfig::Http { .. } => "http",
             RunnerConfig::PgSql { .. } => "pgsql",
             RunnerConfig::MySql { .. } => "mysql",
+            RunnerConfig::Sqlite { .. } => "sqlite",
             RunnerConfig::Python { .. } => "python",
             RunnerConfig::Node { .. } => "node",
+            RunnerConfig::Wasm { .. } => "wasm",
+            RunnerConfig::Plugin { .. } => "plugin",
+        }
+    }
+
+    /// The HTTP TLS options, if this is an HTTP runner that sets any.
+    pub fn http_tls(&self) -> Option<&HttpTlsConfig> {
+        match self {
+            RunnerConfig::Http { tls, .. } => tls.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The HTTP request-signing options, if this is an HTTP runner that sets any.
+    pub fn http_signing(&self) -> Option<&HttpSigningConfig> {
+        match self {
+            RunnerConfig::Http { signing, .. } => signing.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Where the job's code comes from, for a shell/python/node runner that sets it.
+    pub fn code(&self) -> Option<&CodeSource> {
+        match self {
+            RunnerConfig::Shell { code, .. }
+            | RunnerConfig::Python { code, .. }
+            | RunnerConfig::Node { code, .. } => code.as_ref(),
+            _ => None,
         }
     }
 }
@@ -228,37 +857,71 @@ pub struct ExecutableConfigSnapshot {
     pub name: Option<String>,
     pub job_name: String,
     pub meta: ExecutableConfigSnapshotMeta,
+    /// The job's retry classification at claim time (older snapshots: defaults).
+    #[serde(default)]
+    pub retry_on: RetryOn,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
 #[serde(rename_all = "camelCase", tag = "type")]
 #[ts(export)]
+#[allow(clippy::large_enum_variant)] // HTTP carries TLS + signing options; configs are few
 pub enum ExecutableConfigSnapshotMeta {
     #[serde(rename_all = "camelCase")]
     Shell {
         command: String,
         working_dir: Option<String>,
+        #[serde(default)]
+        code: Option<CodeSource>,
+        #[serde(default)]
+        workspace: WorkspaceMode,
+        #[serde(default)]
+        secret_delivery: SecretDelivery,
         env: HashMap<String, String>,
     },
     #[serde(rename_all = "camelCase")]
     PgSql {
+        /// The shared config this was built from; keys the worker's connection pool.
+        #[serde(default)]
+        config_id: Uuid,
         host: String,
         port: u16,
         username: String,
         password_secret: String,
         database: String,
+        #[serde(default)]
+        pool_size: Option<u32>,
         query: String,
         timeout_sec: Option<u32>,
+        #[serde(default)]
+        output: Option<DbOutputConfig>,
     },
     #[serde(rename_all = "camelCase")]
     MySql {
+        /// The shared config this was built from; keys the worker's connection pool.
+        #[serde(default)]
+        config_id: Uuid,
         host: String,
         port: u16,
         username: String,
         password_secret: String,
         database: String,
+        #[serde(default)]
+        pool_size: Option<u32>,
         query: String,
         timeout_sec: Option<u32>,
+        #[serde(default)]
+        output: Option<DbOutputConfig>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Sqlite {
+        path: String,
+        query: String,
+        timeout_sec: Option<u32>,
+        #[serde(default)]
+        busy_timeout_sec: Option<u32>,
+        #[serde(default)]
+        output: Option<DbOutputConfig>,
     },
     #[serde(rename_all = "camelCase")]
     Http {
@@ -268,12 +931,28 @@ pub enum ExecutableConfigSnapshotMeta {
         headers: HashMap<String, String>,
         body: Option<String>,
         timeout_sec: Option<u32>,
+        #[serde(default)]
+        tls: Option<HttpTlsConfig>,
+        #[serde(default)]
+        signing: Option<HttpSigningConfig>,
     },
     #[serde(rename_all = "camelCase")]
     Node {
         module: String,
         function_name: String,
         timeout_sec: Option<u32>,
+        #[serde(default)]
+        transport: RuntimeTransport,
+        #[serde(default)]
+        max_runs: Option<u32>,
+        #[serde(default)]
+        secrets: Vec<String>,
+        #[serde(default)]
+        code: Option<CodeSource>,
+        #[serde(default)]
+        workspace: WorkspaceMode,
+        #[serde(default)]
+        secret_delivery: SecretDelivery,
         env: HashMap<String, String>,
     },
     #[serde(rename_all = "camelCase")]
@@ -281,6 +960,43 @@ pub enum ExecutableConfigSnapshotMeta {
         module: String,
         class_name: String,
         timeout_sec: Option<u32>,
+        #[serde(default)]
+        transport: RuntimeTransport,
+        #[serde(default)]
+        max_runs: Option<u32>,
+        #[serde(default)]
+        secrets: Vec<String>,
+        #[serde(default)]
+        code: Option<CodeSource>,
+        #[serde(default)]
+        workspace: WorkspaceMode,
+        #[serde(default)]
+        secret_delivery: SecretDelivery,
+        env: HashMap<String, String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Wasm {
+        module: WasmModuleSource,
+        timeout_sec: Option<u32>,
+        #[serde(default)]
+        #[ts(type = "number | null")]
+        fuel: Option<u64>,
+        #[serde(default)]
+        max_memory_mb: Option<u32>,
+        #[serde(default)]
+        preopens: Vec<WasmPreopen>,
+        #[serde(default)]
+        args: Vec<String>,
+        env: HashMap<String, String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Plugin {
+        plugin: String,
+        #[serde(default = "empty_json_object")]
+        #[ts(type = "Record<string, unknown>")]
+        #[schema(value_type = Object)]
+        config: serde_json::Value,
+        timeout_sec: Option<u32>,
         env: HashMap<String, String>,
     },
 }
@@ -292,8 +1008,22 @@ impl ExecutableConfigSnapshotMeta {
             ExecutableConfigSnapshotMeta::Http { .. } => "http",
             ExecutableConfigSnapshotMeta::PgSql { .. } => "pgsql",
             ExecutableConfigSnapshotMeta::MySql { .. } => "mysql",
+            ExecutableConfigSnapshotMeta::Sqlite { .. } => "sqlite",
             ExecutableConfigSnapshotMeta::Python { .. } => "python",
             ExecutableConfigSnapshotMeta::Node { .. } => "node",
+            ExecutableConfigSnapshotMeta::Wasm { .. } => "wasm",
+            ExecutableConfigSnapshotMeta::Plugin { .. } => "plugin",
+        }
+    }
+
+    /// The key a worker's runner registry dispatches on: the snapshot kind, or
+    /// `plugin:<name>` for a plugin runner.
+    pub fn runner_key(&self) -> Cow<'_, str> {
+        match self {
+            ExecutableConfigSnapshotMeta::Plugin { plugin, .. } => {
+                Cow::Owned(format!("plugin:{plugin}"))
+            }
+            other => Cow::Borrowed(other.type_of_str()),
         }
     }
 }
@@ -371,6 +1101,8 @@ pub struct WorkerConfig {
     pub dead_after_secs: u32,
     pub restart_count: u32,
     pub version: String,
+    /// The node's data directory; the worker keeps its code cache under it.
+    pub data_dir: PathBuf,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
@@ -525,12 +1257,14 @@ pub fn retry_backoff_secs(
 }
 
 /// Delay before the next attempt: capped backoff with mandatory full jitter
-/// (uniform in `[0, raw]`).
+/// (uniform in `[0, raw]`). A server-requested `retry_after` (e.g. an HTTP
+/// `Retry-After`) is a floor on the delay, itself bounded by `cap_secs`.
 pub fn next_retry_delay(
     strategy: BackoffStrategy,
     base_secs: u32,
     cap_secs: u32,
     attempt: u32,
+    retry_after: Option<Duration>,
 ) -> Duration {
     let raw = retry_backoff_secs(strategy, base_secs, cap_secs, attempt);
     let jittered = if raw == 0 {
@@ -538,7 +1272,27 @@ pub fn next_retry_delay(
     } else {
         rand::random::<u64>() % (raw + 1)
     };
-    Duration::seconds(jittered as i64)
+    let floor = retry_after
+        .map(|d| d.num_seconds().clamp(0, cap_secs as i64))
+        .unwrap_or(0);
+    Duration::seconds((jittered as i64).max(floor))
+}
+
+/// Longest `Retry-After` taken from a server (a week); anything longer, however many
+/// digits it has, reads as this. The retry cap bounds the actual delay well below it.
+pub const MAX_RETRY_AFTER_SECS: u64 = 7 * 86_400;
+
+/// Parse an HTTP `Retry-After` value: delta-seconds (at most [`MAX_RETRY_AFTER_SECS`]) or
+/// an HTTP-date (relative to `now`).
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    let max = Duration::seconds(MAX_RETRY_AFTER_SECS as i64);
+    if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) {
+        let secs = value.parse::<u64>().unwrap_or(u64::MAX);
+        return Some(Duration::seconds(secs.min(MAX_RETRY_AFTER_SECS) as i64));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((at - now).clamp(Duration::zero(), max))
 }
 
 /// The outcome of one execution attempt, reported by the worker. Streams
@@ -554,6 +1308,8 @@ pub struct RunOutcome {
     pub result_media_type: Option<String>,
     pub error: Option<String>,
     pub error_media_type: Option<String>,
+    /// How long the target asked us to wait before retrying (HTTP `Retry-After`).
+    pub retry_after: Option<Duration>,
 }
 
 // TODO: Consider converting to enum for better state alignment?
@@ -580,6 +1336,69 @@ pub struct JobRun {
     /// The typed error payload (structured failure) and its media type.
     pub error: Option<String>,
     pub error_media_type: Option<String>,
+    /// Ad-hoc parameters the run was started with (run-now), bound by name into DB
+    /// runner queries alongside the run context. Empty for scheduled runs.
+    #[serde(default)]
+    pub params: HashMap<String, RunParam>,
+    /// The latest progress the running task reported, if any. Reset when the run is claimed.
+    #[serde(default)]
+    pub progress: Option<RunProgress>,
+    /// When the running task last proved it is alive (a heartbeat, progress or log record
+    /// event). Dead-worker reclaim skips a run whose heartbeat is recent.
+    #[serde(default)]
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    /// The commit a git code source resolved to for this attempt, recorded by the worker
+    /// before the run starts. Reset when the run is claimed.
+    #[serde(default)]
+    pub code_commit: Option<String>,
+}
+
+/// Progress reported by a running task: a fraction in `0..=1` and an optional message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RunProgress {
+    pub value: f64,
+    pub message: Option<String>,
+}
+
+/// A scalar ad-hoc run parameter. Untagged: a JSON bool, integer, float or string.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(untagged)]
+#[ts(export)]
+pub enum RunParam {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl RunParam {
+    /// Names filled from the run itself; ad-hoc params cannot shadow them.
+    pub const CONTEXT_NAMES: [&'static str; 4] = ["run_id", "job_id", "scheduled_for", "attempt"];
+
+    /// Check ad-hoc parameter names: identifiers (`[A-Za-z_][A-Za-z0-9_]*`, as written
+    /// after `:` in a query) that do not collide with a run context name.
+    pub fn validate_names(params: &HashMap<String, RunParam>) -> Result<()> {
+        for name in params.keys() {
+            let mut chars = name.chars();
+            let ident = chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !ident {
+                return Err(ArbiterError::InvalidInput(format!(
+                    "invalid parameter name {name:?}: use letters, digits and underscores"
+                )));
+            }
+            if Self::CONTEXT_NAMES.contains(&name.as_str()) {
+                return Err(ArbiterError::InvalidInput(format!(
+                    "parameter {name:?} is set from the run context and cannot be overridden"
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
@@ -593,6 +1412,67 @@ pub struct WorkerRecord {
     pub capacity: u32,
     pub restart_count: u32,
     pub version: String,
+    /// Runner plugins this worker discovered at startup.
+    #[serde(default)]
+    pub plugins: Vec<RunnerPluginInfo>,
+    /// What the worker's data dir holds, as of its last workspace sweep. Kept as-is by a
+    /// heartbeat that carries none.
+    #[serde(default)]
+    pub disk_usage: Option<WorkerDiskUsage>,
+}
+
+/// Disk use under a worker's data dir, measured by its periodic maintenance sweep.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct WorkerDiskUsage {
+    /// Per-run workspaces (live, and finished ones still kept) and their bytes.
+    pub run_workspaces: u32,
+    #[ts(type = "number")]
+    pub run_workspace_bytes: u64,
+    /// Per-job workspaces and their bytes.
+    pub job_workspaces: u32,
+    #[ts(type = "number")]
+    pub job_workspace_bytes: u64,
+    /// Fetched job code: unpacked bundles, git mirrors and checkouts.
+    #[ts(type = "number")]
+    pub code_cache_bytes: u64,
+    /// What the sweep removed: expired workspaces, orphaned run scratch and outdated
+    /// runtime copies, and their bytes.
+    #[serde(default)]
+    pub swept_entries: u32,
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub swept_bytes: u64,
+    pub measured_at: DateTime<Utc>,
+}
+
+/// What a runner plugin reported in its handshake; advertised on the worker's record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RunnerPluginInfo {
+    pub name: String,
+    pub version: String,
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// JSON Schema for the job's `config`, if the plugin publishes one.
+    #[serde(default)]
+    #[ts(type = "Record<string, unknown> | null")]
+    #[schema(value_type = Option<Object>)]
+    pub config_schema: Option<serde_json::Value>,
+}
+
+impl RunnerPluginInfo {
+    /// A plugin name as it appears in a job's runner config: 1-64 letters, digits, `-`
+    /// and `_`.
+    pub fn valid_name(name: &str) -> bool {
+        (1..=64).contains(&name.len())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
@@ -709,45 +1589,295 @@ pub struct SharedDbConfig {
     pub password_secret: String,
     pub database: String,
     pub tenant_id: Uuid,
+    /// Upper bound on the connections each worker keeps open to this database (warm
+    /// pool shared by the config's runs). `None` = [`SharedDbConfig::DEFAULT_POOL_SIZE`].
+    #[serde(default)]
+    pub pool_size: Option<u32>,
+}
+
+impl SharedDbConfig {
+    pub const DEFAULT_POOL_SIZE: u32 = 4;
+    pub const MAX_POOL_SIZE: u32 = 64;
+}
+
+/// A queued "test connection" request for a shared config, as claimed by a worker: the
+/// config to probe, resolved at claim time so the worker needs no further lookup.
+#[derive(Debug, Clone)]
+pub struct ConfigTestRequest {
+    pub id: Uuid,
+    pub config: SharedDbConfig,
+}
+
+impl ConfigTestRequest {
+    /// How long the api waits for a worker to report. Workers only claim requests this
+    /// fresh, so nobody probes on behalf of a caller that has given up.
+    pub const MAX_WAIT_SECS: u32 = 15;
+}
+
+/// Outcome of a config connection test, recorded by the worker that ran it. On success
+/// `latency_ms` is the round trip of the no-op query and `connect_ms` the time to open the
+/// connection; on failure `error` says which step failed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ConfigTestResult {
+    pub ok: bool,
+    pub connect_ms: Option<u64>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<ConfigTestError>,
+    pub worker_id: Uuid,
+    pub tested_at: DateTime<Utc>,
+}
+
+/// Why a config test failed. `code` is stable (`secret_unresolved`, `timeout`,
+/// `connect_failed`, `tls_failed`, `auth_failed`, `unknown_database`, `query_failed`);
+/// `message` is the underlying driver error.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ConfigTestError {
+    pub code: String,
+    pub message: String,
+}
+
+/// CRUD for shared DB connection configs (pgsql/mysql). Tenant-scoped like jobs/secrets:
+/// `scope` = `None` for a system caller (all tenants), `Some(t)` restricts to tenant t.
+/// Deletes are soft (runner rows reference configs), so a config in use stays resolvable.
+#[async_trait]
+pub trait ConfigStore {
+    #[allow(clippy::too_many_arguments)]
+    async fn create_db_config(
+        &self,
+        tenant_id: Uuid,
+        engine: DbEngine,
+        name: &str,
+        host: &str,
+        port: u16,
+        username: &str,
+        password_secret: &str,
+        database: &str,
+        pool_size: Option<u32>,
+    ) -> Result<SharedDbConfig>;
+
+    async fn get_db_config(&self, id: Uuid, scope: Option<Uuid>) -> Result<Option<SharedDbConfig>>;
+
+    async fn list_db_configs(&self, scope: Option<Uuid>) -> Result<Vec<SharedDbConfig>>;
+
+    #[allow(clippy::too_many_arguments)]
+    async fn update_db_config(
+        &self,
+        id: Uuid,
+        name: Option<&str>,
+        host: Option<&str>,
+        port: Option<u16>,
+        username: Option<&str>,
+        password_secret: Option<&str>,
+        database: Option<&str>,
+        pool_size: Option<u32>,
+    ) -> Result<SharedDbConfig>;
+
+    /// Soft-delete a config (set `deleted_at`). A snapshot built from a now-deleted config
+    /// fails at claim time; existing references are not broken.
+    async fn delete_db_config(&self, id: Uuid) -> Result<()>;
+
+    /// Queue a connection test for a config. Tests run on a worker (which holds the KEK
+    /// and has network reach to the target), not on the api node; returns the test id.
+    async fn request_config_test(&self, config_id: Uuid) -> Result<Uuid>;
+
+    /// Claim up to `limit` unclaimed tests requested within the last `max_age_secs` for
+    /// this worker. Older requests are left alone: their caller has stopped waiting. Tests
+    /// whose config was deleted meanwhile are not returned.
+    async fn claim_config_tests(
+        &self,
+        worker_id: Uuid,
+        limit: u32,
+        max_age_secs: u32,
+    ) -> Result<Vec<ConfigTestRequest>>;
+
+    /// Record a claimed test's outcome.
+    async fn finish_config_test(&self, id: Uuid, result: &ConfigTestResult) -> Result<()>;
+
+    /// A test's outcome, or `None` while it is unclaimed/running (or unknown).
+    async fn get_config_test(&self, id: Uuid) -> Result<Option<ConfigTestResult>>;
+
+    /// Delete tests requested before `older_than`, finished or not. Returns how many.
+    async fn prune_config_tests(&self, older_than: DateTime<Utc>) -> Result<u64>;
+
+    /// Resolve when a config test may have been requested, so a worker picks it up while
+    /// the caller is still waiting. Same notify-or-backstop contract as the other
+    /// `await_*` hooks. Default never fires.
+    async fn await_config_tests_change(&self) {
+        std::future::pending::<()>().await
+    }
+}
+
+/// A WASM module uploaded for `wasm` runners. Content-addressed within its tenant: the
+/// same bytes uploaded twice are one module.
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct WasmModuleInfo {
+    pub sha256: String,
+    #[ts(type = "number")]
+    pub size_bytes: u64,
+    pub tenant_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Storage for uploaded WASM modules, so a `wasm` job need not ship its module to every
+/// worker's disk. Tenant-scoped like secrets: `scope` = `None` for a system caller (all
+/// tenants), `Some(t)` restricts to tenant t.
+#[async_trait]
+pub trait ModuleStore {
+    /// Store a module under its hex SHA-256 in `tenant_id`. Uploading bytes the tenant
+    /// already has is a no-op returning the existing module.
+    async fn put_wasm_module(
+        &self,
+        tenant_id: Uuid,
+        sha256: &str,
+        content: &[u8],
+    ) -> Result<WasmModuleInfo>;
+
+    /// A module's bytes, or `None` if absent or out of scope.
+    async fn get_wasm_module(&self, sha256: &str, scope: Option<Uuid>) -> Result<Option<Vec<u8>>>;
+
+    async fn list_wasm_modules(&self, scope: Option<Uuid>) -> Result<Vec<WasmModuleInfo>>;
+
+    /// Delete a module. `Conflict` while a job in its tenant still runs it, `NotFound` if
+    /// absent or out of scope.
+    async fn delete_wasm_module(&self, sha256: &str, scope: Option<Uuid>) -> Result<()>;
+}
+
+/// One version of a named code bundle: an archive (tar, tar.gz or zip) of a job's code,
+/// uploaded once and fetched by workers at run time. Each upload of new bytes under a name
+/// becomes its next version.
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct CodeBundle {
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub version: u32,
+    pub sha256: String,
+    pub format: BundleFormat,
+    #[ts(type = "number")]
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Storage for code bundles. Bundles are named per tenant (a job's own bundle is simply one
+/// named for it); `scope` as for [`ModuleStore`].
+#[async_trait]
+pub trait BundleStore {
+    /// Store `content` as the next version of `name` in `tenant_id`. Uploading the bytes
+    /// the latest version already holds is a no-op returning that version.
+    async fn put_code_bundle(
+        &self,
+        tenant_id: Uuid,
+        name: &str,
+        format: BundleFormat,
+        sha256: &str,
+        content: &[u8],
+    ) -> Result<CodeBundle>;
+
+    /// A bundle version's metadata (the latest when `version` is `None`), or `None` if
+    /// absent or out of scope.
+    async fn get_code_bundle(
+        &self,
+        name: &str,
+        version: Option<u32>,
+        scope: Option<Uuid>,
+    ) -> Result<Option<CodeBundle>>;
+
+    /// A bundle version's archive bytes, or `None` if absent or out of scope.
+    async fn get_code_bundle_content(
+        &self,
+        name: &str,
+        version: u32,
+        scope: Option<Uuid>,
+    ) -> Result<Option<Vec<u8>>>;
+
+    /// Every version in scope, by name then version.
+    async fn list_code_bundles(&self, scope: Option<Uuid>) -> Result<Vec<CodeBundle>>;
+
+    /// Delete one version. `Conflict` while a job in its tenant pins it, or follows the
+    /// latest of `name` and it is the only version left; `NotFound` if absent or out of
+    /// scope.
+    async fn delete_code_bundle(&self, name: &str, version: u32, scope: Option<Uuid>)
+    -> Result<()>;
+}
+
+/// A file a run attempt left in its artifacts directory (or wrote via `ctx.artifact`),
+/// uploaded by the worker once the attempt finished. Unique per (run, attempt, name).
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RunArtifact {
+    pub run_id: Uuid,
+    pub attempt: u32,
+    pub name: String,
+    #[ts(type = "number")]
+    pub size_bytes: u64,
+    pub sha256: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Longest artifact name accepted, in bytes.
+pub const MAX_ARTIFACT_NAME_BYTES: usize = 255;
+
+/// Check an artifact name: a single non-empty path component (no separators, `.`/`..` or
+/// control characters), so it is safe as a file name on any backend.
+pub fn validate_artifact_name(name: &str) -> Result<()> {
+    let bad = name.is_empty()
+        || name.len() > MAX_ARTIFACT_NAME_BYTES
+        || name == "."
+        || name == ".."
+        || name.chars().any(|c| c == '/' || c == '\\' || c.is_control());
+    if bad {
+        return Err(ArbiterError::InvalidInput(format!(
+            "invalid artifact name: {name:?}"
+        )));
+    }
+    Ok(())
 }
 
-/// CRUD for shared DB connection configs (pgsql/mysql). Tenant-scoped like jobs/secrets:
-/// `scope` = `None` for a system caller (all tenants), `Some(t)` restricts to tenant t.
-/// Deletes are soft (runner rows reference configs), so a config in use stays resolvable.
+/// Where run artifacts are kept once uploaded. The shared [`Store`] implements it with the
+/// bytes in the database; a node may use another implementation (e.g. a shared directory)
+/// instead. Every call carries the run's tenant, which a backend may use to lay out or scope
+/// its data and against which the size cap is counted.
 #[async_trait]
-pub trait ConfigStore {
-    #[allow(clippy::too_many_arguments)]
-    async fn create_db_config(
+pub trait ArtifactStore {
+    /// Store an artifact, replacing one of the same (run, attempt, name). `sha256` is the
+    /// hex digest of `content`.
+    async fn put_artifact(
         &self,
         tenant_id: Uuid,
-        engine: DbEngine,
+        run_id: Uuid,
+        attempt: u32,
         name: &str,
-        host: &str,
-        port: u16,
-        username: &str,
-        password_secret: &str,
-        database: &str,
-    ) -> Result<SharedDbConfig>;
-
-    async fn get_db_config(&self, id: Uuid, scope: Option<Uuid>) -> Result<Option<SharedDbConfig>>;
+        sha256: &str,
+        content: &[u8],
+    ) -> Result<RunArtifact>;
 
-    async fn list_db_configs(&self, scope: Option<Uuid>) -> Result<Vec<SharedDbConfig>>;
+    /// A run's artifacts across all its attempts, by attempt then name.
+    async fn list_artifacts(&self, tenant_id: Uuid, run_id: Uuid) -> Result<Vec<RunArtifact>>;
 
-    #[allow(clippy::too_many_arguments)]
-    async fn update_db_config(
+    /// An artifact's bytes, or `None` if absent.
+    async fn get_artifact(
         &self,
-        id: Uuid,
-        name: Option<&str>,
-        host: Option<&str>,
-        port: Option<u16>,
-        username: Option<&str>,
-        password_secret: Option<&str>,
-        database: Option<&str>,
-    ) -> Result<SharedDbConfig>;
+        tenant_id: Uuid,
+        run_id: Uuid,
+        attempt: u32,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>>;
 
-    /// Soft-delete a config (set `deleted_at`). A snapshot built from a now-deleted config
-    /// fails at claim time; existing references are not broken.
-    async fn delete_db_config(&self, id: Uuid) -> Result<()>;
+    /// Total bytes of the artifacts a tenant holds, for its size cap.
+    async fn tenant_artifact_bytes(&self, tenant_id: Uuid) -> Result<u64>;
+
+    /// Delete artifacts uploaded before `older_than`. Returns how many. The database
+    /// implementation also drops a run's artifacts with the run in
+    /// [`RunStore::prune_runs`]; this covers backends outside the database.
+    async fn prune_artifacts(&self, older_than: DateTime<Utc>) -> Result<u64>;
 }
 
 /// Which output stream a log chunk came from.
@@ -779,6 +1909,42 @@ impl FromStr for LogStream {
     }
 }
 
+/// Severity of a structured log record a runtime emitted (`ctx.log.info(...)`), as opposed
+/// to raw stdout/stderr text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+        })
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = ArbiterError;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warning" | "warn" => Ok(LogLevel::Warning),
+            "error" => Ok(LogLevel::Error),
+            _ => Err(ArbiterError::InvalidInput(format!("invalid log level: {s}"))),
+        }
+    }
+}
+
 /// One append-only chunk of a run attempt's captured output. Ordered within an attempt by
 /// `seq` (assigned by the worker, which owns the running run, so it is a single writer).
 #[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
@@ -788,6 +1954,9 @@ pub struct LogChunk {
     pub seq: i64,
     pub stream: LogStream,
     pub content: String,
+    /// Set for a structured log record (one record per chunk); `None` for raw output.
+    #[serde(default)]
+    pub level: Option<LogLevel>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -809,12 +1978,14 @@ pub struct LogSize {
 #[async_trait]
 pub trait LogStore {
     /// Append one output chunk for a run attempt. `seq` must be monotonic per (run, attempt).
+    /// `level` is set for a structured log record and `None` for raw output.
     async fn append_run_log(
         &self,
         run_id: Uuid,
         attempt: u32,
         seq: i64,
         stream: LogStream,
+        level: Option<LogLevel>,
         content: &str,
     ) -> Result<()>;
 
@@ -852,6 +2023,9 @@ pub trait Store:
     + TenantStore
     + ConfigStore
     + LogStore
+    + ModuleStore
+    + ArtifactStore
+    + BundleStore
 {
 }
 
@@ -898,6 +2072,18 @@ pub struct RuntimeDefaults {
     /// Per-run cap on captured output in bytes (stdout + stderr combined). Past it the run's
     /// log is truncated with a marker, so a runaway job cannot fill storage. `0` = no cap.
     pub max_log_bytes: u64,
+    /// Cap on the run artifact bytes one tenant may hold; past it new artifacts are not
+    /// uploaded. `0` = no cap.
+    pub artifact_max_tenant_bytes: u64,
+    /// How long a finished run's workspace is kept for inspection. `0` = removed as soon as
+    /// the run is over.
+    pub workspace_keep_run_secs: u64,
+    /// How long a per-job workspace may go unused before it is removed. `0` = kept forever.
+    pub workspace_job_idle_secs: u64,
+    /// How many versions of a secret are kept (at least the current one).
+    pub secret_max_versions: u64,
+    /// How long secret audit events are kept, on their own window. `0` = forever.
+    pub secret_audit_retention_secs: u64,
 }
 
 /// A typed, auto-refreshing view over the runtime [`SettingsStore`]. Reads are sync and
@@ -1000,14 +2186,56 @@ impl RuntimeSettings {
     pub fn max_log_bytes(&self) -> u64 {
         self.u64_or("worker.max_log_bytes", self.defaults.max_log_bytes)
     }
+
+    /// Per-tenant run artifact cap in bytes (`0` = no cap).
+    pub fn artifact_max_tenant_bytes(&self) -> u64 {
+        self.u64_or(
+            "artifacts.max_tenant_bytes",
+            self.defaults.artifact_max_tenant_bytes,
+        )
+    }
+
+    /// How long a finished run's workspace is kept, in seconds (`0` = not kept).
+    pub fn workspace_keep_run_secs(&self) -> u64 {
+        self.u64_or(
+            "worker.workspace_keep_run_secs",
+            self.defaults.workspace_keep_run_secs,
+        )
+    }
+
+    /// How long an unused per-job workspace is kept, in seconds (`0` = forever).
+    pub fn workspace_job_idle_secs(&self) -> u64 {
+        self.u64_or(
+            "worker.workspace_job_idle_secs",
+            self.defaults.workspace_job_idle_secs,
+        )
+    }
+
+    /// Secret audit retention in seconds (stored as days under
+    /// `retention.secret_audit_days`; `0` = forever).
+    pub fn secret_audit_retention_secs(&self) -> u64 {
+        self.raw("retention.secret_audit_days")
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(|days| days * 86_400)
+            .unwrap_or(self.defaults.secret_audit_retention_secs)
+    }
+
+    /// How many versions of a secret are kept (at least 1, the current one).
+    pub fn secret_max_versions(&self) -> u32 {
+        let n = self.u64_or("secrets.max_versions", self.defaults.secret_max_versions);
+        n.clamp(1, u32::MAX as u64) as u32
+    }
 }
 
 /// An encrypted secret as stored: ciphertext + wrapped DEK + the KEK version that
 /// wrapped it. The store treats all of these as opaque bytes (no crypto knowledge).
+/// Each value set is a numbered `version`; a secret row carries its current one, and a
+/// retained older one is read with [`SecretStore::get_secret_version`].
 #[derive(Debug, Clone)]
 pub struct StoredSecret {
     pub id: Uuid,
     pub name: String,
+    pub version: u32,
     pub value_ct: Vec<u8>,
     pub value_nonce: Vec<u8>,
     pub aead_algo: String,
@@ -1021,12 +2249,386 @@ pub struct StoredSecret {
 #[derive(Debug, Clone)]
 pub struct SecretMeta {
     pub id: Uuid,
+    pub tenant_id: Uuid,
     pub name: String,
+    /// The current version.
+    pub version: u32,
+    /// The KEK version wrapping the current version.
     pub kek_version: u32,
+    /// The oldest KEK version wrapping any retained version: a rotation is not done with a
+    /// secret until this is the new KEK.
+    pub oldest_kek_version: u32,
+    /// When the current value was set (a rollback counts as setting it).
+    pub value_set_at: DateTime<Utc>,
+    /// When the credential itself stops working, as told by whoever set it.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// How often the value should be replaced, counted from `value_set_at`.
+    pub rotate_every_secs: Option<u64>,
+    /// The last time a run resolved the secret, and which run.
+    pub last_resolved_at: Option<DateTime<Utc>>,
+    pub last_resolved_run: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl SecretMeta {
+    /// When the value is due to be replaced: `rotate_every_secs` after it was set, or at
+    /// its expiry if that comes first. `None` when neither is configured.
+    pub fn rotation_due_at(&self) -> Option<DateTime<Utc>> {
+        // An interval too large to represent is as good as never.
+        let rotate = self
+            .rotate_every_secs
+            .and_then(|secs| chrono::Duration::try_seconds(i64::try_from(secs).ok()?))
+            .and_then(|every| self.value_set_at.checked_add_signed(every));
+        match (rotate, self.expires_at) {
+            (Some(r), Some(e)) => Some(r.min(e)),
+            (r, e) => r.or(e),
+        }
+    }
+}
+
+/// One retained version of a secret, without its ciphertext.
+#[derive(Debug, Clone)]
+pub struct SecretVersionMeta {
+    pub version: u32,
+    pub kek_version: u32,
+    /// The version a rollback copied into this one.
+    pub restored_from: Option<u32>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// How many versions of a secret are kept when nothing configures it.
+pub const DEFAULT_SECRET_MAX_VERSIONS: u32 = 10;
+
+/// Split a secret reference's name from its pinned version: `db-pass@3` is version 3 of
+/// `db-pass`. A suffix that is not a version number is part of the name.
+pub fn split_secret_pin(reference: &str) -> (&str, Option<u32>) {
+    match reference.rsplit_once('@') {
+        Some((name, version)) if !name.is_empty() => match version.parse() {
+            Ok(v) => (name, Some(v)),
+            Err(_) => (reference, None),
+        },
+        _ => (reference, None),
+    }
+}
+
+/// What refers to a secret: a job (its env, runtime `secrets`, HTTP TLS/signing or plugin
+/// config) or a shared DB config (its password).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum SecretReferrer {
+    Job,
+    DbConfig,
+}
+
+/// One place a secret is referenced. `field` says where in the referrer (`env.DB_PASS`,
+/// `tls.clientKey`, `passwordSecret`, ...); `version` is set when the reference pins one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SecretReference {
+    pub referrer: SecretReferrer,
+    pub id: Uuid,
+    pub name: String,
+    pub field: String,
+    pub version: Option<u32>,
+}
+
+/// The prefix marking a config value as a secret reference (`secret:<name>`).
+pub const SECRET_REF_PREFIX: &str = "secret:";
+
+/// Every `secret:` reference in one tenant's jobs and DB configs, keyed by secret name.
+/// Computed from the stored configs on each call (nothing to keep in sync), so it costs a
+/// read of the tenant's jobs: meant for admin actions like a delete, not hot paths.
+pub async fn secret_references(
+    store: &(dyn Store + Send + Sync),
+    tenant: Uuid,
+) -> Result<HashMap<String, Vec<SecretReference>>> {
+    let mut index: HashMap<String, Vec<SecretReference>> = HashMap::new();
+    let mut add = |referrer, id, name: &str, field: String, reference: &str| {
+        let (secret, version) = split_secret_pin(reference);
+        index.entry(secret.to_string()).or_default().push(SecretReference {
+            referrer,
+            id,
+            name: name.to_string(),
+            field,
+            version,
+        });
+    };
+    for job in store.list_jobs(Some(tenant)).await? {
+        let mut env: Vec<_> = store.get_job_env(job.id).await?.into_iter().collect();
+        env.sort();
+        for (key, value) in env {
+            if let Some(reference) = value.strip_prefix(SECRET_REF_PREFIX) {
+                add(SecretReferrer::Job, job.id, &job.name, format!("env.{key}"), reference);
+            }
+        }
+        for (field, reference) in runner_secret_refs(&job.runner_cfg) {
+            add(SecretReferrer::Job, job.id, &job.name, field, &reference);
+        }
+    }
+    for cfg in store.list_db_configs(Some(tenant)).await? {
+        if let Some(reference) = cfg.password_secret.strip_prefix(SECRET_REF_PREFIX) {
+            add(SecretReferrer::DbConfig, cfg.id, &cfg.name, "passwordSecret".into(), reference);
+        }
+    }
+    Ok(index)
+}
+
+/// The secret references in a runner's own config, as (field, reference without the
+/// prefix). Runtime `secrets` are bare names.
+fn runner_secret_refs(cfg: &RunnerConfig) -> Vec<(String, String)> {
+    fn prefixed(out: &mut Vec<(String, String)>, field: &str, value: Option<&String>) {
+        if let Some(reference) = value.and_then(|v| v.strip_prefix(SECRET_REF_PREFIX)) {
+            out.push((field.to_string(), reference.to_string()));
+        }
+    }
+    fn walk(out: &mut Vec<(String, String)>, path: String, value: &serde_json::Value) {
+        match value {
+            serde_json::Value::String(s) => prefixed(out, &path, Some(s)),
+            serde_json::Value::Array(items) => {
+                for (i, v) in items.iter().enumerate() {
+                    walk(out, format!("{path}[{i}]"), v);
+                }
+            }
+            serde_json::Value::Object(map) => {
+                for (k, v) in map {
+                    walk(out, format!("{path}.{k}"), v);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut out = Vec::new();
+    match cfg {
+        RunnerConfig::Http { tls, signing, .. } => {
+            if let Some(tls) = tls {
+                prefixed(&mut out, "tls.clientCert", tls.client_cert.as_ref());
+                prefixed(&mut out, "tls.clientKey", tls.client_key.as_ref());
+                prefixed(&mut out, "tls.caBundle", tls.ca_bundle.as_ref());
+            }
+            if let Some(signing) = signing {
+                prefixed(&mut out, "signing.key", Some(&signing.key));
+            }
+        }
+        RunnerConfig::Python { secrets, .. } | RunnerConfig::Node { secrets, .. } => {
+            out.extend(secrets.iter().map(|name| ("secrets".to_string(), name.clone())));
+        }
+        RunnerConfig::Plugin { config, .. } => walk(&mut out, "config".into(), config),
+        _ => {}
+    }
+    out
+}
+
+/// Who acted on a secret, as the audit trail records it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+#[ts(export)]
+pub enum SecretActor {
+    /// An API user setting, rolling back, deleting or changing the policy of a secret.
+    #[serde(rename_all = "camelCase")]
+    User { user_id: Uuid },
+    /// A run resolving the secret for its job.
+    #[serde(rename_all = "camelCase")]
+    Run { run_id: Uuid, job_id: Uuid },
+    /// A node on its own behalf: re-wrapping during a KEK rotation, or a config test.
+    #[serde(rename_all = "camelCase")]
+    Node { node_id: Uuid },
+}
+
+impl SecretActor {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SecretActor::User { .. } => "user",
+            SecretActor::Run { .. } => "run",
+            SecretActor::Node { .. } => "node",
+        }
+    }
+
+    /// The user, run or node id.
+    pub fn id(&self) -> Uuid {
+        match self {
+            SecretActor::User { user_id } => *user_id,
+            SecretActor::Run { run_id, .. } => *run_id,
+            SecretActor::Node { node_id } => *node_id,
+        }
+    }
+
+    pub fn job_id(&self) -> Option<Uuid> {
+        match self {
+            SecretActor::Run { job_id, .. } => Some(*job_id),
+            _ => None,
+        }
+    }
+
+    /// Rebuild an actor from its stored columns.
+    pub fn from_parts(kind: &str, id: Uuid, job_id: Option<Uuid>) -> Result<Self> {
+        match (kind, job_id) {
+            ("user", _) => Ok(SecretActor::User { user_id: id }),
+            ("run", Some(job_id)) => Ok(SecretActor::Run { run_id: id, job_id }),
+            ("node", _) => Ok(SecretActor::Node { node_id: id }),
+            _ => Err(ArbiterError::InvalidInput(format!(
+                "invalid secret actor: {kind}"
+            ))),
+        }
+    }
+}
+
+/// What was done to a secret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum SecretAuditAction {
+    /// Its value was decrypted for a run or a config test.
+    Resolve,
+    /// A new value was set (or the secret created).
+    Set,
+    Rollback,
+    /// Its expiry or rotation interval changed.
+    Policy,
+    Delete,
+    /// Its versions were re-wrapped onto a new KEK.
+    Rotate,
+}
+
+impl fmt::Display for SecretAuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SecretAuditAction::Resolve => "resolve",
+            SecretAuditAction::Set => "set",
+            SecretAuditAction::Rollback => "rollback",
+            SecretAuditAction::Policy => "policy",
+            SecretAuditAction::Delete => "delete",
+            SecretAuditAction::Rotate => "rotate",
+        })
+    }
+}
+
+impl FromStr for SecretAuditAction {
+    type Err = ArbiterError;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "resolve" => Ok(SecretAuditAction::Resolve),
+            "set" => Ok(SecretAuditAction::Set),
+            "rollback" => Ok(SecretAuditAction::Rollback),
+            "policy" => Ok(SecretAuditAction::Policy),
+            "delete" => Ok(SecretAuditAction::Delete),
+            "rotate" => Ok(SecretAuditAction::Rotate),
+            _ => Err(ArbiterError::InvalidInput(format!(
+                "invalid secret audit action: {s}"
+            ))),
+        }
+    }
+}
+
+/// How an audited action on a secret ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum SecretAuditOutcome {
+    Ok,
+    /// Refused by policy, e.g. deleting a secret still in use.
+    Denied,
+    /// The secret (or the pinned version) does not exist in the caller's tenant.
+    NotFound,
+    /// Any other error (a missing KEK, a store error).
+    Failed,
+}
+
+impl fmt::Display for SecretAuditOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SecretAuditOutcome::Ok => "ok",
+            SecretAuditOutcome::Denied => "denied",
+            SecretAuditOutcome::NotFound => "notFound",
+            SecretAuditOutcome::Failed => "failed",
+        })
+    }
+}
+
+impl FromStr for SecretAuditOutcome {
+    type Err = ArbiterError;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ok" => Ok(SecretAuditOutcome::Ok),
+            "denied" => Ok(SecretAuditOutcome::Denied),
+            "notFound" => Ok(SecretAuditOutcome::NotFound),
+            "failed" => Ok(SecretAuditOutcome::Failed),
+            _ => Err(ArbiterError::InvalidInput(format!(
+                "invalid secret audit outcome: {s}"
+            ))),
+        }
+    }
+}
+
+/// One entry of the append-only secret audit trail. Never carries a secret value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SecretAuditEvent {
+    pub id: Uuid,
+    /// `None` when no such secret was found (a resolve of an unknown name).
+    pub secret_id: Option<Uuid>,
+    pub tenant_id: Uuid,
+    pub secret_name: String,
+    /// The version resolved, set or rolled back to, when there is one.
+    pub version: Option<u32>,
+    pub action: SecretAuditAction,
+    pub actor: SecretActor,
+    pub outcome: SecretAuditOutcome,
+    /// The error for a failed or refused action.
+    pub detail: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+impl SecretAuditEvent {
+    /// A successful action, stamped now.
+    pub fn ok(
+        secret_id: Uuid,
+        tenant_id: Uuid,
+        secret_name: &str,
+        action: SecretAuditAction,
+        actor: SecretActor,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            secret_id: Some(secret_id),
+            tenant_id,
+            secret_name: secret_name.to_string(),
+            version: None,
+            action,
+            actor,
+            outcome: SecretAuditOutcome::Ok,
+            detail: None,
+            at: Utc::now(),
+        }
+    }
+
+    /// An action that did not go through, stamped now.
+    pub fn refused(
+        secret_id: Option<Uuid>,
+        tenant_id: Uuid,
+        secret_name: &str,
+        action: SecretAuditAction,
+        actor: SecretActor,
+        outcome: SecretAuditOutcome,
+        detail: impl Into<String>,
+    ) -> Self {
+        Self {
+            secret_id,
+            outcome,
+            detail: Some(detail.into()),
+            ..Self::ok(Uuid::nil(), tenant_id, secret_name, action, actor)
+        }
+    }
+
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = Some(version);
+        self
+    }
+}
+
 /// A KEK keyring version and its lifecycle state (e.g. pending/active/retired).
 #[derive(Debug, Clone)]
 pub struct StoredKekVersion {
@@ -1061,7 +2663,9 @@ pub struct StoredNodeKey {
 /// envelope (DEK/KEK semantics) lives in `arbiter-secrets` on top of this.
 #[async_trait]
 pub trait SecretStore {
-    /// Create or replace a secret by (tenant, name); returns its id.
+    /// Create a secret by (tenant, name), or give an existing one a new current version
+    /// (the previous ones are kept until [`prune_secret_versions`](Self::prune_secret_versions));
+    /// returns its id.
     #[allow(clippy::too_many_arguments)]
     async fn upsert_secret(
         &self,
@@ -1083,8 +2687,52 @@ pub trait SecretStore {
     /// `scope` = `None` lists/gets across all tenants (system caller), `Some(t)` restricts.
     async fn get_secret(&self, id: Uuid, scope: Option<Uuid>) -> Result<Option<StoredSecret>>;
     async fn list_secret_names(&self, scope: Option<Uuid>) -> Result<Vec<SecretMeta>>;
+    /// One secret's metadata, scoped like [`get_secret`](Self::get_secret).
+    async fn get_secret_meta(&self, id: Uuid, scope: Option<Uuid>) -> Result<Option<SecretMeta>>;
+    /// Delete a secret with every version it retains.
     async fn delete_secret(&self, id: Uuid) -> Result<()>;
 
+    /// Set a secret's expiry and rotation interval (`None` clears either).
+    async fn set_secret_policy(
+        &self,
+        id: Uuid,
+        expires_at: Option<DateTime<Utc>>,
+        rotate_every_secs: Option<u64>,
+    ) -> Result<()>;
+
+    /// Note that `run_id` resolved the secret `name` of `tenant` just now.
+    async fn record_secret_use(&self, tenant: Uuid, name: &str, run_id: Uuid) -> Result<()>;
+
+    /// A retained version of a secret, or `None` if it never existed or was pruned.
+    async fn get_secret_version(&self, id: Uuid, version: u32) -> Result<Option<StoredSecret>>;
+
+    /// The retained versions of a secret, newest first.
+    async fn list_secret_versions(&self, id: Uuid) -> Result<Vec<SecretVersionMeta>>;
+
+    /// Roll a secret back: copy a retained version into a new current version (so the
+    /// history only grows). Returns the new version, or `None` if `version` is not retained.
+    async fn restore_secret_version(&self, id: Uuid, version: u32) -> Result<Option<u32>>;
+
+    /// Drop all but the newest `keep` versions of a secret (the current one always stays).
+    /// Returns how many were dropped.
+    async fn prune_secret_versions(&self, id: Uuid, keep: u32) -> Result<u32>;
+
+    /// Append an event to the secret audit trail. The trail is append-only: nothing updates
+    /// an event, and only [`prune_secret_audit`](Self::prune_secret_audit) removes one.
+    async fn append_secret_audit(&self, event: &SecretAuditEvent) -> Result<()>;
+
+    /// A secret's audit events, newest first: up to `limit` of them older than `before`.
+    /// Events outlive the secret they are about.
+    async fn list_secret_audit(
+        &self,
+        secret_id: Uuid,
+        before: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Result<Vec<SecretAuditEvent>>;
+
+    /// Drop audit events older than `older_than`; returns how many.
+    async fn prune_secret_audit(&self, older_than: DateTime<Utc>) -> Result<u64>;
+
     async fn insert_kek_version(&self, version: u32, state: &str) -> Result<()>;
     async fn list_kek_versions(&self) -> Result<Vec<StoredKekVersion>>;
 
@@ -1092,11 +2740,13 @@ pub trait SecretStore {
     /// `retired_at` when the state is `retired`.
     async fn set_kek_version_state(&self, version: u32, state: &str) -> Result<()>;
 
-    /// Re-wrap a secret's DEK under a different KEK version during rotation: the value
-    /// ciphertext is unchanged, only the wrapped DEK and its `kek_version`.
+    /// Re-wrap the DEK of one version of a secret under a different KEK version during
+    /// rotation: the value ciphertext is unchanged, only the wrapped DEK and its
+    /// `kek_version` (of the secret row too when it is the current version).
     async fn rewrap_secret(
         &self,
         id: Uuid,
+        version: u32,
         dek_wrapped: &[u8],
         kek_version: u32,
     ) -> Result<()>;
@@ -1131,8 +2781,18 @@ pub trait SecretStore {
 /// secrets layer; the worker depends only on this trait, not the crypto stack.
 #[async_trait]
 pub trait SecretResolver: Send + Sync {
-    /// Resolve a secret by name within the job's tenant. Refuses a secret from another
-    /// tenant (fail closed), enforcing tenant isolation (SECRETS.md I7).
+    /// Resolve a secret by name within the job's tenant, on behalf of `actor` (whom the
+    /// audit trail records). Refuses a secret from another tenant (fail closed), enforcing
+    /// tenant isolation (SECRETS.md I7).
+    async fn resolve_secret_as(
+        &self,
+        tenant: Uuid,
+        name: &str,
+        actor: SecretActor,
+    ) -> Result<String>;
+
+    /// [`resolve_secret_as`](Self::resolve_secret_as) acting as the resolver itself: the
+    /// node for the secrets layer, the run for a worker's per-run resolver.
     async fn resolve_secret(&self, tenant: Uuid, name: &str) -> Result<String>;
 }
 
@@ -1171,7 +2831,7 @@ pub struct RotationStatus {
 /// lifecycle: a `pending` version means the new key is still being distributed/acked; a
 /// `retiring` version (with no pending) means re-wrapping is underway; otherwise no rotation
 /// is in flight. `nodes_acked` counts approved nodes whose share of the target version is
-/// acked; `secrets_rewrapped` counts secrets already on the target.
+/// acked; `secrets_rewrapped` counts secrets whose every retained version is on the target.
 pub async fn rotation_status(
     store: &(dyn SecretStore + Send + Sync),
 ) -> Result<RotationStatus> {
@@ -1213,7 +2873,7 @@ pub async fn rotation_status(
                     nodes_acked += 1;
                 }
             }
-            let rewrapped = secrets.iter().filter(|m| m.kek_version == t).count() as u32;
+            let rewrapped = secrets.iter().filter(|m| m.oldest_kek_version == t).count() as u32;
             (nodes_acked, nodes_total, rewrapped)
         }
         None => (0, 0, 0),
@@ -1236,10 +2896,15 @@ pub async fn rotation_status(
 /// metadata, deletion) go through [`SecretStore`] and need no key.
 #[async_trait]
 pub trait SecretAdmin: Send + Sync {
-    /// Create or replace a secret by (tenant, name); returns its id. The value is
-    /// encrypted before it touches storage.
+    /// Create a secret by (tenant, name), or give it a new version; returns its id. The
+    /// value is encrypted before it touches storage, and the oldest versions past the
+    /// retention limit are dropped.
     async fn set_secret(&self, tenant: Uuid, name: &str, value: &[u8]) -> Result<Uuid>;
 
+    /// Roll a secret back to a retained version (copied into a new current version).
+    /// Returns the new version; `NotFound` if `version` is not retained.
+    async fn rollback_secret(&self, id: Uuid, version: u32) -> Result<u32>;
+
     /// Start a KEK rotation and drive it as far as it can go now. Completes synchronously
     /// on a single healthy node; on a cluster it may return `Distributing` while waiting for
     /// other nodes to ack the new version (a background driver finishes it). Locks out any
@@ -1288,6 +2953,30 @@ pub trait RunStore {
         outcome: RunOutcome,
     ) -> Result<()>;
 
+    /// Record that a running run is alive, optionally with new progress (kept as-is when
+    /// `None`). Applies only while the run is running on `worker_id`; returns `false` once it
+    /// finished, was cancelled or was reclaimed.
+    async fn heartbeat_run(
+        &self,
+        run_id: Uuid,
+        worker_id: Uuid,
+        progress: Option<RunProgress>,
+    ) -> Result<bool>;
+
+    /// Whether the run is still running on `worker_id` at `attempt`: `false` once it
+    /// finished, was cancelled, pruned or reclaimed. Reads only those columns, so a worker
+    /// can poll it for every run it holds.
+    async fn owns_run(&self, run_id: Uuid, worker_id: Uuid, attempt: u32) -> Result<bool>;
+
+    /// Record the commit the running attempt's git code resolved to. Applies only while the
+    /// run is running on `worker_id`; returns `false` otherwise.
+    async fn record_run_code_commit(
+        &self,
+        run_id: Uuid,
+        worker_id: Uuid,
+        commit: &str,
+    ) -> Result<bool>;
+
     /// Requeue a retryable run for another attempt: record the failed attempt's
     /// outcome, then set state back to queued with the given attempt number and
     /// future `scheduled_for`, clearing the worker/started_at.
@@ -1335,7 +3024,10 @@ pub trait WorkerStore {
         restart_count: u32,
     ) -> Result<()>;
 
-    async fn reclaim_dead_workers_jobs(&self, dead_after_secs: u32) -> Result<u64>; // how many jobs requeued
+    /// Requeue running runs of workers not seen for `dead_after_secs`, except runs that
+    /// heartbeated within that window (their worker is evidently still alive). Returns how
+    /// many runs were requeued.
+    async fn reclaim_dead_workers_jobs(&self, dead_after_secs: u32) -> Result<u64>;
 
     /// Resolve when the worker set changes (a worker registers or a dead worker is reclaimed),
     /// so a live workers view updates promptly. Best-effort, same notify-or-backstop contract
@@ -1405,7 +3097,12 @@ pub trait ApiStore {
 
     async fn delete_job(&self, job_id: Uuid) -> Result<()>;
 
-    async fn create_adhoc_run(&self, job_id: Uuid) -> Result<JobRun>;
+    /// Queue a run-now run of a job with the given ad-hoc parameters (may be empty).
+    async fn create_adhoc_run(
+        &self,
+        job_id: Uuid,
+        params: HashMap<String, RunParam>,
+    ) -> Result<JobRun>;
 
     async fn cancel_run(&self, run_id: Uuid) -> Result<()>;
 
@@ -1448,6 +3145,83 @@ pub trait ApiStore {
 mod tests {
     use super::*;
 
+    #[test]
+    fn plugin_names_are_restricted() {
+        assert!(RunnerPluginInfo::valid_name("deploy_v2-beta"));
+        assert!(!RunnerPluginInfo::valid_name(""));
+        assert!(!RunnerPluginInfo::valid_name("../evil"));
+        assert!(!RunnerPluginInfo::valid_name("has space"));
+        assert!(!RunnerPluginInfo::valid_name(&"a".repeat(65)));
+    }
+
+    #[test]
+    fn secret_pins_and_rotation_due() {
+        assert_eq!(split_secret_pin("db-pass"), ("db-pass", None));
+        assert_eq!(split_secret_pin("db-pass@3"), ("db-pass", Some(3)));
+        assert_eq!(split_secret_pin("odd@name"), ("odd@name", None));
+
+        let set = Utc::now();
+        let mut meta = SecretMeta {
+            id: Uuid::new_v4(),
+            tenant_id: DEFAULT_TENANT_ID,
+            name: "db-pass".into(),
+            version: 1,
+            kek_version: 1,
+            oldest_kek_version: 1,
+            value_set_at: set,
+            expires_at: None,
+            rotate_every_secs: None,
+            last_resolved_at: None,
+            last_resolved_run: None,
+            created_at: set,
+            updated_at: set,
+        };
+        assert_eq!(meta.rotation_due_at(), None);
+        meta.rotate_every_secs = Some(3600);
+        assert_eq!(meta.rotation_due_at(), Some(set + chrono::Duration::hours(1)));
+        meta.expires_at = Some(set + chrono::Duration::minutes(5));
+        assert_eq!(meta.rotation_due_at(), meta.expires_at, "expiry first");
+        meta.rotate_every_secs = Some(u64::MAX);
+        assert_eq!(meta.rotation_due_at(), meta.expires_at, "huge interval never due");
+    }
+
+    #[test]
+    fn bundle_names_and_formats() {
+        assert!(validate_bundle_name("etl-jobs_v2.py").is_ok());
+        assert!(validate_bundle_name("").is_err());
+        assert!(validate_bundle_name(".hidden").is_err());
+        assert!(validate_bundle_name("a/b").is_err());
+        assert!(validate_bundle_name(&"a".repeat(129)).is_err());
+
+        let mut tar = vec![0u8; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(BundleFormat::detect(&tar), Some(BundleFormat::Tar));
+        assert_eq!(BundleFormat::detect(&[0x1f, 0x8b, 8]), Some(BundleFormat::TarGz));
+        assert_eq!(BundleFormat::detect(b"PK\x03\x04rest"), Some(BundleFormat::Zip));
+        assert_eq!(BundleFormat::detect(b"#!/bin/sh"), None);
+        assert_eq!("tarGz".parse::<BundleFormat>().unwrap(), BundleFormat::TarGz);
+    }
+
+    #[test]
+    fn git_code_sources_are_checked() {
+        let git = |url: &str, rev: Option<&str>, subdir: Option<&str>| CodeSource::Git {
+            url: url.to_string(),
+            rev: rev.map(str::to_string),
+            subdir: subdir.map(str::to_string),
+        };
+        assert!(git("file:///srv/git/etl.git", Some("v1.2"), Some("jobs/etl")).validate().is_ok());
+        assert!(git("/srv/mirrors/etl.git", None, None).validate().is_ok());
+        assert!(git("", None, None).validate().is_err());
+        assert!(git("--upload-pack=touch /tmp/x", None, None).validate().is_err());
+        assert!(git("/srv/etl.git", Some("--output=/tmp/x"), None).validate().is_err());
+        assert!(git("/srv/etl.git", Some("main branch"), None).validate().is_err());
+        assert!(git("/srv/etl.git", None, Some("../outside")).validate().is_err());
+        assert!(git("/srv/etl.git", None, Some("/abs")).validate().is_err());
+        let parsed: CodeSource =
+            serde_json::from_str(r#"{"source":"git","url":"/srv/etl.git"}"#).unwrap();
+        assert_eq!(parsed, git("/srv/etl.git", None, None));
+    }
+
     struct FakeSettings(std::sync::Mutex<HashMap<String, String>>);
 
     #[async_trait]
@@ -1486,6 +3260,11 @@ mod tests {
             scheduler_backstop_secs: 180,
             worker_claim_backstop_secs: 300,
             max_log_bytes: 0,
+            artifact_max_tenant_bytes: 0,
+            workspace_keep_run_secs: 0,
+            workspace_job_idle_secs: 0,
+            secret_max_versions: 10,
+            secret_audit_retention_secs: 0,
         };
         let settings = RuntimeSettings::new(store.clone(), defaults);
 
@@ -1569,11 +3348,63 @@ mod tests {
         // full jitter: delay in [0, raw]; never exceeds the capped backoff.
         for attempt in 1..=6 {
             for _ in 0..100 {
-                let d = next_retry_delay(BackoffStrategy::Exponential, 30, 600, attempt);
+                let d = next_retry_delay(BackoffStrategy::Exponential, 30, 600, attempt, None);
                 let raw = retry_backoff_secs(BackoffStrategy::Exponential, 30, 600, attempt) as i64;
                 let secs = d.num_seconds();
                 assert!(secs >= 0 && secs <= raw, "delay {secs} out of [0,{raw}]");
             }
         }
     }
+
+    #[test]
+    fn retry_after_is_a_capped_floor() {
+        for _ in 0..100 {
+            let d = next_retry_delay(BackoffStrategy::Fixed, 5, 600, 1, Some(Duration::seconds(120)));
+            assert_eq!(d.num_seconds(), 120);
+        }
+        // Never beyond the cap, whatever the server asks for.
+        let d = next_retry_delay(BackoffStrategy::Fixed, 5, 60, 1, Some(Duration::seconds(86_400)));
+        assert_eq!(d.num_seconds(), 60);
+    }
+
+    #[test]
+    fn parse_retry_after_seconds_and_date() {
+        let now = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_retry_after(" 30 ", now), Some(Duration::seconds(30)));
+        assert_eq!(
+            parse_retry_after("Wed, 01 Jan 2025 00:02:00 GMT", now),
+            Some(Duration::seconds(120))
+        );
+        // A date in the past means "now".
+        assert_eq!(
+            parse_retry_after("Tue, 31 Dec 2024 23:00:00 GMT", now),
+            Some(Duration::zero())
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+        // However large, delta-seconds never overflow; they read as the maximum.
+        let max = Some(Duration::seconds(MAX_RETRY_AFTER_SECS as i64));
+        assert_eq!(parse_retry_after("99999999999999999999", now), max);
+        assert_eq!(parse_retry_after("18446744073709551615", now), max);
+        assert_eq!(parse_retry_after("9223372036854775807", now), max);
+    }
+
+    #[test]
+    fn retry_on_defaults_and_overrides() {
+        let d = RetryOn::default();
+        assert!(d.retries_http_status(503) && !d.retries_http_status(409));
+        assert!(d.retries_exit_code(75) && !d.retries_exit_code(1));
+        assert!(!d.retries_error_type("TimeoutError"));
+
+        let r = RetryOn {
+            http_statuses: Some(vec![409]),
+            exit_codes: Some(vec![3, 75]),
+            error_types: vec!["TimeoutError".into()],
+        };
+        // A set list replaces the default.
+        assert!(r.retries_http_status(409) && !r.retries_http_status(503));
+        assert!(r.retries_exit_code(3));
+        assert!(r.retries_error_type("TimeoutError"));
+    }
 }
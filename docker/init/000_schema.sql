@@ -22,7 +22,7 @@ CREATE TABLE runner_types (
 );
 
 INSERT INTO runner_types (name) VALUES
-    ('shell'), ('http'), ('pgsql'), ('mysql'), ('python'), ('node');
+    ('shell'), ('http'), ('pgsql'), ('mysql'), ('sqlite'), ('python'), ('node'), ('wasm'), ('plugin');
 
 ----------------------------
 -- Core Tables
@@ -35,7 +35,9 @@ CREATE TABLE workers (
     capacity INT NOT NULL,
     active BOOLEAN NOT NULL DEFAULT true,
     restart_count INT NOT NULL DEFAULT 0,
-    version TEXT NOT NULL
+    version TEXT NOT NULL,
+    plugins JSONB NOT NULL DEFAULT '[]',  -- RunnerPluginInfo[] from the worker's plugin dir
+    disk_usage JSONB                      -- WorkerDiskUsage from the worker's last workspace sweep
 );
 
 -- Jobs are scheduler-level metadata + which runner they use
@@ -61,6 +63,9 @@ CREATE TABLE jobs (
     backoff_strategy TEXT NOT NULL DEFAULT 'exponential',
     backoff_base_secs INT NOT NULL DEFAULT 30,
     backoff_cap_secs INT NOT NULL DEFAULT 3600,
+    -- RetryOn: per-job overrides of what counts as retryable (HTTP statuses,
+    -- exit codes, runtime error types). '{}' = built-in defaults.
+    retry_on JSONB NOT NULL DEFAULT '{}',
     tenant_id UUID NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001' REFERENCES tenants(id),
     -- TODO: Make use of for smaller indexes/efficiency on some queries?
     deleted_at TIMESTAMPTZ DEFAULT NULL
@@ -99,6 +104,7 @@ CREATE TABLE pgsql_configs (
     password_secret TEXT NOT NULL,     -- eventually a reference to secrets
     database TEXT NOT NULL,
     tenant_id UUID NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001' REFERENCES tenants(id),
+    pool_size INT,                     -- per-worker connection cap; NULL = default
     UNIQUE(host, port, username, database),
     deleted_at TIMESTAMPTZ DEFAULT NULL
 );
@@ -113,10 +119,25 @@ CREATE TABLE mysql_configs (
     password_secret TEXT NOT NULL,
     database TEXT NOT NULL,
     tenant_id UUID NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001' REFERENCES tenants(id),
+    pool_size INT,                     -- per-worker connection cap; NULL = default
     UNIQUE(host, port, username, database),
     deleted_at TIMESTAMPTZ DEFAULT NULL
 );
 
+-- "Test connection" requests for shared configs. The api node queues a row, a worker
+-- claims it, probes the target and records the outcome; the api polls for it.
+-- config_id spans the config tables, so it carries no foreign key.
+CREATE TABLE config_tests (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    config_id UUID NOT NULL,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    claimed_by UUID,
+    claimed_at TIMESTAMPTZ,
+    result JSONB                       -- ConfigTestResult once finished
+);
+
+CREATE INDEX config_tests_pending_idx ON config_tests (requested_at) WHERE claimed_by IS NULL;
+
 ----------------------------
 -- Runner Config Tables
 ----------------------------
@@ -127,7 +148,10 @@ CREATE TABLE job_runner_shell (
     job_id UUID PRIMARY KEY REFERENCES jobs(id) ON DELETE CASCADE,
     command TEXT NOT NULL,
     working_dir TEXT,
-    shell_user TEXT
+    shell_user TEXT,
+    code JSONB,            -- CodeSource: fetched by the worker before the run
+    workspace TEXT NOT NULL DEFAULT 'run', -- WorkspaceMode: per-run or per-job working dir
+    secret_delivery TEXT NOT NULL DEFAULT 'env'  -- SecretDelivery: env secrets as env or files
 );
 
 CREATE TABLE job_runner_http (
@@ -136,34 +160,104 @@ CREATE TABLE job_runner_http (
     url TEXT NOT NULL,
     headers JSONB,         -- TODO: maybe investigate normalized headers table
     body TEXT,
-    timeout_sec INT
+    timeout_sec INT,
+    tls JSONB,             -- HttpTlsConfig: secret refs for client cert/key + CA bundle
+    signing JSONB          -- HttpSigningConfig: outbound HMAC / SigV4 request signing
 );
 
 CREATE TABLE job_runner_pgsql (
     job_id UUID PRIMARY KEY REFERENCES jobs(id) ON DELETE CASCADE,
     config_id UUID NOT NULL REFERENCES pgsql_configs(id) ON DELETE RESTRICT,
     query TEXT NOT NULL,
-    timeout_sec INT
+    timeout_sec INT,
+    output JSONB           -- DbOutputConfig: result-set format and row/byte caps
 );
 
 CREATE TABLE job_runner_mysql (
     job_id UUID PRIMARY KEY REFERENCES jobs(id) ON DELETE CASCADE,
     config_id UUID NOT NULL REFERENCES mysql_configs(id) ON DELETE RESTRICT,
     query TEXT NOT NULL,
-    timeout_sec INT
+    timeout_sec INT,
+    output JSONB           -- DbOutputConfig: result-set format and row/byte caps
+);
+
+CREATE TABLE job_runner_sqlite (
+    job_id UUID PRIMARY KEY REFERENCES jobs(id) ON DELETE CASCADE,
+    path TEXT NOT NULL,    -- database file on the worker
+    query TEXT NOT NULL,
+    timeout_sec INT,
+    busy_timeout_sec INT,
+    output JSONB           -- DbOutputConfig: result-set format and row/byte caps
 );
 
 CREATE TABLE job_runner_python (
     job_id UUID PRIMARY KEY REFERENCES jobs(id) ON DELETE CASCADE,
     module TEXT NOT NULL,
     class_name TEXT NOT NULL,
-    timeout_sec INT
+    timeout_sec INT,
+    transport TEXT NOT NULL DEFAULT 'file',
+    max_runs INT,
+    secrets JSONB NOT NULL DEFAULT '[]',  -- names readable via ctx.secret
+    code JSONB,                           -- CodeSource: fetched by the worker before the run
+    workspace TEXT NOT NULL DEFAULT 'run', -- WorkspaceMode: per-run or per-job working dir
+    secret_delivery TEXT NOT NULL DEFAULT 'env'  -- SecretDelivery: env secrets as env or files
 );
 
 CREATE TABLE job_runner_node (
     job_id UUID PRIMARY KEY REFERENCES jobs(id) ON DELETE CASCADE,
     module TEXT NOT NULL,
     function_name TEXT NOT NULL,
+    timeout_sec INT,
+    transport TEXT NOT NULL DEFAULT 'file',
+    max_runs INT,
+    secrets JSONB NOT NULL DEFAULT '[]',  -- names readable via ctx.secret
+    code JSONB,                           -- CodeSource: fetched by the worker before the run
+    workspace TEXT NOT NULL DEFAULT 'run', -- WorkspaceMode: per-run or per-job working dir
+    secret_delivery TEXT NOT NULL DEFAULT 'env'  -- SecretDelivery: env secrets as env or files
+);
+
+-- Uploaded WASM modules, content-addressed per tenant (hex SHA-256 of `content`).
+CREATE TABLE wasm_modules (
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    sha256 TEXT NOT NULL,
+    content BYTEA NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (tenant_id, sha256)
+);
+
+-- Uploaded code bundles (tar, tar.gz or zip), versioned per tenant and name. A new
+-- version is only added when the bytes differ from the latest (hex SHA-256 of `content`).
+CREATE TABLE code_bundles (
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    name TEXT NOT NULL,
+    version INT NOT NULL,
+    sha256 TEXT NOT NULL,
+    format TEXT NOT NULL,  -- tar | tarGz | zip
+    size_bytes BIGINT NOT NULL,
+    content BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (tenant_id, name, version)
+);
+
+-- Exactly one module source: a path on the worker, or an uploaded module's sha256.
+CREATE TABLE job_runner_wasm (
+    job_id UUID PRIMARY KEY REFERENCES jobs(id) ON DELETE CASCADE,
+    module_path TEXT,
+    module_sha256 TEXT,
+    timeout_sec INT,
+    fuel BIGINT,
+    max_memory_mb INT,
+    preopens JSONB NOT NULL DEFAULT '[]',  -- WasmPreopen[]
+    args JSONB NOT NULL DEFAULT '[]',
+    CHECK ((module_path IS NULL) <> (module_sha256 IS NULL))
+);
+
+-- An out-of-process runner plugin, by the name it reports in its handshake.
+CREATE TABLE job_runner_plugin (
+    job_id UUID PRIMARY KEY REFERENCES jobs(id) ON DELETE CASCADE,
+    plugin TEXT NOT NULL,
+    config JSONB NOT NULL DEFAULT '{}',
     timeout_sec INT
 );
 
@@ -190,7 +284,12 @@ CREATE TABLE job_runs (
     error TEXT,
     error_media_type TEXT,
     log TEXT,
-    config_snapshot JSONB
+    config_snapshot JSONB,
+    params JSONB,                                           -- ad-hoc run params (run-now), bound into DB runner queries
+    progress DOUBLE PRECISION,                              -- latest task-reported progress (0..1), reset on claim
+    progress_message TEXT,
+    last_heartbeat TIMESTAMPTZ,                             -- last runtime event; a recent one blocks dead-worker reclaim
+    code_commit TEXT                                        -- commit a git code source resolved to, reset on claim
 );
 
 -- Keep DB state in sync with Rust enum
@@ -234,11 +333,49 @@ CREATE TABLE secrets (
     dek_wrapped BYTEA NOT NULL,
     kek_version INT NOT NULL,
     tenant_id UUID NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001' REFERENCES tenants(id),
+    version INT NOT NULL DEFAULT 1,
+    expires_at TIMESTAMPTZ,               -- when the credential stops working (informational)
+    rotate_every_secs BIGINT,             -- rotation reminder interval, from the value's set time
+    last_resolved_at TIMESTAMPTZ,
+    last_resolved_run UUID,               -- no FK: runs are pruned, the record stays
     created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
     updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
     UNIQUE (tenant_id, name)
 );
 
+-- Retained versions of each secret; the current one is mirrored on the secrets row.
+CREATE TABLE secret_versions (
+    secret_id UUID NOT NULL REFERENCES secrets(id) ON DELETE CASCADE,
+    version INT NOT NULL,
+    value_ct BYTEA NOT NULL,
+    value_nonce BYTEA NOT NULL,
+    aead_algo TEXT NOT NULL,
+    dek_wrapped BYTEA NOT NULL,
+    kek_version INT NOT NULL,
+    restored_from INT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (secret_id, version)
+);
+
+-- Append-only trail of every resolve and change of a secret. No FK: events outlive the
+-- secret they are about. Pruned on its own retention window.
+CREATE TABLE secret_audit (
+    id UUID PRIMARY KEY,
+    secret_id UUID,                                         -- NULL: no such secret was found
+    tenant_id UUID NOT NULL,
+    secret_name TEXT NOT NULL,
+    version INT,
+    action TEXT NOT NULL,                                   -- resolve|set|rollback|policy|delete|rotate
+    actor_kind TEXT NOT NULL,                               -- user|run|node
+    actor_id UUID NOT NULL,
+    job_id UUID,                                            -- set for a run actor
+    outcome TEXT NOT NULL,                                  -- ok|denied|notFound|failed
+    detail TEXT,
+    at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX secret_audit_secret_idx ON secret_audit (secret_id, at DESC);
+CREATE INDEX secret_audit_at_idx ON secret_audit (at);
+
 CREATE TABLE kek_versions (
     version INT PRIMARY KEY,
     state TEXT NOT NULL,
@@ -272,10 +409,25 @@ CREATE TABLE run_log_chunks (
     seq BIGINT NOT NULL,
     stream TEXT NOT NULL,
     content TEXT NOT NULL,
+    level TEXT,                                             -- set for a structured log record
     created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
     PRIMARY KEY (run_id, attempt, seq)
 );
 
+-- Files a run attempt left in its artifacts directory, uploaded when it finished (the
+-- database artifact backend). Pruned together with their runs.
+CREATE TABLE run_artifacts (
+    run_id UUID NOT NULL,
+    attempt INT NOT NULL,
+    name TEXT NOT NULL,
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    size_bytes BIGINT NOT NULL,
+    sha256 TEXT NOT NULL,
+    content BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (run_id, attempt, name)
+);
+
 ----------------------------
 -- Indexes
 ----------------------------
@@ -319,3 +471,8 @@ CREATE INDEX workers_last_seen_idx
 CREATE INDEX jobs_enabled_idx
     ON jobs(enabled)
     WHERE enabled = TRUE AND schedule_cron IS NOT NULL;
+
+
+-- Sums a tenant's artifact bytes for its size cap.
+CREATE INDEX run_artifacts_tenant_idx
+    ON run_artifacts(tenant_id);
@@ -1,10 +1,15 @@
 use arbiter_config::NodeConfig;
-use arbiter_core::{ArbiterError, Clock, SecretAdmin, SecretResolver, SystemClock, WorkerStore};
+use arbiter_core::{
+    ArbiterError, ArtifactStore, Clock, SecretAdmin, SecretResolver, SystemClock, WorkerStore,
+};
 use arbiter_core::{Result, RuntimeDefaults, RuntimeSettings, SchedulerConfig, WorkerConfig};
 use arbiter_scheduler::run_scheduler_loop;
 use arbiter_store_pg::PgStore;
-use arbiter_worker::run_worker_loop;
+use arbiter_worker::{
+    FsArtifactStore, RunnerRegistry, WasmRunner, discover_plugins, run_worker_loop,
+};
 use fd_lock::RwLock;
+use std::os::fd::{FromRawFd, RawFd};
 use std::path::PathBuf;
 use std::{path::Path, sync::Arc};
 use tokio::fs::{File, OpenOptions};
@@ -33,9 +38,25 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     let cfg = NodeConfig::try_load()?;
+    // Read the operator KEK first, before anything spawns a child that could inherit its fd.
+    let kek_source = kek_source(&cfg.secrets)?;
 
     let store = Arc::new(PgStore::new(&cfg.database.url).await?);
 
+    // Run artifacts: blobs in the shared store, or files under a directory every node shares.
+    let artifacts: Arc<dyn ArtifactStore + Send + Sync> = match cfg.artifacts.backend.as_str() {
+        "db" => store.clone(),
+        "fs" => {
+            let root = cfg
+                .artifacts
+                .root
+                .clone()
+                .unwrap_or_else(|| format!("{}/artifacts", cfg.node.data_dir));
+            Arc::new(FsArtifactStore::new(root))
+        }
+        other => anyhow::bail!("unknown artifacts.backend '{other}' (expected 'db' or 'fs')"),
+    };
+
     let hostname_str = hostname::get()
         .unwrap_or_default()
         .to_string_lossy()
@@ -66,6 +87,7 @@ async fn main() -> anyhow::Result<()> {
         dead_after_secs: 30,
         restart_count: identity.restart_count,
         version: env!("CARGO_PKG_VERSION").to_string(),
+        data_dir: cfg.node.data_dir.clone().into(),
     };
 
     let scheduler_cfg = SchedulerConfig {
@@ -85,6 +107,11 @@ async fn main() -> anyhow::Result<()> {
             scheduler_backstop_secs: cfg.scheduler.backstop_secs,
             worker_claim_backstop_secs: cfg.worker.claim_backstop_secs,
             max_log_bytes: cfg.worker.max_log_bytes,
+            artifact_max_tenant_bytes: cfg.artifacts.max_tenant_bytes,
+            workspace_keep_run_secs: cfg.worker.workspace_keep_run_secs,
+            workspace_job_idle_secs: cfg.worker.workspace_job_idle_secs,
+            secret_max_versions: cfg.secrets.max_versions as u64,
+            secret_audit_retention_secs: cfg.retention.secret_audit_days as u64 * 86_400,
         },
     );
     if let Err(e) = runtime_settings.refresh().await {
@@ -115,9 +142,10 @@ async fn main() -> anyhow::Result<()> {
     );
     let secret_store: Arc<dyn arbiter_core::SecretStore + Send + Sync> = store.clone();
     let secret_manager = Arc::new(
-        load_or_join_kek(&secret_store, worker_cfg.worker_id, node_keyring)
+        load_or_join_kek(&secret_store, worker_cfg.worker_id, node_keyring, kek_source)
             .await
-            .map_err(|e| ArbiterError::DatabaseError(format!("secret manager: {e}")))?,
+            .map_err(|e| ArbiterError::DatabaseError(format!("secret manager: {e}")))?
+            .with_settings(runtime_settings.clone()),
     );
 
     // KEK upkeep task, on every node that holds the KEK. Two idempotent steps each cycle:
@@ -177,10 +205,34 @@ async fn main() -> anyhow::Result<()> {
         let store_for_worker = store.clone();
         let secrets: arbiter_worker::Secrets =
             Some(secret_manager.clone() as Arc<dyn SecretResolver + Send + Sync>);
+        // Runners this node executes, by snapshot kind. Extra runners register here (the
+        // wasm runner again, confined to the configured host root); plugins found in the
+        // plugin dir are registered as `plugin:<name>` and advertised.
+        let mut runners = RunnerRegistry::with_builtins();
+        runners.register(
+            "wasm",
+            WasmRunner::new(cfg.worker.wasm_host_root.as_ref().map(PathBuf::from)),
+        );
+        if let Some(dir) = &cfg.worker.plugin_dir {
+            for plugin in discover_plugins(std::path::Path::new(dir)).await {
+                runners.register_plugin(plugin);
+            }
+        }
+        let runners = Arc::new(runners);
+        let artifacts = Some(artifacts.clone());
         let settings = runtime_settings.clone();
         let clock: Arc<dyn Clock> = Arc::new(SystemClock);
         tokio::spawn(async move {
-            run_worker_loop(store_for_worker, worker_cfg, secrets, settings, clock).await;
+            run_worker_loop(
+                store_for_worker,
+                worker_cfg,
+                secrets,
+                artifacts,
+                runners,
+                settings,
+                clock,
+            )
+            .await;
         });
     }
 
@@ -188,29 +240,90 @@ async fn main() -> anyhow::Result<()> {
     if cfg.roles.api {
         arbiter_api::seed_admin(store.as_ref(), &cfg.admin).await?;
         let secret_admin: Option<Arc<dyn SecretAdmin>> = Some(secret_manager.clone());
-        arbiter_api::run_http_api(store.clone(), secret_admin, &cfg.api).await?;
+        arbiter_api::run_http_api(store.clone(), secret_admin, artifacts, &cfg.api).await?;
     } else {
         futures::future::pending::<()>().await;
     }
     Ok(())
 }
 
+/// The KEK source `secrets.kek_source` names, reading an operator passphrase or key from
+/// `kek_file` (or `kek_fd`) now. An inherited `kek_fd` is read to its end and closed, so no
+/// job this node later spawns inherits it.
+fn kek_source(
+    cfg: &arbiter_config::SecretsConfig,
+) -> anyhow::Result<Arc<dyn arbiter_secrets::KekSource>> {
+    let kind = cfg.kek_source.as_str();
+    if kind == "cluster" {
+        return Ok(Arc::new(arbiter_secrets::ClusterKek));
+    }
+    if kind != "passphrase" && kind != "key" {
+        anyhow::bail!(
+            "unknown secrets.kek_source '{kind}' (expected 'cluster', 'passphrase' or 'key')"
+        );
+    }
+    let (file, from) = match (&cfg.kek_file, cfg.kek_fd) {
+        (Some(file), _) => (
+            std::fs::File::open(file)
+                .map_err(|e| anyhow::anyhow!("KEK {kind} from {file}: {e}"))?,
+            file.clone(),
+        ),
+        (None, Some(fd)) => (take_inherited_fd(fd)?, format!("fd {fd}")),
+        (None, None) => {
+            anyhow::bail!("secrets.kek_source '{kind}' needs secrets.kek_file or secrets.kek_fd")
+        }
+    };
+    let source = if kind == "passphrase" {
+        arbiter_secrets::OperatorKek::read_passphrase_from(file, Default::default())
+    } else {
+        arbiter_secrets::OperatorKek::read_key_from(file)
+    }
+    .map_err(|e| anyhow::anyhow!("KEK {kind} from {from}: {e}"))?;
+    if let Some(fd) = cfg.kek_fd
+        && cfg.kek_file.is_none()
+        && fd_is_open(fd as RawFd)
+    {
+        anyhow::bail!("secrets.kek_fd {fd} is still open after the KEK was read");
+    }
+    Ok(Arc::new(source))
+}
+
+/// Adopt the inherited descriptor `fd` as a file, so dropping it closes the descriptor.
+fn take_inherited_fd(fd: u32) -> anyhow::Result<std::fs::File> {
+    let raw = RawFd::try_from(fd)
+        .map_err(|_| anyhow::anyhow!("secrets.kek_fd {fd} is not a file descriptor"))?;
+    // Adopting a closed number would later close whatever file reuses it.
+    if !fd_is_open(raw) {
+        anyhow::bail!("secrets.kek_fd {fd} is not an open file descriptor");
+    }
+    // SAFETY: `raw` is open and was handed to this process for the KEK alone; nothing else
+    // refers to it, and the returned file is its only owner from here on.
+    Ok(unsafe { std::fs::File::from_raw_fd(raw) })
+}
+
+fn fd_is_open(fd: RawFd) -> bool {
+    // SAFETY: F_GETFD only reads the descriptor's flags.
+    unsafe { libc::fcntl(fd, libc::F_GETFD) != -1 }
+}
+
 /// Load this node's KEK, or - when a KEK already exists but no share is sealed to this
 /// node yet - register our key and wait for an existing holder to reconcile a share to us
-/// (the multi-node join path). `load_or_bootstrap` registers our public key on each call,
+/// (the multi-node join path). `load_with_source` registers our public key on each call,
 /// so retrying simply waits for a holder's reconcile to seal it.
 async fn load_or_join_kek(
     store: &Arc<dyn arbiter_core::SecretStore + Send + Sync>,
     node_id: Uuid,
     keyring: Arc<arbiter_secrets::NodeKeyring>,
+    source: Arc<dyn arbiter_secrets::KekSource>,
 ) -> std::result::Result<arbiter_secrets::SecretManager, arbiter_secrets::SecretsError> {
     const ATTEMPTS: u32 = 60;
     const DELAY: std::time::Duration = std::time::Duration::from_secs(5);
     for attempt in 1..=ATTEMPTS {
-        match arbiter_secrets::SecretManager::load_or_bootstrap(
+        match arbiter_secrets::SecretManager::load_with_source(
             store.clone(),
             node_id,
             keyring.clone(),
+            source.clone(),
         )
         .await
         {
@@ -378,3 +491,46 @@ async fn acquire_identity_file(
 
     Err(std::io::Error::other("Exhausted worker-id slots"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether a child spawned now sees descriptor `fd`.
+    fn child_sees(fd: RawFd) -> bool {
+        std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("test -e /dev/fd/{fd}"))
+            .status()
+            .expect("spawn sh")
+            .success()
+    }
+
+    #[test]
+    fn kek_fd_is_closed_once_read() {
+        // A pipe without CLOEXEC stands in for the descriptor the node was started with.
+        let mut fds = [0 as RawFd; 2];
+        // SAFETY: `fds` has room for the two descriptors pipe(2) writes.
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0, "pipe");
+        let [read, write] = fds;
+        {
+            use std::io::Write as _;
+            // SAFETY: `write` was just created and is owned only here.
+            let mut writer = unsafe { std::fs::File::from_raw_fd(write) };
+            writer.write_all(&[7u8; 32]).expect("write key");
+        }
+        assert!(
+            child_sees(read),
+            "the inherited fd is visible before the KEK is read"
+        );
+
+        let cfg = arbiter_config::SecretsConfig {
+            kek_source: "key".into(),
+            kek_fd: Some(read as u32),
+            ..Default::default()
+        };
+        kek_source(&cfg).expect("read the KEK from the fd");
+        assert!(!fd_is_open(read), "the node still holds the KEK fd");
+        assert!(!child_sees(read), "a spawned child inherits the KEK fd");
+    }
+}
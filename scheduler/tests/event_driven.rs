@@ -16,7 +16,7 @@ use uuid::Uuid;
 use arbiter_core::{
     BackoffStrategy, Clock, JobSpec, JobStore, MisfirePolicy, Result, RuntimeDefaults,
     RuntimeSettings, SchedulerConfig, Setting, SettingsStore, WorkerRecord, WorkerStore,
-    RunnerConfig,
+    RunnerConfig, SecretDelivery, WorkspaceMode,
 };
 use arbiter_scheduler::run_scheduler_loop;
 
@@ -135,6 +135,9 @@ fn every_minute_job() -> JobSpec {
         runner_cfg: RunnerConfig::Shell {
             command: "echo hi".into(),
             working_dir: None,
+            code: None,
+            workspace: WorkspaceMode::Run,
+            secret_delivery: SecretDelivery::Env,
         },
         max_concurrency: 1,
         misfire_policy: MisfirePolicy::RunImmediately,
@@ -142,6 +145,7 @@ fn every_minute_job() -> JobSpec {
         backoff_strategy: BackoffStrategy::Exponential,
         backoff_base_secs: 30,
         backoff_cap_secs: 3600,
+        retry_on: Default::default(),
     }
 }
 
@@ -153,6 +157,11 @@ fn defaults() -> RuntimeDefaults {
         scheduler_backstop_secs: 180,
         worker_claim_backstop_secs: 300,
         max_log_bytes: 0,
+        artifact_max_tenant_bytes: 0,
+        workspace_keep_run_secs: 0,
+        workspace_job_idle_secs: 0,
+        secret_max_versions: 10,
+        secret_audit_retention_secs: 0,
     }
 }
 
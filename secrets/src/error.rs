@@ -9,6 +9,8 @@ pub enum SecretsError {
     Malformed(String),
     NotFound(String),
     KeyUnavailable(String),
+    /// The node's KEK source does not fit the cluster (wrong passphrase, wrong kind of source).
+    Misconfigured(String),
     Crypto(CryptoError),
 }
 
@@ -20,6 +22,7 @@ impl fmt::Display for SecretsError {
             SecretsError::Malformed(msg) => write!(f, "malformed: {msg}"),
             SecretsError::NotFound(msg) => write!(f, "not found: {msg}"),
             SecretsError::KeyUnavailable(msg) => write!(f, "key unavailable: {msg}"),
+            SecretsError::Misconfigured(msg) => write!(f, "misconfigured: {msg}"),
             SecretsError::Crypto(e) => write!(f, "crypto error: {e}"),
         }
     }
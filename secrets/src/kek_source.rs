@@ -0,0 +1,306 @@
+use std::io::Read;
+use std::path::Path;
+use std::sync::Mutex;
+
+use arbiter_crypto::{
+    Aead, Ciphertext, KEY_LEN, KeyWrap, NodePublicKey, SealedBox, SymKey, XChaChaAead,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as B64;
+use uuid::Uuid;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::error::{Result, SecretsError};
+use crate::identity::NodeKeyring;
+
+/// The pseudo-node every KEK version of an operator-held cluster is also shared to. A node
+/// that has never held a share (a new node, or every node after their identities were lost)
+/// recovers the KEK from it with the operator key. Never a real node id.
+pub const RECOVERY_SHARE_ID: Uuid = Uuid::max();
+
+/// Where a node's KEK comes from (SECRETS.md "KEK source is pluggable"). Each KEK version
+/// is kept in the DB as one share per node; the source decides what a share is sealed to and
+/// how this node opens its own.
+pub trait KekSource: Send + Sync {
+    /// Seal a KEK version into a share for the node whose public key is `node`.
+    fn seal(&self, node: &NodePublicKey, kek: &SymKey) -> Result<Vec<u8>>;
+
+    /// Open a share sealed for this node.
+    fn open(&self, identity: &NodeKeyring, share: &[u8]) -> Result<SymKey>;
+
+    /// Whether a share opens only with the identity of the node it was sealed to. Then an
+    /// admin approves each node before it is sealed a share. Otherwise whoever holds the
+    /// source's key is trusted: a node approves itself and can open any share, including
+    /// the [`RECOVERY_SHARE_ID`] one.
+    fn node_bound(&self) -> bool;
+}
+
+/// The default source: the KEK is generated in the cluster and each share is sealed to one
+/// node's public key, so only that node's private key opens it.
+pub struct ClusterKek;
+
+impl KekSource for ClusterKek {
+    fn seal(&self, node: &NodePublicKey, kek: &SymKey) -> Result<Vec<u8>> {
+        Ok(SealedBox.seal(node, kek.expose_bytes())?)
+    }
+
+    fn open(&self, identity: &NodeKeyring, share: &[u8]) -> Result<SymKey> {
+        // Try every node key version until one opens the share.
+        for entry in identity.entries() {
+            if let Ok(plaintext) = SealedBox.open(entry.keypair(), share) {
+                return sym_from_vec(plaintext);
+            }
+        }
+        Err(SecretsError::KeyUnavailable(
+            "no node key opens the sealed KEK".into(),
+        ))
+    }
+
+    fn node_bound(&self) -> bool {
+        true
+    }
+}
+
+/// Argon2id cost of deriving the operator key from a passphrase. Stored with each share, so
+/// a share stays openable after the defaults change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Cost {
+    /// Memory, in KiB.
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Cost {
+    fn default() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+impl Argon2Cost {
+    /// The costliest derivation a node runs. The cost comes from the share header in the
+    /// DB, so anything above this is refused rather than allocated or iterated.
+    pub const MAX: Argon2Cost = Argon2Cost {
+        memory_kib: 1024 * 1024,
+        iterations: 16,
+        parallelism: 8,
+    };
+}
+
+/// Shortest passphrase accepted, in bytes.
+pub const MIN_PASSPHRASE_LEN: usize = 12;
+
+const SHARE_FORMAT: u8 = 1;
+const KDF_NONE: u8 = 0;
+const KDF_ARGON2ID: u8 = 1;
+const SALT_LEN: usize = 16;
+/// format, kdf, memory, iterations, parallelism, salt.
+const HEADER_LEN: usize = 2 + 3 * 4 + SALT_LEN;
+const SHARE_AAD: &[u8] = b"arbiter-operator-kek";
+const NONCE_LEN: usize = 24;
+
+/// An operator-held source: every share is encrypted under one operator key, read at startup
+/// from a file or file descriptor, either as the key itself or as a passphrase the key is
+/// derived from (Argon2id). Nothing in the DB or on a node's disk opens a share without it,
+/// so a stolen DB is useless even next to the node identity it shares a host with, and any
+/// node given the key recovers the KEK, even when every node identity was lost.
+pub struct OperatorKek {
+    secret: OperatorSecret,
+    /// The header new shares are written with, and the key it derives.
+    header: [u8; HEADER_LEN],
+    key: SymKey,
+    /// Keys derived for other headers (shares written by a node with another salt or cost).
+    derived: Mutex<Vec<([u8; HEADER_LEN], SymKey)>>,
+}
+
+enum OperatorSecret {
+    Key,
+    Passphrase(Zeroizing<Vec<u8>>),
+}
+
+impl OperatorKek {
+    /// Use `key` as the operator key.
+    pub fn from_key(key: SymKey) -> Self {
+        let mut header = [0u8; HEADER_LEN];
+        header[0] = SHARE_FORMAT;
+        header[1] = KDF_NONE;
+        Self {
+            secret: OperatorSecret::Key,
+            header,
+            key,
+            derived: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Derive the operator key from `passphrase` with Argon2id at `cost` (and a fresh salt).
+    pub fn from_passphrase(passphrase: &[u8], cost: Argon2Cost) -> Result<Self> {
+        if passphrase.len() < MIN_PASSPHRASE_LEN {
+            return Err(SecretsError::Misconfigured(format!(
+                "the KEK passphrase must be at least {MIN_PASSPHRASE_LEN} bytes"
+            )));
+        }
+        let mut header = [0u8; HEADER_LEN];
+        header[0] = SHARE_FORMAT;
+        header[1] = KDF_ARGON2ID;
+        header[2..6].copy_from_slice(&cost.memory_kib.to_le_bytes());
+        header[6..10].copy_from_slice(&cost.iterations.to_le_bytes());
+        header[10..14].copy_from_slice(&cost.parallelism.to_le_bytes());
+        header[14..].copy_from_slice(&SymKey::generate().expose_bytes()[..SALT_LEN]);
+        let key = derive(passphrase, &header)?;
+        Ok(Self {
+            secret: OperatorSecret::Passphrase(Zeroizing::new(passphrase.to_vec())),
+            header,
+            key,
+            derived: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Read the operator key from a file: its 32 raw bytes, or their base64.
+    pub fn read_key(path: &Path) -> Result<Self> {
+        Self::read_key_from(std::fs::File::open(path)?)
+    }
+
+    /// Read the operator key, as [`read_key`](Self::read_key) does, from `source` (an
+    /// inherited file descriptor, say) until its end.
+    pub fn read_key_from(mut source: impl Read) -> Result<Self> {
+        let mut bytes = Zeroizing::new(Vec::new());
+        source.read_to_end(&mut bytes)?;
+        if bytes.len() == KEY_LEN {
+            return sym_from_vec(bytes.to_vec()).map(Self::from_key);
+        }
+        let text = std::str::from_utf8(&bytes)
+            .map_err(|_| SecretsError::Misconfigured("the KEK file is not a 32-byte key".into()))?;
+        let decoded = B64.decode(text.trim()).map_err(|_| {
+            SecretsError::Misconfigured("the KEK file is neither 32 raw bytes nor base64".into())
+        })?;
+        sym_from_vec(decoded).map(Self::from_key).map_err(|_| {
+            SecretsError::Misconfigured("the KEK file does not hold a 32-byte key".into())
+        })
+    }
+
+    /// Read a passphrase from a file (one trailing newline is dropped) and derive the
+    /// operator key from it.
+    pub fn read_passphrase(path: &Path, cost: Argon2Cost) -> Result<Self> {
+        Self::read_passphrase_from(std::fs::File::open(path)?, cost)
+    }
+
+    /// Read a passphrase, as [`read_passphrase`](Self::read_passphrase) does, from `source`
+    /// until its end.
+    pub fn read_passphrase_from(mut source: impl Read, cost: Argon2Cost) -> Result<Self> {
+        let mut bytes = Zeroizing::new(Vec::new());
+        source.read_to_end(&mut bytes)?;
+        if bytes.ends_with(b"\n") {
+            bytes.pop();
+            if bytes.ends_with(b"\r") {
+                bytes.pop();
+            }
+        }
+        Self::from_passphrase(&bytes, cost)
+    }
+
+    /// The key a share with `header` is encrypted under, deriving (and caching) it when the
+    /// share was written with another salt or cost than ours.
+    fn key_for(&self, header: &[u8; HEADER_LEN]) -> Result<SymKey> {
+        if *header == self.header {
+            return Ok(self.key.clone());
+        }
+        let OperatorSecret::Passphrase(passphrase) = &self.secret else {
+            return Err(SecretsError::Misconfigured(
+                "the KEK share was sealed with a passphrase, but a key file was given".into(),
+            ));
+        };
+        if header[1] != KDF_ARGON2ID {
+            return Err(SecretsError::Misconfigured(
+                "the KEK share was sealed with a key file, but a passphrase was given".into(),
+            ));
+        }
+        let mut derived = self.derived.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, key)) = derived.iter().find(|(h, _)| h == header) {
+            return Ok(key.clone());
+        }
+        let key = derive(passphrase, header)?;
+        derived.push((*header, key.clone()));
+        Ok(key)
+    }
+}
+
+impl KekSource for OperatorKek {
+    fn seal(&self, _node: &NodePublicKey, kek: &SymKey) -> Result<Vec<u8>> {
+        let ct = XChaChaAead.encrypt(&self.key, kek.expose_bytes(), SHARE_AAD)?;
+        let mut out = Vec::with_capacity(HEADER_LEN + ct.nonce.len() + ct.bytes.len());
+        out.extend_from_slice(&self.header);
+        out.extend_from_slice(&ct.nonce);
+        out.extend_from_slice(&ct.bytes);
+        Ok(out)
+    }
+
+    fn open(&self, _identity: &NodeKeyring, share: &[u8]) -> Result<SymKey> {
+        if share.len() < HEADER_LEN + NONCE_LEN || share[0] != SHARE_FORMAT {
+            return Err(SecretsError::Misconfigured(
+                "the KEK share is not operator-sealed; this cluster's KEK is sealed to node identities"
+                    .into(),
+            ));
+        }
+        let mut header = [0u8; HEADER_LEN];
+        header.copy_from_slice(&share[..HEADER_LEN]);
+        let key = self.key_for(&header)?;
+        let ct = Ciphertext {
+            nonce: share[HEADER_LEN..HEADER_LEN + NONCE_LEN].to_vec(),
+            bytes: share[HEADER_LEN + NONCE_LEN..].to_vec(),
+        };
+        let kek = XChaChaAead.decrypt(&key, &ct, SHARE_AAD).map_err(|_| {
+            SecretsError::Misconfigured(
+                "the operator key does not open the KEK (wrong passphrase or key file?)".into(),
+            )
+        })?;
+        sym_from_vec(kek)
+    }
+
+    fn node_bound(&self) -> bool {
+        false
+    }
+}
+
+/// Run Argon2id over `passphrase` with the cost and salt in `header`. A cost above
+/// [`Argon2Cost::MAX`] is refused before anything is allocated.
+fn derive(passphrase: &[u8], header: &[u8; HEADER_LEN]) -> Result<SymKey> {
+    let word = |at: usize| {
+        u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+    let (memory_kib, iterations, parallelism) = (word(2), word(6), word(10));
+    let max = Argon2Cost::MAX;
+    if memory_kib > max.memory_kib || iterations > max.iterations || parallelism > max.parallelism {
+        return Err(SecretsError::Malformed(format!(
+            "argon2 cost m={memory_kib} t={iterations} p={parallelism} exceeds the maximum \
+             m={} t={} p={}",
+            max.memory_kib, max.iterations, max.parallelism
+        )));
+    }
+    let params = Params::new(memory_kib, iterations, parallelism, Some(KEY_LEN))
+        .map_err(|e| SecretsError::Malformed(format!("argon2 cost: {e}")))?;
+    let mut out = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase, &header[14..], &mut out)
+        .map_err(|e| SecretsError::Malformed(format!("argon2: {e}")))?;
+    let key = SymKey::from_bytes(out);
+    out.zeroize();
+    Ok(key)
+}
+
+pub(crate) fn sym_from_vec(mut bytes: Vec<u8>) -> Result<SymKey> {
+    if bytes.len() != KEY_LEN {
+        bytes.zeroize();
+        return Err(SecretsError::Malformed("wrong key length".into()));
+    }
+    let mut arr = [0u8; KEY_LEN];
+    arr.copy_from_slice(&bytes);
+    bytes.zeroize();
+    let key = SymKey::from_bytes(arr);
+    arr.zeroize();
+    Ok(key)
+}
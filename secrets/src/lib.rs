@@ -4,15 +4,23 @@
 //! keypairs), persisted through a pluggable [`NodeIdentityStore`] (default
 //! [`FileNodeIdentityStore`], a `0600` JSON file). This is the one local secret a node
 //! keeps outside the shared DB.
+//!
+//! The KEK itself comes from a [`KekSource`]: [`ClusterKek`] (the default, generated in the
+//! cluster and sealed to each node's identity) or [`OperatorKek`] (held by the operator as a
+//! key or passphrase, supplied at startup).
 
 mod error;
 mod identity;
+mod kek_source;
 mod manager;
 
 pub use error::{Result, SecretsError};
 pub use identity::{
     FileNodeIdentityStore, NodeIdentityStore, NodeKeyEntry, NodeKeyring, load_or_generate,
 };
+pub use kek_source::{
+    Argon2Cost, ClusterKek, KekSource, MIN_PASSPHRASE_LEN, OperatorKek, RECOVERY_SHARE_ID,
+};
 pub use manager::SecretManager;
 
 #[cfg(test)]
@@ -304,6 +312,124 @@ mod manager_tests {
         assert_eq!(after.kek_version, 2);
     }
 
+    #[tokio::test]
+    async fn pinned_versions_resolve_roll_back_and_survive_rotation() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = store_at(&dir.path().join("s.db")).await;
+        let mgr = SecretManager::load_or_bootstrap(store.clone(), Uuid::new_v4(), Arc::new(NodeKeyring::generate()))
+            .await
+            .expect("bootstrap");
+        let id = mgr.set_secret(DEFAULT_TENANT_ID, "pw", b"right").await.expect("set v1");
+        mgr.set_secret(DEFAULT_TENANT_ID, "pw", b"typo").await.expect("set v2");
+
+        // Unpinned follows the current version; a pin reads a retained one.
+        assert_eq!(&*mgr.resolve(DEFAULT_TENANT_ID, "pw").await.expect("resolve"), b"typo");
+        assert_eq!(&*mgr.resolve(DEFAULT_TENANT_ID, "pw@1").await.expect("pin 1"), b"right");
+        assert_eq!(&*mgr.resolve(DEFAULT_TENANT_ID, "pw@2").await.expect("pin 2"), b"typo");
+        assert!(matches!(
+            mgr.resolve(DEFAULT_TENANT_ID, "pw@9").await,
+            Err(SecretsError::NotFound(_))
+        ));
+
+        // Rotation rewraps every retained version, so the pin outlives the old KEK.
+        mgr.rotate_kek().await.expect("rotate");
+        assert!(store
+            .list_secret_versions(id)
+            .await
+            .expect("versions")
+            .iter()
+            .all(|v| v.kek_version == 2));
+        assert_eq!(&*mgr.resolve(DEFAULT_TENANT_ID, "pw@1").await.expect("pin 1"), b"right");
+
+        // Rolling back makes the old value current as a new version.
+        assert_eq!(mgr.rollback_secret(id, 1).await.expect("rollback"), 3);
+        assert_eq!(&*mgr.resolve(DEFAULT_TENANT_ID, "pw").await.expect("resolve"), b"right");
+        assert!(matches!(
+            mgr.rollback_secret(id, 9).await,
+            Err(SecretsError::NotFound(_))
+        ));
+
+        // Only the newest DEFAULT_SECRET_MAX_VERSIONS are kept.
+        for i in 0..arbiter_core::DEFAULT_SECRET_MAX_VERSIONS {
+            mgr.set_secret(DEFAULT_TENANT_ID, "pw", format!("v{i}").as_bytes())
+                .await
+                .expect("set");
+        }
+        let versions = store.list_secret_versions(id).await.expect("versions");
+        assert_eq!(versions.len() as u32, arbiter_core::DEFAULT_SECRET_MAX_VERSIONS);
+        assert!(matches!(
+            mgr.resolve(DEFAULT_TENANT_ID, "pw@1").await,
+            Err(SecretsError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn resolves_and_rotations_are_audited() {
+        use arbiter_core::{SecretActor, SecretAuditAction, SecretAuditOutcome};
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = store_at(&dir.path().join("s.db")).await;
+        let node_id = Uuid::new_v4();
+        let mgr = SecretManager::load_or_bootstrap(
+            store.clone(),
+            node_id,
+            Arc::new(NodeKeyring::generate()),
+        )
+        .await
+        .expect("bootstrap");
+        let id = mgr
+            .set_secret(DEFAULT_TENANT_ID, "pw", b"hunter2")
+            .await
+            .expect("set");
+
+        let run = SecretActor::Run {
+            run_id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+        };
+        mgr.resolve_as(DEFAULT_TENANT_ID, "pw", run)
+            .await
+            .expect("resolve");
+        assert!(matches!(
+            mgr.resolve_as(DEFAULT_TENANT_ID, "pw@7", run).await,
+            Err(SecretsError::NotFound(_))
+        ));
+        mgr.rotate_kek().await.expect("rotate");
+
+        // Newest first: the rotation's re-wrap by this node, the missing pin, the resolve.
+        let events = store.list_secret_audit(id, None, 10).await.expect("audit");
+        let seen: Vec<_> = events
+            .iter()
+            .map(|e| (e.action, e.actor, e.outcome, e.version))
+            .collect();
+        assert_eq!(
+            seen,
+            vec![
+                (
+                    SecretAuditAction::Rotate,
+                    SecretActor::Node { node_id },
+                    SecretAuditOutcome::Ok,
+                    None
+                ),
+                (
+                    SecretAuditAction::Resolve,
+                    run,
+                    SecretAuditOutcome::NotFound,
+                    Some(7)
+                ),
+                (
+                    SecretAuditAction::Resolve,
+                    run,
+                    SecretAuditOutcome::Ok,
+                    Some(1)
+                ),
+            ]
+        );
+        assert!(
+            events
+                .iter()
+                .all(|e| e.tenant_id == DEFAULT_TENANT_ID && e.secret_name == "pw")
+        );
+    }
+
     #[tokio::test]
     async fn rotation_locks_out_a_revoked_node() {
         let dir = tempfile::tempdir().expect("tempdir");
@@ -395,4 +521,188 @@ mod manager_tests {
         assert_eq!(mgr_b.current_kek_version(), 2);
         assert_eq!(&*mgr_b.resolve(DEFAULT_TENANT_ID, "k").await.expect("b reads new"), b"v");
     }
+
+    /// Cheap enough for tests; the real default is far costlier.
+    fn test_passphrase(passphrase: &[u8]) -> Arc<dyn KekSource> {
+        let cost = Argon2Cost { memory_kib: 64, iterations: 1, parallelism: 1 };
+        Arc::new(OperatorKek::from_passphrase(passphrase, cost).expect("passphrase"))
+    }
+
+    #[tokio::test]
+    async fn operator_passphrase_recovers_a_cluster_with_lost_identities() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = store_at(&dir.path().join("s.db")).await;
+        let passphrase = b"correct horse battery staple";
+
+        let founder = Uuid::new_v4();
+        let id_a = Arc::new(NodeKeyring::generate());
+        {
+            let mgr = SecretManager::load_with_source(
+                store.clone(),
+                founder,
+                id_a.clone(),
+                test_passphrase(passphrase),
+            )
+            .await
+            .expect("bootstrap");
+            mgr.set_secret(DEFAULT_TENANT_ID, "db-pass", b"hunter2").await.expect("set");
+            mgr.rotate_kek().await.expect("rotate");
+        }
+
+        // The DB plus the founder's own identity opens nothing without the passphrase.
+        assert!(matches!(
+            SecretManager::load_or_bootstrap(store.clone(), founder, id_a.clone()).await,
+            Err(SecretsError::Misconfigured(_))
+        ));
+        assert!(matches!(
+            SecretManager::load_with_source(
+                store.clone(),
+                founder,
+                id_a,
+                test_passphrase(b"not the passphrase at all"),
+            )
+            .await,
+            Err(SecretsError::Misconfigured(_))
+        ));
+
+        // Every identity lost: a brand-new node given the passphrase approves itself, takes
+        // its share from the recovery one, and reads the secret (derived with its own salt).
+        let node = Uuid::new_v4();
+        let mgr = SecretManager::load_with_source(
+            store.clone(),
+            node,
+            Arc::new(NodeKeyring::generate()),
+            test_passphrase(passphrase),
+        )
+        .await
+        .expect("recover");
+        assert_eq!(mgr.current_kek_version(), 2);
+        assert_eq!(&*mgr.resolve(DEFAULT_TENANT_ID, "db-pass").await.expect("resolve"), b"hunter2");
+        assert!(store.get_kek_share(2, node).await.expect("share").is_some());
+        let keys = store.list_node_keys().await.expect("node keys");
+        assert!(keys.iter().any(|k| k.node_id == node && k.status == "approved"));
+    }
+
+    #[tokio::test]
+    async fn operator_key_rotation_keeps_a_recovery_share() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = store_at(&dir.path().join("s.db")).await;
+        let key = arbiter_crypto::SymKey::generate();
+        let source = || -> Arc<dyn KekSource> { Arc::new(OperatorKek::from_key(key.clone())) };
+        let mgr = SecretManager::load_with_source(
+            store.clone(),
+            Uuid::new_v4(),
+            Arc::new(NodeKeyring::generate()),
+            source(),
+        )
+        .await
+        .expect("bootstrap");
+        mgr.set_secret(DEFAULT_TENANT_ID, "k", b"v").await.expect("set");
+        assert_eq!(mgr.rotate_kek().await.expect("rotate").phase, RotationPhase::Done);
+
+        // The retired version's recovery share went with it; the new one has its own.
+        assert!(store.get_kek_share(1, RECOVERY_SHARE_ID).await.expect("v1").is_none());
+        assert!(store.get_kek_share(2, RECOVERY_SHARE_ID).await.expect("v2").is_some());
+        // The recovery share is no node: it does not count toward the rotation barrier.
+        let status = mgr.rotate_kek().await.expect("rotate again");
+        assert_eq!((status.phase, status.nodes_total), (RotationPhase::Done, 1));
+
+        let other = SecretManager::load_with_source(
+            store.clone(),
+            Uuid::new_v4(),
+            Arc::new(NodeKeyring::generate()),
+            source(),
+        )
+        .await
+        .expect("recover");
+        assert_eq!(&*other.resolve(DEFAULT_TENANT_ID, "k").await.expect("resolve"), b"v");
+    }
+
+    #[tokio::test]
+    async fn node_sealed_cluster_refuses_an_operator_source() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = store_at(&dir.path().join("s.db")).await;
+        SecretManager::load_or_bootstrap(store.clone(), Uuid::new_v4(), Arc::new(NodeKeyring::generate()))
+            .await
+            .expect("bootstrap");
+        assert!(matches!(
+            SecretManager::load_with_source(
+                store,
+                Uuid::new_v4(),
+                Arc::new(NodeKeyring::generate()),
+                test_passphrase(b"correct horse battery staple"),
+            )
+            .await,
+            Err(SecretsError::Misconfigured(_))
+        ));
+    }
+
+    #[test]
+    fn operator_key_and_passphrase_files_are_read() {
+        use base64::Engine as _;
+        let dir = tempfile::tempdir().expect("tempdir");
+        let key = arbiter_crypto::SymKey::generate();
+        let raw = dir.path().join("raw");
+        std::fs::write(&raw, key.expose_bytes()).expect("write");
+        let b64 = dir.path().join("b64");
+        let encoded = base64::engine::general_purpose::STANDARD.encode(key.expose_bytes());
+        std::fs::write(&b64, format!("{encoded}\n")).expect("write");
+
+        // Either form of the same key opens what the other sealed.
+        let identity = NodeKeyring::generate();
+        let kek = arbiter_crypto::SymKey::generate();
+        let share = OperatorKek::read_key(&raw)
+            .expect("raw")
+            .seal(&identity.current_public(), &kek)
+            .expect("seal");
+        let opened = OperatorKek::read_key(&b64).expect("b64").open(&identity, &share).expect("open");
+        assert_eq!(opened.expose_bytes(), kek.expose_bytes());
+
+        let short = dir.path().join("short");
+        std::fs::write(&short, "hunter2\n").expect("write");
+        let cost = Argon2Cost { memory_kib: 64, iterations: 1, parallelism: 1 };
+        assert!(matches!(
+            OperatorKek::read_passphrase(&short, cost),
+            Err(SecretsError::Misconfigured(_))
+        ));
+        assert!(matches!(OperatorKek::read_key(&short), Err(SecretsError::Misconfigured(_))));
+        // A passphrase file's trailing newline is not part of the passphrase.
+        let pass = dir.path().join("pass");
+        std::fs::write(&pass, "correct horse battery staple\n").expect("write");
+        let share = OperatorKek::read_passphrase(&pass, cost)
+            .expect("read")
+            .seal(&identity.current_public(), &kek)
+            .expect("seal");
+        let opened = OperatorKek::from_passphrase(b"correct horse battery staple", cost)
+            .expect("derive")
+            .open(&identity, &share)
+            .expect("open");
+        assert_eq!(opened.expose_bytes(), kek.expose_bytes());
+    }
+
+    #[test]
+    fn share_headers_cannot_inflate_the_argon2_cost() {
+        let identity = NodeKeyring::generate();
+        let source = test_passphrase(b"correct horse battery staple");
+        let share = source
+            .seal(
+                &identity.current_public(),
+                &arbiter_crypto::SymKey::generate(),
+            )
+            .expect("seal");
+        // memory (KiB), iterations and parallelism sit at 2, 6 and 10 of the header.
+        for at in [2, 6, 10] {
+            let mut inflated = share.clone();
+            inflated[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(matches!(
+                source.open(&identity, &inflated),
+                Err(SecretsError::Malformed(_))
+            ));
+        }
+        let too_costly = Argon2Cost {
+            memory_kib: Argon2Cost::MAX.memory_kib + 1,
+            ..Default::default()
+        };
+        assert!(OperatorKek::from_passphrase(b"correct horse battery staple", too_costly).is_err());
+    }
 }
@@ -1,15 +1,19 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use arbiter_core::{RotationPhase, RotationStatus, SecretStore};
-use arbiter_crypto::{
-    Aead, Ciphertext, KEY_LEN, KeyWrap, NodePublicKey, SealedBox, SymKey, XChaChaAead,
+use arbiter_core::{
+    DEFAULT_SECRET_MAX_VERSIONS, RotationPhase, RotationStatus, RuntimeSettings, SecretActor,
+    SecretAuditAction, SecretAuditEvent, SecretAuditOutcome, SecretMeta, SecretStore, StoredSecret,
+    split_secret_pin,
 };
+use arbiter_crypto::{Aead, Ciphertext, KEY_LEN, NodePublicKey, SymKey, XChaChaAead};
+use chrono::Utc;
 use uuid::Uuid;
-use zeroize::{Zeroize, Zeroizing};
+use zeroize::Zeroizing;
 
 use crate::error::{Result, SecretsError};
 use crate::identity::NodeKeyring;
+use crate::kek_source::{ClusterKek, KekSource, RECOVERY_SHARE_ID, sym_from_vec};
 
 const AEAD_ALGO: &str = "xchacha20poly1305";
 const NONCE_LEN: usize = 24;
@@ -25,24 +29,41 @@ struct KekState {
 
 /// Envelope crypto over a [`SecretStore`]: per-secret DEK, versioned KEK held only in
 /// memory. The KEK keyring lives behind an [`RwLock`] so rotation can install a new version
-/// in place on a shared manager.
+/// in place on a shared manager. Every value set is a new version of its secret (with a DEK
+/// of its own); the newest `secrets.max_versions` are kept.
 pub struct SecretManager {
     store: Arc<dyn SecretStore + Send + Sync>,
     node_id: Uuid,
     identity: Arc<NodeKeyring>,
+    /// What each node's share of a KEK version is sealed to.
+    source: Arc<dyn KekSource>,
     kek: RwLock<KekState>,
     aead: XChaChaAead,
+    /// Where the version retention limit is read; `None` = [`DEFAULT_SECRET_MAX_VERSIONS`].
+    settings: Option<Arc<RuntimeSettings>>,
 }
 
 impl SecretManager {
-    /// Load the KEK keyring for this node, bootstrapping a fresh KEK on first run.
+    /// Load the KEK keyring for this node, bootstrapping a fresh KEK on first run, with the
+    /// default [`ClusterKek`] source.
     pub async fn load_or_bootstrap(
         store: Arc<dyn SecretStore + Send + Sync>,
         node_id: Uuid,
         identity: Arc<NodeKeyring>,
+    ) -> Result<Self> {
+        Self::load_with_source(store, node_id, identity, Arc::new(ClusterKek)).await
+    }
+
+    /// Load the KEK keyring for this node from `source`, bootstrapping a fresh KEK on first
+    /// run. The source is the cluster's from its first KEK on: a node whose source does not
+    /// fit the shares in the store is refused as [`SecretsError::Misconfigured`].
+    pub async fn load_with_source(
+        store: Arc<dyn SecretStore + Send + Sync>,
+        node_id: Uuid,
+        identity: Arc<NodeKeyring>,
+        source: Arc<dyn KekSource>,
     ) -> Result<Self> {
         let aead = XChaChaAead;
-        let wrap = SealedBox;
 
         let versions = store.list_kek_versions().await?;
         if versions.is_empty() {
@@ -57,9 +78,12 @@ impl SecretManager {
                 )
                 .await?;
             let kek = SymKey::generate();
-            let sealed = wrap.seal(&identity.current_public(), kek.expose_bytes())?;
+            let sealed = source.seal(&identity.current_public(), &kek)?;
             store.insert_kek_version(1, "active").await?;
             store.put_kek_share(1, node_id, &sealed).await?;
+            if !source.node_bound() {
+                store.put_kek_share(1, RECOVERY_SHARE_ID, &sealed).await?;
+            }
             store.ack_kek_share(1, node_id).await?;
             let mut keks = HashMap::new();
             keks.insert(1, kek);
@@ -67,14 +91,38 @@ impl SecretManager {
                 store,
                 node_id,
                 identity,
+                source,
                 kek: RwLock::new(KekState { keks, current: 1 }),
                 aead,
+                settings: None,
             });
         }
 
+        // An operator-held cluster keeps a recovery share of its active KEK; a node-sealed
+        // one never does. Refuse a source of the other kind up front rather than wait for a
+        // share it could never open.
+        if let Some(active) = versions.iter().find(|v| v.state == "active") {
+            let operator_held = store
+                .get_kek_share(active.version, RECOVERY_SHARE_ID)
+                .await?
+                .is_some();
+            if operator_held == source.node_bound() {
+                return Err(SecretsError::Misconfigured(if operator_held {
+                    "this cluster's KEK is held by the operator; start the node with its \
+                     passphrase or key (secrets.kek_source)"
+                        .into()
+                } else {
+                    "this cluster's KEK is sealed to node identities; it cannot be opened with an \
+                     operator passphrase or key"
+                        .into()
+                }));
+            }
+        }
+
         // Joining an existing cluster: register our public key as pending (awaiting an
         // admin approval before a holder will seal the KEK to us). Do not re-register if
-        // already present, so a restart never downgrades an approved node.
+        // already present, so a restart never downgrades an approved node. With an operator
+        // key the node approves itself: holding the key is the trust an approval grants.
         let already_registered = store
             .list_node_keys()
             .await?
@@ -86,7 +134,7 @@ impl SecretManager {
                     node_id,
                     identity.current_version(),
                     &identity.current_public().to_bytes(),
-                    "pending",
+                    if source.node_bound() { "pending" } else { "approved" },
                 )
                 .await?;
         }
@@ -97,11 +145,21 @@ impl SecretManager {
             if v.state == "retired" {
                 continue;
             }
-            let Some(share) = store.get_kek_share(v.version, node_id).await? else {
-                continue;
+            let share = match store.get_kek_share(v.version, node_id).await? {
+                Some(share) => share.wrapped_kek,
+                // A node the operator key admits takes its share from the recovery one
+                // (e.g. every node came back with a fresh identity).
+                None if !source.node_bound() => {
+                    let Some(recovery) = store.get_kek_share(v.version, RECOVERY_SHARE_ID).await?
+                    else {
+                        continue;
+                    };
+                    store.put_kek_share(v.version, node_id, &recovery.wrapped_kek).await?;
+                    recovery.wrapped_kek
+                }
+                None => continue,
             };
-            let opened = open_with_identity(&wrap, &identity, &share.wrapped_kek)?;
-            keks.insert(v.version, sym_from_vec(opened)?);
+            keks.insert(v.version, source.open(&identity, &share)?);
             // Ack the share so the rotation barrier knows this node can read this version.
             store.ack_kek_share(v.version, node_id).await?;
             if v.state == "active" {
@@ -124,15 +182,30 @@ impl SecretManager {
             store,
             node_id,
             identity,
+            source,
             kek: RwLock::new(KekState {
                 keks,
                 current: current_kek,
             }),
             aead,
+            settings: None,
         })
     }
 
-    /// Encrypt and store a secret by (tenant, name) with a fresh DEK wrapped by the KEK.
+    /// Read the version retention limit from the live runtime settings.
+    pub fn with_settings(mut self, settings: Arc<RuntimeSettings>) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    fn max_versions(&self) -> u32 {
+        self.settings
+            .as_ref()
+            .map_or(DEFAULT_SECRET_MAX_VERSIONS, |s| s.secret_max_versions())
+    }
+
+    /// Encrypt and store a secret by (tenant, name) with a fresh DEK wrapped by the KEK, as
+    /// its new current version. Versions past the retention limit are dropped.
     pub async fn set_secret(&self, tenant: Uuid, name: &str, value: &[u8]) -> Result<Uuid> {
         let dek = SymKey::generate();
         let value_ct = self.aead.encrypt(&dek, value, name.as_bytes())?;
@@ -158,18 +231,95 @@ impl SecretManager {
                 version,
             )
             .await?;
+        self.store.prune_secret_versions(id, self.max_versions()).await?;
         Ok(id)
     }
 
-    /// Resolve a secret value at the last moment. Fails closed if this node does not
-    /// hold the KEK version the secret was wrapped with. The returned value zeroizes
-    /// on drop.
-    pub async fn resolve(&self, tenant: Uuid, name: &str) -> Result<Zeroizing<Vec<u8>>> {
-        let secret = self
+    /// Roll a secret back to a retained version: its value becomes the secret's new current
+    /// version. Returns that version. Needs no KEK (the ciphertext is copied as is).
+    pub async fn rollback_secret(&self, id: Uuid, version: u32) -> Result<u32> {
+        let restored = self
+            .store
+            .restore_secret_version(id, version)
+            .await?
+            .ok_or_else(|| SecretsError::NotFound(format!("secret {id} version {version}")))?;
+        self.store.prune_secret_versions(id, self.max_versions()).await?;
+        Ok(restored)
+    }
+
+    /// [`resolve_as`](Self::resolve_as) on this node's own behalf.
+    pub async fn resolve(&self, tenant: Uuid, reference: &str) -> Result<Zeroizing<Vec<u8>>> {
+        self.resolve_as(
+            tenant,
+            reference,
+            SecretActor::Node {
+                node_id: self.node_id,
+            },
+        )
+        .await
+    }
+
+    /// Resolve a secret value at the last moment: `name` for its current version, or
+    /// `name@<version>` for a retained one. Fails closed if this node does not hold the KEK
+    /// version the secret was wrapped with. The returned value zeroizes on drop.
+    ///
+    /// Every attempt is audited for `actor`, and a value is only handed out once its resolve
+    /// is on record: a failed audit write fails the resolve.
+    pub async fn resolve_as(
+        &self,
+        tenant: Uuid,
+        reference: &str,
+        actor: SecretActor,
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        let mut found = None;
+        let result = self.open(tenant, reference, &mut found).await;
+        let (outcome, detail) = match &result {
+            Ok(_) => (SecretAuditOutcome::Ok, None),
+            Err(SecretsError::NotFound(_)) => (SecretAuditOutcome::NotFound, None),
+            Err(e) => (SecretAuditOutcome::Failed, Some(e.to_string())),
+        };
+        let event = SecretAuditEvent {
+            id: Uuid::new_v4(),
+            secret_id: found.map(|(id, _)| id),
+            tenant_id: tenant,
+            secret_name: split_secret_pin(reference).0.to_string(),
+            version: found.map(|(_, version)| version),
+            action: SecretAuditAction::Resolve,
+            actor,
+            outcome,
+            detail,
+            at: Utc::now(),
+        };
+        let audited = self.store.append_secret_audit(&event).await;
+        // A failed resolve reports its own error over the audit's.
+        let value = result?;
+        audited?;
+        Ok(value)
+    }
+
+    /// Decrypt a secret, noting its id and the version asked for in `found` once it is known.
+    async fn open(
+        &self,
+        tenant: Uuid,
+        reference: &str,
+        found: &mut Option<(Uuid, u32)>,
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        let (name, pinned) = split_secret_pin(reference);
+        let mut secret = self
             .store
             .get_secret_by_name(tenant, name)
             .await?
             .ok_or_else(|| SecretsError::NotFound(name.to_string()))?;
+        *found = Some((secret.id, pinned.unwrap_or(secret.version)));
+        if let Some(version) = pinned
+            && version != secret.version
+        {
+            secret = self
+                .store
+                .get_secret_version(secret.id, version)
+                .await?
+                .ok_or_else(|| SecretsError::NotFound(reference.to_string()))?;
+        }
         // Unwrap the DEK under the secret's KEK version while holding the read lock, then
         // drop it before any further await (the guard is not Send).
         let dek = {
@@ -215,22 +365,25 @@ impl SecretManager {
         self.seal_version_to_approved(version).await
     }
 
-    /// Seal one held KEK version to every approved node that does not yet hold a share of it.
-    /// Idempotent (nodes that already have a share are skipped). Returns how many shares were
-    /// newly written.
+    /// Seal one held KEK version to every approved node that does not yet hold a share of it
+    /// (and to the recovery share of an operator-held cluster). Idempotent (nodes that
+    /// already have a share are skipped). Returns how many node shares were newly written.
     async fn seal_version_to_approved(&self, version: u32) -> Result<usize> {
-        // Snapshot the KEK bytes under the lock so the seal loop below (which awaits the
-        // store) never holds the guard across an await.
-        let kek_bytes = {
+        // Snapshot the KEK under the lock so the seal loop below (which awaits the store)
+        // never holds the guard across an await.
+        let kek = {
             let guard = self.read_kek();
-            let kek = guard.keks.get(&version).ok_or_else(|| {
+            guard.keks.get(&version).cloned().ok_or_else(|| {
                 SecretsError::KeyUnavailable(format!("KEK version {version} not held"))
-            })?;
-            let mut bytes = [0u8; KEY_LEN];
-            bytes.copy_from_slice(kek.expose_bytes());
-            Zeroizing::new(bytes)
+            })?
         };
-        let wrap = SealedBox;
+
+        if !self.source.node_bound()
+            && self.store.get_kek_share(version, RECOVERY_SHARE_ID).await?.is_none()
+        {
+            let sealed = self.source.seal(&self.identity.current_public(), &kek)?;
+            self.store.put_kek_share(version, RECOVERY_SHARE_ID, &sealed).await?;
+        }
 
         let mut sealed_count = 0;
         for nk in self.approved_node_keys().await? {
@@ -244,7 +397,7 @@ impl SecretManager {
                 ))
             })?;
             let pubkey = NodePublicKey::from_bytes(bytes);
-            let sealed = wrap.seal(&pubkey, kek_bytes.as_slice())?;
+            let sealed = self.source.seal(&pubkey, &kek)?;
             self.store.put_kek_share(version, nk.node_id, &sealed).await?;
             sealed_count += 1;
         }
@@ -276,7 +429,6 @@ impl SecretManager {
     /// retracks the current version. Run periodically by every node so a long-running node
     /// follows rotations without a restart. Returns how many new versions were loaded.
     pub async fn refresh_keyring(&self) -> Result<usize> {
-        let wrap = SealedBox;
         let versions = self.store.list_kek_versions().await?;
         let held: HashSet<u32> = self.read_kek().keks.keys().copied().collect();
 
@@ -299,8 +451,7 @@ impl SecretManager {
             let Some(share) = self.store.get_kek_share(v.version, self.node_id).await? else {
                 continue;
             };
-            let opened = open_with_identity(&wrap, &self.identity, &share.wrapped_kek)?;
-            newly.push((v.version, sym_from_vec(opened)?));
+            newly.push((v.version, self.source.open(&self.identity, &share.wrapped_kek)?));
             self.store.ack_kek_share(v.version, self.node_id).await?;
         }
 
@@ -487,57 +638,107 @@ impl SecretManager {
         }
     }
 
-    /// How many secrets are already on `target` out of the total (for progress reporting).
+    /// How many secrets have every retained version on `target`, out of the total (for
+    /// progress reporting).
     async fn rewrap_progress(&self, target: u32) -> Result<(u32, u32)> {
         let metas = self.store.list_secret_names(None).await?;
         let total = metas.len() as u32;
-        let done = metas.iter().filter(|m| m.kek_version == target).count() as u32;
+        let done = metas.iter().filter(|m| m.oldest_kek_version == target).count() as u32;
         Ok((done, total))
     }
 
-    /// Re-wrap every secret not already on `target` onto it (value ciphertext untouched).
+    /// Re-wrap every retained version of every secret not already on `target` onto it
+    /// (value ciphertext untouched), so a pinned or rolled-back version outlives the old KEK.
+    /// Each secret's re-wrap is audited as a `rotate` by this node.
     async fn rewrap_all_onto(&self, target: u32) -> Result<()> {
         let metas = self.store.list_secret_names(None).await?;
+        let actor = SecretActor::Node {
+            node_id: self.node_id,
+        };
         for meta in metas {
-            if meta.kek_version == target {
+            if meta.oldest_kek_version == target {
                 continue;
             }
-            let Some(secret) = self.store.get_secret(meta.id, None).await? else {
-                continue;
+            let rewrapped = self.rewrap_versions_onto(&meta, target).await;
+            let event = match &rewrapped {
+                Ok(()) => SecretAuditEvent::ok(
+                    meta.id,
+                    meta.tenant_id,
+                    &meta.name,
+                    SecretAuditAction::Rotate,
+                    actor,
+                ),
+                Err(e) => SecretAuditEvent::refused(
+                    Some(meta.id),
+                    meta.tenant_id,
+                    &meta.name,
+                    SecretAuditAction::Rotate,
+                    actor,
+                    SecretAuditOutcome::Failed,
+                    e.to_string(),
+                ),
             };
-            if secret.kek_version == target {
+            let audited = self.store.append_secret_audit(&event).await;
+            rewrapped?;
+            audited?;
+        }
+        Ok(())
+    }
+
+    /// Re-wrap every retained version of one secret not already on `target` onto it.
+    async fn rewrap_versions_onto(&self, meta: &SecretMeta, target: u32) -> Result<()> {
+        for version in self.store.list_secret_versions(meta.id).await? {
+            if version.kek_version == target {
                 continue;
             }
-            // Unwrap under the old KEK and re-wrap under the target, all under the lock. The
-            // AEAD AAD is the secret name, matching set_secret.
-            let dek_wrapped = {
-                let guard = self.read_kek();
-                let old = guard.keks.get(&secret.kek_version).ok_or_else(|| {
-                    SecretsError::KeyUnavailable(format!(
-                        "KEK version {} not held by this node",
-                        secret.kek_version
-                    ))
-                })?;
-                let new = guard.keks.get(&target).ok_or_else(|| {
-                    SecretsError::KeyUnavailable("rotation target KEK missing".into())
-                })?;
-                let (nonce, bytes) = unpack(&secret.dek_wrapped)?;
-                let dek_bytes =
-                    self.aead
-                        .decrypt(old, &Ciphertext { nonce, bytes }, secret.name.as_bytes())?;
-                let dek = sym_from_vec(dek_bytes)?;
-                let rewrapped =
-                    self.aead
-                        .encrypt(new, dek.expose_bytes(), secret.name.as_bytes())?;
-                pack(&rewrapped)
-            };
-            self.store
-                .rewrap_secret(secret.id, &dek_wrapped, target)
-                .await?;
+            if let Some(secret) = self
+                .store
+                .get_secret_version(meta.id, version.version)
+                .await?
+            {
+                self.rewrap_onto(&secret, target).await?;
+            }
+        }
+        // The current version, also when it predates the version history.
+        if let Some(secret) = self.store.get_secret(meta.id, None).await?
+            && secret.kek_version != target
+        {
+            self.rewrap_onto(&secret, target).await?;
         }
         Ok(())
     }
 
+    /// Re-wrap one version's DEK onto `target`.
+    async fn rewrap_onto(&self, secret: &StoredSecret, target: u32) -> Result<()> {
+        // Unwrap under the old KEK and re-wrap under the target, all under the lock. The
+        // AEAD AAD is the secret name, matching set_secret.
+        let dek_wrapped = {
+            let guard = self.read_kek();
+            let old = guard.keks.get(&secret.kek_version).ok_or_else(|| {
+                SecretsError::KeyUnavailable(format!(
+                    "KEK version {} not held by this node",
+                    secret.kek_version
+                ))
+            })?;
+            let new = guard.keks.get(&target).ok_or_else(|| {
+                SecretsError::KeyUnavailable("rotation target KEK missing".into())
+            })?;
+            let (nonce, bytes) = unpack(&secret.dek_wrapped)?;
+            let dek_bytes =
+                self.aead
+                    .decrypt(old, &Ciphertext { nonce, bytes }, secret.name.as_bytes())?;
+            let dek = sym_from_vec(dek_bytes)?;
+            let rewrapped = self
+                .aead
+                .encrypt(new, dek.expose_bytes(), secret.name.as_bytes())?;
+            pack(&rewrapped)
+        };
+        self.store
+            .rewrap_secret(secret.id, secret.version, &dek_wrapped, target)
+            .await?;
+        Ok(())
+    }
+
     /// Read the KEK keyring, recovering the lock if a previous holder panicked (the keyring
     /// is plain data, so a poisoned lock carries no torn invariant).
     fn read_kek(&self) -> RwLockReadGuard<'_, KekState> {
@@ -557,6 +758,15 @@ impl arbiter_core::SecretAdmin for SecretManager {
             .map_err(|e| arbiter_core::ArbiterError::ExecutionError(e.to_string()))
     }
 
+    async fn rollback_secret(&self, id: Uuid, version: u32) -> arbiter_core::Result<u32> {
+        SecretManager::rollback_secret(self, id, version)
+            .await
+            .map_err(|e| match e {
+                SecretsError::NotFound(what) => arbiter_core::ArbiterError::NotFound(what),
+                e => arbiter_core::ArbiterError::ExecutionError(e.to_string()),
+            })
+    }
+
     async fn rotate_kek(&self) -> arbiter_core::Result<RotationStatus> {
         SecretManager::rotate_kek(self)
             .await
@@ -573,8 +783,24 @@ impl arbiter_core::SecretAdmin for SecretManager {
 #[async_trait::async_trait]
 impl arbiter_core::SecretResolver for SecretManager {
     async fn resolve_secret(&self, tenant: Uuid, name: &str) -> arbiter_core::Result<String> {
+        self.resolve_secret_as(
+            tenant,
+            name,
+            SecretActor::Node {
+                node_id: self.node_id,
+            },
+        )
+        .await
+    }
+
+    async fn resolve_secret_as(
+        &self,
+        tenant: Uuid,
+        name: &str,
+        actor: SecretActor,
+    ) -> arbiter_core::Result<String> {
         let value = self
-            .resolve(tenant, name)
+            .resolve_as(tenant, name, actor)
             .await
             .map_err(|e| arbiter_core::ArbiterError::ExecutionError(e.to_string()))?;
         String::from_utf8(value.to_vec()).map_err(|_| {
@@ -583,18 +809,6 @@ impl arbiter_core::SecretResolver for SecretManager {
     }
 }
 
-/// Try every node key version until one opens the sealed blob.
-fn open_with_identity(wrap: &SealedBox, identity: &NodeKeyring, sealed: &[u8]) -> Result<Vec<u8>> {
-    for entry in identity.entries() {
-        if let Ok(plaintext) = wrap.open(entry.keypair(), sealed) {
-            return Ok(plaintext);
-        }
-    }
-    Err(SecretsError::KeyUnavailable(
-        "no node key opens the sealed KEK".into(),
-    ))
-}
-
 fn pack(ct: &Ciphertext) -> Vec<u8> {
     let mut out = Vec::with_capacity(ct.nonce.len() + ct.bytes.len());
     out.extend_from_slice(&ct.nonce);
@@ -608,16 +822,3 @@ fn unpack(blob: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
     }
     Ok((blob[..NONCE_LEN].to_vec(), blob[NONCE_LEN..].to_vec()))
 }
-
-fn sym_from_vec(mut bytes: Vec<u8>) -> Result<SymKey> {
-    if bytes.len() != KEY_LEN {
-        bytes.zeroize();
-        return Err(SecretsError::Malformed("wrong key length".into()));
-    }
-    let mut arr = [0u8; KEY_LEN];
-    arr.copy_from_slice(&bytes);
-    bytes.zeroize();
-    let key = SymKey::from_bytes(arr);
-    arr.zeroize();
-    Ok(key)
-}
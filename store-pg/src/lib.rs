@@ -37,6 +37,95 @@ impl<T> UnwrapHelper<T> for Option<T> {
     }
 }
 
+/// Decode the `jobs.retry_on` JSONB column.
+fn parse_retry_on(v: serde_json::Value) -> Result<RetryOn> {
+    serde_json::from_value(v)
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid retry_on json: {e}")))
+}
+
+/// Decode the optional `job_runner_http.tls` JSONB column.
+fn parse_http_tls(v: Option<serde_json::Value>) -> Result<Option<HttpTlsConfig>> {
+    v.map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid http tls json: {e}")))
+}
+
+/// Decode the optional `output` JSONB column of `job_runner_pgsql` / `job_runner_mysql`.
+fn parse_db_output(v: Option<serde_json::Value>) -> Result<Option<DbOutputConfig>> {
+    v.map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid db output json: {e}")))
+}
+
+/// Decode the `transport` column of `job_runner_python` / `job_runner_node` (NULL when the
+/// row is absent from a LEFT JOIN).
+fn parse_transport(v: Option<String>) -> Result<RuntimeTransport> {
+    v.map(|t| t.parse()).transpose().map(Option::unwrap_or_default)
+}
+
+/// Decode the `workspace` column of `job_runner_shell` / `job_runner_python` /
+/// `job_runner_node` (NULL when the row is absent from a LEFT JOIN).
+fn parse_workspace(v: Option<String>) -> Result<WorkspaceMode> {
+    v.map(|w| w.parse()).transpose().map(Option::unwrap_or_default)
+}
+
+/// Decode the `secret_delivery` column of `job_runner_shell` / `job_runner_python` /
+/// `job_runner_node` (NULL when the row is absent from a LEFT JOIN).
+fn parse_secret_delivery(v: Option<String>) -> Result<SecretDelivery> {
+    v.map(|d| d.parse()).transpose().map(Option::unwrap_or_default)
+}
+
+/// Decode the optional `code` JSONB column of `job_runner_shell` / `job_runner_python` /
+/// `job_runner_node`.
+fn parse_code_source(v: Option<serde_json::Value>) -> Result<Option<CodeSource>> {
+    v.map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid code source json: {e}")))
+}
+
+/// Decode the optional `job_runner_http.signing` JSONB column.
+fn parse_http_signing(v: Option<serde_json::Value>) -> Result<Option<HttpSigningConfig>> {
+    v.map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid http signing json: {e}")))
+}
+
+/// Rebuild a `wasm` runner's module source from `module_path` / `module_sha256` (the
+/// schema keeps exactly one set).
+fn wasm_module_source(path: Option<String>, sha256: Option<String>) -> Result<WasmModuleSource> {
+    match (path, sha256) {
+        (Some(path), None) => Ok(WasmModuleSource::Path { path }),
+        (None, Some(sha256)) => Ok(WasmModuleSource::Stored { sha256 }),
+        _ => Err(ArbiterError::DatabaseError(
+            "wasm runner needs exactly one of module_path/module_sha256".to_string(),
+        )),
+    }
+}
+
+/// Decode a JSONB list column: `preopens`/`args` of `job_runner_wasm`, `secrets` of
+/// `job_runner_python`/`job_runner_node`.
+fn parse_json_list<T: serde::de::DeserializeOwned>(
+    v: Option<serde_json::Value>,
+    what: &str,
+) -> Result<Vec<T>> {
+    v.map(serde_json::from_value)
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid {what} json: {e}")))
+}
+
+/// Decode the optional `job_runs.params` JSONB column (ad-hoc run params).
+fn parse_run_params(v: Option<serde_json::Value>) -> Result<HashMap<String, RunParam>> {
+    v.map(serde_json::from_value)
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid run params json: {e}")))
+}
+
+fn mk_progress(value: Option<f64>, message: Option<String>) -> Option<RunProgress> {
+    value.map(|value| RunProgress { value, message })
+}
+
 impl PgStore {
     pub async fn new(url: &str) -> Result<Self> {
         let pool = Pool::<Postgres>::connect(url)
@@ -136,39 +225,80 @@ impl PgStore {
                 j.backoff_strategy,
                 j.backoff_base_secs,
                 j.backoff_cap_secs,
+                j.retry_on,
 
                 s.command        AS "shell_command?",
                 s.working_dir    AS "shell_working_dir?",
+                s.code           AS "shell_code?",
+                s.workspace      AS "shell_workspace?",
+                s.secret_delivery AS "shell_secret_delivery?",
 
                 h.method         AS "http_method?",
                 h.url            AS "http_url?",
                 h.headers        AS "http_headers?",
                 h.body           AS "http_body?",
                 h.timeout_sec    AS "http_timeout_sec?",
+                h.tls            AS "http_tls?",
+                h.signing        AS "http_signing?",
 
                 pg.config_id     AS "pg_config_id?",
                 pg.query         AS "pg_query?",
                 pg.timeout_sec   AS "pg_timeout_sec?",
+                pg.output        AS "pg_output?",
 
                 my.config_id     AS "my_config_id?",
                 my.query         AS "my_query?",
                 my.timeout_sec   AS "my_timeout_sec?",
+                my.output        AS "my_output?",
+
+                sq.path          AS "sq_path?",
+                sq.query         AS "sq_query?",
+                sq.timeout_sec   AS "sq_timeout_sec?",
+                sq.busy_timeout_sec AS "sq_busy_timeout_sec?",
+                sq.output        AS "sq_output?",
 
                 py.module        AS "py_module?",
                 py.class_name    AS "py_class_name?",
                 py.timeout_sec   AS "py_timeout_sec?",
+                py.transport     AS "py_transport?",
+                py.max_runs      AS "py_max_runs?",
+                py.secrets       AS "py_secrets?",
+                py.code          AS "py_code?",
+                py.workspace     AS "py_workspace?",
+                py.secret_delivery AS "py_secret_delivery?",
 
                 nd.module        AS "node_module?",
                 nd.function_name AS "node_function_name?",
-                nd.timeout_sec   AS "node_timeout_sec?"
+                nd.timeout_sec   AS "node_timeout_sec?",
+                nd.transport     AS "node_transport?",
+                nd.max_runs      AS "node_max_runs?",
+                nd.secrets       AS "node_secrets?",
+                nd.code          AS "node_code?",
+                nd.workspace     AS "node_workspace?",
+                nd.secret_delivery AS "node_secret_delivery?",
+
+                wa.module_path   AS "wasm_module_path?",
+                wa.module_sha256 AS "wasm_module_sha256?",
+                wa.timeout_sec   AS "wasm_timeout_sec?",
+                wa.fuel          AS "wasm_fuel?",
+                wa.max_memory_mb AS "wasm_max_memory_mb?",
+                wa.preopens      AS "wasm_preopens?",
+                wa.args          AS "wasm_args?",
+
+                pl.plugin        AS "plugin_name?",
+                pl.config        AS "plugin_config?",
+                pl.timeout_sec   AS "plugin_timeout_sec?"
 
             FROM jobs j
             LEFT JOIN job_runner_shell  s   ON s.job_id  = j.id
             LEFT JOIN job_runner_http   h   ON h.job_id  = j.id
             LEFT JOIN job_runner_pgsql  pg  ON pg.job_id = j.id
             LEFT JOIN job_runner_mysql  my  ON my.job_id = j.id
+            LEFT JOIN job_runner_sqlite sq  ON sq.job_id = j.id
             LEFT JOIN job_runner_python py  ON py.job_id = j.id
             LEFT JOIN job_runner_node   nd  ON nd.job_id = j.id
+            LEFT JOIN job_runner_wasm   wa  ON wa.job_id = j.id
+            LEFT JOIN job_runner_plugin pl  ON pl.job_id = j.id
             WHERE j.id = $1
               AND j.deleted_at IS NULL
               AND ($2::uuid IS NULL OR j.tenant_id = $2)
@@ -193,6 +323,9 @@ impl PgStore {
                 RunnerConfig::Shell {
                     command,
                     working_dir: r.shell_working_dir,
+                    code: parse_code_source(r.shell_code)?,
+                    workspace: parse_workspace(r.shell_workspace)?,
+                    secret_delivery: parse_secret_delivery(r.shell_secret_delivery)?,
                 }
             }
             "http" => {
@@ -225,6 +358,8 @@ impl PgStore {
                     headers,
                     body: r.http_body,
                     timeout_sec: r.http_timeout_sec.map(|x| x as u32),
+                    tls: parse_http_tls(r.http_tls)?,
+                    signing: parse_http_signing(r.http_signing)?,
                 }
             }
             "pgsql" => {
@@ -235,6 +370,7 @@ impl PgStore {
                     config_id,
                     query,
                     timeout_sec: r.pg_timeout_sec.map(|x| x as u32),
+                    output: parse_db_output(r.pg_output)?,
                 }
             }
             "mysql" => {
@@ -245,6 +381,19 @@ impl PgStore {
                     config_id,
                     query,
                     timeout_sec: r.my_timeout_sec.map(|x| x as u32),
+                    output: parse_db_output(r.my_output)?,
+                }
+            }
+            "sqlite" => {
+                let path = r.sq_path.expected_value("sq_path")?;
+                let query = r.sq_query.expected_value("sq_query")?;
+
+                RunnerConfig::Sqlite {
+                    path,
+                    query,
+                    timeout_sec: r.sq_timeout_sec.map(|x| x as u32),
+                    busy_timeout_sec: r.sq_busy_timeout_sec.map(|x| x as u32),
+                    output: parse_db_output(r.sq_output)?,
                 }
             }
             "python" => {
@@ -255,6 +404,12 @@ impl PgStore {
                     module,
                     class_name,
                     timeout_sec: r.py_timeout_sec.map(|x| x as u32),
+                    transport: parse_transport(r.py_transport)?,
+                    max_runs: r.py_max_runs.map(|x| x as u32),
+                    secrets: parse_json_list(r.py_secrets, "secrets")?,
+                    code: parse_code_source(r.py_code)?,
+                    workspace: parse_workspace(r.py_workspace)?,
+                    secret_delivery: parse_secret_delivery(r.py_secret_delivery)?,
                 }
             }
             "node" => {
@@ -265,8 +420,27 @@ impl PgStore {
                     module,
                     function_name,
                     timeout_sec: r.node_timeout_sec.map(|x| x as u32),
+                    transport: parse_transport(r.node_transport)?,
+                    max_runs: r.node_max_runs.map(|x| x as u32),
+                    secrets: parse_json_list(r.node_secrets, "secrets")?,
+                    code: parse_code_source(r.node_code)?,
+                    workspace: parse_workspace(r.node_workspace)?,
+                    secret_delivery: parse_secret_delivery(r.node_secret_delivery)?,
                 }
             }
+            "wasm" => RunnerConfig::Wasm {
+                module: wasm_module_source(r.wasm_module_path, r.wasm_module_sha256)?,
+                timeout_sec: r.wasm_timeout_sec.map(|x| x as u32),
+                fuel: r.wasm_fuel.map(|x| x as u64),
+                max_memory_mb: r.wasm_max_memory_mb.map(|x| x as u32),
+                preopens: parse_json_list(r.wasm_preopens, "preopens")?,
+                args: parse_json_list(r.wasm_args, "args")?,
+            },
+            "plugin" => RunnerConfig::Plugin {
+                plugin: r.plugin_name.expected_value("plugin_name")?,
+                config: r.plugin_config.unwrap_or_default(),
+                timeout_sec: r.plugin_timeout_sec.map(|x| x as u32),
+            },
             other => {
                 tracing::error!("unknown runner_type '{}' for job {}", other, r.id);
                 return Err(ArbiterError::DatabaseError(format!(
@@ -289,6 +463,7 @@ impl PgStore {
             backoff_strategy: r.backoff_strategy.parse()?,
             backoff_base_secs: r.backoff_base_secs as u32,
             backoff_cap_secs: r.backoff_cap_secs as u32,
+            retry_on: parse_retry_on(r.retry_on)?,
         })
     }
 
@@ -303,10 +478,14 @@ impl PgStore {
                 j.id,
                 j.name,
                 j.runner_type,
+                j.retry_on,
 
                 -- shell
                 s.command        AS "shell_command?",
                 s.working_dir    AS "shell_working_dir?",
+                s.code           AS "shell_code?",
+                s.workspace      AS "shell_workspace?",
+                s.secret_delivery AS "shell_secret_delivery?",
 
                 -- http
                 h.method         AS "http_method?",
@@ -314,11 +493,14 @@ impl PgStore {
                 h.headers        AS "http_headers?",
                 h.body           AS "http_body?",
                 h.timeout_sec    AS "http_timeout_sec?",
+                h.tls            AS "http_tls?",
+                h.signing        AS "http_signing?",
 
                 -- pgsql job-side
                 pg.config_id     AS "pg_config_id?",
                 pg.query         AS "pg_query?",
                 pg.timeout_sec   AS "pg_timeout_sec?",
+                pg.output        AS "pg_output?",
 
                 -- pgsql config-side
                 pgcfg.name       AS "pg_config_name?",
@@ -327,12 +509,14 @@ impl PgStore {
                 pgcfg.username   AS "pg_username?",
                 pgcfg.password_secret AS "pg_password_secret?",
                 pgcfg.database   AS "pg_database?",
+                pgcfg.pool_size  AS "pg_pool_size?",
                 pgcfg.deleted_at AS "pg_deleted_at?",
 
                 -- mysql job-side
                 my.config_id     AS "my_config_id?",
                 my.query         AS "my_query?",
                 my.timeout_sec   AS "my_timeout_sec?",
+                my.output        AS "my_output?",
 
                 -- mysql config-side
                 mycfg.name       AS "my_config_name?",
@@ -341,17 +525,49 @@ impl PgStore {
                 mycfg.username   AS "my_username?",
                 mycfg.password_secret AS "my_password_secret?",
                 mycfg.database   AS "my_database?",
+                mycfg.pool_size  AS "my_pool_size?",
                 mycfg.deleted_at AS "my_deleted_at?",
 
+                -- sqlite
+                sq.path          AS "sq_path?",
+                sq.query         AS "sq_query?",
+                sq.timeout_sec   AS "sq_timeout_sec?",
+                sq.busy_timeout_sec AS "sq_busy_timeout_sec?",
+                sq.output        AS "sq_output?",
+
                 -- python
                 py.module        AS "py_module?",
                 py.class_name    AS "py_class_name?",
                 py.timeout_sec   AS "py_timeout_sec?",
+                py.transport     AS "py_transport?",
+                py.max_runs      AS "py_max_runs?",
+                py.secrets       AS "py_secrets?",
+                py.code          AS "py_code?",
+                py.workspace     AS "py_workspace?",
+                py.secret_delivery AS "py_secret_delivery?",
 
                 -- node
                 nd.module        AS "node_module?",
                 nd.function_name AS "node_function_name?",
-                nd.timeout_sec   AS "node_timeout_sec?"
+                nd.timeout_sec   AS "node_timeout_sec?",
+                nd.transport     AS "node_transport?",
+                nd.max_runs      AS "node_max_runs?",
+                nd.secrets       AS "node_secrets?",
+                nd.code          AS "node_code?",
+                nd.workspace     AS "node_workspace?",
+                nd.secret_delivery AS "node_secret_delivery?",
+
+                wa.module_path   AS "wasm_module_path?",
+                wa.module_sha256 AS "wasm_module_sha256?",
+                wa.timeout_sec   AS "wasm_timeout_sec?",
+                wa.fuel          AS "wasm_fuel?",
+                wa.max_memory_mb AS "wasm_max_memory_mb?",
+                wa.preopens      AS "wasm_preopens?",
+                wa.args          AS "wasm_args?",
+
+                pl.plugin        AS "plugin_name?",
+                pl.config        AS "plugin_config?",
+                pl.timeout_sec   AS "plugin_timeout_sec?"
 
             FROM jobs j
             LEFT JOIN job_runner_shell   s    ON s.job_id   = j.id
@@ -360,8 +576,11 @@ impl PgStore {
             LEFT JOIN pgsql_configs      pgcfg ON pgcfg.id  = pg.config_id
             LEFT JOIN job_runner_mysql   my   ON my.job_id  = j.id
             LEFT JOIN mysql_configs      mycfg ON mycfg.id  = my.config_id
+            LEFT JOIN job_runner_sqlite  sq   ON sq.job_id  = j.id
             LEFT JOIN job_runner_python  py   ON py.job_id  = j.id
             LEFT JOIN job_runner_node    nd   ON nd.job_id  = j.id
+            LEFT JOIN job_runner_wasm    wa   ON wa.job_id  = j.id
+            LEFT JOIN job_runner_plugin  pl   ON pl.job_id  = j.id
             WHERE j.id = $1
               AND j.deleted_at IS NULL
             "#,
@@ -381,7 +600,8 @@ impl PgStore {
         };
 
         // Load env vars once
-        let env = HashMap::from_iter(self.load_env_for_job(tx, job_id).await?.into_iter());
+        let env = HashMap::from_iter(self.load_env_for_job(tx, job_id).await?);
+        let retry_on = parse_retry_on(rec.retry_on)?;
 
         match rec.runner_type.as_str() {
             "shell" => {
@@ -389,9 +609,13 @@ impl PgStore {
                 Ok(ExecutableConfigSnapshot {
                     name: None,
                     job_name: rec.name,
+                    retry_on,
                     meta: ExecutableConfigSnapshotMeta::Shell {
                         command,
                         working_dir: rec.shell_working_dir,
+                        code: parse_code_source(rec.shell_code)?,
+                        workspace: parse_workspace(rec.shell_workspace)?,
+                        secret_delivery: parse_secret_delivery(rec.shell_secret_delivery)?,
                         env,
                     },
                 })
@@ -425,12 +649,15 @@ impl PgStore {
                 Ok(ExecutableConfigSnapshot {
                     name: None,
                     job_name: rec.name,
+                    retry_on,
                     meta: ExecutableConfigSnapshotMeta::Http {
                         method,
                         url,
                         headers,
                         body: rec.http_body,
                         timeout_sec: rec.http_timeout_sec.map(|x| x as u32),
+                        tls: parse_http_tls(rec.http_tls)?,
+                        signing: parse_http_signing(rec.http_signing)?,
                     },
                 })
             }
@@ -460,14 +687,18 @@ impl PgStore {
                 Ok(ExecutableConfigSnapshot {
                     name: Some(config_name),
                     job_name: rec.name,
+                    retry_on,
                     meta: ExecutableConfigSnapshotMeta::PgSql {
+                        config_id,
                         host,
                         port,
                         username,
                         password_secret,
                         database,
+                        pool_size: rec.pg_pool_size.map(|n| n as u32),
                         query,
                         timeout_sec: rec.pg_timeout_sec.map(|x| x as u32),
+                        output: parse_db_output(rec.pg_output)?,
                     },
                 })
             }
@@ -497,14 +728,36 @@ impl PgStore {
                 Ok(ExecutableConfigSnapshot {
                     name: Some(config_name),
                     job_name: rec.name,
+                    retry_on,
                     meta: ExecutableConfigSnapshotMeta::MySql {
+                        config_id,
                         host,
                         port,
                         username,
                         password_secret,
                         database,
+                        pool_size: rec.my_pool_size.map(|n| n as u32),
                         query,
                         timeout_sec: rec.my_timeout_sec.map(|x| x as u32),
+                        output: parse_db_output(rec.my_output)?,
+                    },
+                })
+            }
+
+            "sqlite" => {
+                let path = rec.sq_path.expected_value("sq_path")?;
+                let query = rec.sq_query.expected_value("sq_query")?;
+
+                Ok(ExecutableConfigSnapshot {
+                    name: None,
+                    job_name: rec.name,
+                    retry_on,
+                    meta: ExecutableConfigSnapshotMeta::Sqlite {
+                        path,
+                        query,
+                        timeout_sec: rec.sq_timeout_sec.map(|x| x as u32),
+                        busy_timeout_sec: rec.sq_busy_timeout_sec.map(|x| x as u32),
+                        output: parse_db_output(rec.sq_output)?,
                     },
                 })
             }
@@ -516,10 +769,17 @@ impl PgStore {
                 Ok(ExecutableConfigSnapshot {
                     name: None,
                     job_name: rec.name,
+                    retry_on,
                     meta: ExecutableConfigSnapshotMeta::Python {
                         module,
                         class_name,
                         timeout_sec: rec.py_timeout_sec.map(|x| x as u32),
+                        transport: parse_transport(rec.py_transport)?,
+                        max_runs: rec.py_max_runs.map(|x| x as u32),
+                        secrets: parse_json_list(rec.py_secrets, "secrets")?,
+                        code: parse_code_source(rec.py_code)?,
+                        workspace: parse_workspace(rec.py_workspace)?,
+                        secret_delivery: parse_secret_delivery(rec.py_secret_delivery)?,
                         env,
                     },
                 })
@@ -534,15 +794,49 @@ impl PgStore {
                 Ok(ExecutableConfigSnapshot {
                     name: None,
                     job_name: rec.name,
+                    retry_on,
                     meta: ExecutableConfigSnapshotMeta::Node {
                         module,
                         function_name,
                         timeout_sec: rec.node_timeout_sec.map(|x| x as u32),
+                        transport: parse_transport(rec.node_transport)?,
+                        max_runs: rec.node_max_runs.map(|x| x as u32),
+                        secrets: parse_json_list(rec.node_secrets, "secrets")?,
+                        code: parse_code_source(rec.node_code)?,
+                        workspace: parse_workspace(rec.node_workspace)?,
+                        secret_delivery: parse_secret_delivery(rec.node_secret_delivery)?,
                         env,
                     },
                 })
             }
 
+            "wasm" => Ok(ExecutableConfigSnapshot {
+                name: None,
+                job_name: rec.name,
+                retry_on,
+                meta: ExecutableConfigSnapshotMeta::Wasm {
+                    module: wasm_module_source(rec.wasm_module_path, rec.wasm_module_sha256)?,
+                    timeout_sec: rec.wasm_timeout_sec.map(|x| x as u32),
+                    fuel: rec.wasm_fuel.map(|x| x as u64),
+                    max_memory_mb: rec.wasm_max_memory_mb.map(|x| x as u32),
+                    preopens: parse_json_list(rec.wasm_preopens, "preopens")?,
+                    args: parse_json_list(rec.wasm_args, "args")?,
+                    env,
+                },
+            }),
+
+            "plugin" => Ok(ExecutableConfigSnapshot {
+                name: None,
+                job_name: rec.name,
+                retry_on,
+                meta: ExecutableConfigSnapshotMeta::Plugin {
+                    plugin: rec.plugin_name.expected_value("plugin_name")?,
+                    config: rec.plugin_config.unwrap_or_default(),
+                    timeout_sec: rec.plugin_timeout_sec.map(|x| x as u32),
+                    env,
+                },
+            }),
+
             other => {
                 tracing::error!(
                     "runner_type '{}' not yet supported in build_snapshot_for_job",
@@ -589,39 +883,80 @@ impl JobStore for PgStore {
                 j.backoff_strategy,
                 j.backoff_base_secs,
                 j.backoff_cap_secs,
+                j.retry_on,
 
                 s.command        AS shell_command,
                 s.working_dir    AS shell_working_dir,
+                s.code           AS "shell_code?",
+                s.workspace      AS "shell_workspace?",
+                s.secret_delivery AS "shell_secret_delivery?",
 
                 h.method         AS http_method,
                 h.url            AS http_url,
                 h.headers        AS http_headers,
                 h.body           AS http_body,
                 h.timeout_sec    AS http_timeout_sec,
+                h.tls            AS "http_tls?",
+                h.signing        AS "http_signing?",
 
                 pg.config_id     AS pg_config_id,
                 pg.query         AS pg_query,
                 pg.timeout_sec   AS pg_timeout_sec,
+                pg.output        AS "pg_output?",
 
                 my.config_id     AS my_config_id,
                 my.query         AS my_query,
                 my.timeout_sec   AS my_timeout_sec,
+                my.output        AS "my_output?",
+
+                sq.path          AS "sq_path?",
+                sq.query         AS "sq_query?",
+                sq.timeout_sec   AS "sq_timeout_sec?",
+                sq.busy_timeout_sec AS "sq_busy_timeout_sec?",
+                sq.output        AS "sq_output?",
 
                 py.module        AS py_module,
                 py.class_name    AS py_class_name,
                 py.timeout_sec   AS py_timeout_sec,
+                py.transport     AS "py_transport?",
+                py.max_runs      AS "py_max_runs?",
+                py.secrets       AS "py_secrets?",
+                py.code          AS "py_code?",
+                py.workspace     AS "py_workspace?",
+                py.secret_delivery AS "py_secret_delivery?",
 
                 nd.module        AS node_module,
                 nd.function_name AS node_function_name,
-                nd.timeout_sec   AS node_timeout_sec
+                nd.timeout_sec   AS node_timeout_sec,
+                nd.transport     AS "node_transport?",
+                nd.max_runs      AS "node_max_runs?",
+                nd.secrets       AS "node_secrets?",
+                nd.code          AS "node_code?",
+                nd.workspace     AS "node_workspace?",
+                nd.secret_delivery AS "node_secret_delivery?",
+
+                wa.module_path   AS "wasm_module_path?",
+                wa.module_sha256 AS "wasm_module_sha256?",
+                wa.timeout_sec   AS "wasm_timeout_sec?",
+                wa.fuel          AS "wasm_fuel?",
+                wa.max_memory_mb AS "wasm_max_memory_mb?",
+                wa.preopens      AS "wasm_preopens?",
+                wa.args          AS "wasm_args?",
+
+                pl.plugin        AS "plugin_name?",
+                pl.config        AS "plugin_config?",
+                pl.timeout_sec   AS "plugin_timeout_sec?"
 
             FROM jobs j
             LEFT JOIN job_runner_shell  s   ON s.job_id  = j.id
             LEFT JOIN job_runner_http   h   ON h.job_id  = j.id
             LEFT JOIN job_runner_pgsql  pg  ON pg.job_id = j.id
             LEFT JOIN job_runner_mysql  my  ON my.job_id = j.id
+            LEFT JOIN job_runner_sqlite sq  ON sq.job_id = j.id
             LEFT JOIN job_runner_python py  ON py.job_id = j.id
             LEFT JOIN job_runner_node   nd  ON nd.job_id = j.id
+            LEFT JOIN job_runner_wasm   wa  ON wa.job_id = j.id
+            LEFT JOIN job_runner_plugin pl  ON pl.job_id = j.id
             WHERE j.enabled = TRUE
               AND j.schedule_cron IS NOT NULL
               AND j.deleted_at IS NULL
@@ -640,6 +975,9 @@ impl JobStore for PgStore {
                         RunnerConfig::Shell {
                             command,
                             working_dir: r.shell_working_dir,
+                            code: parse_code_source(r.shell_code)?,
+                            workspace: parse_workspace(r.shell_workspace)?,
+                            secret_delivery: parse_secret_delivery(r.shell_secret_delivery)?,
                         }
                     }
                     "http" => {
@@ -672,6 +1010,8 @@ impl JobStore for PgStore {
                             headers,
                             body: r.http_body,
                             timeout_sec: r.http_timeout_sec.map(|x| x as u32),
+                            tls: parse_http_tls(r.http_tls)?,
+                            signing: parse_http_signing(r.http_signing)?,
                         }
                     }
                     "pgsql" => {
@@ -682,6 +1022,7 @@ impl JobStore for PgStore {
                             config_id,
                             query,
                             timeout_sec: r.pg_timeout_sec.map(|x| x as u32),
+                            output: parse_db_output(r.pg_output)?,
                         }
                     }
                     "mysql" => {
@@ -692,6 +1033,19 @@ impl JobStore for PgStore {
                             config_id,
                             query,
                             timeout_sec: r.my_timeout_sec.map(|x| x as u32),
+                            output: parse_db_output(r.my_output)?,
+                        }
+                    }
+                    "sqlite" => {
+                        let path = r.sq_path.expected_value("sq_path")?;
+                        let query = r.sq_query.expected_value("sq_query")?;
+
+                        RunnerConfig::Sqlite {
+                            path,
+                            query,
+                            timeout_sec: r.sq_timeout_sec.map(|x| x as u32),
+                            busy_timeout_sec: r.sq_busy_timeout_sec.map(|x| x as u32),
+                            output: parse_db_output(r.sq_output)?,
                         }
                     }
                     "python" => {
@@ -702,6 +1056,12 @@ impl JobStore for PgStore {
                             module,
                             class_name,
                             timeout_sec: r.py_timeout_sec.map(|x| x as u32),
+                            transport: parse_transport(r.py_transport)?,
+                            max_runs: r.py_max_runs.map(|x| x as u32),
+                            secrets: parse_json_list(r.py_secrets, "secrets")?,
+                            code: parse_code_source(r.py_code)?,
+                            workspace: parse_workspace(r.py_workspace)?,
+                            secret_delivery: parse_secret_delivery(r.py_secret_delivery)?,
                         }
                     }
                     "node" => {
@@ -713,8 +1073,27 @@ impl JobStore for PgStore {
                             module,
                             function_name,
                             timeout_sec: r.node_timeout_sec.map(|x| x as u32),
+                            transport: parse_transport(r.node_transport)?,
+                            max_runs: r.node_max_runs.map(|x| x as u32),
+                            secrets: parse_json_list(r.node_secrets, "secrets")?,
+                            code: parse_code_source(r.node_code)?,
+                            workspace: parse_workspace(r.node_workspace)?,
+                            secret_delivery: parse_secret_delivery(r.node_secret_delivery)?,
                         }
                     }
+                    "wasm" => RunnerConfig::Wasm {
+                        module: wasm_module_source(r.wasm_module_path, r.wasm_module_sha256)?,
+                        timeout_sec: r.wasm_timeout_sec.map(|x| x as u32),
+                        fuel: r.wasm_fuel.map(|x| x as u64),
+                        max_memory_mb: r.wasm_max_memory_mb.map(|x| x as u32),
+                        preopens: parse_json_list(r.wasm_preopens, "preopens")?,
+                        args: parse_json_list(r.wasm_args, "args")?,
+                    },
+                    "plugin" => RunnerConfig::Plugin {
+                        plugin: r.plugin_name.expected_value("plugin_name")?,
+                        config: r.plugin_config.unwrap_or_default(),
+                        timeout_sec: r.plugin_timeout_sec.map(|x| x as u32),
+                    },
                     other => {
                         tracing::error!("unknown runner_type '{}' for job {}", other, r.id);
                         return Err(ArbiterError::DatabaseError(format!(
@@ -746,6 +1125,7 @@ impl JobStore for PgStore {
                     backoff_strategy: r.backoff_strategy.parse()?,
                     backoff_base_secs: r.backoff_base_secs as u32,
                     backoff_cap_secs: r.backoff_cap_secs as u32,
+                    retry_on: parse_retry_on(r.retry_on)?,
                 })
             })
             .collect()
@@ -795,8 +1175,8 @@ impl JobStore for PgStore {
 #[async_trait]
 impl RunStore for PgStore {
     async fn prune_runs(&self, older_than: DateTime<Utc>) -> Result<u64> {
-        // Drop the pruned runs' log chunks first (they are the runs' output, pruned with the
-        // run), then the runs themselves.
+        // Drop the pruned runs' log chunks and artifacts first (they are the runs' output,
+        // pruned with the run), then the runs themselves.
         sqlx::query!(
             r#"
             DELETE FROM run_log_chunks
@@ -809,6 +1189,18 @@ impl RunStore for PgStore {
         )
         .execute(&self.pool)
         .await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM run_artifacts
+            WHERE run_id IN (
+                SELECT id FROM job_runs
+                WHERE scheduled_for < $1 AND state IN ('succeeded', 'failed', 'cancelled')
+            )
+            "#,
+            older_than
+        )
+        .execute(&self.pool)
+        .await?;
         let res = sqlx::query!(
             r#"
             DELETE FROM job_runs
@@ -885,7 +1277,11 @@ impl RunStore for PgStore {
                 SET state = 'running',
                     worker_id = $2,
                     started_at = now(),
-                    config_snapshot = $3
+                    config_snapshot = $3,
+                    progress = NULL,
+                    progress_message = NULL,
+                    last_heartbeat = NULL,
+                    code_commit = NULL
                 WHERE id = $1
                 RETURNING
                     id,
@@ -896,7 +1292,8 @@ impl RunStore for PgStore {
                     attempt,
                     started_at,
                     finished_at,
-                    exit_code
+                    exit_code,
+                    params
                 "#,
                 c.id,
                 worker_id,
@@ -934,6 +1331,10 @@ impl RunStore for PgStore {
                 result_media_type: None,
                 error: None,
                 error_media_type: None,
+                params: parse_run_params(rec.params)?,
+                progress: None,
+                last_heartbeat: None,
+                code_commit: None,
             });
         }
 
@@ -997,6 +1398,79 @@ impl RunStore for PgStore {
         Ok(())
     }
 
+    async fn heartbeat_run(
+        &self,
+        run_id: Uuid,
+        worker_id: Uuid,
+        progress: Option<RunProgress>,
+    ) -> Result<bool> {
+        let has_progress = progress.is_some();
+        let (value, message) = match progress {
+            Some(p) => (Some(p.value), p.message),
+            None => (None, None),
+        };
+        let res = sqlx::query!(
+            r#"
+            UPDATE job_runs
+            SET last_heartbeat = now(),
+                progress = CASE WHEN $3 THEN $4 ELSE progress END,
+                progress_message = CASE WHEN $3 THEN $5 ELSE progress_message END
+            WHERE id = $1 AND worker_id = $2 AND state = 'running'
+            "#,
+            run_id,
+            worker_id,
+            has_progress,
+            value,
+            message,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Progress shows on the live run view; a bare heartbeat changes nothing visible.
+        if has_progress && res.rows_affected() > 0 {
+            self.pg_notify_channel("arbiter_runs").await;
+        }
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn owns_run(&self, run_id: Uuid, worker_id: Uuid, attempt: u32) -> Result<bool> {
+        let rec = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM job_runs
+                WHERE id = $1 AND worker_id = $2 AND attempt = $3 AND state = 'running'
+            ) AS "owned!"
+            "#,
+            run_id,
+            worker_id,
+            attempt as i32,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(rec)
+    }
+
+    async fn record_run_code_commit(
+        &self,
+        run_id: Uuid,
+        worker_id: Uuid,
+        commit: &str,
+    ) -> Result<bool> {
+        let res = sqlx::query!(
+            r#"
+            UPDATE job_runs
+            SET code_commit = $3
+            WHERE id = $1 AND worker_id = $2 AND state = 'running'
+            "#,
+            run_id,
+            worker_id,
+            commit,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
     async fn reschedule_for_retry(
         &self,
         run_id: Uuid,
@@ -1057,13 +1531,15 @@ impl WorkerStore for PgStore {
     async fn heartbeat(&self, worker: &WorkerRecord) -> Result<()> {
         sqlx::query!(
             r#"
-            INSERT INTO workers(id, display_name, hostname, last_seen, capacity, version, active)
-            VALUES ($1, $2, $3, $4, $5, $6, true)
+            INSERT INTO workers(id, display_name, hostname, last_seen, capacity, version, plugins, disk_usage, active)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, true)
             ON CONFLICT (id) DO UPDATE
             SET last_seen = EXCLUDED.last_seen,
                 hostname = EXCLUDED.hostname,
                 capacity = EXCLUDED.capacity,
                 version = EXCLUDED.version,
+                plugins = EXCLUDED.plugins,
+                disk_usage = COALESCE(EXCLUDED.disk_usage, workers.disk_usage),
                 active = true
         "#,
             worker.id,
@@ -1072,6 +1548,8 @@ impl WorkerStore for PgStore {
             worker.last_seen,
             worker.capacity as i64,
             worker.version,
+            serde_json::json!(worker.plugins),
+            worker.disk_usage.as_ref().map(|d| serde_json::json!(d)),
         )
         .execute(&self.pool)
         .await?;
@@ -1150,6 +1628,8 @@ impl WorkerStore for PgStore {
                 SELECT id FROM workers
                 WHERE last_seen < now() - ($1::bigint || ' seconds')::interval
               )
+              AND (last_heartbeat IS NULL
+                   OR last_heartbeat < now() - ($1::bigint || ' seconds')::interval)
             "#,
             dead_after_secs as i64
         )
@@ -1228,9 +1708,9 @@ impl ApiStore for PgStore {
         INSERT INTO jobs (
             id, tenant_id, name, schedule_cron,
             runner_type, max_concurrency, misfire_policy,
-            max_attempts, backoff_strategy, backoff_base_secs, backoff_cap_secs
+            max_attempts, backoff_strategy, backoff_base_secs, backoff_cap_secs, retry_on
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
         "#,
             new_id,
             tenant_id,
@@ -1243,6 +1723,7 @@ impl ApiStore for PgStore {
             retry.backoff_strategy.to_string(),
             retry.backoff_base_secs as i32,
             retry.backoff_cap_secs as i32,
+            serde_json::to_value(&retry.retry_on).unwrap_or_default(),
         )
         .execute(&mut *tx)
         .await?;
@@ -1251,15 +1732,21 @@ impl ApiStore for PgStore {
             RunnerConfig::Shell {
                 command,
                 working_dir,
+                code,
+                workspace,
+                secret_delivery,
             } => {
                 sqlx::query!(
                     r#"
-                INSERT INTO job_runner_shell (job_id, command, working_dir)
-                VALUES ($1, $2, $3)
+                INSERT INTO job_runner_shell (job_id, command, working_dir, code, workspace, secret_delivery)
+                VALUES ($1, $2, $3, $4, $5, $6)
                 "#,
                     new_id,
                     command,
-                    working_dir.as_deref()
+                    working_dir.as_deref(),
+                    code.as_ref().map(|c| serde_json::json!(c)),
+                    workspace.to_string(),
+                    secret_delivery.to_string()
                 )
                 .execute(&mut *tx)
                 .await?;
@@ -1271,13 +1758,15 @@ impl ApiStore for PgStore {
                 headers,
                 body,
                 timeout_sec,
+                tls,
+                signing,
             } => {
                 sqlx::query!(
                     r#"
                 INSERT INTO job_runner_http (
-                    job_id, method, url, headers, body, timeout_sec
+                    job_id, method, url, headers, body, timeout_sec, tls, signing
                 )
-                VALUES ($1, $2, $3, $4, $5, $6)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
                 "#,
                     new_id,
                     method,
@@ -1285,6 +1774,8 @@ impl ApiStore for PgStore {
                     headers.as_ref().map(|h| serde_json::json!(h)),
                     body.as_deref(),
                     timeout_sec.map(|x| x as i32),
+                    tls.as_ref().map(|t| serde_json::json!(t)),
+                    signing.as_ref().map(|s| serde_json::json!(s)),
                 )
                 .execute(&mut *tx)
                 .await?;
@@ -1294,18 +1785,20 @@ impl ApiStore for PgStore {
                 config_id,
                 query,
                 timeout_sec,
+                output,
             } => {
                 sqlx::query!(
                     r#"
                 INSERT INTO job_runner_pgsql (
-                    job_id, config_id, query, timeout_sec
+                    job_id, config_id, query, timeout_sec, output
                 )
-                VALUES ($1, $2, $3, $4)
+                VALUES ($1, $2, $3, $4, $5)
                 "#,
                     new_id,
                     config_id,
                     query,
-                    timeout_sec.map(|x| x as i32)
+                    timeout_sec.map(|x| x as i32),
+                    output.as_ref().map(|o| serde_json::json!(o))
                 )
                 .execute(&mut *tx)
                 .await?;
@@ -1315,18 +1808,45 @@ impl ApiStore for PgStore {
                 config_id,
                 query,
                 timeout_sec,
+                output,
             } => {
                 sqlx::query!(
                     r#"
                 INSERT INTO job_runner_mysql (
-                    job_id, config_id, query, timeout_sec
+                    job_id, config_id, query, timeout_sec, output
                 )
-                VALUES ($1, $2, $3, $4)
+                VALUES ($1, $2, $3, $4, $5)
                 "#,
                     new_id,
                     config_id,
                     query,
-                    timeout_sec.map(|x| x as i32)
+                    timeout_sec.map(|x| x as i32),
+                    output.as_ref().map(|o| serde_json::json!(o))
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            RunnerConfig::Sqlite {
+                path,
+                query,
+                timeout_sec,
+                busy_timeout_sec,
+                output,
+            } => {
+                sqlx::query!(
+                    r#"
+                INSERT INTO job_runner_sqlite (
+                    job_id, path, query, timeout_sec, busy_timeout_sec, output
+                )
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+                    new_id,
+                    path,
+                    query,
+                    timeout_sec.map(|x| x as i32),
+                    busy_timeout_sec.map(|x| x as i32),
+                    output.as_ref().map(|o| serde_json::json!(o))
                 )
                 .execute(&mut *tx)
                 .await?;
@@ -1336,18 +1856,31 @@ impl ApiStore for PgStore {
                 module,
                 class_name,
                 timeout_sec,
+                transport,
+                max_runs,
+                secrets,
+                code,
+                workspace,
+                secret_delivery,
             } => {
                 sqlx::query!(
                     r#"
                 INSERT INTO job_runner_python (
-                    job_id, module, class_name, timeout_sec
+                    job_id, module, class_name, timeout_sec, transport, max_runs, secrets, code, workspace,
+                    secret_delivery
                 )
-                VALUES ($1, $2, $3, $4)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
                 "#,
                     new_id,
                     module,
                     class_name,
-                    timeout_sec.map(|x| x as i32)
+                    timeout_sec.map(|x| x as i32),
+                    transport.to_string(),
+                    max_runs.map(|x| x as i32),
+                    serde_json::json!(secrets),
+                    code.as_ref().map(|c| serde_json::json!(c)),
+                    workspace.to_string(),
+                    secret_delivery.to_string()
                 )
                 .execute(&mut *tx)
                 .await?;
@@ -1357,17 +1890,78 @@ impl ApiStore for PgStore {
                 module,
                 function_name,
                 timeout_sec,
+                transport,
+                max_runs,
+                secrets,
+                code,
+                workspace,
+                secret_delivery,
             } => {
                 sqlx::query!(
                     r#"
                 INSERT INTO job_runner_node (
-                    job_id, module, function_name, timeout_sec
+                    job_id, module, function_name, timeout_sec, transport, max_runs, secrets, code, workspace,
+                    secret_delivery
                 )
-                VALUES ($1, $2, $3, $4)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
                 "#,
                     new_id,
                     module,
                     function_name,
+                    timeout_sec.map(|x| x as i32),
+                    transport.to_string(),
+                    max_runs.map(|x| x as i32),
+                    serde_json::json!(secrets),
+                    code.as_ref().map(|c| serde_json::json!(c)),
+                    workspace.to_string(),
+                    secret_delivery.to_string()
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            RunnerConfig::Wasm {
+                module,
+                timeout_sec,
+                fuel,
+                max_memory_mb,
+                preopens,
+                args,
+            } => {
+                sqlx::query!(
+                    r#"
+                INSERT INTO job_runner_wasm (
+                    job_id, module_path, module_sha256, timeout_sec, fuel, max_memory_mb,
+                    preopens, args
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+                    new_id,
+                    module.path(),
+                    module.sha256(),
+                    timeout_sec.map(|x| x as i32),
+                    fuel.map(|x| x as i64),
+                    max_memory_mb.map(|x| x as i32),
+                    serde_json::json!(preopens),
+                    serde_json::json!(args)
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            RunnerConfig::Plugin {
+                plugin,
+                config,
+                timeout_sec,
+            } => {
+                sqlx::query!(
+                    r#"
+                INSERT INTO job_runner_plugin (job_id, plugin, config, timeout_sec)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                    new_id,
+                    plugin,
+                    config,
                     timeout_sec.map(|x| x as i32)
                 )
                 .execute(&mut *tx)
@@ -1431,7 +2025,12 @@ impl ApiStore for PgStore {
                 result,
                 result_media_type,
                 error,
-                error_media_type
+                error_media_type,
+                params,
+                progress,
+                progress_message,
+                last_heartbeat,
+                code_commit
             FROM job_runs
             WHERE ($1::timestamptz IS NULL OR scheduled_for < $1)
               AND ($2::timestamptz IS NULL OR scheduled_for > $2)
@@ -1502,6 +2101,10 @@ impl ApiStore for PgStore {
                 result_media_type: r.result_media_type,
                 error: r.error,
                 error_media_type: r.error_media_type,
+                params: parse_run_params(r.params)?,
+                progress: mk_progress(r.progress, r.progress_message),
+                last_heartbeat: r.last_heartbeat,
+                code_commit: r.code_commit,
             });
         }
 
@@ -1514,7 +2117,8 @@ impl ApiStore for PgStore {
             SELECT
                 id, job_id, scheduled_for, state, worker_id, attempt, started_at,
                 finished_at, exit_code, config_snapshot, result_status,
-                result, result_media_type, error, error_media_type
+                result, result_media_type, error, error_media_type, params,
+                progress, progress_message, last_heartbeat, code_commit
             FROM job_runs
             WHERE id = $1
               AND ($2::uuid IS NULL OR job_id IN (SELECT id FROM jobs WHERE tenant_id = $2))
@@ -1553,6 +2157,10 @@ impl ApiStore for PgStore {
             result_media_type: r.result_media_type,
             error: r.error,
             error_media_type: r.error_media_type,
+            params: parse_run_params(r.params)?,
+            progress: mk_progress(r.progress, r.progress_message),
+            last_heartbeat: r.last_heartbeat,
+                code_commit: r.code_commit,
         }))
     }
 
@@ -1621,7 +2229,8 @@ impl ApiStore for PgStore {
                 max_attempts = COALESCE($7, max_attempts),
                 backoff_strategy = COALESCE($8, backoff_strategy),
                 backoff_base_secs = COALESCE($9, backoff_base_secs),
-                backoff_cap_secs = COALESCE($10, backoff_cap_secs)
+                backoff_cap_secs = COALESCE($10, backoff_cap_secs),
+                retry_on = COALESCE($11, retry_on)
             WHERE id = $1 AND deleted_at IS NULL
             RETURNING runner_type
             "#,
@@ -1631,10 +2240,13 @@ impl ApiStore for PgStore {
             max_concurrency.map(|x| x as i32),
             misfire_policy.map(|x| x.to_string()),
             schedule_specified,
-            retry.map(|r| r.max_attempts as i32),
-            retry.map(|r| r.backoff_strategy.to_string()),
-            retry.map(|r| r.backoff_base_secs as i32),
-            retry.map(|r| r.backoff_cap_secs as i32),
+            retry.as_ref().map(|r| r.max_attempts as i32),
+            retry.as_ref().map(|r| r.backoff_strategy.to_string()),
+            retry.as_ref().map(|r| r.backoff_base_secs as i32),
+            retry.as_ref().map(|r| r.backoff_cap_secs as i32),
+            retry
+                .as_ref()
+                .map(|r| serde_json::to_value(&r.retry_on).unwrap_or_default()),
         )
         .fetch_optional(&mut *tx)
         .await?;
@@ -1668,6 +2280,11 @@ impl ApiStore for PgStore {
                         .execute(&mut *tx)
                         .await?;
                 }
+                "sqlite" => {
+                    sqlx::query!("DELETE FROM job_runner_sqlite WHERE job_id=$1", job_id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
                 "python" => {
                     sqlx::query!("DELETE FROM job_runner_python WHERE job_id=$1", job_id)
                         .execute(&mut *tx)
@@ -1678,6 +2295,16 @@ impl ApiStore for PgStore {
                         .execute(&mut *tx)
                         .await?;
                 }
+                "wasm" => {
+                    sqlx::query!("DELETE FROM job_runner_wasm WHERE job_id=$1", job_id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                "plugin" => {
+                    sqlx::query!("DELETE FROM job_runner_plugin WHERE job_id=$1", job_id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
                 _ => {
                     return Err(ArbiterError::DatabaseError(format!(
                         "unknown runner_type '{}' for job {}",
@@ -1703,13 +2330,19 @@ impl ApiStore for PgStore {
                 RunnerConfig::Shell {
                     command,
                     working_dir,
+                    code,
+                    workspace,
+                    secret_delivery,
                 } => {
                     sqlx::query!(
-                        r#"INSERT INTO job_runner_shell(job_id,command,working_dir)
-                       VALUES ($1,$2,$3)"#,
+                        r#"INSERT INTO job_runner_shell(job_id,command,working_dir,code,workspace,secret_delivery)
+                       VALUES ($1,$2,$3,$4,$5,$6)"#,
                         job_id,
                         command,
-                        working_dir
+                        working_dir,
+                        code.as_ref().map(|c| serde_json::json!(c)),
+                        workspace.to_string(),
+                        secret_delivery.to_string()
                     )
                     .execute(&mut *tx)
                     .await?;
@@ -1720,16 +2353,20 @@ impl ApiStore for PgStore {
                     headers,
                     body,
                     timeout_sec,
+                    tls,
+                    signing,
                 } => {
                     sqlx::query!(
-                        r#"INSERT INTO job_runner_http(job_id,method,url,headers,body,timeout_sec)
-                       VALUES ($1,$2,$3,$4,$5,$6)"#,
+                        r#"INSERT INTO job_runner_http(job_id,method,url,headers,body,timeout_sec,tls,signing)
+                       VALUES ($1,$2,$3,$4,$5,$6,$7,$8)"#,
                         job_id,
                         method,
                         url,
                         headers.as_ref().map(|h| serde_json::json!(h)),
                         body,
-                        timeout_sec.map(|x| x as i32)
+                        timeout_sec.map(|x| x as i32),
+                        tls.as_ref().map(|t| serde_json::json!(t)),
+                        signing.as_ref().map(|s| serde_json::json!(s))
                     )
                     .execute(&mut *tx)
                     .await?;
@@ -1738,14 +2375,16 @@ impl ApiStore for PgStore {
                     config_id,
                     query,
                     timeout_sec,
+                    output,
                 } => {
                     sqlx::query!(
-                        r#"INSERT INTO job_runner_pgsql(job_id,config_id,query,timeout_sec)
-                       VALUES ($1,$2,$3,$4)"#,
+                        r#"INSERT INTO job_runner_pgsql(job_id,config_id,query,timeout_sec,output)
+                       VALUES ($1,$2,$3,$4,$5)"#,
                         job_id,
                         config_id,
                         query,
-                        timeout_sec.map(|x| x as i32)
+                        timeout_sec.map(|x| x as i32),
+                        output.as_ref().map(|o| serde_json::json!(o))
                     )
                     .execute(&mut *tx)
                     .await?;
@@ -1754,14 +2393,36 @@ impl ApiStore for PgStore {
                     config_id,
                     query,
                     timeout_sec,
+                    output,
                 } => {
                     sqlx::query!(
-                        r#"INSERT INTO job_runner_mysql(job_id,config_id,query,timeout_sec)
-                       VALUES ($1,$2,$3,$4)"#,
+                        r#"INSERT INTO job_runner_mysql(job_id,config_id,query,timeout_sec,output)
+                       VALUES ($1,$2,$3,$4,$5)"#,
                         job_id,
                         config_id,
                         query,
-                        timeout_sec.map(|x| x as i32)
+                        timeout_sec.map(|x| x as i32),
+                        output.as_ref().map(|o| serde_json::json!(o))
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                RunnerConfig::Sqlite {
+                    path,
+                    query,
+                    timeout_sec,
+                    busy_timeout_sec,
+                    output,
+                } => {
+                    sqlx::query!(
+                        r#"INSERT INTO job_runner_sqlite(job_id,path,query,timeout_sec,busy_timeout_sec,output)
+                       VALUES ($1,$2,$3,$4,$5,$6)"#,
+                        job_id,
+                        path,
+                        query,
+                        timeout_sec.map(|x| x as i32),
+                        busy_timeout_sec.map(|x| x as i32),
+                        output.as_ref().map(|o| serde_json::json!(o))
                     )
                     .execute(&mut *tx)
                     .await?;
@@ -1770,14 +2431,26 @@ impl ApiStore for PgStore {
                     module,
                     class_name,
                     timeout_sec,
+                    transport,
+                    max_runs,
+                    secrets,
+                    code,
+                    workspace,
+                    secret_delivery,
                 } => {
                     sqlx::query!(
-                        r#"INSERT INTO job_runner_python(job_id,module,class_name,timeout_sec)
-                       VALUES ($1,$2,$3,$4)"#,
+                        r#"INSERT INTO job_runner_python(job_id,module,class_name,timeout_sec,transport,max_runs,secrets,code,workspace,secret_delivery)
+                       VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)"#,
                         job_id,
                         module,
                         class_name,
-                        timeout_sec.map(|x| x as i32)
+                        timeout_sec.map(|x| x as i32),
+                        transport.to_string(),
+                        max_runs.map(|x| x as i32),
+                        serde_json::json!(secrets),
+                        code.as_ref().map(|c| serde_json::json!(c)),
+                        workspace.to_string(),
+                        secret_delivery.to_string()
                     )
                     .execute(&mut *tx)
                     .await?;
@@ -1786,13 +2459,64 @@ impl ApiStore for PgStore {
                     module,
                     function_name,
                     timeout_sec,
+                    transport,
+                    max_runs,
+                    secrets,
+                    code,
+                    workspace,
+                    secret_delivery,
                 } => {
                     sqlx::query!(
-                        r#"INSERT INTO job_runner_node(job_id,module,function_name,timeout_sec)
-                       VALUES ($1,$2,$3,$4)"#,
+                        r#"INSERT INTO job_runner_node(job_id,module,function_name,timeout_sec,transport,max_runs,secrets,code,workspace,secret_delivery)
+                       VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)"#,
                         job_id,
                         module,
                         function_name,
+                        timeout_sec.map(|x| x as i32),
+                        transport.to_string(),
+                        max_runs.map(|x| x as i32),
+                        serde_json::json!(secrets),
+                        code.as_ref().map(|c| serde_json::json!(c)),
+                        workspace.to_string(),
+                        secret_delivery.to_string()
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                RunnerConfig::Wasm {
+                    module,
+                    timeout_sec,
+                    fuel,
+                    max_memory_mb,
+                    preopens,
+                    args,
+                } => {
+                    sqlx::query!(
+                        r#"INSERT INTO job_runner_wasm(job_id,module_path,module_sha256,timeout_sec,fuel,max_memory_mb,preopens,args)
+                       VALUES ($1,$2,$3,$4,$5,$6,$7,$8)"#,
+                        job_id,
+                        module.path(),
+                        module.sha256(),
+                        timeout_sec.map(|x| x as i32),
+                        fuel.map(|x| x as i64),
+                        max_memory_mb.map(|x| x as i32),
+                        serde_json::json!(preopens),
+                        serde_json::json!(args)
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                RunnerConfig::Plugin {
+                    plugin,
+                    config,
+                    timeout_sec,
+                } => {
+                    sqlx::query!(
+                        r#"INSERT INTO job_runner_plugin(job_id,plugin,config,timeout_sec)
+                       VALUES ($1,$2,$3,$4)"#,
+                        job_id,
+                        plugin,
+                        config,
                         timeout_sec.map(|x| x as i32)
                     )
                     .execute(&mut *tx)
@@ -1828,7 +2552,11 @@ impl ApiStore for PgStore {
         Ok(())
     }
 
-    async fn create_adhoc_run(&self, job_id: Uuid) -> Result<JobRun> {
+    async fn create_adhoc_run(
+        &self,
+        job_id: Uuid,
+        params: HashMap<String, RunParam>,
+    ) -> Result<JobRun> {
         let mut tx = self.pool.begin().await?;
 
         // Validate job exists + resolve config
@@ -1836,19 +2564,28 @@ impl ApiStore for PgStore {
 
         let snapshot_json = serde_json::to_value(&snapshot)
             .map_err(|e| ArbiterError::ExecutionError(e.to_string()))?;
+        let params_json = if params.is_empty() {
+            None
+        } else {
+            Some(
+                serde_json::to_value(&params)
+                    .map_err(|e| ArbiterError::ExecutionError(e.to_string()))?,
+            )
+        };
 
         let id = Uuid::new_v4();
 
         let rec = sqlx::query!(
             r#"
-        INSERT INTO job_runs(id, job_id, scheduled_for, state, config_snapshot)
-        VALUES ($1, $2, now(), 'queued', $3)
+        INSERT INTO job_runs(id, job_id, scheduled_for, state, config_snapshot, params)
+        VALUES ($1, $2, now(), 'queued', $3, $4)
         RETURNING id, job_id, scheduled_for, state, worker_id, attempt,
                   started_at, finished_at, exit_code
         "#,
             id,
             job_id,
             snapshot_json,
+            params_json,
         )
         .fetch_one(&mut *tx)
         .await?;
@@ -1872,6 +2609,10 @@ impl ApiStore for PgStore {
             result_media_type: None,
             error: None,
             error_media_type: None,
+            params,
+            progress: None,
+            last_heartbeat: None,
+            code_commit: None,
         })
     }
 
@@ -1931,7 +2672,8 @@ impl ApiStore for PgStore {
     async fn list_workers(&self) -> Result<Vec<WorkerRecord>> {
         let rows = sqlx::query!(
             r#"
-            SELECT id, display_name, hostname, last_seen, capacity, restart_count, version
+            SELECT id, display_name, hostname, last_seen, capacity, restart_count, version, plugins,
+                   disk_usage
             FROM workers
             ORDER BY last_seen DESC
             "#
@@ -1939,18 +2681,29 @@ impl ApiStore for PgStore {
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(rows
-            .into_iter()
-            .map(|r| WorkerRecord {
-                id: r.id,
-                display_name: r.display_name,
-                hostname: r.hostname,
-                last_seen: r.last_seen,
-                capacity: r.capacity as u32,
-                restart_count: r.restart_count as u32,
-                version: r.version,
+        rows.into_iter()
+            .map(|r| {
+                Ok(WorkerRecord {
+                    id: r.id,
+                    display_name: r.display_name,
+                    hostname: r.hostname,
+                    last_seen: r.last_seen,
+                    capacity: r.capacity as u32,
+                    restart_count: r.restart_count as u32,
+                    version: r.version,
+                    plugins: serde_json::from_value(r.plugins).map_err(|e| {
+                        ArbiterError::DatabaseError(format!("invalid worker plugins json: {e}"))
+                    })?,
+                    disk_usage: r
+                        .disk_usage
+                        .map(serde_json::from_value::<WorkerDiskUsage>)
+                        .transpose()
+                        .map_err(|e| {
+                            ArbiterError::DatabaseError(format!("invalid worker disk usage json: {e}"))
+                        })?,
+                })
             })
-            .collect())
+            .collect()
     }
 
     async fn get_user_by_id(&self, user_id: Uuid) -> Result<User> {
@@ -2215,14 +2968,16 @@ impl SecretStore for PgStore {
         dek_wrapped: &[u8],
         kek_version: u32,
     ) -> Result<Uuid> {
+        let mut tx = self.pool.begin().await?;
         let rec = sqlx::query!(
             r#"
             INSERT INTO secrets (tenant_id, name, value_ct, value_nonce, aead_algo, dek_wrapped, kek_version)
             VALUES ($1, $2, $3, $4, $5, $6, $7)
             ON CONFLICT (tenant_id, name) DO UPDATE SET
                 value_ct = $3, value_nonce = $4, aead_algo = $5,
-                dek_wrapped = $6, kek_version = $7, updated_at = now()
-            RETURNING id
+                dek_wrapped = $6, kek_version = $7, version = secrets.version + 1,
+                updated_at = now()
+            RETURNING id, version
             "#,
             tenant_id,
             name,
@@ -2232,15 +2987,30 @@ impl SecretStore for PgStore {
             dek_wrapped,
             kek_version as i32,
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"INSERT INTO secret_versions
+                   (secret_id, version, value_ct, value_nonce, aead_algo, dek_wrapped, kek_version)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+            rec.id,
+            rec.version,
+            value_ct,
+            value_nonce,
+            aead_algo,
+            dek_wrapped,
+            kek_version as i32,
+        )
+        .execute(&mut *tx)
         .await?;
+        tx.commit().await?;
         Ok(rec.id)
     }
 
     async fn get_secret_by_name(&self, tenant: Uuid, name: &str) -> Result<Option<StoredSecret>> {
         let rec = sqlx::query!(
-            r#"SELECT id, name, value_ct, value_nonce, aead_algo, dek_wrapped, kek_version,
-                      created_at, updated_at
+            r#"SELECT id, name, version, value_ct, value_nonce, aead_algo, dek_wrapped,
+                      kek_version, created_at, updated_at
                FROM secrets WHERE tenant_id = $1 AND name = $2"#,
             tenant,
             name
@@ -2250,6 +3020,7 @@ impl SecretStore for PgStore {
         Ok(rec.map(|r| StoredSecret {
             id: r.id,
             name: r.name,
+            version: r.version as u32,
             value_ct: r.value_ct,
             value_nonce: r.value_nonce,
             aead_algo: r.aead_algo,
@@ -2262,8 +3033,8 @@ impl SecretStore for PgStore {
 
     async fn get_secret(&self, id: Uuid, scope: Option<Uuid>) -> Result<Option<StoredSecret>> {
         let rec = sqlx::query!(
-            r#"SELECT id, name, value_ct, value_nonce, aead_algo, dek_wrapped, kek_version,
-                      created_at, updated_at
+            r#"SELECT id, name, version, value_ct, value_nonce, aead_algo, dek_wrapped,
+                      kek_version, created_at, updated_at
                FROM secrets WHERE id = $1 AND ($2::uuid IS NULL OR tenant_id = $2)"#,
             id,
             scope
@@ -2273,6 +3044,7 @@ impl SecretStore for PgStore {
         Ok(rec.map(|r| StoredSecret {
             id: r.id,
             name: r.name,
+            version: r.version as u32,
             value_ct: r.value_ct,
             value_nonce: r.value_nonce,
             aead_algo: r.aead_algo,
@@ -2285,8 +3057,18 @@ impl SecretStore for PgStore {
 
     async fn list_secret_names(&self, scope: Option<Uuid>) -> Result<Vec<SecretMeta>> {
         let rows = sqlx::query!(
-            r#"SELECT id, name, kek_version, created_at, updated_at FROM secrets
-               WHERE ($1::uuid IS NULL OR tenant_id = $1) ORDER BY name"#,
+            r#"SELECT s.id, s.tenant_id, s.name, s.version, s.kek_version,
+                      LEAST(s.kek_version, COALESCE(
+                          (SELECT MIN(v.kek_version) FROM secret_versions v
+                           WHERE v.secret_id = s.id), s.kek_version)) AS "oldest_kek_version!",
+                      COALESCE(
+                          (SELECT v.created_at FROM secret_versions v
+                           WHERE v.secret_id = s.id AND v.version = s.version),
+                          s.updated_at) AS "value_set_at!",
+                      s.expires_at, s.rotate_every_secs, s.last_resolved_at, s.last_resolved_run,
+                      s.created_at, s.updated_at
+               FROM secrets s
+               WHERE ($1::uuid IS NULL OR s.tenant_id = $1) ORDER BY s.name"#,
             scope
         )
         .fetch_all(&self.pool)
@@ -2295,28 +3077,271 @@ impl SecretStore for PgStore {
             .into_iter()
             .map(|r| SecretMeta {
                 id: r.id,
+                tenant_id: r.tenant_id,
                 name: r.name,
+                version: r.version as u32,
                 kek_version: r.kek_version as u32,
+                oldest_kek_version: r.oldest_kek_version as u32,
+                value_set_at: r.value_set_at,
+                expires_at: r.expires_at,
+                rotate_every_secs: r.rotate_every_secs.map(|v| v as u64),
+                last_resolved_at: r.last_resolved_at,
+                last_resolved_run: r.last_resolved_run,
                 created_at: r.created_at,
                 updated_at: r.updated_at,
             })
             .collect())
     }
 
-    async fn delete_secret(&self, id: Uuid) -> Result<()> {
-        sqlx::query!("DELETE FROM secrets WHERE id = $1", id)
-            .execute(&self.pool)
-            .await?;
-        Ok(())
-    }
-
-    async fn insert_kek_version(&self, version: u32, state: &str) -> Result<()> {
-        sqlx::query!(
-            "INSERT INTO kek_versions (version, state) VALUES ($1, $2)",
-            version as i32,
-            state
+    async fn get_secret_meta(&self, id: Uuid, scope: Option<Uuid>) -> Result<Option<SecretMeta>> {
+        let rec = sqlx::query!(
+            r#"SELECT s.id, s.tenant_id, s.name, s.version, s.kek_version,
+                      LEAST(s.kek_version, COALESCE(
+                          (SELECT MIN(v.kek_version) FROM secret_versions v
+                           WHERE v.secret_id = s.id), s.kek_version)) AS "oldest_kek_version!",
+                      COALESCE(
+                          (SELECT v.created_at FROM secret_versions v
+                           WHERE v.secret_id = s.id AND v.version = s.version),
+                          s.updated_at) AS "value_set_at!",
+                      s.expires_at, s.rotate_every_secs, s.last_resolved_at, s.last_resolved_run,
+                      s.created_at, s.updated_at
+               FROM secrets s
+               WHERE s.id = $1 AND ($2::uuid IS NULL OR s.tenant_id = $2)"#,
+            id,
+            scope
         )
-        .execute(&self.pool)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(rec.map(|r| SecretMeta {
+            id: r.id,
+            tenant_id: r.tenant_id,
+            name: r.name,
+            version: r.version as u32,
+            kek_version: r.kek_version as u32,
+            oldest_kek_version: r.oldest_kek_version as u32,
+            value_set_at: r.value_set_at,
+            expires_at: r.expires_at,
+            rotate_every_secs: r.rotate_every_secs.map(|v| v as u64),
+            last_resolved_at: r.last_resolved_at,
+            last_resolved_run: r.last_resolved_run,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }))
+    }
+
+    async fn get_secret_version(&self, id: Uuid, version: u32) -> Result<Option<StoredSecret>> {
+        let rec = sqlx::query!(
+            r#"SELECT s.id, s.name, v.version, v.value_ct, v.value_nonce, v.aead_algo,
+                      v.dek_wrapped, v.kek_version, s.created_at, v.created_at AS updated_at
+               FROM secret_versions v JOIN secrets s ON s.id = v.secret_id
+               WHERE v.secret_id = $1 AND v.version = $2"#,
+            id,
+            version as i32
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(rec.map(|r| StoredSecret {
+            id: r.id,
+            name: r.name,
+            version: r.version as u32,
+            value_ct: r.value_ct,
+            value_nonce: r.value_nonce,
+            aead_algo: r.aead_algo,
+            dek_wrapped: r.dek_wrapped,
+            kek_version: r.kek_version as u32,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }))
+    }
+
+    async fn list_secret_versions(&self, id: Uuid) -> Result<Vec<SecretVersionMeta>> {
+        let rows = sqlx::query!(
+            r#"SELECT version, kek_version, restored_from, created_at FROM secret_versions
+               WHERE secret_id = $1 ORDER BY version DESC"#,
+            id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| SecretVersionMeta {
+                version: r.version as u32,
+                kek_version: r.kek_version as u32,
+                restored_from: r.restored_from.map(|v| v as u32),
+                created_at: r.created_at,
+            })
+            .collect())
+    }
+
+    async fn restore_secret_version(&self, id: Uuid, version: u32) -> Result<Option<u32>> {
+        let mut tx = self.pool.begin().await?;
+        let Some(old) = sqlx::query!(
+            r#"SELECT value_ct, value_nonce, aead_algo, dek_wrapped, kek_version
+               FROM secret_versions WHERE secret_id = $1 AND version = $2"#,
+            id,
+            version as i32
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+        let rec = sqlx::query!(
+            r#"UPDATE secrets SET value_ct = $2, value_nonce = $3, aead_algo = $4,
+                   dek_wrapped = $5, kek_version = $6, version = version + 1, updated_at = now()
+               WHERE id = $1
+               RETURNING version"#,
+            id,
+            old.value_ct,
+            old.value_nonce,
+            old.aead_algo,
+            old.dek_wrapped,
+            old.kek_version
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"INSERT INTO secret_versions (secret_id, version, value_ct, value_nonce, aead_algo,
+                   dek_wrapped, kek_version, restored_from)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+            id,
+            rec.version,
+            old.value_ct,
+            old.value_nonce,
+            old.aead_algo,
+            old.dek_wrapped,
+            old.kek_version,
+            version as i32
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some(rec.version as u32))
+    }
+
+    async fn prune_secret_versions(&self, id: Uuid, keep: u32) -> Result<u32> {
+        let res = sqlx::query!(
+            r#"DELETE FROM secret_versions
+               WHERE secret_id = $1
+                 AND version <= (SELECT version FROM secrets WHERE id = $1) - $2"#,
+            id,
+            keep.max(1) as i32
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(res.rows_affected() as u32)
+    }
+
+    async fn delete_secret(&self, id: Uuid) -> Result<()> {
+        sqlx::query!("DELETE FROM secrets WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_secret_policy(
+        &self,
+        id: Uuid,
+        expires_at: Option<DateTime<Utc>>,
+        rotate_every_secs: Option<u64>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE secrets SET expires_at = $2, rotate_every_secs = $3 WHERE id = $1",
+            id,
+            expires_at,
+            rotate_every_secs.map(|v| v.min(i64::MAX as u64) as i64)
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn record_secret_use(&self, tenant: Uuid, name: &str, run_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE secrets SET last_resolved_at = now(), last_resolved_run = $3
+               WHERE tenant_id = $1 AND name = $2"#,
+            tenant,
+            name,
+            run_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn append_secret_audit(&self, event: &SecretAuditEvent) -> Result<()> {
+        sqlx::query!(
+            r#"INSERT INTO secret_audit (id, secret_id, tenant_id, secret_name, version, action,
+                                         actor_kind, actor_id, job_id, outcome, detail, at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"#,
+            event.id,
+            event.secret_id,
+            event.tenant_id,
+            event.secret_name,
+            event.version.map(|v| v as i32),
+            event.action.to_string(),
+            event.actor.kind(),
+            event.actor.id(),
+            event.actor.job_id(),
+            event.outcome.to_string(),
+            event.detail,
+            event.at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_secret_audit(
+        &self,
+        secret_id: Uuid,
+        before: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Result<Vec<SecretAuditEvent>> {
+        let rows = sqlx::query!(
+            r#"SELECT id, secret_id, tenant_id, secret_name, version, action, actor_kind, actor_id,
+                      job_id, outcome, detail, at
+               FROM secret_audit
+               WHERE secret_id = $1 AND ($2::timestamptz IS NULL OR at < $2)
+               ORDER BY at DESC, id DESC LIMIT $3"#,
+            secret_id,
+            before,
+            limit as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|r| {
+                Ok(SecretAuditEvent {
+                    id: r.id,
+                    secret_id: r.secret_id,
+                    tenant_id: r.tenant_id,
+                    secret_name: r.secret_name,
+                    version: r.version.map(|v| v as u32),
+                    action: r.action.parse()?,
+                    actor: SecretActor::from_parts(&r.actor_kind, r.actor_id, r.job_id)?,
+                    outcome: r.outcome.parse()?,
+                    detail: r.detail,
+                    at: r.at,
+                })
+            })
+            .collect()
+    }
+
+    async fn prune_secret_audit(&self, older_than: DateTime<Utc>) -> Result<u64> {
+        let res = sqlx::query!("DELETE FROM secret_audit WHERE at < $1", older_than)
+            .execute(&self.pool)
+            .await?;
+        Ok(res.rows_affected())
+    }
+
+    async fn insert_kek_version(&self, version: u32, state: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO kek_versions (version, state) VALUES ($1, $2)",
+            version as i32,
+            state
+        )
+        .execute(&self.pool)
         .await?;
         Ok(())
     }
@@ -2352,16 +3377,35 @@ impl SecretStore for PgStore {
         Ok(())
     }
 
-    async fn rewrap_secret(&self, id: Uuid, dek_wrapped: &[u8], kek_version: u32) -> Result<()> {
+    async fn rewrap_secret(
+        &self,
+        id: Uuid,
+        version: u32,
+        dek_wrapped: &[u8],
+        kek_version: u32,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
         sqlx::query!(
-            r#"UPDATE secrets SET dek_wrapped = $2, kek_version = $3, updated_at = now()
-               WHERE id = $1"#,
+            r#"UPDATE secret_versions SET dek_wrapped = $3, kek_version = $4
+               WHERE secret_id = $1 AND version = $2"#,
             id,
+            version as i32,
             dek_wrapped,
             kek_version as i32
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"UPDATE secrets SET dek_wrapped = $3, kek_version = $4, updated_at = now()
+               WHERE id = $1 AND version = $2"#,
+            id,
+            version as i32,
+            dek_wrapped,
+            kek_version as i32
+        )
+        .execute(&mut *tx)
         .await?;
+        tx.commit().await?;
         Ok(())
     }
 
@@ -2525,14 +3569,16 @@ impl ConfigStore for PgStore {
         username: &str,
         password_secret: &str,
         database: &str,
+        pool_size: Option<u32>,
     ) -> Result<SharedDbConfig> {
         let port_i = port as i32;
+        let pool_i = pool_size.map(|n| n as i32);
         let id = match engine {
             DbEngine::PgSql => {
                 sqlx::query!(
-                    r#"INSERT INTO pgsql_configs (name, host, port, username, password_secret, database, tenant_id)
-                       VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id"#,
-                    name, host, port_i, username, password_secret, database, tenant_id
+                    r#"INSERT INTO pgsql_configs (name, host, port, username, password_secret, database, tenant_id, pool_size)
+                       VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id"#,
+                    name, host, port_i, username, password_secret, database, tenant_id, pool_i
                 )
                 .fetch_one(&self.pool)
                 .await?
@@ -2540,9 +3586,9 @@ impl ConfigStore for PgStore {
             }
             DbEngine::MySql => {
                 sqlx::query!(
-                    r#"INSERT INTO mysql_configs (name, host, port, username, password_secret, database, tenant_id)
-                       VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id"#,
-                    name, host, port_i, username, password_secret, database, tenant_id
+                    r#"INSERT INTO mysql_configs (name, host, port, username, password_secret, database, tenant_id, pool_size)
+                       VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id"#,
+                    name, host, port_i, username, password_secret, database, tenant_id, pool_i
                 )
                 .fetch_one(&self.pool)
                 .await?
@@ -2559,6 +3605,7 @@ impl ConfigStore for PgStore {
             password_secret: password_secret.to_string(),
             database: database.to_string(),
             tenant_id,
+            pool_size,
         })
     }
 
@@ -2567,10 +3614,10 @@ impl ConfigStore for PgStore {
             r#"
             SELECT id AS "id!", 'pgsql' AS "engine!", name AS "name!", host AS "host!",
                    port AS "port!", username AS "username!", password_secret AS "password_secret!",
-                   database AS "database!", tenant_id AS "tenant_id!"
+                   database AS "database!", tenant_id AS "tenant_id!", pool_size
             FROM pgsql_configs WHERE id = $1 AND deleted_at IS NULL AND ($2::uuid IS NULL OR tenant_id = $2)
             UNION ALL
-            SELECT id, 'mysql', name, host, port, username, password_secret, database, tenant_id
+            SELECT id, 'mysql', name, host, port, username, password_secret, database, tenant_id, pool_size
             FROM mysql_configs WHERE id = $1 AND deleted_at IS NULL AND ($2::uuid IS NULL OR tenant_id = $2)
             "#,
             id,
@@ -2589,6 +3636,7 @@ impl ConfigStore for PgStore {
                 password_secret: r.password_secret,
                 database: r.database,
                 tenant_id: r.tenant_id,
+                pool_size: r.pool_size.map(|n| n as u32),
             })
         })
         .transpose()
@@ -2599,10 +3647,10 @@ impl ConfigStore for PgStore {
             r#"
             SELECT id AS "id!", 'pgsql' AS "engine!", name AS "name!", host AS "host!",
                    port AS "port!", username AS "username!", password_secret AS "password_secret!",
-                   database AS "database!", tenant_id AS "tenant_id!"
+                   database AS "database!", tenant_id AS "tenant_id!", pool_size
             FROM pgsql_configs WHERE deleted_at IS NULL AND ($1::uuid IS NULL OR tenant_id = $1)
             UNION ALL
-            SELECT id, 'mysql', name, host, port, username, password_secret, database, tenant_id
+            SELECT id, 'mysql', name, host, port, username, password_secret, database, tenant_id, pool_size
             FROM mysql_configs WHERE deleted_at IS NULL AND ($1::uuid IS NULL OR tenant_id = $1)
             ORDER BY 3
             "#,
@@ -2622,6 +3670,7 @@ impl ConfigStore for PgStore {
                     password_secret: r.password_secret,
                     database: r.database,
                     tenant_id: r.tenant_id,
+                    pool_size: r.pool_size.map(|n| n as u32),
                 })
             })
             .collect()
@@ -2636,6 +3685,7 @@ impl ConfigStore for PgStore {
         username: Option<&str>,
         password_secret: Option<&str>,
         database: Option<&str>,
+        pool_size: Option<u32>,
     ) -> Result<SharedDbConfig> {
         let engine = self
             .get_db_config(id, None)
@@ -2643,6 +3693,7 @@ impl ConfigStore for PgStore {
             .ok_or_else(|| ArbiterError::NotFound(format!("db config {id}")))?
             .engine;
         let port_i = port.map(|p| p as i32);
+        let pool_i = pool_size.map(|n| n as i32);
         match engine {
             DbEngine::PgSql => {
                 sqlx::query!(
@@ -2650,9 +3701,10 @@ impl ConfigStore for PgStore {
                         name = COALESCE($2, name), host = COALESCE($3, host),
                         port = COALESCE($4, port), username = COALESCE($5, username),
                         password_secret = COALESCE($6, password_secret),
-                        database = COALESCE($7, database)
+                        database = COALESCE($7, database),
+                        pool_size = COALESCE($8, pool_size)
                        WHERE id = $1 AND deleted_at IS NULL"#,
-                    id, name, host, port_i, username, password_secret, database
+                    id, name, host, port_i, username, password_secret, database, pool_i
                 )
                 .execute(&self.pool)
                 .await?;
@@ -2663,9 +3715,10 @@ impl ConfigStore for PgStore {
                         name = COALESCE($2, name), host = COALESCE($3, host),
                         port = COALESCE($4, port), username = COALESCE($5, username),
                         password_secret = COALESCE($6, password_secret),
-                        database = COALESCE($7, database)
+                        database = COALESCE($7, database),
+                        pool_size = COALESCE($8, pool_size)
                        WHERE id = $1 AND deleted_at IS NULL"#,
-                    id, name, host, port_i, username, password_secret, database
+                    id, name, host, port_i, username, password_secret, database, pool_i
                 )
                 .execute(&self.pool)
                 .await?;
@@ -2691,22 +3744,204 @@ impl ConfigStore for PgStore {
         .await?;
         Ok(())
     }
+
+    async fn request_config_test(&self, config_id: Uuid) -> Result<Uuid> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO config_tests (config_id) VALUES ($1) RETURNING id",
+            config_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        self.pg_notify_channel("arbiter_config_tests").await;
+        Ok(id)
+    }
+
+    async fn claim_config_tests(
+        &self,
+        worker_id: Uuid,
+        limit: u32,
+        max_age_secs: u32,
+    ) -> Result<Vec<ConfigTestRequest>> {
+        let cutoff = Utc::now() - Duration::seconds(max_age_secs as i64);
+        let rows = sqlx::query!(
+            r#"
+            UPDATE config_tests SET claimed_by = $1, claimed_at = now()
+            WHERE id IN (
+                SELECT ct.id FROM config_tests ct
+                WHERE ct.claimed_by IS NULL AND ct.requested_at > $2
+                  AND (EXISTS (SELECT 1 FROM pgsql_configs c WHERE c.id = ct.config_id AND c.deleted_at IS NULL)
+                       OR EXISTS (SELECT 1 FROM mysql_configs c WHERE c.id = ct.config_id AND c.deleted_at IS NULL))
+                ORDER BY ct.requested_at
+                LIMIT $3
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, config_id
+            "#,
+            worker_id,
+            cutoff,
+            limit as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut out = Vec::with_capacity(rows.len());
+        for r in rows {
+            if let Some(config) = self.get_db_config(r.config_id, None).await? {
+                out.push(ConfigTestRequest { id: r.id, config });
+            }
+        }
+        Ok(out)
+    }
+
+    async fn finish_config_test(&self, id: Uuid, result: &ConfigTestResult) -> Result<()> {
+        let json = serde_json::to_value(result)
+            .map_err(|e| ArbiterError::ExecutionError(e.to_string()))?;
+        sqlx::query!("UPDATE config_tests SET result = $2 WHERE id = $1", id, json)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_config_test(&self, id: Uuid) -> Result<Option<ConfigTestResult>> {
+        let result = sqlx::query_scalar!("SELECT result FROM config_tests WHERE id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?
+            .flatten();
+        result
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| ArbiterError::DatabaseError(format!("invalid config test result: {e}")))
+    }
+
+    async fn prune_config_tests(&self, older_than: DateTime<Utc>) -> Result<u64> {
+        let res = sqlx::query!("DELETE FROM config_tests WHERE requested_at < $1", older_than)
+            .execute(&self.pool)
+            .await?;
+        Ok(res.rows_affected())
+    }
+
+    async fn await_config_tests_change(&self) {
+        self.pg_await_channel("arbiter_config_tests").await;
+    }
 }
 
 fn mk_log_chunk(
     seq: i64,
     stream: String,
     content: String,
+    level: Option<String>,
     created_at: DateTime<Utc>,
 ) -> Result<LogChunk> {
     Ok(LogChunk {
         seq,
         stream: stream.parse()?,
         content,
+        level: level.map(|l| l.parse()).transpose()?,
         created_at,
     })
 }
 
+#[async_trait]
+impl ModuleStore for PgStore {
+    async fn put_wasm_module(
+        &self,
+        tenant_id: Uuid,
+        sha256: &str,
+        content: &[u8],
+    ) -> Result<WasmModuleInfo> {
+        sqlx::query!(
+            r#"INSERT INTO wasm_modules (tenant_id, sha256, content, size_bytes)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (tenant_id, sha256) DO NOTHING"#,
+            tenant_id,
+            sha256,
+            content,
+            content.len() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+        let r = sqlx::query!(
+            r#"SELECT sha256, size_bytes, tenant_id, created_at FROM wasm_modules
+               WHERE tenant_id = $1 AND sha256 = $2"#,
+            tenant_id,
+            sha256
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(WasmModuleInfo {
+            sha256: r.sha256,
+            size_bytes: r.size_bytes as u64,
+            tenant_id: r.tenant_id,
+            created_at: r.created_at,
+        })
+    }
+
+    async fn get_wasm_module(&self, sha256: &str, scope: Option<Uuid>) -> Result<Option<Vec<u8>>> {
+        let row = sqlx::query!(
+            r#"SELECT content FROM wasm_modules
+               WHERE sha256 = $1 AND ($2::uuid IS NULL OR tenant_id = $2)
+               LIMIT 1"#,
+            sha256,
+            scope
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|r| r.content))
+    }
+
+    async fn list_wasm_modules(&self, scope: Option<Uuid>) -> Result<Vec<WasmModuleInfo>> {
+        let rows = sqlx::query!(
+            r#"SELECT sha256, size_bytes, tenant_id, created_at FROM wasm_modules
+               WHERE ($1::uuid IS NULL OR tenant_id = $1)
+               ORDER BY created_at DESC, sha256"#,
+            scope
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| WasmModuleInfo {
+                sha256: r.sha256,
+                size_bytes: r.size_bytes as u64,
+                tenant_id: r.tenant_id,
+                created_at: r.created_at,
+            })
+            .collect())
+    }
+
+    async fn delete_wasm_module(&self, sha256: &str, scope: Option<Uuid>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        // A live job in the module's tenant still runs it: refuse rather than break it.
+        let in_use = sqlx::query_scalar!(
+            r#"SELECT EXISTS (
+                   SELECT 1 FROM job_runner_wasm w
+                   JOIN jobs j ON j.id = w.job_id
+                   WHERE w.module_sha256 = $1
+                     AND j.deleted_at IS NULL
+                     AND ($2::uuid IS NULL OR j.tenant_id = $2)
+               ) AS "in_use!""#,
+            sha256,
+            scope
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if in_use {
+            return Err(ArbiterError::Conflict(format!("wasm module {sha256} in use by a job")));
+        }
+        let res = sqlx::query!(
+            "DELETE FROM wasm_modules WHERE sha256 = $1 AND ($2::uuid IS NULL OR tenant_id = $2)",
+            sha256,
+            scope
+        )
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(ArbiterError::NotFound(format!("wasm module {sha256} not found")));
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl LogStore for PgStore {
     async fn append_run_log(
@@ -2715,17 +3950,19 @@ impl LogStore for PgStore {
         attempt: u32,
         seq: i64,
         stream: LogStream,
+        level: Option<LogLevel>,
         content: &str,
     ) -> Result<()> {
         sqlx::query!(
-            r#"INSERT INTO run_log_chunks (run_id, attempt, seq, stream, content)
-               VALUES ($1, $2, $3, $4, $5)
+            r#"INSERT INTO run_log_chunks (run_id, attempt, seq, stream, content, level)
+               VALUES ($1, $2, $3, $4, $5, $6)
                ON CONFLICT (run_id, attempt, seq) DO NOTHING"#,
             run_id,
             attempt as i32,
             seq,
             stream.to_string(),
             content,
+            level.map(|l| l.to_string()),
         )
         .execute(&self.pool)
         .await?;
@@ -2740,7 +3977,7 @@ impl LogStore for PgStore {
         limit: u32,
     ) -> Result<Vec<LogChunk>> {
         let rows = sqlx::query!(
-            r#"SELECT seq, stream, content, created_at
+            r#"SELECT seq, stream, content, level, created_at
                FROM run_log_chunks
                WHERE run_id = $1 AND attempt = $2 AND ($3::bigint IS NULL OR seq > $3)
                ORDER BY seq ASC
@@ -2753,7 +3990,7 @@ impl LogStore for PgStore {
         .fetch_all(&self.pool)
         .await?;
         rows.into_iter()
-            .map(|r| mk_log_chunk(r.seq, r.stream, r.content, r.created_at))
+            .map(|r| mk_log_chunk(r.seq, r.stream, r.content, r.level, r.created_at))
             .collect()
     }
 
@@ -2765,8 +4002,8 @@ impl LogStore for PgStore {
         limit: u32,
     ) -> Result<Vec<LogChunk>> {
         let rows = sqlx::query!(
-            r#"SELECT seq, stream, content, created_at FROM (
-                 SELECT seq, stream, content, created_at
+            r#"SELECT seq, stream, content, level, created_at FROM (
+                 SELECT seq, stream, content, level, created_at
                  FROM run_log_chunks
                  WHERE run_id = $1 AND attempt = $2 AND ($3::bigint IS NULL OR seq < $3)
                  ORDER BY seq DESC
@@ -2780,7 +4017,7 @@ impl LogStore for PgStore {
         .fetch_all(&self.pool)
         .await?;
         rows.into_iter()
-            .map(|r| mk_log_chunk(r.seq, r.stream, r.content, r.created_at))
+            .map(|r| mk_log_chunk(r.seq, r.stream, r.content, r.level, r.created_at))
             .collect()
     }
 
@@ -2802,9 +4039,314 @@ impl LogStore for PgStore {
     }
 }
 
+#[async_trait]
+impl ArtifactStore for PgStore {
+    async fn put_artifact(
+        &self,
+        tenant_id: Uuid,
+        run_id: Uuid,
+        attempt: u32,
+        name: &str,
+        sha256: &str,
+        content: &[u8],
+    ) -> Result<RunArtifact> {
+        validate_artifact_name(name)?;
+        let r = sqlx::query!(
+            r#"INSERT INTO run_artifacts (run_id, attempt, name, tenant_id, size_bytes, sha256, content)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)
+               ON CONFLICT (run_id, attempt, name) DO UPDATE
+               SET tenant_id = EXCLUDED.tenant_id, size_bytes = EXCLUDED.size_bytes,
+                   sha256 = EXCLUDED.sha256, content = EXCLUDED.content, created_at = now()
+               RETURNING created_at"#,
+            run_id,
+            attempt as i32,
+            name,
+            tenant_id,
+            content.len() as i64,
+            sha256,
+            content
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(RunArtifact {
+            run_id,
+            attempt,
+            name: name.to_string(),
+            size_bytes: content.len() as u64,
+            sha256: sha256.to_string(),
+            created_at: r.created_at,
+        })
+    }
+
+    async fn list_artifacts(&self, tenant_id: Uuid, run_id: Uuid) -> Result<Vec<RunArtifact>> {
+        let rows = sqlx::query!(
+            r#"SELECT attempt, name, size_bytes, sha256, created_at FROM run_artifacts
+               WHERE tenant_id = $1 AND run_id = $2
+               ORDER BY attempt, name"#,
+            tenant_id,
+            run_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| RunArtifact {
+                run_id,
+                attempt: r.attempt as u32,
+                name: r.name,
+                size_bytes: r.size_bytes as u64,
+                sha256: r.sha256,
+                created_at: r.created_at,
+            })
+            .collect())
+    }
+
+    async fn get_artifact(
+        &self,
+        tenant_id: Uuid,
+        run_id: Uuid,
+        attempt: u32,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        let row = sqlx::query!(
+            r#"SELECT content FROM run_artifacts
+               WHERE tenant_id = $1 AND run_id = $2 AND attempt = $3 AND name = $4"#,
+            tenant_id,
+            run_id,
+            attempt as i32,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|r| r.content))
+    }
+
+    async fn tenant_artifact_bytes(&self, tenant_id: Uuid) -> Result<u64> {
+        let total = sqlx::query_scalar!(
+            r#"SELECT COALESCE(SUM(size_bytes), 0)::BIGINT AS "total!" FROM run_artifacts
+               WHERE tenant_id = $1"#,
+            tenant_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(total as u64)
+    }
+
+    async fn prune_artifacts(&self, older_than: DateTime<Utc>) -> Result<u64> {
+        let res = sqlx::query!("DELETE FROM run_artifacts WHERE created_at < $1", older_than)
+            .execute(&self.pool)
+            .await?;
+        Ok(res.rows_affected())
+    }
+}
+
+#[async_trait]
+impl BundleStore for PgStore {
+    async fn put_code_bundle(
+        &self,
+        tenant_id: Uuid,
+        name: &str,
+        format: BundleFormat,
+        sha256: &str,
+        content: &[u8],
+    ) -> Result<CodeBundle> {
+        validate_bundle_name(name)?;
+        let mut tx = self.pool.begin().await?;
+        // Locking the latest version queues concurrent uploads of the name behind this one.
+        let latest = sqlx::query!(
+            r#"SELECT version, sha256, format, size_bytes, created_at FROM code_bundles
+               WHERE tenant_id = $1 AND name = $2
+               ORDER BY version DESC LIMIT 1
+               FOR UPDATE"#,
+            tenant_id,
+            name
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(l) = &latest
+            && l.sha256 == sha256
+        {
+            return Ok(CodeBundle {
+                tenant_id,
+                name: name.to_string(),
+                version: l.version as u32,
+                sha256: l.sha256.clone(),
+                format: l.format.parse()?,
+                size_bytes: l.size_bytes as u64,
+                created_at: l.created_at,
+            });
+        }
+        let version = latest.map_or(1, |l| l.version + 1);
+        // Only the first version of a name can race (there was no row to lock).
+        let r = sqlx::query!(
+            r#"INSERT INTO code_bundles (tenant_id, name, version, sha256, format, size_bytes, content)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)
+               ON CONFLICT (tenant_id, name, version) DO NOTHING
+               RETURNING created_at"#,
+            tenant_id,
+            name,
+            version,
+            sha256,
+            format.to_string(),
+            content.len() as i64,
+            content
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| {
+            ArbiterError::Conflict(format!("bundle {name} was uploaded concurrently, retry"))
+        })?;
+        tx.commit().await?;
+        Ok(CodeBundle {
+            tenant_id,
+            name: name.to_string(),
+            version: version as u32,
+            sha256: sha256.to_string(),
+            format,
+            size_bytes: content.len() as u64,
+            created_at: r.created_at,
+        })
+    }
+
+    async fn get_code_bundle(
+        &self,
+        name: &str,
+        version: Option<u32>,
+        scope: Option<Uuid>,
+    ) -> Result<Option<CodeBundle>> {
+        let row = sqlx::query!(
+            r#"SELECT tenant_id, name, version, sha256, format, size_bytes, created_at
+               FROM code_bundles
+               WHERE name = $1
+                 AND ($2::int IS NULL OR version = $2)
+                 AND ($3::uuid IS NULL OR tenant_id = $3)
+               ORDER BY version DESC
+               LIMIT 1"#,
+            name,
+            version.map(|v| v as i32),
+            scope
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(|r| {
+            Ok(CodeBundle {
+                tenant_id: r.tenant_id,
+                name: r.name,
+                version: r.version as u32,
+                sha256: r.sha256,
+                format: r.format.parse()?,
+                size_bytes: r.size_bytes as u64,
+                created_at: r.created_at,
+            })
+        })
+        .transpose()
+    }
+
+    async fn get_code_bundle_content(
+        &self,
+        name: &str,
+        version: u32,
+        scope: Option<Uuid>,
+    ) -> Result<Option<Vec<u8>>> {
+        let row = sqlx::query!(
+            r#"SELECT content FROM code_bundles
+               WHERE name = $1 AND version = $2 AND ($3::uuid IS NULL OR tenant_id = $3)
+               LIMIT 1"#,
+            name,
+            version as i32,
+            scope
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|r| r.content))
+    }
+
+    async fn list_code_bundles(&self, scope: Option<Uuid>) -> Result<Vec<CodeBundle>> {
+        let rows = sqlx::query!(
+            r#"SELECT tenant_id, name, version, sha256, format, size_bytes, created_at
+               FROM code_bundles
+               WHERE ($1::uuid IS NULL OR tenant_id = $1)
+               ORDER BY name, version"#,
+            scope
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|r| {
+                Ok(CodeBundle {
+                    tenant_id: r.tenant_id,
+                    name: r.name,
+                    version: r.version as u32,
+                    sha256: r.sha256,
+                    format: r.format.parse()?,
+                    size_bytes: r.size_bytes as u64,
+                    created_at: r.created_at,
+                })
+            })
+            .collect()
+    }
+
+    async fn delete_code_bundle(
+        &self,
+        name: &str,
+        version: u32,
+        scope: Option<Uuid>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        // A live job of the bundle's tenant pins this version, or follows the latest and
+        // this is the last one: refuse rather than break it.
+        let in_use = sqlx::query_scalar!(
+            r#"WITH refs AS (
+                   SELECT j.tenant_id, COALESCE(s.code, py.code, nd.code) AS code
+                   FROM jobs j
+                   LEFT JOIN job_runner_shell  s  ON s.job_id  = j.id
+                   LEFT JOIN job_runner_python py ON py.job_id = j.id
+                   LEFT JOIN job_runner_node   nd ON nd.job_id = j.id
+                   WHERE j.deleted_at IS NULL
+               )
+               SELECT EXISTS (
+                   SELECT 1 FROM code_bundles b
+                   JOIN refs r ON r.tenant_id = b.tenant_id
+                   WHERE b.name = $1 AND b.version = $2
+                     AND ($3::uuid IS NULL OR b.tenant_id = $3)
+                     AND r.code->>'source' = 'bundle'
+                     AND r.code->>'name' = b.name
+                     AND ((r.code->>'version')::int = b.version
+                          OR (r.code->>'version' IS NULL AND NOT EXISTS (
+                                SELECT 1 FROM code_bundles o
+                                WHERE o.tenant_id = b.tenant_id AND o.name = b.name
+                                  AND o.version <> b.version)))
+               ) AS "in_use!""#,
+            name,
+            version as i32,
+            scope
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if in_use {
+            return Err(ArbiterError::Conflict(format!(
+                "bundle {name} v{version} in use by a job"
+            )));
+        }
+        let res = sqlx::query!(
+            r#"DELETE FROM code_bundles
+               WHERE name = $1 AND version = $2 AND ($3::uuid IS NULL OR tenant_id = $3)"#,
+            name,
+            version as i32,
+            scope
+        )
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(ArbiterError::NotFound(format!("bundle {name} v{version} not found")));
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::*;
     use sqlx::PgPool;
     use testcontainers::{ContainerAsync, runners::AsyncRunner};
     use testcontainers_modules::postgres;
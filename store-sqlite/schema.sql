@@ -21,7 +21,9 @@ CREATE TABLE IF NOT EXISTS workers (
     capacity INTEGER NOT NULL,
     active INTEGER NOT NULL DEFAULT 1,
     restart_count INTEGER NOT NULL DEFAULT 0,
-    version TEXT NOT NULL
+    version TEXT NOT NULL,
+    plugins TEXT NOT NULL DEFAULT '[]',
+    disk_usage TEXT
 );
 
 CREATE TABLE IF NOT EXISTS jobs (
@@ -37,6 +39,8 @@ CREATE TABLE IF NOT EXISTS jobs (
     backoff_strategy TEXT NOT NULL DEFAULT 'exponential',
     backoff_base_secs INTEGER NOT NULL DEFAULT 30,
     backoff_cap_secs INTEGER NOT NULL DEFAULT 3600,
+    -- JSON RetryOn: per-job overrides of what counts as retryable.
+    retry_on TEXT NOT NULL DEFAULT '{}',
     tenant_id BLOB NOT NULL DEFAULT x'00000000000000000000000000000001',
     deleted_at TEXT
 );
@@ -44,7 +48,10 @@ CREATE TABLE IF NOT EXISTS jobs (
 CREATE TABLE IF NOT EXISTS job_runner_shell (
     job_id TEXT PRIMARY KEY,
     command TEXT NOT NULL,
-    working_dir TEXT
+    working_dir TEXT,
+    code TEXT,
+    workspace TEXT NOT NULL DEFAULT 'run',
+    secret_delivery TEXT NOT NULL DEFAULT 'env'
 );
 
 CREATE TABLE IF NOT EXISTS job_runner_http (
@@ -53,20 +60,82 @@ CREATE TABLE IF NOT EXISTS job_runner_http (
     url TEXT NOT NULL,
     headers TEXT,
     body TEXT,
-    timeout_sec INTEGER
+    timeout_sec INTEGER,
+    tls TEXT,
+    signing TEXT
 );
 
 CREATE TABLE IF NOT EXISTS job_runner_python (
     job_id TEXT PRIMARY KEY,
     module TEXT NOT NULL,
     class_name TEXT NOT NULL,
-    timeout_sec INTEGER
+    timeout_sec INTEGER,
+    transport TEXT NOT NULL DEFAULT 'file',
+    max_runs INTEGER,
+    secrets TEXT NOT NULL DEFAULT '[]',
+    code TEXT,
+    workspace TEXT NOT NULL DEFAULT 'run',
+    secret_delivery TEXT NOT NULL DEFAULT 'env'
+);
+
+CREATE TABLE IF NOT EXISTS job_runner_sqlite (
+    job_id TEXT PRIMARY KEY,
+    path TEXT NOT NULL,
+    query TEXT NOT NULL,
+    timeout_sec INTEGER,
+    busy_timeout_sec INTEGER,
+    output TEXT
 );
 
 CREATE TABLE IF NOT EXISTS job_runner_node (
     job_id TEXT PRIMARY KEY,
     module TEXT NOT NULL,
     function_name TEXT NOT NULL,
+    timeout_sec INTEGER,
+    transport TEXT NOT NULL DEFAULT 'file',
+    max_runs INTEGER,
+    secrets TEXT NOT NULL DEFAULT '[]',
+    code TEXT,
+    workspace TEXT NOT NULL DEFAULT 'run',
+    secret_delivery TEXT NOT NULL DEFAULT 'env'
+);
+
+CREATE TABLE IF NOT EXISTS wasm_modules (
+    tenant_id BLOB NOT NULL,
+    sha256 TEXT NOT NULL,
+    content BLOB NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (tenant_id, sha256)
+);
+
+CREATE TABLE IF NOT EXISTS code_bundles (
+    tenant_id BLOB NOT NULL,
+    name TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    sha256 TEXT NOT NULL,
+    format TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    content BLOB NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (tenant_id, name, version)
+);
+
+CREATE TABLE IF NOT EXISTS job_runner_wasm (
+    job_id TEXT PRIMARY KEY,
+    module_path TEXT,
+    module_sha256 TEXT,
+    timeout_sec INTEGER,
+    fuel INTEGER,
+    max_memory_mb INTEGER,
+    preopens TEXT NOT NULL DEFAULT '[]',
+    args TEXT NOT NULL DEFAULT '[]'
+);
+
+CREATE TABLE IF NOT EXISTS job_runner_plugin (
+    job_id TEXT PRIMARY KEY,
+    plugin TEXT NOT NULL,
+    config TEXT NOT NULL DEFAULT '{}',
     timeout_sec INTEGER
 );
 
@@ -93,7 +162,12 @@ CREATE TABLE IF NOT EXISTS job_runs (
     result_media_type TEXT,
     error TEXT,
     error_media_type TEXT,
-    config_snapshot TEXT
+    config_snapshot TEXT,
+    params TEXT,
+    progress REAL,
+    progress_message TEXT,
+    last_heartbeat TEXT,
+    code_commit TEXT
 );
 
 CREATE UNIQUE INDEX IF NOT EXISTS job_runs_unique_run ON job_runs(job_id, scheduled_for);
@@ -130,11 +204,49 @@ CREATE TABLE IF NOT EXISTS secrets (
     dek_wrapped BLOB NOT NULL,
     kek_version INTEGER NOT NULL,
     tenant_id BLOB NOT NULL DEFAULT x'00000000000000000000000000000001',
+    version INTEGER NOT NULL DEFAULT 1,
+    expires_at TEXT,
+    rotate_every_secs INTEGER,
+    last_resolved_at TEXT,
+    last_resolved_run BLOB,
     created_at TEXT NOT NULL,
     updated_at TEXT NOT NULL,
     UNIQUE (tenant_id, name)
 );
 
+-- Retained versions of each secret; the current one is mirrored on the secrets row.
+CREATE TABLE IF NOT EXISTS secret_versions (
+    secret_id TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    value_ct BLOB NOT NULL,
+    value_nonce BLOB NOT NULL,
+    aead_algo TEXT NOT NULL,
+    dek_wrapped BLOB NOT NULL,
+    kek_version INTEGER NOT NULL,
+    restored_from INTEGER,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (secret_id, version)
+);
+
+-- Append-only trail of every resolve and change of a secret. No FK: events outlive the
+-- secret they are about. Pruned on its own retention window.
+CREATE TABLE IF NOT EXISTS secret_audit (
+    id TEXT PRIMARY KEY,
+    secret_id TEXT,
+    tenant_id TEXT NOT NULL,
+    secret_name TEXT NOT NULL,
+    version INTEGER,
+    action TEXT NOT NULL,
+    actor_kind TEXT NOT NULL,
+    actor_id TEXT NOT NULL,
+    job_id TEXT,
+    outcome TEXT NOT NULL,
+    detail TEXT,
+    at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS secret_audit_secret_idx ON secret_audit (secret_id, at);
+CREATE INDEX IF NOT EXISTS secret_audit_at_idx ON secret_audit (at);
+
 CREATE TABLE IF NOT EXISTS kek_versions (
     version INTEGER PRIMARY KEY,
     state TEXT NOT NULL,
@@ -167,10 +279,24 @@ CREATE TABLE IF NOT EXISTS run_log_chunks (
     seq INTEGER NOT NULL,
     stream TEXT NOT NULL,
     content TEXT NOT NULL,
+    level TEXT,
     created_at TEXT NOT NULL,
     PRIMARY KEY (run_id, attempt, seq)
 );
 
+-- Files a run attempt left in its artifacts directory (the database artifact backend).
+CREATE TABLE IF NOT EXISTS run_artifacts (
+    run_id TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    tenant_id BLOB NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    sha256 TEXT NOT NULL,
+    content BLOB NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (run_id, attempt, name)
+);
+
 -- Shared DB connection configs (per engine). password_secret holds a secret reference
 -- (secret:<name>), never a plaintext password. Storing configs is backend-agnostic;
 -- executing pgsql/mysql runners is still Postgres-only.
@@ -183,6 +309,7 @@ CREATE TABLE IF NOT EXISTS pgsql_configs (
     password_secret TEXT NOT NULL,
     database TEXT NOT NULL,
     tenant_id BLOB NOT NULL DEFAULT x'00000000000000000000000000000001',
+    pool_size INTEGER,
     deleted_at TEXT DEFAULT NULL,
     UNIQUE (host, port, username, database)
 );
@@ -196,6 +323,18 @@ CREATE TABLE IF NOT EXISTS mysql_configs (
     password_secret TEXT NOT NULL,
     database TEXT NOT NULL,
     tenant_id BLOB NOT NULL DEFAULT x'00000000000000000000000000000001',
+    pool_size INTEGER,
     deleted_at TEXT DEFAULT NULL,
     UNIQUE (host, port, username, database)
 );
+
+-- "Test connection" requests for shared configs: queued by the api, claimed and run by a
+-- worker, result (ConfigTestResult JSON) polled by the api.
+CREATE TABLE IF NOT EXISTS config_tests (
+    id BLOB PRIMARY KEY,
+    config_id BLOB NOT NULL,
+    requested_at TEXT NOT NULL,
+    claimed_by BLOB,
+    claimed_at TEXT,
+    result TEXT
+);
@@ -14,12 +14,17 @@ use std::str::FromStr;
 use std::collections::HashMap;
 
 use arbiter_core::{
-    ApiStore, ArbiterError, BackoffStrategy, ConfigStore, DbEngine, ExecutableConfigSnapshot,
-    ExecutableConfigSnapshotMeta, JobRun, JobRunState, JobSpec, JobStore, LogChunk, LogSize,
-    LogStore, LogStream, MisfirePolicy, ResultStatus, Result, RetryConfig, RunOutcome, RunStore,
-    RunnerConfig, SecretMeta, SecretStore, Setting, SettingsStore, SharedDbConfig, Store,
-    StoredKekShare, StoredKekVersion, StoredNodeKey, StoredSecret, Tenant, TenantStore, User,
-    UserRole, WorkerRecord, WorkerStore,
+    ApiStore, ArbiterError, ArtifactStore, BackoffStrategy, BundleFormat, BundleStore, CodeBundle,
+    CodeSource, ConfigStore, ConfigTestRequest, ConfigTestResult, DbEngine, DbOutputConfig,
+    ExecutableConfigSnapshot, ExecutableConfigSnapshotMeta, HttpSigningConfig, HttpTlsConfig,
+    JobRun, JobRunState, JobSpec, JobStore, LogChunk, LogLevel, LogSize, LogStore, LogStream,
+    MisfirePolicy, ModuleStore, Result, ResultStatus, RetryConfig, RetryOn, RunArtifact,
+    RunOutcome, RunParam, RunProgress, RunStore, RunnerConfig, RuntimeTransport, SecretActor,
+    SecretAuditEvent, SecretDelivery, SecretMeta, SecretStore, SecretVersionMeta, Setting,
+    SettingsStore, SharedDbConfig, Store, StoredKekShare, StoredKekVersion, StoredNodeKey,
+    StoredSecret, Tenant, TenantStore, User, UserRole, WasmModuleInfo, WasmModuleSource,
+    WorkerDiskUsage, WorkerRecord, WorkerStore, WorkspaceMode, validate_artifact_name,
+    validate_bundle_name,
 };
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
@@ -49,11 +54,21 @@ fn mk_run(
     result_media_type: Option<String>,
     error: Option<String>,
     error_media_type: Option<String>,
+    params: Option<String>,
+    progress: Option<f64>,
+    progress_message: Option<String>,
+    last_heartbeat: Option<DateTime<Utc>>,
+    code_commit: Option<String>,
 ) -> Result<JobRun> {
     let result_status = match result_status {
         Some(s) => Some(ResultStatus::from_str(&s)?),
         None => None,
     };
+    let params = params
+        .map(|p| serde_json::from_str(&p))
+        .transpose()
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid run params json: {e}")))?
+        .unwrap_or_default();
     Ok(JobRun {
         id,
         job_id,
@@ -70,6 +85,13 @@ fn mk_run(
         result_media_type,
         error,
         error_media_type,
+        params,
+        progress: progress.map(|value| RunProgress {
+            value,
+            message: progress_message,
+        }),
+        last_heartbeat,
+        code_commit,
     })
 }
 
@@ -84,26 +106,62 @@ fn mk_job_spec(
     misfire_policy: String,
     command: Option<String>,
     working_dir: Option<String>,
+    shell_code: Option<String>,
+    shell_workspace: Option<String>,
+    shell_secret_delivery: Option<String>,
     http_method: Option<String>,
     http_url: Option<String>,
     http_headers: Option<String>,
     http_body: Option<String>,
     http_timeout_sec: Option<i64>,
+    http_tls: Option<String>,
+    http_signing: Option<String>,
+    sq_path: Option<String>,
+    sq_query: Option<String>,
+    sq_timeout_sec: Option<i64>,
+    sq_busy_timeout_sec: Option<i64>,
+    sq_output: Option<String>,
     py_module: Option<String>,
     py_class_name: Option<String>,
     py_timeout_sec: Option<i64>,
+    py_transport: Option<String>,
+    py_max_runs: Option<i64>,
+    py_secrets: Option<String>,
+    py_code: Option<String>,
+    py_workspace: Option<String>,
+    py_secret_delivery: Option<String>,
     node_module: Option<String>,
     node_function_name: Option<String>,
     node_timeout_sec: Option<i64>,
+    node_transport: Option<String>,
+    node_max_runs: Option<i64>,
+    node_secrets: Option<String>,
+    node_code: Option<String>,
+    node_workspace: Option<String>,
+    node_secret_delivery: Option<String>,
+    wasm_module_path: Option<String>,
+    wasm_module_sha256: Option<String>,
+    wasm_timeout_sec: Option<i64>,
+    wasm_fuel: Option<i64>,
+    wasm_max_memory_mb: Option<i64>,
+    wasm_preopens: Option<String>,
+    wasm_args: Option<String>,
+    plugin_name: Option<String>,
+    plugin_config: Option<String>,
+    plugin_timeout_sec: Option<i64>,
     max_attempts: i64,
     backoff_strategy: String,
     backoff_base_secs: i64,
     backoff_cap_secs: i64,
+    retry_on: String,
 ) -> Result<JobSpec> {
     let runner_cfg = match runner_type.as_str() {
         "shell" => RunnerConfig::Shell {
             command: command.unwrap_or_default(),
             working_dir,
+            code: parse_code_source(shell_code)?,
+            workspace: parse_workspace(shell_workspace)?,
+            secret_delivery: parse_secret_delivery(shell_secret_delivery)?,
         },
         "http" => RunnerConfig::Http {
             method: http_method.unwrap_or_else(|| "GET".to_string()),
@@ -111,16 +169,50 @@ fn mk_job_spec(
             headers: http_headers.and_then(|j| serde_json::from_str(&j).ok()),
             body: http_body,
             timeout_sec: http_timeout_sec.map(|x| x as u32),
+            tls: parse_http_tls(http_tls)?,
+            signing: parse_http_signing(http_signing)?,
+        },
+        "sqlite" => RunnerConfig::Sqlite {
+            path: sq_path.unwrap_or_default(),
+            query: sq_query.unwrap_or_default(),
+            timeout_sec: sq_timeout_sec.map(|x| x as u32),
+            busy_timeout_sec: sq_busy_timeout_sec.map(|x| x as u32),
+            output: parse_db_output(sq_output)?,
         },
         "python" => RunnerConfig::Python {
             module: py_module.unwrap_or_default(),
             class_name: py_class_name.unwrap_or_default(),
             timeout_sec: py_timeout_sec.map(|x| x as u32),
+            transport: parse_transport(py_transport)?,
+            max_runs: py_max_runs.map(|x| x as u32),
+            secrets: parse_json_list(py_secrets, "secrets")?,
+            code: parse_code_source(py_code)?,
+            workspace: parse_workspace(py_workspace)?,
+            secret_delivery: parse_secret_delivery(py_secret_delivery)?,
         },
         "node" => RunnerConfig::Node {
             module: node_module.unwrap_or_default(),
             function_name: node_function_name.unwrap_or_default(),
             timeout_sec: node_timeout_sec.map(|x| x as u32),
+            transport: parse_transport(node_transport)?,
+            max_runs: node_max_runs.map(|x| x as u32),
+            secrets: parse_json_list(node_secrets, "secrets")?,
+            code: parse_code_source(node_code)?,
+            workspace: parse_workspace(node_workspace)?,
+            secret_delivery: parse_secret_delivery(node_secret_delivery)?,
+        },
+        "wasm" => RunnerConfig::Wasm {
+            module: wasm_module_source(wasm_module_path, wasm_module_sha256)?,
+            timeout_sec: wasm_timeout_sec.map(|x| x as u32),
+            fuel: wasm_fuel.map(|x| x as u64),
+            max_memory_mb: wasm_max_memory_mb.map(|x| x as u32),
+            preopens: parse_json_list(wasm_preopens, "preopens")?,
+            args: parse_json_list(wasm_args, "args")?,
+        },
+        "plugin" => RunnerConfig::Plugin {
+            plugin: plugin_name.unwrap_or_default(),
+            config: parse_plugin_config(plugin_config)?,
+            timeout_sec: plugin_timeout_sec.map(|x| x as u32),
         },
         // pgsql/mysql need shared connection configs + secrets, not yet on sqlite.
         other => {
@@ -141,9 +233,92 @@ fn mk_job_spec(
         backoff_strategy: BackoffStrategy::from_str(&backoff_strategy)?,
         backoff_base_secs: backoff_base_secs as u32,
         backoff_cap_secs: backoff_cap_secs as u32,
+        retry_on: parse_retry_on(&retry_on)?,
     })
 }
 
+/// Decode the optional `job_runner_http.tls` JSON column.
+fn parse_http_tls(json: Option<String>) -> Result<Option<HttpTlsConfig>> {
+    json.map(|j| serde_json::from_str(&j))
+        .transpose()
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid http tls json: {e}")))
+}
+
+/// Decode the `workspace` column of `job_runner_shell` / `job_runner_python` /
+/// `job_runner_node` (NULL when the row is absent from a LEFT JOIN).
+fn parse_workspace(v: Option<String>) -> Result<WorkspaceMode> {
+    v.map(|w| w.parse()).transpose().map(Option::unwrap_or_default)
+}
+
+/// Decode the `secret_delivery` column of `job_runner_shell` / `job_runner_python` /
+/// `job_runner_node` (NULL when the row is absent from a LEFT JOIN).
+fn parse_secret_delivery(v: Option<String>) -> Result<SecretDelivery> {
+    v.map(|d| d.parse()).transpose().map(Option::unwrap_or_default)
+}
+
+/// Decode the optional `code` JSON column of `job_runner_shell` / `job_runner_python` /
+/// `job_runner_node`.
+fn parse_code_source(json: Option<String>) -> Result<Option<CodeSource>> {
+    json.map(|j| serde_json::from_str(&j))
+        .transpose()
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid code source json: {e}")))
+}
+
+/// Decode the optional `job_runner_http.signing` JSON column.
+fn parse_http_signing(json: Option<String>) -> Result<Option<HttpSigningConfig>> {
+    json.map(|j| serde_json::from_str(&j))
+        .transpose()
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid http signing json: {e}")))
+}
+
+/// Decode the optional `job_runner_sqlite.output` JSON column.
+fn parse_db_output(json: Option<String>) -> Result<Option<DbOutputConfig>> {
+    json.map(|j| serde_json::from_str(&j))
+        .transpose()
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid db output json: {e}")))
+}
+
+/// Rebuild a `wasm` runner's module source from `module_path` / `module_sha256` (exactly
+/// one is set).
+fn wasm_module_source(path: Option<String>, sha256: Option<String>) -> Result<WasmModuleSource> {
+    match (path, sha256) {
+        (Some(path), None) => Ok(WasmModuleSource::Path { path }),
+        (None, Some(sha256)) => Ok(WasmModuleSource::Stored { sha256 }),
+        _ => Err(ArbiterError::DatabaseError(
+            "wasm runner needs exactly one of module_path/module_sha256".to_string(),
+        )),
+    }
+}
+
+/// Decode a JSON list column: `preopens`/`args` of `job_runner_wasm`, `secrets` of
+/// `job_runner_python`/`job_runner_node`.
+fn parse_json_list<T: serde::de::DeserializeOwned>(json: Option<String>, what: &str) -> Result<Vec<T>> {
+    json.map(|j| serde_json::from_str(&j))
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid {what} json: {e}")))
+}
+
+/// Decode the `transport` column of `job_runner_python` / `job_runner_node` (NULL when the
+/// row is absent from a LEFT JOIN).
+fn parse_transport(v: Option<String>) -> Result<RuntimeTransport> {
+    v.map(|t| t.parse()).transpose().map(Option::unwrap_or_default)
+}
+
+/// Decode the `job_runner_plugin.config` JSON column.
+fn parse_plugin_config(json: Option<String>) -> Result<serde_json::Value> {
+    json.map(|j| serde_json::from_str(&j))
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid plugin config json: {e}")))
+}
+
+/// Decode the `jobs.retry_on` JSON column.
+fn parse_retry_on(json: &str) -> Result<RetryOn> {
+    serde_json::from_str(json)
+        .map_err(|e| ArbiterError::DatabaseError(format!("invalid retry_on json: {e}")))
+}
+
 fn mk_user(
     id: Uuid,
     username: String,
@@ -171,6 +346,7 @@ pub struct SqliteStore {
     jobs_notify: std::sync::Arc<tokio::sync::Notify>,
     runs_notify: std::sync::Arc<tokio::sync::Notify>,
     workers_notify: std::sync::Arc<tokio::sync::Notify>,
+    config_tests_notify: std::sync::Arc<tokio::sync::Notify>,
 }
 
 impl SqliteStore {
@@ -194,6 +370,7 @@ impl SqliteStore {
             jobs_notify: std::sync::Arc::new(tokio::sync::Notify::new()),
             runs_notify: std::sync::Arc::new(tokio::sync::Notify::new()),
             workers_notify: std::sync::Arc::new(tokio::sync::Notify::new()),
+            config_tests_notify: std::sync::Arc::new(tokio::sync::Notify::new()),
         })
     }
 
@@ -213,20 +390,40 @@ impl SqliteStore {
     /// claimed run carries everything the worker needs (independent of later edits).
     async fn build_snapshot_for_job(&self, job_id: Uuid) -> Result<ExecutableConfigSnapshot> {
         let row = sqlx::query!(
-            r#"SELECT j.name AS "name!", j.runner_type AS "runner_type!",
+            r#"SELECT j.name AS "name!", j.runner_type AS "runner_type!", j.retry_on AS "retry_on!",
                       s.command AS "shell_command?", s.working_dir AS "shell_working_dir?",
+                      s.code AS "shell_code?", s.workspace AS "shell_workspace?", s.secret_delivery AS "shell_secret_delivery?",
                       h.method AS "http_method?", h.url AS "http_url?",
                       h.headers AS "http_headers?", h.body AS "http_body?",
                       h.timeout_sec AS "http_timeout_sec?: i64",
+                      h.tls AS "http_tls?",
+                      h.signing AS "http_signing?",
+                      sq.path AS "sq_path?", sq.query AS "sq_query?",
+                      sq.timeout_sec AS "sq_timeout_sec?: i64",
+                      sq.busy_timeout_sec AS "sq_busy_timeout_sec?: i64",
+                      sq.output AS "sq_output?",
                       py.module AS "py_module?", py.class_name AS "py_class_name?",
                       py.timeout_sec AS "py_timeout_sec?: i64",
+                      py.transport AS "py_transport?", py.max_runs AS "py_max_runs?: i64",
+                      py.secrets AS "py_secrets?", py.code AS "py_code?", py.workspace AS "py_workspace?", py.secret_delivery AS "py_secret_delivery?",
                       nd.module AS "node_module?", nd.function_name AS "node_function_name?",
-                      nd.timeout_sec AS "node_timeout_sec?: i64"
+                      nd.timeout_sec AS "node_timeout_sec?: i64",
+                      nd.transport AS "node_transport?", nd.max_runs AS "node_max_runs?: i64",
+                      nd.secrets AS "node_secrets?", nd.code AS "node_code?", nd.workspace AS "node_workspace?", nd.secret_delivery AS "node_secret_delivery?",
+                      wa.module_path AS "wasm_module_path?", wa.module_sha256 AS "wasm_module_sha256?",
+                      wa.timeout_sec AS "wasm_timeout_sec?: i64", wa.fuel AS "wasm_fuel?: i64",
+                      wa.max_memory_mb AS "wasm_max_memory_mb?: i64",
+                      wa.preopens AS "wasm_preopens?", wa.args AS "wasm_args?",
+                      pl.plugin AS "plugin_name?", pl.config AS "plugin_config?",
+                      pl.timeout_sec AS "plugin_timeout_sec?: i64"
                FROM jobs j
                LEFT JOIN job_runner_shell s ON s.job_id = j.id
                LEFT JOIN job_runner_http h ON h.job_id = j.id
+               LEFT JOIN job_runner_sqlite sq ON sq.job_id = j.id
                LEFT JOIN job_runner_python py ON py.job_id = j.id
                LEFT JOIN job_runner_node nd ON nd.job_id = j.id
+               LEFT JOIN job_runner_wasm wa ON wa.job_id = j.id
+               LEFT JOIN job_runner_plugin pl ON pl.job_id = j.id
                WHERE j.id = ?"#,
             job_id
         )
@@ -239,6 +436,9 @@ impl SqliteStore {
             "shell" => ExecutableConfigSnapshotMeta::Shell {
                 command: row.shell_command.unwrap_or_default(),
                 working_dir: row.shell_working_dir,
+                code: parse_code_source(row.shell_code)?,
+                workspace: parse_workspace(row.shell_workspace)?,
+                secret_delivery: parse_secret_delivery(row.shell_secret_delivery)?,
                 env: self.load_env_for_job(job_id).await?,
             },
             "http" => {
@@ -252,18 +452,54 @@ impl SqliteStore {
                     headers,
                     body: row.http_body,
                     timeout_sec: row.http_timeout_sec.map(|x| x as u32),
+                    tls: parse_http_tls(row.http_tls)?,
+                    signing: parse_http_signing(row.http_signing)?,
                 }
             }
+            "sqlite" => ExecutableConfigSnapshotMeta::Sqlite {
+                path: row.sq_path.unwrap_or_default(),
+                query: row.sq_query.unwrap_or_default(),
+                timeout_sec: row.sq_timeout_sec.map(|x| x as u32),
+                busy_timeout_sec: row.sq_busy_timeout_sec.map(|x| x as u32),
+                output: parse_db_output(row.sq_output)?,
+            },
             "python" => ExecutableConfigSnapshotMeta::Python {
                 module: row.py_module.unwrap_or_default(),
                 class_name: row.py_class_name.unwrap_or_default(),
                 timeout_sec: row.py_timeout_sec.map(|x| x as u32),
+                transport: parse_transport(row.py_transport)?,
+                max_runs: row.py_max_runs.map(|x| x as u32),
+                secrets: parse_json_list(row.py_secrets, "secrets")?,
+                code: parse_code_source(row.py_code)?,
+                workspace: parse_workspace(row.py_workspace)?,
+                secret_delivery: parse_secret_delivery(row.py_secret_delivery)?,
                 env: self.load_env_for_job(job_id).await?,
             },
             "node" => ExecutableConfigSnapshotMeta::Node {
                 module: row.node_module.unwrap_or_default(),
                 function_name: row.node_function_name.unwrap_or_default(),
                 timeout_sec: row.node_timeout_sec.map(|x| x as u32),
+                transport: parse_transport(row.node_transport)?,
+                max_runs: row.node_max_runs.map(|x| x as u32),
+                secrets: parse_json_list(row.node_secrets, "secrets")?,
+                code: parse_code_source(row.node_code)?,
+                workspace: parse_workspace(row.node_workspace)?,
+                secret_delivery: parse_secret_delivery(row.node_secret_delivery)?,
+                env: self.load_env_for_job(job_id).await?,
+            },
+            "wasm" => ExecutableConfigSnapshotMeta::Wasm {
+                module: wasm_module_source(row.wasm_module_path, row.wasm_module_sha256)?,
+                timeout_sec: row.wasm_timeout_sec.map(|x| x as u32),
+                fuel: row.wasm_fuel.map(|x| x as u64),
+                max_memory_mb: row.wasm_max_memory_mb.map(|x| x as u32),
+                preopens: parse_json_list(row.wasm_preopens, "preopens")?,
+                args: parse_json_list(row.wasm_args, "args")?,
+                env: self.load_env_for_job(job_id).await?,
+            },
+            "plugin" => ExecutableConfigSnapshotMeta::Plugin {
+                plugin: row.plugin_name.unwrap_or_default(),
+                config: parse_plugin_config(row.plugin_config)?,
+                timeout_sec: row.plugin_timeout_sec.map(|x| x as u32),
                 env: self.load_env_for_job(job_id).await?,
             },
             other => {
@@ -277,6 +513,7 @@ impl SqliteStore {
             name: None,
             job_name: row.name,
             meta,
+            retry_on: parse_retry_on(&row.retry_on)?,
         })
     }
 }
@@ -293,18 +530,38 @@ impl JobStore for SqliteStore {
                       j.misfire_policy AS "misfire_policy!",
                       j.max_attempts AS "max_attempts!: i64", j.backoff_strategy AS "backoff_strategy!",
                       j.backoff_base_secs AS "backoff_base_secs!: i64", j.backoff_cap_secs AS "backoff_cap_secs!: i64",
-                      s.command AS "command?", s.working_dir,
+                      j.retry_on AS "retry_on!",
+                      s.command AS "command?", s.working_dir, s.code AS "shell_code?", s.workspace AS "shell_workspace?", s.secret_delivery AS "shell_secret_delivery?",
                       h.method AS "http_method?", h.url AS "http_url?",
                       h.headers AS "http_headers?", h.body AS "http_body?",
                       h.timeout_sec AS "http_timeout_sec?: i64",
+                      h.tls AS "http_tls?",
+                      h.signing AS "http_signing?",
+                      sq.path AS "sq_path?", sq.query AS "sq_query?",
+                      sq.timeout_sec AS "sq_timeout_sec?: i64",
+                      sq.busy_timeout_sec AS "sq_busy_timeout_sec?: i64",
+                      sq.output AS "sq_output?",
                       py.module AS "py_module?", py.class_name AS "py_class_name?",
                       py.timeout_sec AS "py_timeout_sec?: i64",
+                      py.transport AS "py_transport?", py.max_runs AS "py_max_runs?: i64",
+                      py.secrets AS "py_secrets?", py.code AS "py_code?", py.workspace AS "py_workspace?", py.secret_delivery AS "py_secret_delivery?",
                       nd.module AS "node_module?", nd.function_name AS "node_function_name?",
-                      nd.timeout_sec AS "node_timeout_sec?: i64"
+                      nd.timeout_sec AS "node_timeout_sec?: i64",
+                      nd.transport AS "node_transport?", nd.max_runs AS "node_max_runs?: i64",
+                      nd.secrets AS "node_secrets?", nd.code AS "node_code?", nd.workspace AS "node_workspace?", nd.secret_delivery AS "node_secret_delivery?",
+                      wa.module_path AS "wasm_module_path?", wa.module_sha256 AS "wasm_module_sha256?",
+                      wa.timeout_sec AS "wasm_timeout_sec?: i64", wa.fuel AS "wasm_fuel?: i64",
+                      wa.max_memory_mb AS "wasm_max_memory_mb?: i64",
+                      wa.preopens AS "wasm_preopens?", wa.args AS "wasm_args?",
+                      pl.plugin AS "plugin_name?", pl.config AS "plugin_config?",
+                      pl.timeout_sec AS "plugin_timeout_sec?: i64"
                FROM jobs j LEFT JOIN job_runner_shell s ON s.job_id = j.id
                LEFT JOIN job_runner_http h ON h.job_id = j.id
+               LEFT JOIN job_runner_sqlite sq ON sq.job_id = j.id
                LEFT JOIN job_runner_python py ON py.job_id = j.id
                LEFT JOIN job_runner_node nd ON nd.job_id = j.id
+               LEFT JOIN job_runner_wasm wa ON wa.job_id = j.id
+               LEFT JOIN job_runner_plugin pl ON pl.job_id = j.id
                WHERE j.deleted_at IS NULL AND j.enabled = 1 AND j.schedule_cron IS NOT NULL"#
         )
         .fetch_all(&self.pool)
@@ -322,21 +579,54 @@ impl JobStore for SqliteStore {
                     r.misfire_policy,
                     r.command,
                     r.working_dir,
+                    r.shell_code,
+                    r.shell_workspace,
+                    r.shell_secret_delivery,
                     r.http_method,
                     r.http_url,
                     r.http_headers,
                     r.http_body,
                     r.http_timeout_sec,
+                    r.http_tls,
+                    r.http_signing,
+                    r.sq_path,
+                    r.sq_query,
+                    r.sq_timeout_sec,
+                    r.sq_busy_timeout_sec,
+                    r.sq_output,
                     r.py_module,
                     r.py_class_name,
                     r.py_timeout_sec,
+                    r.py_transport,
+                    r.py_max_runs,
+                    r.py_secrets,
+                    r.py_code,
+                    r.py_workspace,
+                    r.py_secret_delivery,
                     r.node_module,
                     r.node_function_name,
                     r.node_timeout_sec,
+                    r.node_transport,
+                    r.node_max_runs,
+                    r.node_secrets,
+                    r.node_code,
+                    r.node_workspace,
+                    r.node_secret_delivery,
+                    r.wasm_module_path,
+                    r.wasm_module_sha256,
+                    r.wasm_timeout_sec,
+                    r.wasm_fuel,
+                    r.wasm_max_memory_mb,
+                    r.wasm_preopens,
+                    r.wasm_args,
+                    r.plugin_name,
+                    r.plugin_config,
+                    r.plugin_timeout_sec,
                     r.max_attempts,
                     r.backoff_strategy,
                     r.backoff_base_secs,
                     r.backoff_cap_secs,
+                    r.retry_on,
                 )
             })
             .collect()
@@ -383,7 +673,7 @@ impl JobStore for SqliteStore {
 #[async_trait]
 impl RunStore for SqliteStore {
     async fn prune_runs(&self, older_than: DateTime<Utc>) -> Result<u64> {
-        // Drop the pruned runs' log chunks (their output) first, then the runs.
+        // Drop the pruned runs' log chunks (their output) and artifacts first, then the runs.
         sqlx::query!(
             "DELETE FROM run_log_chunks WHERE run_id IN \
              (SELECT id FROM job_runs WHERE scheduled_for < ?1 \
@@ -393,6 +683,15 @@ impl RunStore for SqliteStore {
         .execute(&self.pool)
         .await
         .map_err(db)?;
+        sqlx::query!(
+            "DELETE FROM run_artifacts WHERE run_id IN \
+             (SELECT id FROM job_runs WHERE scheduled_for < ?1 \
+              AND state IN ('succeeded', 'failed', 'cancelled'))",
+            older_than
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(db)?;
         let res = sqlx::query!(
             "DELETE FROM job_runs WHERE scheduled_for < ? AND state IN ('succeeded', 'failed', 'cancelled')",
             older_than
@@ -423,7 +722,9 @@ impl RunStore for SqliteStore {
         let now = Utc::now();
         let limit = limit as i64;
         let rows = sqlx::query!(
-            r#"UPDATE job_runs SET state = 'running', worker_id = ?, started_at = ?
+            r#"UPDATE job_runs SET state = 'running', worker_id = ?, started_at = ?,
+                   progress = NULL, progress_message = NULL, last_heartbeat = NULL,
+                   code_commit = NULL
                WHERE id IN (
                    SELECT jr.id FROM job_runs jr JOIN jobs j ON j.id = jr.job_id
                    WHERE jr.state = 'queued' AND jr.scheduled_for <= ?
@@ -434,7 +735,7 @@ impl RunStore for SqliteStore {
                          scheduled_for AS "scheduled_for!: DateTime<Utc>", state AS "state!",
                          worker_id AS "worker_id?: Uuid", exit_code, attempt AS "attempt!: i64",
                          started_at AS "started_at?: DateTime<Utc>",
-                         finished_at AS "finished_at?: DateTime<Utc>""#,
+                         finished_at AS "finished_at?: DateTime<Utc>", params"#,
             worker_id,
             now,
             now,
@@ -475,6 +776,11 @@ impl RunStore for SqliteStore {
                 None,
                 None,
                 None,
+                r.params,
+                None,
+                None,
+                None,
+                None,
             )?;
             run.snapshot = Some(snapshot);
             runs.push(run);
@@ -528,6 +834,74 @@ impl RunStore for SqliteStore {
         Ok(())
     }
 
+    async fn heartbeat_run(
+        &self,
+        run_id: Uuid,
+        worker_id: Uuid,
+        progress: Option<RunProgress>,
+    ) -> Result<bool> {
+        let now = Utc::now();
+        let has_progress = progress.is_some();
+        let (value, message) = match progress {
+            Some(p) => (Some(p.value), p.message),
+            None => (None, None),
+        };
+        let res = sqlx::query!(
+            "UPDATE job_runs SET last_heartbeat = ?1, \
+                 progress = CASE WHEN ?2 THEN ?3 ELSE progress END, \
+                 progress_message = CASE WHEN ?2 THEN ?4 ELSE progress_message END \
+             WHERE id = ?5 AND worker_id = ?6 AND state = 'running'",
+            now,
+            has_progress,
+            value,
+            message,
+            run_id,
+            worker_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(db)?;
+        // Progress shows on the live run view; a bare heartbeat changes nothing visible.
+        if has_progress && res.rows_affected() > 0 {
+            self.runs_notify.notify_waiters();
+        }
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn owns_run(&self, run_id: Uuid, worker_id: Uuid, attempt: u32) -> Result<bool> {
+        let owned = sqlx::query_scalar!(
+            "SELECT EXISTS (SELECT 1 FROM job_runs \
+                 WHERE id = ?1 AND worker_id = ?2 AND attempt = ?3 AND state = 'running') \
+                 AS \"owned!: bool\"",
+            run_id,
+            worker_id,
+            attempt
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(owned)
+    }
+
+    async fn record_run_code_commit(
+        &self,
+        run_id: Uuid,
+        worker_id: Uuid,
+        commit: &str,
+    ) -> Result<bool> {
+        let res = sqlx::query!(
+            "UPDATE job_runs SET code_commit = ?1 \
+             WHERE id = ?2 AND worker_id = ?3 AND state = 'running'",
+            commit,
+            run_id,
+            worker_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(res.rows_affected() > 0)
+    }
+
     async fn reschedule_for_retry(
         &self,
         run_id: Uuid,
@@ -577,18 +951,26 @@ impl WorkerStore for SqliteStore {
     async fn heartbeat(&self, worker: &WorkerRecord) -> Result<()> {
         let capacity = worker.capacity as i64;
         let restart_count = worker.restart_count as i64;
+        let plugins = serde_json::to_string(&worker.plugins).unwrap_or_default();
+        let disk_usage = worker
+            .disk_usage
+            .as_ref()
+            .map(|d| serde_json::to_string(d).unwrap_or_default());
         sqlx::query!(
-            "INSERT INTO workers (id, display_name, hostname, last_seen, capacity, active, restart_count, version) \
-             VALUES (?, ?, ?, ?, ?, 1, ?, ?) \
+            "INSERT INTO workers (id, display_name, hostname, last_seen, capacity, active, restart_count, version, plugins, disk_usage) \
+             VALUES (?, ?, ?, ?, ?, 1, ?, ?, ?, ?) \
              ON CONFLICT(id) DO UPDATE SET last_seen = excluded.last_seen, hostname = excluded.hostname, \
-                capacity = excluded.capacity, version = excluded.version, active = 1",
+                capacity = excluded.capacity, version = excluded.version, plugins = excluded.plugins, \
+                disk_usage = COALESCE(excluded.disk_usage, workers.disk_usage), active = 1",
             worker.id,
             worker.display_name,
             worker.hostname,
             worker.last_seen,
             capacity,
             restart_count,
-            worker.version
+            worker.version,
+            plugins,
+            disk_usage
         )
         .execute(&self.pool)
         .await
@@ -657,7 +1039,8 @@ impl WorkerStore for SqliteStore {
         let threshold = Utc::now() - Duration::seconds(dead_after_secs as i64);
         let res = sqlx::query!(
             "UPDATE job_runs SET state = 'queued', worker_id = NULL, started_at = NULL \
-             WHERE state = 'running' AND worker_id IN (SELECT id FROM workers WHERE last_seen < ?)",
+             WHERE state = 'running' AND worker_id IN (SELECT id FROM workers WHERE last_seen < ?1) \
+               AND (last_heartbeat IS NULL OR last_heartbeat < ?1)",
             threshold
         )
         .execute(&self.pool)
@@ -717,18 +1100,38 @@ impl ApiStore for SqliteStore {
                       j.misfire_policy AS "misfire_policy!",
                       j.max_attempts AS "max_attempts!: i64", j.backoff_strategy AS "backoff_strategy!",
                       j.backoff_base_secs AS "backoff_base_secs!: i64", j.backoff_cap_secs AS "backoff_cap_secs!: i64",
-                      s.command AS "command?", s.working_dir,
+                      j.retry_on AS "retry_on!",
+                      s.command AS "command?", s.working_dir, s.code AS "shell_code?", s.workspace AS "shell_workspace?", s.secret_delivery AS "shell_secret_delivery?",
                       h.method AS "http_method?", h.url AS "http_url?",
                       h.headers AS "http_headers?", h.body AS "http_body?",
                       h.timeout_sec AS "http_timeout_sec?: i64",
+                      h.tls AS "http_tls?",
+                      h.signing AS "http_signing?",
+                      sq.path AS "sq_path?", sq.query AS "sq_query?",
+                      sq.timeout_sec AS "sq_timeout_sec?: i64",
+                      sq.busy_timeout_sec AS "sq_busy_timeout_sec?: i64",
+                      sq.output AS "sq_output?",
                       py.module AS "py_module?", py.class_name AS "py_class_name?",
                       py.timeout_sec AS "py_timeout_sec?: i64",
+                      py.transport AS "py_transport?", py.max_runs AS "py_max_runs?: i64",
+                      py.secrets AS "py_secrets?", py.code AS "py_code?", py.workspace AS "py_workspace?", py.secret_delivery AS "py_secret_delivery?",
                       nd.module AS "node_module?", nd.function_name AS "node_function_name?",
-                      nd.timeout_sec AS "node_timeout_sec?: i64"
+                      nd.timeout_sec AS "node_timeout_sec?: i64",
+                      nd.transport AS "node_transport?", nd.max_runs AS "node_max_runs?: i64",
+                      nd.secrets AS "node_secrets?", nd.code AS "node_code?", nd.workspace AS "node_workspace?", nd.secret_delivery AS "node_secret_delivery?",
+                      wa.module_path AS "wasm_module_path?", wa.module_sha256 AS "wasm_module_sha256?",
+                      wa.timeout_sec AS "wasm_timeout_sec?: i64", wa.fuel AS "wasm_fuel?: i64",
+                      wa.max_memory_mb AS "wasm_max_memory_mb?: i64",
+                      wa.preopens AS "wasm_preopens?", wa.args AS "wasm_args?",
+                      pl.plugin AS "plugin_name?", pl.config AS "plugin_config?",
+                      pl.timeout_sec AS "plugin_timeout_sec?: i64"
                FROM jobs j LEFT JOIN job_runner_shell s ON s.job_id = j.id
                LEFT JOIN job_runner_http h ON h.job_id = j.id
+               LEFT JOIN job_runner_sqlite sq ON sq.job_id = j.id
                LEFT JOIN job_runner_python py ON py.job_id = j.id
                LEFT JOIN job_runner_node nd ON nd.job_id = j.id
+               LEFT JOIN job_runner_wasm wa ON wa.job_id = j.id
+               LEFT JOIN job_runner_plugin pl ON pl.job_id = j.id
                WHERE j.deleted_at IS NULL AND j.id = ?1 AND (?2 IS NULL OR j.tenant_id = ?2)"#,
             job_id,
             scope
@@ -747,21 +1150,54 @@ impl ApiStore for SqliteStore {
                 r.misfire_policy,
                 r.command,
                 r.working_dir,
+                r.shell_code,
+                r.shell_workspace,
+                r.shell_secret_delivery,
                 r.http_method,
                 r.http_url,
                 r.http_headers,
                 r.http_body,
                 r.http_timeout_sec,
+                r.http_tls,
+                r.http_signing,
+                r.sq_path,
+                r.sq_query,
+                r.sq_timeout_sec,
+                r.sq_busy_timeout_sec,
+                r.sq_output,
                 r.py_module,
                 r.py_class_name,
                 r.py_timeout_sec,
+                r.py_transport,
+                r.py_max_runs,
+                r.py_secrets,
+                r.py_code,
+                r.py_workspace,
+                r.py_secret_delivery,
                 r.node_module,
                 r.node_function_name,
                 r.node_timeout_sec,
+                r.node_transport,
+                r.node_max_runs,
+                r.node_secrets,
+                r.node_code,
+                r.node_workspace,
+                r.node_secret_delivery,
+                r.wasm_module_path,
+                r.wasm_module_sha256,
+                r.wasm_timeout_sec,
+                r.wasm_fuel,
+                r.wasm_max_memory_mb,
+                r.wasm_preopens,
+                r.wasm_args,
+                r.plugin_name,
+                r.plugin_config,
+                r.plugin_timeout_sec,
                 r.max_attempts,
                 r.backoff_strategy,
                 r.backoff_base_secs,
                 r.backoff_cap_secs,
+                r.retry_on,
             ),
             None => Err(ArbiterError::NotFound(format!("job {job_id}"))),
         }
@@ -786,9 +1222,10 @@ impl ApiStore for SqliteStore {
         let bs = retry.backoff_strategy.to_string();
         let bb = retry.backoff_base_secs as i64;
         let bc = retry.backoff_cap_secs as i64;
+        let ro = serde_json::to_string(&retry.retry_on).unwrap_or_default();
         sqlx::query!(
-            "INSERT INTO jobs (id, tenant_id, name, schedule_cron, enabled, runner_type, max_concurrency, created_at, misfire_policy, max_attempts, backoff_strategy, backoff_base_secs, backoff_cap_secs) \
-             VALUES (?, ?, ?, ?, 0, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO jobs (id, tenant_id, name, schedule_cron, enabled, runner_type, max_concurrency, created_at, misfire_policy, max_attempts, backoff_strategy, backoff_base_secs, backoff_cap_secs, retry_on) \
+             VALUES (?, ?, ?, ?, 0, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             id,
             tenant_id,
             name,
@@ -800,7 +1237,8 @@ impl ApiStore for SqliteStore {
             ma,
             bs,
             bb,
-            bc
+            bc,
+            ro
         )
         .execute(&self.pool)
         .await
@@ -810,12 +1248,23 @@ impl ApiStore for SqliteStore {
             RunnerConfig::Shell {
                 command,
                 working_dir,
+                code,
+                workspace,
+                secret_delivery,
             } => {
+                let code_json = code
+                    .as_ref()
+                    .map(|c| serde_json::to_string(c).unwrap_or_default());
+                let workspace = workspace.to_string();
+                let secret_delivery = secret_delivery.to_string();
                 sqlx::query!(
-                    "INSERT INTO job_runner_shell (job_id, command, working_dir) VALUES (?, ?, ?)",
+                    "INSERT INTO job_runner_shell (job_id, command, working_dir, code, workspace, secret_delivery) VALUES (?, ?, ?, ?, ?, ?)",
                     id,
                     command,
-                    working_dir
+                    working_dir,
+                    code_json,
+                    workspace,
+                    secret_delivery
                 )
                 .execute(&self.pool)
                 .await
@@ -827,19 +1276,54 @@ impl ApiStore for SqliteStore {
                 headers,
                 body,
                 timeout_sec,
+                tls,
+                signing,
             } => {
                 let headers_json = headers
                     .as_ref()
                     .map(|h| serde_json::to_string(h).unwrap_or_default());
                 let timeout = timeout_sec.as_ref().map(|t| *t as i64);
+                let tls_json = tls
+                    .as_ref()
+                    .map(|t| serde_json::to_string(t).unwrap_or_default());
+                let signing_json = signing
+                    .as_ref()
+                    .map(|s| serde_json::to_string(s).unwrap_or_default());
                 sqlx::query!(
-                    "INSERT INTO job_runner_http (job_id, method, url, headers, body, timeout_sec) VALUES (?, ?, ?, ?, ?, ?)",
+                    "INSERT INTO job_runner_http (job_id, method, url, headers, body, timeout_sec, tls, signing) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
                     id,
                     method,
                     url,
                     headers_json,
                     body,
-                    timeout
+                    timeout,
+                    tls_json,
+                    signing_json
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(db)?;
+            }
+            RunnerConfig::Sqlite {
+                path,
+                query,
+                timeout_sec,
+                busy_timeout_sec,
+                output,
+            } => {
+                let timeout = timeout_sec.as_ref().map(|t| *t as i64);
+                let busy_timeout = busy_timeout_sec.as_ref().map(|t| *t as i64);
+                let output_json = output
+                    .as_ref()
+                    .map(|o| serde_json::to_string(o).unwrap_or_default());
+                sqlx::query!(
+                    "INSERT INTO job_runner_sqlite (job_id, path, query, timeout_sec, busy_timeout_sec, output) VALUES (?, ?, ?, ?, ?, ?)",
+                    id,
+                    path,
+                    query,
+                    timeout,
+                    busy_timeout,
+                    output_json
                 )
                 .execute(&self.pool)
                 .await
@@ -849,14 +1333,34 @@ impl ApiStore for SqliteStore {
                 module,
                 class_name,
                 timeout_sec,
+                transport,
+                max_runs,
+                secrets,
+                code,
+                workspace,
+                secret_delivery,
             } => {
                 let timeout = timeout_sec.as_ref().map(|t| *t as i64);
+                let transport = transport.to_string();
+                let max_runs = max_runs.map(|n| n as i64);
+                let secrets_json = serde_json::to_string(secrets).unwrap_or_default();
+                let code_json = code
+                    .as_ref()
+                    .map(|c| serde_json::to_string(c).unwrap_or_default());
+                let workspace = workspace.to_string();
+                let secret_delivery = secret_delivery.to_string();
                 sqlx::query!(
-                    "INSERT INTO job_runner_python (job_id, module, class_name, timeout_sec) VALUES (?, ?, ?, ?)",
+                    "INSERT INTO job_runner_python (job_id, module, class_name, timeout_sec, transport, max_runs, secrets, code, workspace, secret_delivery) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                     id,
                     module,
                     class_name,
-                    timeout
+                    timeout,
+                    transport,
+                    max_runs,
+                    secrets_json,
+                    code_json,
+                    workspace,
+                    secret_delivery
                 )
                 .execute(&self.pool)
                 .await
@@ -866,13 +1370,81 @@ impl ApiStore for SqliteStore {
                 module,
                 function_name,
                 timeout_sec,
+                transport,
+                max_runs,
+                secrets,
+                code,
+                workspace,
+                secret_delivery,
             } => {
                 let timeout = timeout_sec.as_ref().map(|t| *t as i64);
+                let transport = transport.to_string();
+                let max_runs = max_runs.map(|n| n as i64);
+                let secrets_json = serde_json::to_string(secrets).unwrap_or_default();
+                let code_json = code
+                    .as_ref()
+                    .map(|c| serde_json::to_string(c).unwrap_or_default());
+                let workspace = workspace.to_string();
+                let secret_delivery = secret_delivery.to_string();
                 sqlx::query!(
-                    "INSERT INTO job_runner_node (job_id, module, function_name, timeout_sec) VALUES (?, ?, ?, ?)",
+                    "INSERT INTO job_runner_node (job_id, module, function_name, timeout_sec, transport, max_runs, secrets, code, workspace, secret_delivery) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                     id,
                     module,
                     function_name,
+                    timeout,
+                    transport,
+                    max_runs,
+                    secrets_json,
+                    code_json,
+                    workspace,
+                    secret_delivery
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(db)?;
+            }
+            RunnerConfig::Wasm {
+                module,
+                timeout_sec,
+                fuel,
+                max_memory_mb,
+                preopens,
+                args,
+            } => {
+                let module_path = module.path();
+                let module_sha256 = module.sha256();
+                let timeout = timeout_sec.map(|t| t as i64);
+                let fuel = fuel.map(|f| f as i64);
+                let max_memory = max_memory_mb.map(|m| m as i64);
+                let preopens_json = serde_json::to_string(preopens).unwrap_or_default();
+                let args_json = serde_json::to_string(args).unwrap_or_default();
+                sqlx::query!(
+                    "INSERT INTO job_runner_wasm (job_id, module_path, module_sha256, timeout_sec, fuel, max_memory_mb, preopens, args) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                    id,
+                    module_path,
+                    module_sha256,
+                    timeout,
+                    fuel,
+                    max_memory,
+                    preopens_json,
+                    args_json
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(db)?;
+            }
+            RunnerConfig::Plugin {
+                plugin,
+                config,
+                timeout_sec,
+            } => {
+                let config_json = config.to_string();
+                let timeout = timeout_sec.map(|t| t as i64);
+                sqlx::query!(
+                    "INSERT INTO job_runner_plugin (job_id, plugin, config, timeout_sec) VALUES (?, ?, ?, ?)",
+                    id,
+                    plugin,
+                    config_json,
                     timeout
                 )
                 .execute(&self.pool)
@@ -901,6 +1473,7 @@ impl ApiStore for SqliteStore {
             backoff_strategy: retry.backoff_strategy,
             backoff_base_secs: retry.backoff_base_secs,
             backoff_cap_secs: retry.backoff_cap_secs,
+            retry_on: retry.retry_on,
         })
     }
 
@@ -912,18 +1485,38 @@ impl ApiStore for SqliteStore {
                       j.misfire_policy AS "misfire_policy!",
                       j.max_attempts AS "max_attempts!: i64", j.backoff_strategy AS "backoff_strategy!",
                       j.backoff_base_secs AS "backoff_base_secs!: i64", j.backoff_cap_secs AS "backoff_cap_secs!: i64",
-                      s.command AS "command?", s.working_dir,
+                      j.retry_on AS "retry_on!",
+                      s.command AS "command?", s.working_dir, s.code AS "shell_code?", s.workspace AS "shell_workspace?", s.secret_delivery AS "shell_secret_delivery?",
                       h.method AS "http_method?", h.url AS "http_url?",
                       h.headers AS "http_headers?", h.body AS "http_body?",
                       h.timeout_sec AS "http_timeout_sec?: i64",
+                      h.tls AS "http_tls?",
+                      h.signing AS "http_signing?",
+                      sq.path AS "sq_path?", sq.query AS "sq_query?",
+                      sq.timeout_sec AS "sq_timeout_sec?: i64",
+                      sq.busy_timeout_sec AS "sq_busy_timeout_sec?: i64",
+                      sq.output AS "sq_output?",
                       py.module AS "py_module?", py.class_name AS "py_class_name?",
                       py.timeout_sec AS "py_timeout_sec?: i64",
+                      py.transport AS "py_transport?", py.max_runs AS "py_max_runs?: i64",
+                      py.secrets AS "py_secrets?", py.code AS "py_code?", py.workspace AS "py_workspace?", py.secret_delivery AS "py_secret_delivery?",
                       nd.module AS "node_module?", nd.function_name AS "node_function_name?",
-                      nd.timeout_sec AS "node_timeout_sec?: i64"
+                      nd.timeout_sec AS "node_timeout_sec?: i64",
+                      nd.transport AS "node_transport?", nd.max_runs AS "node_max_runs?: i64",
+                      nd.secrets AS "node_secrets?", nd.code AS "node_code?", nd.workspace AS "node_workspace?", nd.secret_delivery AS "node_secret_delivery?",
+                      wa.module_path AS "wasm_module_path?", wa.module_sha256 AS "wasm_module_sha256?",
+                      wa.timeout_sec AS "wasm_timeout_sec?: i64", wa.fuel AS "wasm_fuel?: i64",
+                      wa.max_memory_mb AS "wasm_max_memory_mb?: i64",
+                      wa.preopens AS "wasm_preopens?", wa.args AS "wasm_args?",
+                      pl.plugin AS "plugin_name?", pl.config AS "plugin_config?",
+                      pl.timeout_sec AS "plugin_timeout_sec?: i64"
                FROM jobs j LEFT JOIN job_runner_shell s ON s.job_id = j.id
                LEFT JOIN job_runner_http h ON h.job_id = j.id
+               LEFT JOIN job_runner_sqlite sq ON sq.job_id = j.id
                LEFT JOIN job_runner_python py ON py.job_id = j.id
                LEFT JOIN job_runner_node nd ON nd.job_id = j.id
+               LEFT JOIN job_runner_wasm wa ON wa.job_id = j.id
+               LEFT JOIN job_runner_plugin pl ON pl.job_id = j.id
                WHERE j.deleted_at IS NULL AND (?1 IS NULL OR j.tenant_id = ?1)"#,
             scope
         )
@@ -942,21 +1535,54 @@ impl ApiStore for SqliteStore {
                     r.misfire_policy,
                     r.command,
                     r.working_dir,
+                    r.shell_code,
+                    r.shell_workspace,
+                    r.shell_secret_delivery,
                     r.http_method,
                     r.http_url,
                     r.http_headers,
                     r.http_body,
                     r.http_timeout_sec,
+                    r.http_tls,
+                    r.http_signing,
+                    r.sq_path,
+                    r.sq_query,
+                    r.sq_timeout_sec,
+                    r.sq_busy_timeout_sec,
+                    r.sq_output,
                     r.py_module,
                     r.py_class_name,
                     r.py_timeout_sec,
+                    r.py_transport,
+                    r.py_max_runs,
+                    r.py_secrets,
+                    r.py_code,
+                    r.py_workspace,
+                    r.py_secret_delivery,
                     r.node_module,
                     r.node_function_name,
                     r.node_timeout_sec,
+                    r.node_transport,
+                    r.node_max_runs,
+                    r.node_secrets,
+                    r.node_code,
+                    r.node_workspace,
+                    r.node_secret_delivery,
+                    r.wasm_module_path,
+                    r.wasm_module_sha256,
+                    r.wasm_timeout_sec,
+                    r.wasm_fuel,
+                    r.wasm_max_memory_mb,
+                    r.wasm_preopens,
+                    r.wasm_args,
+                    r.plugin_name,
+                    r.plugin_config,
+                    r.plugin_timeout_sec,
                     r.max_attempts,
                     r.backoff_strategy,
                     r.backoff_base_secs,
                     r.backoff_cap_secs,
+                    r.retry_on,
                 )
             })
             .collect()
@@ -978,7 +1604,9 @@ impl ApiStore for SqliteStore {
                       worker_id AS "worker_id?: Uuid", exit_code, attempt AS "attempt!: i64",
                       started_at AS "started_at?: DateTime<Utc>",
                       finished_at AS "finished_at?: DateTime<Utc>", result_status,
-                      result, result_media_type, error, error_media_type
+                      result, result_media_type, error, error_media_type, params,
+                      progress, progress_message,
+                      last_heartbeat AS "last_heartbeat?: DateTime<Utc>", code_commit
                FROM job_runs
                WHERE (?1 IS NULL OR job_id = ?1)
                  AND (?2 IS NULL OR worker_id = ?2)
@@ -1014,6 +1642,11 @@ impl ApiStore for SqliteStore {
                     r.result_media_type,
                     r.error,
                     r.error_media_type,
+                    r.params,
+                    r.progress,
+                    r.progress_message,
+                    r.last_heartbeat,
+                    r.code_commit,
                 )
             })
             .collect()
@@ -1026,7 +1659,9 @@ impl ApiStore for SqliteStore {
                       worker_id AS "worker_id?: Uuid", exit_code, attempt AS "attempt!: i64",
                       started_at AS "started_at?: DateTime<Utc>",
                       finished_at AS "finished_at?: DateTime<Utc>", result_status,
-                      result, result_media_type, error, error_media_type
+                      result, result_media_type, error, error_media_type, params,
+                      progress, progress_message,
+                      last_heartbeat AS "last_heartbeat?: DateTime<Utc>", code_commit
                FROM job_runs
                WHERE id = ?1
                  AND (?2 IS NULL OR job_id IN (SELECT id FROM jobs WHERE tenant_id = ?2))"#,
@@ -1052,6 +1687,11 @@ impl ApiStore for SqliteStore {
                 r.result_media_type,
                 r.error,
                 r.error_media_type,
+                r.params,
+                r.progress,
+                r.progress_message,
+                r.last_heartbeat,
+                r.code_commit,
             )?)),
             None => Ok(None),
         }
@@ -1090,10 +1730,13 @@ impl ApiStore for SqliteStore {
         let cron_val = schedule_cron.flatten();
         let max_concurrency = max_concurrency.map(|v| v as i64);
         let misfire_policy = misfire_policy.map(|p| p.to_string());
-        let max_attempts = retry.map(|r| r.max_attempts as i64);
-        let backoff_strategy = retry.map(|r| r.backoff_strategy.to_string());
-        let backoff_base_secs = retry.map(|r| r.backoff_base_secs as i64);
-        let backoff_cap_secs = retry.map(|r| r.backoff_cap_secs as i64);
+        let max_attempts = retry.as_ref().map(|r| r.max_attempts as i64);
+        let backoff_strategy = retry.as_ref().map(|r| r.backoff_strategy.to_string());
+        let backoff_base_secs = retry.as_ref().map(|r| r.backoff_base_secs as i64);
+        let backoff_cap_secs = retry.as_ref().map(|r| r.backoff_cap_secs as i64);
+        let retry_on = retry
+            .as_ref()
+            .map(|r| serde_json::to_string(&r.retry_on).unwrap_or_default());
 
         sqlx::query!(
             "UPDATE jobs SET
@@ -1104,7 +1747,8 @@ impl ApiStore for SqliteStore {
                 max_attempts = COALESCE(?, max_attempts),
                 backoff_strategy = COALESCE(?, backoff_strategy),
                 backoff_base_secs = COALESCE(?, backoff_base_secs),
-                backoff_cap_secs = COALESCE(?, backoff_cap_secs)
+                backoff_cap_secs = COALESCE(?, backoff_cap_secs),
+                retry_on = COALESCE(?, retry_on)
              WHERE id = ? AND deleted_at IS NULL",
             name,
             cron_set,
@@ -1115,6 +1759,7 @@ impl ApiStore for SqliteStore {
             backoff_strategy,
             backoff_base_secs,
             backoff_cap_secs,
+            retry_on,
             job_id
         )
         .execute(&self.pool)
@@ -1126,17 +1771,212 @@ impl ApiStore for SqliteStore {
                 RunnerConfig::Shell {
                     command,
                     working_dir,
+                    code,
+                    workspace,
+                    secret_delivery,
                 } => {
+                    let code_json = code
+                        .as_ref()
+                        .map(|c| serde_json::to_string(c).unwrap_or_default());
+                    let workspace = workspace.to_string();
+                    let secret_delivery = secret_delivery.to_string();
                     sqlx::query!("UPDATE jobs SET runner_type = 'shell' WHERE id = ?", job_id)
                         .execute(&self.pool)
                         .await
                         .map_err(db)?;
                     sqlx::query!(
-                        "INSERT INTO job_runner_shell (job_id, command, working_dir) VALUES (?, ?, ?) \
-                         ON CONFLICT(job_id) DO UPDATE SET command = excluded.command, working_dir = excluded.working_dir",
+                        "INSERT INTO job_runner_shell (job_id, command, working_dir, code, workspace, secret_delivery) VALUES (?, ?, ?, ?, ?, ?) \
+                         ON CONFLICT(job_id) DO UPDATE SET command = excluded.command, working_dir = excluded.working_dir, \
+                         code = excluded.code, workspace = excluded.workspace, \
+                         secret_delivery = excluded.secret_delivery",
                         job_id,
                         command,
-                        working_dir
+                        working_dir,
+                        code_json,
+                        workspace,
+                        secret_delivery
+                    )
+                    .execute(&self.pool)
+                    .await
+                    .map_err(db)?;
+                }
+                RunnerConfig::Sqlite {
+                    path,
+                    query,
+                    timeout_sec,
+                    busy_timeout_sec,
+                    output,
+                } => {
+                    let timeout = timeout_sec.map(|t| t as i64);
+                    let busy_timeout = busy_timeout_sec.map(|t| t as i64);
+                    let output_json = output
+                        .as_ref()
+                        .map(|o| serde_json::to_string(o).unwrap_or_default());
+                    sqlx::query!("UPDATE jobs SET runner_type = 'sqlite' WHERE id = ?", job_id)
+                        .execute(&self.pool)
+                        .await
+                        .map_err(db)?;
+                    sqlx::query!(
+                        "INSERT INTO job_runner_sqlite (job_id, path, query, timeout_sec, busy_timeout_sec, output) VALUES (?, ?, ?, ?, ?, ?) \
+                         ON CONFLICT(job_id) DO UPDATE SET path = excluded.path, query = excluded.query, \
+                         timeout_sec = excluded.timeout_sec, busy_timeout_sec = excluded.busy_timeout_sec, output = excluded.output",
+                        job_id,
+                        path,
+                        query,
+                        timeout,
+                        busy_timeout,
+                        output_json
+                    )
+                    .execute(&self.pool)
+                    .await
+                    .map_err(db)?;
+                }
+                RunnerConfig::Python {
+                    module,
+                    class_name,
+                    timeout_sec,
+                    transport,
+                    max_runs,
+                    secrets,
+                    code,
+                    workspace,
+                    secret_delivery,
+                } => {
+                    let timeout = timeout_sec.map(|t| t as i64);
+                    let transport = transport.to_string();
+                    let max_runs = max_runs.map(|n| n as i64);
+                    let secrets_json = serde_json::to_string(&secrets).unwrap_or_default();
+                    let code_json = code
+                        .as_ref()
+                        .map(|c| serde_json::to_string(c).unwrap_or_default());
+                    let workspace = workspace.to_string();
+                    let secret_delivery = secret_delivery.to_string();
+                    sqlx::query!("UPDATE jobs SET runner_type = 'python' WHERE id = ?", job_id)
+                        .execute(&self.pool)
+                        .await
+                        .map_err(db)?;
+                    sqlx::query!(
+                        "INSERT INTO job_runner_python (job_id, module, class_name, timeout_sec, transport, max_runs, secrets, code, workspace, secret_delivery) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+                         ON CONFLICT(job_id) DO UPDATE SET module = excluded.module, class_name = excluded.class_name, \
+                         timeout_sec = excluded.timeout_sec, transport = excluded.transport, max_runs = excluded.max_runs, \
+                         secrets = excluded.secrets, code = excluded.code, workspace = excluded.workspace, \
+                         secret_delivery = excluded.secret_delivery",
+                        job_id,
+                        module,
+                        class_name,
+                        timeout,
+                        transport,
+                        max_runs,
+                        secrets_json,
+                        code_json,
+                        workspace,
+                        secret_delivery
+                    )
+                    .execute(&self.pool)
+                    .await
+                    .map_err(db)?;
+                }
+                RunnerConfig::Node {
+                    module,
+                    function_name,
+                    timeout_sec,
+                    transport,
+                    max_runs,
+                    secrets,
+                    code,
+                    workspace,
+                    secret_delivery,
+                } => {
+                    let timeout = timeout_sec.map(|t| t as i64);
+                    let transport = transport.to_string();
+                    let max_runs = max_runs.map(|n| n as i64);
+                    let secrets_json = serde_json::to_string(&secrets).unwrap_or_default();
+                    let code_json = code
+                        .as_ref()
+                        .map(|c| serde_json::to_string(c).unwrap_or_default());
+                    let workspace = workspace.to_string();
+                    let secret_delivery = secret_delivery.to_string();
+                    sqlx::query!("UPDATE jobs SET runner_type = 'node' WHERE id = ?", job_id)
+                        .execute(&self.pool)
+                        .await
+                        .map_err(db)?;
+                    sqlx::query!(
+                        "INSERT INTO job_runner_node (job_id, module, function_name, timeout_sec, transport, max_runs, secrets, code, workspace, secret_delivery) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+                         ON CONFLICT(job_id) DO UPDATE SET module = excluded.module, function_name = excluded.function_name, \
+                         timeout_sec = excluded.timeout_sec, transport = excluded.transport, max_runs = excluded.max_runs, \
+                         secrets = excluded.secrets, code = excluded.code, workspace = excluded.workspace, \
+                         secret_delivery = excluded.secret_delivery",
+                        job_id,
+                        module,
+                        function_name,
+                        timeout,
+                        transport,
+                        max_runs,
+                        secrets_json,
+                        code_json,
+                        workspace,
+                        secret_delivery
+                    )
+                    .execute(&self.pool)
+                    .await
+                    .map_err(db)?;
+                }
+                RunnerConfig::Wasm {
+                    module,
+                    timeout_sec,
+                    fuel,
+                    max_memory_mb,
+                    preopens,
+                    args,
+                } => {
+                    let module_path = module.path();
+                    let module_sha256 = module.sha256();
+                    let timeout = timeout_sec.map(|t| t as i64);
+                    let fuel = fuel.map(|f| f as i64);
+                    let max_memory = max_memory_mb.map(|m| m as i64);
+                    let preopens_json = serde_json::to_string(&preopens).unwrap_or_default();
+                    let args_json = serde_json::to_string(&args).unwrap_or_default();
+                    sqlx::query!("UPDATE jobs SET runner_type = 'wasm' WHERE id = ?", job_id)
+                        .execute(&self.pool)
+                        .await
+                        .map_err(db)?;
+                    sqlx::query!(
+                        "INSERT INTO job_runner_wasm (job_id, module_path, module_sha256, timeout_sec, fuel, max_memory_mb, preopens, args) VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+                         ON CONFLICT(job_id) DO UPDATE SET module_path = excluded.module_path, module_sha256 = excluded.module_sha256, \
+                         timeout_sec = excluded.timeout_sec, fuel = excluded.fuel, max_memory_mb = excluded.max_memory_mb, \
+                         preopens = excluded.preopens, args = excluded.args",
+                        job_id,
+                        module_path,
+                        module_sha256,
+                        timeout,
+                        fuel,
+                        max_memory,
+                        preopens_json,
+                        args_json
+                    )
+                    .execute(&self.pool)
+                    .await
+                    .map_err(db)?;
+                }
+                RunnerConfig::Plugin {
+                    plugin,
+                    config,
+                    timeout_sec,
+                } => {
+                    let config_json = config.to_string();
+                    let timeout = timeout_sec.map(|t| t as i64);
+                    sqlx::query!("UPDATE jobs SET runner_type = 'plugin' WHERE id = ?", job_id)
+                        .execute(&self.pool)
+                        .await
+                        .map_err(db)?;
+                    sqlx::query!(
+                        "INSERT INTO job_runner_plugin (job_id, plugin, config, timeout_sec) VALUES (?, ?, ?, ?) \
+                         ON CONFLICT(job_id) DO UPDATE SET plugin = excluded.plugin, config = excluded.config, \
+                         timeout_sec = excluded.timeout_sec",
+                        job_id,
+                        plugin,
+                        config_json,
+                        timeout
                     )
                     .execute(&self.pool)
                     .await
@@ -1165,14 +2005,27 @@ impl ApiStore for SqliteStore {
         Ok(())
     }
 
-    async fn create_adhoc_run(&self, job_id: Uuid) -> Result<JobRun> {
+    async fn create_adhoc_run(
+        &self,
+        job_id: Uuid,
+        params: HashMap<String, RunParam>,
+    ) -> Result<JobRun> {
         let id = Uuid::new_v4();
         let now = Utc::now();
+        let params_json = if params.is_empty() {
+            None
+        } else {
+            Some(
+                serde_json::to_string(&params)
+                    .map_err(|e| ArbiterError::ExecutionError(e.to_string()))?,
+            )
+        };
         sqlx::query!(
-            "INSERT INTO job_runs (id, job_id, scheduled_for, state) VALUES (?, ?, ?, 'queued')",
+            "INSERT INTO job_runs (id, job_id, scheduled_for, state, params) VALUES (?, ?, ?, 'queued', ?)",
             id,
             job_id,
-            now
+            now,
+            params_json
         )
         .execute(&self.pool)
         .await
@@ -1194,6 +2047,10 @@ impl ApiStore for SqliteStore {
             result_media_type: None,
             error: None,
             error_media_type: None,
+            params,
+            progress: None,
+            last_heartbeat: None,
+            code_commit: None,
         })
     }
 
@@ -1246,24 +2103,36 @@ impl ApiStore for SqliteStore {
         let rows = sqlx::query!(
             r#"SELECT id AS "id!: Uuid", display_name AS "display_name!", hostname AS "hostname!",
                       last_seen AS "last_seen!: DateTime<Utc>", capacity AS "capacity!: i64",
-                      restart_count AS "restart_count!: i64", version AS "version!"
+                      restart_count AS "restart_count!: i64", version AS "version!",
+                      plugins AS "plugins!", disk_usage AS "disk_usage?"
                FROM workers"#
         )
         .fetch_all(&self.pool)
         .await
         .map_err(db)?;
-        Ok(rows
-            .into_iter()
-            .map(|r| WorkerRecord {
-                id: r.id,
-                display_name: r.display_name,
-                hostname: r.hostname,
-                last_seen: r.last_seen,
-                capacity: r.capacity as u32,
-                restart_count: r.restart_count as u32,
-                version: r.version,
+        rows.into_iter()
+            .map(|r| {
+                Ok(WorkerRecord {
+                    id: r.id,
+                    display_name: r.display_name,
+                    hostname: r.hostname,
+                    last_seen: r.last_seen,
+                    capacity: r.capacity as u32,
+                    restart_count: r.restart_count as u32,
+                    version: r.version,
+                    plugins: serde_json::from_str(&r.plugins).map_err(|e| {
+                        ArbiterError::DatabaseError(format!("invalid worker plugins json: {e}"))
+                    })?,
+                    disk_usage: r
+                        .disk_usage
+                        .map(|j| serde_json::from_str::<WorkerDiskUsage>(&j))
+                        .transpose()
+                        .map_err(|e| {
+                            ArbiterError::DatabaseError(format!("invalid worker disk usage json: {e}"))
+                        })?,
+                })
             })
-            .collect())
+            .collect()
     }
 
     async fn get_user_by_username(&self, username: &str) -> Result<User> {
@@ -1460,14 +2329,16 @@ impl SecretStore for SqliteStore {
         let id = Uuid::new_v4();
         let now = Utc::now();
         let ver = kek_version as i64;
+        let mut tx = self.pool.begin().await.map_err(db)?;
         let row = sqlx::query!(
             r#"INSERT INTO secrets (id, tenant_id, name, value_ct, value_nonce, aead_algo, dek_wrapped, kek_version, created_at, updated_at)
                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
                ON CONFLICT(tenant_id, name) DO UPDATE SET
                    value_ct = excluded.value_ct, value_nonce = excluded.value_nonce,
                    aead_algo = excluded.aead_algo, dek_wrapped = excluded.dek_wrapped,
-                   kek_version = excluded.kek_version, updated_at = excluded.updated_at
-               RETURNING id AS "id!: Uuid""#,
+                   kek_version = excluded.kek_version, version = secrets.version + 1,
+                   updated_at = excluded.updated_at
+               RETURNING id AS "id!: Uuid", version AS "version!: i64""#,
             id,
             tenant_id,
             name,
@@ -1479,15 +2350,33 @@ impl SecretStore for SqliteStore {
             now,
             now
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db)?;
+        sqlx::query!(
+            r#"INSERT INTO secret_versions (secret_id, version, value_ct, value_nonce, aead_algo,
+                   dek_wrapped, kek_version, created_at)
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#,
+            row.id,
+            row.version,
+            value_ct,
+            value_nonce,
+            aead_algo,
+            dek_wrapped,
+            ver,
+            now
+        )
+        .execute(&mut *tx)
         .await
         .map_err(db)?;
+        tx.commit().await.map_err(db)?;
         Ok(row.id)
     }
 
     async fn get_secret_by_name(&self, tenant: Uuid, name: &str) -> Result<Option<StoredSecret>> {
         let row = sqlx::query!(
-            r#"SELECT id AS "id!: Uuid", name AS "name!", value_ct AS "value_ct!: Vec<u8>",
+            r#"SELECT id AS "id!: Uuid", name AS "name!", version AS "version!: i64",
+                      value_ct AS "value_ct!: Vec<u8>",
                       value_nonce AS "value_nonce!: Vec<u8>", aead_algo AS "aead_algo!",
                       dek_wrapped AS "dek_wrapped!: Vec<u8>", kek_version AS "kek_version!: i64",
                       created_at AS "created_at!: DateTime<Utc>", updated_at AS "updated_at!: DateTime<Utc>"
@@ -1501,6 +2390,7 @@ impl SecretStore for SqliteStore {
         Ok(row.map(|r| StoredSecret {
             id: r.id,
             name: r.name,
+            version: r.version as u32,
             value_ct: r.value_ct,
             value_nonce: r.value_nonce,
             aead_algo: r.aead_algo,
@@ -1513,7 +2403,8 @@ impl SecretStore for SqliteStore {
 
     async fn get_secret(&self, id: Uuid, scope: Option<Uuid>) -> Result<Option<StoredSecret>> {
         let row = sqlx::query!(
-            r#"SELECT id AS "id!: Uuid", name AS "name!", value_ct AS "value_ct!: Vec<u8>",
+            r#"SELECT id AS "id!: Uuid", name AS "name!", version AS "version!: i64",
+                      value_ct AS "value_ct!: Vec<u8>",
                       value_nonce AS "value_nonce!: Vec<u8>", aead_algo AS "aead_algo!",
                       dek_wrapped AS "dek_wrapped!: Vec<u8>", kek_version AS "kek_version!: i64",
                       created_at AS "created_at!: DateTime<Utc>", updated_at AS "updated_at!: DateTime<Utc>"
@@ -1527,6 +2418,7 @@ impl SecretStore for SqliteStore {
         Ok(row.map(|r| StoredSecret {
             id: r.id,
             name: r.name,
+            version: r.version as u32,
             value_ct: r.value_ct,
             value_nonce: r.value_nonce,
             aead_algo: r.aead_algo,
@@ -1539,9 +2431,21 @@ impl SecretStore for SqliteStore {
 
     async fn list_secret_names(&self, scope: Option<Uuid>) -> Result<Vec<SecretMeta>> {
         let rows = sqlx::query!(
-            r#"SELECT id AS "id!: Uuid", name AS "name!", kek_version AS "kek_version!: i64",
-                      created_at AS "created_at!: DateTime<Utc>", updated_at AS "updated_at!: DateTime<Utc>"
-               FROM secrets WHERE (?1 IS NULL OR tenant_id = ?1) ORDER BY name"#,
+            r#"SELECT s.id AS "id!: Uuid", s.tenant_id AS "tenant_id!: Uuid", s.name AS "name!",
+                      s.version AS "version!: i64", s.kek_version AS "kek_version!: i64",
+                      MIN(s.kek_version, COALESCE(
+                          (SELECT MIN(v.kek_version) FROM secret_versions v
+                           WHERE v.secret_id = s.id), s.kek_version)) AS "oldest_kek_version!: i64",
+                      COALESCE(
+                          (SELECT v.created_at FROM secret_versions v
+                           WHERE v.secret_id = s.id AND v.version = s.version),
+                          s.updated_at) AS "value_set_at!: DateTime<Utc>",
+                      s.expires_at AS "expires_at?: DateTime<Utc>",
+                      s.rotate_every_secs AS "rotate_every_secs?: i64",
+                      s.last_resolved_at AS "last_resolved_at?: DateTime<Utc>",
+                      s.last_resolved_run AS "last_resolved_run?: Uuid",
+                      s.created_at AS "created_at!: DateTime<Utc>", s.updated_at AS "updated_at!: DateTime<Utc>"
+               FROM secrets s WHERE (?1 IS NULL OR s.tenant_id = ?1) ORDER BY s.name"#,
             scope
         )
         .fetch_all(&self.pool)
@@ -1551,39 +2455,328 @@ impl SecretStore for SqliteStore {
             .into_iter()
             .map(|r| SecretMeta {
                 id: r.id,
+                tenant_id: r.tenant_id,
                 name: r.name,
+                version: r.version as u32,
                 kek_version: r.kek_version as u32,
+                oldest_kek_version: r.oldest_kek_version as u32,
+                value_set_at: r.value_set_at,
+                expires_at: r.expires_at,
+                rotate_every_secs: r.rotate_every_secs.map(|v| v as u64),
+                last_resolved_at: r.last_resolved_at,
+                last_resolved_run: r.last_resolved_run,
                 created_at: r.created_at,
                 updated_at: r.updated_at,
             })
             .collect())
     }
 
-    async fn delete_secret(&self, id: Uuid) -> Result<()> {
-        sqlx::query!("DELETE FROM secrets WHERE id = ?", id)
-            .execute(&self.pool)
-            .await
-            .map_err(db)?;
-        Ok(())
+    async fn get_secret_meta(&self, id: Uuid, scope: Option<Uuid>) -> Result<Option<SecretMeta>> {
+        let row = sqlx::query!(
+            r#"SELECT s.id AS "id!: Uuid", s.tenant_id AS "tenant_id!: Uuid", s.name AS "name!",
+                      s.version AS "version!: i64", s.kek_version AS "kek_version!: i64",
+                      MIN(s.kek_version, COALESCE(
+                          (SELECT MIN(v.kek_version) FROM secret_versions v
+                           WHERE v.secret_id = s.id), s.kek_version)) AS "oldest_kek_version!: i64",
+                      COALESCE(
+                          (SELECT v.created_at FROM secret_versions v
+                           WHERE v.secret_id = s.id AND v.version = s.version),
+                          s.updated_at) AS "value_set_at!: DateTime<Utc>",
+                      s.expires_at AS "expires_at?: DateTime<Utc>",
+                      s.rotate_every_secs AS "rotate_every_secs?: i64",
+                      s.last_resolved_at AS "last_resolved_at?: DateTime<Utc>",
+                      s.last_resolved_run AS "last_resolved_run?: Uuid",
+                      s.created_at AS "created_at!: DateTime<Utc>", s.updated_at AS "updated_at!: DateTime<Utc>"
+               FROM secrets s WHERE s.id = ?1 AND (?2 IS NULL OR s.tenant_id = ?2)"#,
+            id,
+            scope
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(row.map(|r| SecretMeta {
+            id: r.id,
+            tenant_id: r.tenant_id,
+            name: r.name,
+            version: r.version as u32,
+            kek_version: r.kek_version as u32,
+            oldest_kek_version: r.oldest_kek_version as u32,
+            value_set_at: r.value_set_at,
+            expires_at: r.expires_at,
+            rotate_every_secs: r.rotate_every_secs.map(|v| v as u64),
+            last_resolved_at: r.last_resolved_at,
+            last_resolved_run: r.last_resolved_run,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }))
     }
 
-    async fn insert_kek_version(&self, version: u32, state: &str) -> Result<()> {
-        let now = Utc::now();
+    async fn get_secret_version(&self, id: Uuid, version: u32) -> Result<Option<StoredSecret>> {
         let v = version as i64;
-        sqlx::query!(
-            "INSERT INTO kek_versions (version, state, created_at) VALUES (?, ?, ?)",
-            v,
-            state,
-            now
+        let row = sqlx::query!(
+            r#"SELECT s.id AS "id!: Uuid", s.name AS "name!", v.version AS "version!: i64",
+                      v.value_ct AS "value_ct!: Vec<u8>", v.value_nonce AS "value_nonce!: Vec<u8>",
+                      v.aead_algo AS "aead_algo!", v.dek_wrapped AS "dek_wrapped!: Vec<u8>",
+                      v.kek_version AS "kek_version!: i64",
+                      s.created_at AS "created_at!: DateTime<Utc>", v.created_at AS "updated_at!: DateTime<Utc>"
+               FROM secret_versions v JOIN secrets s ON s.id = v.secret_id
+               WHERE v.secret_id = ? AND v.version = ?"#,
+            id,
+            v
         )
-        .execute(&self.pool)
+        .fetch_optional(&self.pool)
         .await
         .map_err(db)?;
-        Ok(())
-    }
-
-    async fn list_kek_versions(&self) -> Result<Vec<StoredKekVersion>> {
-        let rows = sqlx::query!(
+        Ok(row.map(|r| StoredSecret {
+            id: r.id,
+            name: r.name,
+            version: r.version as u32,
+            value_ct: r.value_ct,
+            value_nonce: r.value_nonce,
+            aead_algo: r.aead_algo,
+            dek_wrapped: r.dek_wrapped,
+            kek_version: r.kek_version as u32,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }))
+    }
+
+    async fn list_secret_versions(&self, id: Uuid) -> Result<Vec<SecretVersionMeta>> {
+        let rows = sqlx::query!(
+            r#"SELECT version AS "version!: i64", kek_version AS "kek_version!: i64",
+                      restored_from AS "restored_from?: i64", created_at AS "created_at!: DateTime<Utc>"
+               FROM secret_versions WHERE secret_id = ? ORDER BY version DESC"#,
+            id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(rows
+            .into_iter()
+            .map(|r| SecretVersionMeta {
+                version: r.version as u32,
+                kek_version: r.kek_version as u32,
+                restored_from: r.restored_from.map(|v| v as u32),
+                created_at: r.created_at,
+            })
+            .collect())
+    }
+
+    async fn restore_secret_version(&self, id: Uuid, version: u32) -> Result<Option<u32>> {
+        let v = version as i64;
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        let Some(old) = sqlx::query!(
+            r#"SELECT value_ct AS "value_ct!: Vec<u8>", value_nonce AS "value_nonce!: Vec<u8>",
+                      aead_algo AS "aead_algo!", dek_wrapped AS "dek_wrapped!: Vec<u8>",
+                      kek_version AS "kek_version!: i64"
+               FROM secret_versions WHERE secret_id = ? AND version = ?"#,
+            id,
+            v
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db)?
+        else {
+            return Ok(None);
+        };
+        let row = sqlx::query!(
+            r#"UPDATE secrets SET value_ct = ?2, value_nonce = ?3, aead_algo = ?4,
+                   dek_wrapped = ?5, kek_version = ?6, version = version + 1, updated_at = ?7
+               WHERE id = ?1
+               RETURNING version AS "version!: i64""#,
+            id,
+            old.value_ct,
+            old.value_nonce,
+            old.aead_algo,
+            old.dek_wrapped,
+            old.kek_version,
+            now
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db)?;
+        sqlx::query!(
+            r#"INSERT INTO secret_versions (secret_id, version, value_ct, value_nonce, aead_algo,
+                   dek_wrapped, kek_version, restored_from, created_at)
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+            id,
+            row.version,
+            old.value_ct,
+            old.value_nonce,
+            old.aead_algo,
+            old.dek_wrapped,
+            old.kek_version,
+            v,
+            now
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db)?;
+        tx.commit().await.map_err(db)?;
+        Ok(Some(row.version as u32))
+    }
+
+    async fn prune_secret_versions(&self, id: Uuid, keep: u32) -> Result<u32> {
+        let keep = keep.max(1) as i64;
+        let res = sqlx::query!(
+            r#"DELETE FROM secret_versions
+               WHERE secret_id = ?1
+                 AND version <= (SELECT version FROM secrets WHERE id = ?1) - ?2"#,
+            id,
+            keep
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(res.rows_affected() as u32)
+    }
+
+    async fn delete_secret(&self, id: Uuid) -> Result<()> {
+        // No foreign keys enforced here, so the version history goes explicitly.
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        sqlx::query!("DELETE FROM secret_versions WHERE secret_id = ?", id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        sqlx::query!("DELETE FROM secrets WHERE id = ?", id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        tx.commit().await.map_err(db)?;
+        Ok(())
+    }
+
+    async fn set_secret_policy(
+        &self,
+        id: Uuid,
+        expires_at: Option<DateTime<Utc>>,
+        rotate_every_secs: Option<u64>,
+    ) -> Result<()> {
+        let every = rotate_every_secs.map(|v| v.min(i64::MAX as u64) as i64);
+        sqlx::query!(
+            "UPDATE secrets SET expires_at = ?2, rotate_every_secs = ?3 WHERE id = ?1",
+            id,
+            expires_at,
+            every
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(())
+    }
+
+    async fn record_secret_use(&self, tenant: Uuid, name: &str, run_id: Uuid) -> Result<()> {
+        let now = Utc::now();
+        sqlx::query!(
+            r#"UPDATE secrets SET last_resolved_at = ?3, last_resolved_run = ?4
+               WHERE tenant_id = ?1 AND name = ?2"#,
+            tenant,
+            name,
+            now,
+            run_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(())
+    }
+
+    async fn append_secret_audit(&self, event: &SecretAuditEvent) -> Result<()> {
+        let version = event.version.map(|v| v as i64);
+        let action = event.action.to_string();
+        let actor_kind = event.actor.kind();
+        let actor_id = event.actor.id();
+        let job_id = event.actor.job_id();
+        let outcome = event.outcome.to_string();
+        sqlx::query!(
+            r#"INSERT INTO secret_audit (id, secret_id, tenant_id, secret_name, version, action,
+                                         actor_kind, actor_id, job_id, outcome, detail, at)
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+            event.id,
+            event.secret_id,
+            event.tenant_id,
+            event.secret_name,
+            version,
+            action,
+            actor_kind,
+            actor_id,
+            job_id,
+            outcome,
+            event.detail,
+            event.at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(())
+    }
+
+    async fn list_secret_audit(
+        &self,
+        secret_id: Uuid,
+        before: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Result<Vec<SecretAuditEvent>> {
+        let limit = limit as i64;
+        let rows = sqlx::query!(
+            r#"SELECT id AS "id!: Uuid", secret_id AS "secret_id?: Uuid", tenant_id AS "tenant_id!: Uuid",
+                      secret_name AS "secret_name!", version AS "version?: i64", action AS "action!",
+                      actor_kind AS "actor_kind!", actor_id AS "actor_id!: Uuid", job_id AS "job_id?: Uuid",
+                      outcome AS "outcome!", detail AS "detail?", at AS "at!: DateTime<Utc>"
+               FROM secret_audit
+               WHERE secret_id = ?1 AND (?2 IS NULL OR at < ?2)
+               ORDER BY at DESC, id DESC LIMIT ?3"#,
+            secret_id,
+            before,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db)?;
+        rows.into_iter()
+            .map(|r| {
+                Ok(SecretAuditEvent {
+                    id: r.id,
+                    secret_id: r.secret_id,
+                    tenant_id: r.tenant_id,
+                    secret_name: r.secret_name,
+                    version: r.version.map(|v| v as u32),
+                    action: r.action.parse()?,
+                    actor: SecretActor::from_parts(&r.actor_kind, r.actor_id, r.job_id)?,
+                    outcome: r.outcome.parse()?,
+                    detail: r.detail,
+                    at: r.at,
+                })
+            })
+            .collect()
+    }
+
+    async fn prune_secret_audit(&self, older_than: DateTime<Utc>) -> Result<u64> {
+        let res = sqlx::query!("DELETE FROM secret_audit WHERE at < ?", older_than)
+            .execute(&self.pool)
+            .await
+            .map_err(db)?;
+        Ok(res.rows_affected())
+    }
+
+    async fn insert_kek_version(&self, version: u32, state: &str) -> Result<()> {
+        let now = Utc::now();
+        let v = version as i64;
+        sqlx::query!(
+            "INSERT INTO kek_versions (version, state, created_at) VALUES (?, ?, ?)",
+            v,
+            state,
+            now
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(())
+    }
+
+    async fn list_kek_versions(&self) -> Result<Vec<StoredKekVersion>> {
+        let rows = sqlx::query!(
             r#"SELECT version AS "version!: i64", state AS "state!",
                       created_at AS "created_at!: DateTime<Utc>", retired_at AS "retired_at?: DateTime<Utc>"
                FROM kek_versions ORDER BY version"#
@@ -1620,19 +2813,41 @@ impl SecretStore for SqliteStore {
         Ok(())
     }
 
-    async fn rewrap_secret(&self, id: Uuid, dek_wrapped: &[u8], kek_version: u32) -> Result<()> {
+    async fn rewrap_secret(
+        &self,
+        id: Uuid,
+        version: u32,
+        dek_wrapped: &[u8],
+        kek_version: u32,
+    ) -> Result<()> {
+        let v = version as i64;
         let kv = kek_version as i64;
         let now = Utc::now();
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        sqlx::query!(
+            r#"UPDATE secret_versions SET dek_wrapped = ?3, kek_version = ?4
+               WHERE secret_id = ?1 AND version = ?2"#,
+            id,
+            v,
+            dek_wrapped,
+            kv
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db)?;
         sqlx::query!(
-            "UPDATE secrets SET dek_wrapped = ?2, kek_version = ?3, updated_at = ?4 WHERE id = ?1",
+            r#"UPDATE secrets SET dek_wrapped = ?3, kek_version = ?4, updated_at = ?5
+               WHERE id = ?1 AND version = ?2"#,
             id,
+            v,
             dek_wrapped,
             kv,
             now
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await
         .map_err(db)?;
+        tx.commit().await.map_err(db)?;
         Ok(())
     }
 
@@ -1831,15 +3046,17 @@ impl ConfigStore for SqliteStore {
         username: &str,
         password_secret: &str,
         database: &str,
+        pool_size: Option<u32>,
     ) -> Result<SharedDbConfig> {
         let id = Uuid::new_v4();
         let port_i = port as i64;
+        let pool_i = pool_size.map(|n| n as i64);
         match engine {
             DbEngine::PgSql => {
                 sqlx::query!(
-                    r#"INSERT INTO pgsql_configs (id, name, host, port, username, password_secret, database, tenant_id)
-                       VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#,
-                    id, name, host, port_i, username, password_secret, database, tenant_id
+                    r#"INSERT INTO pgsql_configs (id, name, host, port, username, password_secret, database, tenant_id, pool_size)
+                       VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+                    id, name, host, port_i, username, password_secret, database, tenant_id, pool_i
                 )
                 .execute(&self.pool)
                 .await
@@ -1847,9 +3064,9 @@ impl ConfigStore for SqliteStore {
             }
             DbEngine::MySql => {
                 sqlx::query!(
-                    r#"INSERT INTO mysql_configs (id, name, host, port, username, password_secret, database, tenant_id)
-                       VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#,
-                    id, name, host, port_i, username, password_secret, database, tenant_id
+                    r#"INSERT INTO mysql_configs (id, name, host, port, username, password_secret, database, tenant_id, pool_size)
+                       VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+                    id, name, host, port_i, username, password_secret, database, tenant_id, pool_i
                 )
                 .execute(&self.pool)
                 .await
@@ -1866,6 +3083,7 @@ impl ConfigStore for SqliteStore {
             password_secret: password_secret.to_string(),
             database: database.to_string(),
             tenant_id,
+            pool_size,
         })
     }
 
@@ -1874,10 +3092,10 @@ impl ConfigStore for SqliteStore {
             r#"
             SELECT id AS "id!: Uuid", 'pgsql' AS "engine!: String", name AS "name!", host AS "host!",
                    port AS "port!: i64", username AS "username!", password_secret AS "password_secret!",
-                   database AS "database!", tenant_id AS "tenant_id!: Uuid"
+                   database AS "database!", tenant_id AS "tenant_id!: Uuid", pool_size AS "pool_size?: i64"
             FROM pgsql_configs WHERE id = ?1 AND deleted_at IS NULL AND (?2 IS NULL OR tenant_id = ?2)
             UNION ALL
-            SELECT id, 'mysql', name, host, port, username, password_secret, database, tenant_id
+            SELECT id, 'mysql', name, host, port, username, password_secret, database, tenant_id, pool_size
             FROM mysql_configs WHERE id = ?1 AND deleted_at IS NULL AND (?2 IS NULL OR tenant_id = ?2)
             "#,
             id,
@@ -1897,6 +3115,7 @@ impl ConfigStore for SqliteStore {
                 password_secret: r.password_secret,
                 database: r.database,
                 tenant_id: r.tenant_id,
+                pool_size: r.pool_size.map(|n| n as u32),
             })
         })
         .transpose()
@@ -1907,10 +3126,10 @@ impl ConfigStore for SqliteStore {
             r#"
             SELECT id AS "id!: Uuid", 'pgsql' AS "engine!: String", name AS "name!", host AS "host!",
                    port AS "port!: i64", username AS "username!", password_secret AS "password_secret!",
-                   database AS "database!", tenant_id AS "tenant_id!: Uuid"
+                   database AS "database!", tenant_id AS "tenant_id!: Uuid", pool_size AS "pool_size?: i64"
             FROM pgsql_configs WHERE deleted_at IS NULL AND (?1 IS NULL OR tenant_id = ?1)
             UNION ALL
-            SELECT id, 'mysql', name, host, port, username, password_secret, database, tenant_id
+            SELECT id, 'mysql', name, host, port, username, password_secret, database, tenant_id, pool_size
             FROM mysql_configs WHERE deleted_at IS NULL AND (?1 IS NULL OR tenant_id = ?1)
             ORDER BY 3
             "#,
@@ -1931,6 +3150,7 @@ impl ConfigStore for SqliteStore {
                     password_secret: r.password_secret,
                     database: r.database,
                     tenant_id: r.tenant_id,
+                    pool_size: r.pool_size.map(|n| n as u32),
                 })
             })
             .collect()
@@ -1945,6 +3165,7 @@ impl ConfigStore for SqliteStore {
         username: Option<&str>,
         password_secret: Option<&str>,
         database: Option<&str>,
+        pool_size: Option<u32>,
     ) -> Result<SharedDbConfig> {
         let engine = self
             .get_db_config(id, None)
@@ -1952,6 +3173,7 @@ impl ConfigStore for SqliteStore {
             .ok_or_else(|| ArbiterError::NotFound(format!("db config {id}")))?
             .engine;
         let port_i = port.map(|p| p as i64);
+        let pool_i = pool_size.map(|n| n as i64);
         match engine {
             DbEngine::PgSql => {
                 sqlx::query!(
@@ -1959,9 +3181,10 @@ impl ConfigStore for SqliteStore {
                         name = COALESCE(?, name), host = COALESCE(?, host),
                         port = COALESCE(?, port), username = COALESCE(?, username),
                         password_secret = COALESCE(?, password_secret),
-                        database = COALESCE(?, database)
+                        database = COALESCE(?, database),
+                        pool_size = COALESCE(?, pool_size)
                        WHERE id = ? AND deleted_at IS NULL"#,
-                    name, host, port_i, username, password_secret, database, id
+                    name, host, port_i, username, password_secret, database, pool_i, id
                 )
                 .execute(&self.pool)
                 .await
@@ -1973,9 +3196,10 @@ impl ConfigStore for SqliteStore {
                         name = COALESCE(?, name), host = COALESCE(?, host),
                         port = COALESCE(?, port), username = COALESCE(?, username),
                         password_secret = COALESCE(?, password_secret),
-                        database = COALESCE(?, database)
+                        database = COALESCE(?, database),
+                        pool_size = COALESCE(?, pool_size)
                        WHERE id = ? AND deleted_at IS NULL"#,
-                    name, host, port_i, username, password_secret, database, id
+                    name, host, port_i, username, password_secret, database, pool_i, id
                 )
                 .execute(&self.pool)
                 .await
@@ -2007,22 +3231,411 @@ impl ConfigStore for SqliteStore {
         .map_err(db)?;
         Ok(())
     }
+
+    async fn request_config_test(&self, config_id: Uuid) -> Result<Uuid> {
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        sqlx::query!(
+            "INSERT INTO config_tests (id, config_id, requested_at) VALUES (?, ?, ?)",
+            id,
+            config_id,
+            now
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(db)?;
+        self.config_tests_notify.notify_waiters();
+        Ok(id)
+    }
+
+    async fn claim_config_tests(
+        &self,
+        worker_id: Uuid,
+        limit: u32,
+        max_age_secs: u32,
+    ) -> Result<Vec<ConfigTestRequest>> {
+        let now = Utc::now();
+        let cutoff = now - Duration::seconds(max_age_secs as i64);
+        let limit = limit as i64;
+        let rows = sqlx::query!(
+            r#"UPDATE config_tests SET claimed_by = ?1, claimed_at = ?2
+               WHERE id IN (
+                   SELECT ct.id FROM config_tests ct
+                   WHERE ct.claimed_by IS NULL AND ct.requested_at > ?3
+                     AND (EXISTS (SELECT 1 FROM pgsql_configs c WHERE c.id = ct.config_id AND c.deleted_at IS NULL)
+                          OR EXISTS (SELECT 1 FROM mysql_configs c WHERE c.id = ct.config_id AND c.deleted_at IS NULL))
+                   ORDER BY ct.requested_at LIMIT ?4
+               )
+               RETURNING id AS "id!: Uuid", config_id AS "config_id!: Uuid""#,
+            worker_id,
+            now,
+            cutoff,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db)?;
+        let mut out = Vec::with_capacity(rows.len());
+        for r in rows {
+            if let Some(config) = self.get_db_config(r.config_id, None).await? {
+                out.push(ConfigTestRequest { id: r.id, config });
+            }
+        }
+        Ok(out)
+    }
+
+    async fn finish_config_test(&self, id: Uuid, result: &ConfigTestResult) -> Result<()> {
+        let json = serde_json::to_string(result)
+            .map_err(|e| ArbiterError::ExecutionError(e.to_string()))?;
+        sqlx::query!("UPDATE config_tests SET result = ? WHERE id = ?", json, id)
+            .execute(&self.pool)
+            .await
+            .map_err(db)?;
+        Ok(())
+    }
+
+    async fn get_config_test(&self, id: Uuid) -> Result<Option<ConfigTestResult>> {
+        let result = sqlx::query_scalar!("SELECT result FROM config_tests WHERE id = ?", id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db)?
+            .flatten();
+        result
+            .map(|s| serde_json::from_str(&s))
+            .transpose()
+            .map_err(|e| ArbiterError::DatabaseError(format!("invalid config test result: {e}")))
+    }
+
+    async fn prune_config_tests(&self, older_than: DateTime<Utc>) -> Result<u64> {
+        let res = sqlx::query!("DELETE FROM config_tests WHERE requested_at < ?", older_than)
+            .execute(&self.pool)
+            .await
+            .map_err(db)?;
+        Ok(res.rows_affected())
+    }
+
+    async fn await_config_tests_change(&self) {
+        self.config_tests_notify.notified().await;
+    }
 }
 
 fn mk_log_chunk(
     seq: i64,
     stream: String,
     content: String,
+    level: Option<String>,
     created_at: DateTime<Utc>,
 ) -> Result<LogChunk> {
     Ok(LogChunk {
         seq,
         stream: stream.parse()?,
         content,
+        level: level.map(|l| l.parse()).transpose()?,
         created_at,
     })
 }
 
+#[async_trait]
+impl ModuleStore for SqliteStore {
+    async fn put_wasm_module(
+        &self,
+        tenant_id: Uuid,
+        sha256: &str,
+        content: &[u8],
+    ) -> Result<WasmModuleInfo> {
+        let now = Utc::now();
+        let size = content.len() as i64;
+        sqlx::query!(
+            "INSERT OR IGNORE INTO wasm_modules (tenant_id, sha256, content, size_bytes, created_at) VALUES (?, ?, ?, ?, ?)",
+            tenant_id,
+            sha256,
+            content,
+            size,
+            now
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(db)?;
+        let r = sqlx::query!(
+            r#"SELECT sha256 AS "sha256!", size_bytes AS "size_bytes!: i64",
+                      tenant_id AS "tenant_id!: Uuid", created_at AS "created_at!: DateTime<Utc>"
+               FROM wasm_modules WHERE tenant_id = ? AND sha256 = ?"#,
+            tenant_id,
+            sha256
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(WasmModuleInfo {
+            sha256: r.sha256,
+            size_bytes: r.size_bytes as u64,
+            tenant_id: r.tenant_id,
+            created_at: r.created_at,
+        })
+    }
+
+    async fn get_wasm_module(&self, sha256: &str, scope: Option<Uuid>) -> Result<Option<Vec<u8>>> {
+        let row = sqlx::query!(
+            r#"SELECT content AS "content!" FROM wasm_modules
+               WHERE sha256 = ?1 AND (?2 IS NULL OR tenant_id = ?2) LIMIT 1"#,
+            sha256,
+            scope
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(row.map(|r| r.content))
+    }
+
+    async fn list_wasm_modules(&self, scope: Option<Uuid>) -> Result<Vec<WasmModuleInfo>> {
+        let rows = sqlx::query!(
+            r#"SELECT sha256 AS "sha256!", size_bytes AS "size_bytes!: i64",
+                      tenant_id AS "tenant_id!: Uuid", created_at AS "created_at!: DateTime<Utc>"
+               FROM wasm_modules WHERE (?1 IS NULL OR tenant_id = ?1)
+               ORDER BY created_at DESC, sha256"#,
+            scope
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(rows
+            .into_iter()
+            .map(|r| WasmModuleInfo {
+                sha256: r.sha256,
+                size_bytes: r.size_bytes as u64,
+                tenant_id: r.tenant_id,
+                created_at: r.created_at,
+            })
+            .collect())
+    }
+
+    async fn delete_wasm_module(&self, sha256: &str, scope: Option<Uuid>) -> Result<()> {
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        // A live job in the module's tenant still runs it: refuse rather than break it.
+        let in_use = sqlx::query_scalar!(
+            r#"SELECT EXISTS (
+                   SELECT 1 FROM job_runner_wasm w
+                   JOIN jobs j ON j.id = w.job_id
+                   WHERE w.module_sha256 = ?1
+                     AND j.deleted_at IS NULL
+                     AND (?2 IS NULL OR j.tenant_id = ?2)
+               ) AS "in_use!: bool""#,
+            sha256,
+            scope
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db)?;
+        if in_use {
+            return Err(ArbiterError::Conflict(format!("wasm module {sha256} in use by a job")));
+        }
+        let res = sqlx::query!(
+            "DELETE FROM wasm_modules WHERE sha256 = ?1 AND (?2 IS NULL OR tenant_id = ?2)",
+            sha256,
+            scope
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db)?;
+        if res.rows_affected() == 0 {
+            return Err(ArbiterError::NotFound(format!("wasm module {sha256} not found")));
+        }
+        tx.commit().await.map_err(db)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BundleStore for SqliteStore {
+    async fn put_code_bundle(
+        &self,
+        tenant_id: Uuid,
+        name: &str,
+        format: BundleFormat,
+        sha256: &str,
+        content: &[u8],
+    ) -> Result<CodeBundle> {
+        validate_bundle_name(name)?;
+        if let Some(latest) = self.get_code_bundle(name, None, Some(tenant_id)).await?
+            && latest.sha256 == sha256
+        {
+            return Ok(latest);
+        }
+        // The next version is picked inside the insert, so concurrent uploads of one name
+        // cannot claim the same number.
+        let size = content.len() as i64;
+        let format_s = format.to_string();
+        let now = Utc::now();
+        let r = sqlx::query!(
+            r#"INSERT INTO code_bundles (tenant_id, name, version, sha256, format, size_bytes, content, created_at)
+               SELECT ?1, ?2, COALESCE(MAX(version), 0) + 1, ?3, ?4, ?5, ?6, ?7
+               FROM code_bundles WHERE tenant_id = ?1 AND name = ?2
+               RETURNING version AS "version!: i64""#,
+            tenant_id,
+            name,
+            sha256,
+            format_s,
+            size,
+            content,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(CodeBundle {
+            tenant_id,
+            name: name.to_string(),
+            version: r.version as u32,
+            sha256: sha256.to_string(),
+            format,
+            size_bytes: content.len() as u64,
+            created_at: now,
+        })
+    }
+
+    async fn get_code_bundle(
+        &self,
+        name: &str,
+        version: Option<u32>,
+        scope: Option<Uuid>,
+    ) -> Result<Option<CodeBundle>> {
+        let version = version.map(|v| v as i64);
+        let row = sqlx::query!(
+            r#"SELECT tenant_id AS "tenant_id!: Uuid", name AS "name!", version AS "version!: i64",
+                      sha256 AS "sha256!", format AS "format!", size_bytes AS "size_bytes!: i64",
+                      created_at AS "created_at!: DateTime<Utc>"
+               FROM code_bundles
+               WHERE name = ?1 AND (?2 IS NULL OR version = ?2) AND (?3 IS NULL OR tenant_id = ?3)
+               ORDER BY version DESC LIMIT 1"#,
+            name,
+            version,
+            scope
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db)?;
+        row.map(|r| {
+            Ok(CodeBundle {
+                tenant_id: r.tenant_id,
+                name: r.name,
+                version: r.version as u32,
+                sha256: r.sha256,
+                format: r.format.parse()?,
+                size_bytes: r.size_bytes as u64,
+                created_at: r.created_at,
+            })
+        })
+        .transpose()
+    }
+
+    async fn get_code_bundle_content(
+        &self,
+        name: &str,
+        version: u32,
+        scope: Option<Uuid>,
+    ) -> Result<Option<Vec<u8>>> {
+        let version = version as i64;
+        let row = sqlx::query!(
+            r#"SELECT content AS "content!" FROM code_bundles
+               WHERE name = ?1 AND version = ?2 AND (?3 IS NULL OR tenant_id = ?3) LIMIT 1"#,
+            name,
+            version,
+            scope
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(row.map(|r| r.content))
+    }
+
+    async fn list_code_bundles(&self, scope: Option<Uuid>) -> Result<Vec<CodeBundle>> {
+        let rows = sqlx::query!(
+            r#"SELECT tenant_id AS "tenant_id!: Uuid", name AS "name!", version AS "version!: i64",
+                      sha256 AS "sha256!", format AS "format!", size_bytes AS "size_bytes!: i64",
+                      created_at AS "created_at!: DateTime<Utc>"
+               FROM code_bundles WHERE (?1 IS NULL OR tenant_id = ?1)
+               ORDER BY name, version"#,
+            scope
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db)?;
+        rows.into_iter()
+            .map(|r| {
+                Ok(CodeBundle {
+                    tenant_id: r.tenant_id,
+                    name: r.name,
+                    version: r.version as u32,
+                    sha256: r.sha256,
+                    format: r.format.parse()?,
+                    size_bytes: r.size_bytes as u64,
+                    created_at: r.created_at,
+                })
+            })
+            .collect()
+    }
+
+    async fn delete_code_bundle(
+        &self,
+        name: &str,
+        version: u32,
+        scope: Option<Uuid>,
+    ) -> Result<()> {
+        let version_i = version as i64;
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        // A live job of the bundle's tenant pins this version, or follows the latest and
+        // this is the last one: refuse rather than break it.
+        let in_use = sqlx::query_scalar!(
+            r#"WITH refs AS (
+                   SELECT j.tenant_id AS tenant_id, COALESCE(s.code, py.code, nd.code) AS code
+                   FROM jobs j
+                   LEFT JOIN job_runner_shell s ON s.job_id = j.id
+                   LEFT JOIN job_runner_python py ON py.job_id = j.id
+                   LEFT JOIN job_runner_node nd ON nd.job_id = j.id
+                   WHERE j.deleted_at IS NULL
+               )
+               SELECT EXISTS (
+                   SELECT 1 FROM code_bundles b
+                   JOIN refs r ON r.tenant_id = b.tenant_id
+                   WHERE b.name = ?1 AND b.version = ?2
+                     AND (?3 IS NULL OR b.tenant_id = ?3)
+                     AND json_extract(r.code, '$.source') = 'bundle'
+                     AND json_extract(r.code, '$.name') = b.name
+                     AND (json_extract(r.code, '$.version') = b.version
+                          OR (json_extract(r.code, '$.version') IS NULL AND NOT EXISTS (
+                                SELECT 1 FROM code_bundles o
+                                WHERE o.tenant_id = b.tenant_id AND o.name = b.name
+                                  AND o.version <> b.version)))
+               ) AS "in_use!: bool""#,
+            name,
+            version_i,
+            scope
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db)?;
+        if in_use {
+            return Err(ArbiterError::Conflict(format!(
+                "bundle {name} v{version} in use by a job"
+            )));
+        }
+        let res = sqlx::query!(
+            "DELETE FROM code_bundles WHERE name = ?1 AND version = ?2 AND (?3 IS NULL OR tenant_id = ?3)",
+            name,
+            version_i,
+            scope
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db)?;
+        if res.rows_affected() == 0 {
+            return Err(ArbiterError::NotFound(format!("bundle {name} v{version} not found")));
+        }
+        tx.commit().await.map_err(db)?;
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl LogStore for SqliteStore {
     async fn append_run_log(
@@ -2031,19 +3644,22 @@ impl LogStore for SqliteStore {
         attempt: u32,
         seq: i64,
         stream: LogStream,
+        level: Option<LogLevel>,
         content: &str,
     ) -> Result<()> {
         let attempt = attempt as i64;
         let stream = stream.to_string();
+        let level = level.map(|l| l.to_string());
         let now = Utc::now();
         sqlx::query!(
-            "INSERT OR IGNORE INTO run_log_chunks (run_id, attempt, seq, stream, content, created_at) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT OR IGNORE INTO run_log_chunks (run_id, attempt, seq, stream, content, level, created_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             run_id,
             attempt,
             seq,
             stream,
             content,
+            level,
             now
         )
         .execute(&self.pool)
@@ -2063,7 +3679,7 @@ impl LogStore for SqliteStore {
         let limit = limit as i64;
         let rows = sqlx::query!(
             r#"SELECT seq AS "seq!: i64", stream AS "stream!", content AS "content!",
-                      created_at AS "created_at!: DateTime<Utc>"
+                      level AS "level?", created_at AS "created_at!: DateTime<Utc>"
                FROM run_log_chunks
                WHERE run_id = ?1 AND attempt = ?2 AND (?3 IS NULL OR seq > ?3)
                ORDER BY seq ASC LIMIT ?4"#,
@@ -2076,7 +3692,7 @@ impl LogStore for SqliteStore {
         .await
         .map_err(db)?;
         rows.into_iter()
-            .map(|r| mk_log_chunk(r.seq, r.stream, r.content, r.created_at))
+            .map(|r| mk_log_chunk(r.seq, r.stream, r.content, r.level, r.created_at))
             .collect()
     }
 
@@ -2091,8 +3707,8 @@ impl LogStore for SqliteStore {
         let limit = limit as i64;
         let rows = sqlx::query!(
             r#"SELECT seq AS "seq!: i64", stream AS "stream!", content AS "content!",
-                      created_at AS "created_at!: DateTime<Utc>" FROM (
-                 SELECT seq, stream, content, created_at
+                      level AS "level?", created_at AS "created_at!: DateTime<Utc>" FROM (
+                 SELECT seq, stream, content, level, created_at
                  FROM run_log_chunks
                  WHERE run_id = ?1 AND attempt = ?2 AND (?3 IS NULL OR seq < ?3)
                  ORDER BY seq DESC LIMIT ?4
@@ -2106,7 +3722,7 @@ impl LogStore for SqliteStore {
         .await
         .map_err(db)?;
         rows.into_iter()
-            .map(|r| mk_log_chunk(r.seq, r.stream, r.content, r.created_at))
+            .map(|r| mk_log_chunk(r.seq, r.stream, r.content, r.level, r.created_at))
             .collect()
     }
 
@@ -2129,3 +3745,113 @@ impl LogStore for SqliteStore {
         })
     }
 }
+
+#[async_trait]
+impl ArtifactStore for SqliteStore {
+    async fn put_artifact(
+        &self,
+        tenant_id: Uuid,
+        run_id: Uuid,
+        attempt: u32,
+        name: &str,
+        sha256: &str,
+        content: &[u8],
+    ) -> Result<RunArtifact> {
+        validate_artifact_name(name)?;
+        let attempt_i = attempt as i64;
+        let size = content.len() as i64;
+        let now = Utc::now();
+        sqlx::query!(
+            "INSERT INTO run_artifacts (run_id, attempt, name, tenant_id, size_bytes, sha256, content, created_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) \
+             ON CONFLICT (run_id, attempt, name) DO UPDATE SET tenant_id = ?4, size_bytes = ?5, \
+             sha256 = ?6, content = ?7, created_at = ?8",
+            run_id,
+            attempt_i,
+            name,
+            tenant_id,
+            size,
+            sha256,
+            content,
+            now
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(RunArtifact {
+            run_id,
+            attempt,
+            name: name.to_string(),
+            size_bytes: content.len() as u64,
+            sha256: sha256.to_string(),
+            created_at: now,
+        })
+    }
+
+    async fn list_artifacts(&self, tenant_id: Uuid, run_id: Uuid) -> Result<Vec<RunArtifact>> {
+        let rows = sqlx::query!(
+            r#"SELECT attempt AS "attempt!: i64", name AS "name!", size_bytes AS "size_bytes!: i64",
+                      sha256 AS "sha256!", created_at AS "created_at!: DateTime<Utc>"
+               FROM run_artifacts WHERE tenant_id = ?1 AND run_id = ?2
+               ORDER BY attempt, name"#,
+            tenant_id,
+            run_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(rows
+            .into_iter()
+            .map(|r| RunArtifact {
+                run_id,
+                attempt: r.attempt as u32,
+                name: r.name,
+                size_bytes: r.size_bytes as u64,
+                sha256: r.sha256,
+                created_at: r.created_at,
+            })
+            .collect())
+    }
+
+    async fn get_artifact(
+        &self,
+        tenant_id: Uuid,
+        run_id: Uuid,
+        attempt: u32,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        let attempt = attempt as i64;
+        let row = sqlx::query!(
+            r#"SELECT content AS "content!" FROM run_artifacts
+               WHERE tenant_id = ?1 AND run_id = ?2 AND attempt = ?3 AND name = ?4"#,
+            tenant_id,
+            run_id,
+            attempt,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(row.map(|r| r.content))
+    }
+
+    async fn tenant_artifact_bytes(&self, tenant_id: Uuid) -> Result<u64> {
+        let total = sqlx::query_scalar!(
+            r#"SELECT COALESCE(SUM(size_bytes), 0) AS "total!: i64" FROM run_artifacts
+               WHERE tenant_id = ?"#,
+            tenant_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db)?;
+        Ok(total as u64)
+    }
+
+    async fn prune_artifacts(&self, older_than: DateTime<Utc>) -> Result<u64> {
+        let res = sqlx::query!("DELETE FROM run_artifacts WHERE created_at < ?", older_than)
+            .execute(&self.pool)
+            .await
+            .map_err(db)?;
+        Ok(res.rows_affected())
+    }
+}
@@ -11,9 +11,11 @@ use std::sync::Arc;
 // `Store` brings its supertrait methods (ApiStore/JobStore/RunStore/WorkerStore)
 // into scope for `dyn Store`, so only the trait and the data types are imported.
 use arbiter_core::{
-    DbEngine, DEFAULT_TENANT_ID, ExecutableConfigSnapshotMeta, JobRunState, LogStream,
-    MisfirePolicy, ResultStatus, RetryConfig, RunOutcome, RunnerConfig, Store, UserRole,
-    WorkerRecord,
+    ArbiterError, BundleFormat, CodeSource, ConfigTestError, ConfigTestResult, DbEngine, DbOutputConfig, DbOutputFormat, DEFAULT_TENANT_ID, ExecutableConfigSnapshotMeta, HttpSigningConfig, HttpTlsConfig,
+    JobRunState, LogLevel, LogStream, MisfirePolicy, ResultStatus, RetryConfig, RetryOn, RunOutcome, RunParam, RunProgress,
+    RunnerConfig, RunnerPluginInfo, RuntimeTransport, SigningAlgorithm, Store, UserRole, WasmModuleSource, WasmPreopen, WorkerDiskUsage, WorkerRecord,
+    SecretActor, SecretAuditAction, SecretAuditEvent, SecretAuditOutcome, SecretDelivery,
+    SecretReferrer, WorkspaceMode,
 };
 use chrono::{DateTime, Duration, Utc};
 use futures::future::BoxFuture;
@@ -35,6 +37,8 @@ pub enum Capability {
     MultiNode,
     Fencing,
     Retention,
+    /// pgsql/mysql runner jobs (shared DB configs referenced by jobs).
+    DbRunners,
 }
 
 /// What a backend promises to support. Drives which cases run vs are skipped.
@@ -47,6 +51,7 @@ pub struct Capabilities {
     pub multi_node: bool,
     pub fencing: bool,
     pub retention: bool,
+    pub db_runners: bool,
 }
 
 impl Capabilities {
@@ -59,6 +64,7 @@ impl Capabilities {
             Capability::MultiNode => self.multi_node,
             Capability::Fencing => self.fencing,
             Capability::Retention => self.retention,
+            Capability::DbRunners => self.db_runners,
         }
     }
 
@@ -211,6 +217,30 @@ pub fn cases() -> Vec<Case> {
             needs: &[],
             run: |s| Box::pin(reaper_spares_live(s)),
         },
+        Case {
+            group: "reaper",
+            name: "spares_heartbeating_runs",
+            needs: &[],
+            run: |s| Box::pin(reaper_spares_heartbeating(s)),
+        },
+        Case {
+            group: "state",
+            name: "heartbeat_records_progress",
+            needs: &[],
+            run: |s| Box::pin(state_heartbeat_records_progress(s)),
+        },
+        Case {
+            group: "state",
+            name: "owns_run_tracks_worker_and_attempt",
+            needs: &[],
+            run: |s| Box::pin(state_owns_run_tracks_worker_and_attempt(s)),
+        },
+        Case {
+            group: "state",
+            name: "records_code_commit",
+            needs: &[],
+            run: |s| Box::pin(state_records_code_commit(s)),
+        },
         Case {
             group: "state",
             name: "cancel_prevents_claim",
@@ -223,6 +253,12 @@ pub fn cases() -> Vec<Case> {
             needs: &[],
             run: |s| Box::pin(state_adhoc_claimable(s)),
         },
+        Case {
+            group: "state",
+            name: "adhoc_params_round_trip",
+            needs: &[],
+            run: |s| Box::pin(state_adhoc_params_round_trip(s)),
+        },
         Case {
             group: "claim",
             name: "skips_deleted_job_runs",
@@ -271,6 +307,18 @@ pub fn cases() -> Vec<Case> {
             needs: &[],
             run: |s| Box::pin(worker_list_registered(s)),
         },
+        Case {
+            group: "worker",
+            name: "heartbeat_advertises_plugins",
+            needs: &[],
+            run: |s| Box::pin(worker_heartbeat_advertises_plugins(s)),
+        },
+        Case {
+            group: "worker",
+            name: "heartbeat_reports_disk_usage",
+            needs: &[],
+            run: |s| Box::pin(worker_heartbeat_reports_disk_usage(s)),
+        },
         Case {
             group: "state",
             name: "failed_records_exit_and_error",
@@ -349,6 +397,30 @@ pub fn cases() -> Vec<Case> {
             needs: &[],
             run: |s| Box::pin(claim_carries_http_snapshot(s)),
         },
+        Case {
+            group: "claim",
+            name: "carries_pgsql_snapshot",
+            needs: &[Capability::DbRunners],
+            run: |s| Box::pin(claim_carries_pgsql_snapshot(s)),
+        },
+        Case {
+            group: "claim",
+            name: "carries_sqlite_snapshot",
+            needs: &[],
+            run: |s| Box::pin(claim_carries_sqlite_snapshot(s)),
+        },
+        Case {
+            group: "claim",
+            name: "carries_wasm_snapshot",
+            needs: &[],
+            run: |s| Box::pin(claim_carries_wasm_snapshot(s)),
+        },
+        Case {
+            group: "claim",
+            name: "carries_plugin_snapshot",
+            needs: &[],
+            run: |s| Box::pin(claim_carries_plugin_snapshot(s)),
+        },
         Case {
             group: "claim",
             name: "carries_python_snapshot",
@@ -361,6 +433,30 @@ pub fn cases() -> Vec<Case> {
             needs: &[],
             run: |s| Box::pin(claim_carries_node_snapshot(s)),
         },
+        Case {
+            group: "claim",
+            name: "carries_resident_runtime_snapshot",
+            needs: &[],
+            run: |s| Box::pin(claim_carries_resident_runtime_snapshot(s)),
+        },
+        Case {
+            group: "claim",
+            name: "carries_runtime_declared_secrets",
+            needs: &[],
+            run: |s| Box::pin(claim_carries_runtime_declared_secrets(s)),
+        },
+        Case {
+            group: "claim",
+            name: "carries_workspace_mode",
+            needs: &[],
+            run: |s| Box::pin(claim_carries_workspace_mode(s)),
+        },
+        Case {
+            group: "claim",
+            name: "carries_secret_delivery",
+            needs: &[],
+            run: |s| Box::pin(claim_carries_secret_delivery(s)),
+        },
         Case {
             group: "env",
             name: "set_get_roundtrip",
@@ -397,6 +493,12 @@ pub fn cases() -> Vec<Case> {
             needs: &[],
             run: |s| Box::pin(retry_reschedule_requeues(s)),
         },
+        Case {
+            group: "retry",
+            name: "retry_on_roundtrips_into_snapshot",
+            needs: &[],
+            run: |s| Box::pin(retry_on_roundtrips(s)),
+        },
         Case {
             group: "secrets",
             name: "upsert_get_roundtrip",
@@ -433,6 +535,30 @@ pub fn cases() -> Vec<Case> {
             needs: &[],
             run: |s| Box::pin(secrets_rewrap_and_retire(s)),
         },
+        Case {
+            group: "secrets",
+            name: "versions_kept_pruned_and_restored",
+            needs: &[],
+            run: |s| Box::pin(secrets_versions(s)),
+        },
+        Case {
+            group: "secrets",
+            name: "policy_and_last_use",
+            needs: &[],
+            run: |s| Box::pin(secrets_policy_and_last_use(s)),
+        },
+        Case {
+            group: "secrets",
+            name: "references_indexed",
+            needs: &[],
+            run: |s| Box::pin(secrets_references_indexed(s)),
+        },
+        Case {
+            group: "secrets",
+            name: "audit_appended_listed_and_pruned",
+            needs: &[],
+            run: |s| Box::pin(secrets_audit(s)),
+        },
         Case {
             group: "secrets",
             name: "ack_and_delete_shares",
@@ -451,6 +577,12 @@ pub fn cases() -> Vec<Case> {
             needs: &[],
             run: |s| Box::pin(logs_pruned_with_run(s)),
         },
+        Case {
+            group: "logs",
+            name: "records_keep_level",
+            needs: &[],
+            run: |s| Box::pin(logs_records_keep_level(s)),
+        },
         Case {
             group: "secrets",
             name: "isolated_per_tenant",
@@ -505,6 +637,54 @@ pub fn cases() -> Vec<Case> {
             needs: &[],
             run: |s| Box::pin(config_scoped_by_tenant(s)),
         },
+        Case {
+            group: "config",
+            name: "test_request_lifecycle",
+            needs: &[],
+            run: |s| Box::pin(config_test_lifecycle(s)),
+        },
+        Case {
+            group: "config",
+            name: "test_claim_skips_stale_and_deleted",
+            needs: &[],
+            run: |s| Box::pin(config_test_claim_skips_stale_and_deleted(s)),
+        },
+        Case {
+            group: "modules",
+            name: "content_addressed_per_tenant",
+            needs: &[],
+            run: |s| Box::pin(modules_content_addressed_per_tenant(s)),
+        },
+        Case {
+            group: "modules",
+            name: "delete_refuses_in_use",
+            needs: &[],
+            run: |s| Box::pin(modules_delete_refuses_in_use(s)),
+        },
+        Case {
+            group: "artifacts",
+            name: "roundtrip_per_tenant",
+            needs: &[],
+            run: |s| Box::pin(artifacts_roundtrip_per_tenant(s)),
+        },
+        Case {
+            group: "artifacts",
+            name: "pruned_with_run",
+            needs: &[],
+            run: |s| Box::pin(artifacts_pruned_with_run(s)),
+        },
+        Case {
+            group: "bundles",
+            name: "versioned_per_tenant",
+            needs: &[],
+            run: |s| Box::pin(bundles_versioned_per_tenant(s)),
+        },
+        Case {
+            group: "bundles",
+            name: "delete_refuses_referenced",
+            needs: &[],
+            run: |s| Box::pin(bundles_delete_refuses_referenced(s)),
+        },
     ]
 }
 
@@ -561,6 +741,9 @@ fn shell() -> RunnerConfig {
     RunnerConfig::Shell {
         command: "echo hi".to_string(),
         working_dir: None,
+        code: None,
+        workspace: WorkspaceMode::Run,
+        secret_delivery: SecretDelivery::Env,
     }
 }
 
@@ -606,6 +789,8 @@ async fn set_last_seen(store: &StoreRef, id: Uuid, last_seen: DateTime<Utc>) {
         capacity: 1,
         restart_count: 0,
         version: "test".to_string(),
+        plugins: Vec::new(),
+        disk_usage: None,
     };
     store.heartbeat(&rec).await.expect("heartbeat");
 }
@@ -1146,6 +1331,129 @@ async fn reaper_spares_live(store: StoreRef) {
     assert_eq!(requeued, 0, "a live worker's run must not be reclaimed");
 }
 
+async fn reaper_spares_heartbeating(store: StoreRef) {
+    let job = seed_job(&store, Some("* * * * *"), true).await;
+    store
+        .insert_job_run_if_missing(job, Utc::now() - Duration::seconds(30))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let claimed = store.claim_job_runs(worker, 1).await.expect("claim_job_runs");
+    assert_eq!(claimed.len(), 1);
+
+    // The worker record looks dead, but the run itself just heartbeated: still alive.
+    assert!(
+        store.heartbeat_run(claimed[0].id, worker, None).await.expect("heartbeat_run"),
+        "the claiming worker can heartbeat its run"
+    );
+    set_last_seen(&store, worker, Utc::now() - Duration::seconds(3600)).await;
+    let requeued = store
+        .reclaim_dead_workers_jobs(60)
+        .await
+        .expect("reclaim_dead_workers_jobs");
+    assert_eq!(requeued, 0, "a heartbeating run must not be reclaimed");
+}
+
+async fn state_heartbeat_records_progress(store: StoreRef) {
+    let job = seed_job(&store, Some("* * * * *"), true).await;
+    store
+        .insert_job_run_if_missing(job, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let run = store.claim_job_runs(worker, 1).await.expect("claim_job_runs").remove(0);
+    assert!(run.progress.is_none() && run.last_heartbeat.is_none());
+
+    let progress = RunProgress { value: 0.25, message: Some("1/4 files".to_string()) };
+    assert!(store.heartbeat_run(run.id, worker, Some(progress.clone())).await.expect("heartbeat"));
+    let got = store.get_run(run.id, None).await.expect("get_run").expect("run");
+    assert_eq!(got.progress, Some(progress.clone()));
+    assert!(got.last_heartbeat.is_some(), "progress counts as a heartbeat");
+
+    // A bare heartbeat keeps the last progress.
+    assert!(store.heartbeat_run(run.id, worker, None).await.expect("heartbeat"));
+    let got = store.get_run(run.id, None).await.expect("get_run").expect("run");
+    assert_eq!(got.progress, Some(progress));
+
+    // Only the run's own worker, and only while it runs.
+    let other = seed_worker(&store).await;
+    assert!(!store.heartbeat_run(run.id, other, None).await.expect("heartbeat other"));
+    store
+        .finalize_run(
+            run.id,
+            JobRunState::Succeeded,
+            RunOutcome { status: Some(ResultStatus::Success), ..Default::default() },
+        )
+        .await
+        .expect("finalize");
+    assert!(!store.heartbeat_run(run.id, worker, None).await.expect("heartbeat after finish"));
+}
+
+async fn state_owns_run_tracks_worker_and_attempt(store: StoreRef) {
+    let job = seed_job(&store, Some("* * * * *"), true).await;
+    store
+        .insert_job_run_if_missing(job, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let run = store.claim_job_runs(worker, 1).await.expect("claim_job_runs").remove(0);
+
+    assert!(store.owns_run(run.id, worker, run.attempt).await.expect("owns_run"));
+    let other = seed_worker(&store).await;
+    assert!(!store.owns_run(run.id, other, run.attempt).await.expect("owns_run other"));
+    assert!(!store.owns_run(run.id, worker, run.attempt + 1).await.expect("owns_run attempt"));
+    assert!(!store.owns_run(Uuid::new_v4(), worker, 1).await.expect("owns_run missing"));
+
+    store
+        .finalize_run(
+            run.id,
+            JobRunState::Cancelled,
+            RunOutcome { status: Some(ResultStatus::Failed), ..Default::default() },
+        )
+        .await
+        .expect("finalize");
+    assert!(!store.owns_run(run.id, worker, run.attempt).await.expect("owns_run finished"));
+}
+
+async fn state_records_code_commit(store: StoreRef) {
+    let job = seed_job(&store, Some("* * * * *"), true).await;
+    store
+        .insert_job_run_if_missing(job, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let run = store.claim_job_runs(worker, 1).await.expect("claim_job_runs").remove(0);
+    assert!(run.code_commit.is_none());
+
+    let commit = "0123456789abcdef0123456789abcdef01234567";
+    let other = seed_worker(&store).await;
+    assert!(!store.record_run_code_commit(run.id, other, commit).await.expect("record other"));
+    assert!(store.record_run_code_commit(run.id, worker, commit).await.expect("record"));
+    let got = store.get_run(run.id, None).await.expect("get_run").expect("run");
+    assert_eq!(got.code_commit.as_deref(), Some(commit));
+
+    // It stays on the failed attempt's run until the retry is claimed, which resolves anew.
+    store
+        .reschedule_for_retry(
+            run.id,
+            2,
+            Utc::now() - Duration::seconds(1),
+            RunOutcome { status: Some(ResultStatus::Retryable), ..Default::default() },
+        )
+        .await
+        .expect("reschedule_for_retry");
+    let listed = store
+        .list_recent_runs(None, None, None, Some(job), None, None)
+        .await
+        .expect("list_recent_runs");
+    assert_eq!(listed[0].code_commit.as_deref(), Some(commit));
+    let retry = store.claim_job_runs(worker, 1).await.expect("claim retry").remove(0);
+    assert_eq!((retry.id, retry.attempt), (run.id, 2));
+    assert!(retry.code_commit.is_none());
+    let got = store.get_run(run.id, None).await.expect("get_run").expect("run");
+    assert!(got.code_commit.is_none());
+}
+
 async fn state_cancel_prevents_claim(store: StoreRef) {
     let job = seed_job(&store, Some("* * * * *"), true).await;
     store
@@ -1170,8 +1478,12 @@ async fn state_cancel_prevents_claim(store: StoreRef) {
 
 async fn state_adhoc_claimable(store: StoreRef) {
     let job = seed_job(&store, None, true).await;
-    let run = store.create_adhoc_run(job).await.expect("create_adhoc_run");
+    let run = store
+        .create_adhoc_run(job, HashMap::new())
+        .await
+        .expect("create_adhoc_run");
     assert!(matches!(run.state, JobRunState::Queued));
+    assert!(run.params.is_empty());
 
     let worker = seed_worker(&store).await;
     let claimed = store
@@ -1184,6 +1496,32 @@ async fn state_adhoc_claimable(store: StoreRef) {
     );
 }
 
+async fn state_adhoc_params_round_trip(store: StoreRef) {
+    let job = seed_job(&store, None, true).await;
+    let params = HashMap::from([
+        ("day".to_string(), RunParam::Text("2026-01-31".to_string())),
+        ("limit".to_string(), RunParam::Int(50)),
+        ("ratio".to_string(), RunParam::Float(0.5)),
+        ("dry_run".to_string(), RunParam::Bool(true)),
+    ]);
+    let run = store
+        .create_adhoc_run(job, params.clone())
+        .await
+        .expect("create_adhoc_run");
+    assert_eq!(run.params, params, "create returns the params");
+
+    let worker = seed_worker(&store).await;
+    let claimed = store
+        .claim_job_runs(worker, 10)
+        .await
+        .expect("claim_job_runs");
+    let claimed = claimed.iter().find(|r| r.id == run.id).expect("claimed");
+    assert_eq!(claimed.params, params, "the worker receives the run's params");
+
+    let got = store.get_run(run.id, None).await.expect("get_run").expect("run");
+    assert_eq!(got.params, params, "params are kept on the run");
+}
+
 async fn claim_skips_deleted(store: StoreRef) {
     let job = seed_job(&store, Some("* * * * *"), true).await;
     store
@@ -1350,6 +1688,76 @@ async fn worker_list_registered(store: StoreRef) {
     assert_eq!(workers.len(), 2, "both registered workers should be listed");
 }
 
+async fn worker_heartbeat_advertises_plugins(store: StoreRef) {
+    let id = Uuid::new_v4();
+    let plugin = RunnerPluginInfo {
+        name: "deploy".to_string(),
+        version: "1.2.0".to_string(),
+        protocol_version: 1,
+        capabilities: vec!["logs".to_string(), "secrets".to_string()],
+        config_schema: Some(serde_json::json!({"type": "object", "required": ["target"]})),
+    };
+    let mut rec = WorkerRecord {
+        id,
+        display_name: "plugin-worker".to_string(),
+        hostname: "localhost".to_string(),
+        last_seen: Utc::now(),
+        capacity: 1,
+        restart_count: 0,
+        version: "test".to_string(),
+        plugins: vec![plugin.clone()],
+        disk_usage: None,
+    };
+    store.heartbeat(&rec).await.expect("heartbeat");
+    let listed = store.list_workers().await.expect("list_workers");
+    let got = listed.iter().find(|w| w.id == id).expect("worker listed");
+    assert_eq!(got.plugins, vec![plugin]);
+
+    // A later heartbeat replaces the advertised set (plugins removed on restart).
+    rec.plugins.clear();
+    store.heartbeat(&rec).await.expect("heartbeat");
+    let listed = store.list_workers().await.expect("list_workers");
+    let got = listed.iter().find(|w| w.id == id).expect("worker listed");
+    assert!(got.plugins.is_empty());
+}
+
+async fn worker_heartbeat_reports_disk_usage(store: StoreRef) {
+    let id = Uuid::new_v4();
+    let mut rec = WorkerRecord {
+        id,
+        display_name: "disk-worker".to_string(),
+        hostname: "localhost".to_string(),
+        last_seen: Utc::now(),
+        capacity: 1,
+        restart_count: 0,
+        version: "test".to_string(),
+        plugins: Vec::new(),
+        disk_usage: None,
+    };
+    store.heartbeat(&rec).await.expect("heartbeat");
+    let listed = store.list_workers().await.expect("list_workers");
+    assert!(listed.iter().find(|w| w.id == id).expect("worker listed").disk_usage.is_none());
+
+    let usage = WorkerDiskUsage {
+        run_workspaces: 2,
+        run_workspace_bytes: 4096,
+        job_workspaces: 1,
+        job_workspace_bytes: 5 * 1024 * 1024 * 1024,
+        code_cache_bytes: 1024,
+        swept_entries: 3,
+        swept_bytes: 2048,
+        measured_at: Utc::now(),
+    };
+    rec.disk_usage = Some(usage.clone());
+    store.heartbeat(&rec).await.expect("heartbeat");
+    // A heartbeat sent before the next sweep carries none and keeps the last report.
+    rec.disk_usage = None;
+    store.heartbeat(&rec).await.expect("heartbeat");
+    let listed = store.list_workers().await.expect("list_workers");
+    let got = listed.iter().find(|w| w.id == id).expect("worker listed");
+    assert_eq!(got.disk_usage, Some(usage));
+}
+
 async fn state_failed_records_exit_and_error(store: StoreRef) {
     let job = seed_job(&store, Some("* * * * *"), true).await;
     store
@@ -1659,6 +2067,23 @@ async fn claim_carries_http_snapshot(store: StoreRef) {
                 headers: None,
                 body: Some("hi".to_string()),
                 timeout_sec: Some(3),
+                tls: Some(HttpTlsConfig {
+                    client_cert: Some("secret:svc-cert".to_string()),
+                    client_key: Some("secret:svc-key".to_string()),
+                    ca_bundle: Some("secret:internal-ca".to_string()),
+                    insecure_skip_verify: false,
+                }),
+                signing: Some(HttpSigningConfig {
+                    algorithm: SigningAlgorithm::HmacSha256,
+                    key: "secret:hook-key".to_string(),
+                    header: Some("X-Hub-Signature-256".to_string()),
+                    timestamp_header: None,
+                    canonical: Some("{body}".to_string()),
+                    prefix: Some("sha256=".to_string()),
+                    encoding: Default::default(),
+                    region: None,
+                    service: None,
+                }),
             },
             1,
             MisfirePolicy::RunImmediately,
@@ -1680,32 +2105,68 @@ async fn claim_carries_http_snapshot(store: StoreRef) {
         .expect("claim must return a usable config snapshot");
     match &snap.meta {
         ExecutableConfigSnapshotMeta::Http {
-            method, url, body, ..
+            method,
+            url,
+            body,
+            tls,
+            signing,
+            ..
         } => {
             assert_eq!(method, "POST");
             assert!(url.contains("example.test"));
             assert_eq!(body.as_deref(), Some("hi"));
+            let tls = tls.as_ref().expect("tls options carried into the snapshot");
+            assert_eq!(tls.client_cert.as_deref(), Some("secret:svc-cert"));
+            assert_eq!(tls.ca_bundle.as_deref(), Some("secret:internal-ca"));
+            let signing = signing.as_ref().expect("signing options carried into the snapshot");
+            assert_eq!(signing.algorithm, SigningAlgorithm::HmacSha256);
+            assert_eq!(signing.key, "secret:hook-key");
+            assert_eq!(signing.canonical.as_deref(), Some("{body}"));
         }
         other => panic!("expected an Http snapshot, got {}", other.type_of_str()),
     }
 }
 
-async fn claim_carries_python_snapshot(store: StoreRef) {
+async fn claim_carries_sqlite_snapshot(store: StoreRef) {
     let job = store
-        .create_job(DEFAULT_TENANT_ID, 
-            "py-job",
+        .create_job(DEFAULT_TENANT_ID,
+            "sqlite-job",
             Some("* * * * *".to_string()),
-            RunnerConfig::Python {
-                module: "mymod".to_string(),
-                class_name: "MyTask".to_string(),
-                timeout_sec: Some(10),
-            },
+            shell(),
             1,
             MisfirePolicy::RunImmediately,
             RetryConfig::default(),
         )
         .await
         .expect("create_job");
+    // Switching runner kinds replaces the runner row.
+    let cfg = RunnerConfig::Sqlite {
+        path: "/var/lib/app/app.db".to_string(),
+        query: "DELETE FROM sessions WHERE expires_at < :scheduled_for".to_string(),
+        timeout_sec: Some(30),
+        busy_timeout_sec: Some(2),
+        output: Some(DbOutputConfig {
+            format: DbOutputFormat::Csv,
+            ..Default::default()
+        }),
+    };
+    store
+        .update_job(job.id, None, None, Some(cfg), None, None, None)
+        .await
+        .expect("update_job");
+    let got = store.get_job(job.id, None).await.expect("get_job");
+    match &got.runner_cfg {
+        RunnerConfig::Sqlite {
+            path,
+            busy_timeout_sec,
+            ..
+        } => {
+            assert_eq!(path, "/var/lib/app/app.db");
+            assert_eq!(*busy_timeout_sec, Some(2));
+        }
+        other => panic!("expected a Sqlite runner, got {}", other.type_of_str()),
+    }
+
     store.enable_job(job.id).await.expect("enable_job");
     store
         .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(10))
@@ -1719,25 +2180,642 @@ async fn claim_carries_python_snapshot(store: StoreRef) {
         .as_ref()
         .expect("claim must return a usable config snapshot");
     match &snap.meta {
-        ExecutableConfigSnapshotMeta::Python {
-            module, class_name, ..
+        ExecutableConfigSnapshotMeta::Sqlite {
+            path,
+            query,
+            timeout_sec,
+            busy_timeout_sec,
+            output,
         } => {
-            assert_eq!(module, "mymod");
-            assert_eq!(class_name, "MyTask");
+            assert_eq!(path, "/var/lib/app/app.db");
+            assert!(query.contains(":scheduled_for"));
+            assert_eq!(*timeout_sec, Some(30));
+            assert_eq!(*busy_timeout_sec, Some(2));
+            assert_eq!(output.as_ref().map(|o| o.format), Some(DbOutputFormat::Csv));
         }
-        other => panic!("expected a Python snapshot, got {}", other.type_of_str()),
+        other => panic!("expected a Sqlite snapshot, got {}", other.type_of_str()),
     }
 }
 
-async fn claim_carries_node_snapshot(store: StoreRef) {
+async fn claim_carries_pgsql_snapshot(store: StoreRef) {
+    let cfg = store
+        .create_db_config(
+            DEFAULT_TENANT_ID,
+            DbEngine::PgSql,
+            "reporting",
+            "pg.example.test",
+            5433,
+            "reporter",
+            "secret:pg-pass",
+            "warehouse",
+            Some(3),
+        )
+        .await
+        .expect("create pgsql config");
     let job = store
-        .create_job(DEFAULT_TENANT_ID, 
-            "node-job",
-            Some("* * * * *".to_string()),
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "pg-report",
+            Some("* * * * *".to_string()),
+            RunnerConfig::PgSql {
+                config_id: cfg.id,
+                query: "SELECT 1".to_string(),
+                timeout_sec: Some(5),
+                output: Some(DbOutputConfig {
+                    format: DbOutputFormat::Csv,
+                    max_rows: Some(50),
+                    max_bytes: None,
+                }),
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    match store.get_job(job.id, None).await.expect("get_job").runner_cfg {
+        RunnerConfig::PgSql { output, .. } => {
+            assert_eq!(output.expect("output options persisted").max_rows, Some(50));
+        }
+        other => panic!("expected a pgsql runner, got {}", other.type_of_str()),
+    }
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let claimed = store.claim_job_runs(worker, 1).await.expect("claim_job_runs");
+    assert_eq!(claimed.len(), 1);
+    let snap = claimed[0]
+        .snapshot
+        .as_ref()
+        .expect("claim must return a usable config snapshot");
+    match &snap.meta {
+        ExecutableConfigSnapshotMeta::PgSql {
+            config_id,
+            host,
+            port,
+            password_secret,
+            pool_size,
+            query,
+            output,
+            ..
+        } => {
+            assert_eq!(*config_id, cfg.id, "snapshot names its config (pool key)");
+            assert_eq!(*pool_size, Some(3));
+            assert_eq!(host, "pg.example.test");
+            assert_eq!(*port, 5433);
+            assert_eq!(password_secret, "secret:pg-pass", "snapshot keeps the reference");
+            assert_eq!(query, "SELECT 1");
+            let output = output.as_ref().expect("output options carried into the snapshot");
+            assert_eq!(output.format, DbOutputFormat::Csv);
+            assert_eq!(output.max_rows, Some(50));
+        }
+        other => panic!("expected a PgSql snapshot, got {}", other.type_of_str()),
+    }
+}
+
+async fn claim_carries_wasm_snapshot(store: StoreRef) {
+    let cfg = RunnerConfig::Wasm {
+        module: WasmModuleSource::Stored {
+            sha256: "ab".repeat(32),
+        },
+        timeout_sec: Some(20),
+        fuel: Some(5_000_000),
+        max_memory_mb: Some(64),
+        preopens: vec![WasmPreopen {
+            host_path: "/srv/reports".to_string(),
+            guest_path: "/reports".to_string(),
+            read_only: true,
+        }],
+        args: vec!["--since".to_string(), "1d".to_string()],
+    };
+    let job = store
+        .create_job(DEFAULT_TENANT_ID,
+            "wasm-job",
+            Some("* * * * *".to_string()),
+            cfg,
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    let got = store.get_job(job.id, None).await.expect("get_job");
+    match &got.runner_cfg {
+        RunnerConfig::Wasm { module, fuel, .. } => {
+            assert_eq!(module.sha256(), Some("ab".repeat(32).as_str()));
+            assert_eq!(*fuel, Some(5_000_000));
+        }
+        other => panic!("expected a Wasm runner, got {}", other.type_of_str()),
+    }
+    store
+        .set_job_env(job.id, HashMap::from([("REGION".to_string(), "eu".to_string())]))
+        .await
+        .expect("set_job_env");
+
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let claimed = store.claim_job_runs(worker, 1).await.expect("claim_job_runs");
+    assert_eq!(claimed.len(), 1);
+    let snap = claimed[0]
+        .snapshot
+        .as_ref()
+        .expect("claim must return a usable config snapshot");
+    match &snap.meta {
+        ExecutableConfigSnapshotMeta::Wasm {
+            module,
+            timeout_sec,
+            fuel,
+            max_memory_mb,
+            preopens,
+            args,
+            env,
+        } => {
+            assert_eq!(module.sha256(), Some("ab".repeat(32).as_str()));
+            assert_eq!(*timeout_sec, Some(20));
+            assert_eq!(*fuel, Some(5_000_000));
+            assert_eq!(*max_memory_mb, Some(64));
+            assert_eq!(preopens.len(), 1);
+            assert!(preopens[0].read_only);
+            assert_eq!(preopens[0].guest_path, "/reports");
+            assert_eq!(args, &["--since", "1d"]);
+            assert_eq!(env.get("REGION").map(String::as_str), Some("eu"));
+        }
+        other => panic!("expected a Wasm snapshot, got {}", other.type_of_str()),
+    }
+
+    // Switching to an on-disk module replaces the runner row.
+    let cfg = RunnerConfig::Wasm {
+        module: WasmModuleSource::Path {
+            path: "/opt/jobs/report.wasm".to_string(),
+        },
+        timeout_sec: None,
+        fuel: None,
+        max_memory_mb: None,
+        preopens: Vec::new(),
+        args: Vec::new(),
+    };
+    store
+        .update_job(job.id, None, None, Some(cfg), None, None, None)
+        .await
+        .expect("update_job");
+    let got = store.get_job(job.id, None).await.expect("get_job");
+    match &got.runner_cfg {
+        RunnerConfig::Wasm {
+            module, preopens, ..
+        } => {
+            assert_eq!(module.path(), Some("/opt/jobs/report.wasm"));
+            assert!(module.sha256().is_none());
+            assert!(preopens.is_empty());
+        }
+        other => panic!("expected a Wasm runner, got {}", other.type_of_str()),
+    }
+}
+
+async fn claim_carries_plugin_snapshot(store: StoreRef) {
+    let config = serde_json::json!({"target": "prod", "token": "secret:deploy-token", "replicas": 3});
+    let cfg = RunnerConfig::Plugin {
+        plugin: "deploy".to_string(),
+        config: config.clone(),
+        timeout_sec: Some(45),
+    };
+    let job = store
+        .create_job(DEFAULT_TENANT_ID,
+            "plugin-job",
+            Some("* * * * *".to_string()),
+            cfg,
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    match &store.get_job(job.id, None).await.expect("get_job").runner_cfg {
+        RunnerConfig::Plugin { plugin, config: got, .. } => {
+            assert_eq!(plugin, "deploy");
+            assert_eq!(got, &config);
+        }
+        other => panic!("expected a Plugin runner, got {}", other.type_of_str()),
+    }
+    store
+        .set_job_env(job.id, HashMap::from([("REGION".to_string(), "eu".to_string())]))
+        .await
+        .expect("set_job_env");
+
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let claimed = store.claim_job_runs(worker, 1).await.expect("claim_job_runs");
+    assert_eq!(claimed.len(), 1);
+    let snap = claimed[0]
+        .snapshot
+        .as_ref()
+        .expect("claim must return a usable config snapshot");
+    assert_eq!(snap.meta.runner_key(), "plugin:deploy");
+    match &snap.meta {
+        ExecutableConfigSnapshotMeta::Plugin {
+            plugin,
+            config: got,
+            timeout_sec,
+            env,
+        } => {
+            assert_eq!(plugin, "deploy");
+            assert_eq!(got, &config);
+            assert_eq!(*timeout_sec, Some(45));
+            assert_eq!(env.get("REGION").map(String::as_str), Some("eu"));
+        }
+        other => panic!("expected a Plugin snapshot, got {}", other.type_of_str()),
+    }
+
+    // Switching plugins replaces the runner row.
+    let cfg = RunnerConfig::Plugin {
+        plugin: "notify".to_string(),
+        config: serde_json::json!({}),
+        timeout_sec: None,
+    };
+    store
+        .update_job(job.id, None, None, Some(cfg), None, None, None)
+        .await
+        .expect("update_job");
+    match &store.get_job(job.id, None).await.expect("get_job").runner_cfg {
+        RunnerConfig::Plugin {
+            plugin,
+            config,
+            timeout_sec,
+        } => {
+            assert_eq!(plugin, "notify");
+            assert_eq!(config, &serde_json::json!({}));
+            assert!(timeout_sec.is_none());
+        }
+        other => panic!("expected a Plugin runner, got {}", other.type_of_str()),
+    }
+}
+
+async fn claim_carries_python_snapshot(store: StoreRef) {
+    let job = store
+        .create_job(DEFAULT_TENANT_ID, 
+            "py-job",
+            Some("* * * * *".to_string()),
+            RunnerConfig::Python {
+                module: "mymod".to_string(),
+                class_name: "MyTask".to_string(),
+                timeout_sec: Some(10),
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let claimed = store.claim_job_runs(worker, 1).await.expect("claim_job_runs");
+    assert_eq!(claimed.len(), 1);
+    let snap = claimed[0]
+        .snapshot
+        .as_ref()
+        .expect("claim must return a usable config snapshot");
+    match &snap.meta {
+        ExecutableConfigSnapshotMeta::Python {
+            module, class_name, ..
+        } => {
+            assert_eq!(module, "mymod");
+            assert_eq!(class_name, "MyTask");
+        }
+        other => panic!("expected a Python snapshot, got {}", other.type_of_str()),
+    }
+}
+
+async fn claim_carries_node_snapshot(store: StoreRef) {
+    let job = store
+        .create_job(DEFAULT_TENANT_ID, 
+            "node-job",
+            Some("* * * * *".to_string()),
+            RunnerConfig::Node {
+                module: "mymod".to_string(),
+                function_name: "run".to_string(),
+                timeout_sec: Some(10),
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let claimed = store.claim_job_runs(worker, 1).await.expect("claim_job_runs");
+    assert_eq!(claimed.len(), 1);
+    let snap = claimed[0]
+        .snapshot
+        .as_ref()
+        .expect("claim must return a usable config snapshot");
+    match &snap.meta {
+        ExecutableConfigSnapshotMeta::Node {
+            module,
+            function_name,
+            ..
+        } => {
+            assert_eq!(module, "mymod");
+            assert_eq!(function_name, "run");
+        }
+        other => panic!("expected a Node snapshot, got {}", other.type_of_str()),
+    }
+}
+
+async fn claim_carries_workspace_mode(store: StoreRef) {
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "workspace-job",
+            Some("* * * * *".to_string()),
+            RunnerConfig::Shell {
+                command: "make".to_string(),
+                working_dir: None,
+                code: None,
+                workspace: WorkspaceMode::Job,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    match store.get_job(job.id, None).await.expect("get_job").runner_cfg {
+        RunnerConfig::Shell { workspace, .. } => assert_eq!(workspace, WorkspaceMode::Job),
+        other => panic!("expected a Shell runner, got {}", other.type_of_str()),
+    }
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let claimed = store.claim_job_runs(worker, 1).await.expect("claim_job_runs");
+    match &claimed[0].snapshot.as_ref().expect("snapshot").meta {
+        ExecutableConfigSnapshotMeta::Shell { workspace, .. } => {
+            assert_eq!(*workspace, WorkspaceMode::Job)
+        }
+        other => panic!("expected a Shell snapshot, got {}", other.type_of_str()),
+    }
+
+    // Switching runner keeps the mode the new config asks for.
+    let cfg = RunnerConfig::Node {
+        module: "mymod".to_string(),
+        function_name: "run".to_string(),
+        timeout_sec: None,
+        transport: RuntimeTransport::File,
+        max_runs: None,
+        secrets: vec![],
+        code: None,
+        workspace: WorkspaceMode::Job,
+        secret_delivery: SecretDelivery::Env,
+    };
+    store
+        .update_job(job.id, None, None, Some(cfg), None, None, None)
+        .await
+        .expect("update_job");
+    let listed = store.list_jobs(None).await.expect("list_jobs");
+    match &listed.iter().find(|j| j.id == job.id).expect("job listed").runner_cfg {
+        RunnerConfig::Node { workspace, .. } => assert_eq!(*workspace, WorkspaceMode::Job),
+        other => panic!("expected a Node runner, got {}", other.type_of_str()),
+    }
+    let cfg = RunnerConfig::Node {
+        module: "mymod".to_string(),
+        function_name: "run".to_string(),
+        timeout_sec: None,
+        transport: RuntimeTransport::File,
+        max_runs: None,
+        secrets: vec![],
+        code: None,
+        workspace: WorkspaceMode::Run,
+        secret_delivery: SecretDelivery::Env,
+    };
+    store
+        .update_job(job.id, None, None, Some(cfg), None, None, None)
+        .await
+        .expect("update_job");
+    match store.get_job(job.id, None).await.expect("get_job").runner_cfg {
+        RunnerConfig::Node { workspace, .. } => assert_eq!(workspace, WorkspaceMode::Run),
+        other => panic!("expected a Node runner, got {}", other.type_of_str()),
+    }
+}
+
+async fn claim_carries_secret_delivery(store: StoreRef) {
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "secret-files-job",
+            Some("* * * * *".to_string()),
+            RunnerConfig::Python {
+                module: "mymod".to_string(),
+                class_name: "MyTask".to_string(),
+                timeout_sec: None,
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Files,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    match store.get_job(job.id, None).await.expect("get_job").runner_cfg {
+        RunnerConfig::Python {
+            secret_delivery, ..
+        } => assert_eq!(secret_delivery, SecretDelivery::Files),
+        other => panic!("expected a Python runner, got {}", other.type_of_str()),
+    }
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let claimed = store.claim_job_runs(worker, 1).await.expect("claim_job_runs");
+    match &claimed[0].snapshot.as_ref().expect("snapshot").meta {
+        ExecutableConfigSnapshotMeta::Python {
+            secret_delivery, ..
+        } => assert_eq!(*secret_delivery, SecretDelivery::Files),
+        other => panic!("expected a Python snapshot, got {}", other.type_of_str()),
+    }
+
+    // Switching runner keeps the delivery the new config asks for, files or the default.
+    let cfg = RunnerConfig::Shell {
+        command: "deploy".to_string(),
+        working_dir: None,
+        code: None,
+        workspace: WorkspaceMode::Run,
+        secret_delivery: SecretDelivery::Files,
+    };
+    store
+        .update_job(job.id, None, None, Some(cfg), None, None, None)
+        .await
+        .expect("update_job");
+    let listed = store.list_jobs(None).await.expect("list_jobs");
+    match &listed.iter().find(|j| j.id == job.id).expect("job listed").runner_cfg {
+        RunnerConfig::Shell {
+            secret_delivery, ..
+        } => assert_eq!(*secret_delivery, SecretDelivery::Files),
+        other => panic!("expected a Shell runner, got {}", other.type_of_str()),
+    }
+    let cfg = RunnerConfig::Node {
+        module: "mymod".to_string(),
+        function_name: "run".to_string(),
+        timeout_sec: None,
+        transport: RuntimeTransport::File,
+        max_runs: None,
+        secrets: vec![],
+        code: None,
+        workspace: WorkspaceMode::Run,
+        secret_delivery: SecretDelivery::Env,
+    };
+    store
+        .update_job(job.id, None, None, Some(cfg), None, None, None)
+        .await
+        .expect("update_job");
+    match store.get_job(job.id, None).await.expect("get_job").runner_cfg {
+        RunnerConfig::Node {
+            secret_delivery, ..
+        } => assert_eq!(secret_delivery, SecretDelivery::Env),
+        other => panic!("expected a Node runner, got {}", other.type_of_str()),
+    }
+}
+
+async fn claim_carries_resident_runtime_snapshot(store: StoreRef) {
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "resident-job",
+            Some("* * * * *".to_string()),
+            RunnerConfig::Python {
+                module: "mymod".to_string(),
+                class_name: "run".to_string(),
+                timeout_sec: None,
+                transport: RuntimeTransport::Socket,
+                max_runs: Some(25),
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    match store.get_job(job.id, None).await.expect("get_job").runner_cfg {
+        RunnerConfig::Python {
+            transport,
+            max_runs,
+            ..
+        } => {
+            assert_eq!(transport, RuntimeTransport::Socket);
+            assert_eq!(max_runs, Some(25));
+        }
+        other => panic!("expected a Python runner, got {}", other.type_of_str()),
+    }
+
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let claimed = store.claim_job_runs(worker, 1).await.expect("claim_job_runs");
+    assert_eq!(claimed.len(), 1);
+    match &claimed[0].snapshot.as_ref().expect("snapshot").meta {
+        ExecutableConfigSnapshotMeta::Python {
+            transport,
+            max_runs,
+            ..
+        } => {
+            assert_eq!(*transport, RuntimeTransport::Socket);
+            assert_eq!(*max_runs, Some(25));
+        }
+        other => panic!("expected a Python snapshot, got {}", other.type_of_str()),
+    }
+
+    let cfg = RunnerConfig::Python {
+        module: "mymod".to_string(),
+        class_name: "run".to_string(),
+        timeout_sec: None,
+        transport: RuntimeTransport::File,
+        max_runs: None,
+        secrets: vec![],
+        code: None,
+        workspace: WorkspaceMode::Run,
+        secret_delivery: SecretDelivery::Env,
+    };
+    store
+        .update_job(job.id, None, None, Some(cfg), None, None, None)
+        .await
+        .expect("update_job");
+    match store.get_job(job.id, None).await.expect("get_job").runner_cfg {
+        RunnerConfig::Python {
+            transport,
+            max_runs,
+            ..
+        } => {
+            assert_eq!(transport, RuntimeTransport::File);
+            assert_eq!(max_runs, None);
+        }
+        other => panic!("expected a Python runner, got {}", other.type_of_str()),
+    }
+}
+
+async fn claim_carries_runtime_declared_secrets(store: StoreRef) {
+    let declared = vec!["db_password".to_string(), "api_token".to_string()];
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "secrets-job",
+            Some("* * * * *".to_string()),
             RunnerConfig::Node {
                 module: "mymod".to_string(),
                 function_name: "run".to_string(),
-                timeout_sec: Some(10),
+                timeout_sec: None,
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: declared.clone(),
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
             },
             1,
             MisfirePolicy::RunImmediately,
@@ -1745,6 +2823,11 @@ async fn claim_carries_node_snapshot(store: StoreRef) {
         )
         .await
         .expect("create_job");
+    match store.get_job(job.id, None).await.expect("get_job").runner_cfg {
+        RunnerConfig::Node { secrets, .. } => assert_eq!(secrets, declared),
+        other => panic!("expected a Node runner, got {}", other.type_of_str()),
+    }
+
     store.enable_job(job.id).await.expect("enable_job");
     store
         .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(10))
@@ -1753,21 +2836,30 @@ async fn claim_carries_node_snapshot(store: StoreRef) {
     let worker = seed_worker(&store).await;
     let claimed = store.claim_job_runs(worker, 1).await.expect("claim_job_runs");
     assert_eq!(claimed.len(), 1);
-    let snap = claimed[0]
-        .snapshot
-        .as_ref()
-        .expect("claim must return a usable config snapshot");
-    match &snap.meta {
-        ExecutableConfigSnapshotMeta::Node {
-            module,
-            function_name,
-            ..
-        } => {
-            assert_eq!(module, "mymod");
-            assert_eq!(function_name, "run");
-        }
+    match &claimed[0].snapshot.as_ref().expect("snapshot").meta {
+        ExecutableConfigSnapshotMeta::Node { secrets, .. } => assert_eq!(secrets, &declared),
         other => panic!("expected a Node snapshot, got {}", other.type_of_str()),
     }
+
+    let cfg = RunnerConfig::Node {
+        module: "mymod".to_string(),
+        function_name: "run".to_string(),
+        timeout_sec: None,
+        transport: RuntimeTransport::File,
+        max_runs: None,
+        secrets: vec!["api_token".to_string()],
+        code: None,
+        workspace: WorkspaceMode::Run,
+        secret_delivery: SecretDelivery::Env,
+    };
+    store
+        .update_job(job.id, None, None, Some(cfg), None, None, None)
+        .await
+        .expect("update_job");
+    match store.get_job(job.id, None).await.expect("get_job").runner_cfg {
+        RunnerConfig::Node { secrets, .. } => assert_eq!(secrets, vec!["api_token".to_string()]),
+        other => panic!("expected a Node runner, got {}", other.type_of_str()),
+    }
 }
 
 async fn env_set_get_roundtrip(store: StoreRef) {
@@ -1929,108 +3021,546 @@ async fn retry_reschedule_requeues(store: StoreRef) {
             },
         )
         .await
-        .expect("reschedule_for_retry");
+        .expect("reschedule_for_retry");
+
+    let runs = store
+        .list_recent_runs(None, None, None, Some(job), None, None)
+        .await
+        .expect("list_recent_runs");
+    let r = runs.iter().find(|r| r.id == claimed[0].id).expect("present");
+    assert!(
+        matches!(r.state, JobRunState::Queued),
+        "a rescheduled run returns to queued"
+    );
+    assert_eq!(r.attempt, 2, "attempt is bumped");
+    assert!(r.worker_id.is_none(), "owner is cleared on requeue");
+
+    // Scheduled in the future, so it is not yet claimable.
+    let w2 = seed_worker(&store).await;
+    let again = store.claim_job_runs(w2, 10).await.expect("claim_job_runs");
+    assert!(
+        again.is_empty(),
+        "a future-scheduled retry is not claimable yet"
+    );
+}
+
+async fn retry_on_roundtrips(store: StoreRef) {
+    let retry_on = RetryOn {
+        http_statuses: Some(vec![409, 503]),
+        exit_codes: Some(vec![3]),
+        error_types: vec!["LockTimeout".into()],
+    };
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "retry-on",
+            Some("* * * * *".to_string()),
+            shell(),
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig {
+                max_attempts: 3,
+                retry_on: retry_on.clone(),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("create_job");
+    assert_eq!(job.retry_on, retry_on);
+    let got = store.get_job(job.id, None).await.expect("get_job");
+    assert_eq!(got.retry_on, retry_on);
+
+    // The claim-time snapshot carries the classification the worker applies.
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(10))
+        .await
+        .expect("insert run");
+    let worker = seed_worker(&store).await;
+    let claimed = store.claim_job_runs(worker, 1).await.expect("claim_job_runs");
+    let snap = claimed[0].snapshot.as_ref().expect("snapshot");
+    assert_eq!(snap.retry_on, retry_on);
+
+    // An update replaces it; defaults (`{}`) decode back to the built-in sets.
+    let updated = store
+        .update_job(job.id, None, None, None, None, None, Some(RetryConfig::default()))
+        .await
+        .expect("update_job");
+    assert_eq!(updated.retry_on, RetryOn::default());
+}
+
+async fn secrets_roundtrip(store: StoreRef) {
+    let id = store
+        .upsert_secret(DEFAULT_TENANT_ID, "db-pass", b"CT", b"NONCE", "xchacha20poly1305", b"WRAPPED", 1)
+        .await
+        .expect("upsert_secret");
+
+    let by_name = store
+        .get_secret_by_name(DEFAULT_TENANT_ID, "db-pass")
+        .await
+        .expect("get_secret_by_name")
+        .expect("present");
+    assert_eq!(by_name.id, id);
+    assert_eq!(by_name.value_ct, b"CT".to_vec());
+    assert_eq!(by_name.value_nonce, b"NONCE".to_vec());
+    assert_eq!(by_name.dek_wrapped, b"WRAPPED".to_vec());
+    assert_eq!(by_name.aead_algo, "xchacha20poly1305");
+    assert_eq!(by_name.kek_version, 1);
+
+    let by_id = store.get_secret(id, None).await.expect("get_secret").expect("present");
+    assert_eq!(by_id.name, "db-pass");
+
+    // Listing exposes metadata only; SecretMeta has no ciphertext field.
+    let names = store.list_secret_names(None).await.expect("list_secret_names");
+    assert!(names.iter().any(|m| m.name == "db-pass" && m.id == id));
+}
+
+async fn secrets_replace(store: StoreRef) {
+    let id1 = store
+        .upsert_secret(DEFAULT_TENANT_ID, "k", b"v1", b"n1", "a", b"w1", 1)
+        .await
+        .expect("upsert");
+    let id2 = store
+        .upsert_secret(DEFAULT_TENANT_ID, "k", b"v2", b"n2", "a", b"w2", 2)
+        .await
+        .expect("upsert");
+    assert_eq!(id1, id2, "upsert by name keeps the same id");
+
+    let s = store
+        .get_secret_by_name(DEFAULT_TENANT_ID, "k")
+        .await
+        .expect("get")
+        .expect("present");
+    assert_eq!(s.value_ct, b"v2".to_vec());
+    assert_eq!(s.kek_version, 2);
+}
+
+async fn secrets_delete(store: StoreRef) {
+    let id = store
+        .upsert_secret(DEFAULT_TENANT_ID, "d", b"x", b"n", "a", b"w", 1)
+        .await
+        .expect("upsert");
+    store.delete_secret(id).await.expect("delete_secret");
+    assert!(store.get_secret(id, None).await.expect("get").is_none());
+}
+
+async fn secrets_rewrap_and_retire(store: StoreRef) {
+    // Two KEK versions; a secret wrapped under v1.
+    store.insert_kek_version(1, "active").await.expect("insert v1");
+    store.insert_kek_version(2, "active").await.expect("insert v2");
+    let id = store
+        .upsert_secret(DEFAULT_TENANT_ID, "rot", b"CT", b"NONCE", "xchacha20poly1305", b"WRAP1", 1)
+        .await
+        .expect("upsert_secret");
+
+    // Re-wrap the DEK under v2: value ciphertext unchanged, wrapped DEK + version updated.
+    store.rewrap_secret(id, 1, b"WRAP2", 2).await.expect("rewrap_secret");
+    let s = store.get_secret(id, None).await.expect("get").expect("present");
+    assert_eq!(s.kek_version, 2, "rewrapped to v2");
+    assert_eq!(s.dek_wrapped, b"WRAP2".to_vec(), "new wrapped DEK");
+    assert_eq!(s.value_ct, b"CT".to_vec(), "value ciphertext is untouched");
+
+    // Retire v1; it is marked retired with a timestamp, v2 stays active.
+    store.set_kek_version_state(1, "retired").await.expect("retire v1");
+    let versions = store.list_kek_versions().await.expect("list");
+    let v1 = versions.iter().find(|v| v.version == 1).expect("v1");
+    let v2 = versions.iter().find(|v| v.version == 2).expect("v2");
+    assert_eq!(v1.state, "retired");
+    assert!(v1.retired_at.is_some(), "retire stamps retired_at");
+    assert_eq!(v2.state, "active");
+}
+
+async fn secrets_versions(store: StoreRef) {
+    // Each set is a new version; the secret row carries the current one.
+    let id = store
+        .upsert_secret(DEFAULT_TENANT_ID, "ver", b"CT1", b"N1", "a", b"W1", 1)
+        .await
+        .expect("upsert v1");
+    for (ct, w) in [(b"CT2", b"W2"), (b"CT3", b"W3")] {
+        let again = store
+            .upsert_secret(DEFAULT_TENANT_ID, "ver", ct, b"N", "a", w, 1)
+            .await
+            .expect("upsert");
+        assert_eq!(again, id, "same secret, new version");
+    }
+    let s = store.get_secret(id, None).await.expect("get").expect("present");
+    assert_eq!(s.version, 3);
+    assert_eq!(s.value_ct, b"CT3".to_vec());
+    let versions = store.list_secret_versions(id).await.expect("list versions");
+    assert_eq!(
+        versions.iter().map(|v| v.version).collect::<Vec<_>>(),
+        vec![3, 2, 1],
+        "newest first"
+    );
+    let v1 = store.get_secret_version(id, 1).await.expect("get v1").expect("v1 kept");
+    assert_eq!(v1.value_ct, b"CT1".to_vec());
+    assert_eq!(v1.name, "ver");
+
+    // Rewrap an old version only: the current row is untouched, the oldest KEK drops.
+    store.rewrap_secret(id, 1, b"W1b", 2).await.expect("rewrap v1");
+    let v1 = store.get_secret_version(id, 1).await.expect("get v1").expect("v1");
+    assert_eq!((v1.kek_version, v1.dek_wrapped), (2, b"W1b".to_vec()));
+    let s = store.get_secret(id, None).await.expect("get").expect("present");
+    assert_eq!((s.kek_version, s.dek_wrapped), (1, b"W3".to_vec()));
+    let meta = store.list_secret_names(None).await.expect("names");
+    let meta = meta.iter().find(|m| m.id == id).expect("listed");
+    assert_eq!((meta.version, meta.kek_version, meta.oldest_kek_version), (3, 1, 1));
+
+    // Restore copies v1 into a new current version, remembering where it came from.
+    let restored = store.restore_secret_version(id, 1).await.expect("restore");
+    assert_eq!(restored, Some(4));
+    let s = store.get_secret(id, None).await.expect("get").expect("present");
+    assert_eq!((s.version, s.value_ct, s.kek_version), (4, b"CT1".to_vec(), 2));
+    let versions = store.list_secret_versions(id).await.expect("list versions");
+    assert_eq!(versions[0].version, 4);
+    assert_eq!(versions[0].restored_from, Some(1));
+    assert_eq!(
+        store.restore_secret_version(id, 99).await.expect("restore missing"),
+        None,
+        "no such version"
+    );
+
+    // Prune keeps the newest `keep` versions (never fewer than one).
+    assert_eq!(store.prune_secret_versions(id, 2).await.expect("prune"), 2);
+    let kept: Vec<u32> = store
+        .list_secret_versions(id)
+        .await
+        .expect("list versions")
+        .iter()
+        .map(|v| v.version)
+        .collect();
+    assert_eq!(kept, vec![4, 3]);
+    assert!(store.get_secret_version(id, 1).await.expect("get v1").is_none(), "pruned");
+    let meta = store.list_secret_names(None).await.expect("names");
+    let meta = meta.iter().find(|m| m.id == id).expect("listed");
+    assert_eq!(meta.oldest_kek_version, 1, "v3 still under KEK 1");
+    assert_eq!(store.prune_secret_versions(id, 0).await.expect("prune"), 1);
+    assert_eq!(store.list_secret_versions(id).await.expect("list").len(), 1);
+
+    // Delete takes the history with it.
+    store.delete_secret(id).await.expect("delete");
+    assert!(store.list_secret_versions(id).await.expect("list").is_empty());
+    assert!(store.get_secret_version(id, 4).await.expect("get").is_none());
+}
+
+async fn secrets_policy_and_last_use(store: StoreRef) {
+    let other = store.create_tenant("other-co").await.expect("create_tenant").id;
+    let id = store
+        .upsert_secret(DEFAULT_TENANT_ID, "rot", b"CT1", b"N1", "a", b"W1", 1)
+        .await
+        .expect("upsert");
+    let meta = store.get_secret_meta(id, None).await.expect("meta").expect("present");
+    assert_eq!((meta.tenant_id, meta.version), (DEFAULT_TENANT_ID, 1));
+    assert!(meta.expires_at.is_none() && meta.rotate_every_secs.is_none());
+    assert!(meta.last_resolved_at.is_none() && meta.last_resolved_run.is_none());
+    assert!(
+        store.get_secret_meta(id, Some(other)).await.expect("meta").is_none(),
+        "another tenant cannot read it"
+    );
+    let first_set = meta.value_set_at;
+
+    // The policy sticks across value changes; setting a value moves `value_set_at`.
+    let expires = DateTime::parse_from_rfc3339("2031-01-01T00:00:00Z")
+        .expect("date")
+        .with_timezone(&Utc);
+    store
+        .set_secret_policy(id, Some(expires), Some(86_400))
+        .await
+        .expect("set policy");
+    store
+        .upsert_secret(DEFAULT_TENANT_ID, "rot", b"CT2", b"N2", "a", b"W2", 1)
+        .await
+        .expect("upsert v2");
+    let meta = store.get_secret_meta(id, None).await.expect("meta").expect("present");
+    assert_eq!(meta.version, 2);
+    assert_eq!((meta.expires_at, meta.rotate_every_secs), (Some(expires), Some(86_400)));
+    assert!(meta.value_set_at >= first_set);
+    assert_eq!(
+        meta.rotation_due_at(),
+        Some(meta.value_set_at + Duration::seconds(86_400)),
+        "rotation comes before expiry"
+    );
+
+    // A resolve is recorded by name, within the tenant.
+    let run = Uuid::new_v4();
+    store
+        .record_secret_use(other, "rot", Uuid::new_v4())
+        .await
+        .expect("record other tenant");
+    assert!(
+        store
+            .get_secret_meta(id, None)
+            .await
+            .expect("meta")
+            .expect("present")
+            .last_resolved_run
+            .is_none(),
+        "another tenant's name does not match"
+    );
+    store.record_secret_use(DEFAULT_TENANT_ID, "rot", run).await.expect("record");
+    let listed = store.list_secret_names(None).await.expect("names");
+    let meta = listed.iter().find(|m| m.id == id).expect("listed");
+    assert_eq!(meta.last_resolved_run, Some(run));
+    assert!(meta.last_resolved_at.is_some());
+    assert_eq!(meta.rotate_every_secs, Some(86_400), "listing carries the policy");
+
+    store.set_secret_policy(id, None, None).await.expect("clear policy");
+    let meta = store.get_secret_meta(id, None).await.expect("meta").expect("present");
+    assert!(meta.expires_at.is_none() && meta.rotate_every_secs.is_none());
+    assert_eq!(meta.rotation_due_at(), None);
+}
+
+async fn secrets_references_indexed(store: StoreRef) {
+    let http = store
+        .create_job(DEFAULT_TENANT_ID,
+            "hook",
+            Some("* * * * *".to_string()),
+            RunnerConfig::Http {
+                method: "POST".to_string(),
+                url: "http://example.test/hook".to_string(),
+                headers: None,
+                body: None,
+                timeout_sec: None,
+                tls: Some(HttpTlsConfig {
+                    client_cert: None,
+                    client_key: Some("secret:svc-key".to_string()),
+                    ca_bundle: None,
+                    insecure_skip_verify: false,
+                }),
+                signing: Some(HttpSigningConfig {
+                    algorithm: SigningAlgorithm::HmacSha256,
+                    key: "secret:hook-key@2".to_string(),
+                    header: None,
+                    timestamp_header: None,
+                    canonical: None,
+                    prefix: None,
+                    encoding: Default::default(),
+                    region: None,
+                    service: None,
+                }),
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create http job");
+    store
+        .set_job_env(
+            http.id,
+            HashMap::from([
+                ("DB_PASS".to_string(), "secret:db-pass".to_string()),
+                ("PLAIN".to_string(), "not-a-ref".to_string()),
+            ]),
+        )
+        .await
+        .expect("set_job_env");
+    let py = store
+        .create_job(DEFAULT_TENANT_ID,
+            "py",
+            None,
+            RunnerConfig::Python {
+                module: "mymod".to_string(),
+                class_name: "MyTask".to_string(),
+                timeout_sec: None,
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: vec!["api-token".to_string()],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create python job");
+    let plugin = store
+        .create_job(DEFAULT_TENANT_ID,
+            "deploy",
+            None,
+            RunnerConfig::Plugin {
+                plugin: "deploy".to_string(),
+                config: serde_json::json!({ "targets": [{ "token": "secret:api-token" }] }),
+                timeout_sec: None,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create plugin job");
+    let db = store
+        .create_db_config(
+            DEFAULT_TENANT_ID,
+            DbEngine::PgSql,
+            "reporting",
+            "pg.example.test",
+            5432,
+            "reporter",
+            "secret:db-pass",
+            "warehouse",
+            None,
+        )
+        .await
+        .expect("create db config");
 
-    let runs = store
-        .list_recent_runs(None, None, None, Some(job), None, None)
+    // Another tenant's references stay in that tenant's index.
+    let other = store.create_tenant("other-co").await.expect("create_tenant").id;
+    let theirs = store
+        .create_job(other,
+            "theirs",
+            None,
+            shell(),
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
         .await
-        .expect("list_recent_runs");
-    let r = runs.iter().find(|r| r.id == claimed[0].id).expect("present");
-    assert!(
-        matches!(r.state, JobRunState::Queued),
-        "a rescheduled run returns to queued"
-    );
-    assert_eq!(r.attempt, 2, "attempt is bumped");
-    assert!(r.worker_id.is_none(), "owner is cleared on requeue");
-
-    // Scheduled in the future, so it is not yet claimable.
-    let w2 = seed_worker(&store).await;
-    let again = store.claim_job_runs(w2, 10).await.expect("claim_job_runs");
-    assert!(
-        again.is_empty(),
-        "a future-scheduled retry is not claimable yet"
-    );
-}
-
-async fn secrets_roundtrip(store: StoreRef) {
-    let id = store
-        .upsert_secret(DEFAULT_TENANT_ID, "db-pass", b"CT", b"NONCE", "xchacha20poly1305", b"WRAPPED", 1)
+        .expect("create other job");
+    store
+        .set_job_env(
+            theirs.id,
+            HashMap::from([("DB_PASS".to_string(), "secret:db-pass".to_string())]),
+        )
         .await
-        .expect("upsert_secret");
+        .expect("set_job_env");
 
-    let by_name = store
-        .get_secret_by_name(DEFAULT_TENANT_ID, "db-pass")
+    let index = arbiter_core::secret_references(store.as_ref(), DEFAULT_TENANT_ID)
         .await
-        .expect("get_secret_by_name")
-        .expect("present");
-    assert_eq!(by_name.id, id);
-    assert_eq!(by_name.value_ct, b"CT".to_vec());
-    assert_eq!(by_name.value_nonce, b"NONCE".to_vec());
-    assert_eq!(by_name.dek_wrapped, b"WRAPPED".to_vec());
-    assert_eq!(by_name.aead_algo, "xchacha20poly1305");
-    assert_eq!(by_name.kek_version, 1);
-
-    let by_id = store.get_secret(id, None).await.expect("get_secret").expect("present");
-    assert_eq!(by_id.name, "db-pass");
-
-    // Listing exposes metadata only; SecretMeta has no ciphertext field.
-    let names = store.list_secret_names(None).await.expect("list_secret_names");
-    assert!(names.iter().any(|m| m.name == "db-pass" && m.id == id));
-}
+        .expect("index");
+    let mut names: Vec<_> = index.keys().cloned().collect();
+    names.sort();
+    assert_eq!(names, vec!["api-token", "db-pass", "hook-key", "svc-key"]);
 
-async fn secrets_replace(store: StoreRef) {
-    let id1 = store
-        .upsert_secret(DEFAULT_TENANT_ID, "k", b"v1", b"n1", "a", b"w1", 1)
-        .await
-        .expect("upsert");
-    let id2 = store
-        .upsert_secret(DEFAULT_TENANT_ID, "k", b"v2", b"n2", "a", b"w2", 2)
-        .await
-        .expect("upsert");
-    assert_eq!(id1, id2, "upsert by name keeps the same id");
+    let fields = |name: &str| {
+        let mut f: Vec<_> = index[name]
+            .iter()
+            .map(|r| (r.referrer, r.id, r.field.clone(), r.version))
+            .collect();
+        f.sort_by(|a, b| a.2.cmp(&b.2));
+        f
+    };
+    assert_eq!(
+        fields("db-pass"),
+        vec![
+            (SecretReferrer::Job, http.id, "env.DB_PASS".to_string(), None),
+            (SecretReferrer::DbConfig, db.id, "passwordSecret".to_string(), None),
+        ]
+    );
+    assert_eq!(
+        fields("hook-key"),
+        vec![(SecretReferrer::Job, http.id, "signing.key".to_string(), Some(2))]
+    );
+    assert_eq!(
+        fields("svc-key"),
+        vec![(SecretReferrer::Job, http.id, "tls.clientKey".to_string(), None)]
+    );
+    assert_eq!(
+        fields("api-token"),
+        vec![
+            (SecretReferrer::Job, plugin.id, "config.targets[0].token".to_string(), None),
+            (SecretReferrer::Job, py.id, "secrets".to_string(), None),
+        ]
+    );
 
-    let s = store
-        .get_secret_by_name(DEFAULT_TENANT_ID, "k")
-        .await
-        .expect("get")
-        .expect("present");
-    assert_eq!(s.value_ct, b"v2".to_vec());
-    assert_eq!(s.kek_version, 2);
+    let index = arbiter_core::secret_references(store.as_ref(), other).await.expect("index");
+    assert_eq!(index.len(), 1);
+    assert_eq!(index["db-pass"].len(), 1);
+    assert_eq!(index["db-pass"][0].id, theirs.id);
 }
 
-async fn secrets_delete(store: StoreRef) {
+async fn secrets_audit(store: StoreRef) {
     let id = store
-        .upsert_secret(DEFAULT_TENANT_ID, "d", b"x", b"n", "a", b"w", 1)
+        .upsert_secret(DEFAULT_TENANT_ID, "audited", b"ct", b"n", "a", b"w", 1)
         .await
         .expect("upsert");
-    store.delete_secret(id).await.expect("delete_secret");
-    assert!(store.get_secret(id, None).await.expect("get").is_none());
-}
+    let base = DateTime::parse_from_rfc3339("2030-06-01T12:00:00Z")
+        .expect("date")
+        .with_timezone(&Utc);
+    let user = SecretActor::User {
+        user_id: Uuid::new_v4(),
+    };
+    let run = SecretActor::Run {
+        run_id: Uuid::new_v4(),
+        job_id: Uuid::new_v4(),
+    };
+    let node = SecretActor::Node {
+        node_id: Uuid::new_v4(),
+    };
+    let mut set = SecretAuditEvent::ok(
+        id,
+        DEFAULT_TENANT_ID,
+        "audited",
+        SecretAuditAction::Set,
+        user,
+    )
+    .with_version(1);
+    set.at = base;
+    let mut resolved = SecretAuditEvent::ok(
+        id,
+        DEFAULT_TENANT_ID,
+        "audited",
+        SecretAuditAction::Resolve,
+        run,
+    )
+    .with_version(1);
+    resolved.at = base + Duration::minutes(1);
+    let mut rotated = SecretAuditEvent::refused(
+        Some(id),
+        DEFAULT_TENANT_ID,
+        "audited",
+        SecretAuditAction::Rotate,
+        node,
+        SecretAuditOutcome::Failed,
+        "KEK version 1 not held by this node",
+    );
+    rotated.at = base + Duration::minutes(2);
+    // A resolve of a name that does not exist belongs to no secret.
+    let mut unknown = SecretAuditEvent::refused(
+        None,
+        DEFAULT_TENANT_ID,
+        "nope",
+        SecretAuditAction::Resolve,
+        run,
+        SecretAuditOutcome::NotFound,
+        "nope",
+    );
+    unknown.at = base + Duration::minutes(3);
+    for event in [&set, &resolved, &rotated, &unknown] {
+        store.append_secret_audit(event).await.expect("append");
+    }
 
-async fn secrets_rewrap_and_retire(store: StoreRef) {
-    // Two KEK versions; a secret wrapped under v1.
-    store.insert_kek_version(1, "active").await.expect("insert v1");
-    store.insert_kek_version(2, "active").await.expect("insert v2");
-    let id = store
-        .upsert_secret(DEFAULT_TENANT_ID, "rot", b"CT", b"NONCE", "xchacha20poly1305", b"WRAP1", 1)
+    // Newest first, every field round-trips.
+    let events = store.list_secret_audit(id, None, 10).await.expect("list");
+    assert_eq!(events, vec![rotated.clone(), resolved.clone(), set.clone()]);
+    // Paging: a limit, then the events before a time.
+    let events = store.list_secret_audit(id, None, 1).await.expect("list");
+    assert_eq!(events, vec![rotated.clone()]);
+    let events = store
+        .list_secret_audit(id, Some(rotated.at), 10)
         .await
-        .expect("upsert_secret");
-
-    // Re-wrap the DEK under v2: value ciphertext unchanged, wrapped DEK + version updated.
-    store.rewrap_secret(id, b"WRAP2", 2).await.expect("rewrap_secret");
-    let s = store.get_secret(id, None).await.expect("get").expect("present");
-    assert_eq!(s.kek_version, 2, "rewrapped to v2");
-    assert_eq!(s.dek_wrapped, b"WRAP2".to_vec(), "new wrapped DEK");
-    assert_eq!(s.value_ct, b"CT".to_vec(), "value ciphertext is untouched");
+        .expect("list");
+    assert_eq!(events, vec![resolved.clone(), set.clone()]);
 
-    // Retire v1; it is marked retired with a timestamp, v2 stays active.
-    store.set_kek_version_state(1, "retired").await.expect("retire v1");
-    let versions = store.list_kek_versions().await.expect("list");
-    let v1 = versions.iter().find(|v| v.version == 1).expect("v1");
-    let v2 = versions.iter().find(|v| v.version == 2).expect("v2");
-    assert_eq!(v1.state, "retired");
-    assert!(v1.retired_at.is_some(), "retire stamps retired_at");
-    assert_eq!(v2.state, "active");
+    // The trail outlives the secret, and is pruned on its own cutoff.
+    store.delete_secret(id).await.expect("delete");
+    assert_eq!(
+        store
+            .list_secret_audit(id, None, 10)
+            .await
+            .expect("list")
+            .len(),
+        3
+    );
+    let pruned = store
+        .prune_secret_audit(base + Duration::seconds(90))
+        .await
+        .expect("prune");
+    assert_eq!(pruned, 2, "the set and the resolve");
+    let events = store.list_secret_audit(id, None, 10).await.expect("list");
+    assert_eq!(events, vec![rotated]);
 }
 
 async fn secrets_kek_roundtrip(store: StoreRef) {
@@ -2089,9 +3619,9 @@ async fn secrets_ack_and_delete_shares(store: StoreRef) {
 
 async fn logs_append_read_size(store: StoreRef) {
     let run = Uuid::new_v4();
-    store.append_run_log(run, 1, 1, LogStream::Stdout, "a").await.expect("append 1");
-    store.append_run_log(run, 1, 2, LogStream::Stderr, "b").await.expect("append 2");
-    store.append_run_log(run, 1, 3, LogStream::Stdout, "cc").await.expect("append 3");
+    store.append_run_log(run, 1, 1, LogStream::Stdout, None, "a").await.expect("append 1");
+    store.append_run_log(run, 1, 2, LogStream::Stderr, None, "b").await.expect("append 2");
+    store.append_run_log(run, 1, 3, LogStream::Stdout, None, "cc").await.expect("append 3");
 
     // Forward read from the start, ascending, with the right stream tags.
     let all = store.read_run_log(run, 1, None, 10).await.expect("read all");
@@ -2116,6 +3646,22 @@ async fn logs_append_read_size(store: StoreRef) {
     assert_eq!(size.max_seq, Some(3));
 }
 
+async fn logs_records_keep_level(store: StoreRef) {
+    let run = Uuid::new_v4();
+    store.append_run_log(run, 1, 1, LogStream::Stdout, None, "raw\n").await.expect("append raw");
+    store
+        .append_run_log(run, 1, 2, LogStream::Stderr, Some(LogLevel::Warning), "disk 91% full\n")
+        .await
+        .expect("append record");
+
+    let all = store.read_run_log(run, 1, None, 10).await.expect("read");
+    assert_eq!(all[0].level, None, "raw output has no level");
+    assert_eq!(all[1].level, Some(LogLevel::Warning));
+    assert_eq!(all[1].content, "disk 91% full\n");
+    let tail = store.read_run_log_tail(run, 1, None, 1).await.expect("tail");
+    assert_eq!(tail[0].level, Some(LogLevel::Warning));
+}
+
 async fn logs_pruned_with_run(store: StoreRef) {
     let job = seed_job(&store, Some("* * * * *"), true).await;
     let old = Utc::now() - Duration::days(2);
@@ -2134,7 +3680,7 @@ async fn logs_pruned_with_run(store: StoreRef) {
         )
         .await
         .expect("finalize");
-    store.append_run_log(run.id, 1, 1, LogStream::Stdout, "hello").await.expect("append");
+    store.append_run_log(run.id, 1, 1, LogStream::Stdout, None, "hello").await.expect("append");
     assert_eq!(store.run_log_size(run.id, 1).await.expect("size").chunk_count, 1);
 
     // Pruning the run drops its chunks too (chunks are the run's output, pruned with it).
@@ -2328,12 +3874,14 @@ async fn config_create_get_list(store: StoreRef) {
             "appuser",
             "secret:pg-pass",
             "appdb",
+            Some(8),
         )
         .await
         .expect("create pgsql config");
     assert_eq!(pg.engine, DbEngine::PgSql);
     assert_eq!(pg.port, 5432);
     assert_eq!(pg.password_secret, "secret:pg-pass");
+    assert_eq!(pg.pool_size, Some(8));
 
     let my = store
         .create_db_config(
@@ -2345,6 +3893,7 @@ async fn config_create_get_list(store: StoreRef) {
             "root",
             "secret:my-pass",
             "shop",
+            None,
         )
         .await
         .expect("create mysql config");
@@ -2359,6 +3908,7 @@ async fn config_create_get_list(store: StoreRef) {
     assert_eq!(got.host, "pg.example.com");
     assert_eq!(got.username, "appuser");
     assert_eq!(got.database, "appdb");
+    assert_eq!(got.pool_size, Some(8), "pool size is persisted");
 
     let got_my = store
         .get_db_config(my.id, None)
@@ -2367,6 +3917,7 @@ async fn config_create_get_list(store: StoreRef) {
         .expect("present");
     assert_eq!(got_my.engine, DbEngine::MySql, "engine is read back per table");
     assert_eq!(got_my.port, 3306);
+    assert_eq!(got_my.pool_size, None, "unset pool size reads back as default");
 
     let all = store.list_db_configs(None).await.expect("list_db_configs");
     assert!(all.iter().any(|c| c.id == pg.id && c.engine == DbEngine::PgSql));
@@ -2380,7 +3931,7 @@ async fn config_list_ordered(store: StoreRef) {
         let store = store.clone();
         async move {
             store
-                .create_db_config(DEFAULT_TENANT_ID, engine, name, host, 5432, "u", "secret:p", "d")
+                .create_db_config(DEFAULT_TENANT_ID, engine, name, host, 5432, "u", "secret:p", "d", None)
                 .await
                 .expect("create");
         }
@@ -2416,13 +3967,14 @@ async fn config_partial_update(store: StoreRef) {
             "olduser",
             "secret:old",
             "olddb",
+            None,
         )
         .await
         .expect("create");
 
     // Change only host and port; everything else must be preserved.
     let updated = store
-        .update_db_config(cfg.id, None, Some("new-host"), Some(6543), None, None, None)
+        .update_db_config(cfg.id, None, Some("new-host"), Some(6543), None, None, None, None)
         .await
         .expect("update_db_config");
     assert_eq!(updated.host, "new-host", "host changed");
@@ -2432,6 +3984,7 @@ async fn config_partial_update(store: StoreRef) {
     assert_eq!(updated.password_secret, "secret:old", "password ref preserved");
     assert_eq!(updated.database, "olddb", "database preserved");
     assert_eq!(updated.engine, DbEngine::PgSql, "engine unchanged");
+    assert_eq!(updated.pool_size, None, "pool size untouched");
 
     // Change only the password reference and name.
     let updated = store
@@ -2443,11 +3996,13 @@ async fn config_partial_update(store: StoreRef) {
             None,
             Some("secret:new"),
             None,
+            Some(12),
         )
         .await
         .expect("update_db_config");
     assert_eq!(updated.name, "renamed");
     assert_eq!(updated.password_secret, "secret:new");
+    assert_eq!(updated.pool_size, Some(12), "pool size changed");
     assert_eq!(updated.host, "new-host", "earlier host edit persists");
     assert_eq!(updated.port, 6543, "earlier port edit persists");
 
@@ -2473,6 +4028,7 @@ async fn config_delete_soft_removes(store: StoreRef) {
             "u",
             "secret:p",
             "d",
+            None,
         )
         .await
         .expect("create");
@@ -2499,11 +4055,11 @@ async fn config_scoped_by_tenant(store: StoreRef) {
     let t2 = store.create_tenant("cfg-t2").await.expect("create_tenant");
 
     let c1 = store
-        .create_db_config(t1.id, DbEngine::PgSql, "t1-cfg", "h1", 5432, "u", "secret:p", "d")
+        .create_db_config(t1.id, DbEngine::PgSql, "t1-cfg", "h1", 5432, "u", "secret:p", "d", None)
         .await
         .expect("create");
     let c2 = store
-        .create_db_config(t2.id, DbEngine::PgSql, "t2-cfg", "h2", 5432, "u", "secret:p", "d")
+        .create_db_config(t2.id, DbEngine::PgSql, "t2-cfg", "h2", 5432, "u", "secret:p", "d", None)
         .await
         .expect("create");
 
@@ -2523,6 +4079,76 @@ async fn config_scoped_by_tenant(store: StoreRef) {
     );
 }
 
+async fn config_test_lifecycle(store: StoreRef) {
+    let cfg = store
+        .create_db_config(DEFAULT_TENANT_ID, DbEngine::PgSql, "probe", "h", 5432, "u", "secret:p", "d", None)
+        .await
+        .expect("create");
+    let test_id = store.request_config_test(cfg.id).await.expect("request_config_test");
+    assert!(
+        store.get_config_test(test_id).await.expect("get").is_none(),
+        "no result before a worker reports"
+    );
+
+    // One worker claims it, with the config resolved; a second claim finds nothing.
+    let worker = Uuid::new_v4();
+    let claimed = store.claim_config_tests(worker, 4, 60).await.expect("claim");
+    assert_eq!(claimed.len(), 1, "the pending test is claimed");
+    assert_eq!(claimed[0].id, test_id);
+    assert_eq!(claimed[0].config.id, cfg.id, "claim carries the config to probe");
+    assert_eq!(claimed[0].config.host, "h");
+    assert!(
+        store.claim_config_tests(Uuid::new_v4(), 4, 60).await.expect("claim").is_empty(),
+        "a claimed test is not handed out twice"
+    );
+
+    let result = ConfigTestResult {
+        ok: false,
+        connect_ms: None,
+        latency_ms: None,
+        error: Some(ConfigTestError {
+            code: "auth_failed".to_string(),
+            message: "password authentication failed".to_string(),
+        }),
+        worker_id: worker,
+        tested_at: Utc::now(),
+    };
+    store.finish_config_test(test_id, &result).await.expect("finish");
+    let got = store.get_config_test(test_id).await.expect("get").expect("finished");
+    assert_eq!(got.error, result.error, "structured error round-trips");
+    assert_eq!(got.worker_id, worker);
+    assert!(!got.ok);
+
+    // Pruning drops tests requested before the cutoff.
+    let pruned = store
+        .prune_config_tests(Utc::now() + Duration::seconds(1))
+        .await
+        .expect("prune");
+    assert_eq!(pruned, 1);
+    assert!(store.get_config_test(test_id).await.expect("get").is_none());
+}
+
+async fn config_test_claim_skips_stale_and_deleted(store: StoreRef) {
+    let cfg = store
+        .create_db_config(DEFAULT_TENANT_ID, DbEngine::MySql, "gone", "h", 3306, "u", "secret:p", "d", None)
+        .await
+        .expect("create");
+    store.request_config_test(cfg.id).await.expect("request_config_test");
+
+    // Past the caller's wait, nobody probes on its behalf.
+    assert!(
+        store.claim_config_tests(Uuid::new_v4(), 4, 0).await.expect("claim").is_empty(),
+        "a request older than max_age is not claimed"
+    );
+
+    // A config deleted after the request is not probed either.
+    store.delete_db_config(cfg.id).await.expect("delete");
+    assert!(
+        store.claim_config_tests(Uuid::new_v4(), 4, 60).await.expect("claim").is_empty(),
+        "a test for a deleted config is not claimed"
+    );
+}
+
 async fn durability_definitions_survive(handle: Box<dyn DurableHandle>) {
     let job_id = {
         let store = handle.open().await;
@@ -2615,3 +4241,282 @@ async fn leadership_stable_across_calls(pair: (StoreRef, StoreRef)) {
         );
     }
 }
+
+async fn modules_content_addressed_per_tenant(store: StoreRef) {
+    let other = store
+        .create_tenant("module-co")
+        .await
+        .expect("create_tenant")
+        .id;
+    let sha = "0f".repeat(32);
+    let first = store
+        .put_wasm_module(DEFAULT_TENANT_ID, &sha, b"\0asm-one")
+        .await
+        .expect("put");
+    assert_eq!(first.size_bytes, 8);
+    // Uploading the same hash again is a no-op that returns the stored module.
+    let again = store
+        .put_wasm_module(DEFAULT_TENANT_ID, &sha, b"\0asm-one")
+        .await
+        .expect("put again");
+    assert_eq!(again.created_at, first.created_at);
+    assert_eq!(store.list_wasm_modules(Some(DEFAULT_TENANT_ID)).await.expect("list").len(), 1);
+
+    assert_eq!(
+        store
+            .get_wasm_module(&sha, Some(DEFAULT_TENANT_ID))
+            .await
+            .expect("get")
+            .as_deref(),
+        Some(&b"\0asm-one"[..])
+    );
+    assert!(
+        store.get_wasm_module(&sha, Some(other)).await.expect("get").is_none(),
+        "another tenant cannot read the module"
+    );
+    assert!(store.list_wasm_modules(Some(other)).await.expect("list").is_empty());
+
+    // The same bytes in another tenant are that tenant's own copy.
+    store.put_wasm_module(other, &sha, b"\0asm-one").await.expect("put other");
+    assert_eq!(store.list_wasm_modules(None).await.expect("list all").len(), 2);
+    store
+        .delete_wasm_module(&sha, Some(other))
+        .await
+        .expect("delete other's copy");
+    assert!(
+        store
+            .get_wasm_module(&sha, Some(DEFAULT_TENANT_ID))
+            .await
+            .expect("get")
+            .is_some(),
+        "deleting one tenant's copy leaves the other"
+    );
+}
+
+async fn modules_delete_refuses_in_use(store: StoreRef) {
+    let sha = "7c".repeat(32);
+    store
+        .put_wasm_module(DEFAULT_TENANT_ID, &sha, b"\0asm")
+        .await
+        .expect("put");
+    let job = store
+        .create_job(DEFAULT_TENANT_ID,
+            "wasm-user",
+            None,
+            RunnerConfig::Wasm {
+                module: WasmModuleSource::Stored { sha256: sha.clone() },
+                timeout_sec: None,
+                fuel: None,
+                max_memory_mb: None,
+                preopens: Vec::new(),
+                args: Vec::new(),
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+
+    assert!(matches!(
+        store.delete_wasm_module(&sha, Some(DEFAULT_TENANT_ID)).await,
+        Err(ArbiterError::Conflict(_))
+    ));
+    store.delete_job(job.id).await.expect("delete_job");
+    store
+        .delete_wasm_module(&sha, Some(DEFAULT_TENANT_ID))
+        .await
+        .expect("delete once unused");
+    assert!(matches!(
+        store.delete_wasm_module(&sha, Some(DEFAULT_TENANT_ID)).await,
+        Err(ArbiterError::NotFound(_))
+    ));
+}
+
+async fn artifacts_roundtrip_per_tenant(store: StoreRef) {
+    let tenant = store.create_tenant("artifact-co").await.expect("create_tenant").id;
+    let other = store.create_tenant("artifact-other").await.expect("create_tenant").id;
+    let run = Uuid::new_v4();
+    store
+        .put_artifact(tenant, run, 2, "report.csv", "aa", b"a,b\n1,2\n")
+        .await
+        .expect("put report");
+    store
+        .put_artifact(tenant, run, 1, "dump.json", "bb", b"{}")
+        .await
+        .expect("put dump");
+    // Same (run, attempt, name) again replaces the artifact.
+    let replaced = store
+        .put_artifact(tenant, run, 2, "report.csv", "cc", b"a,b\n")
+        .await
+        .expect("put again");
+    assert_eq!(replaced.size_bytes, 4);
+
+    let listed = store.list_artifacts(tenant, run).await.expect("list");
+    let keys: Vec<_> = listed.iter().map(|a| (a.attempt, a.name.as_str())).collect();
+    assert_eq!(keys, vec![(1, "dump.json"), (2, "report.csv")], "by attempt, then name");
+    assert_eq!(listed[1].sha256, "cc");
+    assert_eq!(listed[1].size_bytes, 4);
+    assert_eq!(
+        store.get_artifact(tenant, run, 2, "report.csv").await.expect("get").as_deref(),
+        Some(&b"a,b\n"[..])
+    );
+    assert!(store.get_artifact(tenant, run, 1, "report.csv").await.expect("get").is_none());
+    assert_eq!(store.tenant_artifact_bytes(tenant).await.expect("bytes"), 6);
+
+    // Another tenant sees none of it.
+    assert!(store.list_artifacts(other, run).await.expect("list other").is_empty());
+    assert!(store.get_artifact(other, run, 2, "report.csv").await.expect("get other").is_none());
+    assert_eq!(store.tenant_artifact_bytes(other).await.expect("bytes other"), 0);
+
+    assert!(
+        store.put_artifact(tenant, run, 1, "../escape", "dd", b"x").await.is_err(),
+        "a name with a path separator is refused"
+    );
+}
+
+async fn artifacts_pruned_with_run(store: StoreRef) {
+    let job = seed_job(&store, Some("* * * * *"), true).await;
+    store
+        .insert_job_run_if_missing(job, Utc::now() - Duration::days(2))
+        .await
+        .expect("insert run");
+    let run = store
+        .list_recent_runs(None, None, None, Some(job), None, None)
+        .await
+        .expect("list")
+        .pop()
+        .expect("one run");
+    store
+        .finalize_run(
+            run.id,
+            JobRunState::Succeeded,
+            RunOutcome { status: Some(ResultStatus::Success), ..Default::default() },
+        )
+        .await
+        .expect("finalize");
+    let tenant = store.job_tenant(job).await.expect("job_tenant").expect("tenant");
+    store.put_artifact(tenant, run.id, 1, "out.txt", "ee", b"out").await.expect("put");
+
+    // Pruning the run drops its artifacts too, like its log chunks.
+    store.prune_runs(Utc::now()).await.expect("prune");
+    assert!(store.list_artifacts(tenant, run.id).await.expect("list after").is_empty());
+}
+
+async fn bundles_versioned_per_tenant(store: StoreRef) {
+    let tenant = store.create_tenant("bundle-co").await.expect("create_tenant").id;
+    let other = store.create_tenant("bundle-other").await.expect("create_tenant").id;
+    let v1 = store
+        .put_code_bundle(tenant, "etl", BundleFormat::Tar, "aa", b"one")
+        .await
+        .expect("put v1");
+    assert_eq!((v1.version, v1.size_bytes), (1, 3));
+    // The same bytes again are the latest version, not a new one.
+    let same = store
+        .put_code_bundle(tenant, "etl", BundleFormat::Tar, "aa", b"one")
+        .await
+        .expect("put same");
+    assert_eq!(same.version, 1);
+    let v2 = store
+        .put_code_bundle(tenant, "etl", BundleFormat::Zip, "bb", b"two!")
+        .await
+        .expect("put v2");
+    assert_eq!(v2.version, 2);
+    assert_eq!(v2.format, BundleFormat::Zip);
+    // Reverting to earlier bytes makes a new version, so "latest" follows the upload.
+    let v3 = store
+        .put_code_bundle(tenant, "etl", BundleFormat::Tar, "aa", b"one")
+        .await
+        .expect("put v3");
+    assert_eq!(v3.version, 3);
+
+    let latest = store
+        .get_code_bundle("etl", None, Some(tenant))
+        .await
+        .expect("get latest")
+        .expect("latest");
+    assert_eq!((latest.version, latest.sha256.as_str()), (3, "aa"));
+    let pinned = store
+        .get_code_bundle("etl", Some(2), None)
+        .await
+        .expect("get v2")
+        .expect("v2");
+    assert_eq!((pinned.tenant_id, pinned.sha256.as_str()), (tenant, "bb"));
+    assert_eq!(
+        store.get_code_bundle_content("etl", 2, Some(tenant)).await.expect("content").as_deref(),
+        Some(&b"two!"[..])
+    );
+    assert!(store.get_code_bundle("etl", Some(4), Some(tenant)).await.expect("get v4").is_none());
+    let versions: Vec<_> = store
+        .list_code_bundles(Some(tenant))
+        .await
+        .expect("list")
+        .iter()
+        .map(|b| b.version)
+        .collect();
+    assert_eq!(versions, vec![1, 2, 3]);
+
+    // Another tenant neither sees these versions nor shares their numbering.
+    assert!(store.get_code_bundle("etl", None, Some(other)).await.expect("get other").is_none());
+    assert!(store.get_code_bundle_content("etl", 1, Some(other)).await.expect("content other").is_none());
+    assert!(store.list_code_bundles(Some(other)).await.expect("list other").is_empty());
+    let theirs = store
+        .put_code_bundle(other, "etl", BundleFormat::TarGz, "cc", b"theirs")
+        .await
+        .expect("put other");
+    assert_eq!(theirs.version, 1);
+
+    assert!(matches!(
+        store.put_code_bundle(tenant, "../etl", BundleFormat::Tar, "dd", b"x").await,
+        Err(ArbiterError::InvalidInput(_))
+    ));
+}
+
+async fn bundles_delete_refuses_referenced(store: StoreRef) {
+    let tenant = store.create_tenant("bundle-refs").await.expect("create_tenant").id;
+    for (sha, body) in [("aa", &b"one"[..]), ("bb", b"two")] {
+        store
+            .put_code_bundle(tenant, "etl", BundleFormat::Tar, sha, body)
+            .await
+            .expect("put");
+    }
+    let code = CodeSource::Bundle { name: "etl".to_string(), version: Some(1) };
+    let job = store
+        .create_job(
+            tenant,
+            "bundle-user",
+            None,
+            RunnerConfig::Python {
+                module: "etl.main".to_string(),
+                class_name: "run".to_string(),
+                timeout_sec: None,
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: vec![],
+                code: Some(code.clone()),
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    let loaded = store.get_job(job.id, None).await.expect("get_job");
+    assert_eq!(loaded.runner_cfg.code(), Some(&code));
+
+    // v1 is pinned; v2 is free to go.
+    assert!(matches!(
+        store.delete_code_bundle("etl", 1, Some(tenant)).await,
+        Err(ArbiterError::Conflict(_))
+    ));
+    store.delete_code_bundle("etl", 2, Some(tenant)).await.expect("delete v2");
+    assert!(matches!(
+        store.delete_code_bundle("etl", 2, Some(tenant)).await,
+        Err(ArbiterError::NotFound(_))
+    ));
+    store.delete_job(job.id).await.expect("delete_job");
+    store.delete_code_bundle("etl", 1, Some(tenant)).await.expect("delete once unused");
+    assert!(store.list_code_bundles(Some(tenant)).await.expect("list").is_empty());
+}
@@ -70,6 +70,7 @@ impl BackendFactory for PgBackend {
             multi_node: false, // single-instance test harness; HA tested separately
             fencing: false,
             retention: true,
+            db_runners: true,
         }
     }
 
@@ -182,6 +183,7 @@ impl BackendFactory for SqliteBackend {
             multi_node: false,
             fencing: false,
             retention: true,
+            db_runners: false, // pgsql/mysql jobs are Postgres-store only
         }
     }
 
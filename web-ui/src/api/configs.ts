@@ -1,4 +1,5 @@
 import type {
+  ConfigTestResult,
   CreateDbConfigRequest,
   SharedDbConfig,
   UpdateDbConfigRequest,
@@ -31,3 +32,9 @@ export function updateDbConfig(
 export function deleteDbConfig(id: string): Promise<void> {
   return api<void>(`/db-configs/${id}`, { method: 'DELETE' })
 }
+
+// Runs on a worker (which can resolve the password secret and reach the database);
+// the request waits for its result.
+export function testDbConfig(id: string): Promise<ConfigTestResult> {
+  return api<ConfigTestResult>(`/db-configs/${id}/test`, { method: 'POST' })
+}
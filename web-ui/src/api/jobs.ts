@@ -1,6 +1,7 @@
 import type {
   CreateJobRequest,
   JobRun,
+  RunParam,
   UpdateJobRequest,
 } from '../backend-types'
 import type { JobSpec } from '../backend-types/JobSpec'
@@ -22,9 +23,13 @@ export function deleteJob(id: string): Promise<void> {
   return api<void>(`/jobs/${id}`, { method: 'DELETE' })
 }
 
-export function runJobNow(id: string): Promise<JobRun> {
+export function runJobNow(
+  id: string,
+  params?: Record<string, RunParam>,
+): Promise<JobRun> {
   return api<JobRun>(`/jobs/${id}/run`, {
     method: 'POST',
+    body: params ? JSON.stringify({ params }) : undefined,
     headers: { 'Content-Type': 'application/json' },
   })
 }
@@ -0,0 +1,18 @@
+import type { WasmModuleInfo } from '../backend-types'
+import { api } from './client'
+
+export function fetchWasmModules(): Promise<WasmModuleInfo[]> {
+  return api<WasmModuleInfo[]>('/wasm-modules')
+}
+
+export function uploadWasmModule(file: Blob): Promise<WasmModuleInfo> {
+  return api<WasmModuleInfo>('/wasm-modules', {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/wasm' },
+    body: file,
+  })
+}
+
+export function deleteWasmModule(sha256: string): Promise<void> {
+  return api<void>(`/wasm-modules/${sha256}`, { method: 'DELETE' })
+}
@@ -1,4 +1,4 @@
-import type { ListRunsQuery, RunLogPage } from '../backend-types'
+import type { ListRunsQuery, RunArtifact, RunLogPage } from '../backend-types'
 import type { JobRun } from '../backend-types/JobRun'
 import { api } from './client'
 
@@ -15,6 +15,16 @@ export function fetchRunLogs(
   return api<RunLogPage>(`/runs/${runId}/logs`, { method: 'GET' }, query)
 }
 
+/** A run's artifacts across its attempts (metadata only; download via `runArtifactUrl`). */
+export function fetchRunArtifacts(runId: string): Promise<RunArtifact[]> {
+  return api<RunArtifact[]>(`/runs/${runId}/artifacts`, { method: 'GET' })
+}
+
+/** Where an artifact's bytes download from (the session cookie authenticates it). */
+export function runArtifactUrl(runId: string, attempt: number, name: string): string {
+  return `/api/v1/runs/${runId}/artifacts/${attempt}/${encodeURIComponent(name)}`
+}
+
 export function fetchRuns(query: ListRunsQuery): Promise<JobRun[]> {
   return api<JobRun[]>(
     '/runs',
@@ -1,4 +1,11 @@
-import type { CreateSecretRequest, SecretMetaResponse } from '../backend-types'
+import type {
+  CreateSecretRequest,
+  SecretAuditEvent,
+  SecretMetaResponse,
+  SecretPolicyRequest,
+  SecretReference,
+  SecretVersionResponse,
+} from '../backend-types'
 import { api } from './client'
 
 export function fetchSecrets(): Promise<SecretMetaResponse[]> {
@@ -14,6 +21,49 @@ export function createSecret(
   })
 }
 
-export function deleteSecret(id: string): Promise<void> {
-  return api<void>(`/secrets/${id}`, { method: 'DELETE' })
+export function deleteSecret(id: string, force = false): Promise<void> {
+  return api<void>(
+    `/secrets/${id}`,
+    { method: 'DELETE' },
+    force ? { force: 'true' } : {}
+  )
+}
+
+export function fetchSecretReferences(id: string): Promise<SecretReference[]> {
+  return api<SecretReference[]>(`/secrets/${id}/references`)
+}
+
+export function fetchSecretAudit(
+  id: string,
+  limit = 100
+): Promise<SecretAuditEvent[]> {
+  return api<SecretAuditEvent[]>(
+    `/secrets/${id}/audit`,
+    { method: 'GET' },
+    { limit: String(limit) }
+  )
+}
+
+export function setSecretPolicy(
+  id: string,
+  req: SecretPolicyRequest
+): Promise<SecretMetaResponse> {
+  return api<SecretMetaResponse>(`/secrets/${id}/policy`, {
+    method: 'PUT',
+    body: JSON.stringify(req),
+  })
+}
+
+export function fetchSecretVersions(id: string): Promise<SecretVersionResponse[]> {
+  return api<SecretVersionResponse[]>(`/secrets/${id}/versions`)
+}
+
+export function rollbackSecret(
+  id: string,
+  version: number
+): Promise<SecretMetaResponse> {
+  return api<SecretMetaResponse>(`/secrets/${id}/rollback`, {
+    method: 'POST',
+    body: JSON.stringify({ version }),
+  })
 }
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Archive format of a [`CodeBundle`], detected from its leading bytes on upload.
+ */
+export type BundleFormat = "tar" | "tarGz" | "zip";
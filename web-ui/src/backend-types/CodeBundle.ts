@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BundleFormat } from "./BundleFormat";
+
+/**
+ * One version of a named code bundle: an archive (tar, tar.gz or zip) of a job's code,
+ * uploaded once and fetched by workers at run time. Each upload of new bytes under a name
+ * becomes its next version.
+ */
+export type CodeBundle = { tenantId: string, name: string, version: number, sha256: string, format: BundleFormat, sizeBytes: number, createdAt: string, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where a shell/python/node job's code comes from when it is not already on the worker.
+ * The worker fetches it into a local cache before the run.
+ */
+export type CodeSource = { "source": "bundle", name: string, version: number | null, } | { "source": "git", url: string, rev: string | null, subdir: string | null, };
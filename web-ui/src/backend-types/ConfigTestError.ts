@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Why a config test failed. `code` is stable (`secret_unresolved`, `timeout`,
+ * `connect_failed`, `tls_failed`, `auth_failed`, `unknown_database`, `query_failed`);
+ * `message` is the underlying driver error.
+ */
+export type ConfigTestError = { code: string, message: string, };
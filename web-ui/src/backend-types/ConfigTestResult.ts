@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConfigTestError } from "./ConfigTestError";
+
+/**
+ * Outcome of a config connection test, recorded by the worker that ran it. On success
+ * `latency_ms` is the round trip of the no-op query and `connect_ms` the time to open the
+ * connection; on failure `error` says which step failed.
+ */
+export type ConfigTestResult = { ok: boolean, connectMs: bigint | null, latencyMs: bigint | null, error: ConfigTestError | null, workerId: string, testedAt: string, };
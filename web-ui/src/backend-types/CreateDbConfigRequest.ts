@@ -5,4 +5,8 @@ import type { DbEngine } from "./DbEngine";
  * Create a shared DB connection config. `passwordSecret` is a `secret:<name>` reference,
  * not a plaintext password.
  */
-export type CreateDbConfigRequest = { engine: DbEngine, name: string, host: string, port: number, username: string, passwordSecret: string, database: string, };
+export type CreateDbConfigRequest = { engine: DbEngine, name: string, host: string, port: number, username: string, passwordSecret: string, database: string, 
+/**
+ * Per-worker connection pool cap (1..=64); default 4.
+ */
+poolSize: number | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DbOutputFormat } from "./DbOutputFormat";
+
+/**
+ * How a pgsql/mysql run reports a statement that returns rows. The result set goes into
+ * `result` as JSON (`columns` + typed `rows`, `row_count`, `truncated`) or as CSV with a
+ * header line, capped at `max_rows` rows and `max_bytes` of rendered output. Statements
+ * without a result set still report `rows_affected`.
+ */
+export type DbOutputConfig = { format: DbOutputFormat, maxRows: number | null, maxBytes: number | null, };
@@ -1,4 +1,9 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ExecutableConfigSnapshotMeta } from "./ExecutableConfigSnapshotMeta";
+import type { RetryOn } from "./RetryOn";
 
-export type ExecutableConfigSnapshot = { name: string | null, jobName: string, meta: ExecutableConfigSnapshotMeta, };
+export type ExecutableConfigSnapshot = { name: string | null, jobName: string, meta: ExecutableConfigSnapshotMeta, 
+/**
+ * The job's retry classification at claim time (older snapshots: defaults).
+ */
+retryOn: RetryOn, };
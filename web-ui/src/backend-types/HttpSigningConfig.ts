@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SignatureEncoding } from "./SignatureEncoding";
+import type { SigningAlgorithm } from "./SigningAlgorithm";
+
+/**
+ * Outbound request signing for an HTTP job. `key` is usually a `secret:<name>`
+ * reference; for `awsSigV4` its value is `ACCESS_KEY_ID:SECRET_ACCESS_KEY`.
+ *
+ * HMAC signs `canonical`, a template over `{method}`, `{path}` (path + query),
+ * `{timestamp}` (unix seconds, also sent in `timestamp_header`), `{body_sha256}` (hex)
+ * and `{body}`; the default is `{method}\n{path}\n{timestamp}\n{body_sha256}`. The
+ * signature goes in `header` (default `X-Signature`), after an optional `prefix`
+ * such as `sha256=`. `awsSigV4` ignores those and sets `Authorization`/`X-Amz-Date`
+ * for `region`/`service`, signing the job's own headers plus `host`.
+ */
+export type HttpSigningConfig = { algorithm: SigningAlgorithm, key: string, header: string | null, timestampHeader: string | null, canonical: string | null, prefix: string | null, encoding: SignatureEncoding, region: string | null, service: string | null, };
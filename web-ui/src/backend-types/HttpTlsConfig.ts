@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * TLS options for an HTTP job. `client_cert`/`client_key` (mTLS, PEM) and `ca_bundle`
+ * (extra trusted roots, PEM) are resolved at execution in the job's tenant. The cert and
+ * CA bundle may be inline PEM or `secret:<name>` references; the private key must be a
+ * reference, so it is never stored in the job. `insecure_skip_verify` may only be set by
+ * an admin.
+ */
+export type HttpTlsConfig = { clientCert: string | null, clientKey: string | null, caBundle: string | null, insecureSkipVerify: boolean, };
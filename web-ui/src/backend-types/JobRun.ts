@@ -2,6 +2,8 @@
 import type { ExecutableConfigSnapshot } from "./ExecutableConfigSnapshot";
 import type { JobRunState } from "./JobRunState";
 import type { ResultStatus } from "./ResultStatus";
+import type { RunParam } from "./RunParam";
+import type { RunProgress } from "./RunProgress";
 
 export type JobRun = { id: string, jobId: string, scheduledFor: string, state: JobRunState, workerId: string | null, exitCode: number | null, attempt: number, startedAt: string | null, finishedAt: string | null, snapshot: ExecutableConfigSnapshot | null, resultStatus: ResultStatus | null, 
 /**
@@ -11,4 +13,23 @@ result: string | null, resultMediaType: string | null,
 /**
  * The typed error payload (structured failure) and its media type.
  */
-error: string | null, errorMediaType: string | null, };
+error: string | null, errorMediaType: string | null, 
+/**
+ * Ad-hoc parameters the run was started with (run-now), bound by name into DB
+ * runner queries alongside the run context. Empty for scheduled runs.
+ */
+params: { [key in string]?: RunParam }, 
+/**
+ * The latest progress the running task reported, if any. Reset when the run is claimed.
+ */
+progress: RunProgress | null, 
+/**
+ * When the running task last proved it is alive (a heartbeat, progress or log record
+ * event). Dead-worker reclaim skips a run whose heartbeat is recent.
+ */
+lastHeartbeat: string | null, 
+/**
+ * The commit a git code source resolved to for this attempt, recorded by the worker
+ * before the run starts. Reset when the run is claimed.
+ */
+codeCommit: string | null, };
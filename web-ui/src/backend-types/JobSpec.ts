@@ -1,6 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { BackoffStrategy } from "./BackoffStrategy";
 import type { MisfirePolicy } from "./MisfirePolicy";
+import type { RetryOn } from "./RetryOn";
 import type { RunnerConfig } from "./RunnerConfig";
 
 export type JobSpec = { id: string, name: string, scheduleCron: string | null, enabled: boolean, runnerCfg: RunnerConfig, maxConcurrency: number, misfirePolicy: MisfirePolicy, 
@@ -8,4 +9,8 @@ export type JobSpec = { id: string, name: string, scheduleCron: string | null, e
  * Retry config. `max_attempts = 1` means no retry. A `retryable` run is requeued
  * with a backoff (jittered) until attempts are exhausted, then it fails.
  */
-maxAttempts: number, backoffStrategy: BackoffStrategy, backoffBaseSecs: number, backoffCapSecs: number, };
+maxAttempts: number, backoffStrategy: BackoffStrategy, backoffBaseSecs: number, backoffCapSecs: number, 
+/**
+ * Which outcomes count as `retryable` for this job (defaults when unset).
+ */
+retryOn: RetryOn, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Severity of a structured log record a runtime emitted (`ctx.log.info(...)`), as opposed
+ * to raw stdout/stderr text.
+ */
+export type LogLevel = "debug" | "info" | "warning" | "error";
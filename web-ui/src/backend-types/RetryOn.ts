@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Per-job classification of which failures are `retryable`. An unset list keeps the
+ * built-in default for that runner kind; a set list replaces it (so `[]` = never).
+ * `error_types` match the structured `error.type` a Python/Node runtime reports.
+ */
+export type RetryOn = { httpStatuses: Array<number> | null, exitCodes: Array<number> | null, errorTypes: Array<string>, };
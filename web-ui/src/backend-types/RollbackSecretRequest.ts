@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Roll a secret back to one of its retained versions. The value of `version` becomes a new
+ * current version; the history is kept.
+ */
+export type RollbackSecretRequest = { version: number, };
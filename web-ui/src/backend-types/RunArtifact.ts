@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A file a run attempt left in its artifacts directory (or wrote via `ctx.artifact`),
+ * uploaded by the worker once the attempt finished. Unique per (run, attempt, name).
+ */
+export type RunArtifact = { runId: string, attempt: number, name: string, sizeBytes: number, sha256: string, createdAt: string, };
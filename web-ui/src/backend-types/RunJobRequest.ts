@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RunParam } from "./RunParam";
+
+/**
+ * Optional body for a run-now request.
+ */
+export type RunJobRequest = { 
+/**
+ * Ad-hoc parameters, bound by name (`:name`) into DB runner queries.
+ */
+params: { [key in string]?: RunParam }, };
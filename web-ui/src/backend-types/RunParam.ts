@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A scalar ad-hoc run parameter. Untagged: a JSON bool, integer, float or string.
+ */
+export type RunParam = boolean | bigint | number | string;
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Progress reported by a running task: a fraction in `0..=1` and an optional message.
+ */
+export type RunProgress = { value: number, message: string | null, };
@@ -1,3 +1,12 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CodeSource } from "./CodeSource";
+import type { DbOutputConfig } from "./DbOutputConfig";
+import type { HttpSigningConfig } from "./HttpSigningConfig";
+import type { HttpTlsConfig } from "./HttpTlsConfig";
+import type { RuntimeTransport } from "./RuntimeTransport";
+import type { SecretDelivery } from "./SecretDelivery";
+import type { WasmModuleSource } from "./WasmModuleSource";
+import type { WasmPreopen } from "./WasmPreopen";
+import type { WorkspaceMode } from "./WorkspaceMode";
 
-export type RunnerConfig = { "type": "shell", command: string, workingDir: string | null, } | { "type": "http", method: string, url: string, headers: { [key in string]?: string } | null, body: string | null, timeoutSec: number | null, } | { "type": "pgSql", configId: string, query: string, timeoutSec: number | null, } | { "type": "mySql", configId: string, query: string, timeoutSec: number | null, } | { "type": "python", module: string, className: string, timeoutSec: number | null, } | { "type": "node", module: string, functionName: string, timeoutSec: number | null, };
+export type RunnerConfig = { "type": "shell", command: string, workingDir: string | null, code: CodeSource | null, workspace: WorkspaceMode, secretDelivery: SecretDelivery, } | { "type": "http", method: string, url: string, headers: { [key in string]?: string } | null, body: string | null, timeoutSec: number | null, tls: HttpTlsConfig | null, signing: HttpSigningConfig | null, } | { "type": "pgSql", configId: string, query: string, timeoutSec: number | null, output: DbOutputConfig | null, } | { "type": "mySql", configId: string, query: string, timeoutSec: number | null, output: DbOutputConfig | null, } | { "type": "sqlite", path: string, query: string, timeoutSec: number | null, busyTimeoutSec: number | null, output: DbOutputConfig | null, } | { "type": "python", module: string, className: string, timeoutSec: number | null, transport: RuntimeTransport, maxRuns: number | null, secrets: Array<string>, code: CodeSource | null, workspace: WorkspaceMode, secretDelivery: SecretDelivery, } | { "type": "node", module: string, functionName: string, timeoutSec: number | null, transport: RuntimeTransport, maxRuns: number | null, secrets: Array<string>, code: CodeSource | null, workspace: WorkspaceMode, secretDelivery: SecretDelivery, } | { "type": "wasm", module: WasmModuleSource, timeoutSec: number | null, fuel: number | null, maxMemoryMb: number | null, preopens: Array<WasmPreopen>, args: Array<string>, } | { "type": "plugin", plugin: string, config: Record<string, unknown>, timeoutSec: number | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What a runner plugin reported in its handshake; advertised on the worker's record.
+ */
+export type RunnerPluginInfo = { name: string, version: string, protocolVersion: number, capabilities: Array<string>, 
+/**
+ * JSON Schema for the job's `config`, if the plugin publishes one.
+ */
+configSchema: Record<string, unknown> | null, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How the worker talks to a Python/Node runtime.
+ *
+ * - `file`: a fresh interpreter per run that writes its result to a file and exits.
+ * - `socket`: a resident interpreter per job, reached over a Unix socket. It runs the
+ *   module's `prepare(ctx)` once and `run(ctx)` per fire, and is recycled after
+ *   `max_runs` runs (default [`RuntimeTransport::DEFAULT_MAX_RUNS`]) or when it dies.
+ */
+export type RuntimeTransport = "file" | "socket";
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Who acted on a secret, as the audit trail records it.
+ */
+export type SecretActor = { "kind": "user", userId: string, } | { "kind": "run", runId: string, jobId: string, } | { "kind": "node", nodeId: string, };
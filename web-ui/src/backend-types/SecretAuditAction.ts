@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What was done to a secret.
+ */
+export type SecretAuditAction = "resolve" | "set" | "rollback" | "policy" | "delete" | "rotate";
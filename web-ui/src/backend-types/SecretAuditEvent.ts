@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SecretActor } from "./SecretActor";
+import type { SecretAuditAction } from "./SecretAuditAction";
+import type { SecretAuditOutcome } from "./SecretAuditOutcome";
+
+/**
+ * One entry of the append-only secret audit trail. Never carries a secret value.
+ */
+export type SecretAuditEvent = { id: string, 
+/**
+ * `None` when no such secret was found (a resolve of an unknown name).
+ */
+secretId: string | null, tenantId: string, secretName: string, 
+/**
+ * The version resolved, set or rolled back to, when there is one.
+ */
+version: number | null, action: SecretAuditAction, actor: SecretActor, outcome: SecretAuditOutcome, 
+/**
+ * The error for a failed or refused action.
+ */
+detail: string | null, at: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How an audited action on a secret ended.
+ */
+export type SecretAuditOutcome = "ok" | "denied" | "notFound" | "failed";
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Page through a secret's audit trail, newest first.
+ */
+export type SecretAuditQuery = { 
+/**
+ * Only events before this time.
+ */
+before?: string, 
+/**
+ * At most this many events (default 100, max 1000).
+ */
+limit?: number, };
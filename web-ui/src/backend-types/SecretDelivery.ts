@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How the `secret:<name>` values of a shell/python/node job's env reach the process.
+ * - `env` (default): resolved in place, as the variable's value.
+ * - `files`: each written to a 0600 file named after its variable, in a private directory
+ *   of the run's that is removed when the run is over (also on timeout). The variable is
+ *   left out of the env; the directory is passed as `ARBITER_SECRETS_DIR` to a shell job,
+ *   and to a runtime as its context's secrets dir. Keeps values out of the environment,
+ *   which grandchildren inherit and `/proc/<pid>/environ` and crash dumps expose.
+ */
+export type SecretDelivery = "env" | "files";
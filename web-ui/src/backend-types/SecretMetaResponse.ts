@@ -4,4 +4,13 @@
  * Secret metadata. Deliberately carries no ciphertext or plaintext: secret values are
  * write-only and never leave the server.
  */
-export type SecretMetaResponse = { id: string, name: string, kekVersion: number, createdAt: string, updatedAt: string, };
+export type SecretMetaResponse = { id: string, name: string, 
+/**
+ * The current version; older retained ones are listed under `/secrets/{id}/versions`.
+ */
+version: number, kekVersion: number, expiresAt: string | null, rotateEverySecs: number | null, 
+/**
+ * When the value is due to be replaced (rotation interval or expiry, whichever is
+ * first); past it the secret is overdue.
+ */
+rotationDueAt: string | null, lastResolvedAt: string | null, lastResolvedRun: string | null, createdAt: string, updatedAt: string, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Set a secret's expiry and rotation interval. Both are reminders: an expired or overdue
+ * secret still resolves. `null` clears either.
+ */
+export type SecretPolicyRequest = { expiresAt: string | null, rotateEverySecs: number | null, };
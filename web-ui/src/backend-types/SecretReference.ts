@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SecretReferrer } from "./SecretReferrer";
+
+/**
+ * One place a secret is referenced. `field` says where in the referrer (`env.DB_PASS`,
+ * `tls.clientKey`, `passwordSecret`, ...); `version` is set when the reference pins one.
+ */
+export type SecretReference = { referrer: SecretReferrer, id: string, name: string, field: string, version: number | null, };
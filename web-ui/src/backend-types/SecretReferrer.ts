@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What refers to a secret: a job (its env, runtime `secrets`, HTTP TLS/signing or plugin
+ * config) or a shared DB config (its password).
+ */
+export type SecretReferrer = "job" | "dbConfig";
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One retained version of a secret: metadata only, like [`SecretMetaResponse`].
+ * `restoredFrom` is set when the version was made by rolling back to an older one.
+ */
+export type SecretVersionResponse = { version: number, kekVersion: number, current: boolean, restoredFrom: number | null, createdAt: string, };
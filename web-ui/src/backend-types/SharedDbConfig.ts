@@ -10,4 +10,9 @@ export type SharedDbConfig = { id: string, engine: DbEngine, name: string, host:
 /**
  * A `secret:<name>` reference for the connection password. Never plaintext.
  */
-passwordSecret: string, database: string, tenantId: string, };
+passwordSecret: string, database: string, tenantId: string, 
+/**
+ * Upper bound on the connections each worker keeps open to this database (warm
+ * pool shared by the config's runs). `None` = [`SharedDbConfig::DEFAULT_POOL_SIZE`].
+ */
+poolSize: number | null, };
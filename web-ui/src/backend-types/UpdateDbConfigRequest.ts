@@ -4,4 +4,4 @@
  * Update a shared DB connection config. Absent fields are left unchanged. The engine is
  * fixed at create time and not editable.
  */
-export type UpdateDbConfigRequest = { name: string | null, host: string | null, port: number | null, username: string | null, passwordSecret: string | null, database: string | null, };
+export type UpdateDbConfigRequest = { name: string | null, host: string | null, port: number | null, username: string | null, passwordSecret: string | null, database: string | null, poolSize: number | null, };
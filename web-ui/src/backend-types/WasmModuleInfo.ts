@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A WASM module uploaded for `wasm` runners. Content-addressed within its tenant: the
+ * same bytes uploaded twice are one module.
+ */
+export type WasmModuleInfo = { sha256: string, sizeBytes: number, tenantId: string, createdAt: string, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where a `wasm` runner's module comes from: a `.wasm` file on the worker (under its
+ * `wasm_host_root`), or a module uploaded to the store (tenant-scoped, addressed by the hex
+ * SHA-256 of its bytes).
+ */
+export type WasmModuleSource = { "source": "path", path: string, } | { "source": "stored", sha256: string, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A host directory the module may access, mounted at `guest_path`. Nothing else on the
+ * worker's filesystem is visible to it. `host_path` must be a canonical path (no symlinks,
+ * `.` or `..`) inside the worker's `wasm_host_root`; a worker without one refuses it.
+ */
+export type WasmPreopen = { hostPath: string, guestPath: string, readOnly: boolean, };
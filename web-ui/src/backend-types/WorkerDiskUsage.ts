@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Disk use under a worker's data dir, measured by its periodic maintenance sweep.
+ */
+export type WorkerDiskUsage = { 
+/**
+ * Per-run workspaces (live, and finished ones still kept) and their bytes.
+ */
+runWorkspaces: number, runWorkspaceBytes: number, 
+/**
+ * Per-job workspaces and their bytes.
+ */
+jobWorkspaces: number, jobWorkspaceBytes: number, 
+/**
+ * Fetched job code: unpacked bundles, git mirrors and checkouts.
+ */
+codeCacheBytes: number, 
+/**
+ * What the sweep removed: expired workspaces, orphaned run scratch and outdated
+ * runtime copies, and their bytes.
+ */
+sweptEntries: number, sweptBytes: number, measuredAt: string, };
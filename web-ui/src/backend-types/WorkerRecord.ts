@@ -1,3 +1,14 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RunnerPluginInfo } from "./RunnerPluginInfo";
+import type { WorkerDiskUsage } from "./WorkerDiskUsage";
 
-export type WorkerRecord = { id: string, displayName: string, hostname: string, lastSeen: string, capacity: number, restartCount: number, version: string, };
+export type WorkerRecord = { id: string, displayName: string, hostname: string, lastSeen: string, capacity: number, restartCount: number, version: string, 
+/**
+ * Runner plugins this worker discovered at startup.
+ */
+plugins: Array<RunnerPluginInfo>, 
+/**
+ * What the worker's data dir holds, as of its last workspace sweep. Kept as-is by a
+ * heartbeat that carries none.
+ */
+diskUsage: WorkerDiskUsage | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The working directory a shell/python/node run gets under the worker's data dir.
+ * - `run` (default): a fresh directory per run attempt, removed once the attempt is over
+ *   (or kept for a while, per the worker's workspace retention).
+ * - `job`: one directory per job that persists across its runs, removed once the job
+ *   has not run on the worker for a while. Concurrent runs of the job share it.
+ */
+export type WorkspaceMode = "run" | "job";
@@ -9,6 +9,7 @@ export type { MisfirePolicy } from './MisfirePolicy'
 export type { RunNowRequest } from './RunNowRequest'
 export type { ListRunsQuery } from './ListRunsQuery'
 export type { WorkerRecord } from './WorkerRecord'
+export type { WorkerDiskUsage } from './WorkerDiskUsage'
 export type { User } from './User'
 export type { UserRole } from './UserRole'
 export type { CreateUserRequest } from './CreateUserRequest'
@@ -17,14 +18,42 @@ export type { Tenant } from './Tenant'
 export type { CreateTenantRequest } from './CreateTenantRequest'
 export type { CreateSecretRequest } from './CreateSecretRequest'
 export type { SecretMetaResponse } from './SecretMetaResponse'
+export type { SecretVersionResponse } from './SecretVersionResponse'
+export type { RollbackSecretRequest } from './RollbackSecretRequest'
+export type { SecretPolicyRequest } from './SecretPolicyRequest'
+export type { DeleteSecretQuery } from './DeleteSecretQuery'
+export type { SecretReference } from './SecretReference'
+export type { SecretReferrer } from './SecretReferrer'
+export type { SecretAuditEvent } from './SecretAuditEvent'
+export type { SecretAuditAction } from './SecretAuditAction'
+export type { SecretAuditOutcome } from './SecretAuditOutcome'
+export type { SecretActor } from './SecretActor'
+export type { SecretAuditQuery } from './SecretAuditQuery'
 export type { NodeKeyResponse } from './NodeKeyResponse'
 export type { RotateKekResponse } from './RotateKekResponse'
 export type { LogStream } from './LogStream'
+export type { LogLevel } from './LogLevel'
 export type { LogChunk } from './LogChunk'
 export type { LogSize } from './LogSize'
 export type { RunLogPage } from './RunLogPage'
+export type { RunArtifact } from './RunArtifact'
+export type { CodeSource } from './CodeSource'
+export type { BundleFormat } from './BundleFormat'
+export type { CodeBundle } from './CodeBundle'
+export type { WorkspaceMode } from './WorkspaceMode'
+export type { SecretDelivery } from './SecretDelivery'
 export type { RunLogQuery } from './RunLogQuery'
 export type { DbEngine } from './DbEngine'
 export type { SharedDbConfig } from './SharedDbConfig'
 export type { CreateDbConfigRequest } from './CreateDbConfigRequest'
 export type { UpdateDbConfigRequest } from './UpdateDbConfigRequest'
+export type { ConfigTestResult } from './ConfigTestResult'
+export type { ConfigTestError } from './ConfigTestError'
+export type { RunParam } from './RunParam'
+export type { RunProgress } from './RunProgress'
+export type { RunJobRequest } from './RunJobRequest'
+export type { WasmModuleSource } from './WasmModuleSource'
+export type { WasmPreopen } from './WasmPreopen'
+export type { WasmModuleInfo } from './WasmModuleInfo'
+export type { RunnerPluginInfo } from './RunnerPluginInfo'
+export type { RuntimeTransport } from './RuntimeTransport'
@@ -3,7 +3,8 @@ import type { LogChunk } from '../backend-types'
 import { Button } from './Button'
 
 /**
- * Render a run's captured output from append-only chunks. stderr is tinted. Auto-scrolls to
+ * Render a run's captured output from append-only chunks. stderr is tinted; a structured
+ * log record (`ctx.log.*` in a runtime) gets a level prefix. Auto-scrolls to
  * the bottom while the viewer is near the bottom (so live output follows without yanking the
  * view if the user has scrolled up to read). "Load earlier" pages backward; "Pop out" opens a
  * taller full-screen view. (Virtualized scrolling for very large logs is a follow-up; for now
@@ -120,10 +121,8 @@ function LogBody({
       ) : (
         <pre className="whitespace-pre-wrap break-words">
           {chunks.map((c, i) => (
-            <span
-              key={`${c.seq}-${i}`}
-              className={c.stream === 'stderr' ? 'text-(--text-error)' : 'text-(--text-code)'}
-            >
+            <span key={`${c.seq}-${i}`} className={chunkClass(c)}>
+              {c.level && <span className="font-semibold">[{c.level}] </span>}
               {c.content}
             </span>
           ))}
@@ -132,3 +131,18 @@ function LogBody({
     </div>
   )
 }
+
+function chunkClass(c: LogChunk): string {
+  switch (c.level) {
+    case 'debug':
+      return 'text-(--text-muted)'
+    case 'info':
+      return 'text-(--text-code)'
+    case 'warning':
+      return 'text-(--text-warning)'
+    case 'error':
+      return 'text-(--text-error)'
+    default:
+      return c.stream === 'stderr' ? 'text-(--text-error)' : 'text-(--text-code)'
+  }
+}
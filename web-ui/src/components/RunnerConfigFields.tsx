@@ -1,6 +1,15 @@
 import { useState } from 'react'
-import type { RunnerConfig, SharedDbConfig } from '../backend-types'
+import type {
+  CodeSource,
+  RunnerConfig,
+  RuntimeTransport,
+  SecretDelivery,
+  SharedDbConfig,
+  WasmPreopen,
+  WorkspaceMode,
+} from '../backend-types'
 import { KeyValueEditor } from './KeyValueEditor'
+import { useWorkers } from '../hooks/useWorkers'
 import { pairsToRecord, recordToPairs, type KvPair } from '../utils/keyvalue'
 import { defaultRunner, RUNNER_LABELS, type RunnerType } from '../utils/runner'
 
@@ -21,6 +30,28 @@ export function RunnerConfigFields({ initial, onChange, dbConfigs }: Props) {
     initial?.type === 'http' ? recordToPairs(initial.headers) : []
   )
 
+  // Kept as typed text so a half-written line doesn't get normalised away mid-edit.
+  const [wasmArgs, setWasmArgs] = useState(
+    initial?.type === 'wasm' ? initial.args.join('\n') : ''
+  )
+  const [wasmPreopens, setWasmPreopens] = useState(
+    initial?.type === 'wasm' ? formatPreopens(initial.preopens) : ''
+  )
+  const [runtimeSecrets, setRuntimeSecrets] = useState(
+    initial?.type === 'python' || initial?.type === 'node'
+      ? initial.secrets.join('\n')
+      : ''
+  )
+  const [pluginConfig, setPluginConfig] = useState(
+    initial?.type === 'plugin' ? JSON.stringify(initial.config, null, 2) : '{}'
+  )
+  const [pluginConfigError, setPluginConfigError] = useState<string | null>(null)
+  // Plugin names the live workers advertise, offered as suggestions.
+  const { data: workers } = useWorkers()
+  const pluginNames = [
+    ...new Set((workers ?? []).flatMap((w) => w.plugins.map((p) => p.name))),
+  ].sort()
+
   function emit(next: RunnerConfig) {
     setCfg(next)
     onChange(next)
@@ -28,6 +59,11 @@ export function RunnerConfigFields({ initial, onChange, dbConfigs }: Props) {
 
   function changeType(type: RunnerType) {
     setHeaderPairs([])
+    setWasmArgs('')
+    setWasmPreopens('')
+    setRuntimeSecrets('')
+    setPluginConfig('{}')
+    setPluginConfigError(null)
     emit(defaultRunner(type))
   }
 
@@ -68,6 +104,18 @@ export function RunnerConfigFields({ initial, onChange, dbConfigs }: Props) {
               }
             />
           </Field>
+          <CodeSourceFields
+            value={cfg.code}
+            onChange={(code) => emit({ ...cfg, code })}
+          />
+          <WorkspaceField
+            value={cfg.workspace}
+            onChange={(workspace) => emit({ ...cfg, workspace })}
+          />
+          <SecretDeliveryField
+            value={cfg.secretDelivery}
+            onChange={(secretDelivery) => emit({ ...cfg, secretDelivery })}
+          />
         </>
       )}
 
@@ -145,6 +193,47 @@ export function RunnerConfigFields({ initial, onChange, dbConfigs }: Props) {
         </>
       )}
 
+      {cfg.type === 'sqlite' && (
+        <>
+          <Field label="Database file (absolute path on the worker)">
+            <input
+              type="text"
+              className={inputCls}
+              placeholder="/var/lib/app/app.db"
+              value={cfg.path}
+              onChange={(e) => emit({ ...cfg, path: e.target.value })}
+            />
+          </Field>
+          <Field label="Statements (run in one transaction)">
+            <textarea
+              className={`${inputCls} font-mono`}
+              rows={4}
+              value={cfg.query}
+              onChange={(e) => emit({ ...cfg, query: e.target.value })}
+            />
+          </Field>
+          <TimeoutField
+            value={cfg.timeoutSec}
+            onChange={(v) => emit({ ...cfg, timeoutSec: v })}
+          />
+          <Field label="Busy timeout seconds (optional, default 5)">
+            <input
+              type="number"
+              min={0}
+              className={inputCls}
+              value={cfg.busyTimeoutSec ?? ''}
+              onChange={(e) =>
+                emit({
+                  ...cfg,
+                  busyTimeoutSec:
+                    e.target.value === '' ? null : Number(e.target.value),
+                })
+              }
+            />
+          </Field>
+        </>
+      )}
+
       {cfg.type === 'python' && (
         <>
           <Field label="Module">
@@ -168,6 +257,42 @@ export function RunnerConfigFields({ initial, onChange, dbConfigs }: Props) {
             value={cfg.timeoutSec}
             onChange={(v) => emit({ ...cfg, timeoutSec: v })}
           />
+          <TransportFields
+            transport={cfg.transport}
+            maxRuns={cfg.maxRuns}
+            onChange={(transport, maxRuns) =>
+              emit({ ...cfg, transport, maxRuns })
+            }
+          />
+          <CodeSourceFields
+            value={cfg.code}
+            onChange={(code) => emit({ ...cfg, code })}
+          />
+          <WorkspaceField
+            value={cfg.workspace}
+            onChange={(workspace) => emit({ ...cfg, workspace })}
+          />
+          <SecretDeliveryField
+            value={cfg.secretDelivery}
+            onChange={(secretDelivery) => emit({ ...cfg, secretDelivery })}
+          />
+          <Field label="Secrets readable via ctx.secret (one per line)">
+            <textarea
+              className={`${inputCls} font-mono`}
+              rows={2}
+              value={runtimeSecrets}
+              onChange={(e) => {
+                setRuntimeSecrets(e.target.value)
+                emit({
+                  ...cfg,
+                  secrets: e.target.value
+                    .split('\n')
+                    .map((n) => n.trim())
+                    .filter((n) => n !== ''),
+                })
+              }}
+            />
+          </Field>
         </>
       )}
 
@@ -194,12 +319,237 @@ export function RunnerConfigFields({ initial, onChange, dbConfigs }: Props) {
             value={cfg.timeoutSec}
             onChange={(v) => emit({ ...cfg, timeoutSec: v })}
           />
+          <TransportFields
+            transport={cfg.transport}
+            maxRuns={cfg.maxRuns}
+            onChange={(transport, maxRuns) =>
+              emit({ ...cfg, transport, maxRuns })
+            }
+          />
+          <CodeSourceFields
+            value={cfg.code}
+            onChange={(code) => emit({ ...cfg, code })}
+          />
+          <WorkspaceField
+            value={cfg.workspace}
+            onChange={(workspace) => emit({ ...cfg, workspace })}
+          />
+          <SecretDeliveryField
+            value={cfg.secretDelivery}
+            onChange={(secretDelivery) => emit({ ...cfg, secretDelivery })}
+          />
+          <Field label="Secrets readable via ctx.secret (one per line)">
+            <textarea
+              className={`${inputCls} font-mono`}
+              rows={2}
+              value={runtimeSecrets}
+              onChange={(e) => {
+                setRuntimeSecrets(e.target.value)
+                emit({
+                  ...cfg,
+                  secrets: e.target.value
+                    .split('\n')
+                    .map((n) => n.trim())
+                    .filter((n) => n !== ''),
+                })
+              }}
+            />
+          </Field>
+        </>
+      )}
+
+      {cfg.type === 'wasm' && (
+        <>
+          <Field label="Module source">
+            <select
+              className={inputCls}
+              value={cfg.module.source}
+              onChange={(e) =>
+                emit({
+                  ...cfg,
+                  module:
+                    e.target.value === 'path'
+                      ? { source: 'path', path: '' }
+                      : { source: 'stored', sha256: '' },
+                })
+              }
+            >
+              <option value="stored">Uploaded module</option>
+              <option value="path">File on the worker</option>
+            </select>
+          </Field>
+          {cfg.module.source === 'stored' ? (
+            <Field label="Module SHA-256">
+              <input
+                type="text"
+                className={`${inputCls} font-mono`}
+                value={cfg.module.sha256}
+                onChange={(e) =>
+                  emit({
+                    ...cfg,
+                    module: { source: 'stored', sha256: e.target.value.trim() },
+                  })
+                }
+              />
+            </Field>
+          ) : (
+            <Field label="Module file (absolute path on the worker)">
+              <input
+                type="text"
+                className={inputCls}
+                placeholder="/opt/jobs/report.wasm"
+                value={cfg.module.path}
+                onChange={(e) =>
+                  emit({
+                    ...cfg,
+                    module: { source: 'path', path: e.target.value },
+                  })
+                }
+              />
+            </Field>
+          )}
+          <Field label="Arguments (one per line)">
+            <textarea
+              className={`${inputCls} font-mono`}
+              rows={2}
+              value={wasmArgs}
+              onChange={(e) => {
+                setWasmArgs(e.target.value)
+                emit({
+                  ...cfg,
+                  args: e.target.value.split('\n').filter((a) => a !== ''),
+                })
+              }}
+            />
+          </Field>
+          <Field label="Preopened dirs (host:guest, append :ro for read-only; one per line)">
+            <textarea
+              className={`${inputCls} font-mono`}
+              rows={2}
+              placeholder="/srv/data:/data:ro"
+              value={wasmPreopens}
+              onChange={(e) => {
+                setWasmPreopens(e.target.value)
+                emit({ ...cfg, preopens: parsePreopens(e.target.value) })
+              }}
+            />
+          </Field>
+          <TimeoutField
+            value={cfg.timeoutSec}
+            onChange={(v) => emit({ ...cfg, timeoutSec: v })}
+          />
+          <Field label="Fuel (optional, default 10,000,000,000)">
+            <input
+              type="number"
+              min={1}
+              className={inputCls}
+              value={cfg.fuel ?? ''}
+              onChange={(e) =>
+                emit({
+                  ...cfg,
+                  fuel: e.target.value === '' ? null : Number(e.target.value),
+                })
+              }
+            />
+          </Field>
+          <Field label="Max memory MB (optional, default 256)">
+            <input
+              type="number"
+              min={1}
+              max={4096}
+              className={inputCls}
+              value={cfg.maxMemoryMb ?? ''}
+              onChange={(e) =>
+                emit({
+                  ...cfg,
+                  maxMemoryMb:
+                    e.target.value === '' ? null : Number(e.target.value),
+                })
+              }
+            />
+          </Field>
+        </>
+      )}
+
+      {cfg.type === 'plugin' && (
+        <>
+          <Field label="Plugin (name reported by a worker's runner plugin)">
+            <input
+              type="text"
+              className={inputCls}
+              list="runner-plugin-names"
+              value={cfg.plugin}
+              onChange={(e) => emit({ ...cfg, plugin: e.target.value.trim() })}
+            />
+            <datalist id="runner-plugin-names">
+              {pluginNames.map((n) => (
+                <option key={n} value={n} />
+              ))}
+            </datalist>
+          </Field>
+          <Field label="Config (JSON object; secret:<name> strings are resolved for the plugin)">
+            <textarea
+              className={`${inputCls} font-mono`}
+              rows={5}
+              value={pluginConfig}
+              onChange={(e) => {
+                setPluginConfig(e.target.value)
+                try {
+                  const parsed: unknown = JSON.parse(e.target.value)
+                  if (
+                    typeof parsed !== 'object' ||
+                    parsed === null ||
+                    Array.isArray(parsed)
+                  ) {
+                    setPluginConfigError('Config must be a JSON object')
+                    return
+                  }
+                  setPluginConfigError(null)
+                  emit({ ...cfg, config: parsed as Record<string, unknown> })
+                } catch {
+                  setPluginConfigError('Invalid JSON')
+                }
+              }}
+            />
+            {pluginConfigError && (
+              <p className="text-xs text-(--text-danger) mt-1">
+                {pluginConfigError}
+              </p>
+            )}
+          </Field>
+          <TimeoutField
+            value={cfg.timeoutSec}
+            onChange={(v) => emit({ ...cfg, timeoutSec: v })}
+          />
         </>
       )}
     </div>
   )
 }
 
+function formatPreopens(preopens: WasmPreopen[]): string {
+  return preopens
+    .map((p) => `${p.hostPath}:${p.guestPath}${p.readOnly ? ':ro' : ''}`)
+    .join('\n')
+}
+
+function parsePreopens(text: string): WasmPreopen[] {
+  return text
+    .split('\n')
+    .map((line) => line.trim())
+    .filter((line) => line !== '')
+    .map((line) => {
+      const parts = line.split(':')
+      const readOnly = parts.length > 2 && parts[parts.length - 1] === 'ro'
+      if (readOnly) parts.pop()
+      return {
+        hostPath: parts[0] ?? '',
+        guestPath: parts.slice(1).join(':'),
+        readOnly,
+      }
+    })
+}
+
 function Field({
   label,
   children,
@@ -237,6 +587,186 @@ function TimeoutField({
   )
 }
 
+/** Runtime transport: a fresh process per run, or a resident one kept warm per job. */
+function TransportFields({
+  transport,
+  maxRuns,
+  onChange,
+}: {
+  transport: RuntimeTransport
+  maxRuns: number | null
+  onChange: (transport: RuntimeTransport, maxRuns: number | null) => void
+}) {
+  return (
+    <>
+      <Field label="Transport">
+        <select
+          className={inputCls}
+          value={transport}
+          onChange={(e) =>
+            onChange(e.target.value as RuntimeTransport, null)
+          }
+        >
+          <option value="file">File (fresh process per run)</option>
+          <option value="socket">Socket (resident process, prepare once)</option>
+        </select>
+      </Field>
+      {transport === 'socket' && (
+        <Field label="Recycle after runs (optional, default 100)">
+          <input
+            type="number"
+            min={1}
+            className={inputCls}
+            value={maxRuns ?? ''}
+            onChange={(e) =>
+              onChange(
+                transport,
+                e.target.value === '' ? null : Number(e.target.value)
+              )
+            }
+          />
+        </Field>
+      )}
+    </>
+  )
+}
+
+/** Where the job's code comes from: already on the worker, an uploaded bundle, or git. */
+function CodeSourceFields({
+  value,
+  onChange,
+}: {
+  value: CodeSource | null
+  onChange: (code: CodeSource | null) => void
+}) {
+  return (
+    <>
+      <Field label="Code source">
+        <select
+          className={inputCls}
+          value={value?.source ?? 'worker'}
+          onChange={(e) =>
+            onChange(
+              e.target.value === 'bundle'
+                ? { source: 'bundle', name: '', version: null }
+                : e.target.value === 'git'
+                  ? { source: 'git', url: '', rev: null, subdir: null }
+                  : null
+            )
+          }
+        >
+          <option value="worker">On the worker</option>
+          <option value="bundle">Uploaded code bundle</option>
+          <option value="git">Git repository</option>
+        </select>
+      </Field>
+      {value?.source === 'bundle' && (
+        <>
+          <Field label="Bundle name">
+            <input
+              type="text"
+              className={inputCls}
+              value={value.name}
+              onChange={(e) => onChange({ ...value, name: e.target.value.trim() })}
+            />
+          </Field>
+          <Field label="Bundle version (optional, default latest)">
+            <input
+              type="number"
+              min={1}
+              className={inputCls}
+              value={value.version ?? ''}
+              onChange={(e) =>
+                onChange({
+                  ...value,
+                  version: e.target.value === '' ? null : Number(e.target.value),
+                })
+              }
+            />
+          </Field>
+        </>
+      )}
+      {value?.source === 'git' && (
+        <>
+          <Field label="Repository URL (or local mirror path)">
+            <input
+              type="text"
+              className={inputCls}
+              placeholder="file:///srv/git/jobs.git"
+              value={value.url}
+              onChange={(e) => onChange({ ...value, url: e.target.value.trim() })}
+            />
+          </Field>
+          <Field label="Branch, tag or commit (optional, default HEAD)">
+            <input
+              type="text"
+              className={inputCls}
+              value={value.rev ?? ''}
+              onChange={(e) =>
+                onChange({ ...value, rev: e.target.value.trim() || null })
+              }
+            />
+          </Field>
+          <Field label="Subdirectory (optional)">
+            <input
+              type="text"
+              className={inputCls}
+              value={value.subdir ?? ''}
+              onChange={(e) =>
+                onChange({ ...value, subdir: e.target.value.trim() || null })
+              }
+            />
+          </Field>
+        </>
+      )}
+    </>
+  )
+}
+
+/** The directory the run works in: its own, removed after it, or one the job's runs share. */
+function WorkspaceField({
+  value,
+  onChange,
+}: {
+  value: WorkspaceMode
+  onChange: (workspace: WorkspaceMode) => void
+}) {
+  return (
+    <Field label="Workspace">
+      <select
+        className={inputCls}
+        value={value}
+        onChange={(e) => onChange(e.target.value as WorkspaceMode)}
+      >
+        <option value="run">Per run (removed when the run is over)</option>
+        <option value="job">Per job (shared by its runs, kept between them)</option>
+      </select>
+    </Field>
+  )
+}
+
+/** How the env's `secret:` values reach the process: as env vars, or as files. */
+function SecretDeliveryField({
+  value,
+  onChange,
+}: {
+  value: SecretDelivery
+  onChange: (delivery: SecretDelivery) => void
+}) {
+  return (
+    <Field label="Env secrets">
+      <select
+        className={inputCls}
+        value={value}
+        onChange={(e) => onChange(e.target.value as SecretDelivery)}
+      >
+        <option value="env">As env vars</option>
+        <option value="files">As files in a private dir (ARBITER_SECRETS_DIR / ctx)</option>
+      </select>
+    </Field>
+  )
+}
+
 function DbConfigPicker({
   engine,
   value,
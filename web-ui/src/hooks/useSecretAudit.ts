@@ -0,0 +1,10 @@
+import { useQuery } from '@tanstack/react-query'
+import { fetchSecretAudit } from '../api/secrets'
+
+export function useSecretAudit(id: string | null) {
+  return useQuery({
+    queryKey: ['secrets', id, 'audit'],
+    queryFn: () => fetchSecretAudit(id!),
+    enabled: id !== null,
+  })
+}
@@ -0,0 +1,10 @@
+import { useQuery } from '@tanstack/react-query'
+import { fetchSecretReferences } from '../api/secrets'
+
+export function useSecretReferences(id: string | null) {
+  return useQuery({
+    queryKey: ['secrets', id, 'references'],
+    queryFn: () => fetchSecretReferences(id!),
+    enabled: id !== null,
+  })
+}
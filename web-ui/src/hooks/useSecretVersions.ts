@@ -0,0 +1,10 @@
+import { useQuery } from '@tanstack/react-query'
+import { fetchSecretVersions } from '../api/secrets'
+
+export function useSecretVersions(id: string | null) {
+  return useQuery({
+    queryKey: ['secrets', id, 'versions'],
+    queryFn: () => fetchSecretVersions(id!),
+    enabled: id !== null,
+  })
+}
@@ -1,7 +1,12 @@
 import { useState } from 'react'
 import { useMutation, useQueryClient } from '@tanstack/react-query'
 import { useDbConfigs } from '../hooks/useDbConfigs'
-import { createDbConfig, deleteDbConfig, updateDbConfig } from '../api/configs'
+import {
+  createDbConfig,
+  deleteDbConfig,
+  testDbConfig,
+  updateDbConfig,
+} from '../api/configs'
 import { SlideOver } from '../components/SlideOver'
 import { Button } from '../components/Button'
 import { SecretRefPicker } from '../components/SecretRefPicker'
@@ -75,6 +80,7 @@ export function DbConfigsPage() {
                   <Td>{c.username}</Td>
                   <Td className="font-mono text-(--text-muted)">{c.passwordSecret}</Td>
                   <Td align="right">
+                    <TestConnection id={c.id} />
                     <Button
                       variant="ghost"
                       className="text-(--text-danger)"
@@ -119,6 +125,48 @@ export function DbConfigsPage() {
   )
 }
 
+/** Row action: probe the config from a worker and show latency or the failing step. */
+function TestConnection({ id }: { id: string }) {
+  const mutation = useMutation({ mutationFn: () => testDbConfig(id) })
+  const result = mutation.data
+
+  return (
+    <span className="inline-flex items-center gap-2">
+      {mutation.isPending && (
+        <span className="text-xs text-(--text-muted)">Testing…</span>
+      )}
+      {result &&
+        (result.ok ? (
+          <span className="text-xs text-(--text-success)">
+            OK · {result.latencyMs} ms
+          </span>
+        ) : (
+          <span
+            className="text-xs text-(--text-danger)"
+            title={result.error?.message}
+          >
+            {result.error?.code ?? 'failed'}
+          </span>
+        ))}
+      {mutation.error && (
+        <span className="text-xs text-(--text-danger)">
+          {String(mutation.error)}
+        </span>
+      )}
+      <Button
+        variant="ghost"
+        disabled={mutation.isPending}
+        onClick={(e) => {
+          e.stopPropagation()
+          mutation.mutate()
+        }}
+      >
+        Test
+      </Button>
+    </span>
+  )
+}
+
 type FormProps =
   | { mode: 'create'; initial?: undefined; onDone: () => void }
   | { mode: 'edit'; initial: SharedDbConfig; onDone: () => void }
@@ -135,6 +183,9 @@ function DbConfigForm({ mode, initial, onDone }: FormProps) {
   const [passwordSecret, setPasswordSecret] = useState(
     initial?.passwordSecret ?? ''
   )
+  const [poolSize, setPoolSize] = useState<number | null>(
+    initial?.poolSize ?? null
+  )
 
   const mutation = useMutation({
     mutationFn: () => {
@@ -146,6 +197,7 @@ function DbConfigForm({ mode, initial, onDone }: FormProps) {
           username,
           passwordSecret,
           database,
+          poolSize,
         })
       }
       return createDbConfig({
@@ -156,6 +208,7 @@ function DbConfigForm({ mode, initial, onDone }: FormProps) {
         username,
         passwordSecret,
         database,
+        poolSize,
       })
     },
     onSuccess: () => {
@@ -244,6 +297,28 @@ function DbConfigForm({ mode, initial, onDone }: FormProps) {
         </span>
       </label>
 
+      <label className="block space-y-1">
+        <span className="text-sm text-(--text-secondary)">Pool size</span>
+        <input
+          type="number"
+          value={poolSize ?? ''}
+          min={1}
+          max={64}
+          placeholder="4"
+          onChange={(e) =>
+            setPoolSize(e.target.value === '' ? null : Number(e.target.value))
+          }
+          className="
+            w-full px-3 py-1.5 rounded
+            bg-(--bg-input) text-(--text-primary)
+            border border-(--border-color)
+          "
+        />
+        <span className="text-xs text-(--text-muted)">
+          Connections each worker keeps open to this database.
+        </span>
+      </label>
+
       {mutation.error && (
         <div className="text-(--text-danger) text-sm">
           {String(mutation.error)}
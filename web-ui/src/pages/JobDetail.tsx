@@ -130,8 +130,11 @@ const RUNNER_TYPE_LABEL: Record<RunnerConfig['type'], string> = {
   http: 'HTTP',
   pgSql: 'PostgreSQL',
   mySql: 'MySQL',
+  sqlite: 'SQLite',
   python: 'Python',
   node: 'Node',
+  wasm: 'WebAssembly',
+  plugin: 'Plugin',
 }
 
 function RunnerSummary({ cfg }: { cfg: RunnerConfig }) {
@@ -145,16 +148,38 @@ function RunnerSummary({ cfg }: { cfg: RunnerConfig }) {
         </Code>
       )}
       {(cfg.type === 'pgSql' || cfg.type === 'mySql') && <Code>{cfg.query}</Code>}
+      {cfg.type === 'sqlite' && (
+        <>
+          <Code>{cfg.path}</Code>
+          <Code>{cfg.query}</Code>
+        </>
+      )}
       {cfg.type === 'python' && (
         <Code>
           {cfg.module}.{cfg.className}
+          {cfg.transport === 'socket' && ' (resident)'}
         </Code>
       )}
       {cfg.type === 'node' && (
         <Code>
           {cfg.module} → {cfg.functionName}
+          {cfg.transport === 'socket' && ' (resident)'}
         </Code>
       )}
+      {cfg.type === 'wasm' && (
+        <Code>
+          {cfg.module.source === 'path'
+            ? cfg.module.path
+            : `sha256:${cfg.module.sha256}`}
+          {cfg.args.length > 0 && ` ${cfg.args.join(' ')}`}
+        </Code>
+      )}
+      {cfg.type === 'plugin' && (
+        <>
+          <Code>plugin:{cfg.plugin}</Code>
+          <Code>{JSON.stringify(cfg.config, null, 2)}</Code>
+        </>
+      )}
     </div>
   )
 }
@@ -1,10 +1,10 @@
-import { useMutation, useQueryClient } from '@tanstack/react-query'
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
 import type { JobRun } from '../backend-types/JobRun'
 import { useJobs } from '../hooks/useJobs'
 import { useRunLog } from '../hooks/useRunLog'
 import { RunLogView } from '../components/RunLogView'
 import { Button } from '../components/Button'
-import { cancelRun } from '../api/runs'
+import { cancelRun, fetchRunArtifacts, runArtifactUrl } from '../api/runs'
 import { runJobNow } from '../api/jobs'
 
 export function RunDetail({ run: runProp }: { run: JobRun }) {
@@ -14,6 +14,11 @@ export function RunDetail({ run: runProp }: { run: JobRun }) {
   // per run id by the caller, so state resets cleanly on selection change.
   const { run, chunks, loadEarlier, loadingEarlier, hasEarlier } = useRunLog(runProp)
   const live = !['succeeded', 'failed', 'cancelled'].includes(run.state)
+  // Artifacts are uploaded when an attempt finishes, so refetch as the run's state moves.
+  const { data: artifacts } = useQuery({
+    queryKey: ['runs', run.id, 'artifacts', run.state, run.attempt],
+    queryFn: () => fetchRunArtifacts(run.id),
+  })
 
   const cancelMutation = useMutation({
     mutationFn: () => cancelRun(run.id),
@@ -23,7 +28,7 @@ export function RunDetail({ run: runProp }: { run: JobRun }) {
   })
 
   const rerunMutation = useMutation({
-    mutationFn: () => runJobNow(run.jobId),
+    mutationFn: () => runJobNow(run.jobId, run.params),
     onSuccess: () => {
       qc.invalidateQueries({ queryKey: ['runs'] })
     },
@@ -47,6 +52,28 @@ export function RunDetail({ run: runProp }: { run: JobRun }) {
         <p className="mt-1 text-(--text-primary)">{run.state}</p>
       </div>
 
+      {/* Progress (reported by a Python/Node runtime via ctx.progress) */}
+      {run.progress && (
+        <div>
+          <h3 className="text-sm font-semibold text-(--text-primary)">Progress</h3>
+          <div className="mt-1 flex justify-between text-xs text-(--text-muted) mb-1">
+            <span>{run.progress.message ?? ''}</span>
+            <span>{Math.round(run.progress.value * 100)}%</span>
+          </div>
+          <div className="h-2 rounded bg-(--bg-header) overflow-hidden">
+            <div
+              className="h-full bg-(--bg-btn-primary) transition-all"
+              style={{ width: `${Math.round(run.progress.value * 100)}%` }}
+            />
+          </div>
+          {live && run.lastHeartbeat && (
+            <p className="mt-1 text-xs text-(--text-muted)">
+              Last heartbeat {format(run.lastHeartbeat)}
+            </p>
+          )}
+        </div>
+      )}
+
       {/* Command */}
       <div>
         <h3 className="text-sm font-semibold text-(--text-primary)">Command</h3>
@@ -60,6 +87,18 @@ export function RunDetail({ run: runProp }: { run: JobRun }) {
         </pre>
       </div>
 
+      {/* Code commit (what a git code source resolved to for this attempt) */}
+      {run.codeCommit && (
+        <div>
+          <h3 className="text-sm font-semibold text-(--text-primary)">
+            Code Commit
+          </h3>
+          <p className="mt-1 font-mono text-sm text-(--text-primary)">
+            {run.codeCommit}
+          </p>
+        </div>
+      )}
+
       {/* Started At*/}
       <div>
         <h3 className="text-sm font-semibold text-(--text-primary)">
@@ -94,6 +133,29 @@ export function RunDetail({ run: runProp }: { run: JobRun }) {
         </div>
       )}
 
+      {/* Artifacts (files the run left in its artifacts directory) */}
+      {artifacts && artifacts.length > 0 && (
+        <div>
+          <h3 className="text-sm font-semibold text-(--text-primary)">Artifacts</h3>
+          <ul className="mt-1 space-y-1 text-sm">
+            {artifacts.map((a) => (
+              <li key={`${a.attempt}/${a.name}`} className="flex justify-between gap-3">
+                <a
+                  className="text-(--text-accent) hover:text-(--text-accent-hover) hover:underline"
+                  href={runArtifactUrl(run.id, a.attempt, a.name)}
+                  download={a.name}
+                >
+                  {a.name}
+                </a>
+                <span className="text-(--text-muted)">
+                  attempt {a.attempt} · {formatBytes(a.sizeBytes)}
+                </span>
+              </li>
+            ))}
+          </ul>
+        </div>
+      )}
+
       {/* Output (live for a running run, paginated tail for a finished one) */}
       <RunLogView
         chunks={chunks}
@@ -119,6 +181,12 @@ export function RunDetail({ run: runProp }: { run: JobRun }) {
   )
 }
 
+function formatBytes(n: number) {
+  if (n < 1024) return `${n} B`
+  if (n < 1024 * 1024) return `${(n / 1024).toFixed(1)} KiB`
+  return `${(n / (1024 * 1024)).toFixed(1)} MiB`
+}
+
 function format(t?: string | null) {
   if (!t) return '—'
   return new Date(t).toLocaleString()
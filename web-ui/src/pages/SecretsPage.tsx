@@ -2,22 +2,53 @@ import { useState } from 'react'
 import { useMutation, useQueryClient } from '@tanstack/react-query'
 import { useSecrets } from '../hooks/useSecrets'
 import { useCreateSecret } from '../hooks/useCreateSecret'
-import { deleteSecret } from '../api/secrets'
+import { useSecretVersions } from '../hooks/useSecretVersions'
+import { useSecretReferences } from '../hooks/useSecretReferences'
+import { useSecretAudit } from '../hooks/useSecretAudit'
+import { useAuth } from '../auth/useAuth'
+import {
+  deleteSecret,
+  fetchSecretReferences,
+  rollbackSecret,
+  setSecretPolicy,
+} from '../api/secrets'
 import { SlideOver } from '../components/SlideOver'
 import { Button } from '../components/Button'
 import { Table, THead, Th, TBody, Tr, Td } from '../components/Table'
 import { formatTime } from '../utils/time'
+import type { SecretActor, SecretMetaResponse } from '../backend-types'
 
 export function SecretsPage() {
   const { data: secrets, isLoading, error } = useSecrets()
   const [createOpen, setCreateOpen] = useState(false)
+  const [detailsId, setDetailsId] = useState<string | null>(null)
+  const details = secrets?.find((s) => s.id === detailsId) ?? null
   const qc = useQueryClient()
+  const { state } = useAuth()
+
+  // The audit trail is for admins (a tenant admin sees their own tenant's).
+  const isAdmin = state.status === 'authenticated' && state.user.role === 'admin'
 
   const deleteMutation = useMutation({
-    mutationFn: (id: string) => deleteSecret(id),
+    mutationFn: ({ id, force }: { id: string; force: boolean }) =>
+      deleteSecret(id, force),
     onSuccess: () => qc.invalidateQueries({ queryKey: ['secrets'] }),
   })
 
+  // A secret still referenced needs a forced delete; say what would break first.
+  async function confirmDelete(s: SecretMetaResponse) {
+    const refs = await fetchSecretReferences(s.id)
+    const message =
+      refs.length === 0
+        ? `Delete secret "${s.name}"?`
+        : `Secret "${s.name}" is used by ${refs
+            .map((r) => `${r.name} (${r.field})`)
+            .join(', ')}. Delete it anyway?`
+    if (confirm(message)) {
+      deleteMutation.mutate({ id: s.id, force: refs.length > 0 })
+    }
+  }
+
   return (
     <div className="space-y-6">
       <h2 className="text-xl font-semibold text-(--text-primary)">Secrets</h2>
@@ -25,7 +56,9 @@ export function SecretsPage() {
       <p className="text-sm text-(--text-muted) max-w-2xl">
         Secret values are write-only: they are encrypted on the server and never
         shown again. Reference one from a job env var or DB config as{' '}
-        <code className="text-(--text-primary)">secret:&lt;name&gt;</code>.
+        <code className="text-(--text-primary)">secret:&lt;name&gt;</code>, or pin
+        a retained version as{' '}
+        <code className="text-(--text-primary)">secret:&lt;name&gt;@&lt;version&gt;</code>.
       </p>
 
       <Button variant="primary" onClick={() => setCreateOpen(true)}>
@@ -43,8 +76,10 @@ export function SecretsPage() {
           <Table>
             <THead>
               <Th>Name</Th>
+              <Th>Version</Th>
               <Th>Key version</Th>
-              <Th>Created</Th>
+              <Th>Rotation due</Th>
+              <Th>Last used</Th>
               <Th>Updated</Th>
               <Th align="right">Actions</Th>
             </THead>
@@ -52,18 +87,21 @@ export function SecretsPage() {
               {secrets.map((s) => (
                 <Tr key={s.id}>
                   <Td className="font-mono">{s.name}</Td>
+                  <Td>{s.version}</Td>
                   <Td>v{s.kekVersion}</Td>
-                  <Td>{formatTime(s.createdAt)}</Td>
+                  <Td>
+                    <RotationDue at={s.rotationDueAt} />
+                  </Td>
+                  <Td>{formatTime(s.lastResolvedAt)}</Td>
                   <Td>{formatTime(s.updatedAt)}</Td>
                   <Td align="right">
+                    <Button variant="ghost" onClick={() => setDetailsId(s.id)}>
+                      Details
+                    </Button>
                     <Button
                       variant="ghost"
                       className="text-(--text-danger)"
-                      onClick={() => {
-                        if (confirm(`Delete secret "${s.name}"?`)) {
-                          deleteMutation.mutate(s.id)
-                        }
-                      }}
+                      onClick={() => void confirmDelete(s)}
                     >
                       Delete
                     </Button>
@@ -81,6 +119,27 @@ export function SecretsPage() {
       >
         <SecretForm onDone={() => setCreateOpen(false)} />
       </SlideOver>
+
+      {deleteMutation.error && (
+        <div className="text-(--text-danger) text-sm">
+          {String(deleteMutation.error)}
+        </div>
+      )}
+
+      <SlideOver
+        open={details !== null}
+        onClose={() => setDetailsId(null)}
+        title={details ? `Secret ${details.name}` : 'Secret'}
+      >
+        {details && (
+          <div className="space-y-8">
+            <SecretPolicyForm key={details.id} secret={details} />
+            <SecretReferences secret={details} />
+            <SecretVersions secret={details} />
+            {isAdmin && <SecretAudit secret={details} />}
+          </div>
+        )}
+      </SlideOver>
     </div>
   )
 }
@@ -165,3 +224,302 @@ function SecretForm({ onDone }: { onDone: () => void }) {
     </form>
   )
 }
+
+function RotationDue({ at }: { at: string | null }) {
+  if (!at) return <span className="text-(--text-muted)">—</span>
+  const overdue = new Date(at).getTime() <= Date.now()
+  return (
+    <span className={overdue ? 'text-(--text-danger)' : undefined}>
+      {formatTime(at)}
+      {overdue && ' (overdue)'}
+    </span>
+  )
+}
+
+const DAY_SECS = 86400
+
+// Expiry and rotation interval are reminders only: an overdue secret still resolves.
+function SecretPolicyForm({ secret }: { secret: SecretMetaResponse }) {
+  const [expiresAt, setExpiresAt] = useState(
+    secret.expiresAt ? secret.expiresAt.slice(0, 10) : ''
+  )
+  const [rotateDays, setRotateDays] = useState(
+    secret.rotateEverySecs ? String(secret.rotateEverySecs / DAY_SECS) : ''
+  )
+  const qc = useQueryClient()
+
+  const policyMutation = useMutation({
+    mutationFn: () =>
+      setSecretPolicy(secret.id, {
+        expiresAt: expiresAt ? new Date(expiresAt).toISOString() : null,
+        rotateEverySecs: rotateDays
+          ? Math.round(Number(rotateDays) * DAY_SECS)
+          : null,
+      }),
+    onSuccess: () => qc.invalidateQueries({ queryKey: ['secrets'] }),
+  })
+
+  const validDays = rotateDays === '' || Number(rotateDays) > 0
+
+  return (
+    <form
+      className="space-y-4"
+      onSubmit={(e) => {
+        e.preventDefault()
+        if (validDays) policyMutation.mutate()
+      }}
+    >
+      <h3 className="font-semibold text-(--text-primary)">Rotation</h3>
+      <p className="text-sm text-(--text-muted)">
+        Set when the credential expires and how often it should be replaced;
+        the secret is flagged once either is due. It keeps resolving either way.
+      </p>
+
+      <label className="block space-y-1">
+        <span className="text-sm text-(--text-secondary)">Expires on</span>
+        <input
+          type="date"
+          value={expiresAt}
+          onChange={(e) => setExpiresAt(e.target.value)}
+          className="
+            w-full px-3 py-1.5 rounded
+            bg-(--bg-input) text-(--text-primary)
+            border border-(--border-color)
+          "
+        />
+      </label>
+
+      <label className="block space-y-1">
+        <span className="text-sm text-(--text-secondary)">
+          Rotate every (days)
+        </span>
+        <input
+          type="number"
+          min={1}
+          value={rotateDays}
+          onChange={(e) => setRotateDays(e.target.value)}
+          placeholder="none"
+          className="
+            w-full px-3 py-1.5 rounded
+            bg-(--bg-input) text-(--text-primary)
+            border border-(--border-color)
+          "
+        />
+      </label>
+
+      <div className="text-sm text-(--text-muted)">
+        Last resolved: {formatTime(secret.lastResolvedAt)}
+        {secret.lastResolvedRun && (
+          <>
+            {' '}
+            by run <span className="font-mono">{secret.lastResolvedRun}</span>
+          </>
+        )}
+      </div>
+
+      {policyMutation.error && (
+        <div className="text-(--text-danger) text-sm">
+          {String(policyMutation.error)}
+        </div>
+      )}
+
+      <Button
+        type="submit"
+        variant="primary"
+        disabled={!validDays || policyMutation.isPending}
+      >
+        {policyMutation.isPending ? 'Saving…' : 'Save'}
+      </Button>
+    </form>
+  )
+}
+
+function SecretReferences({ secret }: { secret: SecretMetaResponse }) {
+  const { data: refs, isLoading, error } = useSecretReferences(secret.id)
+
+  return (
+    <div className="space-y-2">
+      <h3 className="font-semibold text-(--text-primary)">Used by</h3>
+
+      {isLoading && <div className="text-(--text-muted)">Loading…</div>}
+
+      {error && <div className="text-(--text-danger)">{String(error)}</div>}
+
+      {refs &&
+        (refs.length === 0 ? (
+          <div className="text-sm text-(--text-muted)">
+            No job or DB config references this secret.
+          </div>
+        ) : (
+          <Table>
+            <THead>
+              <Th>{'Job / config'}</Th>
+              <Th>Field</Th>
+              <Th>Version</Th>
+            </THead>
+            <TBody>
+              {refs.map((r) => (
+                <Tr key={`${r.id}-${r.field}`}>
+                  <Td>
+                    {r.name}
+                    <span className="ml-2 text-xs text-(--text-muted)">
+                      {r.referrer === 'job' ? 'job' : 'db config'}
+                    </span>
+                  </Td>
+                  <Td className="font-mono">{r.field}</Td>
+                  <Td>{r.version ?? 'current'}</Td>
+                </Tr>
+              ))}
+            </TBody>
+          </Table>
+        ))}
+    </div>
+  )
+}
+
+function SecretVersions({ secret }: { secret: SecretMetaResponse }) {
+  const { data: versions, isLoading, error } = useSecretVersions(secret.id)
+  const qc = useQueryClient()
+
+  const rollbackMutation = useMutation({
+    mutationFn: (version: number) => rollbackSecret(secret.id, version),
+    onSuccess: () => qc.invalidateQueries({ queryKey: ['secrets'] }),
+  })
+
+  return (
+    <div className="space-y-4">
+      <h3 className="font-semibold text-(--text-primary)">Versions</h3>
+      <p className="text-sm text-(--text-muted)">
+        Rolling back makes an older value the new current version; the history
+        is kept. Values are never shown.
+      </p>
+
+      {isLoading && <div className="text-(--text-muted)">Loading…</div>}
+
+      {error && <div className="text-(--text-danger)">{String(error)}</div>}
+
+      {rollbackMutation.error && (
+        <div className="text-(--text-danger) text-sm">
+          {String(rollbackMutation.error)}
+        </div>
+      )}
+
+      {versions && (
+        <Table>
+          <THead>
+            <Th>Version</Th>
+            <Th>Key version</Th>
+            <Th>Created</Th>
+            <Th align="right">Actions</Th>
+          </THead>
+          <TBody>
+            {versions.map((v) => (
+              <Tr key={v.version}>
+                <Td>
+                  {v.version}
+                  {v.current && (
+                    <span className="ml-2 text-xs text-(--text-muted)">
+                      current
+                    </span>
+                  )}
+                  {v.restoredFrom !== null && (
+                    <span className="ml-2 text-xs text-(--text-muted)">
+                      from {v.restoredFrom}
+                    </span>
+                  )}
+                </Td>
+                <Td>v{v.kekVersion}</Td>
+                <Td>{formatTime(v.createdAt)}</Td>
+                <Td align="right">
+                  {!v.current && (
+                    <Button
+                      variant="ghost"
+                      disabled={rollbackMutation.isPending}
+                      onClick={() => {
+                        if (
+                          confirm(`Roll "${secret.name}" back to version ${v.version}?`)
+                        ) {
+                          rollbackMutation.mutate(v.version)
+                        }
+                      }}
+                    >
+                      Roll back
+                    </Button>
+                  )}
+                </Td>
+              </Tr>
+            ))}
+          </TBody>
+        </Table>
+      )}
+    </div>
+  )
+}
+
+function actorLabel(actor: SecretActor): string {
+  switch (actor.kind) {
+    case 'user':
+      return `user ${actor.userId.slice(0, 8)}`
+    case 'run':
+      return `run ${actor.runId.slice(0, 8)}`
+    case 'node':
+      return `node ${actor.nodeId.slice(0, 8)}`
+  }
+}
+
+function SecretAudit({ secret }: { secret: SecretMetaResponse }) {
+  const { data: events, isLoading, error } = useSecretAudit(secret.id)
+
+  return (
+    <div className="space-y-2">
+      <h3 className="font-semibold text-(--text-primary)">Audit</h3>
+      <p className="text-sm text-(--text-muted)">
+        Every resolve and change of this secret, newest first.
+      </p>
+
+      {isLoading && <div className="text-(--text-muted)">Loading…</div>}
+
+      {error && <div className="text-(--text-danger)">{String(error)}</div>}
+
+      {events &&
+        (events.length === 0 ? (
+          <div className="text-sm text-(--text-muted)">No events yet.</div>
+        ) : (
+          <Table>
+            <THead>
+              <Th>When</Th>
+              <Th>Action</Th>
+              <Th>By</Th>
+              <Th>Outcome</Th>
+            </THead>
+            <TBody>
+              {events.map((e) => (
+                <Tr key={e.id}>
+                  <Td>{formatTime(e.at)}</Td>
+                  <Td>
+                    {e.action}
+                    {e.version !== null && (
+                      <span className="ml-2 text-xs text-(--text-muted)">
+                        v{e.version}
+                      </span>
+                    )}
+                  </Td>
+                  <Td className="font-mono">{actorLabel(e.actor)}</Td>
+                  <Td>
+                    <span
+                      className={
+                        e.outcome === 'ok' ? undefined : 'text-(--text-danger)'
+                      }
+                      title={e.detail ?? undefined}
+                    >
+                      {e.outcome}
+                    </span>
+                  </Td>
+                </Tr>
+              ))}
+            </TBody>
+          </Table>
+        ))}
+    </div>
+  )
+}
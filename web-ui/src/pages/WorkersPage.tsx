@@ -2,6 +2,7 @@ import { useEffect, useState } from 'react'
 import { useWorkers } from '../hooks/useWorkers'
 import { useChangeStream } from '../hooks/useChangeStream'
 import { Table, THead, Th, TBody, Tr, Td } from '../components/Table'
+import type { WorkerDiskUsage } from '../backend-types'
 
 export function WorkersPage() {
   const { data: workers, isLoading, error } = useWorkers()
@@ -32,6 +33,8 @@ export function WorkersPage() {
             <Th>Restart Count</Th>
             <Th>Version</Th>
             <Th>Capacity</Th>
+            <Th>Plugins</Th>
+            <Th>Disk</Th>
             <Th>Status</Th>
           </THead>
           <TBody>
@@ -43,6 +46,14 @@ export function WorkersPage() {
                 <Td>{w.restartCount}</Td>
                 <Td>{w.version}</Td>
                 <Td>{w.capacity}</Td>
+                <Td>
+                  {w.plugins.length === 0
+                    ? '—'
+                    : w.plugins.map((p) => `${p.name} ${p.version}`).join(', ')}
+                </Td>
+                <Td>
+                  <DiskUsage usage={w.diskUsage} />
+                </Td>
                 <Td>
                   <WorkerStatus lastSeen={w.lastSeen} now={now} />
                 </Td>
@@ -59,6 +70,31 @@ function formatTime(t: string) {
   return new Date(t).toLocaleString()
 }
 
+/** What the worker's last maintenance sweep measured under its data dir (and removed). */
+function DiskUsage({ usage }: { usage: WorkerDiskUsage | null }) {
+  if (!usage) return <>—</>
+  const swept = usage.sweptEntries
+    ? `, removed ${usage.sweptEntries} entries (${formatBytes(usage.sweptBytes)})`
+    : ''
+  return (
+    <span title={`measured ${formatTime(usage.measuredAt)}${swept}`}>
+      {usage.runWorkspaces} run / {usage.jobWorkspaces} job workspaces ·{' '}
+      {formatBytes(usage.runWorkspaceBytes + usage.jobWorkspaceBytes)}
+      <br />
+      <span className="text-xs text-(--text-muted)">
+        code cache {formatBytes(usage.codeCacheBytes)}
+      </span>
+    </span>
+  )
+}
+
+function formatBytes(n: number) {
+  if (n < 1024) return `${n} B`
+  if (n < 1024 * 1024) return `${(n / 1024).toFixed(1)} KiB`
+  if (n < 1024 * 1024 * 1024) return `${(n / (1024 * 1024)).toFixed(1)} MiB`
+  return `${(n / (1024 * 1024 * 1024)).toFixed(1)} GiB`
+}
+
 function WorkerStatus({ lastSeen, now }: { lastSeen: string; now: number }) {
   // `now` is a ticking value from the parent, so this stays a pure render.
   const delta = now - new Date(lastSeen).getTime()
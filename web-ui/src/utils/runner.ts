@@ -7,14 +7,24 @@ export const RUNNER_LABELS: Record<RunnerType, string> = {
   http: 'HTTP',
   pgSql: 'PostgreSQL',
   mySql: 'MySQL',
+  sqlite: 'SQLite',
   python: 'Python',
   node: 'Node',
+  wasm: 'WebAssembly',
+  plugin: 'Plugin',
 }
 
 export function defaultRunner(type: RunnerType): RunnerConfig {
   switch (type) {
     case 'shell':
-      return { type: 'shell', command: '', workingDir: null }
+      return {
+        type: 'shell',
+        command: '',
+        workingDir: null,
+        code: null,
+        workspace: 'run',
+        secretDelivery: 'env',
+      }
     case 'http':
       return {
         type: 'http',
@@ -23,15 +33,60 @@ export function defaultRunner(type: RunnerType): RunnerConfig {
         headers: null,
         body: null,
         timeoutSec: null,
+        tls: null,
+        signing: null,
       }
     case 'pgSql':
-      return { type: 'pgSql', configId: '', query: '', timeoutSec: null }
+      return { type: 'pgSql', configId: '', query: '', timeoutSec: null, output: null }
     case 'mySql':
-      return { type: 'mySql', configId: '', query: '', timeoutSec: null }
+      return { type: 'mySql', configId: '', query: '', timeoutSec: null, output: null }
+    case 'sqlite':
+      return {
+        type: 'sqlite',
+        path: '',
+        query: '',
+        timeoutSec: null,
+        busyTimeoutSec: null,
+        output: null,
+      }
     case 'python':
-      return { type: 'python', module: '', className: '', timeoutSec: null }
+      return {
+        type: 'python',
+        module: '',
+        className: '',
+        timeoutSec: null,
+        transport: 'file',
+        maxRuns: null,
+        secrets: [],
+        code: null,
+        workspace: 'run',
+        secretDelivery: 'env',
+      }
     case 'node':
-      return { type: 'node', module: '', functionName: '', timeoutSec: null }
+      return {
+        type: 'node',
+        module: '',
+        functionName: '',
+        timeoutSec: null,
+        transport: 'file',
+        maxRuns: null,
+        secrets: [],
+        code: null,
+        workspace: 'run',
+        secretDelivery: 'env',
+      }
+    case 'wasm':
+      return {
+        type: 'wasm',
+        module: { source: 'stored', sha256: '' },
+        timeoutSec: null,
+        fuel: null,
+        maxMemoryMb: null,
+        preopens: [],
+        args: [],
+      }
+    case 'plugin':
+      return { type: 'plugin', plugin: '', config: {}, timeoutSec: null }
   }
 }
 
@@ -45,9 +100,30 @@ export function isRunnerValid(cfg: RunnerConfig): boolean {
     case 'pgSql':
     case 'mySql':
       return cfg.configId !== '' && cfg.query.trim() !== ''
+    case 'sqlite':
+      return cfg.path.startsWith('/') && cfg.query.trim() !== ''
     case 'python':
-      return cfg.module.trim() !== '' && cfg.className.trim() !== ''
+      return (
+        cfg.module.trim() !== '' &&
+        cfg.className.trim() !== '' &&
+        (cfg.maxRuns === null || cfg.maxRuns >= 1)
+      )
     case 'node':
-      return cfg.module.trim() !== '' && cfg.functionName.trim() !== ''
+      return (
+        cfg.module.trim() !== '' &&
+        cfg.functionName.trim() !== '' &&
+        (cfg.maxRuns === null || cfg.maxRuns >= 1)
+      )
+    case 'wasm':
+      return (
+        (cfg.module.source === 'path'
+          ? cfg.module.path.startsWith('/')
+          : /^[0-9a-f]{64}$/.test(cfg.module.sha256)) &&
+        cfg.preopens.every(
+          (p) => p.hostPath.startsWith('/') && p.guestPath.startsWith('/')
+        )
+      )
+    case 'plugin':
+      return /^[A-Za-z0-9_-]{1,64}$/.test(cfg.plugin)
   }
 }
@@ -4,20 +4,32 @@
  * Arbiter Node runner runtime (Layer B).
  *
  * Vendored, dependency-free. The worker writes this file once (reused across
- * runs) and invokes it per run with the handshake on argv; it requires the
- * user's module, runs the entrypoint, marshals the return value, captures
- * errors, and writes a result document. User code (Layer C) only implements
- * `run(ctx)` (and optionally `prepare(ctx)`) and never touches the wire format
- * -- swapping the transport (file -> socket) is a Layer B/A change.
+ * runs) and invokes it with the handshake on argv; it requires the user's
+ * module, runs the entrypoint, marshals the return value, captures errors, and
+ * reports a result document. User code (Layer C) only implements `run(ctx)`
+ * (and optionally `prepare(ctx)`) and never touches the wire format -- the
+ * transport (Layer A: `recvTask`, `sendEvent`, `sendResult`) is swapped
+ * underneath it.
  *
- * Handshake (argv): --module M --entry E (default "run") --result-file PATH
- * --run-id ID --transport file --protocol N. The process env is reserved for the
- * user's own variables (e.g. NODE_PATH); arbiter does not inject control vars.
+ * Handshake (argv): --module M --entry E (default "run") --protocol N, then
+ * either --transport file --result-file PATH --events-file PATH --task-file PATH
+ * --run-id ID (one run, then exit) or --transport socket --socket PATH
+ * (resident: `prepare` once, then one task per message until the worker closes
+ * the socket). A task carries the run's identity, its params, the job's
+ * declared secrets and the run's artifacts directory (`ctx.artifact`); on the
+ * file transport it is read from the private task file (protocol 2; with
+ * protocol 1 only --run-id is known). Events -- leveled `ctx.log` records,
+ * `ctx.progress` and `ctx.heartbeat` -- go to the events file as NDJSON or over
+ * the socket. The process env is reserved for the user's own variables (e.g.
+ * NODE_PATH); arbiter does not inject control vars, and secrets never travel on
+ * argv or env.
  */
 
 const fs = require("fs");
+const net = require("net");
+const path = require("path");
 
-const PROTOCOL_VERSION = 1;
+const PROTOCOL_VERSION = 2;
 
 function parseArgs(argv) {
   const args = {};
@@ -33,10 +45,22 @@ function parseArgs(argv) {
   return args;
 }
 
-function makeContext(args) {
-  // Structured logs go to stderr (captured by the worker as run logs).
-  const emit = (level, msg) => process.stderr.write(`[${level}] ${msg}\n`);
-  return {
+function protocol(args) {
+  return parseInt(args["protocol"], 10) || 1;
+}
+
+// Thrown by `ctx.retryable(msg)`: the run failed but is worth another attempt.
+class Retryable extends Error {
+  constructor(message) {
+    super(message);
+    this.name = "Retryable";
+  }
+}
+
+function makeContext(args, transport) {
+  // Leveled records, kept by the worker as structured log chunks.
+  const emit = (level, msg) => transport.sendEvent({ type: "record", level, message: String(msg) });
+  const ctx = {
     log: {
       debug: (m) => emit("debug", m),
       info: (m) => emit("info", m),
@@ -46,10 +70,48 @@ function makeContext(args) {
     state: {},
     params: {},
     runId: args["run-id"],
-    jobId: args["job-id"],
-    // v1: no event stream consumed yet; surface progress as a log line.
-    progress: (pct) => emit("info", `progress ${pct}`),
+    jobId: null,
+    scheduledFor: null,
+    attempt: null,
+    artifactsDir: null,
+    workspaceDir: null,
+    secretsDir: null,
+    _secrets: {},
+    // The value of a secret the job declares in its `secrets` list.
+    secret: (name) => {
+      if (!Object.prototype.hasOwnProperty.call(ctx._secrets, name)) {
+        throw new Error(`secret '${name}' is not declared by the job`);
+      }
+      return ctx._secrets[name];
+    },
+    // Saves `data` (a Buffer or string) as the run artifact `name`; the worker uploads it
+    // when the run finishes. Returns the file's path.
+    artifact: (name, data) => {
+      if (!ctx.artifactsDir) {
+        throw new Error("this worker keeps no artifacts");
+      }
+      if (!name || name === "." || name === ".." || /[\\/]/.test(name)) {
+        throw new Error(`invalid artifact name: ${JSON.stringify(name)}`);
+      }
+      const file = path.join(ctx.artifactsDir, name);
+      fs.writeFileSync(file, data);
+      return file;
+    },
+    // Fails the run as retryable, whatever the job's retry rules say.
+    retryable: (message) => {
+      throw new Retryable(message);
+    },
+    // A fraction in 0..1 with an optional message; also counts as a heartbeat.
+    progress: (value, message) =>
+      transport.sendEvent(
+        message === undefined
+          ? { type: "progress", value: Number(value) }
+          : { type: "progress", value: Number(value), message: String(message) },
+      ),
+    // Proves the task is alive: a run that heartbeats is never reclaimed as dead.
+    heartbeat: () => transport.sendEvent({ type: "heartbeat" }),
   };
+  return ctx;
 }
 
 function structuredError(err) {
@@ -63,46 +125,174 @@ function structuredError(err) {
   return { type: "Error", message: String(err), stack: [] };
 }
 
+function resultDoc(status, output, error) {
+  return { protocolVersion: PROTOCOL_VERSION, status, output, error };
+}
+
+// One task from argv; the result goes to --result-file and events are appended
+// to --events-file, one JSON object per line. Output stays on the process's own
+// stdout/stderr, which the worker captures.
+function fileTransport(args) {
+  let task = { runId: args["run-id"] };
+  const taskFile = args["task-file"];
+  if (taskFile && protocol(args) >= 2) {
+    task = JSON.parse(fs.readFileSync(taskFile, "utf8"));
+    // It holds secret values: gone as soon as it is read.
+    try {
+      fs.unlinkSync(taskFile);
+    } catch (_) {}
+  }
+  const eventsFile = args["events-file"];
+  return {
+    recvTask: async () => {
+      const next = task;
+      task = null;
+      return next;
+    },
+    sendEvent: (event) => {
+      if (eventsFile) {
+        fs.appendFileSync(eventsFile, JSON.stringify(event) + "\n");
+      } else if (event.type === "record") {
+        // A worker without an events file: keep records readable on stderr.
+        process.stderr.write(`[${event.level}] ${event.message}\n`);
+      }
+    },
+    sendResult: (doc) => {
+      if (args["result-file"]) {
+        fs.writeFileSync(args["result-file"], JSON.stringify(doc));
+      }
+    },
+    capture: async (body) => body(),
+    close: async () => {},
+  };
+}
+
+// Newline-delimited JSON over the worker's Unix socket: `task` in; events (`log`,
+// `record`, `progress`, `heartbeat`) and one `result` per task out. EOF from the
+// worker means shut down.
+function socketTransport(path) {
+  const sock = net.createConnection(path);
+  const tasks = [];
+  const waiting = [];
+  let buffered = "";
+  let closed = false;
+  const push = (task) => (waiting.length ? waiting.shift()(task) : tasks.push(task));
+  sock.setEncoding("utf8");
+  sock.on("data", (chunk) => {
+    buffered += chunk;
+    let nl;
+    while ((nl = buffered.indexOf("\n")) >= 0) {
+      const msg = JSON.parse(buffered.slice(0, nl));
+      buffered = buffered.slice(nl + 1);
+      if (msg.type === "task") push(msg);
+    }
+  });
+  const end = () => {
+    closed = true;
+    while (waiting.length) waiting.shift()(null);
+  };
+  sock.on("end", end);
+  sock.on("error", end);
+
+  const sendEvent = (event) => sock.write(JSON.stringify(event) + "\n");
+  return {
+    recvTask: () =>
+      tasks.length
+        ? Promise.resolve(tasks.shift())
+        : closed
+          ? Promise.resolve(null)
+          : new Promise((resolve) => waiting.push(resolve)),
+    sendEvent,
+    sendResult: (doc) => sendEvent({ ...doc, type: "result" }),
+    // Route process.stdout/stderr writes through the transport for the duration
+    // of a task, so what a task prints reaches its run ahead of its result.
+    capture: async (body) => {
+      const saved = [process.stdout.write, process.stderr.write];
+      const route = (stream) => (chunk, encoding, cb) => {
+        sendEvent({ type: "log", stream, text: String(chunk) });
+        if (typeof encoding === "function") encoding();
+        else if (typeof cb === "function") cb();
+        return true;
+      };
+      process.stdout.write = route("stdout");
+      process.stderr.write = route("stderr");
+      try {
+        return await body();
+      } finally {
+        [process.stdout.write, process.stderr.write] = saved;
+      }
+    },
+    close: () =>
+      new Promise((resolve) =>
+        sock.destroyed || sock.writableEnded ? resolve() : sock.end(resolve),
+      ),
+  };
+}
+
 async function main() {
   const args = parseArgs(process.argv.slice(2));
-  const resultFile = args["result-file"];
   const moduleName = args["module"];
   const entry = args["entry"] || "run";
+  const transport =
+    args["transport"] === "socket" ? socketTransport(args["socket"]) : fileTransport(args);
 
-  const ctx = makeContext(args);
-  let status = "success";
-  let output = null;
-  let error = null;
-
+  const ctx = makeContext(args, transport);
+  let fn;
   try {
-    if (!moduleName) {
-      throw new Error("--module is not set");
-    }
-    const mod = require(moduleName);
-    if (typeof mod.prepare === "function") {
-      await mod.prepare(ctx);
-    }
-    const fn = mod[entry];
-    if (typeof fn !== "function") {
-      throw new TypeError(`entrypoint '${entry}' is not a function`);
-    }
-    const ret = await fn(ctx);
-    output = ret === undefined ? null : ret;
+    // Require and prepare once; their output belongs to the first task's run.
+    await transport.capture(async () => {
+      if (!moduleName) {
+        throw new Error("--module is not set");
+      }
+      const mod = require(moduleName);
+      if (typeof mod.prepare === "function") {
+        await mod.prepare(ctx);
+      }
+      fn = mod[entry];
+      if (typeof fn !== "function") {
+        throw new TypeError(`entrypoint '${entry}' is not a function`);
+      }
+    });
   } catch (err) {
-    status = "failed";
-    error = structuredError(err);
+    // Answer the pending task with the failure, then exit: a resident runtime
+    // that cannot prepare is recycled.
+    if ((await transport.recvTask()) !== null) {
+      transport.sendResult({ ...resultDoc("failed", null, structuredError(err)), closing: true });
+    }
+    await transport.close();
+    process.exit(1);
   }
 
-  const doc = {
-    protocolVersion: PROTOCOL_VERSION,
-    status,
-    output,
-    error,
-  };
-  if (resultFile) {
-    fs.writeFileSync(resultFile, JSON.stringify(doc));
+  let status = "success";
+  for (let task = await transport.recvTask(); task !== null; task = await transport.recvTask()) {
+    ctx.runId = task.runId;
+    ctx.jobId = task.jobId ?? null;
+    ctx.scheduledFor = task.scheduledFor ?? null;
+    ctx.attempt = task.attempt ?? null;
+    ctx.params = task.params || {};
+    ctx.artifactsDir = task.artifactsDir ?? null;
+    ctx.workspaceDir = task.workspaceDir ?? null;
+    // With `secretDelivery: files`, the job env's secrets as files named after their key.
+    ctx.secretsDir = task.secretsDir ?? null;
+    ctx._secrets = task.secrets || {};
+    // Each task runs in its own run's workspace, also in a resident runtime.
+    if (ctx.workspaceDir) {
+      process.chdir(ctx.workspaceDir);
+    }
+    status = "success";
+    let output = null;
+    let error = null;
+    try {
+      const ret = await transport.capture(() => fn(ctx));
+      output = ret === undefined ? null : ret;
+    } catch (err) {
+      status = err instanceof Retryable ? "retryable" : "failed";
+      error = structuredError(err);
+    }
+    transport.sendResult(resultDoc(status, output, error));
   }
 
+  await transport.close();
   process.exit(status === "success" ? 0 : 1);
 }
 
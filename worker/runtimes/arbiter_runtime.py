@@ -2,25 +2,37 @@
 """Arbiter Python runner runtime (Layer B).
 
 Vendored, stdlib-only. The worker writes this file once (reused across runs) and
-invokes it per run with the handshake on argv; it imports the user's module, runs
-the entrypoint, marshals the return value, captures errors, and writes a result
+invokes it with the handshake on argv; it imports the user's module, runs the
+entrypoint, marshals the return value, captures errors, and reports a result
 document. User code (Layer C) only implements `run(ctx)` (and optionally
-`prepare(ctx)`) and never touches the wire format -- swapping the transport
-(file -> socket) is a Layer B/A change.
-
-Handshake (argv): --module M --entry E (default "run") --result-file PATH
---run-id ID --transport file --protocol N. The process env is reserved for the
-user's own variables (e.g. PYTHONPATH); arbiter does not inject control vars.
+`prepare(ctx)`) and never touches the wire format -- the transport (Layer A:
+`recv_task`, `send_event`, `send_result`) is swapped underneath it.
+
+Handshake (argv): --module M --entry E (default "run") --protocol N, then either
+--transport file --result-file PATH --events-file PATH --task-file PATH --run-id ID
+(one run, then exit) or --transport socket --socket PATH (resident: `prepare` once,
+then one task per message until the worker closes the socket). A task carries the
+run's identity, its params, the job's declared secrets and the run's artifacts
+directory (`ctx.artifact`); on the file transport it is read from the private task
+file (protocol 2; with protocol 1 only --run-id is known). Events -- leveled
+`ctx.log` records, `ctx.progress` and `ctx.heartbeat` -- go to the events file as
+NDJSON or over the socket. The process env is reserved for the user's own variables
+(e.g. PYTHONPATH); arbiter does not inject control vars, and secrets never travel on
+argv or env.
 """
 
 import dataclasses
 import importlib
 import inspect
+import io
 import json
+import os
+import socket
 import sys
+import threading
 import traceback
 
-PROTOCOL_VERSION = 1
+PROTOCOL_VERSION = 2
 
 
 def _parse_args(argv):
@@ -39,11 +51,13 @@ def _parse_args(argv):
 
 
 class _Logger:
-    """Routes structured logs to stderr (captured by the worker as run logs)."""
+    """Leveled log records, sent as `record` events and kept as structured log chunks."""
+
+    def __init__(self, transport):
+        self._transport = transport
 
     def _emit(self, level, msg):
-        sys.stderr.write("[{}] {}\n".format(level, msg))
-        sys.stderr.flush()
+        self._transport.send_event({"type": "record", "level": level, "message": str(msg)})
 
     def debug(self, msg):
         self._emit("debug", msg)
@@ -62,17 +76,75 @@ class _State:
     """Scratch namespace; prepare() can stash warmed resources here for run()."""
 
 
+class Retryable(Exception):
+    """Raised by `ctx.retryable(msg)`: the run failed but is worth another attempt."""
+
+
 class Context:
-    def __init__(self, args):
-        self.log = _Logger()
+    def __init__(self, args, transport):
+        self._transport = transport
+        self.log = _Logger(transport)
         self.state = _State()
         self.run_id = args.get("run-id")
-        self.job_id = args.get("job-id")
+        self.job_id = None
+        self.scheduled_for = None
+        self.attempt = None
         self.params = {}
-
-    def progress(self, pct):
-        # v1: no event stream consumed yet; surface progress as a log line.
-        self.log.info("progress {}".format(pct))
+        self.artifacts_dir = None
+        self.workspace_dir = None
+        self.secrets_dir = None
+        self._secrets = {}
+
+    def _start(self, task):
+        """Point the context at a task; `state` carries over between tasks."""
+        self.run_id = task.get("runId", self.run_id)
+        self.job_id = task.get("jobId")
+        self.scheduled_for = task.get("scheduledFor")
+        self.attempt = task.get("attempt")
+        self.params = task.get("params") or {}
+        self.artifacts_dir = task.get("artifactsDir")
+        self.workspace_dir = task.get("workspaceDir")
+        # With `secretDelivery: files`, the job env's secrets as files named after their key.
+        self.secrets_dir = task.get("secretsDir")
+        self._secrets = task.get("secrets") or {}
+        # Each task runs in its own run's workspace, also in a resident runtime.
+        if self.workspace_dir:
+            os.chdir(self.workspace_dir)
+
+    def secret(self, name):
+        """The value of a secret the job declares in its `secrets` list."""
+        try:
+            return self._secrets[name]
+        except KeyError:
+            raise LookupError("secret '{}' is not declared by the job".format(name)) from None
+
+    def artifact(self, name, data):
+        """Save `data` (bytes or str) as the run artifact `name`; the worker uploads it
+        when the run finishes. Returns the file's path."""
+        if not self.artifacts_dir:
+            raise RuntimeError("this worker keeps no artifacts")
+        if not name or name in (".", "..") or "/" in name or "\\" in name:
+            raise ValueError("invalid artifact name: {!r}".format(name))
+        path = os.path.join(self.artifacts_dir, name)
+        with open(path, "wb") as f:
+            f.write(data.encode("utf-8") if isinstance(data, str) else data)
+        return path
+
+    def retryable(self, message):
+        """Fail the run as retryable, whatever the job's retry rules say."""
+        raise Retryable(message)
+
+    def progress(self, value, message=None):
+        """Report progress as a fraction in 0..1, with an optional message. Also counts
+        as a heartbeat."""
+        event = {"type": "progress", "value": float(value)}
+        if message is not None:
+            event["message"] = str(message)
+        self._transport.send_event(event)
+
+    def heartbeat(self):
+        """Prove the task is alive: a run that heartbeats is never reclaimed as dead."""
+        self._transport.send_event({"type": "heartbeat"})
 
 
 def _jsonable(value):
@@ -130,40 +202,190 @@ def _call(fn, ctx):
         raise
 
 
+def _protocol(args):
+    try:
+        return int(args.get("protocol") or 1)
+    except ValueError:
+        return 1
+
+
+def _failure(exc):
+    return {
+        "type": type(exc).__name__,
+        "message": str(exc),
+        "stack": traceback.format_exception(type(exc), exc, exc.__traceback__),
+    }
+
+
+def _result(status, output=None, error=None):
+    return {
+        "protocolVersion": PROTOCOL_VERSION,
+        "status": status,
+        "output": output,
+        "error": error,
+    }
+
+
+class _FileTransport:
+    """One task from argv; the result goes to --result-file and events are appended to
+    --events-file, one JSON object per line. Output stays on the process's own
+    stdout/stderr, which the worker captures."""
+
+    def __init__(self, args):
+        self._task = {"runId": args.get("run-id")}
+        task_file = args.get("task-file")
+        if task_file and _protocol(args) >= 2:
+            with open(task_file, encoding="utf-8") as handle:
+                self._task = json.load(handle)
+            # It holds secret values: gone as soon as it is read.
+            try:
+                os.remove(task_file)
+            except OSError:
+                pass
+        self._result_file = args.get("result-file")
+        self._events = None
+        if args.get("events-file"):
+            self._events = open(args["events-file"], "a", encoding="utf-8")
+        self._lock = threading.Lock()
+
+    def recv_task(self):
+        task, self._task = self._task, None
+        return task
+
+    def send_event(self, event):
+        if self._events is None:
+            # A worker without an events file: keep records readable on stderr.
+            if event.get("type") == "record":
+                sys.stderr.write("[{}] {}\n".format(event["level"], event["message"]))
+                sys.stderr.flush()
+            return
+        with self._lock:
+            self._events.write(json.dumps(event) + "\n")
+            self._events.flush()
+
+    def send_result(self, doc):
+        if self._result_file:
+            with open(self._result_file, "w") as handle:
+                json.dump(doc, handle)
+
+    def capture(self):
+        return _NoCapture()
+
+
+class _SocketTransport:
+    """Newline-delimited JSON over the worker's Unix socket: `task` in; events (`log`,
+    `record`, `progress`, `heartbeat`) and one `result` per task out. EOF from the
+    worker means shut down."""
+
+    def __init__(self, path):
+        self._sock = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+        self._sock.connect(path)
+        self._reader = self._sock.makefile("r", encoding="utf-8")
+        self._lock = threading.Lock()
+
+    def recv_task(self):
+        for line in self._reader:
+            msg = json.loads(line)
+            if msg.get("type") == "task":
+                return msg
+        return None
+
+    def send_event(self, event):
+        data = (json.dumps(event) + "\n").encode("utf-8")
+        with self._lock:
+            self._sock.sendall(data)
+
+    def send_result(self, doc):
+        self.send_event(dict(doc, type="result"))
+
+    def capture(self):
+        return _Capture(self)
+
+
+class _EventStream(io.TextIOBase):
+    """A text stream whose writes become `log` events on the transport."""
+
+    def __init__(self, transport, name):
+        self._transport = transport
+        self._name = name
+
+    def writable(self):
+        return True
+
+    def write(self, text):
+        if text:
+            self._transport.send_event({"type": "log", "stream": self._name, "text": text})
+        return len(text)
+
+
+class _Capture:
+    """Route sys.stdout/sys.stderr through the transport for the duration of a task,
+    so what a task prints reaches its run ahead of its result."""
+
+    def __init__(self, transport):
+        self._transport = transport
+
+    def __enter__(self):
+        self._saved = (sys.stdout, sys.stderr)
+        sys.stdout = _EventStream(self._transport, "stdout")
+        sys.stderr = _EventStream(self._transport, "stderr")
+
+    def __exit__(self, *exc):
+        sys.stdout, sys.stderr = self._saved
+        return False
+
+
+class _NoCapture:
+    def __enter__(self):
+        pass
+
+    def __exit__(self, *exc):
+        return False
+
+
 def main():
     args = _parse_args(sys.argv[1:])
-    result_file = args.get("result-file")
     module_name = args.get("module")
     entry = args.get("entry") or "run"
+    if args.get("transport") == "socket":
+        transport = _SocketTransport(args.get("socket"))
+    else:
+        transport = _FileTransport(args)
 
-    ctx = Context(args)
-    status, output, error = "success", None, None
+    ctx = Context(args, transport)
     try:
-        if not module_name:
-            raise RuntimeError("--module is not set")
-        module = importlib.import_module(module_name)
-        prepare = getattr(module, "prepare", None)
-        if callable(prepare):
-            _call(prepare, ctx)
-        fn = _as_callable(getattr(module, entry))
-        output = _jsonable(_call(fn, ctx))
+        # Import and prepare once; their output belongs to the first task's run.
+        with transport.capture():
+            if not module_name:
+                raise RuntimeError("--module is not set")
+            module = importlib.import_module(module_name)
+            prepare = getattr(module, "prepare", None)
+            if callable(prepare):
+                _call(prepare, ctx)
+            fn = _as_callable(getattr(module, entry))
     except Exception as exc:  # noqa: BLE001 - report any user error structurally
-        status = "failed"
-        error = {
-            "type": type(exc).__name__,
-            "message": str(exc),
-            "stack": traceback.format_exception(type(exc), exc, exc.__traceback__),
-        }
-
-    doc = {
-        "protocolVersion": PROTOCOL_VERSION,
-        "status": status,
-        "output": output,
-        "error": error,
-    }
-    if result_file:
-        with open(result_file, "w") as handle:
-            json.dump(doc, handle)
+        # Answer the pending task with the failure, then exit: a resident runtime
+        # that cannot prepare is recycled.
+        if transport.recv_task() is not None:
+            doc = _result("failed", error=_failure(exc))
+            doc["closing"] = True
+            transport.send_result(doc)
+        sys.exit(1)
+
+    status = "success"
+    task = transport.recv_task()
+    while task is not None:
+        ctx._start(task)
+        status, output, error = "success", None, None
+        try:
+            with transport.capture():
+                output = _jsonable(_call(fn, ctx))
+        except Retryable as exc:
+            status, error = "retryable", _failure(exc)
+        except Exception as exc:  # noqa: BLE001 - report any user error structurally
+            status, error = "failed", _failure(exc)
+        transport.send_result(_result(status, output, error))
+        task = transport.recv_task()
 
     sys.exit(0 if status == "success" else 1)
 
@@ -0,0 +1,461 @@
+//! Run artifacts: files a run leaves in its per-run artifacts directory (handed to shell
+//! jobs as `ARBITER_ARTIFACTS_DIR`, to runtimes in their task as `artifactsDir` and to WASM
+//! modules as a preopen at [`WASM_ARTIFACTS_DIR`]). Once the attempt finishes the worker
+//! uploads the directory's top-level regular files through an [`ArtifactStore`], within the
+//! tenant's size cap, and the directory goes with the run's temp files.
+//!
+//! Two stores to start: the shared [`Store`](arbiter_core::Store) itself (bytes as database
+//! blobs), and [`FsArtifactStore`], files under a directory every node can reach.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use arbiter_core::{ArbiterError, ArtifactStore, Result, RunArtifact, validate_artifact_name};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+use crate::runner::RunContext;
+
+/// Optional artifact store shared with run execution. `None` disables artifacts: runs get
+/// no artifacts directory.
+pub type Artifacts = Option<Arc<dyn ArtifactStore + Send + Sync>>;
+
+/// Env var pointing a shell job at its artifacts directory.
+pub(crate) const ARTIFACTS_ENV: &str = "ARBITER_ARTIFACTS_DIR";
+
+/// Guest path a WASM module sees its artifacts directory at.
+pub(crate) const WASM_ARTIFACTS_DIR: &str = "/artifacts";
+
+/// Upload the files an attempt left in its artifacts directory. Only top-level regular
+/// files with a valid name are taken; anything else, a file past the tenant's cap
+/// (`max_tenant_bytes`, `0` = no cap) or a failed upload is skipped. Returns one note per
+/// skipped file, for the run's log.
+pub(crate) async fn upload_artifacts(
+    ctx: &RunContext,
+    store: &(dyn ArtifactStore + Send + Sync),
+    max_tenant_bytes: u64,
+) -> Vec<String> {
+    let (Some(dir), Some(tenant)) = (ctx.artifacts_dir(), ctx.tenant()) else {
+        return Vec::new();
+    };
+    let run = ctx.run();
+    let mut notes = Vec::new();
+    let mut files = match artifact_files(dir, &mut notes).await {
+        Ok(files) => files,
+        Err(e) => return vec![format!("artifacts directory unreadable: {e}")],
+    };
+    files.sort();
+
+    let mut used = None;
+    for (name, path) in files {
+        let (file, len) = match open_artifact(&path).await {
+            Ok(Some(opened)) => opened,
+            Ok(None) => {
+                notes.push(format!("artifact {name} skipped: not a regular file"));
+                continue;
+            }
+            Err(e) => {
+                notes.push(format!("artifact {name} skipped: {e}"));
+                continue;
+            }
+        };
+        if max_tenant_bytes > 0 {
+            let held = match used {
+                Some(n) => n,
+                None => match store.tenant_artifact_bytes(tenant).await {
+                    Ok(n) => n,
+                    Err(e) => {
+                        notes.push(format!("artifacts not uploaded: {e}"));
+                        return notes;
+                    }
+                },
+            };
+            if held.saturating_add(len) > max_tenant_bytes {
+                notes.push(format!(
+                    "artifact {name} ({len} bytes) skipped: tenant artifact cap of {max_tenant_bytes} bytes reached"
+                ));
+                used = Some(held);
+                continue;
+            }
+            used = Some(held + len);
+        }
+        // No more than was counted against the cap, should the file still be growing.
+        let mut content = Vec::new();
+        if let Err(e) = file.take(len).read_to_end(&mut content).await {
+            notes.push(format!("artifact {name} skipped: {e}"));
+            continue;
+        }
+        let sha256 = format!("{:x}", Sha256::digest(&content));
+        if let Err(e) = store
+            .put_artifact(tenant, run.id, run.attempt, &name, &sha256, &content)
+            .await
+        {
+            notes.push(format!("artifact {name} not uploaded: {e}"));
+        }
+    }
+    notes
+}
+
+/// The entries of `dir` with an uploadable name: (name, path). An invalid name gets a note;
+/// what kind of file each is gets checked once it is opened.
+async fn artifact_files(
+    dir: &Path,
+    notes: &mut Vec<String>,
+) -> std::io::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let shown = entry.file_name().to_string_lossy().into_owned();
+        match entry.file_name().into_string() {
+            Ok(name) if validate_artifact_name(&name).is_ok() => files.push((name, entry.path())),
+            _ => notes.push(format!("artifact {shown} skipped: invalid name")),
+        }
+    }
+    Ok(files)
+}
+
+/// Open an artifact and size it from the open handle, so the job cannot swap the file
+/// between the checks and the read. `None` for anything but a regular file: a symlink is
+/// not followed (it could point anywhere on the worker), and a FIFO does not block the open.
+async fn open_artifact(path: &Path) -> std::io::Result<Option<(tokio::fs::File, u64)>> {
+    let file = match tokio::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOFOLLOW | libc::O_NONBLOCK)
+        .open(path)
+        .await
+    {
+        Ok(file) => file,
+        Err(e) if e.raw_os_error() == Some(libc::ELOOP) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let meta = file.metadata().await?;
+    Ok(meta.is_file().then_some((file, meta.len())))
+}
+
+/// Artifacts as files under `root`, which every api and worker node must share (e.g. a
+/// network mount). Laid out as `<tenant>/<run>/<attempt>/data/<name>`, with the artifact's
+/// metadata beside it in `<tenant>/<run>/<attempt>/meta/<name>.json`.
+pub struct FsArtifactStore {
+    root: PathBuf,
+}
+
+impl FsArtifactStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        FsArtifactStore { root: root.into() }
+    }
+
+    fn attempt_dir(&self, tenant_id: Uuid, run_id: Uuid, attempt: u32) -> PathBuf {
+        self.root
+            .join(tenant_id.to_string())
+            .join(run_id.to_string())
+            .join(attempt.to_string())
+    }
+
+    /// The metadata of every artifact under `dir` (a tenant, run or attempt directory,
+    /// `depth` levels above the attempts), with the attempt directory each lives in.
+    async fn metas(&self, dir: PathBuf, depth: usize) -> Result<Vec<(PathBuf, RunArtifact)>> {
+        let mut attempts = vec![dir];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for d in attempts {
+                next.extend(subdirs(&d).await?);
+            }
+            attempts = next;
+        }
+        let mut out = Vec::new();
+        for attempt in attempts {
+            for meta in files(&attempt.join("meta")).await? {
+                let bytes = tokio::fs::read(&meta).await.map_err(io)?;
+                let artifact: RunArtifact = serde_json::from_slice(&bytes).map_err(|e| {
+                    ArbiterError::ExecutionError(format!(
+                        "invalid artifact metadata {}: {e}",
+                        meta.display()
+                    ))
+                })?;
+                out.push((attempt.clone(), artifact));
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[async_trait]
+impl ArtifactStore for FsArtifactStore {
+    async fn put_artifact(
+        &self,
+        tenant_id: Uuid,
+        run_id: Uuid,
+        attempt: u32,
+        name: &str,
+        sha256: &str,
+        content: &[u8],
+    ) -> Result<RunArtifact> {
+        validate_artifact_name(name)?;
+        let dir = self.attempt_dir(tenant_id, run_id, attempt);
+        tokio::fs::create_dir_all(dir.join("data"))
+            .await
+            .map_err(io)?;
+        tokio::fs::create_dir_all(dir.join("meta"))
+            .await
+            .map_err(io)?;
+        let artifact = RunArtifact {
+            run_id,
+            attempt,
+            name: name.to_string(),
+            size_bytes: content.len() as u64,
+            sha256: sha256.to_string(),
+            created_at: Utc::now(),
+        };
+        let meta = serde_json::to_vec(&artifact)
+            .map_err(|e| ArbiterError::ExecutionError(e.to_string()))?;
+        // Write-then-rename, so a reader never sees a partial file; the data lands first so
+        // listed metadata always has its bytes.
+        write_atomic(&dir, &dir.join("data").join(name), content).await?;
+        write_atomic(&dir, &dir.join("meta").join(format!("{name}.json")), &meta).await?;
+        Ok(artifact)
+    }
+
+    async fn list_artifacts(&self, tenant_id: Uuid, run_id: Uuid) -> Result<Vec<RunArtifact>> {
+        let dir = self
+            .root
+            .join(tenant_id.to_string())
+            .join(run_id.to_string());
+        let mut artifacts: Vec<RunArtifact> = self
+            .metas(dir, 1)
+            .await?
+            .into_iter()
+            .map(|(_, a)| a)
+            .collect();
+        artifacts.sort_by(|a, b| (a.attempt, &a.name).cmp(&(b.attempt, &b.name)));
+        Ok(artifacts)
+    }
+
+    async fn get_artifact(
+        &self,
+        tenant_id: Uuid,
+        run_id: Uuid,
+        attempt: u32,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        if validate_artifact_name(name).is_err() {
+            return Ok(None);
+        }
+        let path = self
+            .attempt_dir(tenant_id, run_id, attempt)
+            .join("data")
+            .join(name);
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io(e)),
+        }
+    }
+
+    async fn tenant_artifact_bytes(&self, tenant_id: Uuid) -> Result<u64> {
+        let dir = self.root.join(tenant_id.to_string());
+        Ok(self
+            .metas(dir, 2)
+            .await?
+            .iter()
+            .map(|(_, a)| a.size_bytes)
+            .sum())
+    }
+
+    async fn prune_artifacts(&self, older_than: DateTime<Utc>) -> Result<u64> {
+        let mut pruned = 0;
+        for tenant in subdirs(&self.root).await? {
+            for (dir, artifact) in self.metas(tenant.clone(), 2).await? {
+                if artifact.created_at >= older_than {
+                    continue;
+                }
+                let meta = dir.join("meta").join(format!("{}.json", artifact.name));
+                remove_if_present(&meta).await?;
+                remove_if_present(&dir.join("data").join(&artifact.name)).await?;
+                pruned += 1;
+            }
+            // Drop the attempt and run directories left empty.
+            for run in subdirs(&tenant).await? {
+                for attempt in subdirs(&run).await? {
+                    if files(&attempt.join("meta")).await?.is_empty() {
+                        let _ = tokio::fs::remove_dir_all(&attempt).await;
+                    }
+                }
+                let _ = tokio::fs::remove_dir(&run).await;
+            }
+        }
+        Ok(pruned)
+    }
+}
+
+fn io(e: std::io::Error) -> ArbiterError {
+    ArbiterError::ExecutionError(format!("artifact store: {e}"))
+}
+
+/// Write `content` to `path` through a temp file in `dir` renamed into place.
+async fn write_atomic(dir: &Path, path: &Path, content: &[u8]) -> Result<()> {
+    let tmp = dir.join(format!(".tmp-{}", Uuid::new_v4()));
+    tokio::fs::write(&tmp, content).await.map_err(io)?;
+    if let Err(e) = tokio::fs::rename(&tmp, path).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(io(e));
+    }
+    Ok(())
+}
+
+async fn remove_if_present(path: &Path) -> Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io(e)),
+        _ => Ok(()),
+    }
+}
+
+/// The subdirectories of `dir`; none when it does not exist.
+async fn subdirs(dir: &Path) -> Result<Vec<PathBuf>> {
+    Ok(entries(dir)
+        .await?
+        .into_iter()
+        .filter_map(|(path, is_dir)| is_dir.then_some(path))
+        .collect())
+}
+
+/// The files of `dir`; none when it does not exist.
+async fn files(dir: &Path) -> Result<Vec<PathBuf>> {
+    Ok(entries(dir)
+        .await?
+        .into_iter()
+        .filter_map(|(path, is_dir)| (!is_dir).then_some(path))
+        .collect())
+}
+
+async fn entries(dir: &Path) -> Result<Vec<(PathBuf, bool)>> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(io(e)),
+    };
+    let mut out = Vec::new();
+    while let Some(entry) = entries.next_entry().await.map_err(io)? {
+        let is_dir = entry.file_type().await.map_err(io)?.is_dir();
+        out.push((entry.path(), is_dir));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fs_store_roundtrip_and_prune() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = FsArtifactStore::new(dir.path());
+        let (tenant, run) = (Uuid::new_v4(), Uuid::new_v4());
+        store
+            .put_artifact(tenant, run, 2, "b.txt", "bb", b"two")
+            .await
+            .expect("put b");
+        store
+            .put_artifact(tenant, run, 1, "a.txt", "aa", b"one!")
+            .await
+            .expect("put a");
+        let replaced = store
+            .put_artifact(tenant, run, 2, "b.txt", "cc", b"2")
+            .await
+            .expect("replace b");
+        assert_eq!(replaced.size_bytes, 1);
+
+        let listed = store.list_artifacts(tenant, run).await.expect("list");
+        let keys: Vec<_> = listed
+            .iter()
+            .map(|a| (a.attempt, a.name.as_str()))
+            .collect();
+        assert_eq!(keys, vec![(1, "a.txt"), (2, "b.txt")]);
+        assert_eq!(listed[1].sha256, "cc");
+        assert_eq!(
+            store
+                .get_artifact(tenant, run, 2, "b.txt")
+                .await
+                .expect("get")
+                .as_deref(),
+            Some(&b"2"[..])
+        );
+        assert!(
+            store
+                .get_artifact(tenant, run, 1, "../a.txt")
+                .await
+                .expect("get")
+                .is_none()
+        );
+        assert!(
+            store
+                .put_artifact(tenant, run, 1, "..", "dd", b"x")
+                .await
+                .is_err()
+        );
+        assert_eq!(store.tenant_artifact_bytes(tenant).await.expect("bytes"), 5);
+        assert_eq!(
+            store
+                .tenant_artifact_bytes(Uuid::new_v4())
+                .await
+                .expect("bytes"),
+            0
+        );
+
+        assert_eq!(
+            store
+                .prune_artifacts(Utc::now() - chrono::Duration::hours(1))
+                .await
+                .expect("prune"),
+            0
+        );
+        assert_eq!(store.prune_artifacts(Utc::now()).await.expect("prune"), 2);
+        assert!(
+            store
+                .list_artifacts(tenant, run)
+                .await
+                .expect("list")
+                .is_empty()
+        );
+        assert!(
+            !dir.path()
+                .join(tenant.to_string())
+                .join(run.to_string())
+                .exists(),
+            "the emptied run directory is removed"
+        );
+    }
+
+    #[tokio::test]
+    async fn only_regular_files_are_opened() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let outside = dir.path().join("outside");
+        std::fs::write(&outside, b"worker secret").expect("write");
+        let artifacts = dir.path().join("artifacts");
+        std::fs::create_dir_all(artifacts.join("nested")).expect("mkdir");
+        std::fs::write(artifacts.join("report.csv"), b"a,b\n").expect("write");
+        std::os::unix::fs::symlink(&outside, artifacts.join("link")).expect("symlink");
+        let fifo = artifacts.join("pipe").into_os_string().into_encoded_bytes();
+        let fifo = std::ffi::CString::new(fifo).expect("path");
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0, "mkfifo");
+
+        let (mut file, len) = open_artifact(&artifacts.join("report.csv"))
+            .await
+            .expect("open")
+            .expect("regular file");
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).await.expect("read");
+        assert_eq!((len, content.as_slice()), (4, &b"a,b\n"[..]));
+        for name in ["link", "pipe", "nested"] {
+            assert!(
+                open_artifact(&artifacts.join(name))
+                    .await
+                    .expect("open")
+                    .is_none(),
+                "{name} is not uploadable"
+            );
+        }
+    }
+}
@@ -0,0 +1,585 @@
+//! The runners shipped with arbiter, one per built-in snapshot kind, registered by
+//! [`RunnerRegistry::with_builtins`](crate::RunnerRegistry::with_builtins). Each is a thin
+//! adapter: `prepare` unpacks its snapshot variant and resolves secrets, `execute` calls
+//! the runner's existing execution path.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use arbiter_core::{
+    ArbiterError, DbOutputConfig, ExecutableConfigSnapshotMeta, HttpSigningConfig, Result,
+    RunOutcome, RuntimeTransport, WasmModuleSource, WasmPreopen,
+};
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use crate::artifacts::ARTIFACTS_ENV;
+use crate::code::{apply_code_env, checkout, code_working_dir};
+use crate::db_pool::DbTarget;
+#[cfg(unix)]
+use crate::resident::{ResidentPool, ResidentSpec, execute_resident};
+use crate::runner::{RunContext, Runner, RunnerRegistry, SECRETS_DIR_ENV};
+use crate::workspace::WORKSPACE_ENV;
+use crate::{
+    CommandRunOutput, DocumentRun, Lang, build_shell_command, execute_http_request,
+    execute_mysql_query, execute_pgsql_query, execute_runtime, execute_sqlite_query,
+    SECRET_PREFIX, prepare_http_run, process_outcome, result_document_outcome, run_subprocess,
+    wasm,
+};
+
+pub(crate) fn register(reg: &mut RunnerRegistry) {
+    reg.register("shell", ShellRunner)
+        .register("python", RuntimeRunner::new(Lang::Python))
+        .register("node", RuntimeRunner::new(Lang::Node))
+        .register("http", HttpRunner)
+        .register("pgsql", SqlRunner)
+        .register("mysql", SqlRunner)
+        .register("sqlite", SqliteRunner)
+        .register("wasm", WasmRunner::default());
+}
+
+/// A runner was handed a snapshot of a kind it does not execute (a registry mix-up).
+fn wrong_snapshot(expected: &str, meta: &ExecutableConfigSnapshotMeta) -> ArbiterError {
+    ArbiterError::ExecutionError(format!(
+        "{expected} runner cannot execute a {} snapshot",
+        meta.type_of_str()
+    ))
+}
+
+struct ShellRunner;
+
+#[async_trait]
+impl Runner for ShellRunner {
+    type Input = ExecutableConfigSnapshotMeta;
+    type Prepared = (Command, HashMap<String, String>);
+    type Output = CommandRunOutput;
+
+    async fn prepare(
+        &self,
+        ctx: &RunContext,
+        meta: ExecutableConfigSnapshotMeta,
+    ) -> Result<Self::Prepared> {
+        match meta {
+            ExecutableConfigSnapshotMeta::Shell {
+                command,
+                working_dir,
+                code,
+                workspace,
+                secret_delivery,
+                env,
+            } => {
+                let mut env = ctx.resolve_env_as(&env, secret_delivery).await?;
+                if let Some(dir) = ctx.secrets_dir() {
+                    env.insert(SECRETS_DIR_ENV.to_string(), dir.to_string_lossy().into_owned());
+                }
+                if let Some(dir) = ctx.artifacts_dir() {
+                    env.insert(ARTIFACTS_ENV.to_string(), dir.to_string_lossy().into_owned());
+                }
+                let workspace = ctx.workspace(workspace)?;
+                env.insert(
+                    WORKSPACE_ENV.to_string(),
+                    workspace.to_string_lossy().into_owned(),
+                );
+                // The child starts in its fetched code, or else an explicit `working_dir`,
+                // or else its workspace.
+                let mut cmd = build_shell_command(&command);
+                match &code {
+                    Some(source) => {
+                        let dir = checkout(ctx, source).await?;
+                        apply_code_env(&mut env, &dir, None);
+                        cmd.current_dir(code_working_dir(&dir, working_dir.as_deref()));
+                    }
+                    None => {
+                        cmd.current_dir(working_dir.as_deref().map_or(workspace.as_path(), Path::new));
+                    }
+                }
+                Ok((cmd, env))
+            }
+            other => Err(wrong_snapshot("shell", &other)),
+        }
+    }
+
+    async fn execute(&self, ctx: &RunContext, (cmd, env): Self::Prepared) -> Result<Self::Output> {
+        run_subprocess(ctx, cmd, &env, None, None).await
+    }
+
+    fn outcome(&self, ctx: &RunContext, output: Self::Output) -> RunOutcome {
+        process_outcome(output, ctx.retry_on())
+    }
+}
+
+/// Python and Node jobs, through the injected language runtime: a one-shot process per
+/// run, or (on the `socket` transport) the job's resident process.
+struct RuntimeRunner {
+    lang: Lang,
+    #[cfg(unix)]
+    resident: ResidentPool,
+}
+
+impl RuntimeRunner {
+    fn new(lang: Lang) -> Self {
+        RuntimeRunner {
+            lang,
+            #[cfg(unix)]
+            resident: ResidentPool::default(),
+        }
+    }
+}
+
+struct RuntimeJob {
+    module: String,
+    entry: String,
+    timeout_sec: Option<u32>,
+    transport: RuntimeTransport,
+    max_runs: Option<u32>,
+    env: HashMap<String, String>,
+    /// The declared secrets, resolved by name for `ctx.secret`.
+    secrets: HashMap<String, String>,
+}
+
+#[async_trait]
+impl Runner for RuntimeRunner {
+    type Input = ExecutableConfigSnapshotMeta;
+    type Prepared = RuntimeJob;
+    type Output = DocumentRun;
+
+    async fn prepare(
+        &self,
+        ctx: &RunContext,
+        meta: ExecutableConfigSnapshotMeta,
+    ) -> Result<Self::Prepared> {
+        let (
+            module,
+            entry,
+            timeout_sec,
+            transport,
+            max_runs,
+            secrets,
+            code,
+            workspace,
+            secret_delivery,
+            env,
+        ) = match (self.lang, meta) {
+                (
+                    Lang::Python,
+                    ExecutableConfigSnapshotMeta::Python {
+                        module,
+                        class_name,
+                        timeout_sec,
+                        transport,
+                        max_runs,
+                        secrets,
+                        code,
+                        workspace,
+                        secret_delivery,
+                        env,
+                    },
+                ) => (
+                    module,
+                    class_name,
+                    timeout_sec,
+                    transport,
+                    max_runs,
+                    secrets,
+                    code,
+                    workspace,
+                    secret_delivery,
+                    env,
+                ),
+                (
+                    Lang::Node,
+                    ExecutableConfigSnapshotMeta::Node {
+                        module,
+                        function_name,
+                        timeout_sec,
+                        transport,
+                        max_runs,
+                        secrets,
+                        code,
+                        workspace,
+                        secret_delivery,
+                        env,
+                    },
+                ) => (
+                    module,
+                    function_name,
+                    timeout_sec,
+                    transport,
+                    max_runs,
+                    secrets,
+                    code,
+                    workspace,
+                    secret_delivery,
+                    env,
+                ),
+                (lang, other) => return Err(wrong_snapshot(lang.name(), &other)),
+            };
+        // Files-delivered secrets reach the runtime through its task (`secretsDir`), not the
+        // env, which would recycle a resident process on every run.
+        let mut env = ctx.resolve_env_as(&env, secret_delivery).await?;
+        // The runtime makes the workspace its working dir for the task.
+        ctx.workspace(workspace)?;
+        // A new code version is a new directory, so it also changes the resident process's
+        // env and gets it recycled. A git checkout is per run, so its process is too.
+        if let Some(source) = &code {
+            let dir = checkout(ctx, source).await?;
+            apply_code_env(&mut env, &dir, Some(self.lang.search_path_env()));
+        }
+        Ok(RuntimeJob {
+            module,
+            entry,
+            timeout_sec,
+            transport,
+            max_runs,
+            env,
+            secrets: resolve_named(ctx, secrets).await?,
+        })
+    }
+
+    async fn execute(&self, ctx: &RunContext, job: Self::Prepared) -> Result<Self::Output> {
+        // Without Unix sockets, or while another run of the job holds its resident process,
+        // a socket job runs one-shot.
+        #[cfg(unix)]
+        if job.transport == RuntimeTransport::Socket
+            && let Some(slot) = self.resident.acquire(ctx.run().job_id)
+        {
+            let spec = ResidentSpec {
+                module: &job.module,
+                entry: &job.entry,
+                env: &job.env,
+                secrets: &job.secrets,
+                max_runs: job
+                    .max_runs
+                    .unwrap_or(RuntimeTransport::DEFAULT_MAX_RUNS)
+                    .max(1),
+            };
+            return execute_resident(ctx, slot, self.lang, spec, job.timeout_sec).await;
+        }
+        execute_runtime(
+            ctx,
+            self.lang,
+            &job.module,
+            &job.entry,
+            &job.env,
+            &job.secrets,
+            job.timeout_sec,
+        )
+        .await
+    }
+
+    fn outcome(&self, ctx: &RunContext, output: Self::Output) -> RunOutcome {
+        result_document_outcome(output.doc, output.raw, ctx.retry_on())
+    }
+}
+
+/// Resolve secrets by name in the run's tenant.
+async fn resolve_named(ctx: &RunContext, names: Vec<String>) -> Result<HashMap<String, String>> {
+    let mut out = HashMap::with_capacity(names.len());
+    for name in names {
+        let value = ctx.resolve(&format!("{SECRET_PREFIX}{name}")).await?;
+        out.insert(name, value);
+    }
+    Ok(out)
+}
+
+struct HttpRunner;
+
+struct HttpJob {
+    client: reqwest::Client,
+    signing: Option<(HttpSigningConfig, String)>,
+    method: String,
+    url: String,
+    headers: HashMap<String, String>,
+    body: Option<String>,
+    timeout_sec: Option<u32>,
+}
+
+#[async_trait]
+impl Runner for HttpRunner {
+    type Input = ExecutableConfigSnapshotMeta;
+    type Prepared = HttpJob;
+    type Output = RunOutcome;
+
+    async fn prepare(
+        &self,
+        ctx: &RunContext,
+        meta: ExecutableConfigSnapshotMeta,
+    ) -> Result<Self::Prepared> {
+        match meta {
+            ExecutableConfigSnapshotMeta::Http {
+                method,
+                url,
+                headers,
+                body,
+                timeout_sec,
+                tls,
+                signing,
+            } => {
+                let (client, key) = prepare_http_run(tls.as_ref(), signing.as_ref(), ctx).await?;
+                Ok(HttpJob {
+                    client,
+                    signing: signing.zip(key),
+                    method,
+                    url,
+                    headers,
+                    body,
+                    timeout_sec,
+                })
+            }
+            other => Err(wrong_snapshot("http", &other)),
+        }
+    }
+
+    async fn execute(&self, ctx: &RunContext, job: Self::Prepared) -> Result<Self::Output> {
+        execute_http_request(
+            ctx.worker_id(),
+            ctx.run().id,
+            &job.client,
+            job.signing.as_ref().map(|(cfg, key)| (cfg, key.as_str())),
+            &job.method,
+            &job.url,
+            &job.headers,
+            job.body.as_deref(),
+            job.timeout_sec,
+            ctx.retry_on(),
+        )
+        .await
+    }
+
+    fn outcome(&self, _ctx: &RunContext, output: Self::Output) -> RunOutcome {
+        output
+    }
+}
+
+/// pgsql and mysql: the same shared-config snapshot, on the engine's warm pool.
+struct SqlRunner;
+
+struct SqlJob {
+    mysql: bool,
+    target: DbTarget,
+    query: String,
+    timeout_sec: Option<u32>,
+    output: DbOutputConfig,
+}
+
+#[async_trait]
+impl Runner for SqlRunner {
+    type Input = ExecutableConfigSnapshotMeta;
+    type Prepared = SqlJob;
+    type Output = RunOutcome;
+
+    async fn prepare(
+        &self,
+        ctx: &RunContext,
+        meta: ExecutableConfigSnapshotMeta,
+    ) -> Result<Self::Prepared> {
+        let mysql = matches!(meta, ExecutableConfigSnapshotMeta::MySql { .. });
+        match meta {
+            ExecutableConfigSnapshotMeta::PgSql {
+                config_id,
+                host,
+                port,
+                username,
+                password_secret,
+                database,
+                pool_size,
+                query,
+                timeout_sec,
+                output,
+            }
+            | ExecutableConfigSnapshotMeta::MySql {
+                config_id,
+                host,
+                port,
+                username,
+                password_secret,
+                database,
+                pool_size,
+                query,
+                timeout_sec,
+                output,
+            } => {
+                let password = ctx.resolve(&password_secret).await?;
+                Ok(SqlJob {
+                    mysql,
+                    target: DbTarget {
+                        config_id,
+                        host,
+                        port,
+                        username,
+                        password,
+                        database,
+                        pool_size,
+                    },
+                    query,
+                    timeout_sec,
+                    output: output.unwrap_or_default(),
+                })
+            }
+            other => Err(wrong_snapshot("sql", &other)),
+        }
+    }
+
+    async fn execute(&self, ctx: &RunContext, job: Self::Prepared) -> Result<Self::Output> {
+        let bind = ctx.bind();
+        if job.mysql {
+            execute_mysql_query(
+                ctx.worker_id(),
+                &job.target,
+                &job.query,
+                &bind,
+                job.timeout_sec,
+                &job.output,
+            )
+            .await
+        } else {
+            execute_pgsql_query(
+                ctx.worker_id(),
+                &job.target,
+                &job.query,
+                &bind,
+                job.timeout_sec,
+                &job.output,
+            )
+            .await
+        }
+    }
+
+    fn outcome(&self, _ctx: &RunContext, output: Self::Output) -> RunOutcome {
+        output
+    }
+}
+
+struct SqliteRunner;
+
+struct SqliteJob {
+    path: String,
+    query: String,
+    timeout_sec: Option<u32>,
+    busy_timeout_sec: Option<u32>,
+    output: DbOutputConfig,
+}
+
+#[async_trait]
+impl Runner for SqliteRunner {
+    type Input = ExecutableConfigSnapshotMeta;
+    type Prepared = SqliteJob;
+    type Output = RunOutcome;
+
+    async fn prepare(
+        &self,
+        _ctx: &RunContext,
+        meta: ExecutableConfigSnapshotMeta,
+    ) -> Result<Self::Prepared> {
+        match meta {
+            ExecutableConfigSnapshotMeta::Sqlite {
+                path,
+                query,
+                timeout_sec,
+                busy_timeout_sec,
+                output,
+            } => Ok(SqliteJob {
+                path,
+                query,
+                timeout_sec,
+                busy_timeout_sec,
+                output: output.unwrap_or_default(),
+            }),
+            other => Err(wrong_snapshot("sqlite", &other)),
+        }
+    }
+
+    async fn execute(&self, ctx: &RunContext, job: Self::Prepared) -> Result<Self::Output> {
+        execute_sqlite_query(
+            ctx.worker_id(),
+            &job.path,
+            job.busy_timeout_sec,
+            &job.query,
+            &ctx.bind(),
+            job.timeout_sec,
+            &job.output,
+        )
+        .await
+    }
+
+    fn outcome(&self, _ctx: &RunContext, output: Self::Output) -> RunOutcome {
+        output
+    }
+}
+
+/// `wasm`: a WASI module run in-process. Host paths (preopens and on-disk modules) are
+/// confined to `host_root`; the built-in registration has none, so only uploaded modules
+/// without preopens run until the node registers one with [`WasmRunner::new`].
+#[derive(Default)]
+pub struct WasmRunner {
+    host_root: Option<PathBuf>,
+}
+
+impl WasmRunner {
+    /// A runner allowing host paths under `host_root` (the `[worker] wasm_host_root`).
+    pub fn new(host_root: Option<PathBuf>) -> Self {
+        Self { host_root }
+    }
+}
+
+/// A `wasm` snapshot with its env resolved, ready to run.
+pub struct WasmJob {
+    module: WasmModuleSource,
+    timeout_sec: Option<u32>,
+    fuel: Option<u64>,
+    max_memory_mb: Option<u32>,
+    preopens: Vec<WasmPreopen>,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+}
+
+#[async_trait]
+impl Runner for WasmRunner {
+    type Input = ExecutableConfigSnapshotMeta;
+    type Prepared = WasmJob;
+    type Output = DocumentRun;
+
+    async fn prepare(
+        &self,
+        ctx: &RunContext,
+        meta: ExecutableConfigSnapshotMeta,
+    ) -> Result<Self::Prepared> {
+        match meta {
+            ExecutableConfigSnapshotMeta::Wasm {
+                module,
+                timeout_sec,
+                fuel,
+                max_memory_mb,
+                preopens,
+                args,
+                env,
+            } => Ok(WasmJob {
+                module,
+                timeout_sec,
+                fuel,
+                max_memory_mb,
+                preopens,
+                args,
+                env: ctx.resolve_env(&env).await?,
+            }),
+            other => Err(wrong_snapshot("wasm", &other)),
+        }
+    }
+
+    async fn execute(&self, ctx: &RunContext, job: Self::Prepared) -> Result<Self::Output> {
+        let run = wasm::WasmRun {
+            module: &job.module,
+            timeout_sec: job.timeout_sec,
+            fuel: job.fuel,
+            max_memory_mb: job.max_memory_mb,
+            preopens: &job.preopens,
+            args: &job.args,
+            host_root: self.host_root.as_deref(),
+        };
+        wasm::execute_wasm(ctx, run, &job.env).await
+    }
+
+    fn outcome(&self, ctx: &RunContext, output: Self::Output) -> RunOutcome {
+        result_document_outcome(output.doc, output.raw, ctx.retry_on())
+    }
+}
@@ -0,0 +1,383 @@
+//! Job code fetched at run time ([`CodeSource`]) instead of deployed to every worker. An
+//! uploaded bundle is downloaded once per tenant and content hash and unpacked, read-only,
+//! into the worker's code cache, `<data_dir>/code/bundles/<tenant>/<sha256>`; runs of the
+//! tenant's jobs using those bytes share it.
+//! A git source is mirrored once per repository URL under `<data_dir>/code/git/` and
+//! fetched as runs need it; each run gets its own checkout of the resolved commit under
+//! `<data_dir>/code/checkouts/`, removed when the run is over.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use arbiter_core::{ArbiterError, BundleFormat, CodeSource, Result};
+use sha2::{Digest, Sha256};
+
+use crate::runner::RunContext;
+
+/// Env var carrying the directory of the run's fetched code.
+pub const CODE_DIR_ENV: &str = "ARBITER_CODE_DIR";
+
+/// Upper bound on one `git` invocation (a clone or fetch from a hung remote fails the run
+/// instead of holding it forever).
+const GIT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// The local directory holding `source`'s code, fetched first if this worker's cache does
+/// not have it yet.
+pub(crate) async fn checkout(ctx: &RunContext, source: &CodeSource) -> Result<PathBuf> {
+    match source {
+        CodeSource::Bundle { name, version } => checkout_bundle(ctx, name, *version).await,
+        CodeSource::Git { url, rev, subdir } => {
+            checkout_git(ctx, url, rev.as_deref(), subdir.as_deref()).await
+        }
+    }
+}
+
+async fn checkout_bundle(ctx: &RunContext, name: &str, version: Option<u32>) -> Result<PathBuf> {
+    let missing = || {
+        ArbiterError::ExecutionError(match version {
+            Some(v) => format!("code bundle {name} v{v} not found"),
+            None => format!("code bundle {name} not found"),
+        })
+    };
+    // Bundles are per tenant; a job without one has none to use.
+    let tenant = ctx.tenant().ok_or_else(missing)?;
+    let bundle = ctx
+        .store()
+        .get_code_bundle(name, version, Some(tenant))
+        .await?
+        .ok_or_else(missing)?;
+    let dir = ctx
+        .data_dir()
+        .join("code")
+        .join("bundles")
+        .join(tenant.to_string())
+        .join(&bundle.sha256);
+    if tokio::fs::try_exists(&dir).await.unwrap_or(false) {
+        return Ok(dir);
+    }
+
+    let content = ctx
+        .store()
+        .get_code_bundle_content(name, bundle.version, Some(tenant))
+        .await?
+        .ok_or_else(missing)?;
+    if format!("{:x}", Sha256::digest(&content)) != bundle.sha256 {
+        return Err(ArbiterError::ExecutionError(format!(
+            "code bundle {name} v{} does not match its sha256",
+            bundle.version
+        )));
+    }
+    tracing::debug!(
+        "run {}: unpacking code bundle {name} v{} into {}",
+        ctx.run().id,
+        bundle.version,
+        dir.display()
+    );
+    let target = dir.clone();
+    tokio::task::spawn_blocking(move || unpack(&content, bundle.format, &target))
+        .await
+        .map_err(|e| ArbiterError::ExecutionError(e.to_string()))?
+        .map_err(|e| {
+            ArbiterError::ExecutionError(format!("code bundle {name}: cannot unpack: {e}"))
+        })?;
+    Ok(dir)
+}
+
+/// Check out `url` at `rev` into a fresh per-run directory and record the commit on the run.
+async fn checkout_git(
+    ctx: &RunContext,
+    url: &str,
+    rev: Option<&str>,
+    subdir: Option<&str>,
+) -> Result<PathBuf> {
+    let code = ctx.data_dir().join("code");
+    let repo = code
+        .join("git")
+        .join(format!("{:x}", Sha256::digest(url.as_bytes())));
+    let commit = {
+        let lock = repo_lock(&repo);
+        let _held = lock.lock().await;
+        sync_repo(&repo, url, rev.unwrap_or("HEAD")).await?
+    };
+    tracing::debug!("run {}: {url} at {commit}", ctx.run().id);
+    ctx.store()
+        .record_run_code_commit(ctx.run().id, ctx.worker_id(), &commit)
+        .await?;
+
+    let checkouts = code.join("checkouts");
+    let workspace = tokio::fs::create_dir_all(&checkouts)
+        .await
+        .and_then(|()| {
+            tempfile::Builder::new()
+                .prefix(&format!("{}-", ctx.run().id))
+                .tempdir_in(&checkouts)
+        })
+        .map_err(|e| ArbiterError::ExecutionError(format!("no checkout directory: {e}")))?;
+    let mut args = vec!["archive", "--format=tar", commit.as_str()];
+    if let Some(subdir) = subdir {
+        args.extend(["--", subdir]);
+    }
+    let archive = git(Some(&repo), &args).await?;
+    let target = workspace.path().to_path_buf();
+    tokio::task::spawn_blocking(move || tar::Archive::new(&archive[..]).unpack(&target))
+        .await
+        .map_err(|e| ArbiterError::ExecutionError(e.to_string()))?
+        .map_err(|e| ArbiterError::ExecutionError(format!("cannot check out {url}: {e}")))?;
+    let dir = match subdir {
+        Some(subdir) => workspace.path().join(subdir),
+        None => workspace.path().to_path_buf(),
+    };
+    if !dir.is_dir() {
+        return Err(ArbiterError::ExecutionError(format!(
+            "{url} has no directory {} at {commit}",
+            subdir.unwrap_or_default()
+        )));
+    }
+    ctx.keep_until_done(workspace);
+    Ok(dir)
+}
+
+/// One lock per repository cache: a clone or fetch must not race another on the same
+/// mirror, while different repositories sync in parallel.
+fn repo_lock(repo: &Path) -> Arc<tokio::sync::Mutex<()>> {
+    static LOCKS: LazyLock<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>> =
+        LazyLock::new(Mutex::default);
+    LOCKS
+        .lock()
+        .expect("repo locks")
+        .entry(repo.to_path_buf())
+        .or_default()
+        .clone()
+}
+
+/// Bring the mirror at `repo` up to date as far as `rev` needs and resolve it to a commit.
+/// A full commit id already in the mirror needs no fetch; a branch or tag always fetches,
+/// so a run never starts on a stale ref.
+async fn sync_repo(repo: &Path, url: &str, rev: &str) -> Result<String> {
+    if !repo.join("HEAD").is_file() {
+        let parent = repo
+            .parent()
+            .ok_or_else(|| ArbiterError::ExecutionError("git cache has no parent".into()))?;
+        let tmp = tokio::fs::create_dir_all(parent)
+            .await
+            .and_then(|()| tempfile::Builder::new().prefix(".tmp-").tempdir_in(parent))
+            .map_err(|e| ArbiterError::ExecutionError(format!("no git cache directory: {e}")))?;
+        let dest = tmp.path().as_os_str();
+        git(
+            None,
+            &[
+                OsStr::new("clone"),
+                OsStr::new("--mirror"),
+                OsStr::new("--quiet"),
+                OsStr::new("--"),
+                OsStr::new(url),
+                dest,
+            ],
+        )
+        .await?;
+        std::fs::rename(tmp.path(), repo)
+            .map_err(|e| ArbiterError::ExecutionError(format!("git cache: {e}")))?;
+        let _ = tmp.keep();
+    } else if !(is_commit_id(rev) && resolve_rev(repo, rev).await.is_ok()) {
+        git(Some(repo), &["fetch", "--prune", "--quiet", "origin"]).await?;
+    }
+    resolve_rev(repo, rev).await.map_err(|_| {
+        ArbiterError::ExecutionError(format!("{url} has no branch, tag or commit {rev}"))
+    })
+}
+
+async fn resolve_rev(repo: &Path, rev: &str) -> Result<String> {
+    let out = git(
+        Some(repo),
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{rev}^{{commit}}"),
+        ],
+    )
+    .await?;
+    Ok(String::from_utf8_lossy(&out).trim().to_string())
+}
+
+/// A full SHA-1 or SHA-256 object id, which names the same commit forever.
+fn is_commit_id(rev: &str) -> bool {
+    matches!(rev.len(), 40 | 64) && rev.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Run `git` (against `git_dir` when given) and return its stdout. Never prompts for
+/// credentials; a failure carries git's stderr.
+async fn git(git_dir: Option<&Path>, args: &[impl AsRef<OsStr>]) -> Result<Vec<u8>> {
+    let mut cmd = tokio::process::Command::new("git");
+    if let Some(dir) = git_dir {
+        cmd.arg("--git-dir").arg(dir);
+    }
+    cmd.args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    let what = args
+        .first()
+        .map(|a| a.as_ref().to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let out = tokio::time::timeout(GIT_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| ArbiterError::ExecutionError(format!("git {what} timed out")))?
+        .map_err(|e| ArbiterError::ExecutionError(format!("cannot run git: {e}")))?;
+    if !out.status.success() {
+        return Err(ArbiterError::ExecutionError(format!(
+            "git {what} failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        )));
+    }
+    Ok(out.stdout)
+}
+
+/// Unpack an archive into `dir`, atomically: it is extracted into a sibling temp dir that
+/// is made read-only and renamed into place, so a crash or a concurrent fetch of the same
+/// bytes never leaves a half-written tree behind, and a run cannot change the code the next
+/// run of the same bundle gets. Entries escaping the archive root are not written.
+fn unpack(content: &[u8], format: BundleFormat, dir: &Path) -> std::io::Result<()> {
+    let parent = dir
+        .parent()
+        .ok_or_else(|| std::io::Error::other("code cache dir has no parent"))?;
+    std::fs::create_dir_all(parent)?;
+    let tmp = tempfile::Builder::new()
+        .prefix(".tmp-")
+        .tempdir_in(parent)?;
+    match format {
+        BundleFormat::Tar => tar::Archive::new(content).unpack(tmp.path())?,
+        BundleFormat::TarGz => {
+            tar::Archive::new(flate2::read::GzDecoder::new(content)).unpack(tmp.path())?
+        }
+        BundleFormat::Zip => zip::ZipArchive::new(Cursor::new(content))
+            .and_then(|mut archive| archive.extract(tmp.path()))
+            .map_err(std::io::Error::other)?,
+    }
+    set_read_only(tmp.path(), true)?;
+    match std::fs::rename(tmp.path(), dir) {
+        Ok(()) => {
+            // Now lives at `dir`; nothing left for the guard to remove.
+            let _ = tmp.keep();
+            Ok(())
+        }
+        Err(e) => {
+            // Writable again, or the guard could not remove it.
+            let _ = set_read_only(tmp.path(), false);
+            // Another run unpacked the same bytes first; ours is dropped.
+            if dir.is_dir() { Ok(()) } else { Err(e) }
+        }
+    }
+}
+
+/// Clear the write bits of everything under `path` (a 0644 file becomes 0444, a directory
+/// 0555), or give the owner write access back. Symlinks are left alone rather than followed.
+fn set_read_only(path: &Path, read_only: bool) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let meta = std::fs::symlink_metadata(path)?;
+    if meta.is_symlink() {
+        return Ok(());
+    }
+    if meta.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            set_read_only(&entry?.path(), read_only)?;
+        }
+    }
+    let mode = meta.permissions().mode();
+    let mode = if read_only {
+        mode & !0o222
+    } else {
+        mode | 0o200
+    };
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+/// Point a run at its code: `ARBITER_CODE_DIR`, plus for python/node `search_path_var`
+/// (`PYTHONPATH`/`NODE_PATH`) led by the code dir, ahead of the job's (or else the
+/// worker's) own entries.
+pub(crate) fn apply_code_env(
+    env: &mut HashMap<String, String>,
+    dir: &Path,
+    search_path_var: Option<&str>,
+) {
+    env.insert(CODE_DIR_ENV.to_string(), dir.to_string_lossy().into_owned());
+    let Some(var) = search_path_var else {
+        return;
+    };
+    let inherited = env
+        .get(var)
+        .cloned()
+        .or_else(|| std::env::var(var).ok())
+        .unwrap_or_default();
+    let paths = std::iter::once(dir.to_path_buf())
+        .chain(std::env::split_paths(&inherited).filter(|p| !p.as_os_str().is_empty()));
+    if let Ok(joined) = std::env::join_paths(paths) {
+        env.insert(var.to_string(), joined.to_string_lossy().into_owned());
+    }
+}
+
+/// A shell job's working directory when it has fetched code: `working_dir` inside the
+/// code dir (an absolute one as-is), or the code dir itself.
+pub(crate) fn code_working_dir(dir: &Path, working_dir: Option<&str>) -> PathBuf {
+    match working_dir {
+        Some(wd) => dir.join(wd),
+        None => dir.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpacks_tar_gz_once_and_leads_search_path() {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        let body = b"def run(ctx):\n    return 1\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(body.len() as u64);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "pkg/job.py", &body[..])
+            .expect("append");
+        let archive = builder.into_inner().expect("tar").finish().expect("gz");
+        assert_eq!(BundleFormat::detect(&archive), Some(BundleFormat::TarGz));
+
+        let cache = tempfile::tempdir().expect("tempdir");
+        let dir = cache.path().join("bundles").join("abc");
+        unpack(&archive, BundleFormat::TarGz, &dir).expect("unpack");
+        assert_eq!(std::fs::read(dir.join("pkg/job.py")).expect("read"), body);
+        let mode = |path: &Path| {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::metadata(path).expect("stat").permissions().mode() & 0o777
+        };
+        assert_eq!(
+            mode(&dir.join("pkg/job.py")),
+            0o444,
+            "the cached code is read-only"
+        );
+        assert_eq!(mode(&dir.join("pkg")), 0o555);
+        assert_eq!(mode(&dir), 0o555);
+        // A second unpack of the same bytes finds the dir in place and leaves no temp dirs.
+        unpack(&archive, BundleFormat::TarGz, &dir).expect("unpack again");
+        let entries: Vec<_> = std::fs::read_dir(dir.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, vec![std::ffi::OsString::from("abc")]);
+
+        let mut env = HashMap::from([("PYTHONPATH".to_string(), "/opt/lib".to_string())]);
+        apply_code_env(&mut env, &dir, Some("PYTHONPATH"));
+        let expected = std::env::join_paths([dir.as_path(), Path::new("/opt/lib")]).unwrap();
+        assert_eq!(env["PYTHONPATH"], expected.to_string_lossy());
+        assert_eq!(env[CODE_DIR_ENV], dir.to_string_lossy());
+        assert_eq!(code_working_dir(&dir, Some("pkg")), dir.join("pkg"));
+    }
+}
@@ -0,0 +1,234 @@
+//! "Test connection" for shared configs. The api queues a request; a worker claims it
+//! here because only workers hold the KEK to resolve the password secret and are the ones
+//! that need network reach to the target. The probe opens a fresh connection (not the
+//! warm pool, which would hide a broken edit behind an already-open connection), runs a
+//! no-op `SELECT 1`, and reports timings or which step failed. Nothing is mutated.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arbiter_core::{
+    ConfigTestError, ConfigTestRequest, ConfigTestResult, DbEngine, SharedDbConfig, Store,
+    snooze,
+};
+use chrono::Utc;
+use sqlx::{ConnectOptions, Connection, Executor};
+use uuid::Uuid;
+
+use crate::db_pool::DbTarget;
+use crate::{Secrets, resolve_ref};
+
+/// Budget for each probe step (connect, then query).
+const STEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tests claimed per pass; they are cheap and bounded by [`STEP_TIMEOUT`].
+const CLAIM_LIMIT: u32 = 4;
+
+/// Poll backstop for a missed request notification. Well under the api's wait.
+const BACKSTOP: Duration = Duration::from_secs(3);
+
+/// Claim and run config tests on their own loop, so a probe never waits behind the claim
+/// loop's idle sleep and never takes a run slot.
+pub(crate) fn spawn_config_tester(
+    store: Arc<dyn Store + Send + Sync>,
+    worker_id: Uuid,
+    secrets: Secrets,
+) {
+    tokio::spawn(async move {
+        loop {
+            match store
+                .claim_config_tests(worker_id, CLAIM_LIMIT, ConfigTestRequest::MAX_WAIT_SECS)
+                .await
+            {
+                Ok(tests) => {
+                    for test in tests {
+                        let store = store.clone();
+                        let secrets = secrets.clone();
+                        tokio::spawn(async move {
+                            let result = test_db_config(worker_id, &test.config, &secrets).await;
+                            if let Err(e) = store.finish_config_test(test.id, &result).await {
+                                tracing::error!(
+                                    "{worker_id}: recording config test {} failed: {e:?}",
+                                    test.id
+                                );
+                            }
+                        });
+                    }
+                }
+                Err(e) => tracing::error!("{worker_id}: claim_config_tests failed: {e:?}"),
+            }
+            tokio::select! {
+                _ = snooze(BACKSTOP, 30) => {}
+                _ = store.await_config_tests_change() => {}
+            }
+        }
+    });
+}
+
+/// Open one connection and round-trip a no-op query; returns (connect, query) durations.
+/// A macro rather than a generic fn: sqlx's per-lifetime `Executor` impls do not satisfy
+/// the higher-ranked bound a generic version needs inside a spawned task.
+macro_rules! probe {
+    ($opts:expr) => {{
+        let started = Instant::now();
+        match tokio::time::timeout(STEP_TIMEOUT, $opts.connect()).await {
+            Err(_) => Err(timeout_error("connect")),
+            Ok(Err(e)) => Err(classify(e, "connect_failed")),
+            Ok(Ok(mut conn)) => {
+                let connected = started.elapsed();
+                let started = Instant::now();
+                let queried = tokio::time::timeout(STEP_TIMEOUT, conn.execute("SELECT 1")).await;
+                let latency = started.elapsed();
+                let _ = conn.close().await;
+                match queried {
+                    Err(_) => Err(timeout_error("query")),
+                    Ok(Err(e)) => Err(classify(e, "query_failed")),
+                    Ok(Ok(_)) => Ok((connected, latency)),
+                }
+            }
+        }
+    }};
+}
+
+/// Resolve the config's password, connect, and run `SELECT 1`, each step bounded.
+pub(crate) async fn test_db_config(
+    worker_id: Uuid,
+    cfg: &SharedDbConfig,
+    secrets: &Secrets,
+) -> ConfigTestResult {
+    let password = resolve_ref(secrets, Some(cfg.tenant_id), &cfg.password_secret).await;
+    let outcome = match password {
+        Ok(password) => {
+            let target = DbTarget {
+                config_id: cfg.id,
+                host: cfg.host.clone(),
+                port: cfg.port,
+                username: cfg.username.clone(),
+                password,
+                database: cfg.database.clone(),
+                pool_size: cfg.pool_size,
+            };
+            match cfg.engine {
+                DbEngine::PgSql => probe!(target.pg_options()),
+                DbEngine::MySql => probe!(target.mysql_options()),
+            }
+        }
+        Err(e) => Err(test_error("secret_unresolved", e)),
+    };
+    let (ok, connect_ms, latency_ms, error) = match outcome {
+        Ok((connect, query)) => (true, Some(millis(connect)), Some(millis(query)), None),
+        Err(e) => (false, None, None, Some(e)),
+    };
+    ConfigTestResult {
+        ok,
+        connect_ms,
+        latency_ms,
+        error,
+        worker_id,
+        tested_at: Utc::now(),
+    }
+}
+
+/// Map a driver error to a stable code. Credential and unknown-database rejections are
+/// called out since they are the usual result of a bad edit; anything else falls back to
+/// the step that failed.
+fn classify(e: sqlx::Error, fallback: &str) -> ConfigTestError {
+    let code = match &e {
+        sqlx::Error::Tls(_) => "tls_failed",
+        sqlx::Error::Io(_) => "connect_failed",
+        sqlx::Error::Database(db) => {
+            let mysql_number = db
+                .try_downcast_ref::<sqlx::mysql::MySqlDatabaseError>()
+                .map(|e| e.number());
+            match (db.code().as_deref(), mysql_number) {
+                (_, Some(1045)) | (Some("28P01" | "28000"), None) => "auth_failed",
+                (_, Some(1049)) | (Some("3D000"), None) => "unknown_database",
+                _ => fallback,
+            }
+        }
+        _ => fallback,
+    };
+    test_error(code, e)
+}
+
+fn timeout_error(step: &str) -> ConfigTestError {
+    ConfigTestError {
+        code: "timeout".to_string(),
+        message: format!("{step} did not complete within {}s", STEP_TIMEOUT.as_secs()),
+    }
+}
+
+fn test_error(code: &str, e: impl std::fmt::Display) -> ConfigTestError {
+    ConfigTestError {
+        code: code.to_string(),
+        message: e.to_string(),
+    }
+}
+
+fn millis(d: Duration) -> u64 {
+    d.as_millis().min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A config pointing at `ARBITER_TEST_DATABASE_URL`, with the password held inline.
+    fn config_from_url(url: &str) -> Option<SharedDbConfig> {
+        let rest = url.strip_prefix("postgres://")?;
+        let (creds, hostpart) = rest.split_once('@')?;
+        let (user, pass) = creds.split_once(':')?;
+        let (hostport, db) = hostpart.split_once('/')?;
+        let (host, port) = hostport.split_once(':')?;
+        Some(SharedDbConfig {
+            id: Uuid::new_v4(),
+            engine: DbEngine::PgSql,
+            name: "probe".to_string(),
+            host: host.to_string(),
+            port: port.parse().ok()?,
+            username: user.to_string(),
+            password_secret: pass.to_string(),
+            database: db.split('?').next().unwrap_or(db).to_string(),
+            tenant_id: Uuid::new_v4(),
+            pool_size: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn probe_reports_latency_or_failing_step() {
+        let url = match std::env::var("ARBITER_TEST_DATABASE_URL") {
+            Ok(u) => u,
+            Err(_) => {
+                eprintln!("skipping config probe: ARBITER_TEST_DATABASE_URL unset");
+                return;
+            }
+        };
+        let cfg = config_from_url(&url).expect("parse pg url");
+        let wid = Uuid::new_v4();
+
+        let ok = test_db_config(wid, &cfg, &None).await;
+        assert!(ok.ok, "got {:?}", ok.error);
+        assert!(ok.latency_ms.is_some() && ok.connect_ms.is_some());
+        assert_eq!(ok.worker_id, wid);
+
+        let bad_db = SharedDbConfig {
+            database: "arbiter_no_such_db".to_string(),
+            ..cfg.clone()
+        };
+        let r = test_db_config(wid, &bad_db, &None).await;
+        assert_eq!(r.error.map(|e| e.code).as_deref(), Some("unknown_database"));
+
+        let down = SharedDbConfig { port: 1, ..cfg.clone() };
+        let r = test_db_config(wid, &down, &None).await;
+        assert_eq!(r.error.map(|e| e.code).as_deref(), Some("connect_failed"));
+
+        // A secret reference with no resolver fails before any connection is attempted.
+        let unresolved = SharedDbConfig {
+            password_secret: "secret:db-pass".to_string(),
+            ..cfg
+        };
+        let r = test_db_config(wid, &unresolved, &None).await;
+        assert!(!r.ok);
+        assert_eq!(r.error.map(|e| e.code).as_deref(), Some("secret_unresolved"));
+    }
+}
@@ -0,0 +1,408 @@
+//! Result-set capture for the pgsql/mysql/sqlite runners: a statement that returns rows
+//! is decoded into typed JSON values, bounded by the job's row/byte caps, and rendered as
+//! JSON or CSV. Statements without a result set report rows affected as before.
+
+use arbiter_core::{DbOutputConfig, DbOutputFormat};
+use base64::Engine;
+use futures::TryStreamExt;
+use serde_json::{Value, json};
+use sqlx::{Column, Connection, Either, Executor, Row, Statement, TypeInfo, ValueRef};
+
+use crate::db_params::{self, BindContext, Placeholders};
+
+/// What a DB statement produced.
+pub(crate) enum DbStatementOutput {
+    RowsAffected(u64),
+    Rows(ResultSet),
+}
+
+impl DbStatementOutput {
+    /// The run `result` body and its media type.
+    pub(crate) fn render(self) -> (String, String) {
+        match self {
+            DbStatementOutput::RowsAffected(n) => (
+                format!("{{\"rows_affected\":{n}}}"),
+                "application/json".to_string(),
+            ),
+            DbStatementOutput::Rows(rs) => rs.render(),
+        }
+    }
+}
+
+/// Captured rows, stopped at `max_rows` rows or `max_bytes` of rendered output.
+pub(crate) struct ResultSet {
+    format: DbOutputFormat,
+    max_rows: usize,
+    max_bytes: usize,
+    columns: Vec<(String, String)>,
+    rows: Vec<Vec<Value>>,
+    bytes: usize,
+    truncated: bool,
+}
+
+impl ResultSet {
+    pub(crate) fn new(cfg: &DbOutputConfig, columns: Vec<(String, String)>) -> Self {
+        Self {
+            format: cfg.format,
+            max_rows: cfg.max_rows(),
+            max_bytes: cfg.max_bytes(),
+            columns,
+            rows: Vec::new(),
+            bytes: 0,
+            truncated: false,
+        }
+    }
+
+    /// Keep `row` if it fits; `false` means a cap was hit and fetching should stop.
+    pub(crate) fn push(&mut self, row: Vec<Value>) -> bool {
+        let cost = match self.format {
+            DbOutputFormat::Json => Value::Array(row.clone()).to_string().len() + 1,
+            DbOutputFormat::Csv => csv_line(&row).len(),
+        };
+        if self.rows.len() >= self.max_rows || self.bytes + cost > self.max_bytes {
+            self.truncated = true;
+            return false;
+        }
+        self.bytes += cost;
+        self.rows.push(row);
+        true
+    }
+
+    fn render(self) -> (String, String) {
+        match self.format {
+            DbOutputFormat::Json => {
+                let columns: Vec<Value> = self
+                    .columns
+                    .iter()
+                    .map(|(name, ty)| json!({ "name": name, "type": ty }))
+                    .collect();
+                let body = json!({
+                    "columns": columns,
+                    "row_count": self.rows.len(),
+                    "truncated": self.truncated,
+                    "rows": self.rows,
+                });
+                (body.to_string(), "application/json".to_string())
+            }
+            DbOutputFormat::Csv => {
+                let header: Vec<Value> = self
+                    .columns
+                    .iter()
+                    .map(|(name, _)| Value::String(name.clone()))
+                    .collect();
+                let mut body = csv_line(&header);
+                for row in &self.rows {
+                    body.push_str(&csv_line(row));
+                }
+                // CSV has no room for the flag, so it rides on the media type.
+                let media_type = if self.truncated {
+                    "text/csv; header=present; truncated=true"
+                } else {
+                    "text/csv; header=present"
+                };
+                (body, media_type.to_string())
+            }
+        }
+    }
+}
+
+/// One CSV record (RFC 4180 quoting), newline-terminated.
+fn csv_line(row: &[Value]) -> String {
+    let cells: Vec<String> = row
+        .iter()
+        .map(|v| {
+            let cell = match v {
+                Value::Null => String::new(),
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell
+            }
+        })
+        .collect();
+    let mut line = cells.join(",");
+    line.push('\n');
+    line
+}
+
+/// Run one statement on a Postgres connection, binding its `:name` parameters from the
+/// run and capturing its rows if it returns any.
+pub(crate) async fn run_pg_statement(
+    conn: &mut sqlx::postgres::PgConnection,
+    query: &str,
+    bind: &BindContext<'_>,
+    output: &DbOutputConfig,
+) -> std::result::Result<DbStatementOutput, sqlx::Error> {
+    let named = db_params::prepare(query, Placeholders::Numbered, bind);
+    let stmt = conn.prepare(&named.sql).await?;
+    let args = db_params::pg_arguments(&named.names, stmt.parameters(), bind)?;
+    if stmt.columns().is_empty() {
+        let done = stmt.query_with(args).execute(&mut *conn).await?;
+        return Ok(DbStatementOutput::RowsAffected(done.rows_affected()));
+    }
+    let mut rs = ResultSet::new(output, columns_of(stmt.columns()));
+    let mut rows = stmt.query_with(args).fetch(&mut *conn);
+    while let Some(row) = rows.try_next().await? {
+        if !rs.push((0..row.len()).map(|i| pg_value(&row, i)).collect()) {
+            break;
+        }
+    }
+    Ok(DbStatementOutput::Rows(rs))
+}
+
+/// Run one statement on a MySQL connection, binding its `:name` parameters from the run
+/// and capturing its rows if it returns any.
+pub(crate) async fn run_mysql_statement(
+    conn: &mut sqlx::mysql::MySqlConnection,
+    query: &str,
+    bind: &BindContext<'_>,
+    output: &DbOutputConfig,
+) -> std::result::Result<DbStatementOutput, sqlx::Error> {
+    let named = db_params::prepare(query, Placeholders::Positional, bind);
+    let stmt = conn.prepare(&named.sql).await?;
+    let args = db_params::mysql_arguments(&named.names, bind)?;
+    if stmt.columns().is_empty() {
+        let done = stmt.query_with(args).execute(&mut *conn).await?;
+        return Ok(DbStatementOutput::RowsAffected(done.rows_affected()));
+    }
+    let mut rs = ResultSet::new(output, columns_of(stmt.columns()));
+    let mut rows = stmt.query_with(args).fetch(&mut *conn);
+    while let Some(row) = rows.try_next().await? {
+        if !rs.push((0..row.len()).map(|i| mysql_value(&row, i)).collect()) {
+            break;
+        }
+    }
+    Ok(DbStatementOutput::Rows(rs))
+}
+
+/// Run a SQLite script (one or more statements) in a single `BEGIN IMMEDIATE`
+/// transaction, binding its `:name` parameters from the run. Taking the write lock up
+/// front means a busy file is waited on (busy timeout) before anything runs, rather than
+/// failing mid-script on a lock upgrade. The result set is that of the last statement
+/// returning rows; a script without one reports the rows its statements changed.
+/// Any error rolls the whole script back.
+pub(crate) async fn run_sqlite_script(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    query: &str,
+    bind: &BindContext<'_>,
+    output: &DbOutputConfig,
+) -> std::result::Result<DbStatementOutput, sqlx::Error> {
+    let named = db_params::prepare(query, Placeholders::Indexed, bind);
+    let args = db_params::sqlite_arguments(&named.names, bind)?;
+    let mut tx = conn.begin_with("BEGIN IMMEDIATE").await?;
+    let mut rs: Option<ResultSet> = None;
+    {
+        // `fetch_many` steps through every statement of the script; a `Left` marks the
+        // end of one. Rows past the caps are dropped but the script still runs to the end.
+        let mut steps = tx.fetch_many(sqlx::query_with(&named.sql, args));
+        let mut statement_done = true;
+        while let Some(step) = steps.try_next().await? {
+            match step {
+                Either::Left(_) => statement_done = true,
+                Either::Right(row) => {
+                    let rs = match rs.as_mut() {
+                        Some(rs) if !statement_done => rs,
+                        _ => rs.insert(ResultSet::new(output, columns_of(row.columns()))),
+                    };
+                    statement_done = false;
+                    rs.push((0..row.len()).map(|i| sqlite_value(&row, i)).collect());
+                }
+            }
+        }
+    }
+    // `changes()` after each statement is the last write's count, not that statement's,
+    // so ask the connection for its total (it was opened for this run).
+    let changed: i64 = sqlx::query_scalar("SELECT total_changes()")
+        .fetch_one(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(match rs {
+        Some(rs) => DbStatementOutput::Rows(rs),
+        None => DbStatementOutput::RowsAffected(changed as u64),
+    })
+}
+
+fn columns_of<C: Column>(cols: &[C]) -> Vec<(String, String)> {
+    cols.iter()
+        .map(|c| (c.name().to_string(), c.type_info().name().to_string()))
+        .collect()
+}
+
+/// A Postgres cell as JSON. Exact numerics are strings (no precision loss), bytes are
+/// base64, times are ISO 8601. Types without a mapping decode as text if they can,
+/// otherwise null (cast in SQL, e.g. `::text`).
+fn pg_value(row: &sqlx::postgres::PgRow, i: usize) -> Value {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+    match row.try_get_raw(i) {
+        Ok(raw) if !raw.is_null() => {}
+        _ => return Value::Null,
+    }
+    let ty = row.column(i).type_info().name();
+    let v: Option<Value> = match ty {
+        "BOOL" => row.try_get::<bool, _>(i).ok().map(Value::from),
+        "INT2" => row.try_get::<i16, _>(i).ok().map(Value::from),
+        "INT4" => row.try_get::<i32, _>(i).ok().map(Value::from),
+        "INT8" => row.try_get::<i64, _>(i).ok().map(Value::from),
+        "OID" => row
+            .try_get::<sqlx::postgres::types::Oid, _>(i)
+            .ok()
+            .map(|o| Value::from(o.0)),
+        "FLOAT4" => row.try_get::<f32, _>(i).ok().map(|f| Value::from(f as f64)),
+        "FLOAT8" => row.try_get::<f64, _>(i).ok().map(Value::from),
+        "NUMERIC" => row
+            .try_get::<sqlx::types::BigDecimal, _>(i)
+            .ok()
+            .map(|d| Value::String(d.to_string())),
+        "JSON" | "JSONB" => row.try_get::<Value, _>(i).ok(),
+        "UUID" => row
+            .try_get::<uuid::Uuid, _>(i)
+            .ok()
+            .map(|u| Value::String(u.to_string())),
+        "TIMESTAMPTZ" => row
+            .try_get::<DateTime<Utc>, _>(i)
+            .ok()
+            .map(|t| Value::String(t.to_rfc3339())),
+        "TIMESTAMP" => row
+            .try_get::<NaiveDateTime, _>(i)
+            .ok()
+            .map(|t| Value::String(t.format("%Y-%m-%dT%H:%M:%S%.f").to_string())),
+        "DATE" => row
+            .try_get::<NaiveDate, _>(i)
+            .ok()
+            .map(|d| Value::String(d.to_string())),
+        "TIME" => row
+            .try_get::<NaiveTime, _>(i)
+            .ok()
+            .map(|t| Value::String(t.to_string())),
+        "BYTEA" => row.try_get::<Vec<u8>, _>(i).ok().map(base64_value),
+        _ => None,
+    };
+    v.or_else(|| row.try_get::<String, _>(i).ok().map(Value::String))
+        .unwrap_or(Value::Null)
+}
+
+/// A MySQL cell as JSON, with the same conventions as [`pg_value`].
+fn mysql_value(row: &sqlx::mysql::MySqlRow, i: usize) -> Value {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+    match row.try_get_raw(i) {
+        Ok(raw) if !raw.is_null() => {}
+        _ => return Value::Null,
+    }
+    let ty = row.column(i).type_info().name();
+    let v: Option<Value> = match ty {
+        "BOOLEAN" => row.try_get::<bool, _>(i).ok().map(Value::from),
+        t if t.ends_with(" UNSIGNED") => row.try_get::<u64, _>(i).ok().map(Value::from),
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+            row.try_get::<i64, _>(i).ok().map(Value::from)
+        }
+        "FLOAT" => row.try_get::<f32, _>(i).ok().map(|f| Value::from(f as f64)),
+        "DOUBLE" => row.try_get::<f64, _>(i).ok().map(Value::from),
+        "DECIMAL" => row
+            .try_get::<sqlx::types::BigDecimal, _>(i)
+            .ok()
+            .map(|d| Value::String(d.to_string())),
+        "JSON" => row.try_get::<Value, _>(i).ok(),
+        "TIMESTAMP" => row
+            .try_get::<DateTime<Utc>, _>(i)
+            .ok()
+            .map(|t| Value::String(t.to_rfc3339())),
+        "DATETIME" => row
+            .try_get::<NaiveDateTime, _>(i)
+            .ok()
+            .map(|t| Value::String(t.format("%Y-%m-%dT%H:%M:%S%.f").to_string())),
+        "DATE" => row
+            .try_get::<NaiveDate, _>(i)
+            .ok()
+            .map(|d| Value::String(d.to_string())),
+        "TIME" => row
+            .try_get::<NaiveTime, _>(i)
+            .ok()
+            .map(|t| Value::String(t.to_string())),
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" => {
+            row.try_get::<Vec<u8>, _>(i).ok().map(base64_value)
+        }
+        _ => None,
+    };
+    v.or_else(|| row.try_get::<String, _>(i).ok().map(Value::String))
+        .unwrap_or(Value::Null)
+}
+
+/// A SQLite cell as JSON, by the value's storage class (a column's declared type is
+/// only advisory in SQLite): integers and reals as numbers, text as strings, blobs as
+/// base64.
+fn sqlite_value(row: &sqlx::sqlite::SqliteRow, i: usize) -> Value {
+    let kind = match row.try_get_raw(i) {
+        Ok(raw) if !raw.is_null() => raw.type_info().name().to_string(),
+        _ => return Value::Null,
+    };
+    let v: Option<Value> = match kind.as_str() {
+        "INTEGER" => row.try_get::<i64, _>(i).ok().map(Value::from),
+        "REAL" => row.try_get::<f64, _>(i).ok().map(Value::from),
+        "BLOB" => row.try_get::<Vec<u8>, _>(i).ok().map(base64_value),
+        _ => None,
+    };
+    v.or_else(|| row.try_get::<String, _>(i).ok().map(Value::String))
+        .unwrap_or(Value::Null)
+}
+
+fn base64_value(bytes: Vec<u8>) -> Value {
+    Value::String(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cols() -> Vec<(String, String)> {
+        vec![
+            ("id".to_string(), "INT4".to_string()),
+            ("name".to_string(), "TEXT".to_string()),
+        ]
+    }
+
+    #[test]
+    fn json_result_set_is_capped_and_flagged() {
+        let cfg = DbOutputConfig {
+            max_rows: Some(2),
+            ..Default::default()
+        };
+        let mut rs = ResultSet::new(&cfg, cols());
+        assert!(rs.push(vec![json!(1), json!("a")]));
+        assert!(rs.push(vec![json!(2), Value::Null]));
+        assert!(!rs.push(vec![json!(3), json!("c")]));
+
+        let (body, media_type) = DbStatementOutput::Rows(rs).render();
+        assert_eq!(media_type, "application/json");
+        let v: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(v["columns"][0], json!({ "name": "id", "type": "INT4" }));
+        assert_eq!(v["rows"], json!([[1, "a"], [2, null]]));
+        assert_eq!(v["row_count"], json!(2));
+        assert_eq!(v["truncated"], json!(true));
+    }
+
+    #[test]
+    fn csv_quotes_and_caps_by_bytes() {
+        let cfg = DbOutputConfig {
+            format: DbOutputFormat::Csv,
+            max_bytes: Some(24),
+            ..Default::default()
+        };
+        let mut rs = ResultSet::new(&cfg, cols());
+        assert!(rs.push(vec![json!(1), json!("say \"hi\", ok")]));
+        assert!(!rs.push(vec![json!(2), json!("too long for the cap")]));
+
+        let (body, media_type) = DbStatementOutput::Rows(rs).render();
+        assert_eq!(media_type, "text/csv; header=present; truncated=true");
+        assert_eq!(body, "id,name\n1,\"say \"\"hi\"\", ok\"\n");
+    }
+
+    #[test]
+    fn statements_without_rows_report_rows_affected() {
+        let (body, media_type) = DbStatementOutput::RowsAffected(3).render();
+        assert_eq!(body, "{\"rows_affected\":3}");
+        assert_eq!(media_type, "application/json");
+    }
+}
@@ -0,0 +1,509 @@
+//! Named bind parameters for the pgsql/mysql/sqlite runners. A query refers to `:name`;
+//! the worker rewrites each to the driver's placeholder (`$n` for Postgres, `?` for MySQL,
+//! `?n` for SQLite) and binds the value through sqlx, so nothing from a run's parameters
+//! is ever spliced into SQL text. Values come from the run context (`run_id`, `job_id`,
+//! `scheduled_for`, `attempt`) and the run's ad-hoc params (run-now).
+//!
+//! A query that references none of those names runs exactly as written, so SQL's own uses
+//! of `:` (a Postgres array slice `arr[1:n]`, a MySQL label) need no escaping. Nothing
+//! inside `[...]` is rewritten for Postgres or SQLite either.
+//!
+//! Postgres infers a type for every placeholder at prepare time, and each value is
+//! converted to that type (a `'2026-01-31'` param bound against a `date` column arrives
+//! as a date). Where Postgres cannot infer one, cast in SQL: `:day::date`. MySQL reports
+//! no parameter types and converts on its side, so values are bound as-is; so does
+//! SQLite, whose columns take any value.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use arbiter_core::RunParam;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use sqlx::mysql::MySqlArguments;
+use sqlx::postgres::{PgArguments, PgTypeInfo};
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{Arguments, Either, TypeInfo};
+use uuid::Uuid;
+
+/// How a rewritten query spells its placeholders.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Placeholders {
+    /// `$1`, `$2`, ... one per distinct name, reused on repeats (Postgres).
+    Numbered,
+    /// `?` per occurrence, bound in order (MySQL).
+    Positional,
+    /// `?1`, `?2`, ... one per distinct name, reused on repeats (SQLite). The numbers hold
+    /// across the statements of a multi-statement script.
+    Indexed,
+}
+
+/// A query with its `:name` references replaced, and the name bound at each placeholder.
+#[derive(Debug, PartialEq)]
+pub(crate) struct NamedQuery {
+    pub sql: String,
+    pub names: Vec<String>,
+}
+
+/// What a run's query can bind by name.
+pub(crate) struct BindContext<'a> {
+    pub run_id: Uuid,
+    pub job_id: Uuid,
+    pub scheduled_for: DateTime<Utc>,
+    pub attempt: u32,
+    pub params: &'a HashMap<String, RunParam>,
+}
+
+/// A value bound to a placeholder.
+#[derive(Debug, Clone, PartialEq)]
+enum BindValue {
+    Uuid(Uuid),
+    Timestamp(DateTime<Utc>),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+}
+
+impl BindContext<'_> {
+    fn value(&self, name: &str) -> Option<BindValue> {
+        Some(match name {
+            "run_id" => BindValue::Uuid(self.run_id),
+            "job_id" => BindValue::Uuid(self.job_id),
+            "scheduled_for" => BindValue::Timestamp(self.scheduled_for),
+            "attempt" => BindValue::Int(self.attempt as i64),
+            _ => match self.params.get(name)? {
+                RunParam::Bool(b) => BindValue::Bool(*b),
+                RunParam::Int(i) => BindValue::Int(*i),
+                RunParam::Float(f) => BindValue::Float(*f),
+                RunParam::Text(s) => BindValue::Text(s.clone()),
+            },
+        })
+    }
+
+    /// The value for each placeholder, or an error naming the first unknown parameter.
+    fn values(&self, names: &[String]) -> Result<Vec<BindValue>, sqlx::Error> {
+        names
+            .iter()
+            .map(|name| {
+                self.value(name).ok_or_else(|| {
+                    sqlx::Error::InvalidArgument(format!(
+                        "query references :{name}, which is neither a run context value \
+                         ({}) nor a parameter of this run",
+                        RunParam::CONTEXT_NAMES.join(", ")
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+impl BindValue {
+    fn text(&self) -> String {
+        match self {
+            BindValue::Uuid(u) => u.to_string(),
+            BindValue::Timestamp(t) => t.to_rfc3339(),
+            BindValue::Int(i) => i.to_string(),
+            BindValue::Float(f) => f.to_string(),
+            BindValue::Bool(b) => b.to_string(),
+            BindValue::Text(s) => s.clone(),
+        }
+    }
+}
+
+/// The query to run and the name bound at each placeholder: [`rewrite`]'s, when the query
+/// references at least one name `bind` can supply, else the query unchanged with nothing
+/// to bind. Once one name is bound, every `:name` is a parameter and an unknown one fails
+/// the run.
+pub(crate) fn prepare(query: &str, style: Placeholders, bind: &BindContext<'_>) -> NamedQuery {
+    let named = rewrite(query, style);
+    if named.names.iter().any(|name| bind.value(name).is_some()) {
+        named
+    } else {
+        NamedQuery {
+            sql: query.to_string(),
+            names: Vec::new(),
+        }
+    }
+}
+
+/// Replace `:name` references outside string literals, quoted identifiers, `[...]`
+/// (Postgres subscripts, SQLite identifiers), comments and (Postgres) dollar-quoted bodies.
+/// `::` casts and `:=` are left alone, as is a `:` not followed by an identifier.
+fn rewrite(query: &str, style: Placeholders) -> NamedQuery {
+    let bytes = query.as_bytes();
+    let mut sql = String::with_capacity(query.len());
+    let mut names: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        // Each arm yields the end of a span copied verbatim; a `:name` writes its
+        // placeholder instead.
+        let end = match rest[0] {
+            b'\'' => {
+                // MySQL strings and Postgres E'' strings honour backslash escapes.
+                let backslash = style == Placeholders::Positional
+                    || (style == Placeholders::Numbered
+                        && i > 0
+                        && matches!(bytes[i - 1], b'E' | b'e'));
+                quoted_end(bytes, i, b'\'', backslash)
+            }
+            b'"' => quoted_end(bytes, i, b'"', false),
+            b'`' if style != Placeholders::Numbered => quoted_end(bytes, i, b'`', false),
+            b'[' if style != Placeholders::Positional => {
+                find(bytes, i + 1, b"]").map_or(bytes.len(), |p| p + 1)
+            }
+            b'-' if rest.starts_with(b"--") => line_end(bytes, i),
+            b'#' if style == Placeholders::Positional => line_end(bytes, i),
+            b'/' if rest.starts_with(b"/*") => {
+                find(bytes, i + 2, b"*/").map_or(bytes.len(), |p| p + 2)
+            }
+            b'$' if style == Placeholders::Numbered => dollar_quote_end(bytes, i).unwrap_or(i + 1),
+            b':' if rest.starts_with(b"::") => i + 2,
+            b':' if rest.len() > 1 && is_ident_start(rest[1]) => {
+                let end = (i + 1..bytes.len())
+                    .find(|&j| !is_ident_char(bytes[j]))
+                    .unwrap_or(bytes.len());
+                let name = &query[i + 1..end];
+                match style {
+                    Placeholders::Numbered | Placeholders::Indexed => {
+                        let n = match names.iter().position(|x| x == name) {
+                            Some(p) => p + 1,
+                            None => {
+                                names.push(name.to_string());
+                                names.len()
+                            }
+                        };
+                        sql.push(if style == Placeholders::Numbered { '$' } else { '?' });
+                        sql.push_str(&n.to_string());
+                    }
+                    Placeholders::Positional => {
+                        names.push(name.to_string());
+                        sql.push('?');
+                    }
+                }
+                i = end;
+                continue;
+            }
+            // One whole UTF-8 character.
+            _ => i + query[i..].chars().next().map_or(1, char::len_utf8),
+        };
+        sql.push_str(&query[i..end]);
+        i = end;
+    }
+    NamedQuery { sql, names }
+}
+
+fn is_ident_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_'
+}
+
+fn is_ident_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes
+        .get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| p + from)
+}
+
+fn line_end(bytes: &[u8], from: usize) -> usize {
+    find(bytes, from, b"\n").map_or(bytes.len(), |p| p + 1)
+}
+
+/// End (exclusive) of a quoted run starting at `start`. A doubled quote is an escaped
+/// quote; so is a backslash-escaped one when `backslash` is set. Unterminated runs to EOF.
+fn quoted_end(bytes: &[u8], start: usize, quote: u8, backslash: bool) -> usize {
+    let mut j = start + 1;
+    while j < bytes.len() {
+        match bytes[j] {
+            b'\\' if backslash => j += 2,
+            b if b == quote => {
+                if bytes.get(j + 1) == Some(&quote) {
+                    j += 2;
+                } else {
+                    return j + 1;
+                }
+            }
+            _ => j += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// End (exclusive) of a `$tag$ ... $tag$` body starting at `start`, or `None` if the
+/// `$` does not open one (e.g. a `$1` placeholder).
+fn dollar_quote_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let tag_len = bytes[start + 1..].iter().position(|&b| !is_ident_char(b))?;
+    let close = start + 1 + tag_len;
+    if bytes.get(close) != Some(&b'$') || bytes.get(start + 1).is_some_and(u8::is_ascii_digit) {
+        return None;
+    }
+    let tag = &bytes[start..=close];
+    Some(find(bytes, close + 1, tag).map_or(bytes.len(), |p| p + tag.len()))
+}
+
+/// Postgres arguments for a prepared statement, each value converted to the type the
+/// server inferred for its placeholder.
+pub(crate) fn pg_arguments(
+    names: &[String],
+    types: Option<Either<&[PgTypeInfo], usize>>,
+    ctx: &BindContext<'_>,
+) -> Result<PgArguments, sqlx::Error> {
+    let values = ctx.values(names)?;
+    let types: &[PgTypeInfo] = match types {
+        Some(Either::Left(types)) => types,
+        _ => &[],
+    };
+    let mut args = PgArguments::default();
+    for (i, (name, value)) in names.iter().zip(values).enumerate() {
+        let ty = types.get(i).map_or("TEXT", |t| t.name());
+        add_pg(&mut args, name, ty, value)?;
+    }
+    Ok(args)
+}
+
+fn add_pg(
+    args: &mut PgArguments,
+    name: &str,
+    ty: &str,
+    value: BindValue,
+) -> Result<(), sqlx::Error> {
+    let mismatch = |value: &BindValue| {
+        sqlx::Error::InvalidArgument(format!(
+            ":{name} = {:?} cannot be bound as {ty}; pass a matching value or cast in SQL \
+             (e.g. :{name}::text)",
+            value.text()
+        ))
+    };
+    let int = |value: &BindValue| match value {
+        BindValue::Int(i) => Some(*i),
+        BindValue::Text(s) => s.trim().parse().ok(),
+        _ => None,
+    };
+    let float = |value: &BindValue| match value {
+        BindValue::Int(i) => Some(*i as f64),
+        BindValue::Float(f) => Some(*f),
+        BindValue::Text(s) => s.trim().parse().ok(),
+        _ => None,
+    };
+    let added = match ty {
+        "TEXT" | "VARCHAR" | "BPCHAR" | "NAME" | "UNKNOWN" => args.add(value.text()),
+        "INT2" => {
+            let v = int(&value).and_then(|i| i16::try_from(i).ok());
+            args.add(v.ok_or_else(|| mismatch(&value))?)
+        }
+        "INT4" => {
+            let v = int(&value).and_then(|i| i32::try_from(i).ok());
+            args.add(v.ok_or_else(|| mismatch(&value))?)
+        }
+        "INT8" => args.add(int(&value).ok_or_else(|| mismatch(&value))?),
+        "FLOAT4" => args.add(float(&value).ok_or_else(|| mismatch(&value))? as f32),
+        "FLOAT8" => args.add(float(&value).ok_or_else(|| mismatch(&value))?),
+        "NUMERIC" => {
+            let v = match &value {
+                BindValue::Int(_) | BindValue::Float(_) | BindValue::Text(_) => {
+                    sqlx::types::BigDecimal::from_str(value.text().trim()).ok()
+                }
+                _ => None,
+            };
+            args.add(v.ok_or_else(|| mismatch(&value))?)
+        }
+        "BOOL" => {
+            let v = match &value {
+                BindValue::Bool(b) => Some(*b),
+                BindValue::Text(s) => s.trim().parse().ok(),
+                _ => None,
+            };
+            args.add(v.ok_or_else(|| mismatch(&value))?)
+        }
+        "UUID" => {
+            let v = match &value {
+                BindValue::Uuid(u) => Some(*u),
+                BindValue::Text(s) => Uuid::parse_str(s.trim()).ok(),
+                _ => None,
+            };
+            args.add(v.ok_or_else(|| mismatch(&value))?)
+        }
+        "TIMESTAMPTZ" => {
+            let v = match &value {
+                BindValue::Timestamp(t) => Some(*t),
+                BindValue::Text(s) => DateTime::parse_from_rfc3339(s.trim())
+                    .ok()
+                    .map(|t| t.with_timezone(&Utc)),
+                _ => None,
+            };
+            args.add(v.ok_or_else(|| mismatch(&value))?)
+        }
+        "TIMESTAMP" => {
+            let v = match &value {
+                BindValue::Timestamp(t) => Some(t.naive_utc()),
+                BindValue::Text(s) => NaiveDateTime::from_str(s.trim()).ok(),
+                _ => None,
+            };
+            args.add(v.ok_or_else(|| mismatch(&value))?)
+        }
+        "DATE" => {
+            let v = match &value {
+                BindValue::Timestamp(t) => Some(t.date_naive()),
+                BindValue::Text(s) => NaiveDate::from_str(s.trim()).ok(),
+                _ => None,
+            };
+            args.add(v.ok_or_else(|| mismatch(&value))?)
+        }
+        _ => return Err(mismatch(&value)),
+    };
+    added.map_err(sqlx::Error::Encode)
+}
+
+/// MySQL arguments, one per `?`, bound with their natural types (UUIDs as text).
+pub(crate) fn mysql_arguments(
+    names: &[String],
+    ctx: &BindContext<'_>,
+) -> Result<MySqlArguments, sqlx::Error> {
+    let mut args = MySqlArguments::default();
+    for value in ctx.values(names)? {
+        let added = match value {
+            BindValue::Uuid(u) => args.add(u.to_string()),
+            BindValue::Timestamp(t) => args.add(t),
+            BindValue::Int(i) => args.add(i),
+            BindValue::Float(f) => args.add(f),
+            BindValue::Bool(b) => args.add(b),
+            BindValue::Text(s) => args.add(s),
+        };
+        added.map_err(sqlx::Error::Encode)?;
+    }
+    Ok(args)
+}
+
+/// SQLite arguments, one per distinct name in `?n` order. UUIDs and timestamps are
+/// bound as text (RFC 3339), booleans as 0/1.
+pub(crate) fn sqlite_arguments(
+    names: &[String],
+    ctx: &BindContext<'_>,
+) -> Result<SqliteArguments<'static>, sqlx::Error> {
+    let mut args = SqliteArguments::default();
+    for value in ctx.values(names)? {
+        let added = match value {
+            BindValue::Uuid(u) => args.add(u.to_string()),
+            BindValue::Timestamp(t) => args.add(t.to_rfc3339()),
+            BindValue::Int(i) => args.add(i),
+            BindValue::Float(f) => args.add(f),
+            BindValue::Bool(b) => args.add(b),
+            BindValue::Text(s) => args.add(s),
+        };
+        added.map_err(sqlx::Error::Encode)?;
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn numbered_placeholders_reuse_repeated_names() {
+        let q = rewrite(
+            "SELECT * FROM t WHERE d >= :day AND d < :day::date + 1 AND n = :attempt",
+            Placeholders::Numbered,
+        );
+        assert_eq!(
+            q.sql,
+            "SELECT * FROM t WHERE d >= $1 AND d < $1::date + 1 AND n = $2"
+        );
+        assert_eq!(q.names, names(&["day", "attempt"]));
+    }
+
+    #[test]
+    fn positional_placeholders_repeat_per_occurrence() {
+        let q = rewrite(
+            "UPDATE t SET a = :x WHERE b = :y OR c = :x",
+            Placeholders::Positional,
+        );
+        assert_eq!(q.sql, "UPDATE t SET a = ? WHERE b = ? OR c = ?");
+        assert_eq!(q.names, names(&["x", "y", "x"]));
+    }
+
+    #[test]
+    fn indexed_placeholders_span_statements() {
+        let q = rewrite(
+            "INSERT INTO log(day) VALUES (:day); DELETE FROM t WHERE d < :day AND [a:no] = `b:no`",
+            Placeholders::Indexed,
+        );
+        assert_eq!(
+            q.sql,
+            "INSERT INTO log(day) VALUES (?1); DELETE FROM t WHERE d < ?1 AND [a:no] = `b:no`"
+        );
+        assert_eq!(q.names, names(&["day"]));
+    }
+
+    #[test]
+    fn literals_comments_and_casts_are_untouched() {
+        let pg = "SELECT ':no', \"col:no\", $body$ :no $body$, x::int, E'it\\'s :no' -- :no\n\
+                  /* :no */ FROM t WHERE y = :yes";
+        let q = rewrite(pg, Placeholders::Numbered);
+        assert_eq!(q.names, names(&["yes"]));
+        assert_eq!(q.sql, pg.replace(":yes", "$1"));
+
+        let my = "SELECT 'it\\'s :no', `a:no` # :no\nFROM t WHERE @v := :yes";
+        let q = rewrite(my, Placeholders::Positional);
+        assert_eq!(q.names, names(&["yes"]));
+        assert_eq!(q.sql, my.replace(":yes", "?"));
+    }
+
+    #[test]
+    fn pg_subscripts_are_untouched() {
+        let pg = "SELECT arr[1:n], arr[:i] FROM t WHERE run = :run_id";
+        let q = rewrite(pg, Placeholders::Numbered);
+        assert_eq!(q.names, names(&["run_id"]));
+        assert_eq!(q.sql, pg.replace(":run_id", "$1"));
+    }
+
+    #[test]
+    fn queries_without_bindable_names_run_as_written() {
+        let params = HashMap::from([("day".to_string(), RunParam::Text("2026-01-31".into()))]);
+        let ctx = BindContext {
+            run_id: Uuid::nil(),
+            job_id: Uuid::nil(),
+            scheduled_for: Utc::now(),
+            attempt: 1,
+            params: &params,
+        };
+        // No parameters: a `:ident` is SQL's own, not a placeholder.
+        let loop_label = "SELECT n FROM t WHERE x = :label";
+        let q = prepare(loop_label, Placeholders::Positional, &ctx);
+        assert_eq!(q.sql, loop_label);
+        assert!(q.names.is_empty());
+        // One bindable name makes every `:name` a parameter.
+        let q = prepare("SELECT :day, :other", Placeholders::Numbered, &ctx);
+        assert_eq!(q.sql, "SELECT $1, $2");
+        assert_eq!(q.names, names(&["day", "other"]));
+    }
+
+    #[test]
+    fn unknown_names_are_reported() {
+        let params = HashMap::from([("day".to_string(), RunParam::Text("2026-01-31".into()))]);
+        let ctx = BindContext {
+            run_id: Uuid::nil(),
+            job_id: Uuid::nil(),
+            scheduled_for: Utc::now(),
+            attempt: 2,
+            params: &params,
+        };
+        let values = ctx
+            .values(&names(&["day", "attempt"]))
+            .expect("known names");
+        assert_eq!(
+            values,
+            vec![BindValue::Text("2026-01-31".into()), BindValue::Int(2)]
+        );
+        let err = ctx.values(&names(&["missing"])).expect_err("unknown name");
+        assert!(err.to_string().contains(":missing"), "{err}");
+    }
+}
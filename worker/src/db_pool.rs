@@ -0,0 +1,202 @@
+//! Warm connection pools for the pgsql/mysql runners, one per shared DB config, so runs
+//! stop paying connect + TLS + auth each time and a burst of runs queues for a bounded
+//! number of connections instead of exhausting the target's connection limit.
+//!
+//! A run must not see session state an earlier run left behind (settings, temp tables,
+//! prepared statements, an open transaction). A Postgres connection is reset with
+//! `DISCARD ALL` when it is released, and closed instead if that fails (say, inside a
+//! transaction). MySQL's reset (`COM_RESET_CONNECTION`) is not exposed by sqlx, so a MySQL
+//! connection is closed on release: its pool only bounds concurrent connections.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use arbiter_core::SharedDbConfig;
+use sha2::{Digest, Sha256};
+use sqlx::pool::PoolOptions;
+use sqlx::{Connection, Database, Executor, Pool};
+use uuid::Uuid;
+
+/// Idle pooled connections are closed after this long.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Where a DB run connects, with its password already resolved.
+pub(crate) struct DbTarget {
+    pub config_id: Uuid,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub database: String,
+    pub pool_size: Option<u32>,
+}
+
+/// What a config's pool was built for. A changed host/user/size or password no longer
+/// matches, and the pool is replaced. The password is keyed by what it resolved to, not by
+/// which secret (or version) it came from, so repointing or recreating the secret counts.
+#[derive(Debug, PartialEq, Eq)]
+struct PoolKey {
+    host: String,
+    port: u16,
+    username: String,
+    database: String,
+    pool_size: Option<u32>,
+    /// SHA-256 of the resolved password.
+    password: [u8; 32],
+}
+
+impl DbTarget {
+    fn pool_key(&self) -> PoolKey {
+        PoolKey {
+            host: self.host.clone(),
+            port: self.port,
+            username: self.username.clone(),
+            database: self.database.clone(),
+            pool_size: self.pool_size,
+            password: Sha256::digest(self.password.as_bytes()).into(),
+        }
+    }
+
+    pub(crate) fn pg_options(&self) -> sqlx::postgres::PgConnectOptions {
+        sqlx::postgres::PgConnectOptions::new()
+            .host(&self.host)
+            .port(self.port)
+            .username(&self.username)
+            .password(&self.password)
+            .database(&self.database)
+    }
+
+    pub(crate) fn mysql_options(&self) -> sqlx::mysql::MySqlConnectOptions {
+        sqlx::mysql::MySqlConnectOptions::new()
+            .host(&self.host)
+            .port(self.port)
+            .username(&self.username)
+            .password(&self.password)
+            .database(&self.database)
+    }
+
+    fn max_connections(&self) -> u32 {
+        self.pool_size
+            .unwrap_or(SharedDbConfig::DEFAULT_POOL_SIZE)
+            .clamp(1, SharedDbConfig::MAX_POOL_SIZE)
+    }
+}
+
+/// Pools by config id, each with the key of the target it was built for.
+type Pools<DB> = LazyLock<Mutex<HashMap<Uuid, (PoolKey, Pool<DB>)>>>;
+
+static PG_POOLS: Pools<sqlx::Postgres> = LazyLock::new(Default::default);
+static MYSQL_POOLS: Pools<sqlx::MySql> = LazyLock::new(Default::default);
+
+/// The warm Postgres pool for this target's config.
+pub(crate) fn pg_pool(t: &DbTarget) -> Pool<sqlx::Postgres> {
+    pool_for(&PG_POOLS, t, t.pg_options(), |opts| {
+        opts.after_release(|conn, _| {
+            Box::pin(async move {
+                // Drop sqlx's statement cache first: DISCARD ALL deallocates them server-side.
+                let reset = match conn.clear_cached_statements().await {
+                    Ok(()) => conn.execute("DISCARD ALL").await.map(|_| ()),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = &reset {
+                    tracing::debug!("closing a pgsql connection that did not reset: {e}");
+                }
+                Ok(reset.is_ok())
+            })
+        })
+    })
+}
+
+/// The MySQL pool for this target's config. Connections are not reused (see the module
+/// docs).
+pub(crate) fn mysql_pool(t: &DbTarget) -> Pool<sqlx::MySql> {
+    pool_for(&MYSQL_POOLS, t, t.mysql_options(), |opts| {
+        opts.after_release(|_, _| Box::pin(async { Ok(false) }))
+    })
+}
+
+/// Reuse the config's pool while its key matches; otherwise build a new (lazy) pool in
+/// its place and close the stale one once its in-flight runs release it.
+fn pool_for<DB: Database>(
+    pools: &Pools<DB>,
+    t: &DbTarget,
+    opts: <DB::Connection as Connection>::Options,
+    reset: impl FnOnce(PoolOptions<DB>) -> PoolOptions<DB>,
+) -> Pool<DB> {
+    let key = t.pool_key();
+    let mut pools = pools.lock().expect("db pools lock");
+    if let Some((built_for, pool)) = pools.get(&t.config_id)
+        && *built_for == key
+        && !pool.is_closed()
+    {
+        return pool.clone();
+    }
+    let pool = reset(
+        PoolOptions::<DB>::new()
+            .max_connections(t.max_connections())
+            .min_connections(0)
+            .idle_timeout(Some(IDLE_TIMEOUT)),
+    )
+    .connect_lazy_with(opts);
+    if let Some((_, stale)) = pools.insert(t.config_id, (key, pool.clone())) {
+        tracing::debug!(
+            "db config {} changed; replacing its connection pool",
+            t.config_id
+        );
+        tokio::spawn(async move { stale.close().await });
+    }
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(config_id: Uuid, password: &str) -> DbTarget {
+        DbTarget {
+            config_id,
+            host: "127.0.0.1".to_string(),
+            port: 1,
+            username: "u".to_string(),
+            password: password.to_string(),
+            database: "d".to_string(),
+            pool_size: Some(2),
+        }
+    }
+
+    #[tokio::test]
+    async fn pool_is_reused_until_the_target_changes() {
+        let id = Uuid::new_v4();
+        let first = pg_pool(&target(id, "old"));
+        let again = pg_pool(&target(id, "old"));
+        assert_eq!(first.options().get_max_connections(), 2);
+        tokio::task::yield_now().await;
+        assert!(
+            !first.is_closed() && !again.is_closed(),
+            "same target keeps its pool"
+        );
+
+        // A rotated (or repointed) password resolves to another value: fresh pool, stale
+        // one closed.
+        let rotated = pg_pool(&target(id, "new"));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(first.is_closed(), "stale pool is closed");
+        assert!(!rotated.is_closed());
+        assert_eq!(
+            PG_POOLS
+                .lock()
+                .unwrap()
+                .get(&id)
+                .map(|(key, _)| key.password == <[u8; 32]>::from(Sha256::digest(b"new"))),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn passwords_key_by_digest() {
+        let id = Uuid::new_v4();
+        assert_eq!(target(id, "a").pool_key(), target(id, "a").pool_key());
+        assert_ne!(target(id, "a").pool_key(), target(id, "b").pool_key());
+    }
+}
@@ -0,0 +1,125 @@
+//! Runtime events: what a Python/Node runtime reports about a run in progress besides its
+//! result (see `RUNNER_RESULT_PROTOCOL.md`). The `socket` transport sends them as messages
+//! on the socket; the `file` transport appends them as NDJSON to the `--events-file` the
+//! worker tails while the child runs. Either way they land in the run's [`RunLogSink`]:
+//! captured output and leveled records become log chunks, progress and heartbeats are
+//! written to the run (and keep dead-worker reclaim off it).
+
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use arbiter_core::{LogLevel, LogStream};
+use serde::Deserialize;
+use tokio::sync::oneshot;
+
+use crate::RunLogSink;
+
+/// How often the events file is checked for new lines while the child runs.
+const EVENTS_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// One event line. Unknown types (and malformed lines) are ignored.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum RuntimeEvent {
+    /// Output the task printed, routed through the runtime.
+    Log {
+        #[serde(default)]
+        stream: Option<String>,
+        #[serde(default)]
+        text: String,
+    },
+    /// A leveled record from `ctx.log`.
+    Record { level: String, message: String },
+    /// Progress in `0..=1` with an optional message.
+    Progress {
+        value: f64,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    /// Proof of life with nothing else to report.
+    Heartbeat,
+}
+
+/// Apply one event line to the run's logs. Returns `false` if it is not a known event.
+pub(crate) fn apply_event_line(line: &str, sink: &RunLogSink) -> bool {
+    let Ok(event) = serde_json::from_str::<RuntimeEvent>(line) else {
+        return false;
+    };
+    match event {
+        RuntimeEvent::Log { stream, text } => {
+            let stream = match stream.as_deref() {
+                Some("stderr") => LogStream::Stderr,
+                _ => LogStream::Stdout,
+            };
+            sink.write(stream, text.as_bytes());
+        }
+        RuntimeEvent::Record { level, message } => {
+            sink.record(level.parse().unwrap_or(LogLevel::Info), &message);
+        }
+        RuntimeEvent::Progress { value, message } => sink.progress(value, message),
+        RuntimeEvent::Heartbeat => sink.heartbeat(),
+    }
+    true
+}
+
+/// Follows a `file`-transport events file while the child runs, applying each complete line.
+pub(crate) struct EventsTail {
+    stop: oneshot::Sender<()>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl EventsTail {
+    pub(crate) fn spawn(path: PathBuf, sink: RunLogSink) -> Self {
+        let (stop, mut stopped) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let mut off = 0u64;
+            let mut partial = Vec::new();
+            loop {
+                // A dropped sender stops the tail too.
+                let last = tokio::select! {
+                    _ = &mut stopped => true,
+                    _ = tokio::time::sleep(EVENTS_POLL_INTERVAL) => false,
+                };
+                read_new_events(&path, &mut off, &mut partial, &sink).await;
+                if last {
+                    // A last line without its newline (the child was killed mid-write).
+                    if !partial.is_empty() {
+                        apply_event_line(&String::from_utf8_lossy(&partial), &sink);
+                    }
+                    break;
+                }
+            }
+        });
+        EventsTail { stop, task }
+    }
+
+    /// Read what the child wrote after the last poll, then stop. Call once it has exited.
+    pub(crate) async fn finish(self) {
+        let _ = self.stop.send(());
+        let _ = self.task.await;
+    }
+}
+
+async fn read_new_events(path: &Path, off: &mut u64, partial: &mut Vec<u8>, sink: &RunLogSink) {
+    use tokio::io::{AsyncReadExt as _, AsyncSeekExt as _};
+    let Ok(mut file) = tokio::fs::File::open(path).await else {
+        return;
+    };
+    if file.seek(SeekFrom::Start(*off)).await.is_err() {
+        return;
+    }
+    let mut buf = Vec::new();
+    let Ok(n) = file.read_to_end(&mut buf).await else {
+        return;
+    };
+    *off += n as u64;
+    partial.extend_from_slice(&buf);
+    while let Some(nl) = partial.iter().position(|&b| b == b'\n') {
+        let line: Vec<u8> = partial.drain(..=nl).collect();
+        let line = String::from_utf8_lossy(&line[..nl]);
+        if !line.trim().is_empty() && !apply_event_line(&line, sink) {
+            tracing::debug!("ignoring malformed runtime event: {line}");
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! The worker's housekeeping, run at startup (clearing what a crashed process left behind)
+//! and then periodically. Besides the workspaces past retention ([`workspace::sweep`]), it
+//! removes the scratch a run keeps outside its workspace once no run this worker owns
+//! holds it: the runtime task/result/events files and the artifacts, wasm result and
+//! secrets dirs in the system temp dir, and git checkouts under `<data_dir>/code/checkouts/`.
+//! Scratch is named after its run (`arbiter-result-<run_id>-…`, `<run_id>-…`), so ownership
+//! is read off the name; a run still in flight on another worker sharing the host or the
+//! data dir is left alone. Runtime copies an older build wrote to the runtime dir go as well.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime};
+
+use arbiter_core::{ArbiterError, JobRunState, Result, Store, WorkerDiskUsage};
+use uuid::Uuid;
+
+use crate::workspace::{self, dir_size};
+
+/// Kinds of per-run scratch created in the system temp dir, named
+/// `arbiter-<kind>-<run_id>-<random>`.
+const SCRATCH_KINDS: [&str; 6] = ["task", "result", "events", "artifacts", "wasm", "secrets"];
+
+/// Scratch named before it carried its run (`arbiter-result-<random>.json`) is removed once
+/// it is this old.
+const UNATTRIBUTED_GRACE: Duration = Duration::from_secs(86_400);
+
+/// A runtime copy being written (`.tmp-*` in the runtime dir) is abandoned after this long.
+const RUNTIME_TMP_GRACE: Duration = Duration::from_secs(3_600);
+
+/// Runs in flight on this worker, with how many contexts hold each. Their scratch is never
+/// swept.
+static OWNED: LazyLock<Mutex<HashMap<Uuid, usize>>> = LazyLock::new(Mutex::default);
+
+/// A run's claim on its scratch, held by its context from before the first file is created
+/// until the last one is gone.
+pub(crate) struct RunHold(Uuid);
+
+impl RunHold {
+    pub(crate) fn new(run_id: Uuid) -> Self {
+        *OWNED
+            .lock()
+            .expect("owned runs lock")
+            .entry(run_id)
+            .or_default() += 1;
+        RunHold(run_id)
+    }
+}
+
+impl Drop for RunHold {
+    fn drop(&mut self) {
+        let mut owned = OWNED.lock().expect("owned runs lock");
+        if let Some(n) = owned.get_mut(&self.0) {
+            *n -= 1;
+            if *n == 0 {
+                owned.remove(&self.0);
+            }
+        }
+    }
+}
+
+/// The prefix of a run's scratch of `kind` in the system temp dir.
+pub(crate) fn scratch_prefix(kind: &str, run_id: Uuid) -> String {
+    debug_assert!(SCRATCH_KINDS.contains(&kind));
+    format!("arbiter-{kind}-{run_id}-")
+}
+
+/// A file or directory that belongs to a run (or did, when `run` is `None` and it is past
+/// [`UNATTRIBUTED_GRACE`]).
+struct Scratch {
+    path: PathBuf,
+    run: Option<Uuid>,
+}
+
+/// What a sweep found: the scratch it may remove and the run workspaces it may expire, each
+/// with the run it is named after.
+#[derive(Default)]
+struct Found {
+    scratch: Vec<Scratch>,
+    run_workspaces: Vec<(Uuid, u32)>,
+}
+
+/// Runs found still in flight on another worker, with their current attempt (`None` when
+/// the store could not say, which spares every attempt).
+type Elsewhere = HashMap<Uuid, Option<u32>>;
+
+/// Sweep the worker's data dir and scratch: expire workspaces past retention
+/// (`keep_run_secs` after an attempt, `job_idle_secs` after a job's last run), remove the
+/// scratch of runs this worker no longer owns and outdated runtime copies, and measure what
+/// is left. The returned usage also counts what was removed.
+pub async fn sweep_worker_data(
+    store: &(dyn Store + Send + Sync),
+    worker_id: Uuid,
+    data_dir: &Path,
+    keep_run_secs: u64,
+    job_idle_secs: u64,
+) -> Result<WorkerDiskUsage> {
+    let join = |e: tokio::task::JoinError| ArbiterError::ExecutionError(e.to_string());
+    let dir = data_dir.to_path_buf();
+    let found = tokio::task::spawn_blocking(move || find(&dir))
+        .await
+        .map_err(join)?;
+
+    // Whatever is named after a run no context here holds may still be another worker's.
+    let mut elsewhere = Elsewhere::new();
+    let runs = found
+        .scratch
+        .iter()
+        .filter_map(|s| s.run)
+        .chain(found.run_workspaces.iter().map(|&(run, _)| run));
+    for run_id in runs {
+        if elsewhere.contains_key(&run_id) || owned(run_id) {
+            continue;
+        }
+        match store.get_run(run_id, None).await {
+            Ok(Some(run))
+                if matches!(run.state, JobRunState::Running)
+                    && run.worker_id != Some(worker_id) =>
+            {
+                elsewhere.insert(run_id, Some(run.attempt));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("sweep: run {run_id} not looked up, keeping its files: {e}");
+                elsewhere.insert(run_id, None);
+            }
+        }
+    }
+
+    let dir = data_dir.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let (scratch, scratch_bytes) = remove_scratch(found.scratch, &elsewhere);
+        let (runtimes, runtime_bytes) = remove_outdated_runtimes(&crate::runtime_dir());
+        let spare = |path: &Path| {
+            run_and_attempt(path).is_some_and(|(run, attempt)| {
+                elsewhere
+                    .get(&run)
+                    .is_some_and(|current| current.is_none_or(|a| a == attempt))
+            })
+        };
+        let mut usage = workspace::sweep(&dir, keep_run_secs, job_idle_secs, &spare);
+        let workspaces = usage.swept_entries;
+        usage.swept_entries += scratch + runtimes;
+        usage.swept_bytes += scratch_bytes + runtime_bytes;
+        if usage.swept_entries > 0 {
+            tracing::info!(
+                "sweep removed {} entries ({} bytes): {scratch} run scratch, {workspaces} \
+                 workspaces, {runtimes} runtime copies",
+                usage.swept_entries,
+                usage.swept_bytes,
+            );
+        }
+        usage
+    })
+    .await
+    .map_err(join)
+}
+
+fn owned(run_id: Uuid) -> bool {
+    OWNED.lock().expect("owned runs lock").contains_key(&run_id)
+}
+
+/// List the scratch in the system temp dir and the data dir, and the run workspaces.
+fn find(data_dir: &Path) -> Found {
+    let mut found = Found::default();
+    let now = SystemTime::now();
+    for entry in read_dir(&std::env::temp_dir()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(rest) = SCRATCH_KINDS
+            .iter()
+            .find_map(|kind| name.strip_prefix(&format!("arbiter-{kind}-")))
+        else {
+            continue;
+        };
+        let run = leading_uuid(rest);
+        if run.is_some() || age(&entry.path(), now) >= UNATTRIBUTED_GRACE {
+            found.scratch.push(Scratch {
+                path: entry.path(),
+                run,
+            });
+        }
+    }
+    for entry in read_dir(&data_dir.join("code").join("checkouts")) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if let Some(run) = leading_uuid(&name) {
+            found.scratch.push(Scratch {
+                path: entry.path(),
+                run: Some(run),
+            });
+        }
+    }
+    for entry in read_dir(&data_dir.join("workspaces").join("runs")) {
+        found.run_workspaces.extend(run_and_attempt(&entry.path()));
+    }
+    found
+}
+
+/// Remove the scratch no run here owns and no other worker is running, holding the owned
+/// runs across each removal so a run cannot start on it meanwhile. Returns how many entries
+/// went and their bytes.
+fn remove_scratch(scratch: Vec<Scratch>, elsewhere: &Elsewhere) -> (u32, u64) {
+    let (mut count, mut bytes) = (0, 0);
+    for Scratch { path, run } in scratch {
+        let owned = OWNED.lock().expect("owned runs lock");
+        if run.is_some_and(|run| owned.contains_key(&run) || elsewhere.contains_key(&run)) {
+            continue;
+        }
+        let size = dir_size(&path);
+        if remove(&path) {
+            tracing::info!("removed orphaned run scratch {}", path.display());
+            count += 1;
+            bytes += size;
+        }
+    }
+    (count, bytes)
+}
+
+/// Remove the runtime copies in `dir` this build does not use (content-addressed, so any
+/// other `arbiter_runtime_*` is an older build's) and abandoned partial writes.
+fn remove_outdated_runtimes(dir: &Path) -> (u32, u64) {
+    let current = crate::runtime_file_names();
+    let now = SystemTime::now();
+    let (mut count, mut bytes) = (0, 0);
+    for entry in read_dir(dir) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let outdated = name.starts_with("arbiter_runtime_") && !current.contains(&name)
+            || name.starts_with(".tmp-") && age(&path, now) >= RUNTIME_TMP_GRACE;
+        let size = dir_size(&path);
+        if outdated && remove(&path) {
+            tracing::info!("removed outdated runtime copy {}", path.display());
+            count += 1;
+            bytes += size;
+        }
+    }
+    (count, bytes)
+}
+
+fn read_dir(dir: &Path) -> impl Iterator<Item = std::fs::DirEntry> {
+    std::fs::read_dir(dir).into_iter().flatten().flatten()
+}
+
+/// Remove a file or a directory tree, logging a failure.
+fn remove(path: &Path) -> bool {
+    let removed = match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => std::fs::remove_dir_all(path),
+        Ok(_) => std::fs::remove_file(path),
+        Err(e) => Err(e),
+    };
+    match removed {
+        Ok(()) => true,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => {
+            tracing::warn!("sweep: {} not removed: {e}", path.display());
+            false
+        }
+    }
+}
+
+/// How long ago `path` was last modified (unknown = forever).
+fn age(path: &Path, now: SystemTime) -> Duration {
+    std::fs::symlink_metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| now.duration_since(t).ok())
+        .unwrap_or(Duration::MAX)
+}
+
+/// The run id a name starts with, followed by `-`.
+fn leading_uuid(name: &str) -> Option<Uuid> {
+    let (id, rest) = name.split_at_checked(36)?;
+    rest.starts_with('-').then(|| id.parse().ok()).flatten()
+}
+
+/// The run and attempt of a run workspace (`<run_id>-<attempt>`).
+fn run_and_attempt(path: &Path) -> Option<(Uuid, u32)> {
+    let name = path.file_name()?.to_str()?;
+    let run = leading_uuid(name)?;
+    Some((run, name[37..].parse().ok()?))
+}
@@ -0,0 +1,482 @@
+//! Out-of-process runner plugins: executables in the worker's plugin dir that speak
+//! JSON-RPC 2.0 over stdio, one message per line (the wire contract is in
+//! `PLUGIN_PROTOCOL.md`). The worker probes each at startup with a `handshake`, registers it
+//! as `plugin:<name>`, and advertises what it reported on its worker record.
+//!
+//! Every run spawns a fresh plugin process: `handshake`, then one `run` request carrying the
+//! run's identity, the job's config and env, and the secrets the config references. `log`
+//! notifications stream into the run's logs; the response is a result document
+//! (`{status, output, error}`, the same shape the language runtimes write), so it maps onto
+//! the run's outcome exactly like theirs. The plugin's stderr is captured as the stderr log.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use arbiter_core::{ArbiterError, LogStream, Result, RunOutcome, RunnerPluginInfo};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+use crate::runner::{RunContext, Runner, RunnerPayload};
+use crate::{
+    CommandRunOutput, DocumentRun, SECRET_PREFIX, opt, result_document_outcome, spawn_pipe_reader,
+};
+
+/// The protocol revision this worker speaks; a plugin reporting another one is rejected.
+pub const PLUGIN_PROTOCOL_VERSION: u32 = 1;
+
+/// What the worker tells plugins it provides: `log` notifications are accepted, and the
+/// `run` request carries resolved secrets.
+const WORKER_CAPABILITIES: &[&str] = &["logs", "secrets"];
+
+/// Bound on a plugin answering `handshake`, at discovery and at the start of every run.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a plugin may take to exit once it has answered and its stdin is closed.
+const EXIT_GRACE: Duration = Duration::from_secs(5);
+
+/// A probed plugin executable, ready to be registered.
+pub struct PluginRunner {
+    path: PathBuf,
+    info: RunnerPluginInfo,
+}
+
+impl PluginRunner {
+    /// Spawn `path` and handshake with it. Fails if it does not answer in time, speaks
+    /// another protocol revision, or reports an invalid name.
+    pub async fn probe(path: &Path) -> Result<Self> {
+        let mut proc = PluginProcess::spawn(path, Stdio::null())?;
+        let info = proc.handshake().await;
+        proc.close().await;
+        Ok(PluginRunner {
+            path: path.to_path_buf(),
+            info: info?,
+        })
+    }
+
+    pub fn info(&self) -> &RunnerPluginInfo {
+        &self.info
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Probe every executable file in `dir` (in name order). Plugins that fail their handshake
+/// are logged and skipped; when two report the same name, the first one wins.
+pub async fn discover_plugins(dir: &Path) -> Vec<PluginRunner> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::warn!("plugin dir {}: {e}", dir.display());
+            return Vec::new();
+        }
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| is_executable(p))
+        .collect();
+    paths.sort();
+
+    let mut seen = HashSet::new();
+    let mut plugins = Vec::new();
+    for path in paths {
+        match PluginRunner::probe(&path).await {
+            Ok(plugin) if !seen.insert(plugin.info.name.clone()) => tracing::warn!(
+                "plugin {} reports name '{}', already provided by another plugin; skipped",
+                path.display(),
+                plugin.info.name
+            ),
+            Ok(plugin) => {
+                tracing::info!(
+                    "runner plugin '{}' {} from {}",
+                    plugin.info.name,
+                    plugin.info.version,
+                    path.display()
+                );
+                plugins.push(plugin);
+            }
+            Err(e) => tracing::warn!("plugin {} skipped: {e}", path.display()),
+        }
+    }
+    plugins
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt as _;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// A plugin run's resolved inputs.
+pub struct PluginJob {
+    config: Value,
+    env: HashMap<String, String>,
+    secrets: BTreeMap<String, String>,
+    timeout_sec: Option<u32>,
+}
+
+#[async_trait]
+impl Runner for PluginRunner {
+    type Input = RunnerPayload;
+    type Prepared = PluginJob;
+    type Output = DocumentRun;
+
+    async fn prepare(&self, ctx: &RunContext, payload: RunnerPayload) -> Result<Self::Prepared> {
+        let mut names = Vec::new();
+        collect_secret_refs(&payload.config, &mut names);
+        let mut secrets = BTreeMap::new();
+        for name in names {
+            let value = ctx.resolve(&format!("{SECRET_PREFIX}{name}")).await?;
+            secrets.insert(name, value);
+        }
+        Ok(PluginJob {
+            config: payload.config,
+            env: ctx.resolve_env(&payload.env).await?,
+            secrets,
+            timeout_sec: payload.timeout_sec,
+        })
+    }
+
+    async fn execute(&self, ctx: &RunContext, job: Self::Prepared) -> Result<Self::Output> {
+        let mut proc = PluginProcess::spawn(&self.path, Stdio::piped())?;
+        let logs = ctx.logs();
+        let err_reader =
+            spawn_pipe_reader(proc.child.stderr.take(), logs.buffer(LogStream::Stderr));
+
+        let run = ctx.run();
+        let params = json!({
+            "run": {
+                "runId": run.id,
+                "jobId": run.job_id,
+                "attempt": run.attempt,
+                "scheduledFor": run.scheduled_for,
+                "params": run.params,
+            },
+            "config": job.config,
+            "env": job.env,
+            "secrets": job.secrets,
+        });
+        let exchange = async {
+            let info = proc.handshake().await?;
+            if info.name != self.info.name {
+                return Err(ArbiterError::ExecutionError(format!(
+                    "plugin at {} now reports name '{}', expected '{}'",
+                    self.path.display(),
+                    info.name,
+                    self.info.name
+                )));
+            }
+            proc.call("run", params, |stream, text| {
+                logs.write(stream, text.as_bytes())
+            })
+            .await
+        };
+        let mut timed_out = false;
+        let (doc, failure) = match job.timeout_sec {
+            Some(secs) if secs > 0 => {
+                match tokio::time::timeout(Duration::from_secs(secs as u64), exchange).await {
+                    Ok(res) => settle(res),
+                    Err(_) => {
+                        timed_out = true;
+                        (None, Some(format!("run exceeded timeout of {secs}s")))
+                    }
+                }
+            }
+            _ => settle(exchange.await),
+        };
+        let exit_code = if timed_out {
+            proc.kill().await;
+            -1
+        } else {
+            proc.close().await
+        };
+
+        let _ = err_reader.await;
+        let (stdout, mut stderr) = logs.finish().await;
+        if let Some(msg) = failure {
+            stderr = if stderr.is_empty() {
+                msg
+            } else {
+                format!("{stderr}\n{msg}")
+            };
+        }
+        let raw = CommandRunOutput {
+            exit_code,
+            stdout: opt(&stdout),
+            stderr: opt(&stderr),
+        };
+        let doc = doc
+            .map(|d| serde_json::to_vec(&d).map_err(std::io::Error::other))
+            .unwrap_or_else(|| Err(std::io::Error::other("plugin returned no result")));
+        Ok(DocumentRun { raw, doc })
+    }
+
+    fn outcome(&self, ctx: &RunContext, output: Self::Output) -> RunOutcome {
+        result_document_outcome(output.doc, output.raw, ctx.retry_on())
+    }
+}
+
+/// Split the exchange's result into the result document (if the plugin answered) and an
+/// error to append to stderr (if it did not). A JSON-RPC error response is a failed run
+/// with a `PluginError`.
+fn settle(res: Result<std::result::Result<Value, RpcError>>) -> (Option<Value>, Option<String>) {
+    match res {
+        Ok(Ok(doc)) => (Some(doc), None),
+        Ok(Err(e)) => (
+            Some(json!({
+                "status": "failed",
+                "error": { "type": "PluginError", "message": e.message },
+            })),
+            None,
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+/// Every `secret:<name>` string anywhere in a plugin config, deduplicated.
+fn collect_secret_refs(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::String(s) => {
+            if let Some(name) = s.strip_prefix(SECRET_PREFIX)
+                && !out.iter().any(|n| n == name)
+            {
+                out.push(name.to_string());
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_secret_refs(v, out)),
+        Value::Object(map) => map.values().for_each(|v| collect_secret_refs(v, out)),
+        _ => {}
+    }
+}
+
+/// One line from the plugin: a response (`id` plus `result` or `error`) or a notification
+/// (`method`, no `id`).
+#[derive(Deserialize)]
+struct Incoming {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    params: Option<Value>,
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HandshakeResult {
+    protocol_version: u32,
+    name: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    capabilities: Vec<String>,
+    #[serde(default)]
+    config_schema: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct LogParams {
+    #[serde(default)]
+    stream: Option<String>,
+    #[serde(default)]
+    text: String,
+}
+
+/// A running plugin process and its JSON-RPC channel.
+struct PluginProcess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: Lines<BufReader<ChildStdout>>,
+    next_id: u64,
+}
+
+impl PluginProcess {
+    fn spawn(path: &Path, stderr: Stdio) -> Result<Self> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(stderr)
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                ArbiterError::ExecutionError(format!("spawn plugin {}: {e}", path.display()))
+            })?;
+        let stdin = child.stdin.take();
+        let stdout = child
+            .stdout
+            .take()
+            .map(|s| BufReader::new(s).lines())
+            .ok_or_else(|| ArbiterError::ExecutionError("plugin stdout not captured".into()))?;
+        Ok(PluginProcess {
+            child,
+            stdin,
+            stdout,
+            next_id: 1,
+        })
+    }
+
+    async fn handshake(&mut self) -> Result<RunnerPluginInfo> {
+        let params = json!({
+            "protocolVersion": PLUGIN_PROTOCOL_VERSION,
+            "workerVersion": env!("CARGO_PKG_VERSION"),
+            "capabilities": WORKER_CAPABILITIES,
+        });
+        let reply =
+            tokio::time::timeout(HANDSHAKE_TIMEOUT, self.call("handshake", params, |_, _| {}))
+                .await
+                .map_err(|_| ArbiterError::ExecutionError("plugin handshake timed out".into()))??
+                .map_err(|e| {
+                    ArbiterError::ExecutionError(format!(
+                        "plugin rejected handshake: {}",
+                        e.message
+                    ))
+                })?;
+        let hs: HandshakeResult = serde_json::from_value(reply).map_err(|e| {
+            ArbiterError::ExecutionError(format!("invalid plugin handshake reply: {e}"))
+        })?;
+        if hs.protocol_version != PLUGIN_PROTOCOL_VERSION {
+            return Err(ArbiterError::ExecutionError(format!(
+                "plugin speaks protocol {}, this worker speaks {PLUGIN_PROTOCOL_VERSION}",
+                hs.protocol_version
+            )));
+        }
+        if !RunnerPluginInfo::valid_name(&hs.name) {
+            return Err(ArbiterError::ExecutionError(format!(
+                "invalid plugin name '{}'",
+                hs.name
+            )));
+        }
+        Ok(RunnerPluginInfo {
+            name: hs.name,
+            version: hs.version,
+            protocol_version: hs.protocol_version,
+            capabilities: hs.capabilities,
+            config_schema: hs.config_schema,
+        })
+    }
+
+    /// Send a request and wait for its response, handing `log` notifications that arrive
+    /// meanwhile to `on_log`. Lines that are not JSON-RPC are kept as stdout log text, so a
+    /// stray print does not break the run. The outer error is transport (write failure,
+    /// plugin exited); the inner one is the plugin's JSON-RPC error.
+    async fn call(
+        &mut self,
+        method: &str,
+        params: Value,
+        mut on_log: impl FnMut(LogStream, &str),
+    ) -> Result<std::result::Result<Value, RpcError>> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut line =
+            json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string();
+        line.push('\n');
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| ArbiterError::ExecutionError("plugin stdin closed".into()))?;
+        let written = async {
+            stdin.write_all(line.as_bytes()).await?;
+            stdin.flush().await
+        };
+        written
+            .await
+            .map_err(|e| ArbiterError::ExecutionError(format!("write to plugin: {e}")))?;
+
+        loop {
+            let line = self
+                .stdout
+                .next_line()
+                .await
+                .map_err(|e| ArbiterError::ExecutionError(format!("read from plugin: {e}")))?
+                .ok_or_else(|| {
+                    ArbiterError::ExecutionError(format!(
+                        "plugin exited before answering '{method}'"
+                    ))
+                })?;
+            let Ok(msg) = serde_json::from_str::<Incoming>(&line) else {
+                on_log(LogStream::Stdout, &format!("{line}\n"));
+                continue;
+            };
+            match (msg.id, msg.method.as_deref()) {
+                (Some(got), None) if got == id => {
+                    return Ok(match msg.error {
+                        Some(e) => Err(e),
+                        None => Ok(msg.result.unwrap_or(Value::Null)),
+                    });
+                }
+                (None, Some("log")) => {
+                    if let Some(p) = msg
+                        .params
+                        .and_then(|p| serde_json::from_value::<LogParams>(p).ok())
+                    {
+                        let stream = match p.stream.as_deref() {
+                            Some("stderr") => LogStream::Stderr,
+                            _ => LogStream::Stdout,
+                        };
+                        on_log(stream, &p.text);
+                    }
+                }
+                // Unknown notifications and stale responses are ignored (forward compatible).
+                _ => {}
+            }
+        }
+    }
+
+    /// Close stdin (the plugin's cue to exit) and reap it, killing it after a grace period.
+    /// Returns the exit code (-1 if killed or signalled).
+    async fn close(&mut self) -> i32 {
+        self.stdin.take();
+        match tokio::time::timeout(EXIT_GRACE, self.child.wait()).await {
+            Ok(Ok(status)) => status.code().unwrap_or(-1),
+            Ok(Err(_)) => -1,
+            Err(_) => {
+                self.kill().await;
+                -1
+            }
+        }
+    }
+
+    async fn kill(&mut self) {
+        let _ = self.child.kill().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_refs_are_collected_from_nested_config() {
+        let config = json!({
+            "token": "secret:api-token",
+            "targets": [{ "password": "secret:db-pass" }, "plain"],
+            "again": "secret:api-token",
+            "count": 3,
+        });
+        let mut names = Vec::new();
+        collect_secret_refs(&config, &mut names);
+        names.sort();
+        assert_eq!(names, ["api-token", "db-pass"]);
+    }
+}
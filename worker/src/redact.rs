@@ -0,0 +1,294 @@
+//! Masking of the secret values a run resolved. A run's context wraps the worker's
+//! [`SecretResolver`] so every value it hands out is recorded in the run's [`Redactor`],
+//! along with the forms it commonly takes in output: base64 (standard and URL-safe, at any
+//! alignment inside a longer encoding, e.g. a Basic auth header), URL-encoding and JSON
+//! string escaping. Log chunks, structured records and the run's result and error are
+//! masked before they are written; a stream being flushed holds back a tail that may be
+//! the start of a secret, so a chunk boundary cannot split one past the redactor.
+
+use std::sync::{Arc, RwLock};
+
+use arbiter_core::{
+    Result, RunOutcome, SecretActor, SecretResolver, SecretStore, split_secret_pin,
+};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD};
+use uuid::Uuid;
+
+/// What a masked occurrence is replaced with.
+pub(crate) const REDACTED: &str = "***";
+
+/// Forms shorter than this are not masked: they would mangle unrelated output, and a
+/// secret that short is no secret.
+const MIN_PATTERN_LEN: usize = 4;
+
+/// The secret values (and their encodings) resolved for one run. Cheap to clone; clones
+/// share the same set.
+#[derive(Clone, Default)]
+pub(crate) struct Redactor(Arc<RwLock<Patterns>>);
+
+#[derive(Default)]
+struct Patterns {
+    /// Longest first, so the longest of overlapping forms is the one masked.
+    forms: Vec<Vec<u8>>,
+}
+
+impl Redactor {
+    /// Mask `secret` (and its encodings) from now on.
+    pub(crate) fn add(&self, secret: &str) {
+        let mut patterns = self.0.write().unwrap_or_else(|p| p.into_inner());
+        for form in encodings(secret.as_bytes()) {
+            if form.len() >= MIN_PATTERN_LEN && !patterns.forms.contains(&form) {
+                patterns.forms.push(form);
+            }
+        }
+        patterns.forms.sort_by_key(|f| std::cmp::Reverse(f.len()));
+    }
+
+    fn patterns(&self) -> std::sync::RwLockReadGuard<'_, Patterns> {
+        self.0.read().unwrap_or_else(|p| p.into_inner())
+    }
+
+    /// `text` with every known secret masked.
+    pub(crate) fn redact(&self, text: &str) -> String {
+        let patterns = self.patterns();
+        let bytes = text.as_bytes();
+        let matches = patterns.find(bytes);
+        if matches.is_empty() {
+            return text.to_string();
+        }
+        String::from_utf8_lossy(&mask(bytes, &matches)).into_owned()
+    }
+
+    /// Mask the text a run reports: its streams, result and error.
+    pub(crate) fn redact_outcome(&self, mut outcome: RunOutcome) -> RunOutcome {
+        if self.patterns().forms.is_empty() {
+            return outcome;
+        }
+        for field in [
+            &mut outcome.stdout,
+            &mut outcome.stderr,
+            &mut outcome.result,
+            &mut outcome.error,
+        ] {
+            if let Some(text) = field.as_mut() {
+                *text = self.redact(text);
+            }
+        }
+        outcome
+    }
+
+    /// Split the pending bytes of a stream into what can be written now, masked, and how
+    /// many bytes it consumed. Unless `last`, a tail that may be the start of a secret is
+    /// held back for the next flush, as is a secret it would cut through.
+    pub(crate) fn redact_stream(&self, pending: &[u8], last: bool) -> (Vec<u8>, usize) {
+        let patterns = self.patterns();
+        if patterns.forms.is_empty() {
+            return (pending.to_vec(), pending.len());
+        }
+        let matches = patterns.find(pending);
+        let mut cut = pending.len();
+        if !last {
+            cut -= patterns.partial_suffix(pending);
+            if let Some(&(start, _)) = matches.iter().find(|&&(s, e)| s < cut && cut < e) {
+                cut = start;
+            }
+        }
+        let within: Vec<_> = matches.into_iter().filter(|&(_, e)| e <= cut).collect();
+        (mask(&pending[..cut], &within), cut)
+    }
+}
+
+impl Patterns {
+    /// Non-overlapping occurrences, leftmost first and longest at a position.
+    fn find(&self, text: &[u8]) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        let mut at = 0;
+        while at < text.len() {
+            match self.forms.iter().find(|f| text[at..].starts_with(f)) {
+                Some(form) => {
+                    found.push((at, at + form.len()));
+                    at += form.len();
+                }
+                None => at += 1,
+            }
+        }
+        found
+    }
+
+    /// The length of the longest tail of `text` that is a proper prefix of some form.
+    fn partial_suffix(&self, text: &[u8]) -> usize {
+        let longest = self.forms.first().map_or(0, Vec::len);
+        (1..longest.min(text.len() + 1))
+            .rev()
+            .find(|&n| {
+                let tail = &text[text.len() - n..];
+                self.forms
+                    .iter()
+                    .any(|f| f.len() > n && f.starts_with(tail))
+            })
+            .unwrap_or(0)
+    }
+}
+
+fn mask(text: &[u8], matches: &[(usize, usize)]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut at = 0;
+    for &(start, end) in matches {
+        out.extend_from_slice(&text[at..start]);
+        out.extend_from_slice(REDACTED.as_bytes());
+        at = end;
+    }
+    out.extend_from_slice(&text[at..]);
+    out
+}
+
+/// A secret as it may appear in output.
+fn encodings(secret: &[u8]) -> Vec<Vec<u8>> {
+    let mut forms = vec![secret.to_vec()];
+    for engine in [STANDARD_NO_PAD, URL_SAFE_NO_PAD] {
+        // Inside a longer encoding the secret starts at any offset mod 3; the characters
+        // that encode only its own bits are the same wherever that is.
+        for shift in 0..3 {
+            let mut padded = vec![0u8; shift];
+            padded.extend_from_slice(secret);
+            let encoded = engine.encode(&padded).into_bytes();
+            let (first, last) = (
+                (shift * 8).div_ceil(6),
+                ((shift + secret.len()) * 8 / 6).min(encoded.len()),
+            );
+            if first < last {
+                forms.push(encoded[first..last].to_vec());
+            }
+        }
+    }
+    forms.push(percent_encode(secret, false));
+    forms.push(percent_encode(secret, true));
+    if let Ok(text) = std::str::from_utf8(secret)
+        && let Ok(json) = serde_json::to_string(text)
+    {
+        forms.push(json.as_bytes()[1..json.len() - 1].to_vec());
+    }
+    forms.dedup();
+    forms
+}
+
+/// Percent-encode everything but the unreserved characters (`form`: a space is `+`).
+fn percent_encode(bytes: &[u8], form: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    for &b in bytes {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => out.push(b),
+            b' ' if form => out.push(b'+'),
+            _ => out.extend_from_slice(format!("%{b:02X}").as_bytes()),
+        }
+    }
+    out
+}
+
+/// The worker's resolver as a run sees it: it resolves as the run (for the audit trail),
+/// every value it resolves is also added to the run's redactor, and the secret is marked as
+/// last resolved by the run.
+pub(crate) struct RecordingResolver {
+    pub(crate) inner: Arc<dyn SecretResolver + Send + Sync>,
+    pub(crate) redactor: Redactor,
+    pub(crate) store: Arc<dyn SecretStore + Send + Sync>,
+    pub(crate) run_id: Uuid,
+    pub(crate) job_id: Uuid,
+}
+
+#[async_trait]
+impl SecretResolver for RecordingResolver {
+    async fn resolve_secret(&self, tenant: Uuid, name: &str) -> Result<String> {
+        let actor = SecretActor::Run {
+            run_id: self.run_id,
+            job_id: self.job_id,
+        };
+        self.resolve_secret_as(tenant, name, actor).await
+    }
+
+    async fn resolve_secret_as(
+        &self,
+        tenant: Uuid,
+        name: &str,
+        actor: SecretActor,
+    ) -> Result<String> {
+        let value = self.inner.resolve_secret_as(tenant, name, actor).await?;
+        self.redactor.add(&value);
+        // Usage tracking is informational: a failed write does not fail the run.
+        let (secret, _) = split_secret_pin(name);
+        if let Err(e) = self.store.record_secret_use(tenant, secret, self.run_id).await {
+            tracing::warn!("run {}: cannot record use of secret {secret}: {e}", self.run_id);
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor(secret: &str) -> Redactor {
+        let r = Redactor::default();
+        r.add(secret);
+        r
+    }
+
+    #[test]
+    fn masks_a_secret_and_its_encodings() {
+        let r = redactor("p@ss word/1");
+        assert_eq!(r.redact("pw=p@ss word/1;"), "pw=***;");
+        assert_eq!(
+            r.redact("postgres://u:p%40ss%20word%2F1@db"),
+            "postgres://u:***@db"
+        );
+        assert_eq!(r.redact("q=p%40ss+word%2F1"), "q=***");
+
+        // Base64 of the value alone, and inside a longer encoding at each alignment.
+        for prefix in ["", "u:", "us:", "use:"] {
+            let header = STANDARD_NO_PAD.encode(format!("{prefix}p@ss word/1"));
+            let masked = r.redact(&header);
+            // What is left encodes the prefix and the last bits of the secret, no more.
+            assert!(masked.contains(REDACTED), "{prefix:?}: {masked}");
+            assert!(
+                masked.len() <= REDACTED.len() + prefix.len() * 4 / 3 + 4,
+                "{masked}"
+            );
+        }
+
+        let quoted = redactor("say \"hi\"");
+        assert_eq!(quoted.redact(r#"{"msg":"say \"hi\""}"#), r#"{"msg":"***"}"#);
+
+        let outcome = r.redact_outcome(RunOutcome {
+            error: Some("login failed for p@ss word/1".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(outcome.error.as_deref(), Some("login failed for ***"));
+
+        // Too short to mask without mangling everything else.
+        assert_eq!(redactor("ab").redact("abc"), "abc");
+    }
+
+    #[test]
+    fn a_stream_never_splits_a_secret() {
+        let r = redactor("hunter2!");
+        let mut sink = Vec::new();
+        let mut pending = Vec::new();
+        for piece in ["log: hun", "ter2", "! and hunter", "", "2!"] {
+            pending.extend_from_slice(piece.as_bytes());
+            let (out, used) = r.redact_stream(&pending, false);
+            sink.extend(out);
+            pending.drain(..used);
+        }
+        let (out, used) = r.redact_stream(&pending, true);
+        sink.extend(out);
+        assert_eq!(used, pending.len());
+        assert_eq!(String::from_utf8(sink).unwrap(), "log: *** and ***");
+
+        // Held back only while it may still become a secret.
+        assert_eq!(r.redact_stream(b"hunt", false), (Vec::new(), 0));
+        assert_eq!(r.redact_stream(b"hunt!", false), (b"hunt!".to_vec(), 5));
+        assert_eq!(r.redact_stream(b"hunt", true), (b"hunt".to_vec(), 4));
+    }
+}
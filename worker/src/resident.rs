@@ -0,0 +1,386 @@
+//! Resident Python/Node runtimes, for jobs on the `socket` transport (see
+//! `RUNNER_RESULT_PROTOCOL.md`). The first fire of such a job spawns the injected runtime
+//! with `--transport socket`; it connects back over a Unix socket in a private temp dir,
+//! imports the module, runs `prepare(ctx)` once and then serves one task per fire with
+//! `run(ctx)`, staying warm in between.
+//!
+//! The wire is one JSON object per line. The worker sends a `task` (the run's identity,
+//! params and declared secrets); the runtime answers with events while it works (captured output, leveled
+//! records, progress, heartbeats: see [`crate::events`]) and ends each task with a
+//! `result`, the same document the `file` transport writes. A result marked `closing` (the
+//! import or `prepare` failed) means the runtime exits after sending it.
+//!
+//! A process is recycled after `max_runs` tasks, when it dies, when a task times out or is
+//! cancelled (it is killed mid-task, since its state is unknown), when the job's module,
+//! entry or env change, and after sitting idle for [`IDLE_TTL`].
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use arbiter_core::{ArbiterError, LogStream, Result};
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::UnixListener;
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::process::{Child, Command};
+use tokio::sync::OwnedMutexGuard;
+use uuid::Uuid;
+
+use crate::events::apply_event_line;
+use crate::runner::{RunContext, RunLogs};
+use crate::{
+    CommandRunOutput, DocumentRun, Lang, PROTOCOL_VERSION, RuntimeResult, opt, runtime_task,
+};
+
+/// A resident process unused for this long is shut down at the next acquire.
+const IDLE_TTL: Duration = Duration::from_secs(3600);
+
+/// How long a recycled process gets to exit on its own after its socket closes.
+const EXIT_GRACE: Duration = Duration::from_secs(5);
+
+type Slot = Arc<tokio::sync::Mutex<Option<Resident>>>;
+
+/// The current run's stdout/stderr buffers, which the process's pipes are copied into.
+type Sink = Arc<Mutex<Option<(Arc<Mutex<Vec<u8>>>, Arc<Mutex<Vec<u8>>>)>>>;
+
+/// The resident runtimes of one language, at most one per job.
+#[derive(Default)]
+pub(crate) struct ResidentPool {
+    slots: Mutex<HashMap<Uuid, Slot>>,
+}
+
+impl ResidentPool {
+    /// Take the job's slot for one run. `None` when another run of the job holds it; that
+    /// run gets a one-shot process instead, so `max_concurrency` still applies.
+    pub(crate) fn acquire(&self, job_id: Uuid) -> Option<OwnedMutexGuard<Option<Resident>>> {
+        let mut slots = self.slots.lock().unwrap_or_else(|p| p.into_inner());
+        slots.retain(|id, slot| {
+            if *id == job_id {
+                return true;
+            }
+            let Ok(mut held) = slot.try_lock() else {
+                return true;
+            };
+            match held.take() {
+                Some(p) if p.last_used.elapsed() < IDLE_TTL => {
+                    *held = Some(p);
+                    true
+                }
+                Some(p) => {
+                    p.retire();
+                    false
+                }
+                None => false,
+            }
+        });
+        slots
+            .entry(job_id)
+            .or_default()
+            .clone()
+            .try_lock_owned()
+            .ok()
+    }
+}
+
+/// What a resident process is started with; a change means a fresh process.
+pub(crate) struct ResidentSpec<'a> {
+    pub(crate) module: &'a str,
+    pub(crate) entry: &'a str,
+    pub(crate) env: &'a HashMap<String, String>,
+    /// Sent with each task; values may change between fires without a respawn.
+    pub(crate) secrets: &'a HashMap<String, String>,
+    pub(crate) max_runs: u32,
+}
+
+impl ResidentSpec<'_> {
+    fn signature(&self, lang: Lang) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        (lang.name(), self.module, self.entry).hash(&mut hasher);
+        let mut env: Vec<_> = self.env.iter().collect();
+        env.sort_unstable();
+        env.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Run one task on the job's resident process, spawning it first if there is none (or it
+/// is stale), and put it back in `slot` afterwards unless it is due for recycling.
+pub(crate) async fn execute_resident(
+    ctx: &RunContext,
+    mut slot: OwnedMutexGuard<Option<Resident>>,
+    lang: Lang,
+    spec: ResidentSpec<'_>,
+    timeout_sec: Option<u32>,
+) -> Result<DocumentRun> {
+    let signature = spec.signature(lang);
+    // The process leaves the slot for the run. If the run is cancelled or times out it is
+    // dropped with this future, which kills it.
+    let warm = slot.take().and_then(|mut p| {
+        if p.signature == signature && p.is_alive() {
+            Some(p)
+        } else {
+            p.retire();
+            None
+        }
+    });
+
+    let logs = ctx.logs();
+    let task = async {
+        let mut proc = match warm {
+            Some(p) => p,
+            None => Resident::spawn(lang, &spec, signature).await?,
+        };
+        proc.attach(&logs);
+        let reply = proc.task(&runtime_task(ctx, spec.secrets), &logs).await;
+        proc.detach();
+        Ok::<_, ArbiterError>((proc, reply))
+    };
+    let finished = match timeout_sec {
+        Some(secs) if secs > 0 => {
+            match tokio::time::timeout(Duration::from_secs(secs as u64), task).await {
+                Ok(done) => Some(done?),
+                Err(_) => None,
+            }
+        }
+        _ => Some(task.await?),
+    };
+
+    let (exit_code, doc, note) = match finished {
+        Some((mut proc, Reply::Result { doc, closing })) => {
+            proc.runs += 1;
+            let exit_code = match serde_json::from_slice::<RuntimeResult>(&doc) {
+                Ok(res) if res.status == "success" => 0,
+                _ => 1,
+            };
+            if closing || proc.runs >= spec.max_runs {
+                proc.retire();
+            } else {
+                proc.last_used = Instant::now();
+                *slot = Some(proc);
+            }
+            (exit_code, Ok(doc), None)
+        }
+        Some((proc, Reply::Closed(reason))) => {
+            let exit_code = proc.exit_code().await;
+            (exit_code, Err(reason), None)
+        }
+        None => {
+            let secs = timeout_sec.unwrap_or(0);
+            let reason = std::io::Error::other("timed out");
+            (
+                -1,
+                Err(reason),
+                Some(format!("run exceeded timeout of {secs}s")),
+            )
+        }
+    };
+
+    let (stdout, mut stderr) = logs.finish().await;
+    if let Some(msg) = note {
+        if !stderr.is_empty() {
+            stderr.push('\n');
+        }
+        stderr.push_str(&msg);
+    }
+    Ok(DocumentRun {
+        raw: CommandRunOutput {
+            exit_code,
+            stdout: opt(&stdout),
+            stderr: opt(&stderr),
+        },
+        doc,
+    })
+}
+
+/// How a task ended: the runtime's result document, or the connection closing first (the
+/// process died).
+enum Reply {
+    Result { doc: Vec<u8>, closing: bool },
+    Closed(std::io::Error),
+}
+
+/// A message from the runtime. Unknown types are ignored.
+#[derive(Deserialize)]
+struct Message {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    closing: bool,
+}
+
+/// One resident runtime process and its socket.
+pub(crate) struct Resident {
+    child: Child,
+    reader: Option<Lines<BufReader<OwnedReadHalf>>>,
+    writer: Option<OwnedWriteHalf>,
+    sink: Sink,
+    signature: u64,
+    runs: u32,
+    last_used: Instant,
+    // Holds the socket; removed when the process is dropped.
+    _dir: tempfile::TempDir,
+}
+
+impl Resident {
+    /// Start the runtime and wait for it to connect. It runs the import and `prepare` after
+    /// connecting, while the first task waits in the socket.
+    async fn spawn(lang: Lang, spec: &ResidentSpec<'_>, signature: u64) -> Result<Self> {
+        let exec = |e: std::io::Error| ArbiterError::ExecutionError(e.to_string());
+        let (runtime_path, program) = lang.runtime_file()?;
+        let dir = tempfile::Builder::new()
+            .prefix("arbiter-resident-")
+            .tempdir()
+            .map_err(exec)?;
+        let socket_path = dir.path().join("runtime.sock");
+        let listener = UnixListener::bind(&socket_path).map_err(exec)?;
+
+        let mut cmd = Command::new(program);
+        cmd.arg(&runtime_path)
+            .arg("--module")
+            .arg(spec.module)
+            .arg("--entry")
+            .arg(spec.entry)
+            .arg("--socket")
+            .arg(&socket_path)
+            .arg("--transport")
+            .arg("socket")
+            .arg("--protocol")
+            .arg(PROTOCOL_VERSION)
+            .envs(spec.env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = cmd.spawn().map_err(exec)?;
+
+        let sink = Sink::default();
+        spawn_sink_reader(child.stdout.take(), sink.clone(), LogStream::Stdout);
+        spawn_sink_reader(child.stderr.take(), sink.clone(), LogStream::Stderr);
+
+        let (reader, writer) = tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = accepted.map_err(exec)?;
+                let (read, write) = stream.into_split();
+                (Some(BufReader::new(read).lines()), Some(write))
+            }
+            // Exited before connecting (a bad interpreter, a broken runtime): the task
+            // reports the connection as closed and the run falls back to the exit code.
+            _ = child.wait() => (None, None),
+        };
+
+        Ok(Resident {
+            child,
+            reader,
+            writer,
+            sink,
+            signature,
+            runs: 0,
+            last_used: Instant::now(),
+            _dir: dir,
+        })
+    }
+
+    fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Copy the process's stdout/stderr into this run's logs until [`Resident::detach`].
+    fn attach(&self, logs: &RunLogs) {
+        *self.sink.lock().unwrap_or_else(|p| p.into_inner()) = Some((
+            logs.buffer(LogStream::Stdout),
+            logs.buffer(LogStream::Stderr),
+        ));
+    }
+
+    fn detach(&self) {
+        *self.sink.lock().unwrap_or_else(|p| p.into_inner()) = None;
+    }
+
+    /// Send the run as a task and read events until its result.
+    async fn task(&mut self, task: &serde_json::Value, logs: &RunLogs) -> Reply {
+        let (Some(reader), Some(writer)) = (self.reader.as_mut(), self.writer.as_mut()) else {
+            return Reply::Closed(std::io::Error::other("runtime exited before connecting"));
+        };
+        let sink = logs.sink();
+        let mut line = task.to_string();
+        line.push('\n');
+        if let Err(e) = writer.write_all(line.as_bytes()).await {
+            return Reply::Closed(e);
+        }
+
+        loop {
+            let line = match reader.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => return Reply::Closed(std::io::Error::other("runtime closed")),
+                Err(e) => return Reply::Closed(e),
+            };
+            let Ok(msg) = serde_json::from_str::<Message>(&line) else {
+                tracing::debug!("ignoring malformed runtime message: {line}");
+                continue;
+            };
+            if msg.kind == "result" {
+                return Reply::Result {
+                    doc: line.into_bytes(),
+                    closing: msg.closing,
+                };
+            }
+            apply_event_line(&line, &sink);
+        }
+    }
+
+    /// Wait briefly for a dead (or dying) process's exit code; -1 if it will not say.
+    async fn exit_code(mut self) -> i32 {
+        match tokio::time::timeout(EXIT_GRACE, self.child.wait()).await {
+            Ok(Ok(status)) => status.code().unwrap_or(-1),
+            _ => -1,
+        }
+    }
+
+    /// Close the socket so the runtime exits on its own, killing it if it has not after
+    /// [`EXIT_GRACE`].
+    fn retire(mut self) {
+        self.writer = None;
+        tokio::spawn(async move {
+            let _ = tokio::time::timeout(EXIT_GRACE, self.child.wait()).await;
+        });
+    }
+}
+
+/// Copy a resident process's pipe into whichever run is attached; output between runs
+/// is only traced.
+fn spawn_sink_reader<R>(reader: Option<R>, sink: Sink, stream: LogStream)
+where
+    R: tokio::io::AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        use tokio::io::AsyncReadExt as _;
+        let Some(mut reader) = reader else { return };
+        let mut chunk = [0u8; 8192];
+        loop {
+            match reader.read(&mut chunk).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let sink = sink.lock().unwrap_or_else(|p| p.into_inner());
+                    match &*sink {
+                        Some((out, err)) => {
+                            let buf = match stream {
+                                LogStream::Stdout => out,
+                                LogStream::Stderr => err,
+                            };
+                            buf.lock()
+                                .unwrap_or_else(|p| p.into_inner())
+                                .extend_from_slice(&chunk[..n]);
+                        }
+                        None => tracing::debug!(
+                            "resident runtime output between runs: {}",
+                            String::from_utf8_lossy(&chunk[..n])
+                        ),
+                    }
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,626 @@
+//! The runner extension point. A [`Runner`] executes one kind of config snapshot (keyed by
+//! its `type` tag, or `plugin:<name>`, see [`ExecutableConfigSnapshotMeta::runner_key`]); the
+//! worker looks the kind up in a [`RunnerRegistry`] instead of matching on the snapshot
+//! itself, so a runner can live in another crate, or another process (a
+//! [`PluginRunner`](crate::PluginRunner)), and be registered by whoever builds the node.
+//! Built-in runners take the typed snapshot; a runner registered under `plugin:<name>` takes
+//! its opaque [`RunnerPayload`] instead, so adding one needs no new snapshot variant.
+//!
+//! A run goes `prepare` (resolve secrets, build clients/commands; an error fails the run
+//! before anything executes) -> `execute` -> `outcome` (map the raw output onto a
+//! [`RunOutcome`]). The [`RunContext`] carries the run's identity, secret resolution, the
+//! live log sink and cancellation.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use arbiter_core::{
+    ArbiterError, ExecutableConfigSnapshotMeta, JobRun, LogLevel, LogStream, Result, RetryOn,
+    RunOutcome, RunProgress, RunnerPluginInfo, SecretDelivery, Store, WorkspaceMode,
+};
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::db_params::BindContext;
+use crate::plugin::PluginRunner;
+use crate::maintenance::{RunHold, scratch_prefix};
+use crate::redact::{RecordingResolver, Redactor};
+use crate::workspace::Workspace;
+use crate::{LogFlusher, SECRET_PREFIX, Secrets, resolve_env, resolve_ref, snapshot_buffers};
+
+/// Env var carrying a shell run's secrets directory, with [`SecretDelivery::Files`].
+pub const SECRETS_DIR_ENV: &str = "ARBITER_SECRETS_DIR";
+
+/// What a runner is handed from its run's snapshot (see [`Runner::Input`]).
+pub trait RunnerInput: Sized + Send {
+    /// Extract the input from the snapshot the registry dispatched on.
+    fn from_snapshot(meta: ExecutableConfigSnapshotMeta) -> Result<Self>;
+}
+
+impl RunnerInput for ExecutableConfigSnapshotMeta {
+    fn from_snapshot(meta: ExecutableConfigSnapshotMeta) -> Result<Self> {
+        Ok(meta)
+    }
+}
+
+/// The runner-keyed part of a `plugin:<name>` snapshot: the free-form config as the job
+/// stored it (`secret:` references unresolved), with the job's env.
+#[derive(Debug, Clone)]
+pub struct RunnerPayload {
+    /// The `<name>` the job's runner config asked for.
+    pub name: String,
+    pub config: serde_json::Value,
+    pub timeout_sec: Option<u32>,
+    pub env: HashMap<String, String>,
+}
+
+impl RunnerInput for RunnerPayload {
+    fn from_snapshot(meta: ExecutableConfigSnapshotMeta) -> Result<Self> {
+        match meta {
+            ExecutableConfigSnapshotMeta::Plugin {
+                plugin,
+                config,
+                timeout_sec,
+                env,
+            } => Ok(RunnerPayload {
+                name: plugin,
+                config,
+                timeout_sec,
+                env,
+            }),
+            other => Err(ArbiterError::ExecutionError(format!(
+                "a {} snapshot carries no runner payload",
+                other.type_of_str()
+            ))),
+        }
+    }
+}
+
+/// Executes one kind of snapshot. `Input`, `Prepared` and `Output` are the runner's own
+/// types, so the three phases stay typed; the registry erases them.
+#[async_trait]
+pub trait Runner: Send + Sync + 'static {
+    /// What `prepare` is given: the whole [`ExecutableConfigSnapshotMeta`] for a built-in
+    /// kind, or a [`RunnerPayload`] for a runner registered under `plugin:<name>`.
+    type Input: RunnerInput;
+    /// What `prepare` hands to `execute`: resolved secrets, a built command or client.
+    type Prepared: Send;
+    /// What `execute` produced, before it is classified.
+    type Output: Send;
+
+    /// Resolve everything the run needs from its input. An error fails the run without
+    /// executing anything.
+    async fn prepare(&self, ctx: &RunContext, input: Self::Input) -> Result<Self::Prepared>;
+
+    /// Run the job. Dropped if the run is cancelled, so anything that must not outlive it
+    /// (a child process, a connection) should be tied to this future.
+    async fn execute(&self, ctx: &RunContext, prepared: Self::Prepared) -> Result<Self::Output>;
+
+    /// Map the raw output onto the run's outcome (status, streams, typed result/error).
+    fn outcome(&self, ctx: &RunContext, output: Self::Output) -> RunOutcome;
+}
+
+/// Object-safe form of [`Runner`], so runners with different associated types share a table.
+#[async_trait]
+trait DynRunner: Send + Sync {
+    async fn run(&self, ctx: &RunContext, meta: ExecutableConfigSnapshotMeta)
+    -> Result<RunOutcome>;
+}
+
+#[async_trait]
+impl<R: Runner> DynRunner for R {
+    async fn run(
+        &self,
+        ctx: &RunContext,
+        meta: ExecutableConfigSnapshotMeta,
+    ) -> Result<RunOutcome> {
+        let input = R::Input::from_snapshot(meta)?;
+        let prepared = self.prepare(ctx, input).await?;
+        let output = self.execute(ctx, prepared).await?;
+        Ok(self.outcome(ctx, output))
+    }
+}
+
+/// The runners a worker can execute, keyed by snapshot kind. Built by the node (usually
+/// [`RunnerRegistry::with_builtins`] plus anything extra) and shared by every run task.
+#[derive(Default)]
+pub struct RunnerRegistry {
+    runners: HashMap<String, Arc<dyn DynRunner>>,
+    plugins: Vec<RunnerPluginInfo>,
+}
+
+impl RunnerRegistry {
+    /// An empty registry; runs of any kind fail until something is registered.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The runners shipped with arbiter, one per built-in snapshot kind.
+    pub fn with_builtins() -> Self {
+        let mut reg = Self::new();
+        crate::builtin::register(&mut reg);
+        reg
+    }
+
+    /// Register `runner` for snapshots of `kind`, replacing any runner already there. A
+    /// runner outside this crate registers under `plugin:<name>` with a [`RunnerPayload`]
+    /// input.
+    pub fn register<R: Runner>(&mut self, kind: impl Into<String>, runner: R) -> &mut Self {
+        self.runners.insert(kind.into(), Arc::new(runner));
+        self
+    }
+
+    /// Register a probed plugin for `plugin:<name>` snapshots and advertise it.
+    pub fn register_plugin(&mut self, plugin: PluginRunner) -> &mut Self {
+        let info = plugin.info().clone();
+        self.plugins.retain(|p| p.name != info.name);
+        self.runners
+            .insert(format!("plugin:{}", info.name), Arc::new(plugin));
+        self.plugins.push(info);
+        self
+    }
+
+    /// What the registered plugins reported in their handshakes, for the worker record.
+    pub fn plugins(&self) -> &[RunnerPluginInfo] {
+        &self.plugins
+    }
+
+    /// The kinds this registry can execute, sorted.
+    pub fn kinds(&self) -> Vec<&str> {
+        let mut kinds: Vec<&str> = self.runners.keys().map(String::as_str).collect();
+        kinds.sort_unstable();
+        kinds
+    }
+
+    /// Run `meta` through the runner registered for its kind.
+    pub(crate) async fn run(
+        &self,
+        ctx: &RunContext,
+        meta: ExecutableConfigSnapshotMeta,
+    ) -> Result<RunOutcome> {
+        let key = meta.runner_key().into_owned();
+        match self.runners.get(&key) {
+            Some(runner) => runner.run(ctx, meta).await,
+            None => Err(ArbiterError::ExecutionError(format!(
+                "no runner registered for '{key}' on this worker"
+            ))),
+        }
+    }
+}
+
+/// Everything a runner may use for one run. The snapshot itself is handed to
+/// [`Runner::prepare`] separately.
+pub struct RunContext {
+    store: Arc<dyn Store + Send + Sync>,
+    secrets: Secrets,
+    worker_id: Uuid,
+    data_dir: PathBuf,
+    run: JobRun,
+    tenant: Option<Uuid>,
+    retry_on: RetryOn,
+    max_log_bytes: u64,
+    artifacts_dir: Option<tempfile::TempDir>,
+    /// Per-run directories (a git checkout) removed with the context.
+    scratch: Mutex<Vec<tempfile::TempDir>>,
+    /// How long the run's own workspace outlives it (`0` = removed with the context).
+    workspace_keep_run_secs: u64,
+    /// The run's working directory, held until the context drops.
+    workspace: Mutex<Option<Workspace>>,
+    /// The private directory env secrets are delivered in as files, removed with the
+    /// context.
+    secrets_dir: Mutex<Option<tempfile::TempDir>>,
+    /// Every secret value the run resolved, masked in what it logs and reports.
+    redactor: Redactor,
+    cancel: CancelToken,
+    /// Marks the run as this worker's, so the maintenance sweep leaves its scratch alone.
+    /// Last, so it is released once everything above is gone.
+    _owned: RunHold,
+}
+
+impl RunContext {
+    /// `with_artifacts` gives the run a private artifacts directory, removed with the
+    /// context (a failure to create one is logged and the run goes without).
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        store: Arc<dyn Store + Send + Sync>,
+        secrets: Secrets,
+        with_artifacts: bool,
+        worker_id: Uuid,
+        data_dir: PathBuf,
+        run: JobRun,
+        tenant: Option<Uuid>,
+        retry_on: RetryOn,
+        max_log_bytes: u64,
+        workspace_keep_run_secs: u64,
+    ) -> Self {
+        let owned = RunHold::new(run.id);
+        // Resolving through the context acts as the run and records what it resolved, for
+        // masking and usage.
+        let redactor = Redactor::default();
+        let secrets: Secrets = secrets.map(|inner| {
+            Arc::new(RecordingResolver {
+                inner,
+                redactor: redactor.clone(),
+                store: store.clone(),
+                run_id: run.id,
+                job_id: run.job_id,
+            }) as _
+        });
+        let artifacts_dir = with_artifacts
+            .then(|| {
+                tempfile::Builder::new()
+                    .prefix(&scratch_prefix("artifacts", run.id))
+                    .tempdir()
+                    .inspect_err(|e| {
+                        tracing::warn!("run {}: no artifacts directory: {e}", run.id)
+                    })
+                    .ok()
+            })
+            .flatten();
+        RunContext {
+            store,
+            secrets,
+            worker_id,
+            data_dir,
+            run,
+            tenant,
+            retry_on,
+            max_log_bytes,
+            artifacts_dir,
+            scratch: Mutex::default(),
+            workspace_keep_run_secs,
+            workspace: Mutex::default(),
+            secrets_dir: Mutex::default(),
+            redactor,
+            cancel: CancelToken::default(),
+            _owned: owned,
+        }
+    }
+
+    pub fn store(&self) -> &Arc<dyn Store + Send + Sync> {
+        &self.store
+    }
+
+    pub fn worker_id(&self) -> Uuid {
+        self.worker_id
+    }
+
+    /// The node's data directory, home of the worker's code cache.
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// The claimed run (id, job, attempt, `scheduled_for`, ad-hoc params). Its snapshot has
+    /// been moved out.
+    pub fn run(&self) -> &JobRun {
+        &self.run
+    }
+
+    /// The job's tenant, which scopes every secret the run resolves.
+    pub fn tenant(&self) -> Option<Uuid> {
+        self.tenant
+    }
+
+    /// The job's retry classification overrides, captured in the snapshot.
+    pub fn retry_on(&self) -> &RetryOn {
+        &self.retry_on
+    }
+
+    /// The directory whose top-level files are uploaded as the attempt's artifacts once it
+    /// finishes, or `None` when this worker keeps no artifacts.
+    pub fn artifacts_dir(&self) -> Option<&Path> {
+        self.artifacts_dir.as_ref().map(|d| d.path())
+    }
+
+    /// Keep `dir` until the run is over: it is removed with the context, however the run
+    /// ends.
+    pub(crate) fn keep_until_done(&self, dir: tempfile::TempDir) {
+        self.scratch.lock().expect("scratch lock").push(dir);
+    }
+
+    /// The run's workspace, a directory the worker manages under its data dir (of the run's
+    /// own, or shared by the job's runs with [`WorkspaceMode::Job`]). Created on first use
+    /// and held until the run is over.
+    pub(crate) fn workspace(&self, mode: WorkspaceMode) -> Result<PathBuf> {
+        let mut slot = self.workspace.lock().expect("workspace lock");
+        if let Some(ws) = slot.as_ref() {
+            return Ok(ws.path().to_path_buf());
+        }
+        let ws = Workspace::acquire(&self.data_dir, &self.run, mode, self.workspace_keep_run_secs)?;
+        let path = ws.path().to_path_buf();
+        *slot = Some(ws);
+        Ok(path)
+    }
+
+    /// The workspace the run was given, if its runner uses one.
+    pub fn workspace_dir(&self) -> Option<PathBuf> {
+        let slot = self.workspace.lock().expect("workspace lock");
+        slot.as_ref().map(|ws| ws.path().to_path_buf())
+    }
+
+    /// Resolve a `secret:<name>` reference in the run's tenant; any other value is returned
+    /// as-is.
+    pub async fn resolve(&self, value: &str) -> Result<String> {
+        resolve_ref(&self.secrets, self.tenant, value).await
+    }
+
+    /// Resolve every `secret:<name>` value in a job env.
+    pub async fn resolve_env(
+        &self,
+        env: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>> {
+        resolve_env(env, &self.secrets, self.tenant).await
+    }
+
+    /// Resolve a job env for `delivery`. With [`SecretDelivery::Files`], each
+    /// `secret:<name>` value is left out of the env and written instead to a file named
+    /// after its key, readable by the worker's user only, in the run's
+    /// [`secrets_dir`](Self::secrets_dir).
+    pub(crate) async fn resolve_env_as(
+        &self,
+        env: &HashMap<String, String>,
+        delivery: SecretDelivery,
+    ) -> Result<HashMap<String, String>> {
+        if delivery == SecretDelivery::Env {
+            return self.resolve_env(env).await;
+        }
+        let dir = self.create_secrets_dir()?;
+        let mut out = HashMap::with_capacity(env.len());
+        for (key, value) in env {
+            if !value.starts_with(SECRET_PREFIX) {
+                out.insert(key.clone(), value.clone());
+                continue;
+            }
+            if key.is_empty() || key == "." || key == ".." || key.contains(['/', '\\', '\0']) {
+                return Err(ArbiterError::ExecutionError(format!(
+                    "env key {key:?} cannot name a secret file"
+                )));
+            }
+            let secret = self.resolve(value).await?;
+            write_private(&dir.join(key), secret.as_bytes()).map_err(|e| {
+                ArbiterError::ExecutionError(format!("cannot write secret file {key}: {e}"))
+            })?;
+        }
+        Ok(out)
+    }
+
+    /// Create the run's secrets directory (private to the worker's user), or return the
+    /// one it has.
+    fn create_secrets_dir(&self) -> Result<PathBuf> {
+        let mut slot = self.secrets_dir.lock().expect("secrets dir lock");
+        if let Some(dir) = slot.as_ref() {
+            return Ok(dir.path().to_path_buf());
+        }
+        let dir = tempfile::Builder::new()
+            .prefix(&scratch_prefix("secrets", self.run.id))
+            .tempdir()
+            .map_err(|e| {
+                ArbiterError::ExecutionError(format!("cannot create secrets directory: {e}"))
+            })?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o700)).map_err(
+                |e| ArbiterError::ExecutionError(format!("cannot protect secrets directory: {e}")),
+            )?;
+        }
+        let path = dir.path().to_path_buf();
+        *slot = Some(dir);
+        Ok(path)
+    }
+
+    /// The directory the run's env secrets were delivered in as files, if its job asks for
+    /// [`SecretDelivery::Files`]. Removed once the run is over, however it ends.
+    pub fn secrets_dir(&self) -> Option<PathBuf> {
+        let slot = self.secrets_dir.lock().expect("secrets dir lock");
+        slot.as_ref().map(|dir| dir.path().to_path_buf())
+    }
+
+    /// Start capturing the run's stdout/stderr: what is written is flushed to the store as
+    /// live log chunks until [`RunLogs::finish`], with the run's secrets masked.
+    pub fn logs(&self) -> RunLogs {
+        RunLogs::start(
+            &self.store,
+            self.worker_id,
+            self.run.id,
+            self.run.attempt,
+            &self.redactor,
+            self.max_log_bytes,
+        )
+    }
+
+    /// The secret values the run resolved so far.
+    pub(crate) fn redactor(&self) -> &Redactor {
+        &self.redactor
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Resolves once the run is cancelled.
+    pub async fn cancelled(&self) {
+        self.cancel.cancelled().await
+    }
+
+    pub(crate) fn secrets(&self) -> &Secrets {
+        &self.secrets
+    }
+
+    pub(crate) fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    /// Named parameters a DB runner's query can bind.
+    pub(crate) fn bind(&self) -> BindContext<'_> {
+        BindContext {
+            run_id: self.run.id,
+            job_id: self.run.job_id,
+            scheduled_for: self.run.scheduled_for,
+            attempt: self.run.attempt,
+            params: &self.run.params,
+        }
+    }
+}
+
+/// A run's captured output. Each stream is a byte buffer (child pipes and WASI streams
+/// write into them directly); a background [`LogFlusher`] appends what is new as chunks,
+/// along with the structured records, progress and heartbeats fed through the [`RunLogSink`].
+pub struct RunLogs {
+    sink: RunLogSink,
+    flusher: LogFlusher,
+    redactor: Redactor,
+}
+
+impl RunLogs {
+    pub(crate) fn start(
+        store: &Arc<dyn Store + Send + Sync>,
+        worker_id: Uuid,
+        run_id: Uuid,
+        attempt: u32,
+        redactor: &Redactor,
+        max_log_bytes: u64,
+    ) -> Self {
+        let sink = RunLogSink::default();
+        let flusher =
+            LogFlusher::spawn(store, worker_id, run_id, attempt, &sink, redactor, max_log_bytes);
+        RunLogs {
+            sink,
+            flusher,
+            redactor: redactor.clone(),
+        }
+    }
+
+    /// The shared buffer behind a stream, for a reader task or sandbox to write into.
+    pub fn buffer(&self, stream: LogStream) -> Arc<Mutex<Vec<u8>>> {
+        match stream {
+            LogStream::Stdout => self.sink.out.clone(),
+            LogStream::Stderr => self.sink.err.clone(),
+        }
+    }
+
+    /// Append to a stream; it is flushed with the next chunk.
+    pub fn write(&self, stream: LogStream, bytes: &[u8]) {
+        self.sink.write(stream, bytes);
+    }
+
+    /// A handle for feeding this run's logs and events from another task.
+    pub fn sink(&self) -> RunLogSink {
+        self.sink.clone()
+    }
+
+    /// Flush what is left and return the full stdout and stderr text, masked.
+    pub async fn finish(self) -> (String, String) {
+        self.flusher.finish().await;
+        let (out, err) = snapshot_buffers(&self.sink.out, &self.sink.err);
+        (self.redactor.redact(&out), self.redactor.redact(&err))
+    }
+}
+
+/// Where a run's output and runtime events accumulate until the next flush. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct RunLogSink {
+    pub(crate) out: Arc<Mutex<Vec<u8>>>,
+    pub(crate) err: Arc<Mutex<Vec<u8>>>,
+    pub(crate) events: Arc<Mutex<PendingEvents>>,
+}
+
+/// Runtime events received since the last flush.
+#[derive(Default)]
+pub(crate) struct PendingEvents {
+    pub(crate) records: Vec<(LogLevel, String)>,
+    pub(crate) progress: Option<RunProgress>,
+    pub(crate) alive: bool,
+}
+
+impl RunLogSink {
+    /// Append raw output to a stream.
+    pub fn write(&self, stream: LogStream, bytes: &[u8]) {
+        let buf = match stream {
+            LogStream::Stdout => &self.out,
+            LogStream::Stderr => &self.err,
+        };
+        buf.lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .extend_from_slice(bytes);
+    }
+
+    /// Add a structured log record. It becomes a chunk of its own tagged with `level`, and
+    /// counts as a heartbeat.
+    pub fn record(&self, level: LogLevel, message: &str) {
+        let mut ev = self.lock_events();
+        ev.records.push((level, message.to_string()));
+        ev.alive = true;
+    }
+
+    /// Report progress; `value` is clamped to `0..=1` (NaN is ignored). Only the latest
+    /// report per flush is kept. Counts as a heartbeat.
+    pub fn progress(&self, value: f64, message: Option<String>) {
+        let mut ev = self.lock_events();
+        if !value.is_nan() {
+            ev.progress = Some(RunProgress {
+                value: value.clamp(0.0, 1.0),
+                message,
+            });
+        }
+        ev.alive = true;
+    }
+
+    /// Mark the run alive. Heartbeats are coalesced into one store write per interval.
+    pub fn heartbeat(&self) {
+        self.lock_events().alive = true;
+    }
+
+    fn lock_events(&self) -> std::sync::MutexGuard<'_, PendingEvents> {
+        self.events.lock().unwrap_or_else(|p| p.into_inner())
+    }
+}
+
+/// Cancellation flag for one run, shared between the worker and the runner.
+#[derive(Clone, Default)]
+pub(crate) struct CancelToken(Arc<CancelState>);
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    notify: tokio::sync::Notify,
+}
+
+impl CancelToken {
+    pub(crate) fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        self.0.notify.notify_waiters();
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    async fn cancelled(&self) {
+        loop {
+            let notified = self.0.notify.notified();
+            tokio::pin!(notified);
+            // Register before checking, so a cancel between the check and the await is seen.
+            notified.as_mut().enable();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Write a new file only the worker's user can read.
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write as _;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt as _;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(bytes)
+}
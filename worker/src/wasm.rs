@@ -0,0 +1,578 @@
+//! `wasm` runner: a WASI (preview 1) module executed in-process with wasmtime, so a worker
+//! needs no interpreter for it. The module is sandboxed by capability: it sees only the
+//! job's env, its `args`, and the directories the job preopens, plus a private result
+//! directory at [`RESULT_DIR`] and, when the worker keeps artifacts, the run's artifacts
+//! directory at [`WASM_ARTIFACTS_DIR`]. Fuel bounds the instructions it may execute, a store
+//! limiter its linear memory, and the epoch ticker its wall-clock time.
+//!
+//! Output goes through the same [`RunLogs`](crate::RunLogs) as a subprocess, so logs stream
+//! into `run_log_chunks` while the module runs. A module that writes a result document
+//! (RUNNER_RESULT_PROTOCOL.md) to `/arbiter/result.json` gets a typed result; otherwise
+//! its exit code decides, as for a shell job.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use arbiter_core::{
+    ArbiterError, LogStream, Result, Store, WasmLimits, WasmModuleSource, WasmPreopen,
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use wasmtime::{
+    Config, Engine, Linker, Module, StoreLimits, StoreLimitsBuilder, Trap, UpdateDeadline,
+};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{
+    DirPerms, FilePerms, I32Exit, OutputStream, Pollable, StdoutStream, StreamResult,
+    WasiCtxBuilder,
+};
+
+use crate::artifacts::WASM_ARTIFACTS_DIR;
+use crate::runner::RunContext;
+use crate::{CommandRunOutput, DocumentRun, opt};
+
+/// Guest path of the worker-owned directory holding the result document.
+const RESULT_DIR: &str = "/arbiter";
+const RESULT_FILE: &str = "result.json";
+
+/// How often the engine's epoch advances; the granularity of a run's timeout.
+const EPOCH_TICK: Duration = Duration::from_millis(100);
+
+/// One engine for all runs (compiled modules are tied to it). Fuel metering and epoch
+/// interruption are on for every store; a background thread advances the epoch so each
+/// run's deadline callback can check its own timeout.
+static ENGINE: LazyLock<Engine> = LazyLock::new(|| {
+    let mut config = Config::new();
+    config.consume_fuel(true).epoch_interruption(true);
+    let engine = Engine::new(&config).expect("wasm engine config");
+    let ticker = engine.clone();
+    std::thread::Builder::new()
+        .name("arbiter-wasm-epoch".to_string())
+        .spawn(move || {
+            loop {
+                std::thread::sleep(EPOCH_TICK);
+                ticker.increment_epoch();
+            }
+        })
+        .expect("spawn wasm epoch thread");
+    engine
+});
+
+/// The run's tenant + content hash of the module bytes.
+type ModuleKey = (Option<Uuid>, String);
+
+/// Compiled modules keyed by the run's tenant and the content hash of their bytes, so a
+/// hash alone never reaches another tenant's module.
+static MODULES: LazyLock<Mutex<HashMap<ModuleKey, Module>>> = LazyLock::new(Default::default);
+
+/// Bound on cached modules; past it the cache is cleared.
+const MAX_CACHED_MODULES: usize = 32;
+
+/// A `wasm` job's snapshot, as the runner needs it.
+pub(crate) struct WasmRun<'a> {
+    pub module: &'a WasmModuleSource,
+    pub timeout_sec: Option<u32>,
+    pub fuel: Option<u64>,
+    pub max_memory_mb: Option<u32>,
+    pub preopens: &'a [WasmPreopen],
+    pub args: &'a [String],
+    /// The worker's `wasm_host_root`; see [`confine`].
+    pub host_root: Option<&'a Path>,
+}
+
+/// How the module's `_start` ended.
+enum WasmExit {
+    Exited(i32),
+    OutOfFuel,
+    TimedOut,
+    Trapped(String),
+}
+
+/// Per-run host state: the WASI context and the memory limiter.
+struct RunState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+pub(crate) async fn execute_wasm(
+    ctx: &RunContext,
+    job: WasmRun<'_>,
+    env: &HashMap<String, String>,
+) -> Result<DocumentRun> {
+    let (worker_id, run_id) = (ctx.worker_id(), ctx.run().id);
+    let module = load_module(ctx.store(), job.module, job.host_root, ctx.tenant()).await?;
+    let mut preopens = Vec::with_capacity(job.preopens.len());
+    for p in job.preopens {
+        let host_path = confine(job.host_root, &p.host_path).await?;
+        preopens.push(WasmPreopen {
+            host_path: host_path.to_string_lossy().into_owned(),
+            ..p.clone()
+        });
+    }
+    let exec = |e: std::io::Error| ArbiterError::ExecutionError(e.to_string());
+    let result_dir = tempfile::Builder::new()
+        .prefix(&crate::maintenance::scratch_prefix("wasm", run_id))
+        .tempdir()
+        .map_err(exec)?;
+
+    let mut argv = vec![program_name(job.module)];
+    argv.extend(job.args.iter().cloned());
+    let plan = Plan {
+        argv,
+        env: env.clone(),
+        preopens,
+        result_dir: result_dir.path().to_path_buf(),
+        artifacts_dir: ctx.artifacts_dir().map(|d| d.to_path_buf()),
+        fuel: job.fuel.unwrap_or(WasmLimits::DEFAULT_FUEL),
+        max_memory_bytes: job
+            .max_memory_mb
+            .unwrap_or(WasmLimits::DEFAULT_MAX_MEMORY_MB)
+            .min(WasmLimits::MAX_MEMORY_MB) as usize
+            * 1024
+            * 1024,
+        deadline: job
+            .timeout_sec
+            .filter(|s| *s > 0)
+            .map(|s| Instant::now() + Duration::from_secs(s as u64)),
+    };
+
+    let logs = ctx.logs();
+    let exit = {
+        let out = LogPipe(logs.buffer(LogStream::Stdout));
+        let err = LogPipe(logs.buffer(LogStream::Stderr));
+        tokio::task::spawn_blocking(move || run_module(&module, plan, out, err))
+            .await
+            .map_err(|e| ArbiterError::ExecutionError(e.to_string()))?
+    };
+    let (stdout, mut stderr) = logs.finish().await;
+    let failure = match &exit {
+        Ok(WasmExit::Exited(_)) => None,
+        Ok(WasmExit::OutOfFuel) => Some(format!(
+            "wasm module ran out of fuel ({} units)",
+            job.fuel.unwrap_or(WasmLimits::DEFAULT_FUEL)
+        )),
+        Ok(WasmExit::TimedOut) => Some(format!(
+            "run exceeded timeout of {}s",
+            job.timeout_sec.unwrap_or(0)
+        )),
+        Ok(WasmExit::Trapped(msg)) => Some(format!("wasm module trapped: {msg}")),
+        Err(e) => Some(e.to_string()),
+    };
+    if let Some(msg) = &failure {
+        tracing::debug!("{worker_id}: wasm run {run_id}: {msg}");
+        stderr = if stderr.is_empty() {
+            msg.clone()
+        } else {
+            format!("{stderr}\n{msg}")
+        };
+    }
+    let raw = CommandRunOutput {
+        exit_code: match exit {
+            Ok(WasmExit::Exited(code)) => code,
+            _ => -1,
+        },
+        stdout: opt(&stdout),
+        stderr: opt(&stderr),
+    };
+
+    // Only a module that exited on its own may have written a trustworthy result.
+    let doc = match failure {
+        None => tokio::fs::read(result_dir.path().join(RESULT_FILE)).await,
+        Some(_) => Err(std::io::ErrorKind::NotFound.into()),
+    };
+    Ok(DocumentRun { raw, doc })
+}
+
+/// Everything the blocking run needs, owned so it can move to a blocking thread.
+struct Plan {
+    argv: Vec<String>,
+    env: HashMap<String, String>,
+    preopens: Vec<WasmPreopen>,
+    result_dir: std::path::PathBuf,
+    artifacts_dir: Option<std::path::PathBuf>,
+    fuel: u64,
+    max_memory_bytes: usize,
+    deadline: Option<Instant>,
+}
+
+/// Instantiate and run the module's `_start` to completion. Blocking: WASI calls and the
+/// guest itself run on the calling thread.
+fn run_module(module: &Module, plan: Plan, out: LogPipe, err: LogPipe) -> Result<WasmExit> {
+    let setup = |e: wasmtime::Error| ArbiterError::ExecutionError(format!("wasm setup: {e:#}"));
+
+    let mut wasi = WasiCtxBuilder::new();
+    wasi.args(&plan.argv).stdout(out).stderr(err);
+    for (k, v) in &plan.env {
+        wasi.env(k, v);
+    }
+    for p in &plan.preopens {
+        let (dir_perms, file_perms) = if p.read_only {
+            (DirPerms::READ, FilePerms::READ)
+        } else {
+            (DirPerms::all(), FilePerms::all())
+        };
+        wasi.preopened_dir(&p.host_path, &p.guest_path, dir_perms, file_perms)
+            .map_err(|e| {
+                ArbiterError::ExecutionError(format!("wasm preopen {}: {e:#}", p.host_path))
+            })?;
+    }
+    wasi.preopened_dir(
+        &plan.result_dir,
+        RESULT_DIR,
+        DirPerms::all(),
+        FilePerms::all(),
+    )
+    .map_err(setup)?;
+    if let Some(dir) = &plan.artifacts_dir {
+        wasi.preopened_dir(dir, WASM_ARTIFACTS_DIR, DirPerms::all(), FilePerms::all())
+            .map_err(setup)?;
+    }
+
+    let state = RunState {
+        wasi: wasi.build_p1(),
+        limits: StoreLimitsBuilder::new()
+            .memory_size(plan.max_memory_bytes)
+            .trap_on_grow_failure(true)
+            .build(),
+    };
+    let mut store = wasmtime::Store::new(&ENGINE, state);
+    store.limiter(|s| &mut s.limits);
+    store.set_fuel(plan.fuel).map_err(setup)?;
+    let deadline = plan.deadline;
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_callback(move |_| match deadline {
+        Some(at) if Instant::now() >= at => Err(Trap::Interrupt.into()),
+        _ => Ok(UpdateDeadline::Continue(1)),
+    });
+
+    let mut linker: Linker<RunState> = Linker::new(&ENGINE);
+    preview1::add_to_linker_sync(&mut linker, |s| &mut s.wasi).map_err(setup)?;
+    let instance = linker.instantiate(&mut store, module).map_err(setup)?;
+    let start = instance
+        .get_typed_func::<(), ()>(&mut store, "_start")
+        .map_err(setup)?;
+
+    Ok(match start.call(&mut store, ()) {
+        Ok(()) => WasmExit::Exited(0),
+        Err(e) => {
+            if let Some(exit) = e.downcast_ref::<I32Exit>() {
+                WasmExit::Exited(exit.0)
+            } else {
+                match e.downcast_ref::<Trap>() {
+                    Some(Trap::OutOfFuel) => WasmExit::OutOfFuel,
+                    Some(Trap::Interrupt) => WasmExit::TimedOut,
+                    _ => WasmExit::Trapped(format!("{e:#}")),
+                }
+            }
+        }
+    })
+}
+
+/// Check a job's host path (a preopen or an on-disk module) against the worker's
+/// `wasm_host_root`. The path must be canonical, with no symlink, `.` or `..` on it, and
+/// inside the canonicalized root, so a job reaches nothing outside the root, not even
+/// through a link planted inside it. Without a root every host path is refused.
+pub(crate) async fn confine(root: Option<&Path>, path: &str) -> Result<PathBuf> {
+    let refuse =
+        |why: String| ArbiterError::ExecutionError(format!("wasm host path {path}: {why}"));
+    let root = root.ok_or_else(|| {
+        refuse("host paths are disabled on this worker (no [worker] wasm_host_root)".to_string())
+    })?;
+    let root = tokio::fs::canonicalize(root)
+        .await
+        .map_err(|e| refuse(format!("wasm_host_root {}: {e}", root.display())))?;
+    let requested = Path::new(path);
+    let real = tokio::fs::canonicalize(requested)
+        .await
+        .map_err(|e| refuse(e.to_string()))?;
+    if real != requested {
+        return Err(refuse(format!(
+            "not canonical (resolves to {}); symlinks, `.` and `..` are not allowed",
+            real.display()
+        )));
+    }
+    if !real.starts_with(&root) {
+        return Err(refuse(format!("outside wasm_host_root {}", root.display())));
+    }
+    Ok(real)
+}
+
+/// The compiled module for a job, from the cache or freshly compiled. An on-disk module is
+/// confined to the host root, read each run (so a replaced file takes effect) and cached by
+/// its content hash; an uploaded one is fetched from the store in the run's tenant and
+/// checked against its hash.
+async fn load_module(
+    store: &Arc<dyn Store + Send + Sync>,
+    source: &WasmModuleSource,
+    host_root: Option<&Path>,
+    tenant: Option<Uuid>,
+) -> Result<Module> {
+    let (key, bytes) = match source {
+        WasmModuleSource::Path { path } => {
+            let real = confine(host_root, path).await?;
+            let bytes = tokio::fs::read(&real)
+                .await
+                .map_err(|e| ArbiterError::ExecutionError(format!("wasm module {path}: {e}")))?;
+            ((tenant, sha256_hex(&bytes)), Some(bytes))
+        }
+        WasmModuleSource::Stored { sha256 } => {
+            let tenant = tenant.ok_or_else(|| {
+                ArbiterError::ExecutionError(
+                    "stored wasm module but the run has no tenant".to_string(),
+                )
+            })?;
+            ((Some(tenant), sha256.clone()), None)
+        }
+    };
+    if let Some(module) = MODULES.lock().expect("wasm modules lock").get(&key) {
+        return Ok(module.clone());
+    }
+
+    let bytes = match bytes {
+        Some(bytes) => bytes,
+        None => {
+            let sha256 = &key.1;
+            let bytes = store.get_wasm_module(sha256, key.0).await?.ok_or_else(|| {
+                ArbiterError::ExecutionError(format!("wasm module {sha256} not found"))
+            })?;
+            if sha256_hex(&bytes) != *sha256 {
+                return Err(ArbiterError::ExecutionError(format!(
+                    "wasm module {sha256} does not match its hash"
+                )));
+            }
+            bytes
+        }
+    };
+    let module = tokio::task::spawn_blocking(move || Module::new(&ENGINE, bytes))
+        .await
+        .map_err(|e| ArbiterError::ExecutionError(e.to_string()))?
+        .map_err(|e| ArbiterError::ExecutionError(format!("invalid wasm module: {e:#}")))?;
+
+    let mut cache = MODULES.lock().expect("wasm modules lock");
+    if cache.len() >= MAX_CACHED_MODULES {
+        cache.clear();
+    }
+    cache.insert(key, module.clone());
+    Ok(module)
+}
+
+/// `argv[0]` for the guest: the module's file name, or its hash for an uploaded module.
+fn program_name(source: &WasmModuleSource) -> String {
+    match source {
+        WasmModuleSource::Path { path } => std::path::Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.clone()),
+        WasmModuleSource::Stored { sha256 } => format!("{sha256}.wasm"),
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// A guest stdout/stderr that appends into a run's shared capture buffer, which the
+/// [`LogFlusher`] drains into log chunks.
+#[derive(Clone)]
+struct LogPipe(Arc<Mutex<Vec<u8>>>);
+
+/// Largest single guest write we accept; WASI splits bigger writes.
+const PIPE_WRITE_BUDGET: usize = 64 * 1024;
+
+impl OutputStream for LogPipe {
+    fn write(&mut self, bytes: bytes::Bytes) -> StreamResult<()> {
+        self.0
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .extend_from_slice(&bytes);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        Ok(PIPE_WRITE_BUDGET)
+    }
+}
+
+#[wasmtime_wasi::async_trait]
+impl Pollable for LogPipe {
+    async fn ready(&mut self) {}
+}
+
+impl StdoutStream for LogPipe {
+    fn stream(&self) -> Box<dyn OutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result_document_outcome;
+    use arbiter_core::{JobRun, JobRunState, ResultStatus, RetryOn, RunOutcome};
+    use arbiter_store_sqlite::SqliteStore;
+
+    async fn run_wat(
+        wat: &str,
+        fuel: Option<u64>,
+        timeout_sec: Option<u32>,
+        max_memory_mb: Option<u32>,
+    ) -> RunOutcome {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store: Arc<dyn Store + Send + Sync> = Arc::new(
+            SqliteStore::connect(dir.path().join("arbiter.db").to_str().expect("utf-8"))
+                .await
+                .expect("connect"),
+        );
+        let path = dir.path().join("job.wat");
+        std::fs::write(&path, wat).expect("write module");
+        let module = WasmModuleSource::Path {
+            path: path.to_string_lossy().into_owned(),
+        };
+        let job = WasmRun {
+            module: &module,
+            timeout_sec,
+            fuel,
+            max_memory_mb,
+            preopens: &[],
+            args: &[],
+            host_root: Some(dir.path()),
+        };
+        let ctx = RunContext::new(
+            store,
+            None,
+            false,
+            Uuid::new_v4(),
+            std::env::temp_dir(),
+            queued_run(),
+            None,
+            RetryOn::default(),
+            0,
+            0,
+        );
+        let run = execute_wasm(&ctx, job, &HashMap::new())
+            .await
+            .expect("execute_wasm");
+        result_document_outcome(run.doc, run.raw, ctx.retry_on())
+    }
+
+    fn queued_run() -> JobRun {
+        JobRun {
+            id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+            scheduled_for: chrono::Utc::now(),
+            state: JobRunState::Running,
+            worker_id: None,
+            exit_code: None,
+            attempt: 1,
+            started_at: None,
+            finished_at: None,
+            snapshot: None,
+            result_status: None,
+            result: None,
+            result_media_type: None,
+            error: None,
+            error_media_type: None,
+            params: HashMap::new(),
+            progress: None,
+            last_heartbeat: None,
+            code_commit: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn host_paths_are_confined_to_the_root() {
+        let base = tempfile::tempdir().expect("tempdir");
+        let base = std::fs::canonicalize(base.path()).expect("canonical tempdir");
+        let root = base.join("root");
+        std::fs::create_dir_all(root.join("data")).expect("mkdir");
+        std::fs::create_dir(base.join("outside")).expect("mkdir");
+        std::os::unix::fs::symlink(base.join("outside"), root.join("link")).expect("symlink");
+        let at = |p: &Path| p.to_string_lossy().into_owned();
+
+        let data = root.join("data");
+        assert_eq!(
+            confine(Some(&root), &at(&data)).await.expect("inside"),
+            data
+        );
+        for refused in [
+            at(&base.join("outside")),
+            at(&root.join("link")),
+            format!("{}/data/../../outside", root.display()),
+            "/".to_string(),
+        ] {
+            assert!(
+                confine(Some(&root), &refused).await.is_err(),
+                "{refused} is refused"
+            );
+        }
+        assert!(
+            confine(None, &at(&data)).await.is_err(),
+            "no root, no host paths"
+        );
+    }
+
+    const SPIN: &str = r#"(module (func (export "_start") (loop $l (br $l))))"#;
+
+    #[tokio::test]
+    async fn wasm_limits_and_exit_codes_map_to_outcomes() {
+        let exit = run_wat(
+            r#"(module
+                 (import "wasi_snapshot_preview1" "proc_exit" (func $exit (param i32)))
+                 (memory (export "memory") 1)
+                 (func (export "_start") (call $exit (i32.const 75))))"#,
+            None,
+            None,
+            None,
+        )
+        .await;
+        assert_eq!(exit.exit_code, Some(75));
+        assert!(
+            matches!(exit.status, Some(ResultStatus::Retryable)),
+            "EX_TEMPFAIL retries"
+        );
+
+        let fuel = run_wat(SPIN, Some(100_000), None, None).await;
+        assert!(matches!(fuel.status, Some(ResultStatus::Failed)));
+        assert!(
+            fuel.stderr.as_deref().unwrap_or("").contains("out of fuel"),
+            "{fuel:?}"
+        );
+
+        let started = Instant::now();
+        let slow = run_wat(SPIN, Some(u64::MAX), Some(1), None).await;
+        assert!(matches!(slow.status, Some(ResultStatus::Failed)));
+        assert!(
+            slow.stderr
+                .as_deref()
+                .unwrap_or("")
+                .contains("exceeded timeout"),
+            "{slow:?}"
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let greedy = run_wat(
+            r#"(module
+                 (memory (export "memory") 1)
+                 (func (export "_start") (drop (memory.grow (i32.const 64)))))"#,
+            None,
+            None,
+            Some(1),
+        )
+        .await;
+        assert!(matches!(greedy.status, Some(ResultStatus::Failed)));
+        assert!(
+            greedy.stderr.as_deref().unwrap_or("").contains("trapped"),
+            "{greedy:?}"
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Working directories the worker manages for subprocess runs, under
+//! `<data_dir>/workspaces/`: `runs/<run_id>-<attempt>` for an attempt of its own (the
+//! default), or `jobs/<job_id>` shared by every run of a job that opts in with
+//! [`WorkspaceMode::Job`]. An attempt's workspace is removed once it is over, unless
+//! `worker.workspace_keep_run_secs` keeps it around for inspection; a job's is kept until it
+//! has gone unused for `worker.workspace_job_idle_secs`. Both are reclaimed by a periodic
+//! [`sweep`] (part of the worker's [`maintenance`](crate::maintenance)), which also
+//! measures what the data dir holds for the worker's heartbeat.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime};
+
+use arbiter_core::{ArbiterError, JobRun, Result, WorkerDiskUsage, WorkspaceMode};
+use chrono::Utc;
+
+/// Env var carrying a shell run's workspace.
+pub const WORKSPACE_ENV: &str = "ARBITER_WORKSPACE";
+
+/// Workspaces in use by a run on this worker, with how many runs hold each. The sweep
+/// leaves these alone.
+static ACTIVE: LazyLock<Mutex<HashMap<PathBuf, usize>>> = LazyLock::new(Mutex::default);
+
+/// A run's hold on its workspace. Dropping it releases the directory: an attempt's own
+/// workspace is removed right away when nothing keeps it, anything else is stamped as
+/// last used now so its retention counts from the end of the run.
+pub(crate) struct Workspace {
+    path: PathBuf,
+    remove_on_release: bool,
+}
+
+impl Workspace {
+    /// Create (or reuse) the workspace `mode` gives `run` and hold it until dropped.
+    pub(crate) fn acquire(
+        data_dir: &Path,
+        run: &JobRun,
+        mode: WorkspaceMode,
+        keep_run_secs: u64,
+    ) -> Result<Self> {
+        let root = data_dir.join("workspaces");
+        let path = match mode {
+            WorkspaceMode::Run => root
+                .join("runs")
+                .join(format!("{}-{}", run.id, run.attempt)),
+            WorkspaceMode::Job => root.join("jobs").join(run.job_id.to_string()),
+        };
+        // Registered before it is created, so a sweep never takes it from under us.
+        let mut active = ACTIVE.lock().expect("workspaces lock");
+        std::fs::create_dir_all(&path).map_err(|e| {
+            ArbiterError::ExecutionError(format!("cannot create workspace {}: {e}", path.display()))
+        })?;
+        *active.entry(path.clone()).or_default() += 1;
+        Ok(Workspace {
+            path,
+            remove_on_release: mode == WorkspaceMode::Run && keep_run_secs == 0,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let mut active = ACTIVE.lock().expect("workspaces lock");
+        let holders = active.get_mut(&self.path).map(|n| {
+            *n -= 1;
+            *n
+        });
+        if holders != Some(0) {
+            return;
+        }
+        active.remove(&self.path);
+        if self.remove_on_release {
+            if let Err(e) = std::fs::remove_dir_all(&self.path) {
+                tracing::warn!("workspace {}: not removed: {e}", self.path.display());
+            }
+        } else if let Err(e) = touch(&self.path) {
+            tracing::warn!("workspace {}: not stamped: {e}", self.path.display());
+        }
+    }
+}
+
+/// Mark a directory as used now (its retention is counted from its mtime).
+fn touch(dir: &Path) -> std::io::Result<()> {
+    File::open(dir)?.set_modified(SystemTime::now())
+}
+
+/// Remove the workspaces no run holds that are past retention (`keep_run_secs` after an
+/// attempt's last use, `job_idle_secs` after a job's, `0` = never for jobs) and that
+/// `spare` does not claim for an attempt still running elsewhere, and measure what is left
+/// along with the code cache. Blocking; call it off the async runtime.
+pub(crate) fn sweep(
+    data_dir: &Path,
+    keep_run_secs: u64,
+    job_idle_secs: u64,
+    spare: &dyn Fn(&Path) -> bool,
+) -> WorkerDiskUsage {
+    let root = data_dir.join("workspaces");
+    let now = SystemTime::now();
+    let expired = |dir: &Path, keep_secs: u64| {
+        let idle = std::fs::metadata(dir)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| now.duration_since(t).ok())
+            .unwrap_or(Duration::MAX);
+        idle >= Duration::from_secs(keep_secs)
+    };
+
+    let runs = sweep_dir(&root.join("runs"), &|dir| {
+        !spare(dir) && expired(dir, keep_run_secs)
+    });
+    let jobs = sweep_dir(&root.join("jobs"), &|dir| {
+        job_idle_secs > 0 && expired(dir, job_idle_secs)
+    });
+    WorkerDiskUsage {
+        run_workspaces: runs.count,
+        run_workspace_bytes: runs.bytes,
+        job_workspaces: jobs.count,
+        job_workspace_bytes: jobs.bytes,
+        code_cache_bytes: dir_size(&data_dir.join("code")),
+        swept_entries: runs.removed + jobs.removed,
+        swept_bytes: runs.removed_bytes + jobs.removed_bytes,
+        measured_at: Utc::now(),
+    }
+}
+
+/// What [`sweep_dir`] left and removed.
+#[derive(Default)]
+struct Swept {
+    count: u32,
+    bytes: u64,
+    removed: u32,
+    removed_bytes: u64,
+}
+
+/// Remove the entries of `parent` that are not in use and `expired`.
+fn sweep_dir(parent: &Path, expired: &dyn Fn(&Path) -> bool) -> Swept {
+    let mut swept = Swept::default();
+    let Ok(entries) = std::fs::read_dir(parent) else {
+        return swept;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let size = dir_size(&path);
+        let removed = {
+            // Held across the removal, so a run cannot acquire the directory meanwhile.
+            let active = ACTIVE.lock().expect("workspaces lock");
+            !active.contains_key(&path) && expired(&path) && {
+                match std::fs::remove_dir_all(&path) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        tracing::warn!("workspace {}: not removed: {e}", path.display());
+                        false
+                    }
+                }
+            }
+        };
+        if removed {
+            tracing::info!("removed workspace {}", path.display());
+            swept.removed += 1;
+            swept.removed_bytes += size;
+        } else {
+            swept.count += 1;
+            swept.bytes += size;
+        }
+    }
+    swept
+}
+
+/// Bytes of the files under `path` (symlinks are not followed).
+pub(crate) fn dir_size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| dir_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arbiter_core::JobRunState;
+    use uuid::Uuid;
+
+    fn run() -> JobRun {
+        JobRun {
+            id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+            scheduled_for: Utc::now(),
+            state: JobRunState::Running,
+            worker_id: None,
+            exit_code: None,
+            attempt: 1,
+            started_at: None,
+            finished_at: None,
+            snapshot: None,
+            result_status: None,
+            result: None,
+            result_media_type: None,
+            error: None,
+            error_media_type: None,
+            params: HashMap::new(),
+            progress: None,
+            last_heartbeat: None,
+            code_commit: None,
+        }
+    }
+
+    #[test]
+    fn workspaces_are_released_and_swept_by_mode() {
+        let data = tempfile::tempdir().unwrap();
+        let run = run();
+
+        // An attempt's own workspace is gone as soon as it is released.
+        let ws = Workspace::acquire(data.path(), &run, WorkspaceMode::Run, 0).unwrap();
+        let path = ws.path().to_path_buf();
+        std::fs::write(path.join("out.txt"), b"12345").unwrap();
+        drop(ws);
+        assert!(!path.exists());
+
+        // Kept for inspection, it survives until the sweep finds it past retention.
+        let ws = Workspace::acquire(data.path(), &run, WorkspaceMode::Run, 3600).unwrap();
+        let kept = ws.path().to_path_buf();
+        std::fs::write(kept.join("out.txt"), b"12345").unwrap();
+        drop(ws);
+        let usage = sweep(data.path(), 3600, 0, &|_| false);
+        assert_eq!((usage.run_workspaces, usage.run_workspace_bytes), (1, 5));
+        let usage = sweep(data.path(), 0, 0, &|_| false);
+        assert_eq!(usage.run_workspaces, 0);
+        assert!(!kept.exists());
+
+        // A job's workspace is shared by its runs, never swept while held, and kept forever
+        // with a `0` idle limit.
+        let first = Workspace::acquire(data.path(), &run, WorkspaceMode::Job, 0).unwrap();
+        let second = Workspace::acquire(data.path(), &run, WorkspaceMode::Job, 0).unwrap();
+        assert_eq!(first.path(), second.path());
+        std::fs::write(first.path().join("state"), b"abc").unwrap();
+        let shared = first.path().to_path_buf();
+        drop(first);
+        assert_eq!(sweep(data.path(), 0, 1, &|_| false).job_workspaces, 1);
+        drop(second);
+        let usage = sweep(data.path(), 0, 0, &|_| false);
+        assert_eq!((usage.job_workspaces, usage.job_workspace_bytes), (1, 3));
+
+        // Idle past the limit, it goes.
+        File::open(&shared)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(120))
+            .unwrap();
+        assert_eq!(sweep(data.path(), 0, 60, &|_| false).job_workspaces, 0);
+        assert!(!shared.exists());
+    }
+}
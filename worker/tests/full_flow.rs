@@ -7,12 +7,19 @@ use std::sync::atomic::AtomicU32;
 use std::time::Duration as StdDuration;
 
 use arbiter_core::{
-    BackoffStrategy, DEFAULT_TENANT_ID, JobRun, JobRunState, MisfirePolicy, RetryConfig,
-    RunnerConfig, SecretResolver, SecretStore, Store, WorkerConfig,
+    ArtifactStore, BackoffStrategy, BundleFormat, CodeSource, DEFAULT_TENANT_ID, DbEngine,
+    ExecutableConfigSnapshotMeta, HttpSigningConfig, JobRun, JobRunState, LogLevel, LogStream,
+    MisfirePolicy, ResultStatus, RetryConfig, RetryOn, RunOutcome, RunParam, RunProgress,
+    RunnerConfig, RuntimeDefaults, RuntimeSettings, RuntimeTransport, SecretActor,
+    SecretAuditAction, SecretAuditOutcome, SecretDelivery, SecretResolver, SecretStore,
+    SigningAlgorithm, Store, SystemClock, WasmModuleSource, WorkerConfig, WorkspaceMode,
 };
 use arbiter_secrets::{NodeKeyring, SecretManager};
 use arbiter_store_sqlite::SqliteStore;
-use arbiter_worker::worker_tick;
+use arbiter_worker::{
+    Artifacts, RunContext, RunLimits, Runner, RunnerPayload, RunnerRegistry, discover_plugins,
+    run_worker_loop, worker_tick,
+};
 use chrono::{Duration, Utc};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -32,6 +39,10 @@ async fn set_job_env(store: &Arc<dyn Store + Send + Sync>, job_id: Uuid, key: &s
     store.set_job_env(job_id, env).await.expect("set_job_env");
 }
 
+fn builtin_runners() -> Arc<RunnerRegistry> {
+    Arc::new(RunnerRegistry::with_builtins())
+}
+
 fn worker_cfg() -> WorkerConfig {
     WorkerConfig {
         worker_id: Uuid::new_v4(),
@@ -43,6 +54,7 @@ fn worker_cfg() -> WorkerConfig {
         dead_after_secs: 30,
         restart_count: 0,
         version: "test".to_string(),
+        data_dir: std::env::temp_dir().join("arbiter-worker-tests"),
     }
 }
 
@@ -53,13 +65,13 @@ async fn await_terminal(store: &Arc<dyn Store + Send + Sync>, job_id: Uuid) -> J
             .list_recent_runs(None, None, None, Some(job_id), None, None)
             .await
             .expect("list_recent_runs");
-        if let Some(run) = runs.first() {
-            if matches!(
+        if let Some(run) = runs.first()
+            && matches!(
                 run.state,
                 JobRunState::Succeeded | JobRunState::Failed | JobRunState::Cancelled
-            ) {
-                return run.clone();
-            }
+            )
+        {
+            return run.clone();
         }
         tokio::time::sleep(StdDuration::from_millis(25)).await;
     }
@@ -82,6 +94,9 @@ async fn shell_runner_full_flow() {
             RunnerConfig::Shell {
                 command: "echo hello-from-shell".to_string(),
                 working_dir: None,
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
             },
             1,
             MisfirePolicy::RunImmediately,
@@ -96,7 +111,7 @@ async fn shell_runner_full_flow() {
         .expect("materialize run");
 
     let running = Arc::new(AtomicU32::new(0));
-    worker_tick(store.clone(), &cfg, &running, &None, 0)
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &builtin_runners(), RunLimits::default())
         .await
         .expect("worker_tick");
 
@@ -136,6 +151,9 @@ async fn shell_runner_retries_on_tempfail() {
             RunnerConfig::Shell {
                 command: "exit 75".to_string(),
                 working_dir: None,
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
             },
             1,
             MisfirePolicy::RunImmediately,
@@ -144,6 +162,7 @@ async fn shell_runner_retries_on_tempfail() {
                 backoff_strategy: BackoffStrategy::Fixed,
                 backoff_base_secs: 0,
                 backoff_cap_secs: 0,
+                ..Default::default()
             },
         )
         .await
@@ -158,7 +177,7 @@ async fn shell_runner_retries_on_tempfail() {
     // Tick repeatedly: claim -> retryable -> requeue (attempt 2) -> claim -> fail.
     let mut terminal = None;
     for _ in 0..40 {
-        worker_tick(store.clone(), &cfg, &running, &None, 0)
+        worker_tick(store.clone(), &cfg, &running, &None, &None, &builtin_runners(), RunLimits::default())
             .await
             .expect("worker_tick");
         tokio::time::sleep(StdDuration::from_millis(25)).await;
@@ -166,11 +185,11 @@ async fn shell_runner_retries_on_tempfail() {
             .list_recent_runs(None, None, None, Some(job.id), None, None)
             .await
             .expect("list_recent_runs");
-        if let Some(r) = runs.first() {
-            if matches!(r.state, JobRunState::Failed | JobRunState::Succeeded) {
-                terminal = Some(r.clone());
-                break;
-            }
+        if let Some(r) = runs.first()
+            && matches!(r.state, JobRunState::Failed | JobRunState::Succeeded)
+        {
+            terminal = Some(r.clone());
+            break;
         }
     }
 
@@ -183,6 +202,89 @@ async fn shell_runner_retries_on_tempfail() {
     assert_eq!(run.attempt, 2, "should have run twice (attempt 2)");
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn http_runner_retries_configured_status() {
+    // 409 is not retryable by default; the job lists it, so the run is requeued once
+    // (zero backoff, Retry-After capped to 0) and then fails at attempt 2.
+    let server = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::method("POST"))
+        .and(wiremock::matchers::path("/lock"))
+        .respond_with(
+            wiremock::ResponseTemplate::new(409)
+                .insert_header("Retry-After", "120")
+                .set_body_string("locked"),
+        )
+        .mount(&server)
+        .await;
+
+    let store = fresh_store().await;
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "http-retry-job",
+            None,
+            RunnerConfig::Http {
+                method: "POST".to_string(),
+                url: format!("{}/lock", server.uri()),
+                headers: None,
+                body: None,
+                timeout_sec: Some(5),
+                tls: None,
+                signing: None,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig {
+                max_attempts: 2,
+                backoff_strategy: BackoffStrategy::Fixed,
+                backoff_base_secs: 0,
+                backoff_cap_secs: 0,
+                retry_on: RetryOn {
+                    http_statuses: Some(vec![409]),
+                    ..Default::default()
+                },
+            },
+        )
+        .await
+        .expect("create_job");
+    assert_eq!(job.retry_on.http_statuses, Some(vec![409]));
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(5))
+        .await
+        .expect("materialize run");
+
+    let running = Arc::new(AtomicU32::new(0));
+    let mut terminal = None;
+    for _ in 0..40 {
+        worker_tick(store.clone(), &cfg, &running, &None, &None, &builtin_runners(), RunLimits::default())
+            .await
+            .expect("worker_tick");
+        tokio::time::sleep(StdDuration::from_millis(25)).await;
+        let runs = store
+            .list_recent_runs(None, None, None, Some(job.id), None, None)
+            .await
+            .expect("list_recent_runs");
+        if let Some(r) = runs.first()
+            && matches!(r.state, JobRunState::Failed | JobRunState::Succeeded)
+        {
+            terminal = Some(r.clone());
+            break;
+        }
+    }
+
+    let run = terminal.expect("run should reach a terminal state");
+    assert!(matches!(run.state, JobRunState::Failed), "got {:?}", run.state);
+    assert_eq!(run.attempt, 2, "409 should have been retried once");
+    assert_eq!(run.error.as_deref(), Some("locked"));
+}
+
 /// Skip a test gracefully if an interpreter is not installed in the environment.
 fn has_binary(name: &str) -> bool {
     std::process::Command::new(name)
@@ -231,6 +333,12 @@ async fn python_runner_full_flow() {
                 module: "mytask".to_string(),
                 class_name: "MyTask".to_string(),
                 timeout_sec: Some(30),
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
             },
             1,
             MisfirePolicy::RunImmediately,
@@ -247,7 +355,7 @@ async fn python_runner_full_flow() {
         .expect("materialize run");
 
     let running = Arc::new(AtomicU32::new(0));
-    worker_tick(store.clone(), &cfg, &running, &None, 0)
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &builtin_runners(), RunLimits::default())
         .await
         .expect("worker_tick");
 
@@ -289,6 +397,12 @@ async fn python_runner_structured_output() {
                 module: "mytask".to_string(),
                 class_name: "run".to_string(),
                 timeout_sec: Some(30),
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
             },
             1,
             MisfirePolicy::RunImmediately,
@@ -304,7 +418,7 @@ async fn python_runner_structured_output() {
         .expect("materialize run");
 
     let running = Arc::new(AtomicU32::new(0));
-    worker_tick(store.clone(), &cfg, &running, &None, 0)
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &builtin_runners(), RunLimits::default())
         .await
         .expect("worker_tick");
 
@@ -342,6 +456,12 @@ async fn node_runner_failure_is_structured() {
                 module: "mytask".to_string(),
                 function_name: "run".to_string(),
                 timeout_sec: Some(30),
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
             },
             1,
             MisfirePolicy::RunImmediately,
@@ -357,7 +477,7 @@ async fn node_runner_failure_is_structured() {
         .expect("materialize run");
 
     let running = Arc::new(AtomicU32::new(0));
-    worker_tick(store.clone(), &cfg, &running, &None, 0)
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &builtin_runners(), RunLimits::default())
         .await
         .expect("worker_tick");
 
@@ -402,6 +522,12 @@ async fn node_runner_full_flow() {
                 module: "mytask".to_string(),
                 function_name: "run".to_string(),
                 timeout_sec: Some(30),
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
             },
             1,
             MisfirePolicy::RunImmediately,
@@ -418,7 +544,7 @@ async fn node_runner_full_flow() {
         .expect("materialize run");
 
     let running = Arc::new(AtomicU32::new(0));
-    worker_tick(store.clone(), &cfg, &running, &None, 0)
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &builtin_runners(), RunLimits::default())
         .await
         .expect("worker_tick");
 
@@ -432,14 +558,87 @@ async fn node_runner_full_flow() {
     assert_eq!(run.result.as_deref(), Some("hello-from-node"));
 }
 
+/// Queue a run-now run of `job_id`, run one worker tick, and wait for that run to finish.
+async fn fire_and_wait(
+    store: &Arc<dyn Store + Send + Sync>,
+    cfg: &WorkerConfig,
+    runners: &Arc<RunnerRegistry>,
+    job_id: Uuid,
+    params: HashMap<String, RunParam>,
+) -> JobRun {
+    fire_and_wait_with(store, cfg, runners, &None, job_id, params).await
+}
+
+/// [`fire_and_wait`] with a secret resolver. Ticks again while waiting, so a requeued
+/// retry is picked up too.
+async fn fire_and_wait_with(
+    store: &Arc<dyn Store + Send + Sync>,
+    cfg: &WorkerConfig,
+    runners: &Arc<RunnerRegistry>,
+    secrets: &arbiter_worker::Secrets,
+    job_id: Uuid,
+    params: HashMap<String, RunParam>,
+) -> JobRun {
+    let run = store
+        .create_adhoc_run(job_id, params)
+        .await
+        .expect("create_adhoc_run");
+    let running = Arc::new(AtomicU32::new(0));
+    for _ in 0..400 {
+        worker_tick(store.clone(), cfg, &running, secrets, &None, runners, RunLimits::default())
+            .await
+            .expect("worker_tick");
+        let got = store
+            .get_run(run.id, None)
+            .await
+            .expect("get_run")
+            .expect("run exists");
+        if matches!(got.state, JobRunState::Succeeded | JobRunState::Failed) {
+            return got;
+        }
+        tokio::time::sleep(StdDuration::from_millis(25)).await;
+    }
+    panic!("run never reached a terminal state");
+}
+
+async fn run_stdout(store: &Arc<dyn Store + Send + Sync>, run: &JobRun) -> String {
+    store
+        .read_run_log(run.id, run.attempt, None, 1000)
+        .await
+        .expect("read_run_log")
+        .iter()
+        .filter(|c| c.stream == LogStream::Stdout)
+        .map(|c| c.content.as_str())
+        .collect()
+}
+
+fn json_result(run: &JobRun) -> serde_json::Value {
+    assert!(
+        matches!(run.state, JobRunState::Succeeded),
+        "expected Succeeded, got {:?} ({:?})",
+        run.state,
+        run.error
+    );
+    serde_json::from_str(run.result.as_deref().expect("result")).expect("json result")
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-async fn http_runner_full_flow() {
-    let server = wiremock::MockServer::start().await;
-    wiremock::Mock::given(wiremock::matchers::method("GET"))
-        .and(wiremock::matchers::path("/ping"))
-        .respond_with(wiremock::ResponseTemplate::new(200).set_body_string("pong"))
-        .mount(&server)
-        .await;
+async fn python_runtime_stays_resident_over_socket() {
+    if !has_binary("python3") {
+        eprintln!("skipping python_runtime_stays_resident_over_socket: python3 not found");
+        return;
+    }
+
+    // prepare() runs once per process; run() reports which process served it and what
+    // prepare left in ctx.state. A `crash` param kills the interpreter mid-task.
+    let dir = write_module(
+        "resident_task.py",
+        "import os\n\
+         PREPARED = []\n\
+         def prepare(ctx):\n    PREPARED.append(1)\n    ctx.state.token = 'warm'\n    print('preparing')\n\
+         def run(ctx):\n    if ctx.params.get('crash'):\n        os._exit(3)\n    print('firing', ctx.params.get('n'))\n    \
+         return {'pid': os.getpid(), 'prepared': len(PREPARED), 'state': ctx.state.token}\n",
+    );
 
     let store = fresh_store().await;
     let cfg = worker_cfg();
@@ -447,17 +646,21 @@ async fn http_runner_full_flow() {
         .insert_worker(cfg.worker_id, "test", "test", "test", 0)
         .await
         .expect("insert_worker");
-
     let job = store
-        .create_job(DEFAULT_TENANT_ID, 
-            "http-job",
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "py-resident",
             None,
-            RunnerConfig::Http {
-                method: "GET".to_string(),
-                url: format!("{}/ping", server.uri()),
-                headers: None,
-                body: None,
-                timeout_sec: Some(5),
+            RunnerConfig::Python {
+                module: "resident_task".to_string(),
+                class_name: "run".to_string(),
+                timeout_sec: Some(30),
+                transport: RuntimeTransport::Socket,
+                max_runs: Some(2),
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
             },
             1,
             MisfirePolicy::RunImmediately,
@@ -465,36 +668,240 @@ async fn http_runner_full_flow() {
         )
         .await
         .expect("create_job");
+    set_job_env(&store, job.id, "PYTHONPATH", dir.to_str().unwrap()).await;
     store.enable_job(job.id).await.expect("enable_job");
+
+    // One registry across fires, as on a node: it holds the resident processes.
+    let runners = builtin_runners();
+    let n = |n: i64| HashMap::from([("n".to_string(), RunParam::Int(n))]);
+
+    let first = fire_and_wait(&store, &cfg, &runners, job.id, n(1)).await;
+    let first_out = json_result(&first);
+    assert_eq!(first_out["prepared"], 1);
+    assert_eq!(first_out["state"], "warm");
+    assert_eq!(run_stdout(&store, &first).await, "preparing\nfiring 1\n");
+
+    // Warm: same process, prepare not run again, state kept.
+    let second = fire_and_wait(&store, &cfg, &runners, job.id, n(2)).await;
+    let second_out = json_result(&second);
+    assert_eq!(second_out["pid"], first_out["pid"]);
+    assert_eq!(second_out["prepared"], 1);
+    assert_eq!(run_stdout(&store, &second).await, "firing 2\n");
+
+    // max_runs = 2: the third fire gets a fresh process, prepared anew.
+    let third = fire_and_wait(&store, &cfg, &runners, job.id, n(3)).await;
+    let third_out = json_result(&third);
+    assert_ne!(third_out["pid"], first_out["pid"]);
+    assert_eq!(third_out["prepared"], 1);
+
+    // A crash fails the run with the exit code, and the next fire respawns.
+    let crash = HashMap::from([("crash".to_string(), RunParam::Bool(true))]);
+    let crashed = fire_and_wait(&store, &cfg, &runners, job.id, crash).await;
+    assert!(matches!(crashed.state, JobRunState::Failed));
+    assert_eq!(crashed.exit_code, Some(3));
+    let after = fire_and_wait(&store, &cfg, &runners, job.id, n(5)).await;
+    let after_out = json_result(&after);
+    assert_ne!(after_out["pid"], third_out["pid"]);
+    assert_eq!(run_stdout(&store, &after).await, "preparing\nfiring 5\n");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn node_runtime_stays_resident_over_socket() {
+    if !has_binary("node") {
+        eprintln!("skipping node_runtime_stays_resident_over_socket: node not found");
+        return;
+    }
+
+    let dir = write_module(
+        "resident_task.js",
+        "let prepared = 0;\n\
+         exports.prepare = async (ctx) => { prepared += 1; ctx.state.token = 'warm'; };\n\
+         exports.run = async (ctx) => {\n  \
+           if (ctx.params.fail) throw new Error('bad input');\n  \
+           console.log('firing', ctx.params.n);\n  \
+           return { pid: process.pid, prepared, state: ctx.state.token };\n\
+         };\n",
+    );
+
+    let store = fresh_store().await;
+    let cfg = worker_cfg();
     store
-        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(5))
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
         .await
-        .expect("materialize run");
-
-    let running = Arc::new(AtomicU32::new(0));
-    worker_tick(store.clone(), &cfg, &running, &None, 0)
+        .expect("insert_worker");
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "node-resident",
+            None,
+            RunnerConfig::Node {
+                module: "resident_task".to_string(),
+                function_name: "run".to_string(),
+                timeout_sec: Some(30),
+                transport: RuntimeTransport::Socket,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
         .await
-        .expect("worker_tick");
+        .expect("create_job");
+    set_job_env(&store, job.id, "NODE_PATH", dir.to_str().unwrap()).await;
+    store.enable_job(job.id).await.expect("enable_job");
 
-    let run = await_terminal(&store, job.id).await;
-    assert!(
-        matches!(run.state, JobRunState::Succeeded),
-        "expected Succeeded, got {:?}",
-        run.state
+    let runners = builtin_runners();
+    let n = |n: i64| HashMap::from([("n".to_string(), RunParam::Int(n))]);
+
+    let first = fire_and_wait(&store, &cfg, &runners, job.id, n(1)).await;
+    let first_out = json_result(&first);
+    assert_eq!(run_stdout(&store, &first).await, "firing 1\n");
+
+    // A thrown error fails that run only; the process stays warm.
+    let fail = HashMap::from([("fail".to_string(), RunParam::Bool(true))]);
+    let failed = fire_and_wait(&store, &cfg, &runners, job.id, fail).await;
+    assert!(matches!(failed.state, JobRunState::Failed));
+    assert!(failed.error.unwrap_or_default().contains("bad input"));
+
+    let third = fire_and_wait(&store, &cfg, &runners, job.id, n(3)).await;
+    let third_out = json_result(&third);
+    assert_eq!(third_out["pid"], first_out["pid"]);
+    assert_eq!(third_out["prepared"], 1);
+    assert_eq!(third_out["state"], "warm");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn runtimes_report_records_and_progress() {
+    let python = has_binary("python3");
+    let node = has_binary("node");
+    if !python && !node {
+        eprintln!("skipping runtimes_report_records_and_progress: no python3 or node");
+        return;
+    }
+
+    let py_dir = write_module(
+        "events_task.py",
+        "def run(ctx):\n    ctx.log.info('starting')\n    ctx.progress(0.5, 'half way')\n    \
+         ctx.heartbeat()\n    ctx.log.warning('almost')\n    return 'done'\n",
     );
-    assert_eq!(run.result.as_deref(), Some("pong"));
+    let js_dir = write_module(
+        "events_task.js",
+        "exports.run = async (ctx) => {\n  ctx.log.info('starting');\n  \
+           ctx.progress(0.5, 'half way');\n  ctx.heartbeat();\n  ctx.log.warning('almost');\n  \
+           return 'done';\n};\n",
+    );
+
+    let store = fresh_store().await;
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+    let runners = builtin_runners();
+
+    for transport in [RuntimeTransport::File, RuntimeTransport::Socket] {
+        let mut jobs = Vec::new();
+        if python {
+            let runner = RunnerConfig::Python {
+                module: "events_task".to_string(),
+                class_name: "run".to_string(),
+                timeout_sec: Some(30),
+                transport,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            };
+            jobs.push((runner, "PYTHONPATH", &py_dir));
+        }
+        if node {
+            let runner = RunnerConfig::Node {
+                module: "events_task".to_string(),
+                function_name: "run".to_string(),
+                timeout_sec: Some(30),
+                transport,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            };
+            jobs.push((runner, "NODE_PATH", &js_dir));
+        }
+        for (runner, path_var, dir) in jobs {
+            let job = store
+                .create_job(
+                    DEFAULT_TENANT_ID,
+                    "events",
+                    None,
+                    runner,
+                    1,
+                    MisfirePolicy::RunImmediately,
+                    RetryConfig::default(),
+                )
+                .await
+                .expect("create_job");
+            set_job_env(&store, job.id, path_var, dir.to_str().unwrap()).await;
+            store.enable_job(job.id).await.expect("enable_job");
+
+            let run = fire_and_wait(&store, &cfg, &runners, job.id, HashMap::new()).await;
+            assert_eq!(run.result.as_deref(), Some("done"), "{path_var} over {transport}");
+            assert_eq!(
+                run.progress,
+                Some(RunProgress { value: 0.5, message: Some("half way".to_string()) }),
+                "{path_var} over {transport}"
+            );
+            assert!(run.last_heartbeat.is_some(), "{path_var} over {transport}");
+
+            // ctx.log records are their own chunks, tagged with their level.
+            let records: Vec<(Option<LogLevel>, String)> = store
+                .read_run_log(run.id, run.attempt, None, 1000)
+                .await
+                .expect("read_run_log")
+                .into_iter()
+                .filter(|c| c.level.is_some())
+                .map(|c| (c.level, c.content))
+                .collect();
+            assert_eq!(
+                records,
+                vec![
+                    (Some(LogLevel::Info), "starting\n".to_string()),
+                    (Some(LogLevel::Warning), "almost\n".to_string()),
+                ],
+                "{path_var} over {transport}"
+            );
+        }
+    }
 }
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-async fn python_runner_resolves_secret_env() {
-    if !has_binary("python3") {
-        eprintln!("skipping python_runner_resolves_secret_env: python3 not found");
+async fn runtimes_expose_run_context_and_secrets() {
+    let python = has_binary("python3");
+    let node = has_binary("node");
+    if !python && !node {
+        eprintln!("skipping runtimes_expose_run_context_and_secrets: no python3 or node");
         return;
     }
 
-    let dir = write_module(
-        "mytask.py",
-        "import os\n\ndef run(ctx):\n    return os.environ.get('MY_SECRET', '')\n",
+    let py_dir = write_module(
+        "ctx_task.py",
+        "def run(ctx):\n    if ctx.params.get('busy'):\n        ctx.retryable('upstream busy')\n    \
+         try:\n        ctx.secret('undeclared')\n        hidden = False\n    except LookupError:\n        \
+         hidden = True\n    return {'secret': ctx.secret('apikey'), 'hidden': hidden, \
+         'attempt': ctx.attempt, 'jobId': ctx.job_id, 'runId': ctx.run_id, \
+         'scheduled': ctx.scheduled_for is not None, 'n': ctx.params.get('n')}\n",
+    );
+    let js_dir = write_module(
+        "ctx_task.js",
+        "exports.run = async (ctx) => {\n  if (ctx.params.busy) ctx.retryable('upstream busy');\n  \
+           let hidden = false;\n  try { ctx.secret('undeclared'); } catch (e) { hidden = true; }\n  \
+           return { secret: ctx.secret('apikey'), hidden, attempt: ctx.attempt, jobId: ctx.jobId, \
+           runId: ctx.runId, scheduled: ctx.scheduledFor !== null, n: ctx.params.n };\n};\n",
     );
 
     let path = std::env::temp_dir().join(format!("arbiter_flow_{}.db", Uuid::new_v4().simple()));
@@ -505,7 +912,6 @@ async fn python_runner_resolves_secret_env() {
     );
     let store: Arc<dyn Store + Send + Sync> = sqlite.clone();
     let secret_store: Arc<dyn SecretStore + Send + Sync> = sqlite.clone();
-
     let mgr = SecretManager::load_or_bootstrap(
         secret_store,
         Uuid::new_v4(),
@@ -516,6 +922,9 @@ async fn python_runner_resolves_secret_env() {
     mgr.set_secret(DEFAULT_TENANT_ID, "apikey", b"s3cr3t")
         .await
         .expect("set_secret");
+    mgr.set_secret(DEFAULT_TENANT_ID, "undeclared", b"nope")
+        .await
+        .expect("set_secret");
     let resolver: arbiter_worker::Secrets =
         Some(Arc::new(mgr) as Arc<dyn SecretResolver + Send + Sync>);
 
@@ -524,14 +933,124 @@ async fn python_runner_resolves_secret_env() {
         .insert_worker(cfg.worker_id, "test", "test", "test", 0)
         .await
         .expect("insert_worker");
+    let runners = builtin_runners();
+    let declared = vec!["apikey".to_string()];
+
+    for transport in [RuntimeTransport::File, RuntimeTransport::Socket] {
+        let mut jobs = Vec::new();
+        if python {
+            let runner = RunnerConfig::Python {
+                module: "ctx_task".to_string(),
+                class_name: "run".to_string(),
+                timeout_sec: Some(30),
+                transport,
+                max_runs: None,
+                secrets: declared.clone(),
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            };
+            jobs.push((runner, "PYTHONPATH", &py_dir));
+        }
+        if node {
+            let runner = RunnerConfig::Node {
+                module: "ctx_task".to_string(),
+                function_name: "run".to_string(),
+                timeout_sec: Some(30),
+                transport,
+                max_runs: None,
+                secrets: declared.clone(),
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            };
+            jobs.push((runner, "NODE_PATH", &js_dir));
+        }
+        for (runner, path_var, dir) in jobs {
+            let job = store
+                .create_job(
+                    DEFAULT_TENANT_ID,
+                    "ctx",
+                    None,
+                    runner,
+                    1,
+                    MisfirePolicy::RunImmediately,
+                    RetryConfig {
+                        max_attempts: 2,
+                        backoff_strategy: BackoffStrategy::Fixed,
+                        backoff_base_secs: 0,
+                        backoff_cap_secs: 0,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .expect("create_job");
+            set_job_env(&store, job.id, path_var, dir.to_str().unwrap()).await;
+            store.enable_job(job.id).await.expect("enable_job");
+
+            let n = HashMap::from([("n".to_string(), RunParam::Int(7))]);
+            let run = fire_and_wait_with(&store, &cfg, &runners, &resolver, job.id, n).await;
+            assert!(
+                matches!(run.state, JobRunState::Succeeded),
+                "{path_var} over {transport}: {:?}",
+                run.error
+            );
+            assert_eq!(
+                json_result(&run),
+                // The resolved value came through; the stored result masks it.
+                serde_json::json!({
+                    "secret": "***",
+                    "hidden": true,
+                    "attempt": 1,
+                    "jobId": job.id,
+                    "runId": run.id,
+                    "scheduled": true,
+                    "n": 7,
+                }),
+                "{path_var} over {transport}"
+            );
+
+            // ctx.retryable retries even though the job lists no retryable error types.
+            let busy = HashMap::from([("busy".to_string(), RunParam::Bool(true))]);
+            let run = fire_and_wait_with(&store, &cfg, &runners, &resolver, job.id, busy).await;
+            assert!(matches!(run.state, JobRunState::Failed), "{path_var} over {transport}");
+            assert_eq!(run.attempt, 2, "{path_var} over {transport}");
+            let error: serde_json::Value =
+                serde_json::from_str(run.error.as_deref().expect("error")).expect("error json");
+            assert_eq!(error["type"], "Retryable", "{path_var} over {transport}");
+            assert_eq!(error["message"], "upstream busy", "{path_var} over {transport}");
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn http_runner_full_flow() {
+    let server = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::method("GET"))
+        .and(wiremock::matchers::path("/ping"))
+        .respond_with(wiremock::ResponseTemplate::new(200).set_body_string("pong"))
+        .mount(&server)
+        .await;
+
+    let store = fresh_store().await;
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+
     let job = store
         .create_job(DEFAULT_TENANT_ID, 
-            "py-secret",
+            "http-job",
             None,
-            RunnerConfig::Python {
-                module: "mytask".to_string(),
-                class_name: "run".to_string(),
-                timeout_sec: Some(30),
+            RunnerConfig::Http {
+                method: "GET".to_string(),
+                url: format!("{}/ping", server.uri()),
+                headers: None,
+                body: None,
+                timeout_sec: Some(5),
+                tls: None,
+                signing: None,
             },
             1,
             MisfirePolicy::RunImmediately,
@@ -539,11 +1058,6 @@ async fn python_runner_resolves_secret_env() {
         )
         .await
         .expect("create_job");
-
-    let mut env = HashMap::new();
-    env.insert("PYTHONPATH".to_string(), dir.to_str().unwrap().to_string());
-    env.insert("MY_SECRET".to_string(), "secret:apikey".to_string());
-    store.set_job_env(job.id, env).await.expect("set_job_env");
     store.enable_job(job.id).await.expect("enable_job");
     store
         .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(5))
@@ -551,17 +1065,1543 @@ async fn python_runner_resolves_secret_env() {
         .expect("materialize run");
 
     let running = Arc::new(AtomicU32::new(0));
-    worker_tick(store.clone(), &cfg, &running, &resolver, 0)
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &builtin_runners(), RunLimits::default())
         .await
         .expect("worker_tick");
 
     let run = await_terminal(&store, job.id).await;
     assert!(
         matches!(run.state, JobRunState::Succeeded),
-        "expected Succeeded, got {:?} ({:?})",
-        run.state,
-        run.error
+        "expected Succeeded, got {:?}",
+        run.state
     );
-    // The `secret:apikey` env reference was resolved to the real value at run time.
-    assert_eq!(run.result.as_deref(), Some("s3cr3t"));
+    assert_eq!(run.result.as_deref(), Some("pong"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn http_runner_signs_requests() {
+    let server = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::method("POST"))
+        .and(wiremock::matchers::path("/hook"))
+        .and(wiremock::matchers::header_exists("X-Signature"))
+        .and(wiremock::matchers::header_exists("X-Signature-Timestamp"))
+        .respond_with(wiremock::ResponseTemplate::new(200).set_body_string("verified"))
+        .mount(&server)
+        .await;
+
+    let store = fresh_store().await;
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "http-signed",
+            None,
+            RunnerConfig::Http {
+                method: "POST".to_string(),
+                url: format!("{}/hook", server.uri()),
+                headers: None,
+                body: Some("{\"event\":\"tick\"}".to_string()),
+                timeout_sec: Some(5),
+                tls: None,
+                signing: Some(HttpSigningConfig {
+                    algorithm: SigningAlgorithm::HmacSha256,
+                    key: "whsec".to_string(),
+                    header: None,
+                    timestamp_header: None,
+                    canonical: None,
+                    prefix: None,
+                    encoding: Default::default(),
+                    region: None,
+                    service: None,
+                }),
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(5))
+        .await
+        .expect("materialize run");
+
+    let running = Arc::new(AtomicU32::new(0));
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &builtin_runners(), RunLimits::default())
+        .await
+        .expect("worker_tick");
+
+    // An unsigned request would fall through to wiremock's 404.
+    let run = await_terminal(&store, job.id).await;
+    assert!(
+        matches!(run.state, JobRunState::Succeeded),
+        "expected Succeeded, got {:?}",
+        run.state
+    );
+    assert_eq!(run.result.as_deref(), Some("verified"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn python_runner_resolves_secret_env() {
+    if !has_binary("python3") {
+        eprintln!("skipping python_runner_resolves_secret_env: python3 not found");
+        return;
+    }
+
+    let dir = write_module(
+        "mytask.py",
+        "import os\n\ndef run(ctx):\n    return os.environ.get('MY_SECRET', '')\n",
+    );
+
+    let path = std::env::temp_dir().join(format!("arbiter_flow_{}.db", Uuid::new_v4().simple()));
+    let sqlite = Arc::new(
+        SqliteStore::connect(path.to_str().expect("utf-8"))
+            .await
+            .expect("connect"),
+    );
+    let store: Arc<dyn Store + Send + Sync> = sqlite.clone();
+    let secret_store: Arc<dyn SecretStore + Send + Sync> = sqlite.clone();
+
+    let mgr = SecretManager::load_or_bootstrap(
+        secret_store,
+        Uuid::new_v4(),
+        std::sync::Arc::new(NodeKeyring::generate()),
+    )
+    .await
+    .expect("secret manager");
+    mgr.set_secret(DEFAULT_TENANT_ID, "apikey", b"s3cr3t")
+        .await
+        .expect("set_secret");
+    let resolver: arbiter_worker::Secrets =
+        Some(Arc::new(mgr) as Arc<dyn SecretResolver + Send + Sync>);
+
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+    let job = store
+        .create_job(DEFAULT_TENANT_ID, 
+            "py-secret",
+            None,
+            RunnerConfig::Python {
+                module: "mytask".to_string(),
+                class_name: "run".to_string(),
+                timeout_sec: Some(30),
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+
+    let mut env = HashMap::new();
+    env.insert("PYTHONPATH".to_string(), dir.to_str().unwrap().to_string());
+    env.insert("MY_SECRET".to_string(), "secret:apikey".to_string());
+    store.set_job_env(job.id, env).await.expect("set_job_env");
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(5))
+        .await
+        .expect("materialize run");
+
+    let running = Arc::new(AtomicU32::new(0));
+    worker_tick(store.clone(), &cfg, &running, &resolver, &None, &builtin_runners(), RunLimits::default())
+        .await
+        .expect("worker_tick");
+
+    let run = await_terminal(&store, job.id).await;
+    assert!(
+        matches!(run.state, JobRunState::Succeeded),
+        "expected Succeeded, got {:?} ({:?})",
+        run.state,
+        run.error
+    );
+    // The `secret:apikey` env reference was resolved to the real value at run time (which
+    // the stored result masks).
+    assert_eq!(run.result.as_deref(), Some("***"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn config_test_runs_on_the_worker_loop() {
+    let path = std::env::temp_dir().join(format!("arbiter_flow_{}.db", Uuid::new_v4().simple()));
+    let sqlite = Arc::new(
+        SqliteStore::connect(path.to_str().expect("utf-8"))
+            .await
+            .expect("connect"),
+    );
+    let store: Arc<dyn Store + Send + Sync> = sqlite.clone();
+    let secret_store: Arc<dyn SecretStore + Send + Sync> = sqlite.clone();
+
+    let mgr = SecretManager::load_or_bootstrap(
+        secret_store,
+        Uuid::new_v4(),
+        std::sync::Arc::new(NodeKeyring::generate()),
+    )
+    .await
+    .expect("secret manager");
+    mgr.set_secret(DEFAULT_TENANT_ID, "dbpass", b"pw")
+        .await
+        .expect("set_secret");
+    let resolver: arbiter_worker::Secrets =
+        Some(Arc::new(mgr) as Arc<dyn SecretResolver + Send + Sync>);
+
+    // Nothing listens on port 1: the secret resolves, then the connect step fails.
+    let cfg = store
+        .create_db_config(
+            DEFAULT_TENANT_ID,
+            DbEngine::PgSql,
+            "unreachable",
+            "127.0.0.1",
+            1,
+            "u",
+            "secret:dbpass",
+            "d",
+            None,
+        )
+        .await
+        .expect("create_db_config");
+
+    let wcfg = worker_cfg();
+    let settings = RuntimeSettings::new(
+        store.clone(),
+        RuntimeDefaults {
+            misfire_catchup_secs: 0,
+            run_retention_secs: 0,
+            prune_interval_secs: 3600,
+            scheduler_backstop_secs: 180,
+            worker_claim_backstop_secs: 300,
+            max_log_bytes: 0,
+            artifact_max_tenant_bytes: 0,
+            workspace_keep_run_secs: 0,
+            workspace_job_idle_secs: 0,
+            secret_max_versions: 10,
+            secret_audit_retention_secs: 0,
+        },
+    );
+    let loop_store = store.clone();
+    let worker_id = wcfg.worker_id;
+    tokio::spawn(async move {
+        run_worker_loop(
+            loop_store,
+            wcfg,
+            resolver,
+            None,
+            builtin_runners(),
+            settings,
+            Arc::new(SystemClock),
+        ).await;
+    });
+
+    let test_id = store.request_config_test(cfg.id).await.expect("request");
+    let mut result = None;
+    for _ in 0..200 {
+        if let Some(r) = store.get_config_test(test_id).await.expect("get") {
+            result = Some(r);
+            break;
+        }
+        tokio::time::sleep(StdDuration::from_millis(25)).await;
+    }
+    let result = result.expect("a worker reported the test");
+    assert!(!result.ok);
+    assert_eq!(result.worker_id, worker_id);
+    assert_eq!(
+        result.error.map(|e| e.code).as_deref(),
+        Some("connect_failed"),
+        "the password secret resolved on the worker; the connection was refused"
+    );
+}
+
+/// Writes a line to stdout, then a result document into the worker's `/arbiter` preopen
+/// (fd 3: the job has no preopens of its own).
+const WASM_REPORT: &str = r#"(module
+  (import "wasi_snapshot_preview1" "fd_write"
+    (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "path_open"
+    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 100) "hello from wasm\n")
+  (data (i32.const 200) "result.json")
+  (data (i32.const 300) "{\"status\":\"success\",\"output\":{\"rows\":3}}")
+  (func (export "_start")
+    (i32.store (i32.const 0) (i32.const 100))
+    (i32.store (i32.const 4) (i32.const 16))
+    (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+    ;; O_CREAT|O_TRUNC, rights: fd_write
+    (drop (call $path_open (i32.const 3) (i32.const 0) (i32.const 200) (i32.const 11)
+      (i32.const 9) (i64.const 64) (i64.const 0) (i32.const 0) (i32.const 16)))
+    (i32.store (i32.const 0) (i32.const 300))
+    (i32.store (i32.const 4) (i32.const 40))
+    (drop (call $fd_write (i32.load (i32.const 16)) (i32.const 0) (i32.const 1) (i32.const 8)))))"#;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wasm_runner_runs_a_stored_module() {
+    use sha2::{Digest, Sha256};
+
+    let store = fresh_store().await;
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+
+    let sha256 = format!("{:x}", Sha256::digest(WASM_REPORT.as_bytes()));
+    store
+        .put_wasm_module(DEFAULT_TENANT_ID, &sha256, WASM_REPORT.as_bytes())
+        .await
+        .expect("put_wasm_module");
+    let job = store
+        .create_job(DEFAULT_TENANT_ID,
+            "wasm-job",
+            None,
+            RunnerConfig::Wasm {
+                module: WasmModuleSource::Stored { sha256 },
+                timeout_sec: Some(10),
+                fuel: None,
+                max_memory_mb: None,
+                preopens: Vec::new(),
+                args: Vec::new(),
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(5))
+        .await
+        .expect("materialize run");
+
+    let running = Arc::new(AtomicU32::new(0));
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &builtin_runners(), RunLimits::default())
+        .await
+        .expect("worker_tick");
+
+    let run = await_terminal(&store, job.id).await;
+    assert!(
+        matches!(run.state, JobRunState::Succeeded),
+        "expected Succeeded, got {:?} ({:?})",
+        run.state,
+        run.error
+    );
+    assert_eq!(run.result.as_deref(), Some("{\"rows\":3}"));
+    assert_eq!(run.result_media_type.as_deref(), Some("application/json"));
+    let chunks = store
+        .read_run_log(run.id, run.attempt, None, 1000)
+        .await
+        .expect("read_run_log");
+    let output: String = chunks.iter().map(|c| c.content.as_str()).collect();
+    assert!(output.contains("hello from wasm"), "got: {output:?}");
+}
+
+/// Stands in for the built-in shell runner: echoes the command into the run log and returns
+/// it as the result, without spawning anything.
+struct EchoRunner;
+
+#[async_trait::async_trait]
+impl Runner for EchoRunner {
+    type Input = ExecutableConfigSnapshotMeta;
+    type Prepared = String;
+    type Output = String;
+
+    async fn prepare(
+        &self,
+        _ctx: &RunContext,
+        meta: ExecutableConfigSnapshotMeta,
+    ) -> arbiter_core::Result<String> {
+        match meta {
+            ExecutableConfigSnapshotMeta::Shell { command, .. } => Ok(command),
+            other => panic!("echo runner got a {} snapshot", other.type_of_str()),
+        }
+    }
+
+    async fn execute(&self, ctx: &RunContext, command: String) -> arbiter_core::Result<String> {
+        let logs = ctx.logs();
+        logs.write(LogStream::Stdout, format!("echo: {command}").as_bytes());
+        logs.finish().await;
+        Ok(command)
+    }
+
+    fn outcome(&self, _ctx: &RunContext, command: String) -> RunOutcome {
+        RunOutcome {
+            status: Some(ResultStatus::Success),
+            result: Some(command),
+            result_media_type: Some("text/plain".to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+async fn shell_job_with_due_run(store: &Arc<dyn Store + Send + Sync>, command: &str) -> Uuid {
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "registry-job",
+            None,
+            RunnerConfig::Shell {
+                command: command.to_string(),
+                working_dir: None,
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(5))
+        .await
+        .expect("materialize run");
+    job.id
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn runs_dispatch_through_the_registry() {
+    let store = fresh_store().await;
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+    let running = Arc::new(AtomicU32::new(0));
+
+    // A registered runner replaces the built-in one for its kind.
+    let mut registry = RunnerRegistry::with_builtins();
+    registry.register("shell", EchoRunner);
+    let job_id = shell_job_with_due_run(&store, "rm -rf /nowhere").await;
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &Arc::new(registry), RunLimits::default())
+        .await
+        .expect("worker_tick");
+    let run = await_terminal(&store, job_id).await;
+    assert!(matches!(run.state, JobRunState::Succeeded), "{:?}", run.error);
+    assert_eq!(run.result.as_deref(), Some("rm -rf /nowhere"));
+    let chunks = store
+        .read_run_log(run.id, run.attempt, None, 1000)
+        .await
+        .expect("read_run_log");
+    let output: String = chunks.iter().map(|c| c.content.as_str()).collect();
+    assert_eq!(output, "echo: rm -rf /nowhere");
+
+    // A kind nobody registered fails the run instead of leaving it running.
+    let job_id = shell_job_with_due_run(&store, "true").await;
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &Arc::new(RunnerRegistry::new()), RunLimits::default())
+        .await
+        .expect("worker_tick");
+    let run = await_terminal(&store, job_id).await;
+    assert!(matches!(run.state, JobRunState::Failed));
+    assert!(
+        run.error.as_deref().unwrap_or("").contains("no runner registered for 'shell'"),
+        "{:?}",
+        run.error
+    );
+}
+
+/// An in-process runner outside the worker crate: takes its `plugin:greet` payload, no
+/// snapshot variant of its own.
+struct GreetRunner;
+
+#[async_trait::async_trait]
+impl Runner for GreetRunner {
+    type Input = RunnerPayload;
+    type Prepared = String;
+    type Output = String;
+
+    async fn prepare(
+        &self,
+        _ctx: &RunContext,
+        payload: RunnerPayload,
+    ) -> arbiter_core::Result<String> {
+        assert_eq!(payload.name, "greet");
+        Ok(format!(
+            "hello {}",
+            payload.config["who"].as_str().unwrap_or("nobody")
+        ))
+    }
+
+    async fn execute(&self, _ctx: &RunContext, greeting: String) -> arbiter_core::Result<String> {
+        Ok(greeting)
+    }
+
+    fn outcome(&self, _ctx: &RunContext, greeting: String) -> RunOutcome {
+        RunOutcome {
+            status: Some(ResultStatus::Success),
+            result: Some(greeting),
+            result_media_type: Some("text/plain".to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn payload_runners_register_under_plugin_keys() {
+    let store = fresh_store().await;
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+    let mut registry = RunnerRegistry::with_builtins();
+    registry.register("plugin:greet", GreetRunner);
+    let registry = Arc::new(registry);
+
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "greet-job",
+            None,
+            RunnerConfig::Plugin {
+                plugin: "greet".to_string(),
+                config: serde_json::json!({"who": "ops"}),
+                timeout_sec: None,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(5))
+        .await
+        .expect("materialize run");
+    let running = Arc::new(AtomicU32::new(0));
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &registry, RunLimits::default())
+        .await
+        .expect("worker_tick");
+    let run = await_terminal(&store, job.id).await;
+    assert!(matches!(run.state, JobRunState::Succeeded), "{:?}", run.error);
+    assert_eq!(run.result.as_deref(), Some("hello ops"));
+
+    // Registered under a built-in kind, a payload runner fails the run rather than guessing.
+    let mut registry = RunnerRegistry::with_builtins();
+    registry.register("shell", GreetRunner);
+    let job_id = shell_job_with_due_run(&store, "true").await;
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &Arc::new(registry), RunLimits::default())
+        .await
+        .expect("worker_tick");
+    let run = await_terminal(&store, job_id).await;
+    assert!(matches!(run.state, JobRunState::Failed));
+    assert!(
+        run.error.as_deref().unwrap_or("").contains("carries no runner payload"),
+        "{:?}",
+        run.error
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn cancelled_running_run_is_abandoned() {
+    let store = fresh_store().await;
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+    let job_id = shell_job_with_due_run(&store, "sleep 30").await;
+    let running = Arc::new(AtomicU32::new(0));
+    worker_tick(store.clone(), &cfg, &running, &None, &None, &builtin_runners(), RunLimits::default())
+        .await
+        .expect("worker_tick");
+
+    let runs = store
+        .list_recent_runs(None, None, None, Some(job_id), None, None)
+        .await
+        .expect("list_recent_runs");
+    assert_eq!(running.load(std::sync::atomic::Ordering::Relaxed), 1);
+    store.cancel_run(runs[0].id).await.expect("cancel_run");
+
+    // The worker notices within its ownership check, kills the child and frees the slot.
+    let started = std::time::Instant::now();
+    while running.load(std::sync::atomic::Ordering::Relaxed) > 0 {
+        assert!(started.elapsed() < StdDuration::from_secs(15), "run was not abandoned");
+        tokio::time::sleep(StdDuration::from_millis(50)).await;
+    }
+    // ...and records nothing over the cancellation.
+    let run = store
+        .get_run(runs[0].id, None)
+        .await
+        .expect("get_run")
+        .expect("run exists");
+    assert!(matches!(run.state, JobRunState::Cancelled), "{:?}", run.state);
+}
+
+/// A stdio JSON-RPC runner plugin: reports itself in `handshake`, then answers `run` with a
+/// log notification and a result built from the config, the run and the resolved secret.
+const ECHO_PLUGIN: &str = r#"#!/usr/bin/env python3
+import json, sys
+
+def send(msg):
+    sys.stdout.write(json.dumps(msg) + "\n")
+    sys.stdout.flush()
+
+for line in sys.stdin:
+    req = json.loads(line)
+    if req["method"] == "handshake":
+        send({"jsonrpc": "2.0", "id": req["id"], "result": {
+            "protocolVersion": 1, "name": "echo", "version": "0.3.0",
+            "capabilities": ["logs"],
+            "configSchema": {"type": "object", "required": ["target"]},
+        }})
+    elif req["method"] == "run":
+        p = req["params"]
+        send({"jsonrpc": "2.0", "method": "log",
+              "params": {"stream": "stdout", "text": "deploying " + p["config"]["target"] + "\n"}})
+        sys.stderr.write("plugin diagnostics\n")
+        sys.stderr.flush()
+        send({"jsonrpc": "2.0", "id": req["id"], "result": {"status": "success", "output": {
+            "target": p["config"]["target"],
+            "token": p["secrets"][p["config"]["token"][len("secret:"):]],
+            "attempt": p["run"]["attempt"],
+            "region": p["env"]["REGION"],
+        }}})
+"#;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn plugin_runner_full_flow() {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    if !has_binary("python3") {
+        eprintln!("skipping plugin_runner_full_flow: python3 not found");
+        return;
+    }
+    let dir = write_module("echo-plugin", ECHO_PLUGIN);
+    std::fs::set_permissions(dir.join("echo-plugin"), std::fs::Permissions::from_mode(0o755))
+        .expect("chmod plugin");
+    // Neither a non-executable file nor an executable that does not speak the protocol is
+    // registered.
+    std::fs::write(dir.join("README"), "not a plugin").expect("write readme");
+    std::fs::write(dir.join("broken"), "#!/bin/sh\necho nope\n").expect("write broken");
+    std::fs::set_permissions(dir.join("broken"), std::fs::Permissions::from_mode(0o755))
+        .expect("chmod broken");
+
+    let plugins = discover_plugins(&dir).await;
+    assert_eq!(plugins.len(), 1, "only the echo plugin should register");
+    assert_eq!(plugins[0].info().name, "echo");
+    assert_eq!(plugins[0].info().version, "0.3.0");
+    let mut runners = RunnerRegistry::with_builtins();
+    for plugin in plugins {
+        runners.register_plugin(plugin);
+    }
+    assert!(runners.kinds().contains(&"plugin:echo"));
+    assert_eq!(runners.plugins()[0].capabilities, ["logs"]);
+    let runners = Arc::new(runners);
+
+    let path = std::env::temp_dir().join(format!("arbiter_flow_{}.db", Uuid::new_v4().simple()));
+    let sqlite = Arc::new(
+        SqliteStore::connect(path.to_str().expect("utf-8"))
+            .await
+            .expect("connect"),
+    );
+    let store: Arc<dyn Store + Send + Sync> = sqlite.clone();
+    let mgr = SecretManager::load_or_bootstrap(
+        sqlite.clone(),
+        Uuid::new_v4(),
+        Arc::new(NodeKeyring::generate()),
+    )
+    .await
+    .expect("secret manager");
+    mgr.set_secret(DEFAULT_TENANT_ID, "deploy-token", b"t0k3n")
+        .await
+        .expect("set_secret");
+    let resolver: arbiter_worker::Secrets =
+        Some(Arc::new(mgr) as Arc<dyn SecretResolver + Send + Sync>);
+
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+    let job = store
+        .create_job(DEFAULT_TENANT_ID,
+            "plugin-job",
+            None,
+            RunnerConfig::Plugin {
+                plugin: "echo".to_string(),
+                config: serde_json::json!({"target": "prod", "token": "secret:deploy-token"}),
+                timeout_sec: Some(30),
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    set_job_env(&store, job.id, "REGION", "eu").await;
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(5))
+        .await
+        .expect("materialize run");
+
+    let running = Arc::new(AtomicU32::new(0));
+    worker_tick(store.clone(), &cfg, &running, &resolver, &None, &runners, RunLimits::default())
+        .await
+        .expect("worker_tick");
+
+    let run = await_terminal(&store, job.id).await;
+    assert!(
+        matches!(run.state, JobRunState::Succeeded),
+        "expected Succeeded, got {:?} ({:?})",
+        run.state,
+        run.error
+    );
+    let result: serde_json::Value =
+        serde_json::from_str(run.result.as_deref().expect("result")).expect("json result");
+    assert_eq!(
+        result,
+        serde_json::json!({"target": "prod", "token": "***", "attempt": 1, "region": "eu"})
+    );
+    let chunks = store
+        .read_run_log(run.id, run.attempt, None, 1000)
+        .await
+        .expect("read_run_log");
+    let stdout: String = chunks
+        .iter()
+        .filter(|c| c.stream == LogStream::Stdout)
+        .map(|c| c.content.as_str())
+        .collect();
+    let stderr: String = chunks
+        .iter()
+        .filter(|c| c.stream == LogStream::Stderr)
+        .map(|c| c.content.as_str())
+        .collect();
+    assert_eq!(stdout, "deploying prod\n");
+    assert!(stderr.contains("plugin diagnostics"), "got: {stderr:?}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn runs_upload_artifacts_within_tenant_cap() {
+    let store = fresh_store().await;
+    let artifacts: Artifacts = Some(store.clone() as Arc<dyn ArtifactStore + Send + Sync>);
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+    // 10 bytes per tenant: the 4-byte report fits, the 20-byte dump does not.
+    let limits = RunLimits {
+        artifact_max_tenant_bytes: 10,
+        ..Default::default()
+    };
+
+    let mut jobs = vec![(
+        RunnerConfig::Shell {
+            command: "printf 'a,b\\n' > \"$ARBITER_ARTIFACTS_DIR/report.csv\"; \
+                      printf '01234567890123456789' > \"$ARBITER_ARTIFACTS_DIR/dump.bin\"; \
+                      mkdir \"$ARBITER_ARTIFACTS_DIR/nested\""
+                .to_string(),
+            working_dir: None,
+            code: None,
+            workspace: WorkspaceMode::Run,
+            secret_delivery: SecretDelivery::Env,
+        },
+        None,
+    )];
+    let py_dir = write_module(
+        "artifact_task.py",
+        "def run(ctx):\n    ctx.artifact('py.txt', 'hi')\n    return 'ok'\n",
+    );
+    if has_binary("python3") {
+        let runner = RunnerConfig::Python {
+            module: "artifact_task".to_string(),
+            class_name: "run".to_string(),
+            timeout_sec: Some(30),
+            transport: RuntimeTransport::File,
+            max_runs: None,
+            secrets: vec![],
+            code: None,
+            workspace: WorkspaceMode::Run,
+            secret_delivery: SecretDelivery::Env,
+        };
+        jobs.push((runner, Some(&py_dir)));
+    }
+
+    let mut runs = Vec::new();
+    for (runner, module_dir) in jobs {
+        // A tenant of its own per job, so the cap is counted per job.
+        let tenant = store
+            .create_tenant(&format!("artifacts-{}", Uuid::new_v4().simple()))
+            .await
+            .expect("create_tenant")
+            .id;
+        let job = store
+            .create_job(
+                tenant,
+                "artifacts",
+                None,
+                runner,
+                1,
+                MisfirePolicy::RunImmediately,
+                RetryConfig::default(),
+            )
+            .await
+            .expect("create_job");
+        if let Some(dir) = module_dir {
+            set_job_env(&store, job.id, "PYTHONPATH", dir.to_str().unwrap()).await;
+        }
+        store.enable_job(job.id).await.expect("enable_job");
+        let run = store
+            .create_adhoc_run(job.id, HashMap::new())
+            .await
+            .expect("create_adhoc_run");
+        runs.push((tenant, run.id));
+    }
+
+    let running = Arc::new(AtomicU32::new(0));
+    let mut done = Vec::new();
+    for _ in 0..400 {
+        worker_tick(store.clone(), &cfg, &running, &None, &artifacts, &builtin_runners(), limits)
+            .await
+            .expect("worker_tick");
+        done.clear();
+        for (tenant, id) in &runs {
+            let run = store.get_run(*id, None).await.expect("get_run").expect("run");
+            if matches!(run.state, JobRunState::Succeeded | JobRunState::Failed) {
+                done.push((*tenant, run));
+            }
+        }
+        if done.len() == runs.len() {
+            break;
+        }
+        tokio::time::sleep(StdDuration::from_millis(25)).await;
+    }
+    assert_eq!(done.len(), runs.len(), "runs never finished");
+
+    let (tenant, shell) = &done[0];
+    assert!(matches!(shell.state, JobRunState::Succeeded), "{:?}", shell.error);
+    let listed = store.list_artifacts(*tenant, shell.id).await.expect("list");
+    assert_eq!(listed.len(), 1, "only the report fits the cap: {listed:?}");
+    assert_eq!(listed[0].name, "report.csv");
+    assert_eq!(listed[0].attempt, 1);
+    assert_eq!(
+        listed[0].sha256,
+        "5be08c9684a1d25efcee09318204824278b08bbfb4aef973ffefd0b9d7478313"
+    );
+    assert_eq!(
+        store
+            .get_artifact(*tenant, shell.id, 1, "report.csv")
+            .await
+            .expect("get")
+            .as_deref(),
+        Some(&b"a,b\n"[..])
+    );
+    // What was skipped is noted in the run's log.
+    let notes: Vec<String> = store
+        .read_run_log(shell.id, 1, None, 1000)
+        .await
+        .expect("read_run_log")
+        .into_iter()
+        .filter(|c| c.level == Some(LogLevel::Warning))
+        .map(|c| c.content)
+        .collect();
+    assert!(
+        notes.iter().any(|n| n.contains("dump.bin") && n.contains("cap")),
+        "got: {notes:?}"
+    );
+    assert!(notes.iter().any(|n| n.contains("nested")), "got: {notes:?}");
+
+    if let Some((tenant, py)) = done.get(1) {
+        assert!(matches!(py.state, JobRunState::Succeeded), "{:?}", py.error);
+        assert_eq!(
+            store.get_artifact(*tenant, py.id, 1, "py.txt").await.expect("get").as_deref(),
+            Some(&b"hi"[..])
+        );
+    }
+}
+
+/// A tar.gz of `(path, contents)` entries, as a job's code bundle.
+fn tar_gz(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::default(),
+    ));
+    for (path, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o755);
+        builder
+            .append_data(&mut header, path, contents.as_bytes())
+            .expect("append");
+    }
+    builder.into_inner().expect("tar").finish().expect("gz")
+}
+
+async fn put_bundle(store: &Arc<dyn Store + Send + Sync>, name: &str, content: &[u8]) -> u32 {
+    use sha2::{Digest, Sha256};
+
+    let sha256 = format!("{:x}", Sha256::digest(content));
+    store
+        .put_code_bundle(DEFAULT_TENANT_ID, name, BundleFormat::TarGz, &sha256, content)
+        .await
+        .expect("put_code_bundle")
+        .version
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn jobs_run_code_from_bundles() {
+    let store = fresh_store().await;
+    let mut cfg = worker_cfg();
+    // A cache of its own, so the bundles are fetched by this test.
+    let data_dir = tempfile::tempdir().expect("tempdir");
+    cfg.data_dir = data_dir.path().to_path_buf();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+    let runners = builtin_runners();
+
+    // A shell job runs in the bundle's subdir, which it can find via ARBITER_CODE_DIR too.
+    put_bundle(
+        &store,
+        "scripts",
+        &tar_gz(&[("bin/hello.sh", "echo \"hi from $(basename \"$PWD\")\"\n")]),
+    )
+    .await;
+    let shell = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "bundle-shell",
+            None,
+            RunnerConfig::Shell {
+                command: "sh hello.sh".to_string(),
+                working_dir: Some("bin".to_string()),
+                code: Some(CodeSource::Bundle { name: "scripts".to_string(), version: None }),
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    store.enable_job(shell.id).await.expect("enable_job");
+    let run = fire_and_wait(&store, &cfg, &runners, shell.id, HashMap::new()).await;
+    assert!(
+        matches!(run.state, JobRunState::Succeeded),
+        "expected Succeeded, got {:?} ({:?})",
+        run.state,
+        run.error
+    );
+    assert_eq!(run_stdout(&store, &run).await, "hi from bin\n");
+
+    // A job naming a missing bundle fails rather than running without its code.
+    let missing = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "bundle-missing",
+            None,
+            RunnerConfig::Shell {
+                command: "true".to_string(),
+                working_dir: None,
+                code: Some(CodeSource::Bundle { name: "scripts".to_string(), version: Some(9) }),
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    store.enable_job(missing.id).await.expect("enable_job");
+    let run = fire_and_wait(&store, &cfg, &runners, missing.id, HashMap::new()).await;
+    assert!(matches!(run.state, JobRunState::Failed));
+    assert!(
+        run.error.as_deref().unwrap_or_default().contains("scripts v9 not found"),
+        "got {:?}",
+        run.error
+    );
+
+    if !has_binary("python3") {
+        eprintln!("skipping the python half of jobs_run_code_from_bundles: python3 not found");
+        return;
+    }
+    // A python job imports its module from the bundle, with no PYTHONPATH of its own, and
+    // an unpinned job picks up a newly uploaded version on its next run.
+    let module = |answer: u32| format!("def run(ctx):\n    return {{'answer': {answer}}}\n");
+    put_bundle(&store, "etl", &tar_gz(&[("etl/main.py", &module(1)), ("etl/__init__.py", "")]))
+        .await;
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "bundle-python",
+            None,
+            RunnerConfig::Python {
+                module: "etl.main".to_string(),
+                class_name: "run".to_string(),
+                timeout_sec: Some(30),
+                transport: RuntimeTransport::Socket,
+                max_runs: None,
+                secrets: vec![],
+                code: Some(CodeSource::Bundle { name: "etl".to_string(), version: None }),
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    store.enable_job(job.id).await.expect("enable_job");
+    let first = fire_and_wait(&store, &cfg, &runners, job.id, HashMap::new()).await;
+    assert_eq!(json_result(&first)["answer"], 1);
+
+    let v2 = put_bundle(&store, "etl", &tar_gz(&[("etl/main.py", &module(2)), ("etl/__init__.py", "")]))
+        .await;
+    assert_eq!(v2, 2);
+    let second = fire_and_wait(&store, &cfg, &runners, job.id, HashMap::new()).await;
+    assert_eq!(json_result(&second)["answer"], 2, "the resident process is replaced");
+
+    // Each version is unpacked once, under its tenant and content hash.
+    let cached = std::fs::read_dir(
+        data_dir.path().join("code").join("bundles").join(DEFAULT_TENANT_ID.to_string()),
+    )
+    .expect("code cache")
+    .count();
+    assert_eq!(cached, 3);
+}
+
+/// Run `git` in `dir` (as a throwaway identity) and return its trimmed stdout.
+fn git_in(dir: &std::path::Path, args: &[&str]) -> String {
+    let out = std::process::Command::new("git")
+        .args(["-c", "user.name=arbiter", "-c", "user.email=arbiter@example.invalid"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("run git");
+    assert!(out.status.success(), "git {args:?}: {}", String::from_utf8_lossy(&out.stderr));
+    String::from_utf8_lossy(&out.stdout).trim().to_string()
+}
+
+/// Write `path` in the repo and commit it, returning the new commit.
+fn git_commit(repo: &std::path::Path, path: &str, contents: &str) -> String {
+    let file = repo.join(path);
+    std::fs::create_dir_all(file.parent().unwrap()).expect("create dirs");
+    std::fs::write(&file, contents).expect("write file");
+    git_in(repo, &["add", "-A"]);
+    git_in(repo, &["commit", "-q", "-m", path]);
+    git_in(repo, &["rev-parse", "HEAD"])
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn jobs_run_code_from_git() {
+    if !has_binary("git") {
+        eprintln!("skipping jobs_run_code_from_git: git not found");
+        return;
+    }
+    let repo = tempfile::tempdir().expect("tempdir");
+    git_in(repo.path(), &["init", "-q", "-b", "main"]);
+    let first = git_commit(repo.path(), "jobs/report.sh", "echo report v1 in $(basename \"$PWD\")\n");
+    git_commit(repo.path(), "README", "not part of the job\n");
+
+    let store = fresh_store().await;
+    let mut cfg = worker_cfg();
+    let data_dir = tempfile::tempdir().expect("tempdir");
+    cfg.data_dir = data_dir.path().to_path_buf();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+    let runners = builtin_runners();
+    let url = format!("file://{}", repo.path().display());
+    let git_job = |name: &'static str, rev: Option<String>| {
+        let store = store.clone();
+        let url = url.clone();
+        async move {
+            let job = store
+                .create_job(
+                    DEFAULT_TENANT_ID,
+                    name,
+                    None,
+                    RunnerConfig::Shell {
+                        command: "sh report.sh".to_string(),
+                        working_dir: None,
+                        code: Some(CodeSource::Git { url, rev, subdir: Some("jobs".to_string()) }),
+                        workspace: WorkspaceMode::Run,
+                        secret_delivery: SecretDelivery::Env,
+                    },
+                    1,
+                    MisfirePolicy::RunImmediately,
+                    RetryConfig::default(),
+                )
+                .await
+                .expect("create_job");
+            store.enable_job(job.id).await.expect("enable_job");
+            job.id
+        }
+    };
+    let tracking = git_job("git-main", Some("main".to_string())).await;
+    let pinned = git_job("git-pinned", Some(first.clone())).await;
+
+    let head = git_in(repo.path(), &["rev-parse", "HEAD"]);
+    let run = fire_and_wait(&store, &cfg, &runners, tracking, HashMap::new()).await;
+    assert!(
+        matches!(run.state, JobRunState::Succeeded),
+        "expected Succeeded, got {:?} ({:?})",
+        run.state,
+        run.error
+    );
+    assert_eq!(run_stdout(&store, &run).await, "report v1 in jobs\n");
+    assert_eq!(run.code_commit.as_deref(), Some(head.as_str()), "the resolved commit is recorded");
+
+    // A new commit on the branch is fetched for the tracking job; the pinned one stays put.
+    let second = git_commit(repo.path(), "jobs/report.sh", "echo report v2\n");
+    let run = fire_and_wait(&store, &cfg, &runners, tracking, HashMap::new()).await;
+    assert_eq!(run_stdout(&store, &run).await, "report v2\n");
+    assert_eq!(run.code_commit.as_deref(), Some(second.as_str()));
+    let run = fire_and_wait(&store, &cfg, &runners, pinned, HashMap::new()).await;
+    assert_eq!(run_stdout(&store, &run).await, "report v1 in jobs\n");
+    assert_eq!(run.code_commit.as_deref(), Some(first.as_str()));
+
+    // An unknown ref fails the run.
+    let missing = git_job("git-missing", Some("no-such-branch".to_string())).await;
+    let run = fire_and_wait(&store, &cfg, &runners, missing, HashMap::new()).await;
+    assert!(matches!(run.state, JobRunState::Failed));
+    assert!(
+        run.error.as_deref().unwrap_or_default().contains("no-such-branch"),
+        "got {:?}",
+        run.error
+    );
+
+    // One mirror for the repository; every per-run checkout is gone once its run is over.
+    let code = data_dir.path().join("code");
+    assert_eq!(std::fs::read_dir(code.join("git")).expect("git cache").count(), 1);
+    assert_eq!(std::fs::read_dir(code.join("checkouts")).expect("checkouts").count(), 0);
+}
+
+/// Poll until `path` is gone (a run's workspace is released just after it is finalized).
+async fn await_removed(path: &std::path::Path) {
+    for _ in 0..100 {
+        if !path.exists() {
+            return;
+        }
+        tokio::time::sleep(StdDuration::from_millis(20)).await;
+    }
+    panic!("{} was never removed", path.display());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn runs_get_managed_workspaces() {
+    let store = fresh_store().await;
+    let mut cfg = worker_cfg();
+    let data_dir = tempfile::tempdir().expect("tempdir");
+    cfg.data_dir = data_dir.path().to_path_buf();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+    let runners = builtin_runners();
+    let shell_job = |name: &'static str, command: &'static str, workspace: WorkspaceMode| {
+        let store = store.clone();
+        async move {
+            let job = store
+                .create_job(
+                    DEFAULT_TENANT_ID,
+                    name,
+                    None,
+                    RunnerConfig::Shell {
+                        command: command.to_string(),
+                        working_dir: None,
+                        code: None,
+                        workspace,
+                        secret_delivery: SecretDelivery::Env,
+                    },
+                    1,
+                    MisfirePolicy::RunImmediately,
+                    RetryConfig::default(),
+                )
+                .await
+                .expect("create_job");
+            store.enable_job(job.id).await.expect("enable_job");
+            job.id
+        }
+    };
+
+    // By default a run starts in a fresh directory of its own, removed once it is over.
+    let command = "pwd; echo \"$ARBITER_WORKSPACE\"; ls -A; touch out.txt";
+    let job = shell_job("ws-run", command, WorkspaceMode::Run).await;
+    let run = fire_and_wait(&store, &cfg, &runners, job, HashMap::new()).await;
+    assert!(matches!(run.state, JobRunState::Succeeded), "{:?}", run.error);
+    let stdout = run_stdout(&store, &run).await;
+    let lines: Vec<&str> = stdout.lines().collect();
+    let expected = data_dir
+        .path()
+        .join("workspaces")
+        .join("runs")
+        .join(format!("{}-1", run.id));
+    assert_eq!(lines, [expected.to_str().unwrap(); 2], "cwd, then ARBITER_WORKSPACE, and empty");
+    await_removed(&expected).await;
+
+    // A per-job workspace is shared by the job's runs and outlives them.
+    let command = "echo run >> runs.log; wc -l < runs.log";
+    let job = shell_job("ws-job", command, WorkspaceMode::Job).await;
+    for n in 1..=2 {
+        let run = fire_and_wait(&store, &cfg, &runners, job, HashMap::new()).await;
+        assert!(matches!(run.state, JobRunState::Succeeded), "{:?}", run.error);
+        assert_eq!(run_stdout(&store, &run).await.trim(), n.to_string());
+    }
+    let job_dir = data_dir.path().join("workspaces").join("jobs").join(job.to_string());
+    assert!(job_dir.join("runs.log").exists());
+
+    if !has_binary("python3") {
+        eprintln!("skipping the python half of runs_get_managed_workspaces: python3 not found");
+        return;
+    }
+    // A runtime job runs in its workspace too, and sees it on the context.
+    let dir = write_module(
+        "wstask.py",
+        "import os\n\ndef run(ctx):\n    open('note.txt', 'w').close()\n    return {'cwd': os.getcwd(), 'ctx': ctx.workspace_dir}\n",
+    );
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "ws-python",
+            None,
+            RunnerConfig::Python {
+                module: "wstask".to_string(),
+                class_name: "run".to_string(),
+                timeout_sec: Some(30),
+                transport: RuntimeTransport::Socket,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Job,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    set_job_env(&store, job.id, "PYTHONPATH", dir.to_str().unwrap()).await;
+    store.enable_job(job.id).await.expect("enable_job");
+    let run = fire_and_wait(&store, &cfg, &runners, job.id, HashMap::new()).await;
+    let result = json_result(&run);
+    let job_dir = data_dir.path().join("workspaces").join("jobs").join(job.id.to_string());
+    assert_eq!(result["cwd"], job_dir.to_str().unwrap());
+    assert_eq!(result["ctx"], job_dir.to_str().unwrap());
+    assert!(job_dir.join("note.txt").exists());
+}
+
+#[tokio::test]
+async fn maintenance_sweeps_orphaned_scratch() {
+    let store = fresh_store().await;
+    let cfg = worker_cfg();
+    let data_dir = tempfile::tempdir().expect("tempdir");
+    let other_worker = Uuid::new_v4();
+    store
+        .insert_worker(other_worker, "other", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+
+    // A run in flight on another worker sharing the host and the data dir.
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "busy",
+            None,
+            RunnerConfig::Shell {
+                command: "true".to_string(),
+                working_dir: None,
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    store.enable_job(job.id).await.expect("enable_job");
+    store
+        .insert_job_run_if_missing(job.id, Utc::now() - Duration::seconds(5))
+        .await
+        .expect("insert run");
+    let busy = store
+        .claim_job_runs(other_worker, 1)
+        .await
+        .expect("claim")
+        .pop()
+        .expect("claimed run");
+
+    let tmp = std::env::temp_dir();
+    let orphan_result = tmp.join(format!("arbiter-result-{}-x.json", Uuid::new_v4()));
+    let busy_result = tmp.join(format!("arbiter-result-{}-x.json", busy.id));
+    let orphan_checkout = data_dir
+        .path()
+        .join("code")
+        .join("checkouts")
+        .join(format!("{}-abc", Uuid::new_v4()));
+    let runs = data_dir.path().join("workspaces").join("runs");
+    let orphan_workspace = runs.join(format!("{}-1", Uuid::new_v4()));
+    let busy_workspace = runs.join(format!("{}-{}", busy.id, busy.attempt));
+    let stale_runtime = tmp.join("arbiter-runtime").join("arbiter_runtime_0000000000000000.py");
+    for dir in [&orphan_checkout, &orphan_workspace, &busy_workspace] {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("file"), b"12345").unwrap();
+    }
+    std::fs::create_dir_all(stale_runtime.parent().unwrap()).unwrap();
+    for file in [&orphan_result, &busy_result, &stale_runtime] {
+        std::fs::write(file, b"{}").unwrap();
+    }
+
+    let usage = arbiter_worker::sweep_worker_data(store.as_ref(), cfg.worker_id, data_dir.path(), 0, 0)
+        .await
+        .expect("sweep");
+    for gone in [&orphan_result, &orphan_checkout, &orphan_workspace, &stale_runtime] {
+        assert!(!gone.exists(), "{} was kept", gone.display());
+    }
+    assert!(busy_result.exists() && busy_workspace.exists());
+    assert!(usage.swept_entries >= 4 && usage.swept_bytes >= 14, "{usage:?}");
+    assert_eq!((usage.run_workspaces, usage.run_workspace_bytes), (1, 5));
+    std::fs::remove_file(busy_result).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn resolved_secrets_are_masked_in_logs_and_outcome() {
+    let path = std::env::temp_dir().join(format!("arbiter_flow_{}.db", Uuid::new_v4().simple()));
+    let sqlite = Arc::new(
+        SqliteStore::connect(path.to_str().expect("utf-8"))
+            .await
+            .expect("connect"),
+    );
+    let store: Arc<dyn Store + Send + Sync> = sqlite.clone();
+    let mgr = SecretManager::load_or_bootstrap(
+        sqlite.clone(),
+        Uuid::new_v4(),
+        Arc::new(NodeKeyring::generate()),
+    )
+    .await
+    .expect("secret manager");
+    let token = "hunter2-very/secret";
+    mgr.set_secret(DEFAULT_TENANT_ID, "token", token.as_bytes())
+        .await
+        .expect("set_secret");
+    let resolver: arbiter_worker::Secrets =
+        Some(Arc::new(mgr) as Arc<dyn SecretResolver + Send + Sync>);
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+
+    // The value is written in two halves either side of a flush, then encoded, then to
+    // stderr.
+    let command = concat!(
+        "printf 'token=%.7s' \"$TOKEN\"; sleep 1; printf '%s\\n' \"${TOKEN#hunter2}\"; ",
+        "printf '%s' \"$TOKEN\" | base64; ",
+        "echo \"Authorization: Basic $(printf 'user:%s' \"$TOKEN\" | base64)\"; ",
+        "echo \"https://host/?t=hunter2-very%2Fsecret\"; ",
+        "echo \"bad token $TOKEN\" >&2; exit 1",
+    );
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "leaky",
+            None,
+            RunnerConfig::Shell {
+                command: command.to_string(),
+                working_dir: None,
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Env,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    set_job_env(&store, job.id, "TOKEN", "secret:token").await;
+    store.enable_job(job.id).await.expect("enable_job");
+    let run =
+        fire_and_wait_with(&store, &cfg, &builtin_runners(), &resolver, job.id, HashMap::new())
+            .await;
+    assert!(matches!(run.state, JobRunState::Failed), "{:?}", run.state);
+
+    let chunks = store
+        .read_run_log(run.id, run.attempt, None, 1000)
+        .await
+        .expect("read_run_log");
+    assert!(chunks.len() > 2, "the output spans several flushes: {chunks:?}");
+    let logs: String = chunks.iter().map(|c| c.content.as_str()).collect();
+    let stored = format!("{logs}{:?}{:?}", run.result, run.error);
+    use base64::Engine;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(token);
+    for leak in [token, "very/secret", &encoded[..encoded.len() - 4], "very%2Fsecret"] {
+        assert!(!stored.contains(leak), "{leak:?} leaked: {stored}");
+    }
+    assert!(logs.starts_with("token=***\n"), "{logs}");
+    assert_eq!(logs.matches("***").count(), 5, "{logs}");
+    assert!(logs.contains("bad token ***"), "{logs}");
+
+    // The resolve is on the audit trail as the run's.
+    let secret = store
+        .get_secret_by_name(DEFAULT_TENANT_ID, "token")
+        .await
+        .expect("get secret")
+        .expect("present");
+    let audit = store
+        .list_secret_audit(secret.id, None, 10)
+        .await
+        .expect("audit");
+    let resolve = audit
+        .iter()
+        .find(|e| e.action == SecretAuditAction::Resolve)
+        .expect("resolve audited");
+    assert_eq!(
+        resolve.actor,
+        SecretActor::Run {
+            run_id: run.id,
+            job_id: job.id
+        }
+    );
+    assert_eq!(
+        (resolve.outcome, resolve.version),
+        (SecretAuditOutcome::Ok, Some(1))
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn secrets_can_be_delivered_as_files() {
+    let path = std::env::temp_dir().join(format!("arbiter_flow_{}.db", Uuid::new_v4().simple()));
+    let sqlite = Arc::new(
+        SqliteStore::connect(path.to_str().expect("utf-8"))
+            .await
+            .expect("connect"),
+    );
+    let store: Arc<dyn Store + Send + Sync> = sqlite.clone();
+    let mgr = SecretManager::load_or_bootstrap(
+        sqlite.clone(),
+        Uuid::new_v4(),
+        Arc::new(NodeKeyring::generate()),
+    )
+    .await
+    .expect("secret manager");
+    mgr.set_secret(DEFAULT_TENANT_ID, "token", b"hunter2-in-a-file")
+        .await
+        .expect("set_secret");
+    let resolver: arbiter_worker::Secrets =
+        Some(Arc::new(mgr) as Arc<dyn SecretResolver + Send + Sync>);
+    let cfg = worker_cfg();
+    store
+        .insert_worker(cfg.worker_id, "test", "test", "test", 0)
+        .await
+        .expect("insert_worker");
+
+    // The secret is a file in a private directory, and not in the env; plain values are.
+    let command = concat!(
+        "echo \"$ARBITER_SECRETS_DIR\"; echo \"token=${TOKEN:-unset} plain=$PLAIN\"; ",
+        "stat -c %a \"$ARBITER_SECRETS_DIR\" \"$ARBITER_SECRETS_DIR/TOKEN\"; ",
+        "[ \"$(cat \"$ARBITER_SECRETS_DIR/TOKEN\")\" = hunter2-in-a-file ] && echo match",
+    );
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "files",
+            None,
+            RunnerConfig::Shell {
+                command: command.to_string(),
+                working_dir: None,
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Files,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    let env = HashMap::from([
+        ("TOKEN".to_string(), "secret:token".to_string()),
+        ("PLAIN".to_string(), "visible".to_string()),
+    ]);
+    store.set_job_env(job.id, env).await.expect("set_job_env");
+    store.enable_job(job.id).await.expect("enable_job");
+    let runners = builtin_runners();
+    let run = fire_and_wait_with(&store, &cfg, &runners, &resolver, job.id, HashMap::new()).await;
+    assert!(matches!(run.state, JobRunState::Succeeded), "{:?}", run.error);
+    let stdout = run_stdout(&store, &run).await;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[1..], ["token=unset plain=visible", "700", "600", "match"], "{stdout}");
+    let dir = std::path::Path::new(lines[0]);
+    let name = dir.file_name().expect("dir name").to_string_lossy();
+    assert!(name.starts_with(&format!("arbiter-secrets-{}-", run.id)), "{stdout}");
+    await_removed(dir).await;
+
+    if !has_binary("python3") {
+        eprintln!("skipping the python half of secrets_can_be_delivered_as_files: no python3");
+        return;
+    }
+    // A runtime sees the directory on its context, and it is gone even when the run times
+    // out.
+    let module = write_module(
+        "slowsecret.py",
+        concat!(
+            "import os, time\n\n",
+            "def run(ctx):\n",
+            "    with open(os.path.join(ctx.secrets_dir, 'TOKEN')) as f:\n",
+            "        ok = f.read() == 'hunter2-in-a-file'\n",
+            "    print(ctx.secrets_dir, ok, 'TOKEN' in os.environ, flush=True)\n",
+            "    time.sleep(30)\n",
+        ),
+    );
+    let job = store
+        .create_job(
+            DEFAULT_TENANT_ID,
+            "files-python",
+            None,
+            RunnerConfig::Python {
+                module: "slowsecret".to_string(),
+                class_name: "run".to_string(),
+                timeout_sec: Some(2),
+                transport: RuntimeTransport::File,
+                max_runs: None,
+                secrets: vec![],
+                code: None,
+                workspace: WorkspaceMode::Run,
+                secret_delivery: SecretDelivery::Files,
+            },
+            1,
+            MisfirePolicy::RunImmediately,
+            RetryConfig::default(),
+        )
+        .await
+        .expect("create_job");
+    let env = HashMap::from([
+        ("TOKEN".to_string(), "secret:token".to_string()),
+        ("PYTHONPATH".to_string(), module.to_str().unwrap().to_string()),
+    ]);
+    store.set_job_env(job.id, env).await.expect("set_job_env");
+    store.enable_job(job.id).await.expect("enable_job");
+    let run = fire_and_wait_with(&store, &cfg, &runners, &resolver, job.id, HashMap::new()).await;
+    assert!(!matches!(run.state, JobRunState::Succeeded), "{:?}", run.state);
+    let stdout = run_stdout(&store, &run).await;
+    let (dir, rest) = stdout.trim().split_once(' ').expect("secrets dir printed");
+    assert_eq!(rest, "True False", "{stdout}");
+    await_removed(std::path::Path::new(dir)).await;
 }
@@ -4,7 +4,8 @@
 //!
 //! The mock's `claim_job_runs` returns empty (it only records *when* a due run would have
 //! been claimed), so the loop never executes anything - this isolates wake/claim timing.
-//! Every other `Store` method is `unimplemented!()` and never reached on this path.
+//! Config-test claims are likewise always empty. Every other `Store` method is
+//! `unimplemented!()` and never reached on this path.
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -18,13 +19,16 @@ use tokio::time::Instant;
 use uuid::Uuid;
 
 use arbiter_core::{
-    ApiStore, Clock, ConfigStore, DbEngine, JobRun, JobRunState, JobSpec, JobStore, LogChunk,
-    LogSize, LogStore, LogStream, MisfirePolicy, Result, RetryConfig, RunOutcome, RunStore,
-    RunnerConfig, RuntimeDefaults, RuntimeSettings, SecretMeta, SecretStore, Setting, SettingsStore,
-    SharedDbConfig, Store, StoredKekShare, StoredKekVersion, StoredNodeKey, StoredSecret, Tenant,
-    TenantStore, User, UserRole, WorkerConfig, WorkerRecord, WorkerStore,
+    ApiStore, ArtifactStore, BundleFormat, BundleStore, Clock, CodeBundle, ConfigStore,
+    ConfigTestRequest, ConfigTestResult, DbEngine, JobRun, JobRunState, JobSpec, JobStore,
+    LogChunk, LogLevel, LogSize, LogStore, LogStream, MisfirePolicy, ModuleStore, Result,
+    RetryConfig, RunArtifact, RunOutcome, RunParam, RunProgress, RunStore, RunnerConfig,
+    RuntimeDefaults, RuntimeSettings, SecretAuditEvent, SecretMeta, SecretStore, SecretVersionMeta,
+    Setting, SettingsStore, SharedDbConfig, Store, StoredKekShare, StoredKekVersion, StoredNodeKey,
+    StoredSecret, Tenant, TenantStore, User, UserRole, WasmModuleInfo, WorkerConfig, WorkerRecord,
+    WorkerStore,
 };
-use arbiter_worker::run_worker_loop;
+use arbiter_worker::{RunnerRegistry, run_worker_loop};
 
 /// A clock pinned to tokio's (paused) virtual time: `now = base + virtual_elapsed`.
 struct VirtualClock {
@@ -72,11 +76,11 @@ impl RunStore for MockStore {
     async fn claim_job_runs(&self, _worker_id: Uuid, _limit: u32) -> Result<Vec<JobRun>> {
         let now = self.clock.now();
         let due = *self.due.lock().expect("lock");
-        if let Some(d) = due {
-            if now >= d {
-                *self.due.lock().expect("lock") = None;
-                *self.claimed_at.lock().expect("lock") = Some(now);
-            }
+        if let Some(d) = due
+            && now >= d
+        {
+            *self.due.lock().expect("lock") = None;
+            *self.claimed_at.lock().expect("lock") = Some(now);
         }
         Ok(vec![]) // never execute; this test only checks claim timing
     }
@@ -89,6 +93,15 @@ impl RunStore for MockStore {
     async fn finalize_run(&self, _: Uuid, _: JobRunState, _: RunOutcome) -> Result<()> {
         unimplemented!()
     }
+    async fn heartbeat_run(&self, _: Uuid, _: Uuid, _: Option<RunProgress>) -> Result<bool> {
+        unimplemented!()
+    }
+    async fn owns_run(&self, _: Uuid, _: Uuid, _: u32) -> Result<bool> {
+        unimplemented!()
+    }
+    async fn record_run_code_commit(&self, _: Uuid, _: Uuid, _: &str) -> Result<bool> {
+        unimplemented!()
+    }
     async fn reschedule_for_retry(
         &self,
         _: Uuid,
@@ -215,7 +228,7 @@ impl ApiStore for MockStore {
     async fn delete_job(&self, _: Uuid) -> Result<()> {
         unimplemented!()
     }
-    async fn create_adhoc_run(&self, _: Uuid) -> Result<JobRun> {
+    async fn create_adhoc_run(&self, _: Uuid, _: HashMap<String, RunParam>) -> Result<JobRun> {
         unimplemented!()
     }
     async fn cancel_run(&self, _: Uuid) -> Result<()> {
@@ -286,9 +299,49 @@ impl SecretStore for MockStore {
     async fn list_secret_names(&self, _: Option<Uuid>) -> Result<Vec<SecretMeta>> {
         unimplemented!()
     }
+    async fn get_secret_version(&self, _: Uuid, _: u32) -> Result<Option<StoredSecret>> {
+        unimplemented!()
+    }
+    async fn list_secret_versions(&self, _: Uuid) -> Result<Vec<SecretVersionMeta>> {
+        unimplemented!()
+    }
+    async fn restore_secret_version(&self, _: Uuid, _: u32) -> Result<Option<u32>> {
+        unimplemented!()
+    }
+    async fn prune_secret_versions(&self, _: Uuid, _: u32) -> Result<u32> {
+        unimplemented!()
+    }
+    async fn append_secret_audit(&self, _: &SecretAuditEvent) -> Result<()> {
+        unimplemented!()
+    }
+    async fn list_secret_audit(
+        &self,
+        _: Uuid,
+        _: Option<DateTime<Utc>>,
+        _: u32,
+    ) -> Result<Vec<SecretAuditEvent>> {
+        unimplemented!()
+    }
+    async fn prune_secret_audit(&self, _: DateTime<Utc>) -> Result<u64> {
+        unimplemented!()
+    }
+    async fn get_secret_meta(&self, _: Uuid, _: Option<Uuid>) -> Result<Option<SecretMeta>> {
+        unimplemented!()
+    }
     async fn delete_secret(&self, _: Uuid) -> Result<()> {
         unimplemented!()
     }
+    async fn set_secret_policy(
+        &self,
+        _: Uuid,
+        _: Option<DateTime<Utc>>,
+        _: Option<u64>,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+    async fn record_secret_use(&self, _: Uuid, _: &str, _: Uuid) -> Result<()> {
+        Ok(())
+    }
     async fn insert_kek_version(&self, _: u32, _: &str) -> Result<()> {
         unimplemented!()
     }
@@ -298,7 +351,7 @@ impl SecretStore for MockStore {
     async fn set_kek_version_state(&self, _: u32, _: &str) -> Result<()> {
         unimplemented!()
     }
-    async fn rewrap_secret(&self, _: Uuid, _: &[u8], _: u32) -> Result<()> {
+    async fn rewrap_secret(&self, _: Uuid, _: u32, _: &[u8], _: u32) -> Result<()> {
         unimplemented!()
     }
     async fn put_kek_share(&self, _: u32, _: Uuid, _: &[u8]) -> Result<()> {
@@ -350,6 +403,7 @@ impl ConfigStore for MockStore {
         _: &str,
         _: &str,
         _: &str,
+        _: Option<u32>,
     ) -> Result<SharedDbConfig> {
         unimplemented!()
     }
@@ -369,17 +423,41 @@ impl ConfigStore for MockStore {
         _: Option<&str>,
         _: Option<&str>,
         _: Option<&str>,
+        _: Option<u32>,
     ) -> Result<SharedDbConfig> {
         unimplemented!()
     }
     async fn delete_db_config(&self, _: Uuid) -> Result<()> {
         unimplemented!()
     }
+    async fn request_config_test(&self, _: Uuid) -> Result<Uuid> {
+        unimplemented!()
+    }
+    async fn claim_config_tests(&self, _: Uuid, _: u32, _: u32) -> Result<Vec<ConfigTestRequest>> {
+        Ok(vec![])
+    }
+    async fn finish_config_test(&self, _: Uuid, _: &ConfigTestResult) -> Result<()> {
+        unimplemented!()
+    }
+    async fn get_config_test(&self, _: Uuid) -> Result<Option<ConfigTestResult>> {
+        unimplemented!()
+    }
+    async fn prune_config_tests(&self, _: DateTime<Utc>) -> Result<u64> {
+        unimplemented!()
+    }
 }
 
 #[async_trait]
 impl LogStore for MockStore {
-    async fn append_run_log(&self, _: Uuid, _: u32, _: i64, _: LogStream, _: &str) -> Result<()> {
+    async fn append_run_log(
+        &self,
+        _: Uuid,
+        _: u32,
+        _: i64,
+        _: LogStream,
+        _: Option<LogLevel>,
+        _: &str,
+    ) -> Result<()> {
         unimplemented!()
     }
     async fn read_run_log(
@@ -405,6 +483,85 @@ impl LogStore for MockStore {
     }
 }
 
+#[async_trait]
+impl ModuleStore for MockStore {
+    async fn put_wasm_module(&self, _: Uuid, _: &str, _: &[u8]) -> Result<WasmModuleInfo> {
+        unimplemented!()
+    }
+    async fn get_wasm_module(&self, _: &str, _: Option<Uuid>) -> Result<Option<Vec<u8>>> {
+        unimplemented!()
+    }
+    async fn list_wasm_modules(&self, _: Option<Uuid>) -> Result<Vec<WasmModuleInfo>> {
+        unimplemented!()
+    }
+    async fn delete_wasm_module(&self, _: &str, _: Option<Uuid>) -> Result<()> {
+        unimplemented!()
+    }
+}
+
+#[async_trait]
+impl ArtifactStore for MockStore {
+    async fn put_artifact(
+        &self,
+        _: Uuid,
+        _: Uuid,
+        _: u32,
+        _: &str,
+        _: &str,
+        _: &[u8],
+    ) -> Result<RunArtifact> {
+        unimplemented!()
+    }
+    async fn list_artifacts(&self, _: Uuid, _: Uuid) -> Result<Vec<RunArtifact>> {
+        unimplemented!()
+    }
+    async fn get_artifact(&self, _: Uuid, _: Uuid, _: u32, _: &str) -> Result<Option<Vec<u8>>> {
+        unimplemented!()
+    }
+    async fn tenant_artifact_bytes(&self, _: Uuid) -> Result<u64> {
+        unimplemented!()
+    }
+    async fn prune_artifacts(&self, _: DateTime<Utc>) -> Result<u64> {
+        unimplemented!()
+    }
+}
+
+#[async_trait]
+impl BundleStore for MockStore {
+    async fn put_code_bundle(
+        &self,
+        _: Uuid,
+        _: &str,
+        _: BundleFormat,
+        _: &str,
+        _: &[u8],
+    ) -> Result<CodeBundle> {
+        unimplemented!()
+    }
+    async fn get_code_bundle(
+        &self,
+        _: &str,
+        _: Option<u32>,
+        _: Option<Uuid>,
+    ) -> Result<Option<CodeBundle>> {
+        unimplemented!()
+    }
+    async fn get_code_bundle_content(
+        &self,
+        _: &str,
+        _: u32,
+        _: Option<Uuid>,
+    ) -> Result<Option<Vec<u8>>> {
+        unimplemented!()
+    }
+    async fn list_code_bundles(&self, _: Option<Uuid>) -> Result<Vec<CodeBundle>> {
+        unimplemented!()
+    }
+    async fn delete_code_bundle(&self, _: &str, _: u32, _: Option<Uuid>) -> Result<()> {
+        unimplemented!()
+    }
+}
+
 fn defaults() -> RuntimeDefaults {
     RuntimeDefaults {
         misfire_catchup_secs: 0,
@@ -413,6 +570,11 @@ fn defaults() -> RuntimeDefaults {
         scheduler_backstop_secs: 180,
         worker_claim_backstop_secs: 300,
         max_log_bytes: 0,
+        artifact_max_tenant_bytes: 0,
+        workspace_keep_run_secs: 0,
+        workspace_job_idle_secs: 0,
+        secret_max_versions: 10,
+        secret_audit_retention_secs: 0,
     }
 }
 
@@ -427,6 +589,7 @@ fn worker_cfg() -> WorkerConfig {
         dead_after_secs: 30,
         restart_count: 0,
         version: "test".into(),
+        data_dir: std::env::temp_dir().join("arbiter-worker-tests"),
     }
 }
 
@@ -440,7 +603,15 @@ fn spawn(store: Arc<MockStore>, clock: Arc<VirtualClock>) {
     let settings = RuntimeSettings::new(store.clone(), defaults());
     let dyn_clock: Arc<dyn Clock> = clock;
     tokio::spawn(async move {
-        run_worker_loop(store, worker_cfg(), None, settings, dyn_clock).await;
+        run_worker_loop(
+            store,
+            worker_cfg(),
+            None,
+            None,
+            Arc::new(RunnerRegistry::with_builtins()),
+            settings,
+            dyn_clock,
+        ).await;
     });
 }
 